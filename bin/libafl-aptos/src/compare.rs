@@ -0,0 +1,206 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::Path;
+
+use anyhow::{bail, Context};
+use serde_json::Value;
+
+/// What a campaign reached, read back from its `--stats-log`.
+#[derive(Debug, Default)]
+pub struct CampaignSummary {
+    pub run_time: f64,
+    pub executions: u64,
+    pub exec_per_sec: f64,
+    pub edges: u64,
+    /// Covered and total edges of each function, as of the last stats event
+    pub functions: BTreeMap<String, (u64, u64)>,
+    /// Kind and calls of each solution, e.g. `("abort", "0xcafe::pool::swap")`
+    pub objectives: BTreeSet<(String, String)>,
+}
+
+impl CampaignSummary {
+    /// Read the last `stats` event and every `solution` event of a stats log.
+    /// Solutions logged before their kind was are counted as `other`.
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let contents = fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+        let mut summary = Self::default();
+        let mut has_stats = false;
+        for (number, line) in contents.lines().enumerate().filter(|(_, line)| !line.trim().is_empty()) {
+            let event: Value = serde_json::from_str(line)
+                .with_context(|| format!("{}:{}: not a stats log event", path.display(), number + 1))?;
+            match event["event"].as_str() {
+                Some("stats") => {
+                    has_stats = true;
+                    summary.run_time = event["run_time"].as_f64().unwrap_or(0.0);
+                    summary.executions = event["executions"].as_u64().unwrap_or(0);
+                    summary.exec_per_sec = event["exec_per_sec"].as_f64().unwrap_or(0.0);
+                    summary.edges = event["edges"].as_u64().unwrap_or(0);
+                    summary.functions = event["functions"]
+                        .as_array()
+                        .into_iter()
+                        .flatten()
+                        .filter_map(|function| {
+                            Some((
+                                function["function"].as_str()?.to_string(),
+                                (function["covered"].as_u64()?, function["total"].as_u64()?),
+                            ))
+                        })
+                        .collect();
+                }
+                Some("solution") => {
+                    let kind = event["kind"].as_str().unwrap_or("other").to_string();
+                    let calls = event["calls"].as_str().unwrap_or("script").to_string();
+                    summary.objectives.insert((kind, calls));
+                }
+                _ => {}
+            }
+        }
+        if !has_stats {
+            bail!(
+                "{} holds no stats events; was it written by --stats-log?",
+                path.display()
+            );
+        }
+        Ok(summary)
+    }
+}
+
+// Print how run `b` differs from run `a`: throughput, coverage per function
+// and the objectives only one of them found
+pub fn print_comparison(a_name: &str, a: &CampaignSummary, b_name: &str, b: &CampaignSummary) {
+    println!("A: {}", a_name);
+    println!("B: {}", b_name);
+    println!(
+        "run time: {:.0}s vs {:.0}s, executions: {} vs {}",
+        a.run_time, b.run_time, a.executions, b.executions
+    );
+    println!(
+        "exec/s: {:.1} vs {:.1} ({:+.1}%)",
+        a.exec_per_sec,
+        b.exec_per_sec,
+        percent_change(a.exec_per_sec, b.exec_per_sec)
+    );
+    println!(
+        "edges: {} vs {} ({:+})",
+        a.edges,
+        b.edges,
+        b.edges as i64 - a.edges as i64
+    );
+
+    let functions: BTreeSet<&String> = a.functions.keys().chain(b.functions.keys()).collect();
+    let mut deltas: Vec<(&String, u64, u64, u64)> = functions
+        .into_iter()
+        .filter_map(|function| {
+            let (a_covered, a_total) = a.functions.get(function).copied().unwrap_or_default();
+            let (b_covered, b_total) = b.functions.get(function).copied().unwrap_or_default();
+            (a_covered != b_covered).then_some((function, a_covered, b_covered, a_total.max(b_total)))
+        })
+        .collect();
+    deltas.sort_by_key(|&(function, a_covered, b_covered, _)| {
+        (std::cmp::Reverse(a_covered.abs_diff(b_covered)), function)
+    });
+    if deltas.is_empty() {
+        println!("coverage per function: identical");
+    } else {
+        println!("coverage per function (A -> B of total edges):");
+        for (function, a_covered, b_covered, total) in deltas {
+            println!(
+                "  {} {} -> {} of {} ({:+})",
+                function,
+                a_covered,
+                b_covered,
+                total,
+                b_covered as i64 - a_covered as i64
+            );
+        }
+    }
+
+    for (name, only, other) in [("A", &a.objectives, &b.objectives), ("B", &b.objectives, &a.objectives)] {
+        let found: Vec<&(String, String)> = only.difference(other).collect();
+        if found.is_empty() {
+            continue;
+        }
+        println!("objectives only {} found:", name);
+        for (kind, calls) in found {
+            println!("  {} in {}", kind, calls);
+        }
+    }
+}
+
+fn percent_change(from: f64, to: f64) -> f64 {
+    if from <= 0.0 {
+        return 0.0;
+    }
+    (to - from) * 100.0 / from
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::{fs, process};
+
+    use super::{percent_change, CampaignSummary};
+
+    /// A stats log holding `contents`, removed when dropped.
+    struct StatsLog(PathBuf);
+
+    impl StatsLog {
+        fn new(name: &str, contents: &str) -> Self {
+            let path = std::env::temp_dir().join(format!("libafl-aptos-compare-{}-{}.jsonl", process::id(), name));
+            fs::write(&path, contents).unwrap();
+            Self(path)
+        }
+    }
+
+    impl Drop for StatsLog {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+
+    #[test]
+    fn keeps_the_last_stats_and_every_solution() {
+        let log = StatsLog::new(
+            "summary",
+            r#"{"event":"stats","run_time":1.0,"executions":10,"exec_per_sec":10.0,"edges":3,"functions":[{"function":"0xcafe::pool::swap","covered":1,"total":9}]}
+{"event":"solution","kind":"abort","calls":"0xcafe::pool::swap"}
+
+{"event":"solution"}
+{"event":"stats","run_time":2.0,"executions":30,"exec_per_sec":15.0,"edges":5,"functions":[{"function":"0xcafe::pool::swap","covered":4,"total":9}]}
+"#,
+        );
+        let summary = CampaignSummary::load(&log.0).unwrap();
+        assert_eq!(summary.run_time, 2.0);
+        assert_eq!(summary.executions, 30);
+        assert_eq!(summary.exec_per_sec, 15.0);
+        assert_eq!(summary.edges, 5);
+        assert_eq!(summary.functions["0xcafe::pool::swap"], (4, 9));
+        let objectives: Vec<(&str, &str)> = summary
+            .objectives
+            .iter()
+            .map(|(kind, calls)| (kind.as_str(), calls.as_str()))
+            .collect();
+        assert_eq!(objectives, vec![("abort", "0xcafe::pool::swap"), ("other", "script")]);
+    }
+
+    #[test]
+    fn rejects_logs_without_stats() {
+        let log = StatsLog::new("no-stats", "{\"event\":\"solution\",\"kind\":\"abort\"}\n");
+        let err = CampaignSummary::load(&log.0).unwrap_err();
+        assert!(err.to_string().contains("no stats events"));
+    }
+
+    #[test]
+    fn rejects_lines_that_are_not_events() {
+        let log = StatsLog::new("not-json", "{\"event\":\"stats\"}\nnot json\n");
+        let err = CampaignSummary::load(&log.0).unwrap_err();
+        assert!(err.to_string().ends_with(":2: not a stats log event"));
+    }
+
+    #[test]
+    fn percent_change_is_relative_to_the_first_run() {
+        assert_eq!(percent_change(100.0, 150.0), 50.0);
+        assert_eq!(percent_change(200.0, 100.0), -50.0);
+        assert_eq!(percent_change(0.0, 100.0), 0.0);
+    }
+}
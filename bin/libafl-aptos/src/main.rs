@@ -1,203 +1,1331 @@
+mod compare;
+mod ui;
 mod utils;
 
+use std::collections::{BTreeMap, BTreeSet};
 use std::path::PathBuf;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
-use aptos_fuzzer::static_analysis::run_static_analysis;
-use aptos_fuzzer::{
-    AbortCodeObjective, AptosFuzzerMutator, AptosFuzzerState, AptosMoveExecutor, ShiftOverflowObjective,
+#[cfg(feature = "concolic")]
+use aptos_fuzzer::abort_target::AbortTarget;
+use aptos_fuzzer::bisect::{bisect_solution, RootCause};
+use aptos_fuzzer::check::{check_script_composer, check_targets};
+use aptos_fuzzer::config::{
+    CampaignConfig, CorpusConfig, DetectorConfig, ExecutionConfig, FuzzerConfig, MutatorConfig, ObjectiveConfig,
+    SchedulerConfig, TargetConfig,
+};
+use aptos_fuzzer::executor::Backend;
+use aptos_fuzzer::package::DEFAULT_COMPILER;
+use aptos_fuzzer::runner::{
+    load_input, load_seeds, CampaignEvent, CampaignMonitor, CampaignReport, FuzzerBuilder, StopReason,
+};
+use aptos_fuzzer::runtime_detector::{INFINITE_LOOP_THRESHOLD, UNBOUNDED_LOOP_INSTRUCTIONS};
+use aptos_fuzzer::scheduler::{Fairness, DEFAULT_MIN_FUNCTION_SHARE};
+use aptos_fuzzer::sender::SenderStrategy;
+#[cfg(feature = "concolic")]
+use aptos_fuzzer::solver::{DEFAULT_QUERIES_PER_ITERATION, DEFAULT_QUERY_TIMEOUT};
+use aptos_fuzzer::supervisor::{install_quarantine, supervise, supervised_timeout};
+use aptos_fuzzer::upgrade::DEFAULT_UPGRADE_AFTER;
+use aptos_fuzzer::{AptosFuzzerInput, AptosFuzzerState, AptosMoveExecutor, MutationRates, Severity, MAP_SIZE};
+use clap::parser::ValueSource;
+use clap::{ArgMatches, CommandFactory, FromArgMatches, Parser, Subcommand};
+use compare::{print_comparison, CampaignSummary};
+use ui::{say, Dashboard, StatsLog, UiMode};
+#[cfg(feature = "concolic")]
+use utils::{print_abort_search, print_solver_stats};
+use utils::{
+    print_branch_worklist, print_function_executions, print_fuzzer_stats, print_instantiation_stats,
+    print_mutation_stats, print_ordering_conflicts, print_profile, print_redundant_entries, print_skipped_functions,
+    print_static_finding, print_suppressed_errors, print_target_checks, write_call_graph, write_solutions, write_trace,
 };
-use clap::Parser;
-use libafl::corpus::Corpus;
-use libafl::events::SimpleEventManager;
-use libafl::feedbacks::{EagerOrFeedback, MaxMapFeedback, StateInitializer};
-use libafl::fuzzer::Fuzzer;
-use libafl::monitors::NopMonitor;
-use libafl::schedulers::QueueScheduler;
-use libafl::stages::StdMutationalStage;
-use libafl::state::{HasCorpus, HasExecutions, HasSolutions};
-use libafl::{Evaluator, StdFuzzer};
-use libafl_bolts::tuples::tuple_list;
-use utils::print_fuzzer_stats;
 
+/// Exit code when the campaign found at least one solution
+const FOUND_OBJECTIVES_EXIT_CODE: i32 = 1;
+/// Exit code when the campaign couldn't be set up or stopped on an error
+const FAILURE_EXIT_CODE: i32 = 2;
+/// Functions listed by `--profile`, and hottest pcs shown for each
+const PROFILE_FUNCTIONS: usize = 20;
+const PROFILE_PCS: usize = 5;
+/// One-sided branches of the worklist listed in the final report
+const WORKLIST_BRANCHES: usize = 20;
+
+/// Exits with 0 if no solutions were found, 1 otherwise.
 #[derive(Debug, Parser)]
 #[command(author, version, about = "LibAFL-based fuzzer for Aptos Move modules")]
 struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// TOML file with campaign settings; flags given on the command line
+    /// override it
+    #[arg(long = "config", value_name = "FILE")]
+    config: Option<PathBuf>,
+
     /// Path to a directory containing compiled Move modules to publish before
     /// fuzzing
-    #[arg(long = "modules-dir", value_name = "MODULES_DIR")]
-    modules_dir: PathBuf,
+    #[arg(
+        long = "modules-dir",
+        value_name = "MODULES_DIR",
+        required_unless_present_any = ["config", "package_path"],
+        conflicts_with = "package_path"
+    )]
+    modules_dir: Option<PathBuf>,
+
+    /// Move source package (directory with `Move.toml`) to compile with
+    /// `aptos move compile` and fuzz, instead of `--modules-dir`
+    #[arg(long = "package-path", value_name = "DIR")]
+    package_path: Option<PathBuf>,
+
+    /// Named address assignment for the package build, e.g. `pool=0xcafe`
+    /// (repeatable)
+    #[arg(long = "named-address", value_name = "NAME=ADDRESS", requires = "package_path")]
+    named_addresses: Vec<String>,
+
+    /// `aptos` CLI used to compile `--package-path`
+    #[arg(long = "aptos-cli", value_name = "PATH", default_value = DEFAULT_COMPILER)]
+    aptos_cli: PathBuf,
 
     /// Timeout in seconds (0 = no timeout, run indefinitely)
     #[arg(long = "timeout", short = 't', default_value = "0")]
     timeout_seconds: u64,
+
+    /// Stop once this many solutions have been found
+    #[arg(long = "stop-after-objectives", value_name = "N")]
+    stop_after_objectives: Option<usize>,
+
+    /// Stop on the first input that crashes the VM
+    #[arg(long = "stop-on-first-crash")]
+    stop_on_first_crash: bool,
+
+    /// Stop after this many seconds without new edges or solutions (0 =
+    /// never)
+    #[arg(long = "plateau-seconds", value_name = "S", default_value = "0")]
+    plateau_seconds: u64,
+
+    /// Seed for all random decisions; a run is replayed by passing the
+    /// seed it printed at startup
+    #[arg(long = "rng-seed", value_name = "SEED")]
+    rng_seed: Option<u64>,
+
+    /// Run the fuzzer in a child process and respawn it when it panics,
+    /// resuming from the last checkpoint (requires `--checkpoint-interval`)
+    #[arg(long = "restart-on-crash")]
+    restart_on_crash: bool,
+
+    /// How corpus entries are scheduled across the target functions:
+    /// `queue` (corpus order), `round-robin` (the functions in turn) or
+    /// `budgeted` (corpus order, but functions below
+    /// `--min-function-share` of the executions first)
+    #[arg(long = "fairness", value_name = "POLICY", default_value = "queue")]
+    fairness: Fairness,
+
+    /// Fraction of an even split of the executions a function may fall
+    /// below under `--fairness budgeted`
+    #[arg(long = "min-function-share", value_name = "FRACTION", default_value_t = DEFAULT_MIN_FUNCTION_SHARE)]
+    min_function_share: f64,
+
+    /// Number of entries in the edge coverage map
+    #[arg(long = "map-size", default_value_t = MAP_SIZE, value_parser = parse_map_size)]
+    map_size: usize,
+
+    /// VM inputs run on: `aptos` (full transactions) or `move-vm` (direct
+    /// Move VM session calls, no framework, prologue or gas)
+    #[arg(long = "backend", value_name = "BACKEND", default_value = "aptos")]
+    backend: Backend,
+
+    /// Wall-clock limit for a single execution in milliseconds (0 = unlimited)
+    #[arg(long = "exec-timeout-ms", default_value = "0")]
+    exec_timeout_ms: u64,
+
+    /// Maximum number of Move instructions per execution (0 = unlimited)
+    #[arg(long = "max-instructions", default_value = "0")]
+    max_instructions: u64,
+
+    /// Who sends inputs that don't name their signers: `fixed` (0x1),
+    /// `deployer` (the called module's account), `random` or `rotate`
+    /// (members of the address pool, drawn or in turn)
+    #[arg(long = "sender", value_name = "STRATEGY", default_value = "fixed")]
+    sender: SenderStrategy,
+
+    /// Always send calls into this function from this account (repeatable),
+    /// e.g. `pool::set_fee=0xcafe`
+    #[arg(long = "pin-sender", value_name = "FUNCTION=ADDRESS")]
+    pinned_senders: Vec<String>,
+
+    /// Wall-clock limit of a single constraint solver query in milliseconds
+    #[cfg(feature = "concolic")]
+    #[arg(long = "solver-timeout-ms", default_value_t = DEFAULT_QUERY_TIMEOUT.as_millis() as u64)]
+    solver_timeout_ms: u64,
+
+    /// Solver queries allowed per fuzzing iteration; answers served from the
+    /// cache don't count
+    #[cfg(feature = "concolic")]
+    #[arg(long = "solver-queries", value_name = "N", default_value_t = DEFAULT_QUERIES_PER_ITERATION)]
+    solver_queries: usize,
+
+    /// Ask the solver to flip the N one-sided branches at the top of the
+    /// branch worklist whenever an execution reaches one of them, and run
+    /// the inputs it solves for
+    #[cfg(feature = "concolic")]
+    #[arg(long = "solve-branches", value_name = "N", default_value_t = 0)]
+    solve_branches: usize,
+
+    /// Check the concolic engine against the VM: before every instruction,
+    /// compare the symbolic stack values without free variables with the
+    /// concrete operands and print where they first diverge. Slow
+    #[cfg(feature = "concolic")]
+    #[arg(long = "concolic-self-check")]
+    concolic_self_check: bool,
+
+    /// Report executions that exhaust their budget as solutions
+    #[arg(long = "budget-objective")]
+    budget_objective: bool,
+
+    /// Report transactions that change the total balance of this coin type
+    /// across touched accounts (repeatable), e.g. `0x1::aptos_coin::AptosCoin`
+    #[arg(long = "conserve-coin", value_name = "COIN_TYPE")]
+    conserve_coins: Vec<String>,
+
+    /// Report transactions that delete a resource of this type (repeatable)
+    #[arg(long = "never-delete", value_name = "STRUCT_TAG")]
+    never_delete: Vec<String>,
+
+    /// Don't report transactions that create or destroy coins or fungible
+    /// assets without calling a function named like a mint or burn
+    #[arg(long = "no-asset-conservation")]
+    no_asset_conservation: bool,
+
+    /// Function allowed to mint and burn coins and fungible assets
+    /// (repeatable), e.g. `pool::claim_rewards`
+    #[arg(long = "asset-issuer", value_name = "MODULE::FUNCTION")]
+    asset_issuers: Vec<String>,
+
+    /// Report sequences whose calls return values breaking this property
+    /// (repeatable), e.g. `"pool::withdraw.0 == pool::deposit.0"` or
+    /// `"pool::deposit.1 > 0"`
+    #[arg(long = "return-property", value_name = "PROPERTY")]
+    return_properties: Vec<String>,
+
+    /// Seed sequences calling the first function then the second, and report
+    /// those that leave the sender with more coins than before (repeatable),
+    /// e.g. `"pool::deposit -> pool::withdraw"`
+    #[arg(long = "round-trip", value_name = "FORWARD -> INVERSE")]
+    round_trips: Vec<String>,
+
+    /// Only pass target function arguments callers would (repeatable), e.g.
+    /// `"pool::withdraw.0 in 1..=1000000"`, `"pool::set_fee.0 in [0, 30]"`
+    /// or `"pool::transfer.0 pool"` for an address from the address pool;
+    /// arguments are counted without signers
+    #[arg(long = "arg-constraint", value_name = "CONSTRAINT")]
+    arg_constraints: Vec<String>,
+
+    /// Report transactions that change or delete a target module's resource
+    /// owned by an account other than the sender, unless the resource names
+    /// the sender
+    #[arg(long = "signer-ownership")]
+    signer_ownership: bool,
+
+    /// Report executions that move a resource to an address already holding
+    /// one, or remove a resource every function changing another one at the
+    /// same address borrows, leaving that one out of reach
+    #[arg(long = "resource-leaks")]
+    resource_leaks: bool,
+
+    /// Replay transactions the admin or a target module's account sends from
+    /// an unprivileged account, and report those that change the resources
+    /// privileged accounts own the same way
+    #[arg(long = "access-control")]
+    access_control: bool,
+
+    /// Don't report this expected abort, e.g. `pool::1` or `0xcafe::pool::1`
+    /// (repeatable). It still counts towards coverage.
+    #[arg(long = "allow-abort", value_name = "MODULE::CODE")]
+    allow_aborts: Vec<String>,
+
+    /// Known static finding or runtime issue not to report, e.g.
+    /// `pool::swap::PrecisionLoss`, `*` for any function (repeatable)
+    #[arg(long = "suppress", value_name = "MODULE::FUNCTION::KIND")]
+    suppressions: Vec<String>,
+
+    /// Don't call `invariant_*`/`check_*` functions after each execution
+    #[arg(long = "no-invariant-functions")]
+    no_invariant_functions: bool,
+
+    /// Call the target's view functions before and after each transaction and
+    /// report those it makes abort
+    #[arg(long = "view-functions")]
+    view_functions: bool,
+
+    /// How a view function's result may change across a transaction
+    /// (repeatable), e.g. `"pool::total_supply >="` for never decreasing
+    #[arg(long = "monotonic-view", value_name = "VIEW COMPARISON")]
+    monotonic_views: Vec<String>,
+
+    /// Also run every input on a reference VM with paranoid type checks and
+    /// report divergences in status, write set or events
+    #[arg(long = "differential")]
+    differential: bool,
+
+    /// Feature flag number to enable on the reference VM (repeatable)
+    #[arg(long = "reference-enable-feature", value_name = "FLAG", requires = "differential")]
+    reference_enable_features: Vec<u64>,
+
+    /// Feature flag number to disable on the reference VM (repeatable)
+    #[arg(long = "reference-disable-feature", value_name = "FLAG", requires = "differential")]
+    reference_disable_features: Vec<u64>,
+
+    /// Fuzz against the state of a live network, fetched lazily from this
+    /// REST endpoint (e.g. `https://fullnode.mainnet.aptoslabs.com`)
+    #[arg(long = "fork-url", value_name = "URL")]
+    fork_url: Option<String>,
+
+    /// Ledger version to fork at (default: latest)
+    #[arg(long = "fork-version", value_name = "VERSION", requires = "fork_url")]
+    fork_version: Option<u64>,
+
+    /// TOML or JSON file describing accounts, coin balances, resources and
+    /// modules to set up before fuzzing
+    #[arg(long = "state-fixture", value_name = "FILE")]
+    state_fixture: Option<PathBuf>,
+
+    /// Function to run once before fuzzing instead of the detected
+    /// `initialize` functions, as `0xADDR::module::function`; `init_module`
+    /// still runs when its module is published
+    #[arg(long = "initializer", value_name = "FUNCTION")]
+    initializer: Option<String>,
+
+    /// Argument to the `--initializer` function, in parameter order
+    /// (repeatable)
+    #[arg(long = "initializer-arg", value_name = "VALUE", requires = "initializer")]
+    initializer_args: Vec<String>,
+
+    /// Signer of initializer calls (default: the module's address);
+    /// `init_module` always gets the publisher's
+    #[arg(long = "admin", value_name = "ADDRESS")]
+    admin: Option<String>,
+
+    /// Don't run `init_module` or initializers before fuzzing
+    #[arg(long = "no-initializers", conflicts_with = "initializer")]
+    no_initializers: bool,
+
+    /// Also fuzz public functions that static analysis finds always abort,
+    /// needing a resource nothing holds or can publish
+    #[arg(long = "fuzz-unreachable")]
+    fuzz_unreachable: bool,
+
+    /// Watch the modules (or the package's sources) and reload them when
+    /// they change, keeping the corpus entries that still apply
+    #[arg(long = "watch")]
+    watch: bool,
+
+    /// Compiled modules of a new version of the targets to publish as an
+    /// upgrade, over the state the corpus built, once `--upgrade-after`
+    /// seconds have passed
+    #[arg(long = "upgrade-to", value_name = "DIR")]
+    upgrade_to: Option<PathBuf>,
+
+    /// Seconds to fuzz the first version of the targets before upgrading them
+    #[arg(long = "upgrade-after", value_name = "SECONDS", default_value_t = DEFAULT_UPGRADE_AFTER)]
+    upgrade_after: u64,
+
+    /// Stack up to 2^N mutations per fuzzing iteration
+    #[arg(long = "max-stack-pow", default_value = "5")]
+    max_stack_pow: u32,
+
+    /// Longest vector argument the mutator generates
+    #[arg(long = "max-vector-len", default_value = "1024")]
+    max_vector_len: usize,
+
+    /// Most calls a script sequence grows to; sequences past a few calls
+    /// are mutated less unless they recently found coverage
+    #[arg(long = "max-sequence-len", default_value = "16")]
+    max_sequence_len: usize,
+
+    /// Also mutate the bytecode of script inputs, keeping mutants that
+    /// verify, to fuzz the VM and its verifier with the loaded modules as
+    /// context rather than the modules themselves
+    #[arg(long = "fuzz-vm")]
+    fuzz_vm: bool,
+
+    /// Fuzz the publish path instead of the targets' functions: the corpus
+    /// holds transactions publishing the target modules, mutated over their
+    /// tables and code to find verifier and loader bugs
+    #[arg(long = "fuzz-publish")]
+    fuzz_publish: bool,
+
+    /// Directory caching state fetched from the fork across runs
+    #[arg(long = "fork-cache-dir", default_value = ".fork-cache")]
+    fork_cache_dir: PathBuf,
+
+    /// Directory of JSON-encoded inputs to add to the initial corpus
+    #[arg(long = "seeds-dir", value_name = "DIR")]
+    seeds_dir: Option<PathBuf>,
+
+    /// Directory to write solutions to as JSON when fuzzing stops, with
+    /// their traces and state diffs
+    #[arg(long = "solutions-dir", value_name = "DIR")]
+    solutions_dir: Option<PathBuf>,
+
+    /// Bisect each solution by re-running variants of it: report the calls
+    /// of its sequence and the arguments it needs to reach its objective, and
+    /// write it reduced to those calls next to it in `--solutions-dir`
+    #[arg(long = "bisect-solutions")]
+    bisect_solutions: bool,
+
+    /// Transactions as returned by the Aptos REST API (a JSON array); entry
+    /// function calls into the target modules are added as seeds
+    #[arg(long = "seed-transactions", value_name = "FILE")]
+    seed_transactions: Option<PathBuf>,
+
+    /// Directory shared with other fuzzer instances: new corpus entries are
+    /// exported to a subdirectory of it and those of the others imported,
+    /// every 30 seconds
+    #[arg(long = "sync-dir", value_name = "DIR")]
+    sync_dir: Option<PathBuf>,
+
+    /// Subdirectory of `--sync-dir` to export to, unique per instance
+    /// (default: derived from the RNG seed)
+    #[arg(long = "sync-name", value_name = "NAME", requires = "sync_dir")]
+    sync_name: Option<String>,
+
+    /// Checkpoint the corpus, solutions, coverage and the paths seen to
+    /// `--checkpoint-dir` every this many seconds, resuming from the
+    /// checkpoint there if there is one
+    #[arg(long = "checkpoint-interval", value_name = "SECS")]
+    checkpoint_interval: Option<u64>,
+
+    /// Directory checkpoints are written to and resumed from
+    #[arg(long = "checkpoint-dir", value_name = "DIR", default_value = "checkpoint")]
+    checkpoint_dir: PathBuf,
+
+    /// Directory the input the fuzzer panicked on is written to under
+    /// `--restart-on-crash`, with the panic message and backtrace
+    #[arg(long = "fuzzer-crashes-dir", value_name = "DIR", default_value = "fuzzer-crashes")]
+    fuzzer_crashes_dir: PathBuf,
+
+    /// Skip static analysis before fuzzing
+    #[arg(long = "no-static-analysis")]
+    no_static_analysis: bool,
+
+    /// Analyze the target modules together with every deployed module and
+    /// flag friend functions exposed outside the package
+    #[arg(long = "whole-program-analysis")]
+    whole_program_analysis: bool,
+
+    /// Static analysis finding kind not to report, e.g. `UnusedConstant`
+    /// (repeatable)
+    #[arg(long = "disable-detector", value_name = "KIND")]
+    disabled_detectors: Vec<String>,
+
+    /// Runtime detector to turn off: `PrecisionLoss`, `BoolJudgement`,
+    /// `InfiniteLoop`, `UnboundedLoop` or `TaintedSink` (repeatable)
+    #[arg(long = "disable-runtime-detector", value_name = "NAME")]
+    disabled_runtime_detectors: Vec<String>,
+
+    /// Times a loop may test the same symbolic condition before it is
+    /// reported as potentially infinite
+    #[arg(long = "infinite-loop-threshold", value_name = "N", default_value_t = INFINITE_LOOP_THRESHOLD)]
+    infinite_loop_threshold: usize,
+
+    /// Instructions a loop may run in one execution, callees included,
+    /// before it is reported as unbounded; at most half of
+    /// `--max-instructions`
+    #[arg(
+        long = "unbounded-loop-instructions",
+        value_name = "N",
+        default_value_t = UNBOUNDED_LOOP_INSTRUCTIONS
+    )]
+    unbounded_loop_instructions: u64,
+
+    /// Only log runtime issues instead of reporting their inputs as solutions
+    #[arg(long = "runtime-issues-log-only")]
+    runtime_issues_log_only: bool,
+
+    /// Least severity of a runtime issue that makes its input a solution:
+    /// `info`, `low`, `medium` or `high`. Others are only listed in the
+    /// runtime findings report
+    #[arg(long = "runtime-objective-severity", value_name = "SEVERITY", default_value = "high")]
+    runtime_objective_severity: Severity,
+
+    /// Severity overrides per finding kind, from `detectors.severities` in
+    /// the config
+    #[arg(skip)]
+    severities: BTreeMap<String, Severity>,
+
+    /// How progress is reported while fuzzing
+    #[arg(long = "ui", value_enum, default_value = "plain")]
+    ui: UiMode,
+
+    /// File to append JSON lines to: campaign statistics at every report
+    /// and an entry per new solution
+    #[arg(long = "stats-log", value_name = "PATH")]
+    stats_log: Option<PathBuf>,
+
+    /// Count executed instructions per function and print the hottest
+    /// functions and pcs at exit
+    #[arg(long = "profile")]
+    profile: bool,
+
+    /// Steer the concolic engine towards the abort with this code in this
+    /// function, e.g. `0xcafe::pool::swap::7`, and report a witness input or
+    /// that it is unreachable under the current model
+    #[cfg(feature = "concolic")]
+    #[arg(long = "solve-abort", value_name = "MODULE::FUNCTION::CODE")]
+    solve_abort: Option<AbortTarget>,
 }
 
-fn main() {
-    let cli = Cli::parse();
-    println!("Starting Aptos Move Fuzzer...");
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Replay a stored input, e.g. a solution, against the set-up state and
+    /// write a structured trace of its execution instead of fuzzing
+    Trace {
+        /// JSON-encoded input
+        input: PathBuf,
 
-    if cli.timeout_seconds > 0 {
-        println!("Timeout: {} seconds", cli.timeout_seconds);
-    } else {
-        println!("Timeout: None (will run indefinitely, use Ctrl+C to stop)");
+        /// Where to write the trace; defaults to the input with a `.trace`
+        /// extension
+        #[arg(long = "output", short = 'o', value_name = "PATH")]
+        output: Option<PathBuf>,
+    },
+    /// Run the seeds and the inputs in `--solutions-dir`, then write the
+    /// call graph of the target modules as Graphviz DOT, colored by coverage
+    /// and marking functions with findings or solutions, instead of fuzzing
+    CallGraph {
+        /// Where to write the DOT file
+        #[arg(long = "output", short = 'o', value_name = "PATH", default_value = "call_graph.dot")]
+        output: PathBuf,
+    },
+    /// Load and set up the targets, run one seed of each public function,
+    /// then print which functions are fuzzed and which are skipped and why,
+    /// instead of fuzzing
+    Check,
+    /// Compare two campaigns by their `--stats-log`: exec/s, edges covered
+    /// per function and the objectives only one of them found, e.g. for an
+    /// A/B experiment on mutator or scheduler settings
+    Compare {
+        /// Stats log of the baseline run
+        a: PathBuf,
+        /// Stats log of the run compared against it
+        b: PathBuf,
+    },
+}
+
+/// Take each setting from `config` unless it was given on the command line.
+/// Lists given on the command line replace those of the config rather than
+/// extending them. Returns the mutation rates, which have no flags.
+fn apply_config(cli: &mut Cli, matches: &ArgMatches, config: FuzzerConfig) -> MutationRates {
+    macro_rules! from_config {
+        ($field:ident, $value:expr) => {
+            if matches.value_source(stringify!($field)) != Some(ValueSource::CommandLine) {
+                if let Some(value) = $value {
+                    cli.$field = value;
+                }
+            }
+        };
+    }
+    macro_rules! list_from_config {
+        ($field:ident, $value:expr) => {
+            if matches.value_source(stringify!($field)) != Some(ValueSource::CommandLine) {
+                cli.$field = $value;
+            }
+        };
     }
 
-    // Setup executor and feedback
-    let mut executor = AptosMoveExecutor::new();
-    let mut feedback = MaxMapFeedback::new(executor.pc_observer());
-    let objective = EagerOrFeedback::new(ShiftOverflowObjective::new(), AbortCodeObjective::new());
+    let FuzzerConfig {
+        target,
+        campaign,
+        scheduler,
+        corpus,
+        execution,
+        mutator,
+        detectors,
+        objectives,
+    } = config;
 
-    let mon = NopMonitor::new();
-    let mut mgr = SimpleEventManager::new(mon);
-    let scheduler = QueueScheduler::new();
+    cli.modules_dir = cli.modules_dir.take().or(target.modules_dir);
+    cli.package_path = cli.package_path.take().or(target.package_path);
+    list_from_config!(named_addresses, target.named_addresses);
+    cli.state_fixture = cli.state_fixture.take().or(target.state_fixture);
+    cli.fork_url = cli.fork_url.take().or(target.fork_url);
+    cli.fork_version = cli.fork_version.or(target.fork_version);
+    from_config!(fork_cache_dir, target.fork_cache_dir);
+    if cli.initializer.is_none() {
+        cli.initializer = target.initializer;
+        cli.initializer_args = target.initializer_args;
+    }
+    cli.admin = cli.admin.take().or(target.admin);
+    cli.no_initializers |= target.run_initializers == Some(false);
+    cli.fuzz_unreachable |= target.fuzz_unreachable == Some(true);
+    cli.watch |= target.watch == Some(true);
+    cli.upgrade_to = cli.upgrade_to.take().or(target.upgrade_to);
+    from_config!(upgrade_after, target.upgrade_after);
+    list_from_config!(arg_constraints, target.arg_constraints);
 
-    let mut state = AptosFuzzerState::new(cli.modules_dir.clone());
+    from_config!(timeout_seconds, campaign.timeout);
+    from_config!(map_size, campaign.map_size);
+    cli.stop_after_objectives = cli.stop_after_objectives.or(campaign.stop_after_objectives);
+    cli.stop_on_first_crash |= campaign.stop_on_first_crash == Some(true);
+    from_config!(plateau_seconds, campaign.plateau_seconds);
+    cli.rng_seed = cli.rng_seed.or(campaign.rng_seed);
+    cli.restart_on_crash |= campaign.restart_on_crash == Some(true);
 
-    let static_findings = run_static_analysis(state.aptos_state(), state.target_modules());
-    println!("Completed static analysis.");
-    if !static_findings.is_empty() {
-        println!("Static analysis findings:");
-        for finding in &static_findings {
-            match &finding.function {
-                Some(func) => println!(
-                    "  [{}] {}::{} - {}",
-                    finding.kind.as_str(),
-                    finding.module,
-                    func,
-                    finding.detail
-                ),
-                None => println!("  [{}] {} - {}", finding.kind.as_str(), finding.module, finding.detail),
+    from_config!(fairness, scheduler.fairness);
+    from_config!(min_function_share, scheduler.min_function_share);
+
+    cli.seeds_dir = cli.seeds_dir.take().or(corpus.seeds_dir);
+    cli.solutions_dir = cli.solutions_dir.take().or(corpus.solutions_dir);
+    cli.bisect_solutions |= corpus.bisect_solutions == Some(true);
+    cli.seed_transactions = cli.seed_transactions.take().or(corpus.transactions);
+    cli.sync_dir = cli.sync_dir.take().or(corpus.sync_dir);
+    cli.sync_name = cli.sync_name.take().or(corpus.sync_name);
+    cli.checkpoint_interval = cli.checkpoint_interval.or(corpus.checkpoint_interval);
+    from_config!(checkpoint_dir, corpus.checkpoint_dir);
+    from_config!(fuzzer_crashes_dir, corpus.fuzzer_crashes_dir);
+
+    from_config!(backend, execution.backend);
+    from_config!(exec_timeout_ms, execution.timeout_ms);
+    from_config!(max_instructions, execution.max_instructions);
+    from_config!(sender, execution.sender);
+    list_from_config!(pinned_senders, execution.pinned_senders);
+    #[cfg(feature = "concolic")]
+    {
+        from_config!(solver_timeout_ms, execution.solver_timeout_ms);
+        from_config!(solver_queries, execution.solver_queries_per_iteration);
+        from_config!(solve_branches, execution.solve_branches);
+    }
+    cli.differential |= execution.differential == Some(true);
+    list_from_config!(reference_enable_features, execution.reference_enable_features);
+    list_from_config!(reference_disable_features, execution.reference_disable_features);
+
+    from_config!(max_stack_pow, mutator.max_stack_pow);
+    from_config!(max_vector_len, mutator.max_vector_len);
+    from_config!(max_sequence_len, mutator.max_sequence_len);
+    cli.fuzz_vm |= mutator.fuzz_vm == Some(true);
+    cli.fuzz_publish |= mutator.fuzz_publish == Some(true);
+
+    cli.no_static_analysis |= detectors.enabled == Some(false);
+    cli.whole_program_analysis |= detectors.whole_program == Some(true);
+    list_from_config!(disabled_detectors, detectors.disabled);
+    list_from_config!(disabled_runtime_detectors, detectors.runtime_disabled);
+    from_config!(infinite_loop_threshold, detectors.infinite_loop_threshold);
+    from_config!(unbounded_loop_instructions, detectors.unbounded_loop_instructions);
+    cli.runtime_issues_log_only |= detectors.runtime_issues_objective == Some(false);
+    from_config!(runtime_objective_severity, detectors.runtime_objective_severity);
+    cli.severities = detectors.severities;
+    list_from_config!(suppressions, detectors.suppress);
+
+    cli.budget_objective |= objectives.budget == Some(true);
+    cli.no_invariant_functions |= objectives.invariant_functions == Some(false);
+    list_from_config!(allow_aborts, objectives.allow_aborts);
+    list_from_config!(conserve_coins, objectives.conserve_coins);
+    list_from_config!(never_delete, objectives.never_delete);
+    cli.no_asset_conservation |= objectives.asset_conservation == Some(false);
+    list_from_config!(asset_issuers, objectives.asset_issuers);
+    list_from_config!(return_properties, objectives.return_properties);
+    list_from_config!(round_trips, objectives.round_trips);
+    cli.signer_ownership |= objectives.signer_ownership == Some(true);
+    cli.resource_leaks |= objectives.resource_leaks == Some(true);
+    cli.access_control |= objectives.access_control == Some(true);
+    cli.view_functions |= objectives.view_functions == Some(true);
+    list_from_config!(monotonic_views, objectives.monotonic_views);
+
+    let defaults = MutationRates::default();
+    MutationRates {
+        block_context: mutator.block_context_rate.unwrap_or(defaults.block_context),
+        sender: mutator.sender_rate.unwrap_or(defaults.sender),
+        crossover: mutator.crossover_rate.unwrap_or(defaults.crossover),
+        interesting_value: mutator.interesting_value_rate.unwrap_or(defaults.interesting_value),
+    }
+}
+
+fn parse_map_size(value: &str) -> Result<usize, String> {
+    let size: usize = value.parse().map_err(|e| format!("invalid map size: {e}"))?;
+    if size == 0 {
+        return Err("map size must be greater than 0".to_string());
+    }
+    Ok(size)
+}
+
+/// The campaign settings the merged command line and config describe.
+fn campaign_config(cli: &Cli, rates: MutationRates) -> FuzzerConfig {
+    let config = FuzzerConfig {
+        target: TargetConfig {
+            modules_dir: cli.modules_dir.clone(),
+            package_path: cli.package_path.clone(),
+            named_addresses: cli.named_addresses.clone(),
+            state_fixture: cli.state_fixture.clone(),
+            fork_url: cli.fork_url.clone(),
+            fork_version: cli.fork_version,
+            fork_cache_dir: Some(cli.fork_cache_dir.clone()),
+            initializer: cli.initializer.clone(),
+            initializer_args: cli.initializer_args.clone(),
+            admin: cli.admin.clone(),
+            run_initializers: Some(!cli.no_initializers),
+            fuzz_unreachable: Some(cli.fuzz_unreachable),
+            watch: Some(cli.watch),
+            upgrade_to: cli.upgrade_to.clone(),
+            upgrade_after: Some(cli.upgrade_after),
+            arg_constraints: cli.arg_constraints.clone(),
+        },
+        campaign: CampaignConfig {
+            timeout: Some(cli.timeout_seconds),
+            map_size: Some(cli.map_size),
+            stop_after_objectives: cli.stop_after_objectives,
+            stop_on_first_crash: Some(cli.stop_on_first_crash),
+            plateau_seconds: Some(cli.plateau_seconds),
+            rng_seed: cli.rng_seed,
+            restart_on_crash: Some(cli.restart_on_crash),
+        },
+        scheduler: SchedulerConfig {
+            fairness: Some(cli.fairness),
+            min_function_share: Some(cli.min_function_share),
+        },
+        corpus: CorpusConfig {
+            seeds_dir: cli.seeds_dir.clone(),
+            solutions_dir: cli.solutions_dir.clone(),
+            bisect_solutions: Some(cli.bisect_solutions),
+            transactions: cli.seed_transactions.clone(),
+            sync_dir: cli.sync_dir.clone(),
+            sync_name: cli.sync_name.clone(),
+            checkpoint_interval: cli.checkpoint_interval,
+            checkpoint_dir: Some(cli.checkpoint_dir.clone()),
+            fuzzer_crashes_dir: Some(cli.fuzzer_crashes_dir.clone()),
+        },
+        execution: ExecutionConfig {
+            backend: Some(cli.backend),
+            timeout_ms: Some(cli.exec_timeout_ms),
+            max_instructions: Some(cli.max_instructions),
+            differential: Some(cli.differential),
+            reference_enable_features: cli.reference_enable_features.clone(),
+            reference_disable_features: cli.reference_disable_features.clone(),
+            sender: Some(cli.sender),
+            pinned_senders: cli.pinned_senders.clone(),
+            ..ExecutionConfig::default()
+        },
+        mutator: MutatorConfig {
+            fuzz_vm: Some(cli.fuzz_vm),
+            fuzz_publish: Some(cli.fuzz_publish),
+            max_stack_pow: Some(cli.max_stack_pow),
+            max_vector_len: Some(cli.max_vector_len),
+            max_sequence_len: Some(cli.max_sequence_len),
+            block_context_rate: Some(rates.block_context),
+            crossover_rate: Some(rates.crossover),
+            interesting_value_rate: Some(rates.interesting_value),
+            sender_rate: Some(rates.sender),
+        },
+        detectors: DetectorConfig {
+            enabled: Some(!cli.no_static_analysis),
+            whole_program: Some(cli.whole_program_analysis),
+            disabled: cli.disabled_detectors.clone(),
+            runtime_disabled: cli.disabled_runtime_detectors.clone(),
+            infinite_loop_threshold: Some(cli.infinite_loop_threshold),
+            unbounded_loop_instructions: Some(cli.unbounded_loop_instructions),
+            runtime_issues_objective: Some(!cli.runtime_issues_log_only),
+            runtime_objective_severity: Some(cli.runtime_objective_severity),
+            severities: cli.severities.clone(),
+            suppress: cli.suppressions.clone(),
+        },
+        objectives: ObjectiveConfig {
+            budget: Some(cli.budget_objective),
+            invariant_functions: Some(!cli.no_invariant_functions),
+            allow_aborts: cli.allow_aborts.clone(),
+            conserve_coins: cli.conserve_coins.clone(),
+            never_delete: cli.never_delete.clone(),
+            return_properties: cli.return_properties.clone(),
+            round_trips: cli.round_trips.clone(),
+            asset_conservation: Some(!cli.no_asset_conservation),
+            asset_issuers: cli.asset_issuers.clone(),
+            signer_ownership: Some(cli.signer_ownership),
+            resource_leaks: Some(cli.resource_leaks),
+            access_control: Some(cli.access_control),
+            view_functions: Some(cli.view_functions),
+            monotonic_views: cli.monotonic_views.clone(),
+        },
+    };
+    #[cfg(feature = "concolic")]
+    let config = {
+        let mut config = config;
+        config.execution.solver_timeout_ms = Some(cli.solver_timeout_ms);
+        config.execution.solver_queries_per_iteration = Some(cli.solver_queries);
+        config.execution.solve_branches = Some(cli.solve_branches);
+        config
+    };
+    config
+}
+
+/// Draws the dashboard, appends to the stats log and prints what happens to
+/// the campaign.
+struct CliMonitor {
+    dashboard: Dashboard,
+    stats_log: Option<StatsLog>,
+    start_time: Instant,
+}
+
+impl CampaignMonitor for CliMonitor {
+    fn progress(&mut self, state: &AptosFuzzerState, executor: &AptosMoveExecutor) {
+        self.dashboard.report(state, executor);
+        if let Some(log) = self.stats_log.as_mut() {
+            let logged = log
+                .record_solutions(self.start_time, state)
+                .and_then(|()| log.record_stats(self.start_time, state, executor));
+            if let Err(e) = logged {
+                self.dashboard
+                    .error(format!("[aptos-fuzzer] failed to write stats log: {}", e));
             }
         }
-        println!("(Static issues reported once before fuzzing)");
     }
-    state.set_static_findings(static_findings);
-    let _ = feedback.init_state(&mut state);
-    if state.corpus().count() == 0 {
-        println!("No fuzz inputs discovered from the provided modules; skipping fuzzing after static analysis.");
-        if !state.static_findings().is_empty() {
-            println!("Static analysis findings:");
-            for finding in state.static_findings() {
-                match &finding.function {
-                    Some(func) => println!(
-                        "  [{}] {}::{} - {}",
-                        finding.kind.as_str(),
-                        finding.module,
-                        func,
-                        finding.detail
-                    ),
-                    None => println!("  [{}] {} - {}", finding.kind.as_str(), finding.module, finding.detail),
+
+    fn event(&mut self, event: &CampaignEvent) {
+        match event {
+            CampaignEvent::SeedsLoaded { source, count } => {
+                self.dashboard
+                    .message(format!("Loaded {} seeds from {}", count, source.display()))
+            }
+            CampaignEvent::Started { inputs } => self.dashboard.message(format!(
+                "Starting fuzzing loop with {} initial inputs in corpus",
+                inputs
+            )),
+            CampaignEvent::Resumed { dir, resumed } => self.dashboard.message(format!(
+                "Resumed from {}: added {} corpus entries, replayed {} solutions",
+                dir.display(),
+                resumed.corpus,
+                resumed.solutions
+            )),
+            CampaignEvent::Reloaded { root, dropped, added } => self.dashboard.message(format!(
+                "[*] {} changed: reloaded modules, dropped {} and added {} corpus entries",
+                root.display(),
+                dropped,
+                added
+            )),
+            #[cfg(feature = "concolic")]
+            CampaignEvent::SymbolicDivergence(divergence) => self
+                .dashboard
+                .message(format!("[!] Symbolic divergence: {}", divergence)),
+            CampaignEvent::Revalidated { kept, dropped } => self.dashboard.message(format!(
+                "[*] Revalidated the corpus against the new target state: kept {}, dropped {} that no longer execute",
+                kept, dropped
+            )),
+            CampaignEvent::Upgraded {
+                modules_dir,
+                committed,
+                upgrade,
+                violations,
+            } => {
+                self.dashboard.message(format!(
+                    "[*] Built state from {} corpus entries before the upgrade",
+                    committed
+                ));
+                self.dashboard.message(format!(
+                    "[*] Upgraded the targets to {}: dropped {} and added {} corpus entries",
+                    modules_dir.display(),
+                    upgrade.dropped,
+                    upgrade.added
+                ));
+                for change in &upgrade.incompatibilities {
+                    self.dashboard.message(format!("[!] Incompatible upgrade: {}", change));
+                }
+                for resource in &upgrade.unreadable {
+                    self.dashboard
+                        .message(format!("[!] Unreadable after the upgrade: {}", resource));
+                }
+                for violation in violations.iter() {
+                    self.dashboard.message(format!(
+                        "[!] {} broken by the upgrade: {}",
+                        violation.oracle, violation.message
+                    ));
+                }
+            }
+            CampaignEvent::Failed(e) => self.dashboard.error(format!("[aptos-fuzzer] {:#}", e)),
+            CampaignEvent::Stopped(reason) => {
+                self.dashboard.finish();
+                match reason {
+                    StopReason::Objectives(solutions) => self
+                        .dashboard
+                        .message(format!("[+] Stopping after {} objectives", solutions)),
+                    StopReason::Crash => self.dashboard.message("[+] Stopping on VM crash".to_string()),
+                    StopReason::Plateau(plateau) => self
+                        .dashboard
+                        .message(format!("[+] Stopping: no progress for {}s", plateau.as_secs())),
+                    StopReason::Interrupted => self
+                        .dashboard
+                        .message("\n[*] Received interrupt signal, shutting down gracefully...".to_string()),
+                    StopReason::Timeout | StopReason::MaxExecutions | StopReason::Failed => {}
                 }
             }
         }
-        return;
     }
+}
 
-    let mut fuzzer = StdFuzzer::new(scheduler, feedback, objective);
+fn main() {
+    let matches = Cli::command().get_matches();
+    let mut cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    ui::claim_stdout(cli.ui);
+    say!("Starting Aptos Move Fuzzer...");
 
-    let mutator = AptosFuzzerMutator::default();
-    let mut stages = tuple_list!(StdMutationalStage::new(mutator));
+    let mut mutation_rates = MutationRates::default();
+    if let Some(path) = cli.config.clone() {
+        match FuzzerConfig::load(&path) {
+            Ok(config) => {
+                mutation_rates = apply_config(&mut cli, &matches, config);
+                say!("Loaded config {}", path.display());
+            }
+            Err(e) => {
+                eprintln!("[aptos-fuzzer] failed to load config: {:#}", e);
+                std::process::exit(FAILURE_EXIT_CODE);
+            }
+        }
+    }
+    match supervised_timeout() {
+        Some(timeout) => {
+            cli.timeout_seconds = timeout;
+            let quarantined = install_quarantine(cli.fuzzer_crashes_dir.clone(), |quarantined| match quarantined {
+                Ok(path) => eprintln!(
+                    "[aptos-fuzzer] quarantined the last input executed to {}",
+                    path.display()
+                ),
+                Err(e) => eprintln!("[aptos-fuzzer] {:#}", e),
+            });
+            if let Err(e) = quarantined {
+                eprintln!("[aptos-fuzzer] {:#}", e);
+                std::process::exit(FAILURE_EXIT_CODE);
+            }
+        }
+        None if cli.restart_on_crash && cli.command.is_none() => {
+            if cli.checkpoint_interval.is_none() {
+                eprintln!("[aptos-fuzzer] --restart-on-crash needs --checkpoint-interval to resume from");
+                std::process::exit(FAILURE_EXIT_CODE);
+            }
+            // Interrupts reach the child too, which shuts down by itself
+            let running = Arc::new(AtomicBool::new(true));
+            let r = running.clone();
+            ctrlc::set_handler(move || r.store(false, Ordering::SeqCst)).expect("Error setting Ctrl-C handler");
+            let timeout = (cli.timeout_seconds > 0).then(|| Duration::from_secs(cli.timeout_seconds));
+            let respawned = supervise(timeout, &running, |status| {
+                eprintln!("[aptos-fuzzer] fuzzer process died ({}), respawning", status)
+            });
+            match respawned {
+                Ok(status) => std::process::exit(status.code().unwrap_or(FAILURE_EXIT_CODE)),
+                Err(e) => {
+                    eprintln!("[aptos-fuzzer] {:#}", e);
+                    std::process::exit(FAILURE_EXIT_CODE);
+                }
+            }
+        }
+        None => {}
+    }
+    // Needs no targets, so runs before they are loaded
+    if let Some(Command::Compare { a, b }) = &cli.command {
+        match (CampaignSummary::load(a), CampaignSummary::load(b)) {
+            (Ok(a_summary), Ok(b_summary)) => print_comparison(
+                &a.display().to_string(),
+                &a_summary,
+                &b.display().to_string(),
+                &b_summary,
+            ),
+            (Err(e), _) | (_, Err(e)) => {
+                eprintln!("[aptos-fuzzer] {:#}", e);
+                std::process::exit(FAILURE_EXIT_CODE);
+            }
+        }
+        return;
+    }
+    if cli.timeout_seconds > 0 {
+        say!("Timeout: {} seconds", cli.timeout_seconds);
+    } else {
+        say!("Timeout: None (will run indefinitely, use Ctrl+C to stop)");
+    }
+    if let Some(url) = &cli.fork_url {
+        match cli.fork_version {
+            Some(version) => say!("Forking {} at version {}", url, version),
+            None => say!("Forking {} at the latest version", url),
+        }
+    }
 
-    println!(
-        "Starting fuzzing loop with {} initial inputs in corpus",
-        state.corpus().count()
-    );
+    let call_graph = matches!(cli.command, Some(Command::CallGraph { .. }));
+    let mut config = campaign_config(&cli, mutation_rates);
+    if call_graph {
+        // Only the seeds and solutions run, from a fresh corpus
+        config.corpus.checkpoint_interval = None;
+        config.corpus.sync_dir = None;
+    }
+    let stop = Arc::new(AtomicBool::new(false));
+    let mut builder = FuzzerBuilder::from_config(config)
+        .with_compiler(cli.aptos_cli.clone())
+        .with_instruction_profiling(cli.profile)
+        .with_stop_flag(stop.clone());
+    #[cfg(feature = "concolic")]
+    {
+        builder = builder.with_concolic_self_check(cli.concolic_self_check);
+        if let Some(target) = &cli.solve_abort {
+            say!("Solving for abort {}", target);
+            builder = builder.with_abort_target(target.clone());
+        }
+    }
+    if call_graph {
+        builder = builder.with_max_executions(0);
+    }
+    let mut campaign = match builder.build() {
+        Ok(campaign) => campaign,
+        Err(e) => {
+            eprintln!("[aptos-fuzzer] {:#}", e);
+            std::process::exit(FAILURE_EXIT_CODE);
+        }
+    };
+    say!("RNG seed: {}", campaign.rng_seed());
+    print_skipped_functions(campaign.state());
+    if cli.differential {
+        say!("Differential execution enabled");
+    }
 
-    // Prefer adding initial seeds via fuzzer.add_input to fire events and reflect
-    // in monitor
-    let initial_inputs = state.take_initial_inputs();
-    for input in initial_inputs {
-        let _ = fuzzer
-            .add_input(&mut state, &mut executor, &mut mgr, input)
-            .expect("failed to add initial input");
+    if let Some(Command::Trace { input, output }) = &cli.command {
+        let (executor, state) = campaign.parts_mut();
+        let trace = match load_input(input) {
+            Ok(loaded) => executor.trace_input(state, &loaded),
+            Err(e) => {
+                eprintln!("[aptos-fuzzer] failed to load input: {:#}", e);
+                std::process::exit(FAILURE_EXIT_CODE);
+            }
+        };
+        let path = output.clone().unwrap_or_else(|| input.with_extension("trace"));
+        match write_trace(&path, &trace) {
+            Ok(()) => say!("Wrote trace of {} to {}", input.display(), path.display()),
+            Err(e) => {
+                eprintln!("[aptos-fuzzer] failed to write trace: {:#}", e);
+                std::process::exit(FAILURE_EXIT_CODE);
+            }
+        }
+        return;
     }
 
-    // Setup graceful shutdown
-    let running = Arc::new(AtomicBool::new(true));
-    let r = running.clone();
+    if let Some(Command::Check) = &cli.command {
+        let (executor, state) = campaign.parts_mut();
+        match check_script_composer(state) {
+            Ok(()) => say!("Script sequences compile"),
+            Err(e) => say!(
+                "Script sequences don't compile, only entry functions will be fuzzed: {}",
+                e
+            ),
+        }
+        print_target_checks(&check_targets(executor, state));
+        print_suppressed_errors(state.suppressed_errors());
+        return;
+    }
 
-    ctrlc::set_handler(move || {
-        r.store(false, Ordering::SeqCst);
-        println!("\n[*] Received interrupt signal, shutting down gracefully...");
-    })
-    .expect("Error setting Ctrl-C handler");
+    let static_findings = campaign.analyze();
+    say!("Completed static analysis.");
+    if !static_findings.is_empty() {
+        say!("Static analysis findings:");
+        for finding in static_findings {
+            print_static_finding(finding);
+        }
+        say!("(Static issues reported once before fuzzing)");
+    }
 
-    // Setup timeout thread
-    if cli.timeout_seconds > 0 {
-        let r = running.clone();
-        let timeout_secs = cli.timeout_seconds;
-        std::thread::spawn(move || {
-            std::thread::sleep(Duration::from_secs(timeout_secs));
-            r.store(false, Ordering::SeqCst);
-        });
+    // Coverage of the seeds, plus where the stored solutions abort
+    let mut solution_sites = BTreeSet::new();
+    if call_graph {
+        let solutions = match &cli.solutions_dir {
+            Some(dir) if dir.is_dir() => load_seeds(dir).unwrap_or_else(|e| {
+                eprintln!("[aptos-fuzzer] failed to load solutions: {:#}", e);
+                Vec::new()
+            }),
+            _ => Vec::new(),
+        };
+        let (executor, state) = campaign.parts_mut();
+        for input in &solutions {
+            if let Some(abort) = executor.trace_input(state, input).abort {
+                solution_sites.insert((abort.module, abort.function));
+            }
+        }
+        if let Err(e) = campaign.add_seeds(solutions) {
+            eprintln!("[aptos-fuzzer] failed to add solutions: {:#}", e);
+            std::process::exit(FAILURE_EXIT_CODE);
+        }
     }
 
-    // Main fuzzing loop
+    // Setup graceful shutdown
+    let s = stop.clone();
+    // The campaign reports the interrupt when it stops
+    ctrlc::set_handler(move || s.store(true, Ordering::SeqCst)).expect("Error setting Ctrl-C handler");
+
     let start_time = Instant::now();
-    let mut last_print_time = Instant::now();
-    let print_interval = Duration::from_millis(500);
-
-    while running.load(Ordering::SeqCst) {
-        match fuzzer.fuzz_one(&mut stages, &mut executor, &mut state, &mut mgr) {
-            Ok(_) => {
-                if last_print_time.elapsed() >= print_interval {
-                    // Read cumulative coverage from state
-                    let coverage_map = state.cumulative_coverage();
-                    let total_instructions_executed = executor.total_instructions_executed();
-                    let total_possible_edges = state.aptos_state().total_possible_edges();
-                    print_fuzzer_stats(
-                        start_time,
-                        *state.executions(),
-                        state.corpus().count(),
-                        state.solutions().count(),
-                        coverage_map,
-                        total_instructions_executed,
-                        total_possible_edges,
-                    );
-                    last_print_time = Instant::now();
-                }
-            }
+    let stats_log = match cli.stats_log.as_deref().map(StatsLog::open).transpose() {
+        Ok(log) => log,
+        Err(e) => {
+            eprintln!("[aptos-fuzzer] failed to open stats log: {}", e);
+            std::process::exit(FAILURE_EXIT_CODE);
+        }
+    };
+    let mut monitor = CliMonitor {
+        dashboard: Dashboard::new(cli.ui, start_time),
+        stats_log,
+        start_time,
+    };
+    let report = match campaign.run_with(&mut monitor) {
+        Ok(report) => report,
+        Err(e) => {
+            monitor.dashboard.finish();
+            eprintln!("[aptos-fuzzer] {:#}", e);
+            std::process::exit(FAILURE_EXIT_CODE);
+        }
+    };
+
+    if let Some(Command::CallGraph { output }) = &cli.command {
+        match write_call_graph(output, &report.state, &solution_sites) {
+            Ok(()) => say!("Wrote call graph to {}", output.display()),
             Err(e) => {
-                eprintln!("Error during fuzzing: {:?}", e);
-                break;
+                eprintln!("[aptos-fuzzer] failed to write call graph: {:#}", e);
+                std::process::exit(FAILURE_EXIT_CODE);
+            }
+        }
+        return;
+    }
+    let CampaignReport {
+        stop_reason,
+        executions,
+        corpus_size,
+        solutions,
+        mut state,
+        mut executor,
+        ..
+    } = report;
+    if corpus_size == 0 && executions == 0 {
+        say!("No fuzz inputs discovered from the provided modules; skipping fuzzing after static analysis.");
+        if !state.static_findings().is_empty() {
+            say!("Static analysis findings:");
+            for finding in state.static_findings() {
+                print_static_finding(finding);
             }
         }
+        return;
     }
 
     // Print final statistics
-    println!("\n[+] Fuzzing completed");
-    println!("\nFinal Statistics:");
+    say!("\n[+] Fuzzing completed");
+    say!("\nFinal Statistics:");
     let coverage_map = state.cumulative_coverage();
     let total_instructions_executed = executor.total_instructions_executed();
     let total_possible_edges = state.aptos_state().total_possible_edges();
     print_fuzzer_stats(
         start_time,
-        *state.executions(),
-        state.corpus().count(),
-        state.solutions().count(),
+        executions,
+        corpus_size,
+        solutions.len(),
         coverage_map,
         total_instructions_executed,
         total_possible_edges,
     );
-    let solutions = state.take_solutions();
+    print_mutation_stats(state.mutation_counts(), state.mutation_weights());
+    print_suppressed_errors(state.suppressed_errors());
+    print_function_executions(state.function_executions());
+    print_redundant_entries(&state);
+    print_branch_worklist(&state, WORKLIST_BRANCHES);
+    print_ordering_conflicts(&state);
+    print_instantiation_stats(state.instantiations());
+    #[cfg(feature = "concolic")]
+    print_solver_stats(state.solver_stats());
+    #[cfg(feature = "concolic")]
+    if let (Some(target), Some(search)) = (&cli.solve_abort, state.abort_search()) {
+        print_abort_search(target, search);
+    }
+    if cli.profile {
+        print_profile(&state.hot_functions(PROFILE_FUNCTIONS, PROFILE_PCS));
+    }
+    if !state.runtime_findings().is_empty() {
+        say!("Runtime findings:");
+        for finding in state.runtime_findings() {
+            let location = state
+                .runtime_issue_location(&finding.issue)
+                .map(|location| format!(" at {}", location))
+                .unwrap_or_default();
+            say!(
+                "  [{:?}] [{}] {}{} (seen {} times)",
+                finding.severity,
+                finding.issue.kind.as_str(),
+                finding.issue.message,
+                location,
+                finding.count
+            );
+        }
+    }
+    let found_objectives = !solutions.is_empty();
+    let root_causes: Vec<Option<RootCause>> = solutions
+        .iter()
+        .map(|input| {
+            cli.bisect_solutions
+                .then(|| bisect_solution(&mut executor, &mut state, input))
+                .flatten()
+        })
+        .collect();
+    if let Some(dir) = &cli.solutions_dir {
+        let replay = |input: &AptosFuzzerInput| {
+            let trace = executor.trace_input(&mut state, input);
+            let changes = executor.resource_changes(&mut state, input);
+            (trace, state.struct_layouts().format_changes(&changes))
+        };
+        match write_solutions(dir, &solutions, &root_causes, replay) {
+            Ok(()) => say!("Wrote {} solutions to {}", solutions.len(), dir.display()),
+            Err(e) => eprintln!("[aptos-fuzzer] failed to write solutions: {:#}", e),
+        }
+    }
     if !solutions.is_empty() {
-        println!("Discovered solutions:");
-        for input in solutions {
-            println!("  {:?}", input);
+        say!("Discovered solutions:");
+        for (input, root_cause) in solutions.into_iter().zip(root_causes) {
+            say!("  {:?}", input);
+            let changes = executor.resource_changes(&mut state, &input);
+            if !changes.is_empty() {
+                say!("    State changes:");
+                for line in state.struct_layouts().format_changes(&changes) {
+                    say!("      {}", line);
+                }
+            }
+            if let Some(root_cause) = root_cause {
+                say!("    Root cause:");
+                for hint in &root_cause.hints {
+                    say!("      {}", hint);
+                }
+            }
             if let Some(execution_path) = state.get_solution_execution_path(&input) {
-                println!("    Execution path: {:?}", execution_path);
+                say!("    Execution path: {:?}", execution_path);
+                if let Some(events) = state.get_solution_events(&input) {
+                    for event in events {
+                        say!("    Event: {} ({} bytes)", event.type_tag, event.size);
+                    }
+                }
                 if let Some(path_id) = state.get_solution_execution_path_id(&input) {
                     if state.abort_code_paths.contains(&path_id) {
-                        println!("    Found InvariantViolation!");
+                        say!("    Found InvariantViolation!");
+                    }
+                    if let Some(site) = state.abort_site_paths.get(&path_id) {
+                        say!("    Aborted with {}", site);
+                    }
+                    if let Some(losses) = state.shift_overflow_paths.get(&path_id) {
+                        say!("    Found ShiftOverflow!");
+                        for loss in losses {
+                            say!("      {}", loss);
+                        }
+                    }
+                    if state.budget_exhausted_paths.contains(&path_id) {
+                        say!("    Found execution budget exhaustion!");
+                    }
+                    if let Some(issues) = state.runtime_issue_paths.get(&path_id) {
+                        for issue in issues {
+                            match state.runtime_issue_location(issue) {
+                                Some(location) => say!(
+                                    "    Found runtime issue [{}]: {} ({})",
+                                    issue.kind.as_str(),
+                                    issue.message,
+                                    location
+                                ),
+                                None => {
+                                    say!("    Found runtime issue [{}]: {}", issue.kind.as_str(), issue.message)
+                                }
+                            }
+                        }
+                    }
+                    if let Some(divergence) = state.divergence_paths.get(&path_id) {
+                        say!("    Found differential divergence: {}", divergence);
+                    }
+                    if let Some(leaks) = state.resource_leak_paths.get(&path_id) {
+                        for leak in leaks {
+                            say!("    Found resource leak: {}", leak);
+                        }
                     }
-                    if state.shift_overflow_paths.contains(&path_id) {
-                        println!("    Found ShiftOverflow!");
+                    if let Some(violations) = state.oracle_violation_paths.get(&path_id) {
+                        for violation in violations {
+                            say!(
+                                "    Found invariant violation [{}]: {}",
+                                violation.oracle,
+                                violation.message
+                            );
+                        }
                     }
                 }
             }
         }
     }
+    if found_objectives {
+        std::process::exit(FOUND_OBJECTIVES_EXIT_CODE);
+    }
+    if stop_reason == Some(StopReason::Failed) {
+        std::process::exit(FAILURE_EXIT_CODE);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use aptos_fuzzer::config::{CampaignConfig, FuzzerConfig, MutatorConfig, TargetConfig};
+    use aptos_fuzzer::MutationRates;
+    use clap::{CommandFactory, FromArgMatches};
+
+    use super::{apply_config, Cli};
+
+    /// `args` merged with `config`, as `main` does after loading it.
+    fn merged(args: &[&str], config: FuzzerConfig) -> (Cli, MutationRates) {
+        let args = ["libafl-aptos", "--config", "fuzz.toml"].iter().chain(args).copied();
+        let matches = Cli::command().try_get_matches_from(args).unwrap();
+        let mut cli = Cli::from_arg_matches(&matches).unwrap();
+        let rates = apply_config(&mut cli, &matches, config);
+        (cli, rates)
+    }
+
+    fn config() -> FuzzerConfig {
+        FuzzerConfig {
+            target: TargetConfig {
+                modules_dir: Some("config/modules".into()),
+                arg_constraints: vec!["pool::withdraw.0 in 1..=10".to_string()],
+                ..Default::default()
+            },
+            campaign: CampaignConfig {
+                timeout: Some(600),
+                ..Default::default()
+            },
+            mutator: MutatorConfig {
+                max_sequence_len: Some(4),
+                crossover_rate: Some(1),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn config_fills_what_the_command_line_leaves_out() {
+        let (cli, rates) = merged(&[], config());
+        assert_eq!(cli.modules_dir, Some("config/modules".into()));
+        assert_eq!(cli.timeout_seconds, 600);
+        assert_eq!(cli.max_sequence_len, 4);
+        assert_eq!(cli.arg_constraints, ["pool::withdraw.0 in 1..=10"]);
+        assert_eq!(rates.crossover, 1);
+        assert_eq!(rates.sender, MutationRates::default().sender);
+    }
+
+    #[test]
+    fn command_line_flags_override_the_config() {
+        let (cli, _) = merged(&["--modules-dir", "cli/modules", "--timeout", "30"], config());
+        assert_eq!(cli.modules_dir, Some("cli/modules".into()));
+        assert_eq!(cli.timeout_seconds, 30);
+    }
+
+    #[test]
+    fn flags_given_at_their_default_still_override_the_config() {
+        let (cli, _) = merged(&["--max-sequence-len", "16"], config());
+        assert_eq!(cli.max_sequence_len, 16);
+    }
+
+    #[test]
+    fn command_line_lists_replace_config_lists() {
+        let (cli, _) = merged(&["--arg-constraint", "pool::deposit.0 in 5..=6"], config());
+        assert_eq!(cli.arg_constraints, ["pool::deposit.0 in 5..=6"]);
+    }
+
+    #[test]
+    fn defaults_apply_when_neither_sets_a_value() {
+        let (cli, _) = merged(&["--modules-dir", "cli/modules"], FuzzerConfig::default());
+        assert_eq!(cli.timeout_seconds, 0);
+        assert_eq!(cli.max_sequence_len, 16);
+        assert!(cli.arg_constraints.is_empty());
+    }
 }
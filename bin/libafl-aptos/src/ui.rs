@@ -0,0 +1,405 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use aptos_fuzzer::state::FunctionCoverage;
+use aptos_fuzzer::{AptosFuzzerInput, AptosFuzzerState, AptosMoveExecutor};
+use clap::ValueEnum;
+use libafl::corpus::Corpus;
+use libafl::state::{HasCorpus, HasExecutions, HasSolutions};
+use serde_json::json;
+
+use crate::utils::{print_fuzzer_stats, print_mutation_stats};
+
+/// exec/s samples kept for the trend line
+const EXEC_HISTORY_LEN: usize = 60;
+/// Functions listed in the coverage table, least covered first
+const TUI_FUNCTION_ROWS: usize = 15;
+/// Latest campaign messages listed on the dashboard
+const TUI_MESSAGE_ROWS: usize = 5;
+const SPARKLINE: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+/// Kinds `objective_kind` sorts solutions into
+const OBJECTIVE_KINDS: [&str; 9] = [
+    "abort",
+    "crash",
+    "shift-overflow",
+    "budget-exhausted",
+    "oracle-violation",
+    "divergence",
+    "resource-leak",
+    "runtime-issue",
+    "other",
+];
+
+/// Set under `--ui json`, whose JSON lines own stdout
+static JSON_STDOUT: AtomicBool = AtomicBool::new(false);
+
+/// `println!` for the human-readable output of the CLI. Under `--ui json`
+/// it goes to stderr, so stdout only carries the JSON lines.
+macro_rules! say {
+    ($($arg:tt)*) => {
+        if $crate::ui::json_stdout() {
+            eprintln!($($arg)*)
+        } else {
+            println!($($arg)*)
+        }
+    };
+}
+pub(crate) use say;
+
+/// Reserve stdout for the JSON lines of `mode`, if it prints them.
+pub fn claim_stdout(mode: UiMode) {
+    JSON_STDOUT.store(mode == UiMode::Json, Ordering::Relaxed);
+}
+
+pub fn json_stdout() -> bool {
+    JSON_STDOUT.load(Ordering::Relaxed)
+}
+
+/// How progress is reported while fuzzing.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum UiMode {
+    /// Status lines on stdout
+    Plain,
+    /// Full-screen dashboard redrawn in place
+    Tui,
+    /// One JSON object per line on stdout; everything else goes to stderr
+    Json,
+}
+
+pub struct Dashboard {
+    mode: UiMode,
+    start_time: Instant,
+    last_sample: Instant,
+    last_executions: u64,
+    exec_history: VecDeque<f64>,
+    /// Messages shown on the dashboard, printed once it closes
+    messages: Vec<String>,
+}
+
+impl Dashboard {
+    pub fn new(mode: UiMode, start_time: Instant) -> Self {
+        if mode == UiMode::Tui {
+            // Alternate screen, hidden cursor
+            print!("\x1b[?1049h\x1b[?25l");
+            let _ = io::stdout().flush();
+        }
+        Self {
+            mode,
+            start_time,
+            last_sample: start_time,
+            last_executions: 0,
+            exec_history: VecDeque::with_capacity(EXEC_HISTORY_LEN),
+            messages: Vec::new(),
+        }
+    }
+
+    pub fn report(&mut self, state: &AptosFuzzerState, executor: &AptosMoveExecutor) {
+        let executions = *state.executions();
+        let elapsed = self.last_sample.elapsed().as_secs_f64();
+        if elapsed > 0.0 {
+            if self.exec_history.len() == EXEC_HISTORY_LEN {
+                self.exec_history.pop_front();
+            }
+            self.exec_history
+                .push_back(executions.saturating_sub(self.last_executions) as f64 / elapsed);
+        }
+        self.last_sample = Instant::now();
+        self.last_executions = executions;
+
+        match self.mode {
+            UiMode::Plain => {
+                print_fuzzer_stats(
+                    self.start_time,
+                    executions,
+                    state.corpus().count(),
+                    state.solutions().count(),
+                    state.cumulative_coverage(),
+                    executor.total_instructions_executed(),
+                    state.aptos_state().total_possible_edges(),
+                );
+                print_mutation_stats(state.mutation_counts(), state.mutation_weights());
+            }
+            UiMode::Json => println!("{}", snapshot(self.start_time, state, executor)),
+            UiMode::Tui => self.draw(state, executor),
+        }
+    }
+
+    /// Print a line about the campaign. The dashboard lists it instead, as
+    /// anything printed would be drawn over.
+    pub fn message(&mut self, line: String) {
+        match self.mode {
+            UiMode::Tui => self.messages.push(line),
+            UiMode::Plain | UiMode::Json => say!("{}", line),
+        }
+    }
+
+    /// As `message`, on stderr.
+    pub fn error(&mut self, line: String) {
+        match self.mode {
+            UiMode::Tui => self.messages.push(line),
+            UiMode::Plain | UiMode::Json => eprintln!("{}", line),
+        }
+    }
+
+    /// Restore the terminal before the final report, and print the
+    /// messages the dashboard listed.
+    pub fn finish(&mut self) {
+        if self.mode == UiMode::Tui {
+            print!("\x1b[?25h\x1b[?1049l");
+            let _ = io::stdout().flush();
+            self.mode = UiMode::Plain;
+            for line in self.messages.drain(..) {
+                println!("{}", line);
+            }
+        }
+    }
+
+    fn draw(&self, state: &AptosFuzzerState, executor: &AptosMoveExecutor) {
+        let executions = *state.executions();
+        let run_time = self.start_time.elapsed().as_secs_f64();
+        let covered = state.cumulative_coverage().iter().filter(|&&hit| hit > 0).count();
+        let total_edges = state.aptos_state().total_possible_edges();
+
+        let mut out = String::from("\x1b[H\x1b[2J");
+        out.push_str("Aptos Move Fuzzer\n\n");
+        out.push_str(&format!(
+            "run time: {:.0}s   executions: {}   exec/s: {:.0}   instrs: {}\n",
+            run_time,
+            executions,
+            executions as f64 / run_time.max(f64::EPSILON),
+            executor.total_instructions_executed()
+        ));
+        out.push_str(&format!("exec/s trend: {}\n", self.sparkline()));
+        out.push_str(&format!(
+            "corpus: {}   edges: {}/{}\n\n",
+            state.corpus().count(),
+            covered,
+            total_edges
+        ));
+
+        out.push_str(&format!("objectives: {}\n", state.solutions().count()));
+        for (kind, count) in objective_counts(state) {
+            out.push_str(&format!("  {:<18} {}\n", kind, count));
+        }
+        out.push_str(&format!(
+            "last objective: {}\n\n",
+            last_solution(state).map_or_else(|| "-".to_string(), |input| input.describe())
+        ));
+
+        #[cfg(feature = "concolic")]
+        {
+            let stats = state.solver_stats();
+            out.push_str(&format!(
+                "solver: {} queries   sat: {}   unsat: {}   unknown: {}   time: {:.2}s\n",
+                stats.queries,
+                stats.sat,
+                stats.unsat,
+                stats.unknown,
+                stats.time.as_secs_f64()
+            ));
+            out.push_str(&format!(
+                "        cache hits: {}   over budget: {}\n\n",
+                stats.cache_hits, stats.over_budget
+            ));
+        }
+
+        let mut functions = state.function_coverage();
+        functions.sort_by(|a, b| {
+            let ratio = |c: &FunctionCoverage| c.covered as f64 / c.total.max(1) as f64;
+            ratio(a).total_cmp(&ratio(b))
+        });
+        out.push_str("function coverage (least covered first):\n");
+        for function in functions.iter().take(TUI_FUNCTION_ROWS) {
+            out.push_str(&format!(
+                "  {:>5}/{:<5} {}::{}\n",
+                function.covered, function.total, function.module_id, function.function
+            ));
+        }
+        if functions.len() > TUI_FUNCTION_ROWS {
+            out.push_str(&format!("  ... {} more\n", functions.len() - TUI_FUNCTION_ROWS));
+        }
+        if !self.messages.is_empty() {
+            out.push_str("\nmessages:\n");
+            let skipped = self.messages.len().saturating_sub(TUI_MESSAGE_ROWS);
+            for line in &self.messages[skipped..] {
+                out.push_str(&format!("  {}\n", line));
+            }
+        }
+        out.push_str("\nCtrl-C to stop\n");
+
+        print!("{}", out);
+        let _ = io::stdout().flush();
+    }
+
+    fn sparkline(&self) -> String {
+        let max = self.exec_history.iter().cloned().fold(0.0, f64::max);
+        if max <= 0.0 {
+            return String::new();
+        }
+        self.exec_history
+            .iter()
+            .map(|rate| SPARKLINE[((rate / max) * (SPARKLINE.len() - 1) as f64).round() as usize])
+            .collect()
+    }
+}
+
+/// Current campaign statistics as a JSON object.
+pub fn snapshot(start_time: Instant, state: &AptosFuzzerState, executor: &AptosMoveExecutor) -> serde_json::Value {
+    let run_time = start_time.elapsed().as_secs_f64();
+    let executions = *state.executions();
+    let functions: Vec<serde_json::Value> = state
+        .function_coverage()
+        .iter()
+        .map(|function| {
+            json!({
+                "function": format!("{}::{}", function.module_id, function.function),
+                "covered": function.covered,
+                "total": function.total,
+            })
+        })
+        .collect();
+    let snapshot = json!({
+        "run_time": run_time,
+        "executions": executions,
+        "exec_per_sec": executions as f64 / run_time.max(f64::EPSILON),
+        "instructions": executor.total_instructions_executed(),
+        "corpus": state.corpus().count(),
+        "edges": state.cumulative_coverage().iter().filter(|&&hit| hit > 0).count(),
+        "total_edges": state.aptos_state().total_possible_edges(),
+        "objectives": state.solutions().count(),
+        "objectives_by_kind": objective_counts(state),
+        "mutations": state
+            .mutation_counts()
+            .iter()
+            .map(|(kind, count)| (kind.as_str(), *count))
+            .collect::<BTreeMap<_, _>>(),
+        "mutation_weights": state
+            .mutation_weights()
+            .arms()
+            .map(|(kind, weight, iterations, finds)| {
+                (kind.as_str(), json!({ "weight": weight, "iterations": iterations, "finds": finds }))
+            })
+            .collect::<BTreeMap<_, _>>(),
+        "functions": functions,
+        "one_sided_branches": state.branch_coverage().one_sided().count(),
+        "ordering_conflicts": state.conflict_analysis().observed().len(),
+    });
+    #[cfg(feature = "concolic")]
+    let snapshot = {
+        let mut snapshot = snapshot;
+        snapshot["solver"] = json!(state.solver_stats());
+        snapshot
+    };
+    snapshot
+}
+
+/// Appends one JSON object per line to a file: a `stats` event per
+/// reporting interval and a `solution` event per new solution.
+pub struct StatsLog {
+    file: File,
+    solutions_logged: usize,
+}
+
+impl StatsLog {
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self {
+            file,
+            solutions_logged: 0,
+        })
+    }
+
+    pub fn record_stats(
+        &mut self,
+        start_time: Instant,
+        state: &AptosFuzzerState,
+        executor: &AptosMoveExecutor,
+    ) -> io::Result<()> {
+        let mut event = snapshot(start_time, state, executor);
+        event["event"] = json!("stats");
+        event["timestamp"] = json!(unix_timestamp());
+        self.write(&event)
+    }
+
+    /// Log the solutions found since the last call.
+    pub fn record_solutions(&mut self, start_time: Instant, state: &AptosFuzzerState) -> io::Result<()> {
+        let ids: Vec<_> = state.solutions().ids().skip(self.solutions_logged).collect();
+        for id in ids {
+            self.solutions_logged += 1;
+            let Ok(input) = state.solutions().cloned_input_for_id(id) else {
+                continue;
+            };
+            let event = json!({
+                "event": "solution",
+                "timestamp": unix_timestamp(),
+                "run_time": start_time.elapsed().as_secs_f64(),
+                "executions": *state.executions(),
+                "calls": input.describe(),
+                "kind": objective_kind(state, &input),
+                "input": input,
+            });
+            self.write(&event)?;
+        }
+        Ok(())
+    }
+
+    fn write(&mut self, event: &serde_json::Value) -> io::Result<()> {
+        writeln!(self.file, "{}", event)
+    }
+}
+
+fn unix_timestamp() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0.0, |d| d.as_secs_f64())
+}
+
+/// Solutions per kind of finding, each counted once under the kind
+/// `objective_kind` gives it, so the counts add up to the solutions.
+pub fn objective_counts(state: &AptosFuzzerState) -> BTreeMap<&'static str, usize> {
+    let mut counts: BTreeMap<&'static str, usize> = OBJECTIVE_KINDS.iter().map(|&kind| (kind, 0)).collect();
+    for id in state.solutions().ids() {
+        let kind = state
+            .solutions()
+            .cloned_input_for_id(id)
+            .map_or("other", |input| objective_kind(state, &input));
+        *counts.entry(kind).or_default() += 1;
+    }
+    counts
+}
+
+/// Kind of finding a solution was kept for. Solutions on several kinds of
+/// path take the first that matches.
+pub fn objective_kind(state: &AptosFuzzerState, input: &AptosFuzzerInput) -> &'static str {
+    let Some(id) = state.get_solution_execution_path_id(input) else {
+        return "other";
+    };
+    if state.abort_code_paths.contains(&id) {
+        "abort"
+    } else if state.crash_paths.contains(&id) {
+        "crash"
+    } else if state.shift_overflow_paths.contains_key(&id) {
+        "shift-overflow"
+    } else if state.budget_exhausted_paths.contains(&id) {
+        "budget-exhausted"
+    } else if state.oracle_violation_paths.contains_key(&id) {
+        "oracle-violation"
+    } else if state.divergence_paths.contains_key(&id) {
+        "divergence"
+    } else if state.resource_leak_paths.contains_key(&id) {
+        "resource-leak"
+    } else if state.runtime_issue_paths.contains_key(&id) {
+        "runtime-issue"
+    } else {
+        "other"
+    }
+}
+
+fn last_solution(state: &AptosFuzzerState) -> Option<AptosFuzzerInput> {
+    let id = state.solutions().ids().last()?;
+    state.solutions().cloned_input_for_id(id).ok()
+}
@@ -1,5 +1,27 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::Path;
 use std::time::Instant;
 
+use anyhow::Context;
+#[cfg(feature = "concolic")]
+use aptos_fuzzer::abort_target::{AbortSearch, AbortTarget};
+use aptos_fuzzer::bisect::RootCause;
+use aptos_fuzzer::check::TargetCheck;
+use aptos_fuzzer::generics::InstantiationSchedule;
+#[cfg(feature = "concolic")]
+use aptos_fuzzer::solver::SolverStats;
+use aptos_fuzzer::state::HotFunction;
+use aptos_fuzzer::static_analysis::{build_call_graph, StaticAnalysisFinding};
+use aptos_fuzzer::trace::ExecutionTrace;
+use aptos_fuzzer::{AptosFuzzerInput, AptosFuzzerState, MutationKind, MutationWeights, Subsystem};
+use libafl::corpus::Corpus;
+use libafl::state::HasCorpus;
+
+use crate::ui::say;
+
 /// Size of coverage map segments for statistics reporting
 const COVERAGE_SEGMENT_SIZE: usize = 4096;
 
@@ -38,7 +60,7 @@ pub fn print_fuzzer_stats(
     };
 
     if total_possible_edges > 0 {
-        println!(
+        say!(
             "run time: {:.0}s, clients: 1, corpus: {}, objectives: {}, executions: {}, exec/sec: {}, edges: {} ({:.2}%)",
             elapsed_secs,
             corpus_size,
@@ -49,9 +71,14 @@ pub fn print_fuzzer_stats(
             edge_coverage_pct
         );
     } else {
-        println!(
+        say!(
             "run time: {:.0}s, clients: 1, corpus: {}, objectives: {}, executions: {}, exec/sec: {}, edges: {}",
-            elapsed_secs, corpus_size, solutions_size, executions, exec_per_sec_str, edge_display
+            elapsed_secs,
+            corpus_size,
+            solutions_size,
+            executions,
+            exec_per_sec_str,
+            edge_display
         );
     }
 
@@ -65,12 +92,406 @@ pub fn print_fuzzer_stats(
     let covered_segments = count_covered_segments(coverage_map, COVERAGE_SEGMENT_SIZE);
     let total_segments = coverage_map.len().div_ceil(COVERAGE_SEGMENT_SIZE);
 
-    println!(
+    say!(
         "instrs: {} (avg {:.1}/exec), segments: {}/{}",
-        total_instructions_executed, avg_instrs, covered_segments, total_segments
+        total_instructions_executed,
+        avg_instrs,
+        covered_segments,
+        total_segments
+    );
+}
+
+// Print how the applied mutations are distributed over their kinds, and the
+// weight the mutator has learned for each
+pub fn print_mutation_stats(mutation_counts: &BTreeMap<MutationKind, u64>, weights: &MutationWeights) {
+    let total: u64 = mutation_counts.values().sum();
+    if total == 0 {
+        return;
+    }
+    let breakdown: Vec<String> = mutation_counts
+        .iter()
+        .map(|(kind, count)| format!("{} {:.1}%", kind.as_str(), *count as f64 * 100.0 / total as f64))
+        .collect();
+    say!("mutations: {} ({})", total, breakdown.join(", "));
+    let learned: Vec<String> = weights
+        .arms()
+        .map(|(kind, weight, _, finds)| format!("{} x{:.2} ({} finds)", kind.as_str(), weight, finds))
+        .collect();
+    if !learned.is_empty() {
+        say!("mutation weights: {}", learned.join(", "));
+    }
+}
+
+// Print how many errors each subsystem skipped past, so a run that quietly
+// lost most of its seeds or mutants shows it
+pub fn print_suppressed_errors(suppressed: &BTreeMap<Subsystem, u64>) {
+    if suppressed.is_empty() {
+        return;
+    }
+    let breakdown: Vec<String> = suppressed
+        .iter()
+        .map(|(subsystem, count)| format!("{} {}", subsystem, count))
+        .collect();
+    say!("suppressed errors: {}", breakdown.join(", "));
+}
+
+// Print how many executions called each function, most executed first, so
+// a function starved by the others shows it
+pub fn print_function_executions(executions: &BTreeMap<String, u64>) {
+    let total: u64 = executions.values().sum();
+    if total == 0 {
+        return;
+    }
+    let mut by_count: Vec<(&String, &u64)> = executions.iter().collect();
+    by_count.sort_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.cmp(b.0)));
+    say!("function executions:");
+    for (function, count) in by_count {
+        say!(
+            "  {} {} ({:.1}%)",
+            function,
+            count,
+            *count as f64 * 100.0 / total as f64
+        );
+    }
+}
+
+// Print how many corpus entries cover no edge the others don't, a measure of
+// how much a minimizing scheduler or corpus pruning would save, and how many
+// sequence prefixes were already pruned
+pub fn print_redundant_entries(state: &AptosFuzzerState) {
+    let redundant = state.redundant_corpus_entries().len();
+    if redundant > 0 {
+        say!(
+            "redundant corpus entries: {} of {} (edges all covered by other entries)",
+            redundant,
+            state.corpus().count()
+        );
+    }
+    let pruned = state.pruned_prefixes();
+    if pruned > 0 {
+        say!(
+            "pruned sequence prefixes: {} (edges all covered by a longer sequence)",
+            pruned
+        );
+    }
+}
+
+// Print the branches only one way of which was ever taken, the first `limit`
+// of the worklist in order, with the way missing and why they rank there
+pub fn print_branch_worklist(state: &AptosFuzzerState, limit: usize) {
+    let coverage = state.branch_coverage();
+    let worklist = state.branch_worklist();
+    if worklist.is_empty() {
+        return;
+    }
+    say!(
+        "one-sided branches: {} of {} conditional branches",
+        worklist.len(),
+        coverage.sites().count()
+    );
+    for branch in worklist.iter().take(limit) {
+        let site = branch.site;
+        say!(
+            "  {}::{} @ pc {}: never {}, taken {} times ({}{})",
+            site.module,
+            site.function,
+            site.pc,
+            site.missing().unwrap_or_default(),
+            site.hits(),
+            branch.proximity.as_str(),
+            if site.infeasible { ", infeasible" } else { "" }
+        );
+    }
+    if worklist.len() > limit {
+        say!("  ... and {} more", worklist.len() - limit);
+    }
+    if coverage.queries > 0 {
+        say!(
+            "branch flips: {} queries, {} inputs solved",
+            coverage.queries,
+            coverage.solved
+        );
+    }
+}
+
+// Print the call pairs of script sequences seen touching what the earlier
+// call wrote, whose outcome may depend on their order, and the resources
+// written and accessed by several functions, where transactions of a block
+// would conflict under Block-STM
+pub fn print_ordering_conflicts(state: &AptosFuzzerState) {
+    let analysis = state.conflict_analysis();
+    if !analysis.observed().is_empty() {
+        say!("ordering-sensitive calls:");
+        for (conflict, executions) in analysis.observed() {
+            say!("  {} (seen in {} executions)", conflict, executions);
+        }
+    }
+    let hotspots = analysis.hotspots();
+    if !hotspots.is_empty() {
+        say!("conflict hotspots:");
+        for hotspot in hotspots {
+            let readers = if hotspot.readers.is_empty() {
+                String::new()
+            } else {
+                format!(", read by {}", hotspot.readers.join(", "))
+            };
+            say!(
+                "  {}: written by {}{}",
+                hotspot.resource,
+                hotspot.writers.join(", "),
+                readers
+            );
+        }
+    }
+}
+
+// Print what share of the targets' public functions is fuzzed, and how many
+// are skipped for each kind of reason
+pub fn print_skipped_functions(state: &AptosFuzzerState) {
+    let skipped = state.skipped_functions();
+    let fuzzed = state.public_functions().len() + state.generic_functions().len();
+    let mut reasons: BTreeMap<&str, usize> = BTreeMap::new();
+    for function in skipped {
+        *reasons.entry(function.reason.kind()).or_insert(0) += 1;
+    }
+    let breakdown: Vec<String> = reasons
+        .iter()
+        .map(|(kind, count)| format!("{} {}", count, kind))
+        .collect();
+    if breakdown.is_empty() {
+        say!("Fuzzing all {} public functions", fuzzed);
+    } else {
+        say!(
+            "Fuzzing {} of {} public functions, skipped {} ({}); run `check` for details",
+            fuzzed,
+            fuzzed + skipped.len(),
+            skipped.len(),
+            breakdown.join(", ")
+        );
+    }
+}
+
+// Print a row per public function of the targets: fuzzed with how its seed
+// ran, or skipped with why
+pub fn print_target_checks(checks: &[TargetCheck]) {
+    let width = checks.iter().map(|check| check.function.len()).max().unwrap_or(0);
+    let fuzzed = checks.iter().filter(|check| check.outcome.is_ok()).count();
+    say!("{} of {} public functions fuzzed:", fuzzed, checks.len());
+    for check in checks {
+        let (status, detail) = match &check.outcome {
+            Ok(status) if check.signer_only => ("fuzzed", format!("{}; signer-only, no arguments to mutate", status)),
+            Ok(status) => ("fuzzed", status.clone()),
+            Err(reason) => ("skipped", reason.to_string()),
+        };
+        say!("  {:<width$}  {:<7}  {}", check.function, status, detail, width = width);
+    }
+}
+
+// Print the type arguments each generic function was called with that found
+// something, most productive first
+pub fn print_instantiation_stats(schedule: &InstantiationSchedule) {
+    let mut productive: Vec<_> = schedule
+        .instantiations()
+        .filter(|(_, _, _, _, finds)| *finds > 0)
+        .collect();
+    if productive.is_empty() {
+        return;
+    }
+    productive.sort_by(|a, b| b.4.cmp(&a.4).then(a.3.cmp(&b.3)));
+    say!("generic instantiations:");
+    for (module_id, function, ty_args, runs, finds) in productive {
+        let ty_args: Vec<String> = ty_args.iter().map(|ty| ty.to_canonical_string()).collect();
+        say!(
+            "  {}::{}<{}>: {} finds in {} runs",
+            module_id,
+            function,
+            ty_args.join(", "),
+            finds,
+            runs
+        );
+    }
+}
+
+// Print constraint solver usage, if the solver was used at all
+#[cfg(feature = "concolic")]
+pub fn print_solver_stats(stats: &SolverStats) {
+    if stats.queries == 0 && stats.cache_hits == 0 {
+        return;
+    }
+    say!(
+        "solver: {} queries ({} sat, {} unsat, {} unknown) in {:.2}s, {} cache hits, {} over budget",
+        stats.queries,
+        stats.sat,
+        stats.unsat,
+        stats.unknown,
+        stats.time.as_secs_f64(),
+        stats.cache_hits,
+        stats.over_budget
     );
 }
 
+// Print the outcome of the --solve-abort search
+#[cfg(feature = "concolic")]
+pub fn print_abort_search(target: &AbortTarget, search: &AbortSearch) {
+    say!("abort {}: {}", target, search.verdict());
+    say!(
+        "  {} guarding branch queries ({} solved, {} unsat, {} unknown)",
+        search.queries,
+        search.solved,
+        search.unsat,
+        search.unknown
+    );
+}
+
+// Print where execution time went: hottest functions and their hottest pcs
+pub fn print_profile(functions: &[HotFunction]) {
+    if functions.is_empty() {
+        return;
+    }
+    say!("Hot functions (instructions executed):");
+    for function in functions {
+        let pcs: Vec<String> = function
+            .hottest_pcs
+            .iter()
+            .map(|(pc, count)| format!("pc {} x{}", pc, count))
+            .collect();
+        say!("  {:>12} {} [{}]", function.instructions, function.name, pcs.join(", "));
+    }
+}
+
+// Print one static analysis finding, with its source location when known
+pub fn print_static_finding(finding: &StaticAnalysisFinding) {
+    let location = finding
+        .location
+        .as_ref()
+        .map(|location| format!(" ({})", location))
+        .unwrap_or_default();
+    match &finding.function {
+        Some(func) => say!(
+            "  [{:?}] [{}] {}::{} - {}{}",
+            finding.severity,
+            finding.kind.as_str(),
+            finding.module,
+            func,
+            finding.detail,
+            location
+        ),
+        None => say!(
+            "  [{:?}] [{}] {} - {}{}",
+            finding.severity,
+            finding.kind.as_str(),
+            finding.module,
+            finding.detail,
+            location
+        ),
+    }
+}
+
+// Write each solution to `dir` as `<hash>.json`, loadable as a seed, with
+// the trace of replaying it as `<hash>.trace` and the resources it changes
+// as `<hash>.diff`. A bisected one gets its root-cause hints as
+// `<hash>.cause` and its reduced input as `<hash>.min.json`
+pub fn write_solutions(
+    dir: &Path,
+    solutions: &[AptosFuzzerInput],
+    root_causes: &[Option<RootCause>],
+    mut replay: impl FnMut(&AptosFuzzerInput) -> (ExecutionTrace, Vec<String>),
+) -> anyhow::Result<()> {
+    fs::create_dir_all(dir).with_context(|| format!("failed to create {}", dir.display()))?;
+    for (idx, input) in solutions.iter().enumerate() {
+        let mut hasher = DefaultHasher::new();
+        input.hash(&mut hasher);
+        let path = dir.join(format!("{:016x}.json", hasher.finish()));
+        fs::write(&path, serde_json::to_string_pretty(input)?)
+            .with_context(|| format!("failed to write {}", path.display()))?;
+        let (trace, diff) = replay(input);
+        write_trace(&path.with_extension("trace"), &trace)?;
+        let diff_path = path.with_extension("diff");
+        fs::write(
+            &diff_path,
+            diff.iter().map(|line| format!("{}\n", line)).collect::<String>(),
+        )
+        .with_context(|| format!("failed to write {}", diff_path.display()))?;
+        if let Some(Some(root_cause)) = root_causes.get(idx) {
+            let cause_path = path.with_extension("cause");
+            fs::write(
+                &cause_path,
+                root_cause
+                    .hints
+                    .iter()
+                    .map(|hint| format!("{}\n", hint))
+                    .collect::<String>(),
+            )
+            .with_context(|| format!("failed to write {}", cause_path.display()))?;
+            let reduced_path = path.with_extension("min.json");
+            fs::write(&reduced_path, serde_json::to_string_pretty(&root_cause.reduced)?)
+                .with_context(|| format!("failed to write {}", reduced_path.display()))?;
+        }
+    }
+    Ok(())
+}
+
+// Write the call graph of the target modules as Graphviz DOT. Nodes are
+// filled by the share of their edges covered; functions with static findings
+// get an orange border and those a solution aborted in (as `0xADDR::module`
+// and function) or with runtime findings a red one.
+pub fn write_call_graph(
+    path: &Path,
+    state: &AptosFuzzerState,
+    solution_sites: &BTreeSet<(String, String)>,
+) -> anyhow::Result<()> {
+    let graph = build_call_graph(state.aptos_state(), state.target_modules());
+    let dot = graph.to_dot(|function| {
+        let mut attributes = match state.edge_coverage(&function.module, &function.name) {
+            None => "style=dashed".to_string(),
+            Some((_, 0)) => String::new(),
+            Some((0, total)) => format!("fillcolor=\"#f4cccc\", tooltip=\"0/{} edges\"", total),
+            Some((covered, total)) if covered < total => {
+                format!("fillcolor=\"#fff2cc\", tooltip=\"{}/{} edges\"", covered, total)
+            }
+            Some((covered, total)) => format!("fillcolor=\"#d9ead3\", tooltip=\"{}/{} edges\"", covered, total),
+        };
+        let site = (
+            format!(
+                "{}::{}",
+                function.module.address().to_hex_literal(),
+                function.module.name()
+            ),
+            function.name.clone(),
+        );
+        let solved = solution_sites.contains(&site) ||
+            state
+                .runtime_findings()
+                .iter()
+                .any(|finding| finding.issue.module == site.0 && finding.issue.function == site.1);
+        // Findings name modules the way the move model prints them
+        let module_suffix = format!("::{}", function.module.name());
+        let flagged = state.static_findings().iter().any(|finding| {
+            finding.module.ends_with(&module_suffix) && finding.function.as_ref() == Some(&function.name)
+        });
+        let border = if solved {
+            "color=red, penwidth=3"
+        } else if flagged {
+            "color=orange, penwidth=2"
+        } else {
+            ""
+        };
+        if !border.is_empty() {
+            if !attributes.is_empty() {
+                attributes.push_str(", ");
+            }
+            attributes.push_str(border);
+        }
+        attributes
+    });
+    fs::write(path, dot).with_context(|| format!("failed to write {}", path.display()))
+}
+
+// Write an execution trace as JSON
+pub fn write_trace(path: &Path, trace: &ExecutionTrace) -> anyhow::Result<()> {
+    fs::write(path, serde_json::to_string_pretty(trace)?).with_context(|| format!("failed to write {}", path.display()))
+}
+
 // Count segments that have any coverage
 fn count_covered_segments(coverage_map: &[u8], segment_size: usize) -> usize {
     let num_segments = coverage_map.len().div_ceil(segment_size);
@@ -0,0 +1,52 @@
+use aptos_fuzzer::observers::DirtyMapObserver;
+use aptos_fuzzer::MAP_SIZE;
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use libafl::observers::map::{HitcountsMapObserver, OwnedMapObserver};
+use libafl::observers::Observer;
+use libafl_bolts::AsSliceMut;
+
+/// Slots hit by one execution; typical runs touch a few hundred.
+const TOUCHED: [usize; 3] = [64, 512, 4096];
+
+fn touched_slots(count: usize) -> Vec<usize> {
+    // Spread over the map like layout-assigned edge IDs
+    (0..count).map(|i| i * 7919 % MAP_SIZE).collect()
+}
+
+fn edges() -> HitcountsMapObserver<OwnedMapObserver<u8>> {
+    HitcountsMapObserver::new(OwnedMapObserver::new("edges", vec![0u8; MAP_SIZE]))
+}
+
+/// A run's hits followed by the observer reset before the next run, with the
+/// base map's full reset and with the executor's dirty-slot reset.
+fn coverage_reset(c: &mut Criterion) {
+    let mut group = c.benchmark_group("coverage_reset");
+    for count in TOUCHED {
+        let slots = touched_slots(count);
+        let mut full = edges();
+        group.bench_with_input(BenchmarkId::new("full_map", count), &slots, |b, slots| {
+            b.iter(|| {
+                {
+                    let mut map = full.as_slice_mut();
+                    for &slot in slots {
+                        map[slot] = map[slot].saturating_add(1);
+                    }
+                }
+                Observer::<(), ()>::pre_exec(&mut full, &mut (), &()).unwrap();
+                black_box(&full);
+            })
+        });
+        let mut dirty = DirtyMapObserver::new(edges());
+        group.bench_with_input(BenchmarkId::new("dirty_slots", count), &slots, |b, slots| {
+            b.iter(|| {
+                dirty.record_hits(slots);
+                Observer::<(), ()>::pre_exec(&mut dirty, &mut (), &()).unwrap();
+                black_box(&dirty);
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, coverage_reset);
+criterion_main!(benches);
@@ -0,0 +1,303 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::{anyhow, bail, Context};
+use aptos_move_binary_format::access::ModuleAccess;
+use aptos_move_binary_format::file_format::{Bytecode, ConstantPoolIndex, SignatureToken};
+use aptos_move_binary_format::CompiledModule;
+use aptos_move_core_types::account_address::AccountAddress;
+use aptos_move_core_types::identifier::Identifier;
+use aptos_move_core_types::language_storage::ModuleId;
+use aptos_move_core_types::u256::U256;
+use aptos_types::transaction::{EntryFunction, TransactionPayload};
+
+use crate::input::AptosFuzzerInput;
+use crate::mutator::{decode_uleb128, encode_vector};
+use crate::solver::SolveOutcome;
+
+/// An abort code raised in one function, the goal of `--solve-abort`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AbortTarget {
+    pub module: ModuleId,
+    pub function: String,
+    pub code: u64,
+}
+
+impl FromStr for AbortTarget {
+    type Err = anyhow::Error;
+
+    /// `0xADDR::module::function::code`
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let mut parts = s.rsplitn(3, "::");
+        let (Some(code), Some(function), Some(module)) = (parts.next(), parts.next(), parts.next()) else {
+            bail!(
+                "invalid abort target {}, expected <address>::<module>::<function>::<code>",
+                s
+            );
+        };
+        let (address, name) = module.split_once("::").ok_or_else(|| {
+            anyhow!(
+                "invalid module {} in abort target, expected <address>::<module>",
+                module
+            )
+        })?;
+        let address = AccountAddress::from_hex_literal(address)
+            .with_context(|| format!("invalid address {} in abort target", address))?;
+        let name = Identifier::new(name).with_context(|| format!("invalid module name {}", name))?;
+        let code = code
+            .parse()
+            .with_context(|| format!("invalid abort code {} in abort target", code))?;
+        Ok(Self {
+            module: ModuleId::new(address, name),
+            function: function.to_string(),
+            code,
+        })
+    }
+}
+
+impl fmt::Display for AbortTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}::{}::{}::{}",
+            self.module.address().to_hex_literal(),
+            self.module.name(),
+            self.function,
+            self.code
+        )
+    }
+}
+
+impl AbortTarget {
+    /// Find the target's `Abort` instructions in `module` and the branches
+    /// that decide whether one of them is reached. An abort whose code is a
+    /// literal other than the target's is ignored; one whose code is
+    /// computed is kept, since it may raise the target code.
+    pub fn guards(&self, module: &CompiledModule) -> anyhow::Result<AbortGuards> {
+        let def = module
+            .function_defs()
+            .iter()
+            .find(|def| {
+                module
+                    .identifier_at(module.function_handle_at(def.function).name)
+                    .as_str() ==
+                    self.function
+            })
+            .ok_or_else(|| anyhow!("{} has no function {}", self.module, self.function))?;
+        let code = &def
+            .code
+            .as_ref()
+            .ok_or_else(|| anyhow!("{}::{} is native", self.module, self.function))?
+            .code;
+        let is_target = |pc: usize| {
+            matches!(code[pc], Bytecode::Abort) &&
+                match pc.checked_sub(1).map(|prev| &code[prev]) {
+                    Some(Bytecode::LdU64(literal)) => *literal == self.code,
+                    Some(Bytecode::LdConst(idx)) => constant_u64(module, *idx).is_none_or(|value| value == self.code),
+                    _ => true,
+                }
+        };
+        let abort_pcs: Vec<u16> = (0..code.len())
+            .filter(|&pc| is_target(pc))
+            .map(|pc| pc as u16)
+            .collect();
+        if abort_pcs.is_empty() {
+            bail!(
+                "{}::{} has no abort that can raise {}",
+                self.module,
+                self.function,
+                self.code
+            );
+        }
+
+        let successors = |pc: usize| -> Vec<usize> {
+            match &code[pc] {
+                Bytecode::Branch(offset) => vec![*offset as usize],
+                Bytecode::BrTrue(offset) | Bytecode::BrFalse(offset) => vec![*offset as usize, pc + 1],
+                Bytecode::Ret | Bytecode::Abort => Vec::new(),
+                _ => vec![pc + 1],
+            }
+        };
+        // Instructions from which a target abort can still be reached
+        let mut reaches: Vec<bool> = (0..code.len()).map(is_target).collect();
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for pc in (0..code.len()).rev() {
+                if !reaches[pc] &&
+                    successors(pc)
+                        .into_iter()
+                        .any(|next| reaches.get(next).copied().unwrap_or(false))
+                {
+                    reaches[pc] = true;
+                    changed = true;
+                }
+            }
+        }
+
+        let reaches_at = |pc: usize| reaches.get(pc).copied().unwrap_or(false);
+        let branches = code
+            .iter()
+            .enumerate()
+            .filter_map(|(pc, instruction)| {
+                let (if_false, if_true) = match instruction {
+                    Bytecode::BrTrue(offset) => (pc + 1, *offset as usize),
+                    Bytecode::BrFalse(offset) => (*offset as usize, pc + 1),
+                    _ => return None,
+                };
+                Some((pc as u16, [reaches_at(if_false), reaches_at(if_true)]))
+            })
+            .collect();
+        Ok(AbortGuards {
+            module: format!("{}::{}", self.module.address().to_hex_literal(), self.module.name()),
+            function: self.function.clone(),
+            code: self.code,
+            abort_pcs,
+            branches,
+        })
+    }
+}
+
+fn constant_u64(module: &CompiledModule, idx: ConstantPoolIndex) -> Option<u64> {
+    let constant = module.constant_pool().get(idx.0 as usize)?;
+    if constant.type_ != SignatureToken::U64 {
+        return None;
+    }
+    Some(u64::from_le_bytes(constant.data.as_slice().try_into().ok()?))
+}
+
+/// The control flow of the target function relative to its target aborts,
+/// as the tracer needs it while executing.
+#[derive(Clone, Debug)]
+pub struct AbortGuards {
+    /// `0xADDR::module`, as the tracer names frames
+    pub module: String,
+    pub function: String,
+    pub code: u64,
+    pub abort_pcs: Vec<u16>,
+    /// For each conditional branch, whether a target abort can be reached
+    /// when the condition is false and when it is true
+    branches: HashMap<u16, [bool; 2]>,
+}
+
+impl AbortGuards {
+    /// Whether the branch at `pc`, going the `condition` way, can still reach
+    /// a target abort.
+    pub fn leads_to_abort(&self, pc: u16, condition: bool) -> bool {
+        self.branches.get(&pc).is_some_and(|ways| ways[condition as usize])
+    }
+}
+
+/// Progress of the `--solve-abort` search over the campaign.
+#[derive(Debug, Default)]
+pub struct AbortSearch {
+    /// First executed input that raised the target abort
+    pub reached: Option<AptosFuzzerInput>,
+    /// Solved inputs not executed yet
+    witnesses: Vec<AptosFuzzerInput>,
+    seen: HashSet<AptosFuzzerInput>,
+    /// Guarding branches the solver was asked to flip
+    pub queries: u64,
+    /// Distinct witness inputs solved for
+    pub solved: u64,
+    pub unsat: u64,
+    /// Timed out or refused for lack of budget
+    pub unknown: u64,
+}
+
+impl AbortSearch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record_reached(&mut self, input: &AptosFuzzerInput) {
+        if self.reached.is_none() {
+            self.reached = Some(input.clone());
+        }
+    }
+
+    /// Account for the answer to a query asked while executing `input`,
+    /// queueing the solved input if it is new.
+    pub fn record_outcome(&mut self, input: &AptosFuzzerInput, outcome: SolveOutcome) {
+        self.queries += 1;
+        match outcome {
+            SolveOutcome::Sat(values) => {
+                if let Some(witness) = witness_input(input, &values) {
+                    if self.seen.insert(witness.clone()) {
+                        self.solved += 1;
+                        self.witnesses.push(witness);
+                    }
+                }
+            }
+            SolveOutcome::Unsat => self.unsat += 1,
+            SolveOutcome::Unknown | SolveOutcome::OverBudget => self.unknown += 1,
+        }
+    }
+
+    /// Solved inputs to execute next.
+    pub fn take_witnesses(&mut self) -> Vec<AptosFuzzerInput> {
+        std::mem::take(&mut self.witnesses)
+    }
+
+    /// One-line result for the final report.
+    pub fn verdict(&self) -> String {
+        match &self.reached {
+            Some(input) => format!("reached by {:?}", input),
+            None if self.solved > 0 => format!(
+                "not reached: {} solved witness(es) did not raise the abort when executed",
+                self.solved
+            ),
+            None if self.unknown > 0 => format!(
+                "undecided: {} of {} queries timed out or ran over budget",
+                self.unknown, self.queries
+            ),
+            None if self.queries == 0 => {
+                "unknown: not attempted (no guarding branch was reached with a symbolic condition)".to_string()
+            }
+            None => format!("unreachable under current model ({} queries unsatisfiable)", self.unsat),
+        }
+    }
+}
+
+/// `input` with the entry function arguments the solver assigned replaced.
+/// Arguments are named `<call>.<param>` by the tracer; only the first call of
+/// an entry function payload is rewritten.
+pub(crate) fn witness_input(input: &AptosFuzzerInput, values: &[(String, String)]) -> Option<AptosFuzzerInput> {
+    let TransactionPayload::EntryFunction(entry) = input.payload() else {
+        return None;
+    };
+    let (module, function, ty_args, mut args) = entry.clone().into_inner();
+    for (name, value) in values {
+        let Some(("0", param)) = name.split_once('.') else {
+            continue;
+        };
+        // Vector lengths are named `<call>.<param>.len.<element size>`
+        let (param, element_size) = match param.split_once(".len.") {
+            Some((param, size)) => (param, Some(size.parse::<usize>().ok()?)),
+            None => (param, None),
+        };
+        let Some(arg) = param.parse::<usize>().ok().and_then(|param| args.get_mut(param)) else {
+            continue;
+        };
+        if let Some(size) = element_size {
+            *arg = resize_vector(arg, size, value.parse().ok()?)?;
+            continue;
+        }
+        let value = U256::from_str_radix(value, 10).ok()?;
+        let len = arg.len().min(32);
+        arg[..len].copy_from_slice(&value.to_le_bytes()[..len]);
+    }
+    let mut witness = input.clone();
+    *witness.payload_mut() = TransactionPayload::EntryFunction(EntryFunction::new(module, function, ty_args, args));
+    Some(witness)
+}
+
+/// Re-encode a BCS vector of `size`-byte elements with `len` elements,
+/// keeping its leading elements and zero-filling new ones.
+fn resize_vector(bytes: &[u8], size: usize, len: usize) -> Option<Vec<u8>> {
+    let (_, offset) = decode_uleb128(bytes)?;
+    let mut elements: Vec<Vec<u8>> = bytes[offset..].chunks(size.max(1)).map(<[u8]>::to_vec).collect();
+    elements.resize(len, vec![0; size]);
+    Some(encode_vector(&elements))
+}
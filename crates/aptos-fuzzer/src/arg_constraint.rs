@@ -0,0 +1,394 @@
+use std::str::FromStr;
+
+use anyhow::{anyhow, bail, Context};
+use aptos_move_core_types::account_address::AccountAddress;
+use aptos_move_core_types::identifier::{IdentStr, Identifier};
+use aptos_move_core_types::language_storage::{ModuleId, TypeTag};
+use aptos_move_core_types::u256::U256;
+use libafl::state::HasRand;
+use libafl_bolts::rands::Rand;
+
+use crate::mutator::bit_width;
+use crate::state::AptosFuzzerState;
+
+/// Values a caller would pass as one argument of a target function, so no
+/// executions are spent on the others, e.g. amounts over the total supply:
+///
+/// - `pool::withdraw.1 in 1..=1000000`: a number in a range, `..` leaving out
+///   the end
+/// - `pool::set_fee.0 in [0, 30, 100]`: one of a set of numbers, or of
+///   addresses written `@0xcafe`
+/// - `pool::transfer.0 pool`: an address from the state's address pool
+///
+/// The index counts the function's parameters other than signers, like the
+/// arguments of an entry function transaction. Generated and mutated
+/// arguments breaking a constraint are replaced by values satisfying it, and
+/// the concolic solver assumes the numeric ones of entry functions.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ArgConstraint {
+    address: Option<AccountAddress>,
+    module: Identifier,
+    function: Identifier,
+    arg_idx: usize,
+    allowed: AllowedValues,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AllowedValues {
+    /// Bounds included
+    Range(U256, U256),
+    Numbers(Vec<U256>),
+    Addresses(Vec<AccountAddress>),
+    Pool,
+}
+
+impl ArgConstraint {
+    pub fn applies_to(&self, module: &ModuleId, function: &IdentStr) -> bool {
+        self.address.is_none_or(|address| address == *module.address()) &&
+            module.name() == self.module.as_ident_str() &&
+            function == self.function.as_ident_str()
+    }
+
+    pub fn arg_idx(&self) -> usize {
+        self.arg_idx
+    }
+
+    pub fn allowed(&self) -> &AllowedValues {
+        &self.allowed
+    }
+
+    /// `<module>::<function>.<argument>`, for messages.
+    pub fn name(&self) -> String {
+        format!("{}::{}.{}", self.module, self.function, self.arg_idx)
+    }
+
+    /// Fail unless an argument of type `ty` can satisfy the constraint.
+    pub fn check_type(&self, ty: &TypeTag) -> anyhow::Result<()> {
+        let fits = match (&self.allowed, bit_width(ty)) {
+            (AllowedValues::Range(min, _), Some(bits)) => *min <= type_max(bits),
+            (AllowedValues::Numbers(values), Some(bits)) => values.iter().any(|value| *value <= type_max(bits)),
+            (AllowedValues::Addresses(_) | AllowedValues::Pool, _) => *ty == TypeTag::Address,
+            _ => false,
+        };
+        if !fits {
+            bail!(
+                "{} can't hold for an argument of type {}",
+                self.name(),
+                ty.to_canonical_string()
+            );
+        }
+        Ok(())
+    }
+
+    /// Whether `bytes`, the BCS encoding of an argument of type `ty`,
+    /// satisfies the constraint, with `state`'s address pool as it is now.
+    pub fn admits(&self, ty: &TypeTag, bytes: &[u8], state: &AptosFuzzerState) -> bool {
+        match &self.allowed {
+            AllowedValues::Range(min, max) => {
+                decode_number(ty, bytes).is_some_and(|value| *min <= value && value <= *max)
+            }
+            AllowedValues::Numbers(values) => decode_number(ty, bytes).is_some_and(|value| values.contains(&value)),
+            AllowedValues::Addresses(addresses) => {
+                bcs::from_bytes::<AccountAddress>(bytes).is_ok_and(|address| addresses.contains(&address))
+            }
+            AllowedValues::Pool => {
+                bcs::from_bytes::<AccountAddress>(bytes).is_ok_and(|address| state.address_pool().contains(&address))
+            }
+        }
+    }
+
+    /// BCS encoding of a random argument of type `ty` satisfying the
+    /// constraint, a bound of a range one time in two. `None` when there is
+    /// none, e.g. with an empty address pool.
+    pub fn sample(&self, ty: &TypeTag, state: &mut AptosFuzzerState) -> Option<Vec<u8>> {
+        match &self.allowed {
+            AllowedValues::Range(min, max) => {
+                let max = (*max).min(type_max(bit_width(ty)?));
+                if *min > max {
+                    return None;
+                }
+                let value = match state.rand_mut().next() % 4 {
+                    0 => *min,
+                    1 => max,
+                    _ => {
+                        let span = max - *min;
+                        let random = random_u256(state);
+                        if span == U256::max_value() {
+                            random
+                        } else {
+                            *min + random % (span + U256::one())
+                        }
+                    }
+                };
+                encode_number(ty, value)
+            }
+            AllowedValues::Numbers(values) => {
+                let bits = bit_width(ty)?;
+                let fitting: Vec<U256> = values
+                    .iter()
+                    .copied()
+                    .filter(|value| *value <= type_max(bits))
+                    .collect();
+                if fitting.is_empty() {
+                    return None;
+                }
+                let idx = (state.rand_mut().next() as usize) % fitting.len();
+                encode_number(ty, fitting[idx])
+            }
+            AllowedValues::Addresses(addresses) => {
+                let idx = (state.rand_mut().next() as usize) % addresses.len();
+                bcs::to_bytes(&addresses[idx]).ok()
+            }
+            AllowedValues::Pool => bcs::to_bytes(&state.random_pool_address()?).ok(),
+        }
+    }
+}
+
+impl FromStr for ArgConstraint {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let (target, allowed) = s.trim().split_once(char::is_whitespace).ok_or_else(|| {
+            anyhow!("expected <function>.<argument> in <range>|[<values>] or <function>.<argument> pool")
+        })?;
+        let (path, arg_idx) = target
+            .rsplit_once('.')
+            .ok_or_else(|| anyhow!("expected [<address>::]<module>::<function>.<argument>, got {}", target))?;
+        let arg_idx = arg_idx
+            .parse()
+            .with_context(|| format!("invalid argument index {}", arg_idx))?;
+        let parts: Vec<&str> = path.split("::").collect();
+        let (address, module, function) = match parts.as_slice() {
+            [module, function] => (None, *module, *function),
+            [address, module, function] => (Some(AccountAddress::from_hex_literal(address)?), *module, *function),
+            _ => bail!("expected [<address>::]<module>::<function>.<argument>, got {}", target),
+        };
+        let allowed = allowed.trim();
+        let allowed = if allowed == "pool" {
+            AllowedValues::Pool
+        } else if let Some(values) = allowed.strip_prefix("in").map(str::trim) {
+            parse_allowed(values)?
+        } else {
+            bail!("expected `in <range>`, `in [<values>]` or `pool`, got {}", allowed);
+        };
+        Ok(Self {
+            address,
+            module: Identifier::new(module)?,
+            function: Identifier::new(function)?,
+            arg_idx,
+            allowed,
+        })
+    }
+}
+
+/// `<min>..<end>`, `<min>..=<max>` or `[<value>, ...]`.
+fn parse_allowed(values: &str) -> anyhow::Result<AllowedValues> {
+    if let Some(list) = values.strip_prefix('[').and_then(|values| values.strip_suffix(']')) {
+        let items: Vec<&str> = list.split(',').map(str::trim).filter(|item| !item.is_empty()).collect();
+        if items.is_empty() {
+            bail!("no values in {}", values);
+        }
+        if items.iter().all(|item| item.starts_with('@')) {
+            let addresses = items
+                .iter()
+                .map(|item| {
+                    AccountAddress::from_hex_literal(&item[1..]).with_context(|| format!("invalid address {}", item))
+                })
+                .collect::<anyhow::Result<_>>()?;
+            return Ok(AllowedValues::Addresses(addresses));
+        }
+        let numbers = items
+            .iter()
+            .map(|item| U256::from_str(item).with_context(|| format!("invalid number {}", item)))
+            .collect::<anyhow::Result<_>>()?;
+        return Ok(AllowedValues::Numbers(numbers));
+    }
+    let (min, max) = if let Some((min, max)) = values.split_once("..=") {
+        (parse_number(min)?, parse_number(max)?)
+    } else if let Some((min, end)) = values.split_once("..") {
+        let end = parse_number(end)?;
+        if end == U256::zero() {
+            bail!("empty range {}", values);
+        }
+        (parse_number(min)?, end - U256::one())
+    } else {
+        bail!("expected <min>..<end>, <min>..=<max> or [<values>], got {}", values);
+    };
+    if min > max {
+        bail!("empty range {}", values);
+    }
+    Ok(AllowedValues::Range(min, max))
+}
+
+fn parse_number(number: &str) -> anyhow::Result<U256> {
+    U256::from_str(number.trim()).with_context(|| format!("invalid number {}", number))
+}
+
+fn type_max(bits: u32) -> U256 {
+    if bits == 256 {
+        U256::max_value()
+    } else {
+        (U256::one() << bits as u8) - U256::one()
+    }
+}
+
+fn decode_number(ty: &TypeTag, bytes: &[u8]) -> Option<U256> {
+    let width = bit_width(ty)? as usize / 8;
+    if bytes.len() != width {
+        return None;
+    }
+    let mut le = [0u8; 32];
+    le[..width].copy_from_slice(bytes);
+    Some(U256::from_le_bytes(&le))
+}
+
+fn encode_number(ty: &TypeTag, value: U256) -> Option<Vec<u8>> {
+    let width = bit_width(ty)? as usize / 8;
+    Some(value.to_le_bytes()[..width].to_vec())
+}
+
+fn random_u256(state: &mut AptosFuzzerState) -> U256 {
+    let mut le = [0u8; 32];
+    for chunk in le.chunks_mut(8) {
+        chunk.copy_from_slice(&state.rand_mut().next().to_le_bytes());
+    }
+    U256::from_le_bytes(&le)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use aptos_move_core_types::account_address::AccountAddress;
+    use aptos_move_core_types::identifier::Identifier;
+    use aptos_move_core_types::language_storage::{ModuleId, TypeTag};
+    use aptos_move_core_types::u256::U256;
+
+    use super::{AllowedValues, ArgConstraint};
+    use crate::state::AptosFuzzerState;
+
+    fn constraint(s: &str) -> ArgConstraint {
+        s.parse().unwrap()
+    }
+
+    fn module(address: &str) -> ModuleId {
+        ModuleId::new(
+            AccountAddress::from_hex_literal(address).unwrap(),
+            Identifier::new("pool").unwrap(),
+        )
+    }
+
+    #[test]
+    fn parses_ranges_values_and_pool() {
+        assert_eq!(
+            constraint("pool::withdraw.1 in 1..=1000").allowed(),
+            &AllowedValues::Range(U256::from(1u8), U256::from(1000u16))
+        );
+        assert_eq!(
+            constraint("pool::withdraw.1 in 1..1000").allowed(),
+            &AllowedValues::Range(U256::from(1u8), U256::from(999u16))
+        );
+        assert_eq!(
+            constraint("pool::set_fee.0 in [0, 30, 100]").allowed(),
+            &AllowedValues::Numbers(vec![U256::zero(), U256::from(30u8), U256::from(100u8)])
+        );
+        assert_eq!(
+            constraint("pool::transfer.0 in [@0xcafe, @0x1]").allowed(),
+            &AllowedValues::Addresses(vec![
+                AccountAddress::from_hex_literal("0xcafe").unwrap(),
+                AccountAddress::ONE
+            ])
+        );
+        let pool = constraint("0xcafe::pool::transfer.2 pool");
+        assert_eq!(pool.allowed(), &AllowedValues::Pool);
+        assert_eq!(pool.arg_idx(), 2);
+        assert_eq!(pool.name(), "pool::transfer.2");
+    }
+
+    #[test]
+    fn rejects_malformed_constraints() {
+        for s in [
+            "pool::withdraw.1",
+            "pool::withdraw.1 in 5..5",
+            "pool::withdraw.1 in 5..=4",
+            "pool::withdraw.1 in []",
+            "pool::withdraw.1 in 1..=x",
+            "pool::withdraw.1 below 5",
+            "withdraw.1 in 1..=5",
+            "pool::withdraw.first in 1..=5",
+        ] {
+            assert!(s.parse::<ArgConstraint>().is_err(), "{}", s);
+        }
+    }
+
+    #[test]
+    fn applies_to_the_named_function_at_any_address_unless_given() {
+        let withdraw = Identifier::new("withdraw").unwrap();
+        let deposit = Identifier::new("deposit").unwrap();
+        let anywhere = constraint("pool::withdraw.0 in 1..=5");
+        assert!(anywhere.applies_to(&module("0xcafe"), &withdraw));
+        assert!(anywhere.applies_to(&module("0xbeef"), &withdraw));
+        assert!(!anywhere.applies_to(&module("0xcafe"), &deposit));
+        let at_cafe = constraint("0xcafe::pool::withdraw.0 in 1..=5");
+        assert!(at_cafe.applies_to(&module("0xcafe"), &withdraw));
+        assert!(!at_cafe.applies_to(&module("0xbeef"), &withdraw));
+    }
+
+    #[test]
+    fn check_type_needs_a_value_the_type_can_hold() {
+        assert!(constraint("pool::f.0 in 1..=1000").check_type(&TypeTag::U8).is_ok());
+        assert!(constraint("pool::f.0 in 256..=1000").check_type(&TypeTag::U8).is_err());
+        assert!(constraint("pool::f.0 in [300, 255]").check_type(&TypeTag::U8).is_ok());
+        assert!(constraint("pool::f.0 in [300]").check_type(&TypeTag::U8).is_err());
+        assert!(constraint("pool::f.0 in 1..=5").check_type(&TypeTag::Address).is_err());
+        assert!(constraint("pool::f.0 pool").check_type(&TypeTag::Address).is_ok());
+        assert!(constraint("pool::f.0 pool").check_type(&TypeTag::U64).is_err());
+    }
+
+    #[test]
+    fn admits_checks_the_encoded_argument() {
+        let state = AptosFuzzerState::new(PathBuf::new());
+        let range = constraint("pool::f.0 in 10..=20");
+        assert!(range.admits(&TypeTag::U64, &10u64.to_le_bytes(), &state));
+        assert!(range.admits(&TypeTag::U64, &20u64.to_le_bytes(), &state));
+        assert!(!range.admits(&TypeTag::U64, &21u64.to_le_bytes(), &state));
+        // Bytes of the wrong width are never admitted
+        assert!(!range.admits(&TypeTag::U64, &15u32.to_le_bytes(), &state));
+        let values = constraint("pool::f.0 in [@0xcafe]");
+        let cafe = AccountAddress::from_hex_literal("0xcafe").unwrap();
+        assert!(values.admits(&TypeTag::Address, &bcs::to_bytes(&cafe).unwrap(), &state));
+        assert!(!values.admits(&TypeTag::Address, &bcs::to_bytes(&AccountAddress::TWO).unwrap(), &state));
+    }
+
+    #[test]
+    fn samples_satisfy_the_constraint() {
+        let mut state = AptosFuzzerState::new(PathBuf::new());
+        for (s, ty) in [
+            ("pool::f.0 in 10..=20", TypeTag::U64),
+            ("pool::f.0 in 200..=100000", TypeTag::U8),
+            (
+                "pool::f.0 in 0..=115792089237316195423570985008687907853269984665640564039457584007913129639935",
+                TypeTag::U256,
+            ),
+            ("pool::f.0 in [3, 300, 7]", TypeTag::U8),
+            ("pool::f.0 in [@0xcafe, @0x1]", TypeTag::Address),
+            ("pool::f.0 pool", TypeTag::Address),
+        ] {
+            let constraint = constraint(s);
+            for _ in 0..64 {
+                let bytes = constraint.sample(&ty, &mut state).unwrap();
+                assert!(constraint.admits(&ty, &bytes, &state), "{}", s);
+            }
+        }
+    }
+
+    #[test]
+    fn no_sample_when_the_type_holds_no_allowed_value() {
+        let mut state = AptosFuzzerState::new(PathBuf::new());
+        assert_eq!(
+            constraint("pool::f.0 in 256..=300").sample(&TypeTag::U8, &mut state),
+            None
+        );
+        assert_eq!(constraint("pool::f.0 in [256]").sample(&TypeTag::U8, &mut state), None);
+    }
+}
@@ -0,0 +1,550 @@
+use std::collections::BTreeSet;
+
+use aptos_move_core_types::identifier::Identifier;
+use aptos_move_core_types::language_storage::{ModuleId, TypeTag};
+use aptos_types::transaction::{EntryFunction, TransactionPayload};
+
+use crate::mutator::AptosFuzzerMutator;
+use crate::script_sequence::{compile_sequence, ScriptSequence, SequenceArgument};
+use crate::state::FunctionParameter;
+use crate::{AptosFuzzerInput, AptosFuzzerState, AptosMoveExecutor};
+
+/// What a run of an input ends in, compared across variants of a solution
+/// to tell whether they still reach its objective.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ReplayOutcome {
+    /// Module, function and code of the abort the run ended in
+    pub abort: Option<(String, String, u64)>,
+    /// VM status of a run that neither succeeded nor aborted
+    pub failure: Option<String>,
+    /// Oracles the run violated and runtime issues it hit, by name and not
+    /// message, which tends to quote the values
+    pub findings: BTreeSet<String>,
+}
+
+impl ReplayOutcome {
+    /// Succeeded without findings: nothing a variant could reproduce.
+    pub fn is_clean(&self) -> bool {
+        self.abort.is_none() && self.failure.is_none() && self.findings.is_empty()
+    }
+
+    /// Whether `other` ends the same way, with at least these findings.
+    pub fn reproduced_by(&self, other: &Self) -> bool {
+        self.abort == other.abort && self.failure == other.failure && self.findings.is_subset(&other.findings)
+    }
+}
+
+/// The calls and arguments a solution needs to reach its objective, as
+/// found by re-running variants of it.
+#[derive(Clone, Debug)]
+pub struct RootCause {
+    /// The solution with only the calls it needs
+    pub reduced: AptosFuzzerInput,
+    /// Indices in the solution's sequence of the calls kept; empty for an
+    /// entry function
+    pub calls: Vec<usize>,
+    /// Arguments whose value matters, as call index in the solution and
+    /// parameter index
+    pub arguments: Vec<(usize, usize)>,
+    /// e.g. "call 3 (0xcafe::vault::withdraw)'s `amount` argument (1000)"
+    pub hints: Vec<String>,
+}
+
+/// An argument of the reduced solution to try other values for.
+struct ArgumentSlot {
+    call_idx: usize,
+    /// Parameter index, signers included
+    param_idx: usize,
+    /// Index among the entry function's arguments, or `param_idx` in a
+    /// sequence
+    position: usize,
+    ty: TypeTag,
+    /// `None` for a result taken from an earlier call
+    bytes: Option<Vec<u8>>,
+}
+
+/// Bisect `solution` against the current state: drop the calls of its
+/// sequence it still reaches its objective without, halving then narrowing
+/// the chunks tried as in delta debugging, then give each remaining argument
+/// other values one at a time to find those it needs. `None` when the
+/// solution runs clean, its objective being one a replay doesn't observe,
+/// or doesn't reproduce once its sequence is recompiled.
+pub fn bisect_solution(
+    executor: &mut AptosMoveExecutor,
+    state: &mut AptosFuzzerState,
+    solution: &AptosFuzzerInput,
+) -> Option<RootCause> {
+    let target = executor.replay_outcome(state, solution);
+    if target.is_clean() {
+        return None;
+    }
+    let mut reproduces = |state: &mut AptosFuzzerState, input: &AptosFuzzerInput| {
+        target.reproduced_by(&executor.replay_outcome(state, input))
+    };
+
+    let (reduced, calls) = match (solution.payload(), solution.script_sequence()) {
+        (TransactionPayload::Script(script), Some(sequence)) => {
+            // Argument mutations leave the sequence behind the script
+            let sequence = sequence.with_script_args(script.args())?;
+            let solution = with_sequence(state, solution, sequence.clone())?;
+            if !reproduces(state, &solution) {
+                return None;
+            }
+            reduce_calls(state, &solution, &sequence, &mut reproduces)
+        }
+        _ => (solution.clone(), Vec::new()),
+    };
+
+    let mut hints = Vec::new();
+    if let Some(sequence) = solution
+        .script_sequence()
+        .filter(|sequence| calls.len() < sequence.len())
+    {
+        let needed: Vec<String> = calls
+            .iter()
+            .map(|&idx| {
+                let call = &sequence.calls()[idx];
+                format!("{} ({})", idx, function_name(call.module(), call.function()))
+            })
+            .collect();
+        hints.push(format!(
+            "needs {} of its {} calls: {}",
+            calls.len(),
+            sequence.len(),
+            needed.join(", ")
+        ));
+    }
+    let mut arguments = Vec::new();
+    for slot in argument_slots(state, &reduced) {
+        let alternatives = match &slot.bytes {
+            Some(bytes) => alternative_values(&slot.ty, bytes),
+            None => AptosFuzzerState::default_arg_bytes(&slot.ty).into_iter().collect(),
+        };
+        let mut matters = false;
+        for bytes in alternatives {
+            let Some(variant) = with_argument(state, &reduced, &slot, bytes) else {
+                continue;
+            };
+            if !reproduces(state, &variant) {
+                matters = true;
+                break;
+            }
+        }
+        if !matters {
+            continue;
+        }
+        let original_call = calls.get(slot.call_idx).copied().unwrap_or(slot.call_idx);
+        arguments.push((original_call, slot.param_idx));
+        hints.push(argument_hint(state, &reduced, &calls, &slot));
+    }
+    if arguments.is_empty() {
+        hints.push("no single argument value is needed".to_string());
+    }
+    Some(RootCause {
+        reduced,
+        calls,
+        arguments,
+        hints,
+    })
+}
+
+/// Drop chunks of calls while the rest still reproduces, halving the chunk
+/// size whenever no chunk can go. Returns the reduced input and the indices
+/// of the calls it kept.
+fn reduce_calls(
+    state: &mut AptosFuzzerState,
+    solution: &AptosFuzzerInput,
+    sequence: &ScriptSequence,
+    reproduces: &mut impl FnMut(&mut AptosFuzzerState, &AptosFuzzerInput) -> bool,
+) -> (AptosFuzzerInput, Vec<usize>) {
+    let mut kept: Vec<usize> = (0..sequence.len()).collect();
+    let mut reduced = solution.clone();
+    let mut chunks = 2;
+    while kept.len() > 1 {
+        let chunk_len = kept.len().div_ceil(chunks);
+        let mut dropped = false;
+        for start in (0..kept.len()).step_by(chunk_len) {
+            let end = (start + chunk_len).min(kept.len());
+            let candidate: Vec<usize> = kept[..start].iter().chain(&kept[end..]).copied().collect();
+            if candidate.is_empty() {
+                continue;
+            }
+            let Some(input) = with_calls(state, solution, sequence, &candidate) else {
+                continue;
+            };
+            if reproduces(state, &input) {
+                kept = candidate;
+                reduced = input;
+                dropped = true;
+                break;
+            }
+        }
+        if dropped {
+            chunks = (chunks - 1).max(2);
+        } else if chunk_len == 1 {
+            break;
+        } else {
+            chunks = (chunks * 2).min(kept.len());
+        }
+    }
+    (reduced, kept)
+}
+
+/// `solution` with only the calls of `sequence` at `kept`, results of the
+/// dropped ones replaced as a sequence mutation would.
+fn with_calls(
+    state: &mut AptosFuzzerState,
+    solution: &AptosFuzzerInput,
+    sequence: &ScriptSequence,
+    kept: &[usize],
+) -> Option<AptosFuzzerInput> {
+    let order: Vec<(usize, usize)> = kept.iter().map(|&idx| (0, idx)).collect();
+    let mut candidate = ScriptSequence::rearrange(&[sequence], &order, |producer, return_idx| {
+        AptosFuzzerMutator::default_for_result(state, producer, return_idx)
+    })?;
+    if !AptosFuzzerMutator::repair_sequence(state, &mut candidate) {
+        return None;
+    }
+    with_sequence(state, solution, candidate)
+}
+
+fn with_sequence(
+    state: &AptosFuzzerState,
+    solution: &AptosFuzzerInput,
+    sequence: ScriptSequence,
+) -> Option<AptosFuzzerInput> {
+    let script = compile_sequence(&sequence, state.aptos_state().module_bytes()).ok()?;
+    let mut input = solution.clone();
+    *input.payload_mut() = TransactionPayload::Script(script);
+    input.set_script_sequence(Some(sequence));
+    Some(input)
+}
+
+/// The literal arguments of `input`, and the results it takes by value
+/// from earlier calls.
+fn argument_slots(state: &AptosFuzzerState, input: &AptosFuzzerInput) -> Vec<ArgumentSlot> {
+    let mut slots = Vec::new();
+    match (input.payload(), input.script_sequence()) {
+        (TransactionPayload::EntryFunction(entry), _) => {
+            let Some(function) = state.function_instance(entry.module(), entry.function(), entry.ty_args()) else {
+                return slots;
+            };
+            let values = function
+                .parameters()
+                .iter()
+                .enumerate()
+                .filter_map(|(param_idx, param)| match param {
+                    FunctionParameter::Value(ty) => Some((param_idx, ty)),
+                    FunctionParameter::Signer | FunctionParameter::Reference { .. } => None,
+                });
+            for (position, ((param_idx, ty), bytes)) in values.zip(entry.args()).enumerate() {
+                slots.push(ArgumentSlot {
+                    call_idx: 0,
+                    param_idx,
+                    position,
+                    ty: ty.clone(),
+                    bytes: Some(bytes.clone()),
+                });
+            }
+        }
+        (TransactionPayload::Script(_), Some(sequence)) => {
+            for (call_idx, call) in sequence.calls().iter().enumerate() {
+                let Some(function) = state.function_instance(call.module(), call.function(), call.ty_args()) else {
+                    continue;
+                };
+                for (param_idx, (param, arg)) in function.parameters().iter().zip(call.args()).enumerate() {
+                    let FunctionParameter::Value(ty) = param else {
+                        continue;
+                    };
+                    let bytes = match arg {
+                        SequenceArgument::Raw { bytes, .. } => Some(bytes.clone()),
+                        SequenceArgument::PreviousResult { .. } => None,
+                        _ => continue,
+                    };
+                    slots.push(ArgumentSlot {
+                        call_idx,
+                        param_idx,
+                        position: param_idx,
+                        ty: ty.clone(),
+                        bytes,
+                    });
+                }
+            }
+        }
+        _ => {}
+    }
+    slots
+}
+
+/// Values to try in place of `bytes`: the type's default, and numbers,
+/// addresses and booleans with their lowest bit flipped, as the default
+/// may be what the argument already is.
+fn alternative_values(ty: &TypeTag, bytes: &[u8]) -> Vec<Vec<u8>> {
+    let mut alternatives = Vec::new();
+    if let Some(default) = AptosFuzzerState::default_arg_bytes(ty) {
+        alternatives.push(default);
+    }
+    if matches!(
+        ty,
+        TypeTag::Bool |
+            TypeTag::U8 |
+            TypeTag::U16 |
+            TypeTag::U32 |
+            TypeTag::U64 |
+            TypeTag::U128 |
+            TypeTag::U256 |
+            TypeTag::Address
+    ) {
+        let mut flipped = bytes.to_vec();
+        if let Some(first) = flipped.first_mut() {
+            *first ^= 1;
+        }
+        alternatives.push(flipped);
+    }
+    alternatives.retain(|alternative| alternative != bytes);
+    alternatives.dedup();
+    alternatives
+}
+
+/// `input` with the argument at `slot` set to the literal `bytes`.
+fn with_argument(
+    state: &mut AptosFuzzerState,
+    input: &AptosFuzzerInput,
+    slot: &ArgumentSlot,
+    bytes: Vec<u8>,
+) -> Option<AptosFuzzerInput> {
+    match (input.payload(), input.script_sequence()) {
+        (TransactionPayload::EntryFunction(entry), _) => {
+            let (module, function, ty_args, mut args) = entry.clone().into_inner();
+            *args.get_mut(slot.position)? = bytes;
+            let mut variant = input.clone();
+            *variant.payload_mut() =
+                TransactionPayload::EntryFunction(EntryFunction::new(module, function, ty_args, args));
+            Some(variant)
+        }
+        (TransactionPayload::Script(_), Some(sequence)) => {
+            let mut sequence = sequence.clone();
+            sequence.set_argument(
+                slot.call_idx,
+                slot.position,
+                SequenceArgument::Raw {
+                    bytes,
+                    ty: slot.ty.clone(),
+                },
+            );
+            // A value no longer moved may now be copied instead
+            if !AptosFuzzerMutator::repair_sequence(state, &mut sequence) {
+                return None;
+            }
+            with_sequence(state, input, sequence)
+        }
+        _ => None,
+    }
+}
+
+/// e.g. "call 3 (0xcafe::vault::withdraw)'s `amount` argument (1000)", with
+/// the call's index in the solution before reduction.
+fn argument_hint(state: &AptosFuzzerState, reduced: &AptosFuzzerInput, calls: &[usize], slot: &ArgumentSlot) -> String {
+    let literal = || describe_value(state, &slot.ty, slot.bytes.as_deref().unwrap_or_default());
+    let (caller, module, function, value) = match (reduced.payload(), reduced.script_sequence()) {
+        (TransactionPayload::Script(_), Some(sequence)) => {
+            let call = &sequence.calls()[slot.call_idx];
+            let value = match call.args().get(slot.position) {
+                Some(SequenceArgument::PreviousResult { call_idx, .. }) => {
+                    let producer = calls.get(*call_idx as usize).copied().unwrap_or(*call_idx as usize);
+                    format!("taken from call {}'s result", producer)
+                }
+                _ => literal(),
+            };
+            let call_idx = calls.get(slot.call_idx).copied().unwrap_or(slot.call_idx);
+            (
+                format!("call {} ({})", call_idx, function_name(call.module(), call.function())),
+                call.module().clone(),
+                call.function().clone(),
+                value,
+            )
+        }
+        (TransactionPayload::EntryFunction(entry), _) => (
+            function_name(entry.module(), &entry.function().to_owned()),
+            entry.module().clone(),
+            entry.function().to_owned(),
+            literal(),
+        ),
+        _ => return String::new(),
+    };
+    format!(
+        "{}'s {} argument ({})",
+        caller,
+        parameter_label(state, &module, &function, slot.param_idx),
+        value
+    )
+}
+
+fn function_name(module: &ModuleId, function: &Identifier) -> String {
+    format!("{}::{}", module, function)
+}
+
+/// The parameter's name from the source map, else its index.
+fn parameter_label(state: &AptosFuzzerState, module: &ModuleId, function: &Identifier, param_idx: usize) -> String {
+    state
+        .source_locator()
+        .parameter_names(module, function.as_str())
+        .and_then(|names| names.get(param_idx).cloned())
+        .map_or_else(|| format!("#{}", param_idx), |name| format!("`{}`", name))
+}
+
+fn describe_value(state: &AptosFuzzerState, ty: &TypeTag, bytes: &[u8]) -> String {
+    state
+        .struct_layouts()
+        .decode(ty, bytes)
+        .map_or_else(|| format!("0x{}", hex::encode(bytes)), |value| value.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use aptos_move_core_types::account_address::AccountAddress;
+    use aptos_move_core_types::identifier::Identifier;
+    use aptos_move_core_types::language_storage::{ModuleId, TypeTag};
+    use aptos_types::transaction::{EntryFunction, TransactionPayload};
+
+    use super::{alternative_values, argument_slots, reduce_calls, with_argument, with_sequence};
+    use crate::script_sequence::{ScriptSequence, SequenceArgument, SequenceCall};
+    use crate::state::{FunctionParameter, PublicFunctionTarget};
+    use crate::{AptosFuzzerInput, AptosFuzzerState};
+
+    fn framework(module: &str) -> ModuleId {
+        ModuleId::new(AccountAddress::ONE, Identifier::new(module).unwrap())
+    }
+
+    /// A state fuzzing `0x1::math64::{max, min}(u64, u64): u64` and
+    /// `0x1::timestamp::now_seconds(): u64`, which the framework deploys.
+    fn state() -> AptosFuzzerState {
+        let mut state = AptosFuzzerState::new(PathBuf::new());
+        let u64_pair = || vec![FunctionParameter::Value(TypeTag::U64); 2];
+        for (module, name, parameters) in [
+            ("math64", "max", u64_pair()),
+            ("math64", "min", u64_pair()),
+            ("timestamp", "now_seconds", Vec::new()),
+        ] {
+            state.add_public_function(PublicFunctionTarget::new(
+                framework(module),
+                Identifier::new(name).unwrap(),
+                Vec::new(),
+                parameters,
+                vec![TypeTag::U64],
+                false,
+                false,
+            ));
+        }
+        state
+    }
+
+    fn u64_arg(value: u64) -> SequenceArgument {
+        SequenceArgument::Raw {
+            bytes: bcs::to_bytes(&value).unwrap(),
+            ty: TypeTag::U64,
+        }
+    }
+
+    fn call(module: &str, function: &str, args: Vec<SequenceArgument>) -> SequenceCall {
+        SequenceCall::new(framework(module), Identifier::new(function).unwrap(), Vec::new(), args)
+    }
+
+    /// `now_seconds, max(1, 2), now_seconds, min(3, 4), now_seconds`
+    fn sequence() -> ScriptSequence {
+        let mut sequence = ScriptSequence::new();
+        for call in [
+            call("timestamp", "now_seconds", vec![]),
+            call("math64", "max", vec![u64_arg(1), u64_arg(2)]),
+            call("timestamp", "now_seconds", vec![]),
+            call("math64", "min", vec![u64_arg(3), u64_arg(4)]),
+            call("timestamp", "now_seconds", vec![]),
+        ] {
+            sequence.push_call(call);
+        }
+        sequence
+    }
+
+    fn calls(input: &AptosFuzzerInput, function: &str) -> usize {
+        input
+            .called_functions()
+            .iter()
+            .filter(|called| called.ends_with(function))
+            .count()
+    }
+
+    /// A script input running `sequence`.
+    fn solution(state: &AptosFuzzerState, sequence: &ScriptSequence) -> AptosFuzzerInput {
+        let placeholder = AptosFuzzerInput::new(TransactionPayload::EntryFunction(EntryFunction::new(
+            framework("timestamp"),
+            Identifier::new("now_seconds").unwrap(),
+            Vec::new(),
+            Vec::new(),
+        )));
+        with_sequence(state, &placeholder, sequence.clone()).unwrap()
+    }
+
+    #[test]
+    fn reduction_keeps_only_the_calls_needed() {
+        let mut state = state();
+        let sequence = sequence();
+        let solution = solution(&state, &sequence);
+        let (reduced, kept) = reduce_calls(&mut state, &solution, &sequence, &mut |_, input| {
+            calls(input, "::max") == 1 && calls(input, "::min") == 1
+        });
+        assert_eq!(kept, [1, 3]);
+        assert_eq!(reduced.called_functions().len(), 2);
+    }
+
+    #[test]
+    fn reduction_keeps_a_lone_call() {
+        let mut state = state();
+        let sequence = sequence();
+        let solution = solution(&state, &sequence);
+        let (_, kept) = reduce_calls(&mut state, &solution, &sequence, &mut |_, input| {
+            calls(input, "::min") == 1
+        });
+        assert_eq!(kept, [3]);
+    }
+
+    #[test]
+    fn entry_function_arguments_are_varied_one_at_a_time() {
+        let mut state = state();
+        let input = AptosFuzzerInput::new(TransactionPayload::EntryFunction(EntryFunction::new(
+            framework("math64"),
+            Identifier::new("max").unwrap(),
+            Vec::new(),
+            vec![bcs::to_bytes(&5u64).unwrap(), bcs::to_bytes(&7u64).unwrap()],
+        )));
+        let slots = argument_slots(&state, &input);
+        assert_eq!(slots.len(), 2);
+        let variant = with_argument(&mut state, &input, &slots[1], bcs::to_bytes(&0u64).unwrap()).unwrap();
+        let TransactionPayload::EntryFunction(entry) = variant.payload() else {
+            panic!("variant is not an entry function");
+        };
+        assert_eq!(
+            entry.args(),
+            [bcs::to_bytes(&5u64).unwrap(), bcs::to_bytes(&0u64).unwrap()]
+        );
+    }
+
+    #[test]
+    fn alternatives_are_the_default_and_a_flipped_bit() {
+        let five = bcs::to_bytes(&5u64).unwrap();
+        assert_eq!(
+            alternative_values(&TypeTag::U64, &five),
+            [bcs::to_bytes(&0u64).unwrap(), bcs::to_bytes(&4u64).unwrap()]
+        );
+        // Flipping `true` gives the default, which is tried once
+        assert_eq!(
+            alternative_values(&TypeTag::Bool, &bcs::to_bytes(&true).unwrap()),
+            [bcs::to_bytes(&false).unwrap()]
+        );
+        assert_eq!(
+            alternative_values(&TypeTag::U64, &bcs::to_bytes(&0u64).unwrap()),
+            [bcs::to_bytes(&1u64).unwrap()]
+        );
+    }
+}
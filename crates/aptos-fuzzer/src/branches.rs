@@ -0,0 +1,203 @@
+use std::collections::{HashMap, HashSet};
+
+#[cfg(feature = "concolic")]
+use crate::abort_target::witness_input;
+#[cfg(feature = "concolic")]
+use crate::input::AptosFuzzerInput;
+#[cfg(feature = "concolic")]
+use crate::solver::SolveOutcome;
+
+/// Function hash and pc of a conditional branch, as the tracer keys them
+pub type BranchKey = (u64, u16);
+
+/// A conditional branch of the campaign, with how often each way was taken.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BranchSite {
+    /// `0xADDR::module`
+    pub module: String,
+    pub function: String,
+    pub pc: u16,
+    /// Executions of the branch with its condition false and true
+    pub taken: [u64; 2],
+    /// Flipping it was unsatisfiable on a path reaching it, under the
+    /// current model
+    pub infeasible: bool,
+}
+
+impl BranchSite {
+    /// The way never taken, when the branch only ever went the other.
+    pub fn missing(&self) -> Option<bool> {
+        match self.taken {
+            [0, _] => Some(false),
+            [_, 0] => Some(true),
+            _ => None,
+        }
+    }
+
+    pub fn hits(&self) -> u64 {
+        self.taken[0] + self.taken[1]
+    }
+}
+
+/// How close a branch is to what the campaign found, the first criterion of
+/// the worklist.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Proximity {
+    Elsewhere,
+    /// In a module with a finding
+    SameModule,
+    /// In a function with a finding
+    SameFunction,
+}
+
+impl Proximity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Elsewhere => "elsewhere",
+            Self::SameModule => "same module as a finding",
+            Self::SameFunction => "same function as a finding",
+        }
+    }
+}
+
+/// A one-sided branch of the worklist.
+#[derive(Clone, Copy, Debug)]
+pub struct RankedBranch<'a> {
+    pub key: BranchKey,
+    pub site: &'a BranchSite,
+    pub proximity: Proximity,
+}
+
+/// Every conditional branch executed so far, to find those only one way of
+/// which was ever taken: the conditions the mutators failed to negate.
+#[derive(Debug, Default)]
+pub struct BranchCoverage {
+    sites: HashMap<BranchKey, BranchSite>,
+    /// Bumped whenever the set of one-sided branches changes, so consumers of
+    /// the worklist know to rank it again
+    generation: u64,
+    /// Solved inputs not executed yet
+    #[cfg(feature = "concolic")]
+    witnesses: Vec<AptosFuzzerInput>,
+    #[cfg(feature = "concolic")]
+    seen: HashSet<AptosFuzzerInput>,
+    /// One-sided branches the solver was asked to flip
+    pub queries: u64,
+    /// Distinct inputs solved for
+    pub solved: u64,
+}
+
+impl BranchCoverage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Count the branch at `key` going the `condition` way `count` times,
+    /// naming its function with `names` (module, function) if it is new.
+    pub fn record(
+        &mut self,
+        key: BranchKey,
+        condition: bool,
+        count: u64,
+        names: impl FnOnce() -> Option<(String, String)>,
+    ) {
+        if !self.sites.contains_key(&key) {
+            let Some((module, function)) = names() else {
+                return;
+            };
+            self.generation += 1;
+            self.sites.insert(
+                key,
+                BranchSite {
+                    module,
+                    function,
+                    pc: key.1,
+                    taken: [0, 0],
+                    infeasible: false,
+                },
+            );
+        }
+        let Some(site) = self.sites.get_mut(&key) else {
+            return;
+        };
+        if site.taken[condition as usize] == 0 && site.taken[!condition as usize] > 0 {
+            self.generation += 1;
+        }
+        site.taken[condition as usize] += count;
+    }
+
+    pub fn sites(&self) -> impl Iterator<Item = &BranchSite> {
+        self.sites.values()
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Branches only one way of which was taken so far.
+    pub fn one_sided(&self) -> impl Iterator<Item = (&BranchKey, &BranchSite)> {
+        self.sites.iter().filter(|(_, site)| site.missing().is_some())
+    }
+
+    /// The one-sided branches worth flipping first: those in the functions,
+    /// then the modules, of `findings` (module, function), then the most
+    /// executed, whose condition the mutators keep failing to negate.
+    /// Branches proved infeasible come last.
+    pub fn worklist(&self, findings: &HashSet<(String, String)>) -> Vec<RankedBranch<'_>> {
+        let modules: HashSet<&str> = findings.iter().map(|(module, _)| module.as_str()).collect();
+        let mut worklist: Vec<RankedBranch<'_>> = self
+            .one_sided()
+            .map(|(&key, site)| {
+                let proximity = if findings.contains(&(site.module.clone(), site.function.clone())) {
+                    Proximity::SameFunction
+                } else if modules.contains(site.module.as_str()) {
+                    Proximity::SameModule
+                } else {
+                    Proximity::Elsewhere
+                };
+                RankedBranch { key, site, proximity }
+            })
+            .collect();
+        worklist.sort_by(|a, b| {
+            a.site
+                .infeasible
+                .cmp(&b.site.infeasible)
+                .then_with(|| b.proximity.cmp(&a.proximity))
+                .then_with(|| b.site.hits().cmp(&a.site.hits()))
+                .then_with(|| {
+                    (&a.site.module, &a.site.function, a.site.pc).cmp(&(&b.site.module, &b.site.function, b.site.pc))
+                })
+        });
+        worklist
+    }
+
+    #[cfg(feature = "concolic")]
+    /// Account for the answer to flipping the branch at `key` in the
+    /// execution of `input`, queueing the solved input if it is new.
+    pub fn record_outcome(&mut self, key: BranchKey, input: &AptosFuzzerInput, outcome: SolveOutcome) {
+        self.queries += 1;
+        match outcome {
+            SolveOutcome::Sat(values) => {
+                if let Some(witness) = witness_input(input, &values) {
+                    if self.seen.insert(witness.clone()) {
+                        self.solved += 1;
+                        self.witnesses.push(witness);
+                    }
+                }
+            }
+            SolveOutcome::Unsat => {
+                if let Some(site) = self.sites.get_mut(&key).filter(|site| !site.infeasible) {
+                    site.infeasible = true;
+                    self.generation += 1;
+                }
+            }
+            SolveOutcome::Unknown | SolveOutcome::OverBudget => {}
+        }
+    }
+
+    #[cfg(feature = "concolic")]
+    /// Solved inputs to execute next.
+    pub fn take_witnesses(&mut self) -> Vec<AptosFuzzerInput> {
+        std::mem::take(&mut self.witnesses)
+    }
+}
@@ -0,0 +1,177 @@
+use aptos_move_binary_format::access::ModuleAccess;
+use aptos_move_binary_format::file_format::Visibility;
+use aptos_move_binary_format::CompiledModule;
+use aptos_types::transaction::TransactionPayload;
+use libafl::corpus::Corpus;
+use libafl::state::HasCorpus;
+
+use crate::error::FuzzerError;
+use crate::script_sequence::{compile_sequence, ResultOperation, ScriptSequence, SequenceArgument, SequenceCall};
+use crate::state::{FunctionParameter, PublicFunctionTarget, SkipReason};
+use crate::{AptosFuzzerInput, AptosFuzzerState, AptosMoveExecutor};
+
+/// Whether a public function of the targets is fuzzed and, if so, how its
+/// seed ran.
+#[derive(Clone, Debug)]
+pub struct TargetCheck {
+    /// `<address>::<module>::<function>`
+    pub function: String,
+    pub outcome: Result<String, SkipReason>,
+    /// Takes signers and nothing else, so has no arguments to mutate
+    pub signer_only: bool,
+}
+
+/// Compile the empty script sequence every script input grows from: when
+/// it fails, no sequence of the targets' functions compiles either.
+pub fn check_script_composer(state: &AptosFuzzerState) -> Result<(), FuzzerError> {
+    compile_sequence(&ScriptSequence::new(), state.aptos_state().module_bytes()).map(|_| ())
+}
+
+/// Classify every public function of the target modules and run one seed
+/// of each fuzzed one against the set-up state, which is left as it was.
+pub fn check_targets(executor: &mut AptosMoveExecutor, state: &mut AptosFuzzerState) -> Vec<TargetCheck> {
+    let seeds: Vec<AptosFuzzerInput> = state
+        .corpus()
+        .ids()
+        .filter_map(|id| state.corpus().cloned_input_for_id(id).ok())
+        .collect();
+    let mut checks = Vec::new();
+    for module_id in state.target_modules().to_vec() {
+        let Some(module) = state
+            .aptos_state()
+            .module_bytes()
+            .get(&module_id)
+            .and_then(|bytes| CompiledModule::deserialize(bytes).ok())
+        else {
+            continue;
+        };
+        for func_def in &module.function_defs {
+            if func_def.visibility != Visibility::Public {
+                continue;
+            }
+            let handle = module.function_handle_at(func_def.function);
+            let name = module.identifier_at(handle.name).to_owned();
+            let function = format!("{}::{}", module_id, name);
+            let target = match state.public_function(&module_id, &name) {
+                Some(target) => Some(target.clone()),
+                None => state
+                    .generic_functions()
+                    .iter()
+                    .position(|generic| generic.module_id() == &module_id && generic.name() == &name)
+                    .and_then(|idx| state.schedule_instantiation(idx)),
+            };
+            let outcome = match &target {
+                Some(target) => match seed_for(target, &function, &seeds, state) {
+                    Ok(input) => Ok(executor.trace_input(state, &input).status),
+                    Err(err) => Err(SkipReason::NoSeed(err.to_string())),
+                },
+                None => Err(state
+                    .skip_reason(&module_id, &name)
+                    .cloned()
+                    .unwrap_or(SkipReason::Unreachable)),
+            };
+            let signer_only = target.as_ref().is_some_and(|target| {
+                !target.parameters().is_empty() &&
+                    target
+                        .parameters()
+                        .iter()
+                        .all(|param| matches!(param, FunctionParameter::Signer))
+            });
+            checks.push(TargetCheck {
+                function,
+                outcome,
+                signer_only,
+            });
+        }
+    }
+    checks
+}
+
+/// The corpus seed of an entry function, or a script calling any other,
+/// after a call producing each value it takes by reference.
+fn seed_for(
+    target: &PublicFunctionTarget,
+    function: &str,
+    seeds: &[AptosFuzzerInput],
+    state: &AptosFuzzerState,
+) -> Result<AptosFuzzerInput, FuzzerError> {
+    if target.is_entry() {
+        if let Some(seed) = seeds
+            .iter()
+            .find(|seed| matches!(seed.payload(), TransactionPayload::EntryFunction(_)) && seed.describe() == function)
+        {
+            return Ok(seed.clone());
+        }
+        return AptosFuzzerState::entry_payload_from_function(target).map(AptosFuzzerInput::new);
+    }
+    let mut sequence = ScriptSequence::new();
+    let args = seed_args(target, function, state, &mut sequence, true)?;
+    sequence.push_call(SequenceCall::new(
+        target.module_id().clone(),
+        target.name().clone(),
+        target.ty_args().to_vec(),
+        args,
+    ));
+    let script = compile_sequence(&sequence, state.aptos_state().module_bytes())?;
+    Ok(AptosFuzzerInput::with_script(
+        TransactionPayload::Script(script),
+        sequence,
+    ))
+}
+
+/// Default arguments of `target`. With `borrow`, a reference parameter
+/// borrows the result of a call pushed onto `sequence` to a public function
+/// returning its type.
+fn seed_args(
+    target: &PublicFunctionTarget,
+    function: &str,
+    state: &AptosFuzzerState,
+    sequence: &mut ScriptSequence,
+    borrow: bool,
+) -> Result<Vec<SequenceArgument>, FuzzerError> {
+    let mut args = Vec::new();
+    for param in target.parameters() {
+        args.push(match param {
+            FunctionParameter::Signer => SequenceArgument::Signer(0),
+            FunctionParameter::Value(ty) => SequenceArgument::Raw {
+                bytes: AptosFuzzerState::default_arg_bytes(ty).ok_or_else(|| FuzzerError::SeedArgument {
+                    function: function.to_string(),
+                    ty: ty.to_canonical_string(),
+                })?,
+                ty: ty.clone(),
+            },
+            FunctionParameter::Reference { ty, mutable } => {
+                let unborrowable = || FuzzerError::SeedArgument {
+                    function: function.to_string(),
+                    ty: format!("{}{}", if *mutable { "&mut " } else { "&" }, ty.to_canonical_string()),
+                };
+                if !borrow {
+                    return Err(unborrowable());
+                }
+                let producer = state
+                    .public_functions()
+                    .iter()
+                    .find(|producer| producer.return_types().contains(ty))
+                    .ok_or_else(unborrowable)?;
+                let producer_name = format!("{}::{}", producer.module_id(), producer.name());
+                let producer_args = seed_args(producer, &producer_name, state, sequence, false)?;
+                sequence.push_call(SequenceCall::new(
+                    producer.module_id().clone(),
+                    producer.name().clone(),
+                    producer.ty_args().to_vec(),
+                    producer_args,
+                ));
+                SequenceArgument::PreviousResult {
+                    call_idx: sequence.len() as u16 - 1,
+                    return_idx: producer.return_types().iter().position(|tag| tag == ty).unwrap_or(0) as u16,
+                    operation: if *mutable {
+                        ResultOperation::BorrowMut
+                    } else {
+                        ResultOperation::Borrow
+                    },
+                }
+            }
+        });
+    }
+    Ok(args)
+}
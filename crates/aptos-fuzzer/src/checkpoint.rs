@@ -0,0 +1,328 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashSet;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Context};
+use libafl::corpus::{Corpus, Testcase};
+use libafl::state::{HasCorpus, HasSolutions};
+use libafl::Evaluator;
+use serde::{Deserialize, Serialize};
+
+use crate::input::AptosFuzzerInput;
+use crate::state::AptosFuzzerState;
+use crate::trace::AbortSite;
+
+/// File in the checkpoint directory the checkpoint is written to
+const CHECKPOINT_FILE: &str = "checkpoint.json";
+
+/// What a campaign found so far: enough to resume it after the process
+/// dies, losing only what was found since.
+#[derive(Serialize, Deserialize)]
+struct Checkpoint {
+    /// Fingerprint of the target modules, as a checkpoint of other modules
+    /// has coverage and paths meaning nothing for these
+    targets: u64,
+    corpus: Vec<AptosFuzzerInput>,
+    solutions: Vec<AptosFuzzerInput>,
+    coverage: Vec<u8>,
+    seen_execution_paths: Vec<u64>,
+    seen_abort_sites: Vec<AbortSite>,
+}
+
+/// What resuming from a checkpoint restored.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Resumed {
+    /// Corpus entries added, not counting those already in the corpus
+    pub corpus: usize,
+    /// Solutions replayed that were found again
+    pub solutions: usize,
+}
+
+/// Periodic snapshots of the corpus, solutions, cumulative coverage and
+/// dedup sets to a directory, each replacing the last at once so a crash
+/// mid-write leaves the previous one intact.
+pub struct Checkpointer {
+    dir: PathBuf,
+    interval: Duration,
+    last_write: Instant,
+}
+
+impl Checkpointer {
+    /// Checkpoint to `dir` every `interval`.
+    pub fn new(dir: PathBuf, interval: Duration) -> anyhow::Result<Self> {
+        fs::create_dir_all(&dir).with_context(|| format!("failed to create {}", dir.display()))?;
+        Ok(Self {
+            dir,
+            interval,
+            last_write: Instant::now(),
+        })
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Whether the interval has passed since the last checkpoint.
+    pub fn is_due(&self) -> bool {
+        self.last_write.elapsed() >= self.interval
+    }
+
+    /// Replace the checkpoint with one of `state`.
+    pub fn write(&mut self, state: &AptosFuzzerState) -> anyhow::Result<()> {
+        self.last_write = Instant::now();
+        let checkpoint = Checkpoint {
+            targets: targets_fingerprint(state),
+            corpus: state
+                .corpus()
+                .ids()
+                .filter_map(|id| state.corpus().cloned_input_for_id(id).ok())
+                .collect(),
+            solutions: state.take_solutions(),
+            coverage: state.cumulative_coverage().to_vec(),
+            seen_execution_paths: state.seen_execution_paths().iter().copied().collect(),
+            seen_abort_sites: state.seen_abort_sites().iter().cloned().collect(),
+        };
+        // Write then rename, so a crash never leaves a partial checkpoint
+        let path = self.dir.join(CHECKPOINT_FILE);
+        let partial = path.with_extension("json.tmp");
+        fs::write(&partial, serde_json::to_vec(&checkpoint)?)
+            .with_context(|| format!("failed to write {}", partial.display()))?;
+        fs::rename(&partial, &path).with_context(|| format!("failed to write {}", path.display()))?;
+        Ok(())
+    }
+
+    /// Resume from the checkpoint in the directory, if there is one: add
+    /// its corpus entries, replay its solutions so they are reported again,
+    /// then restore the coverage and the paths and abort sites seen, so
+    /// what was found before is not found again as new. Fails when the
+    /// checkpoint was taken against other target modules.
+    pub fn resume<Z, E, EM>(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        mgr: &mut EM,
+        state: &mut AptosFuzzerState,
+    ) -> anyhow::Result<Option<Resumed>>
+    where
+        Z: Evaluator<E, EM, AptosFuzzerInput, AptosFuzzerState>,
+    {
+        let Some(checkpoint) = self.read(state)? else {
+            return Ok(None);
+        };
+        let resumed = self.restore(checkpoint, state, |state, input| {
+            fuzzer
+                .evaluate_input(state, executor, mgr, input)
+                .map(|_| ())
+                .map_err(|e| anyhow::anyhow!("failed to replay a solution: {}", e))
+        })?;
+        Ok(Some(resumed))
+    }
+
+    /// The checkpoint in the directory, if there is one, checked to be of
+    /// `state`'s target modules and map size.
+    fn read(&self, state: &AptosFuzzerState) -> anyhow::Result<Option<Checkpoint>> {
+        let path = self.dir.join(CHECKPOINT_FILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let contents = fs::read(&path).with_context(|| format!("failed to read {}", path.display()))?;
+        let checkpoint: Checkpoint =
+            serde_json::from_slice(&contents).with_context(|| format!("failed to parse {}", path.display()))?;
+        if checkpoint.targets != targets_fingerprint(state) {
+            bail!("{} was taken against other target modules", path.display());
+        }
+        if checkpoint.coverage.len() != state.map_size() {
+            bail!(
+                "{} has a coverage map of {} entries, not {}",
+                path.display(),
+                checkpoint.coverage.len(),
+                state.map_size()
+            );
+        }
+        Ok(Some(checkpoint))
+    }
+
+    /// Add what `checkpoint` holds to `state`, passing each of its solutions
+    /// to `replay`.
+    fn restore(
+        &mut self,
+        checkpoint: Checkpoint,
+        state: &mut AptosFuzzerState,
+        mut replay: impl FnMut(&mut AptosFuzzerState, &AptosFuzzerInput) -> anyhow::Result<()>,
+    ) -> anyhow::Result<Resumed> {
+        let mut resumed = Resumed::default();
+        let present: HashSet<AptosFuzzerInput> = state
+            .corpus()
+            .ids()
+            .filter_map(|id| state.corpus().cloned_input_for_id(id).ok())
+            .collect();
+        for input in checkpoint.corpus {
+            if !present.contains(&input) && state.corpus_mut().add(Testcase::new(input)).is_ok() {
+                resumed.corpus += 1;
+            }
+        }
+        let before = state.solutions().count();
+        for input in &checkpoint.solutions {
+            replay(state, input)?;
+        }
+        resumed.solutions = state.solutions().count() - before;
+
+        for (hits, saved) in state.cumulative_coverage_mut().iter_mut().zip(&checkpoint.coverage) {
+            *hits = (*hits).max(*saved);
+        }
+        for path_id in checkpoint.seen_execution_paths {
+            state.mark_execution_path_seen(path_id);
+        }
+        for site in &checkpoint.seen_abort_sites {
+            state.mark_abort_site_seen(site);
+        }
+        self.last_write = Instant::now();
+        Ok(resumed)
+    }
+}
+
+/// Hash of the ids and bytecode of the target modules.
+fn targets_fingerprint(state: &AptosFuzzerState) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    for module_id in state.target_modules() {
+        module_id.hash(&mut hasher);
+        state.aptos_state().module_bytes().get(module_id).hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::time::Duration;
+    use std::{fs, process};
+
+    use aptos_move_core_types::account_address::AccountAddress;
+    use aptos_move_core_types::identifier::Identifier;
+    use aptos_move_core_types::language_storage::ModuleId;
+    use aptos_types::transaction::{EntryFunction, TransactionPayload};
+    use libafl::corpus::{Corpus, Testcase};
+    use libafl::state::{HasCorpus, HasSolutions};
+
+    use super::Checkpointer;
+    use crate::input::AptosFuzzerInput;
+    use crate::state::AptosFuzzerState;
+    use crate::trace::AbortSite;
+
+    fn input(function: &str) -> AptosFuzzerInput {
+        let module = ModuleId::new(
+            AccountAddress::from_hex_literal("0xcafe").unwrap(),
+            Identifier::new("m").unwrap(),
+        );
+        AptosFuzzerInput::new(TransactionPayload::EntryFunction(EntryFunction::new(
+            module,
+            Identifier::new(function).unwrap(),
+            Vec::new(),
+            Vec::new(),
+        )))
+    }
+
+    fn abort_site() -> AbortSite {
+        AbortSite {
+            module: "m".to_string(),
+            function: "withdraw".to_string(),
+            pc: 7,
+            code: 1,
+            callers: Vec::new(),
+        }
+    }
+
+    /// A fresh checkpoint directory, removed when dropped.
+    struct CheckpointDir(PathBuf);
+
+    impl CheckpointDir {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!("aptos-fuzzer-checkpoint-{}-{}", process::id(), name));
+            let _ = fs::remove_dir_all(&dir);
+            Self(dir)
+        }
+
+        fn checkpointer(&self) -> Checkpointer {
+            Checkpointer::new(self.0.clone(), Duration::from_secs(60)).unwrap()
+        }
+    }
+
+    impl Drop for CheckpointDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    /// A state that found two corpus entries, one solution, some coverage,
+    /// a path and an abort site.
+    fn campaign_state() -> AptosFuzzerState {
+        let mut state = AptosFuzzerState::new(PathBuf::new());
+        state.corpus_mut().add(Testcase::new(input("deposit"))).unwrap();
+        state.corpus_mut().add(Testcase::new(input("withdraw"))).unwrap();
+        state.solutions_mut().add(Testcase::new(input("drain"))).unwrap();
+        state.set_current_execution_path(vec![1, 2, 3]);
+        state.record_current_execution_path_for(&input("drain"));
+        state.cumulative_coverage_mut()[3] = 5;
+        state.mark_execution_path_seen(42);
+        state.mark_abort_site_seen(&abort_site());
+        state
+    }
+
+    #[test]
+    fn resume_restores_what_was_written() {
+        let dir = CheckpointDir::new("round-trip");
+        dir.checkpointer().write(&campaign_state()).unwrap();
+
+        let mut state = AptosFuzzerState::new(PathBuf::new());
+        let mut checkpointer = dir.checkpointer();
+        let checkpoint = checkpointer.read(&state).unwrap().expect("checkpoint was written");
+        let mut replayed = Vec::new();
+        let resumed = checkpointer
+            .restore(checkpoint, &mut state, |state, input| {
+                replayed.push(input.clone());
+                state.solutions_mut().add(Testcase::new(input.clone())).unwrap();
+                Ok(())
+            })
+            .unwrap();
+
+        assert_eq!(resumed.corpus, 2);
+        assert_eq!(state.corpus().count(), 2);
+        assert_eq!(replayed, [input("drain")]);
+        assert_eq!(resumed.solutions, 1);
+        assert_eq!(state.cumulative_coverage()[3], 5);
+        assert!(state.has_seen_execution_path(42));
+        assert!(!state.mark_abort_site_seen(&abort_site()));
+    }
+
+    #[test]
+    fn resume_skips_corpus_entries_already_present() {
+        let dir = CheckpointDir::new("present");
+        dir.checkpointer().write(&campaign_state()).unwrap();
+
+        let mut state = AptosFuzzerState::new(PathBuf::new());
+        state.corpus_mut().add(Testcase::new(input("deposit"))).unwrap();
+        let mut checkpointer = dir.checkpointer();
+        let checkpoint = checkpointer.read(&state).unwrap().unwrap();
+        let resumed = checkpointer.restore(checkpoint, &mut state, |_, _| Ok(())).unwrap();
+        assert_eq!(resumed.corpus, 1);
+        assert_eq!(state.corpus().count(), 2);
+    }
+
+    #[test]
+    fn nothing_to_resume_without_a_checkpoint() {
+        let dir = CheckpointDir::new("empty");
+        let state = AptosFuzzerState::new(PathBuf::new());
+        assert!(dir.checkpointer().read(&state).unwrap().is_none());
+    }
+
+    #[test]
+    fn checkpoints_of_another_map_size_are_rejected() {
+        let dir = CheckpointDir::new("map-size");
+        dir.checkpointer().write(&campaign_state()).unwrap();
+        let state = AptosFuzzerState::with_map_size(PathBuf::new(), 1024);
+        assert!(dir.checkpointer().read(&state).is_err());
+    }
+}
@@ -1,1184 +1,689 @@
 use std::cmp::Ordering;
-use std::collections::{BTreeMap, HashMap};
+use std::collections::HashMap;
+use std::fmt;
 use std::hash::{Hash, Hasher};
 use std::str::FromStr;
+use std::time::Instant;
 
 use aptos_move_binary_format::file_format::Bytecode;
+use aptos_move_core_types::account_address::AccountAddress;
 use aptos_move_core_types::language_storage::ModuleId;
 use aptos_move_core_types::u256::U256;
-use aptos_move_vm_runtime::{MoveTracer, MoveTracerExtraInfo, MoveTracerFrameInfo, MoveTracerInstructionContext};
+use aptos_move_core_types::vm_status::AbortLocation;
+use aptos_move_vm_runtime::{MoveTracer, MoveTracerFrameInfo, MoveTracerInstructionContext};
 use aptos_move_vm_types::values::{Container, ContainerRef, Value, ValueImpl};
-use log::{trace, warn};
-use z3::ast::{Ast, Bool, Dynamic, Int};
-use z3::DeclKind;
+use serde::{Deserialize, Serialize};
+
+#[cfg(not(feature = "concolic"))]
+use self::inert as symbolic;
+pub(crate) use self::symbolic::{contains_division, int_has_variable};
+pub use self::symbolic::{ConcolicState, Int, PathGuidance};
+use crate::branches::BranchKey;
+use crate::conflicts::{global_op, GlobalAccess, GlobalOp};
+use crate::executor::edge_layout::function_hash;
+use crate::executor::types::{BudgetExhausted, ExecutionBudget};
+use crate::runtime_detector::{builtin_detectors, InstructionContext, RuntimeDetector, RuntimeDetectorConfig};
+#[cfg(feature = "concolic")]
+use crate::symbolic;
+use crate::taint::TaintState;
+use crate::trace::{AbortSite, ExecutionTrace, StackFrame, TraceEvent, TRACE_OPERANDS};
+
+/// How many instructions to execute between wall-clock checks.
+const DEADLINE_CHECK_INTERVAL: u64 = 1024;
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum SymbolValue {
     Value(Int),
     Unknown,
+    /// Reference to a local, by frame depth and index. Reads and writes
+    /// through it go to that local's symbol.
+    Ref {
+        frame: usize,
+        local: usize,
+    },
+    /// Vector whose elements are not tracked, only its length
+    Vector {
+        len: Int,
+    },
 }
 
-#[derive(Clone, Debug)]
-pub struct ConcolicState {
-    pub stack: Vec<SymbolValue>,
-    pub locals: Vec<Vec<SymbolValue>>,
-    pub args: Vec<BTreeMap<usize, Int>>,
-    pub disable: bool,
-    runtime_issues: Vec<RuntimeIssue>,
-    branch_counts: HashMap<String, HashMap<u16, BranchCounter>>,
-}
-
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum RuntimeIssueKind {
     PrecisionLoss,
     BoolJudgement,
     InfiniteLoop,
+    /// A loop running more instructions than allowed in one execution, at
+    /// its head pc
+    UnboundedLoop,
+    /// A transaction argument flows into a sensitive operand
+    TaintedSink,
+    /// The VM or the tracer panicked while executing the input
+    Panic,
+    /// Reported by a user-registered `RuntimeDetector`, named by it
+    Custom(String),
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+/// How serious a runtime issue is. Only issues at or above the objective's
+/// threshold turn an input into a solution; the rest are only reported.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    Low,
+    Medium,
+    High,
+}
+
+impl FromStr for Severity {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "info" => Ok(Self::Info),
+            "low" => Ok(Self::Low),
+            "medium" => Ok(Self::Medium),
+            "high" => Ok(Self::High),
+            _ => anyhow::bail!("unknown severity {}, expected info, low, medium or high", s),
+        }
+    }
+}
+
+impl RuntimeIssueKind {
+    pub fn default_severity(&self) -> Severity {
+        match self {
+            Self::BoolJudgement => Severity::Info,
+            Self::TaintedSink => Severity::Low,
+            Self::PrecisionLoss | Self::Custom(_) => Severity::Medium,
+            Self::InfiniteLoop | Self::UnboundedLoop | Self::Panic => Severity::High,
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::PrecisionLoss => "PrecisionLoss",
+            Self::BoolJudgement => "BoolJudgement",
+            Self::InfiniteLoop => "InfiniteLoop",
+            Self::UnboundedLoop => "UnboundedLoop",
+            Self::TaintedSink => "TaintedSink",
+            Self::Panic => "Panic",
+            Self::Custom(name) => name,
+        }
+    }
+
+    /// The kind `as_str` names; any other name is a custom detector's.
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "PrecisionLoss" => Self::PrecisionLoss,
+            "BoolJudgement" => Self::BoolJudgement,
+            "InfiniteLoop" => Self::InfiniteLoop,
+            "UnboundedLoop" => Self::UnboundedLoop,
+            "TaintedSink" => Self::TaintedSink,
+            "Panic" => Self::Panic,
+            name => Self::Custom(name.to_string()),
+        }
+    }
+}
+
+/// A left shift that pushed set bits out of its operand.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShiftLoss {
+    pub module: String,
+    pub function: String,
+    pub pc: u16,
+    /// Bit width of the shifted operand
+    pub width: u32,
+    pub amount: u32,
+    /// Set high bits shifted out
+    pub lost_bits: u32,
+}
+
+impl fmt::Display for ShiftLoss {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}::{} @ pc {}: u{} << {} lost {} high bits",
+            self.module, self.function, self.pc, self.width, self.amount, self.lost_bits
+        )
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct RuntimeIssue {
     pub kind: RuntimeIssueKind,
+    /// Chosen by the detector; the kind's default unless it knows better
+    pub severity: Severity,
     pub module: String,
     pub function: String,
     pub pc: u16,
     pub message: String,
+    pub backtrace: Option<String>,
 }
 
 impl RuntimeIssue {
-    fn new(kind: RuntimeIssueKind, module: String, function: String, pc: u16, message: String) -> Self {
+    pub fn new(kind: RuntimeIssueKind, module: String, function: String, pc: u16, message: String) -> Self {
         Self {
+            severity: kind.default_severity(),
             kind,
             module,
             function,
             pc,
             message,
+            backtrace: None,
+        }
+    }
+
+    pub fn panic(module: String, function: String, pc: u16, message: String, backtrace: Option<String>) -> Self {
+        Self {
+            kind: RuntimeIssueKind::Panic,
+            severity: RuntimeIssueKind::Panic.default_severity(),
+            module,
+            function,
+            pc,
+            message,
+            backtrace,
         }
     }
 }
 
-#[derive(Clone, Debug, Default)]
-struct BranchCounter {
-    last_hash: Option<u64>,
-    count: usize,
+/// A runtime value the concolic tracer has no symbolic model for (references
+/// to globals, structs, signers, ...).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnsupportedValue(pub String);
+
+impl fmt::Display for UnsupportedValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unsupported value: {}", self.0)
+    }
 }
 
-const INFINITE_LOOP_THRESHOLD: usize = 1000;
+pub(crate) fn unsupported<T>(what: impl fmt::Debug) -> Result<T, UnsupportedValue> {
+    Err(UnsupportedValue(format!("{:?}", what)))
+}
 
-fn extract_primitive_value(v: &ValueImpl) -> ValueImpl {
+pub(crate) fn extract_primitive_value(v: &ValueImpl) -> Result<ValueImpl, UnsupportedValue> {
     match v {
         ValueImpl::IndexedRef(i) => {
             let ContainerRef::Local(local) = &i.container_ref else {
-                panic!("Unsupported container ref {:?} for comparison", i);
+                return unsupported(i);
             };
             match local {
                 Container::Locals(inner) | Container::Vec(inner) | Container::Struct(inner) => {
                     let inner = inner.borrow();
-                    let val = inner.get(i.idx).unwrap();
+                    let Some(val) = inner.get(i.idx) else {
+                        return unsupported(i);
+                    };
                     extract_primitive_value(val)
                 }
                 Container::VecBool(inner) => {
                     let inner = inner.borrow();
-                    let val = inner.get(i.idx).unwrap();
-                    ValueImpl::Bool(*val)
+                    let Some(val) = inner.get(i.idx) else {
+                        return unsupported(i);
+                    };
+                    Ok(ValueImpl::Bool(*val))
                 }
                 Container::VecU8(inner) => {
                     let inner = inner.borrow();
-                    let val = inner.get(i.idx).unwrap();
-                    ValueImpl::U8(*val)
+                    let Some(val) = inner.get(i.idx) else {
+                        return unsupported(i);
+                    };
+                    Ok(ValueImpl::U8(*val))
                 }
                 Container::VecU16(inner) => {
                     let inner = inner.borrow();
-                    let val = inner.get(i.idx).unwrap();
-                    ValueImpl::U16(*val)
+                    let Some(val) = inner.get(i.idx) else {
+                        return unsupported(i);
+                    };
+                    Ok(ValueImpl::U16(*val))
                 }
                 Container::VecU32(inner) => {
                     let inner = inner.borrow();
-                    let val = inner.get(i.idx).unwrap();
-                    ValueImpl::U32(*val)
+                    let Some(val) = inner.get(i.idx) else {
+                        return unsupported(i);
+                    };
+                    Ok(ValueImpl::U32(*val))
                 }
                 Container::VecU64(inner) => {
                     let inner = inner.borrow();
-                    let val = inner.get(i.idx).unwrap();
-                    ValueImpl::U64(*val)
+                    let Some(val) = inner.get(i.idx) else {
+                        return unsupported(i);
+                    };
+                    Ok(ValueImpl::U64(*val))
                 }
                 Container::VecU128(inner) => {
                     let inner = inner.borrow();
-                    let val = inner.get(i.idx).unwrap();
-                    ValueImpl::U128(*val)
+                    let Some(val) = inner.get(i.idx) else {
+                        return unsupported(i);
+                    };
+                    Ok(ValueImpl::U128(*val))
                 }
                 Container::VecU256(inner) => {
                     let inner = inner.borrow();
-                    let val = inner.get(i.idx).unwrap();
-                    ValueImpl::U256(*val)
-                }
-                _ => {
-                    panic!("Unsupported container type {:?} for comparison", local);
+                    let Some(val) = inner.get(i.idx) else {
+                        return unsupported(i);
+                    };
+                    Ok(ValueImpl::U256(*val))
                 }
+                _ => unsupported(local),
             }
         }
-        ValueImpl::Bool(b) => ValueImpl::Bool(*b),
-        ValueImpl::U8(u) => ValueImpl::U8(*u),
-        ValueImpl::U16(u) => ValueImpl::U16(*u),
-        ValueImpl::U32(u) => ValueImpl::U32(*u),
-        ValueImpl::U64(u) => ValueImpl::U64(*u),
-        ValueImpl::U128(u) => ValueImpl::U128(*u),
-        ValueImpl::U256(u) => ValueImpl::U256(*u),
-        _ => panic!("Unsupported value type {:?} for comparison", v),
+        ValueImpl::Bool(b) => Ok(ValueImpl::Bool(*b)),
+        ValueImpl::U8(u) => Ok(ValueImpl::U8(*u)),
+        ValueImpl::U16(u) => Ok(ValueImpl::U16(*u)),
+        ValueImpl::U32(u) => Ok(ValueImpl::U32(*u)),
+        ValueImpl::U64(u) => Ok(ValueImpl::U64(*u)),
+        ValueImpl::U128(u) => Ok(ValueImpl::U128(*u)),
+        ValueImpl::U256(u) => Ok(ValueImpl::U256(*u)),
+        _ => unsupported(v),
     }
 }
 
-fn compare_value_impl(v1: &ValueImpl, v2: &ValueImpl) -> Ordering {
-    let v1_value = extract_primitive_value(v1);
-    let v2_value = extract_primitive_value(v2);
+fn compare_value_impl(v1: &ValueImpl, v2: &ValueImpl) -> Result<Ordering, UnsupportedValue> {
+    let v1_value = extract_primitive_value(v1)?;
+    let v2_value = extract_primitive_value(v2)?;
     match (v1_value, v2_value) {
-        (ValueImpl::Bool(b1), ValueImpl::Bool(b2)) => b1.cmp(&b2),
-        (ValueImpl::U8(u1), ValueImpl::U8(u2)) => u1.cmp(&u2),
-        (ValueImpl::U16(u1), ValueImpl::U16(u2)) => u1.cmp(&u2),
-        (ValueImpl::U32(u1), ValueImpl::U32(u2)) => u1.cmp(&u2),
-        (ValueImpl::U64(u1), ValueImpl::U64(u2)) => u1.cmp(&u2),
-        (ValueImpl::U128(u1), ValueImpl::U128(u2)) => u1.cmp(&u2),
-        (ValueImpl::U256(u1), ValueImpl::U256(u2)) => u1.cmp(&u2),
-        _ => panic!("Unsupported value type {:?} and {:?} for comparison", v1, v2),
+        (ValueImpl::Bool(b1), ValueImpl::Bool(b2)) => Ok(b1.cmp(&b2)),
+        (ValueImpl::U8(u1), ValueImpl::U8(u2)) => Ok(u1.cmp(&u2)),
+        (ValueImpl::U16(u1), ValueImpl::U16(u2)) => Ok(u1.cmp(&u2)),
+        (ValueImpl::U32(u1), ValueImpl::U32(u2)) => Ok(u1.cmp(&u2)),
+        (ValueImpl::U64(u1), ValueImpl::U64(u2)) => Ok(u1.cmp(&u2)),
+        (ValueImpl::U128(u1), ValueImpl::U128(u2)) => Ok(u1.cmp(&u2)),
+        (ValueImpl::U256(u1), ValueImpl::U256(u2)) => Ok(u1.cmp(&u2)),
+        _ => unsupported((v1, v2)),
     }
 }
 
-pub fn compare_value(v1: &Value, v2: &Value) -> Ordering {
+pub fn compare_value(v1: &Value, v2: &Value) -> Result<Ordering, UnsupportedValue> {
     compare_value_impl(&v1.0, &v2.0)
 }
 
-pub fn value_to_u256(v: &Value) -> U256 {
-    let value = extract_primitive_value(&v.0);
+pub fn value_to_u256(v: &Value) -> Result<U256, UnsupportedValue> {
+    let value = extract_primitive_value(&v.0)?;
     match &value {
         ValueImpl::Bool(b) => {
             if *b {
-                U256::one()
+                Ok(U256::one())
             } else {
-                U256::zero()
+                Ok(U256::zero())
             }
         }
-        ValueImpl::U8(u) => U256::from(*u),
-        ValueImpl::U16(u) => U256::from(*u),
-        ValueImpl::U32(u) => U256::from(*u),
-        ValueImpl::U64(u) => U256::from(*u),
-        ValueImpl::U128(u) => U256::from(*u),
-        ValueImpl::U256(u) => *u,
-        _ => panic!("Unsupported value type for conversion to U256"),
+        ValueImpl::U8(u) => Ok(U256::from(*u)),
+        ValueImpl::U16(u) => Ok(U256::from(*u)),
+        ValueImpl::U32(u) => Ok(U256::from(*u)),
+        ValueImpl::U64(u) => Ok(U256::from(*u)),
+        ValueImpl::U128(u) => Ok(U256::from(*u)),
+        ValueImpl::U256(u) => Ok(*u),
+        _ => unsupported(value),
     }
 }
 
-pub fn value_bitwidth(v: &Value) -> u32 {
-    let value = extract_primitive_value(&v.0);
+pub fn value_bitwidth(v: &Value) -> Result<u32, UnsupportedValue> {
+    let value = extract_primitive_value(&v.0)?;
     match &value {
-        ValueImpl::Bool(_) => 1,
-        ValueImpl::U8(_) => 8,
-        ValueImpl::U16(_) => 16,
-        ValueImpl::U32(_) => 32,
-        ValueImpl::U64(_) => 64,
-        ValueImpl::U128(_) => 128,
-        ValueImpl::U256(_) => 256,
-        _ => panic!("Unsupported value type for bitwidth"),
+        ValueImpl::Bool(_) => Ok(1),
+        ValueImpl::U8(_) => Ok(8),
+        ValueImpl::U16(_) => Ok(16),
+        ValueImpl::U32(_) => Ok(32),
+        ValueImpl::U64(_) => Ok(64),
+        ValueImpl::U128(_) => Ok(128),
+        ValueImpl::U256(_) => Ok(256),
+        _ => unsupported(value),
     }
 }
 
-fn int_two_pow(bits: u32) -> Int {
-    let v = U256::one() << bits;
-    Int::from_str(&v.to_string()).unwrap()
-}
-
-fn int_mod_2n(x: &Int, bits: u32) -> Int {
-    x.modulo(int_two_pow(bits))
-}
+/// An executed instruction: function hash, pc, and the pc its frame ran
+/// before it, `None` on entry to the frame. Pairing by frame lets the
+/// instruction after a call continue the caller's edge instead of following
+/// the callee's return, and keeps the calls of a script sequence apart.
+pub type TracedLocation = (u64, u16, Option<u16>);
 
-/// Convert U256 to Int numeral.
-fn int_from_u256(u: U256) -> Int {
-    Int::from_str(&u.to_string()).unwrap()
+pub struct SymbolicMoveTracer {
+    state: ConcolicState,
+    taint: TaintState,
+    /// Every executed instruction, used for edge coverage
+    locations: Vec<TracedLocation>,
+    /// Last pc of every open frame, innermost last
+    frame_pcs: Vec<Option<u16>>,
+    /// Operand stack, as primitives, when each function called by the
+    /// outermost frame returned: the calls of a script sequence, in order
+    call_returns: Vec<Vec<Option<U256>>>,
+    /// Smallest |lhs - rhs| seen at each comparison site during this run
+    branch_distances: HashMap<(u64, u16), u64>,
+    /// Times each conditional branch of this run went false and true
+    branch_outcomes: HashMap<BranchKey, [u64; 2]>,
+    /// (module, function) of every function hash seen, kept across runs
+    function_names: HashMap<u64, (String, String)>,
+    /// Global storage accessed in this run
+    global_accesses: Vec<GlobalAccess>,
+    /// Frames opened right below the outermost one in this run: the calls of
+    /// a script sequence
+    sequence_calls: usize,
+    /// Structured trace of the run, when capturing one
+    trace: Option<ExecutionTrace>,
+    /// Where the run aborted, with its call stack
+    abort_site: Option<AbortSite>,
+    /// Left shifts of this run that lost high bits
+    shift_losses: Vec<ShiftLoss>,
+    budget: ExecutionBudget,
+    deadline: Option<Instant>,
+    instructions: u64,
+    /// (module, function) of every open frame, innermost last
+    frames: Vec<(String, String)>,
+    builtin_detectors: Vec<Box<dyn RuntimeDetector>>,
+    /// Detectors registered on top of the built-in ones
+    detectors: Vec<Box<dyn RuntimeDetector>>,
+    runtime_issues: Vec<RuntimeIssue>,
+    /// Path constraints and solver queries, for the concolic searches
+    guidance: PathGuidance,
 }
 
-/// Integer-only AND with a constant bitmask:
-/// Returns (x & mask) under w-bit semantics, using only Int + div/mod by powers
-/// of two. Implementation uses "run decomposition": split mask's 1-bits into
-/// contiguous runs [a..=b], and for each run extract that window from x, then
-/// place it back.
-pub fn int_bvand_const(x: &Int, mask: U256, bits: u32) -> Int {
-    // Normalize x to w-bit domain (BitVec semantics).
-    let x0 = int_mod_2n(x, bits);
-
-    // Quick exits.
-    if mask == U256::zero() {
-        return Int::from_u64(0);
-    }
-    // Restrict mask to w bits (BV mask)
-    let mask_w = mask & ((U256::one() << bits) - U256::one());
-
-    // Iterate over runs of 1s in mask_w.
-    let mut m = mask_w;
-    let mut i: u32 = 0;
-    let mut terms: Vec<Int> = Vec::new();
-
-    while m != U256::zero() {
-        // skip zeros
-        while m != U256::zero() && (m & U256::one()) == U256::zero() {
-            m = m.checked_shr(1).unwrap();
-            i += 1;
-        }
-        if m == U256::zero() {
-            break;
-        }
-        // start of a run
-        let a = i;
-        // consume ones
-        while (m & U256::one()) == U256::one() {
-            m = m.checked_shr(1).unwrap();
-            i += 1;
+impl SymbolicMoveTracer {
+    pub fn new() -> Self {
+        Self {
+            state: ConcolicState::new(),
+            taint: TaintState::new(),
+            locations: Vec::new(),
+            frame_pcs: Vec::new(),
+            call_returns: Vec::new(),
+            branch_distances: HashMap::new(),
+            branch_outcomes: HashMap::new(),
+            function_names: HashMap::new(),
+            global_accesses: Vec::new(),
+            sequence_calls: 0,
+            trace: None,
+            abort_site: None,
+            shift_losses: Vec::new(),
+            budget: ExecutionBudget::default(),
+            deadline: None,
+            instructions: 0,
+            frames: Vec::new(),
+            builtin_detectors: builtin_detectors(&RuntimeDetectorConfig::default()),
+            detectors: Vec::new(),
+            runtime_issues: Vec::new(),
+            guidance: PathGuidance::default(),
         }
-        let b = i - 1; // inclusive end
-        let L = b - a + 1;
-
-        // term(a,b) = (((x0 mod 2^(b+1)) div 2^a) mod 2^L) * 2^a
-        let term = (x0.clone() % int_two_pow(b + 1) / int_two_pow(a)) % int_two_pow(L) * int_two_pow(a);
-        terms.push(term);
     }
 
-    // Sum all terms (if no runs, it's zero which we handled above).
-    let mut acc = Int::from_u64(0);
-    for t in terms {
-        acc += t;
+    /// Replace the built-in detectors with the ones enabled in `config`.
+    pub fn configure_detectors(&mut self, config: &RuntimeDetectorConfig) {
+        self.builtin_detectors = builtin_detectors(config);
     }
-    acc
-}
-
-/// Integer-only OR with a constant bitmask:
-/// Returns (x | mask) under w-bit semantics, using only Int ops.
-/// Uses identity: x | M = (x & ~M_w) + M_w, where ~M_w is bitwise-not of M
-/// within w bits. We reuse int_bvand_const for the "clear then add" pattern.
-pub fn int_bvor_const(x: &Int, mask: U256, bits: u32) -> Int {
-    // mask limited to w bits
-    let full = (U256::one() << bits) - U256::one();
-    let mask_w = mask & full;
-    let not_mask_w = full ^ mask_w;
-
-    // Keep x's bits where mask is 0, then force-on mask bits by addition.
-    let kept = int_bvand_const(x, not_mask_w, bits);
-    kept + int_from_u256(mask_w)
-}
-
-/// Integer-only NOT under w-bit semantics:
-/// r = ~x  (within w bits)  ==  (2^w - 1) - (x mod 2^w)
-pub fn int_bvnot(x: &Int, bits: u32) -> Int {
-    let x0 = int_mod_2n(x, bits);
-    let full = int_two_pow(bits) - 1;
-    full - x0
-}
-
-/// Integer-only XOR with a constant mask under w-bit semantics:
-/// r = x ^ mask = (x & ~mask_w) + (~x & mask_w)
-pub fn int_bvxor_const(x: &Int, mask: U256, bits: u32) -> Int {
-    let full = (U256::one() << bits) - U256::one();
-    let mask_w = mask & full;
-    let not_mask_w = full ^ mask_w;
 
-    let part_keep = int_bvand_const(x, not_mask_w, bits);
-    let x_not = int_bvnot(x, bits);
-    let part_flip = int_bvand_const(&x_not, mask_w, bits);
-
-    // Disjoint bit regions; sum is exact. Normalize just in case.
-    let sum = part_keep + part_flip;
-    int_mod_2n(&sum, bits)
-}
+    /// Run `detector` on every execution, alongside the built-in ones.
+    pub fn register_detector(&mut self, detector: Box<dyn RuntimeDetector>) {
+        self.detectors.push(detector);
+    }
 
-impl Default for ConcolicState {
-    fn default() -> Self {
-        Self::new()
+    pub fn detectors(&self) -> impl Iterator<Item = &Box<dyn RuntimeDetector>> {
+        self.builtin_detectors.iter().chain(&self.detectors)
     }
-}
 
-impl ConcolicState {
-    pub fn new() -> Self {
-        Self {
-            stack: Vec::new(),
-            locals: Vec::new(),
-            args: Vec::new(),
-            disable: false,
-            runtime_issues: Vec::new(),
-            branch_counts: HashMap::new(),
-        }
+    pub fn set_budget(&mut self, budget: ExecutionBudget) {
+        self.budget = budget;
     }
 
-    pub fn open_frame(&mut self, frame: &MoveTracerFrameInfo<'_>) {
-        trace!(
-            "Open frame: {}::{}",
-            frame.function.module_or_script_id(),
-            frame.function.name()
-        );
-        let func_key = format!(
-            "{}::{}",
-            format_module_name(frame.function.module_or_script_id()),
-            frame.function.name()
-        );
-        self.branch_counts.remove(&func_key);
-        if self.locals.is_empty() {
-            let locals = frame
-                .param_tys
-                .iter()
-                .enumerate()
-                .map(|(i, ty)| Self::resolve_arg(self.args.len(), i, ty))
-                .collect::<Vec<_>>();
-            self.args.push(
-                locals
-                    .iter()
-                    .enumerate()
-                    .filter_map(|(i, v)| match v {
-                        SymbolValue::Value(bv) => Some((i, bv.clone())),
-                        SymbolValue::Unknown => None,
-                    })
-                    .collect(),
-            );
-            self.locals.push(locals);
-            trace!("args: {:?}", self.args);
-        } else {
-            if frame.param_tys.len() > self.stack.len() {
-                self.stack.clear();
-                self.locals.clear();
-                self.disable = true;
-                warn!("Symbolic stack underflow when opening frame");
-                return;
-            }
-            let skip_idx = self.stack.len() - frame.param_tys.len();
-            self.locals.push(self.stack.drain(skip_idx..).collect());
-            self.stack.truncate(skip_idx);
-            if frame.is_native {
-                for _ in 0..frame.return_tys.len() {
-                    self.stack.push(SymbolValue::Unknown);
-                }
-            }
-        }
+    pub fn state(&self) -> &ConcolicState {
+        &self.state
     }
 
-    pub fn close_frame(&mut self) {
-        trace!("Close frame");
-        self.locals.pop();
+    pub fn state_mut(&mut self) -> &mut ConcolicState {
+        &mut self.state
     }
 
-    pub fn before_instruction(&mut self, ctx: &MoveTracerInstructionContext<'_>) -> Option<Bool> {
-        // if self.disable {
-        //     return None;
-        // }
-        let s = &ctx.operand_stack;
-        let extra = ctx.extra;
-        let instruction = ctx.instruction;
-        let pc = ctx.pc;
-        let module_name = format_module_name(ctx.frame.function.module_or_script_id());
-        let function_name = ctx.frame.function.name().to_string();
-        let function_key = format!("{}::{}", module_name, function_name);
-
-        if self.stack.len() != s.values.len() && s.values.is_empty() {
-            self.stack.clear();
+    pub fn reset(&mut self) {
+        self.state.clear();
+        self.taint.clear();
+        self.locations.clear();
+        self.frame_pcs.clear();
+        self.call_returns.clear();
+        self.branch_distances.clear();
+        self.branch_outcomes.clear();
+        self.global_accesses.clear();
+        self.sequence_calls = 0;
+        if let Some(trace) = self.trace.as_mut() {
+            *trace = ExecutionTrace::default();
         }
-        assert_eq!(
-            self.stack.len(),
-            s.values.len(),
-            "stack: {:?}, stack from trace: {:?}",
-            self.stack,
-            s.values,
-        );
-
-        match instruction {
-            Bytecode::Eq | Bytecode::Neq | Bytecode::Lt | Bytecode::Le | Bytecode::Gt | Bytecode::Ge => {
-                self.check_bool_judgement(instruction, &module_name, &function_name, pc, s.values());
-            }
-            Bytecode::BrTrue(_) | Bytecode::BrFalse(_) => {
-                self.check_infinite_loop(&function_key, pc, &module_name, &function_name);
-            }
-            _ => {}
+        self.abort_site = None;
+        self.shift_losses.clear();
+        self.instructions = 0;
+        self.frames.clear();
+        self.runtime_issues.clear();
+        self.guidance.reset();
+        for detector in self.builtin_detectors.iter_mut().chain(&mut self.detectors) {
+            detector.reset();
         }
-
-        let mut process_binary_op = || {
-            let (rhs, lhs) = (self.stack.pop().unwrap(), self.stack.pop().unwrap());
-            let stack_iter = s.last_n(2).unwrap();
-            let true_lhs = stack_iter.get(0).unwrap();
-            let true_rhs = stack_iter.get(1).unwrap();
-            let (new_l, new_r) = match (lhs, rhs) {
-                (SymbolValue::Value(l), SymbolValue::Value(r)) => (l, r),
-                (SymbolValue::Value(l), SymbolValue::Unknown) => {
-                    let new_r = Self::resolve_value(true_rhs);
-                    (l, new_r)
-                }
-                (SymbolValue::Unknown, SymbolValue::Value(r)) => {
-                    let new_l = Self::resolve_value(true_lhs);
-                    (new_l, r)
-                }
-                (SymbolValue::Unknown, SymbolValue::Unknown) => {
-                    return None;
-                }
-            };
-            Some((new_l, new_r))
-        };
-
-        match instruction {
-            Bytecode::Pop |
-            Bytecode::BrTrue(_) |
-            Bytecode::BrFalse(_) |
-            Bytecode::Abort |
-            Bytecode::VecImmBorrow(_) |
-            Bytecode::VecMutBorrow(_) => {
-                self.stack.pop();
-            }
-            Bytecode::LdU8(_) |
-            Bytecode::LdU16(_) |
-            Bytecode::LdU32(_) |
-            Bytecode::LdU64(_) |
-            Bytecode::LdU128(_) |
-            Bytecode::LdU256(_) |
-            Bytecode::LdConst(_) => {
-                self.stack.push(SymbolValue::Unknown);
-            }
-            Bytecode::LdFalse => {
-                self.stack.push(SymbolValue::Value(Int::from_u64(0)));
-            }
-            Bytecode::LdTrue => {
-                self.stack.push(SymbolValue::Value(Int::from_u64(1)));
-            }
-            Bytecode::CastU8 => {
-                if let Some(v) = self.stack.last() {
-                    if let SymbolValue::Value(int) = v {
-                        return Some(int.le(Self::max_u_bits(8)));
-                    }
-                } else {
-                    warn!("Stack underflow at pc {}", pc);
-                }
-            }
-            Bytecode::CastU16 => {
-                if let Some(v) = self.stack.last() {
-                    if let SymbolValue::Value(int) = v {
-                        return Some(int.le(Self::max_u_bits(16)));
-                    }
-                } else {
-                    warn!("Stack underflow at pc {}", pc);
-                }
-            }
-            Bytecode::CastU32 => {
-                if let Some(v) = self.stack.last() {
-                    if let SymbolValue::Value(int) = v {
-                        return Some(int.le(Self::max_u_bits(32)));
-                    }
-                } else {
-                    warn!("Stack underflow at pc {}", pc);
-                }
-            }
-            Bytecode::CastU64 => {
-                if let Some(v) = self.stack.last() {
-                    if let SymbolValue::Value(int) = v {
-                        return Some(int.le(Self::max_u_bits(64)));
-                    }
-                } else {
-                    warn!("Stack underflow at pc {}", pc);
-                }
-            }
-            Bytecode::CastU128 => {
-                if let Some(v) = self.stack.last() {
-                    if let SymbolValue::Value(int) = v {
-                        return Some(int.le(Self::max_u_bits(128)));
-                    }
-                } else {
-                    warn!("Stack underflow at pc {}", pc);
-                }
-            }
-            Bytecode::Add => {
-                if let Some((l, r)) = process_binary_op() {
-                    // overflow check not implemented yet
-                    let sum = l + r;
-                    self.stack.push(SymbolValue::Value(sum));
-                } else {
-                    self.stack.push(SymbolValue::Unknown);
-                }
-            }
-            Bytecode::Sub => {
-                if let Some((l, r)) = process_binary_op() {
-                    // overflow check not implemented yet
-                    let diff = l - r;
-                    self.stack.push(SymbolValue::Value(diff));
-                } else {
-                    self.stack.push(SymbolValue::Unknown);
-                }
-            }
-            Bytecode::Mul => {
-                if let Some((l, r)) = process_binary_op() {
-                    self.check_precision_loss(&module_name, &function_name, pc, &l, &r);
-                    let prod = l * r;
-                    self.stack.push(SymbolValue::Value(prod));
-                } else {
-                    self.stack.push(SymbolValue::Unknown);
-                }
-            }
-            Bytecode::Div => {
-                if let Some((l, r)) = process_binary_op() {
-                    // overflow check not implemented yet
-                    let quot = l / r;
-                    self.stack.push(SymbolValue::Value(quot));
-                } else {
-                    self.stack.push(SymbolValue::Unknown);
-                }
-            }
-            Bytecode::Mod => {
-                if let Some((l, r)) = process_binary_op() {
-                    // overflow check not implemented yet
-                    let rem = l % r;
-                    self.stack.push(SymbolValue::Value(rem));
-                } else {
-                    self.stack.push(SymbolValue::Unknown);
-                }
-            }
-            Bytecode::And | Bytecode::BitAnd => {
-                let (rhs, lhs) = (self.stack.pop().unwrap(), self.stack.pop().unwrap());
-                let stack_iter = s.last_n(2).unwrap();
-                let true_lhs = stack_iter.get(0).unwrap();
-                let true_rhs = stack_iter.get(1).unwrap();
-
-                let bit_width = value_bitwidth(true_lhs);
-                let (true_l, true_r) = (value_to_u256(true_lhs), value_to_u256(true_rhs));
-                match (lhs, rhs) {
-                    (SymbolValue::Value(l), SymbolValue::Value(r)) => {
-                        self.stack.push(SymbolValue::Unknown);
-                    }
-                    (SymbolValue::Value(l), SymbolValue::Unknown) => {
-                        let and = int_bvand_const(&l, true_r, bit_width);
-                        self.stack.push(SymbolValue::Value(and));
-                    }
-                    (SymbolValue::Unknown, SymbolValue::Value(r)) => {
-                        let and = int_bvand_const(&r, true_l, bit_width);
-                        self.stack.push(SymbolValue::Value(and));
-                    }
-                    (SymbolValue::Unknown, SymbolValue::Unknown) => {
-                        self.stack.push(SymbolValue::Unknown);
-                    }
-                }
-            }
-            Bytecode::Or | Bytecode::BitOr => {
-                let (rhs, lhs) = (self.stack.pop().unwrap(), self.stack.pop().unwrap());
-                let stack_iter = s.last_n(2).unwrap();
-                let true_lhs = stack_iter.get(0).unwrap();
-                let true_rhs = stack_iter.get(1).unwrap();
-
-                let bit_width = value_bitwidth(true_lhs);
-                let (true_l, true_r) = (value_to_u256(true_lhs), value_to_u256(true_rhs));
-                match (lhs, rhs) {
-                    (SymbolValue::Value(l), SymbolValue::Value(r)) => {
-                        self.stack.push(SymbolValue::Unknown);
-                    }
-                    (SymbolValue::Value(l), SymbolValue::Unknown) => {
-                        let or = int_bvor_const(&l, true_r, bit_width);
-                        self.stack.push(SymbolValue::Value(or));
-                    }
-                    (SymbolValue::Unknown, SymbolValue::Value(r)) => {
-                        let or = int_bvor_const(&r, true_l, bit_width);
-                        self.stack.push(SymbolValue::Value(or));
-                    }
-                    (SymbolValue::Unknown, SymbolValue::Unknown) => {
-                        self.stack.push(SymbolValue::Unknown);
-                    }
-                }
-            }
-            Bytecode::Xor => {
-                let (rhs, lhs) = (self.stack.pop().unwrap(), self.stack.pop().unwrap());
-                let stack_iter = s.last_n(2).unwrap();
-                let true_lhs = stack_iter.get(0).unwrap();
-                let true_rhs = stack_iter.get(1).unwrap();
-
-                let bit_width = value_bitwidth(true_lhs);
-                let (true_l, true_r) = (value_to_u256(true_lhs), value_to_u256(true_rhs));
-                match (lhs, rhs) {
-                    (SymbolValue::Value(l), SymbolValue::Value(r)) => {
-                        self.stack.push(SymbolValue::Unknown);
-                    }
-                    (SymbolValue::Value(l), SymbolValue::Unknown) => {
-                        let xor = int_bvxor_const(&l, true_r, bit_width);
-                        self.stack.push(SymbolValue::Value(xor));
-                    }
-                    (SymbolValue::Unknown, SymbolValue::Value(r)) => {
-                        let xor = int_bvxor_const(&r, true_l, bit_width);
-                        self.stack.push(SymbolValue::Value(xor));
-                    }
-                    (SymbolValue::Unknown, SymbolValue::Unknown) => {
-                        self.stack.push(SymbolValue::Unknown);
-                    }
-                }
-            }
-            Bytecode::Shl => {
-                let (rhs, lhs) = (self.stack.pop().unwrap(), self.stack.pop().unwrap());
-                let stack_iter = s.last_n(2).unwrap();
-                let true_lhs = stack_iter.get(0).unwrap();
-                let true_rhs = stack_iter.get(1).unwrap();
-                let bit_width = value_bitwidth(true_lhs);
-                let true_r = value_to_u256(true_rhs).unchecked_as_u32();
-                let threshold = Self::max_u_bits(bit_width);
-                match (lhs, rhs) {
-                    (SymbolValue::Value(l), SymbolValue::Value(r)) => {
-                        self.stack.push(SymbolValue::Unknown);
-                    }
-                    (SymbolValue::Value(l), SymbolValue::Unknown) => {
-                        let shl = l * int_two_pow(true_r);
-                        let shl_mod = shl.modulo(int_two_pow(bit_width));
-                        self.stack.push(SymbolValue::Value(shl_mod));
-                        return Some(shl.gt(&threshold)); // cause overflow
-                    }
-                    (SymbolValue::Unknown, SymbolValue::Value(r)) => {
-                        self.stack.push(SymbolValue::Unknown);
-                    }
-                    (SymbolValue::Unknown, SymbolValue::Unknown) => {
-                        self.stack.push(SymbolValue::Unknown);
-                    }
-                }
-            }
-            Bytecode::Shr => {
-                let (rhs, lhs) = (self.stack.pop().unwrap(), self.stack.pop().unwrap());
-                let stack_iter = s.last_n(2).unwrap();
-                let true_lhs = stack_iter.get(0).unwrap();
-                let true_rhs = stack_iter.get(1).unwrap();
-
-                let true_r = value_to_u256(true_rhs).unchecked_as_u32();
-                match (lhs, rhs) {
-                    (SymbolValue::Value(l), SymbolValue::Value(r)) => {
-                        self.stack.push(SymbolValue::Unknown);
-                    }
-                    (SymbolValue::Value(l), SymbolValue::Unknown) => {
-                        let shr = l / int_two_pow(true_r);
-                        self.stack.push(SymbolValue::Value(shr));
-                    }
-                    (SymbolValue::Unknown, SymbolValue::Value(r)) => {
-                        self.stack.push(SymbolValue::Unknown);
-                    }
-                    (SymbolValue::Unknown, SymbolValue::Unknown) => {
-                        self.stack.push(SymbolValue::Unknown);
-                    }
-                }
-            }
-            Bytecode::Not => {
-                if let Some(v) = self.stack.pop() {
-                    match v {
-                        SymbolValue::Value(n) => {
-                            let bit_width = value_bitwidth(s.last_n(1).unwrap().get(0).unwrap());
-                            let not_n = int_bvnot(&n, bit_width);
-                            self.stack.push(SymbolValue::Value(not_n));
-                        }
-                        SymbolValue::Unknown => {
-                            self.stack.push(SymbolValue::Unknown);
-                        }
-                    }
-                } else {
-                    warn!("Stack underflow at pc {}", pc);
-                }
-            }
-            Bytecode::CopyLoc(idx) | Bytecode::MutBorrowLoc(idx) | Bytecode::ImmBorrowLoc(idx) => {
-                if let Some(locals) = self.locals.last() {
-                    if let Some(v) = locals.get(*idx as usize) {
-                        self.stack.push(v.clone());
-                    } else {
-                        warn!("Local index out of bounds at pc {}", pc);
-                        self.stack.push(SymbolValue::Unknown);
-                    }
-                } else {
-                    warn!("No locals available at pc {}", pc);
-                    self.stack.push(SymbolValue::Unknown);
-                }
-            }
-            Bytecode::MoveLoc(idx) => {
-                if let Some(locals) = self.locals.last_mut() {
-                    if let Some(v) = locals.get(*idx as usize) {
-                        self.stack.push(v.clone());
-                        locals[*idx as usize] = SymbolValue::Unknown; // moved-from
-                    } else {
-                        warn!("Local index out of bounds at pc {}", pc);
-                        self.stack.push(SymbolValue::Unknown);
-                    }
-                } else {
-                    warn!("No locals available at pc {}", pc);
-                    self.stack.push(SymbolValue::Unknown);
-                }
-            }
-            Bytecode::StLoc(idx) => {
-                if let Some(v) = self.stack.pop() {
-                    if let Some(locals) = self.locals.last_mut() {
-                        if let Some(slot) = locals.get_mut(*idx as usize) {
-                            *slot = v;
-                        } else {
-                            for _ in locals.len()..=*idx as usize {
-                                locals.push(SymbolValue::Unknown);
-                            }
-                            locals[*idx as usize] = v;
-                        }
-                    } else {
-                        warn!("No locals available at pc {}", pc);
-                    }
-                } else {
-                    warn!("Stack underflow at pc {}", pc);
-                }
-            }
-            Bytecode::WriteRef | Bytecode::VecPushBack(_) => {
-                self.stack.pop();
-                self.stack.pop();
-            }
-            Bytecode::Eq => {
-                let (rhs, lhs) = (self.stack.pop().unwrap(), self.stack.pop().unwrap());
-                let stack_iter = s.last_n(2).unwrap();
-                let true_lhs = stack_iter.get(0).unwrap();
-                let true_rhs = stack_iter.get(1).unwrap();
-                let (new_l, new_r) = match (lhs, rhs) {
-                    (SymbolValue::Value(l), SymbolValue::Value(r)) => (l, r),
-                    (SymbolValue::Value(l), SymbolValue::Unknown) => {
-                        let new_r = Self::resolve_value(true_rhs);
-                        (l, new_r)
-                    }
-                    (SymbolValue::Unknown, SymbolValue::Value(r)) => {
-                        let new_l = Self::resolve_value(true_lhs);
-                        (new_l, r)
-                    }
-                    (SymbolValue::Unknown, SymbolValue::Unknown) => {
-                        self.stack.push(SymbolValue::Unknown);
-                        return None;
-                    }
-                };
-                if matches!(compare_value(true_lhs, true_rhs), Ordering::Equal) {
-                    let eq = new_l._eq(&new_r);
-                    let int = eq.ite(&Int::from_u64(1), &Int::from_u64(0));
-                    self.stack.push(SymbolValue::Value(int));
-                    return Some(eq);
-                } else {
-                    // different values are not equal
-                    let neq = new_l._eq(&new_r).not();
-                    let int = neq.ite(&Int::from_u64(1), &Int::from_u64(0));
-                    self.stack.push(SymbolValue::Value(int));
-                    return Some(neq);
-                }
-            }
-            Bytecode::Neq => {
-                let (rhs, lhs) = (self.stack.pop().unwrap(), self.stack.pop().unwrap());
-                let stack_iter = s.last_n(2).unwrap();
-                let true_lhs = stack_iter.get(0).unwrap();
-                let true_rhs = stack_iter.get(1).unwrap();
-                let (new_l, new_r) = match (lhs, rhs) {
-                    (SymbolValue::Value(l), SymbolValue::Value(r)) => (l, r),
-                    (SymbolValue::Value(l), SymbolValue::Unknown) => {
-                        let new_r = Self::resolve_value(true_rhs);
-                        (l, new_r)
-                    }
-                    (SymbolValue::Unknown, SymbolValue::Value(r)) => {
-                        let new_l = Self::resolve_value(true_lhs);
-                        (new_l, r)
-                    }
-                    (SymbolValue::Unknown, SymbolValue::Unknown) => {
-                        self.stack.push(SymbolValue::Unknown);
-                        return None;
-                    }
-                };
-                if !matches!(compare_value(true_lhs, true_rhs), Ordering::Equal) {
-                    let neq = new_l._eq(&new_r).not();
-                    let bv = neq.ite(&Int::from_u64(1), &Int::from_u64(0));
-                    self.stack.push(SymbolValue::Value(bv));
-                    return Some(neq);
-                } else {
-                    // same values are equal
-                    let eq = new_l._eq(&new_r);
-                    let bv = eq.ite(&Int::from_u64(1), &Int::from_u64(0));
-                    self.stack.push(SymbolValue::Value(bv));
-                    return Some(eq.not());
-                }
-            }
-            Bytecode::Lt => {
-                let (rhs, lhs) = (self.stack.pop().unwrap(), self.stack.pop().unwrap());
-                let stack_iter = s.last_n(2).unwrap();
-                let true_lhs = stack_iter.get(0).unwrap();
-                let true_rhs = stack_iter.get(1).unwrap();
-                let (new_l, new_r) = match (lhs, rhs) {
-                    (SymbolValue::Value(l), SymbolValue::Value(r)) => (l, r),
-                    (SymbolValue::Value(l), SymbolValue::Unknown) => {
-                        let new_r = Self::resolve_value(true_rhs);
-                        (l, new_r)
-                    }
-                    (SymbolValue::Unknown, SymbolValue::Value(r)) => {
-                        let new_l = Self::resolve_value(true_lhs);
-                        (new_l, r)
-                    }
-                    (SymbolValue::Unknown, SymbolValue::Unknown) => {
-                        self.stack.push(SymbolValue::Unknown);
-                        return None;
-                    }
-                };
-                if matches!(compare_value(true_lhs, true_rhs), Ordering::Less) {
-                    let lt = new_l.lt(&new_r);
-                    let int = lt.ite(&Int::from_u64(1), &Int::from_u64(0));
-                    self.stack.push(SymbolValue::Value(int));
-                    return Some(lt);
-                } else {
-                    // not less than
-                    let nlt = new_l.lt(&new_r).not();
-                    let int = nlt.ite(&Int::from_u64(1), &Int::from_u64(0));
-                    self.stack.push(SymbolValue::Value(int));
-                    return Some(nlt);
-                }
-            }
-            Bytecode::Le => {
-                let (rhs, lhs) = (self.stack.pop().unwrap(), self.stack.pop().unwrap());
-                let stack_iter = s.last_n(2).unwrap();
-                let true_lhs = stack_iter.get(0).unwrap();
-                let true_rhs = stack_iter.get(1).unwrap();
-                let (new_l, new_r) = match (lhs, rhs) {
-                    (SymbolValue::Value(l), SymbolValue::Value(r)) => (l, r),
-                    (SymbolValue::Value(l), SymbolValue::Unknown) => {
-                        let new_r = Self::resolve_value(true_rhs);
-                        (l, new_r)
-                    }
-                    (SymbolValue::Unknown, SymbolValue::Value(r)) => {
-                        let new_l = Self::resolve_value(true_lhs);
-                        (new_l, r)
-                    }
-                    (SymbolValue::Unknown, SymbolValue::Unknown) => {
-                        self.stack.push(SymbolValue::Unknown);
-                        return None;
-                    }
-                };
-                if !matches!(compare_value(true_lhs, true_rhs), Ordering::Greater) {
-                    let lt = new_l.le(&new_r);
-                    let int = lt.ite(&Int::from_u64(1), &Int::from_u64(0));
-                    self.stack.push(SymbolValue::Value(int));
-                    return Some(lt);
-                } else {
-                    // not less than
-                    let nlt = new_l.le(&new_r).not();
-                    let int = nlt.ite(&Int::from_u64(1), &Int::from_u64(0));
-                    self.stack.push(SymbolValue::Value(int));
-                    return Some(nlt);
-                }
-            }
-            Bytecode::Gt => {
-                let (rhs, lhs) = (self.stack.pop().unwrap(), self.stack.pop().unwrap());
-                let stack_iter = s.last_n(2).unwrap();
-                let true_lhs = stack_iter.get(0).unwrap();
-                let true_rhs = stack_iter.get(1).unwrap();
-                let (new_l, new_r) = match (lhs, rhs) {
-                    (SymbolValue::Value(l), SymbolValue::Value(r)) => (l, r),
-                    (SymbolValue::Value(l), SymbolValue::Unknown) => {
-                        let new_r = Self::resolve_value(true_rhs);
-                        (l, new_r)
-                    }
-                    (SymbolValue::Unknown, SymbolValue::Value(r)) => {
-                        let new_l = Self::resolve_value(true_lhs);
-                        (new_l, r)
-                    }
-                    (SymbolValue::Unknown, SymbolValue::Unknown) => {
-                        self.stack.push(SymbolValue::Unknown);
-                        return None;
-                    }
-                };
-                if matches!(compare_value(true_lhs, true_rhs), Ordering::Greater) {
-                    let lt = new_l.gt(&new_r);
-                    let int = lt.ite(&Int::from_u64(1), &Int::from_u64(0));
-                    self.stack.push(SymbolValue::Value(int));
-                    return Some(lt);
-                } else {
-                    // not less than
-                    let nlt = new_l.gt(&new_r).not();
-                    let int = nlt.ite(&Int::from_u64(1), &Int::from_u64(0));
-                    self.stack.push(SymbolValue::Value(int));
-                    return Some(nlt);
-                }
-            }
-            Bytecode::Ge => {
-                let (rhs, lhs) = (self.stack.pop().unwrap(), self.stack.pop().unwrap());
-                let stack_iter = s.last_n(2).unwrap();
-                let true_lhs = stack_iter.get(0).unwrap();
-                let true_rhs = stack_iter.get(1).unwrap();
-                let (new_l, new_r) = match (lhs, rhs) {
-                    (SymbolValue::Value(l), SymbolValue::Value(r)) => (l, r),
-                    (SymbolValue::Value(l), SymbolValue::Unknown) => {
-                        let new_r = Self::resolve_value(true_rhs);
-                        (l, new_r)
-                    }
-                    (SymbolValue::Unknown, SymbolValue::Value(r)) => {
-                        let new_l = Self::resolve_value(true_lhs);
-                        (new_l, r)
-                    }
-                    (SymbolValue::Unknown, SymbolValue::Unknown) => {
-                        self.stack.push(SymbolValue::Unknown);
-                        return None;
-                    }
-                };
-                if !matches!(compare_value(true_lhs, true_rhs), Ordering::Less) {
-                    let lt = new_l.ge(&new_r);
-                    let int = lt.ite(&Int::from_u64(1), &Int::from_u64(0));
-                    self.stack.push(SymbolValue::Value(int));
-                    return Some(lt);
-                } else {
-                    // not less than
-                    let nlt = new_l.ge(&new_r).not();
-                    let int = nlt.ite(&Int::from_u64(1), &Int::from_u64(0));
-                    self.stack.push(SymbolValue::Value(int));
-                    return Some(nlt);
-                }
-            }
-            Bytecode::VecPack(_, len) => {
-                for _ in 0..*len {
-                    self.stack.pop();
-                }
-                self.stack.push(SymbolValue::Unknown); // represent the vector
-                                                       // as unknown
-            }
-            Bytecode::VecUnpack(_, len) => {
-                self.stack.pop();
-                for _ in 0..*len {
-                    self.stack.push(SymbolValue::Unknown); // represent each
-                                                           // element as unknown
-                }
-            }
-            Bytecode::VecSwap(_) => {
-                self.stack.pop();
-                self.stack.pop();
-                self.stack.pop();
-            }
-            Bytecode::Pack(_) | Bytecode::PackGeneric(_) => {
-                if let Some(extra_info) = extra {
-                    match extra_info {
-                        MoveTracerExtraInfo::Pack(count) | MoveTracerExtraInfo::PackGeneric(count) => {
-                            for _ in 0..count {
-                                self.stack.pop();
-                            }
-                            self.stack.push(SymbolValue::Unknown); // represent the struct as unknown
-                        }
-                        _ => {}
-                    }
-                } else {
-                    warn!("Missing extra info for pack at pc {}", pc);
-                    self.disable = true;
-                    return None;
-                }
-            }
-            Bytecode::Unpack(_) | Bytecode::UnpackGeneric(_) => {
-                self.stack.pop();
-                if let Some(extra_info) = extra {
-                    match extra_info {
-                        MoveTracerExtraInfo::Unpack(count) | MoveTracerExtraInfo::UnpackGeneric(count) => {
-                            for _ in 0..count {
-                                self.stack.push(SymbolValue::Unknown); // represent each field as unknown
-                            }
-                        }
-                        _ => {}
-                    }
-                } else {
-                    warn!("Missing extra info for unpack at pc {}", pc);
-                    self.disable = true;
-                    return None;
-                }
-            }
-            Bytecode::PackVariant(_) | Bytecode::PackVariantGeneric(_) => {
-                if let Some(extra_info) = extra {
-                    match extra_info {
-                        MoveTracerExtraInfo::PackVariant(count) | MoveTracerExtraInfo::PackVariantGeneric(count) => {
-                            for _ in 0..count {
-                                self.stack.pop();
-                            }
-                            self.stack.push(SymbolValue::Unknown); // represent the enum as unknown
-                        }
-                        _ => {}
-                    }
-                } else {
-                    warn!("Missing extra info for pack variant at pc {}", pc);
-                    self.disable = true;
-                    return None;
-                }
-            }
-            Bytecode::UnpackVariant(_) | Bytecode::UnpackVariantGeneric(_) => {
-                self.stack.pop();
-                if let Some(extra_info) = extra {
-                    match extra_info {
-                        MoveTracerExtraInfo::UnpackVariant(count) |
-                        MoveTracerExtraInfo::UnpackVariantGeneric(count) => {
-                            for _ in 0..count {
-                                self.stack.push(SymbolValue::Unknown); // represent each field as unknown
-                            }
-                        }
-                        _ => {}
-                    }
-                } else {
-                    warn!("Missing extra info for unpack variant at pc {}", pc);
-                    self.disable = true;
-                    return None;
-                }
-            }
-            _ => {}
+        self.deadline = self.budget.timeout.map(|timeout| Instant::now() + timeout);
+    }
+
+    /// Abort the current VM run by unwinding with `BudgetExhausted` once the
+    /// instruction or wall-clock budget is used up.
+    fn enforce_budget(&mut self) {
+        self.instructions += 1;
+        let over_instructions = self.budget.max_instructions.is_some_and(|max| self.instructions > max);
+        let over_time = self.instructions % DEADLINE_CHECK_INTERVAL == 0 &&
+            self.deadline.is_some_and(|deadline| Instant::now() >= deadline);
+        if over_instructions || over_time {
+            std::panic::resume_unwind(Box::new(BudgetExhausted));
         }
-        None
     }
 
-    fn resolve_arg(
-        cmd_index: usize,
-        param_index: usize,
-        ty: &aptos_move_vm_types::loaded_data::runtime_types::Type,
-    ) -> SymbolValue {
-        use aptos_move_vm_types::loaded_data::runtime_types::Type;
-        match ty {
-            Type::Bool | Type::U8 | Type::U16 | Type::U32 | Type::U64 | Type::U128 | Type::U256 => {
-                let name = format!("{}.{}", cmd_index, param_index);
-                let int = Int::new_const(name);
-                SymbolValue::Value(int)
-            }
-            _ => SymbolValue::Unknown,
-        }
+    /// Record a structured trace of the following runs. It slows execution
+    /// down, so is meant for replaying single inputs.
+    pub fn set_trace_capture(&mut self, enabled: bool) {
+        self.trace = enabled.then(ExecutionTrace::default);
     }
 
-    fn resolve_value(value: &Value) -> Int {
-        Self::resolve_value_impl(&value.0)
+    pub fn take_trace(&mut self) -> Option<ExecutionTrace> {
+        self.trace.as_mut().map(std::mem::take)
     }
 
-    fn resolve_value_impl(value_impl: &ValueImpl) -> Int {
-        let primitive_value = extract_primitive_value(value_impl);
-        match primitive_value {
-            ValueImpl::Bool(b) => {
-                let int_val = if b { 1 } else { 0 };
-                Int::from_u64(int_val)
-            }
-            ValueImpl::U8(u) => Int::from_u64(u as u64),
-            ValueImpl::U16(u) => Int::from_u64(u as u64),
-            ValueImpl::U32(u) => Int::from_u64(u as u64),
-            ValueImpl::U64(u) => Int::from_u64(u),
-            ValueImpl::U128(u) => Int::from_str(&u.to_string()).unwrap(),
-            ValueImpl::U256(u) => Int::from_str(&u.to_string()).unwrap(),
-            _ => panic!("Unsupported value type {:?} for symbolic execution", value_impl),
-        }
+    pub fn take_issues(&mut self) -> Vec<RuntimeIssue> {
+        std::mem::take(&mut self.runtime_issues)
     }
 
-    #[inline]
-    fn max_u_bits(n: u32) -> Int {
-        if n <= 63 {
-            Int::from_u64((1u64 << n) - 1)
-        } else {
-            let two_pow_n_minus_1 = match n {
-                64 => "18446744073709551615",
-                128 => "340282366920938463463374607431768211455",
-                256 => "115792089237316195423570985008687907853269984665640564039457584007913129639935",
-                _ => unreachable!("add more cases or compute big ints as needed"),
-            };
-            Int::from_str(two_pow_n_minus_1).unwrap()
-        }
+    pub fn guidance(&self) -> &PathGuidance {
+        &self.guidance
     }
-}
 
-impl ConcolicState {
-    fn record_issue(&mut self, issue: RuntimeIssue) {
-        self.runtime_issues.push(issue);
+    pub fn guidance_mut(&mut self) -> &mut PathGuidance {
+        &mut self.guidance
     }
 
-    fn check_precision_loss(&mut self, module: &str, function: &str, pc: u16, lhs: &Int, rhs: &Int) {
-        if contains_division(lhs) || contains_division(rhs) {
-            let message = format!("Precision loss detected at {}::{} (pc {})", module, function, pc);
-            self.record_issue(RuntimeIssue::new(
-                RuntimeIssueKind::PrecisionLoss,
-                module.to_string(),
-                function.to_string(),
+    /// Let the detectors see the abort the last execution ended with.
+    pub fn on_abort(&mut self, location: &AbortLocation, code: u64) {
+        self.guidance.on_abort(location, code, self.frames.last());
+        let current = self.frames.last().map(|(module, function)| {
+            (
+                module.as_str(),
+                function.as_str(),
+                self.locations.last().map_or(0, |&(_, pc, _)| pc),
+            )
+        });
+        if let Some((module, function, pc)) = current {
+            let callers = self.frames[..self.frames.len() - 1]
+                .iter()
+                .zip(&self.frame_pcs)
+                .map(|((module, function), pc)| StackFrame {
+                    module: module.clone(),
+                    function: function.clone(),
+                    pc: pc.unwrap_or(0),
+                })
+                .collect();
+            let site = AbortSite {
+                module: module.to_string(),
+                function: function.to_string(),
                 pc,
-                message,
-            ));
+                code,
+                callers,
+            };
+            if let Some(trace) = self.trace.as_mut() {
+                trace.abort = Some(site.clone());
+            }
+            self.abort_site = Some(site);
+        }
+        for detector in self.builtin_detectors.iter_mut().chain(&mut self.detectors) {
+            detector.on_abort(location, code, current, &mut self.runtime_issues);
         }
     }
 
-    fn check_bool_judgement(
-        &mut self,
-        instruction: &Bytecode,
-        module: &str,
-        function: &str,
-        pc: u16,
-        operand_stack: &[Value],
-    ) {
-        if self.stack.len() < 2 {
-            return;
-        }
-        let lhs = &self.stack[self.stack.len() - 2];
-        let rhs = &self.stack[self.stack.len() - 1];
-        let mut loss = matches!(
-            (lhs, rhs),
-            (SymbolValue::Value(l), SymbolValue::Value(r))
-                if int_has_variable(l) == Some(false) && int_has_variable(r) == Some(false)
-        );
+    /// Module, function and pc of the instruction executed last, if any.
+    pub fn current_location(&self) -> Option<(&str, &str, u16)> {
+        let (module, function) = self.frames.last()?;
+        let pc = self.locations.last().map(|&(_, pc, _)| pc).unwrap_or(0);
+        Some((module, function, pc))
+    }
 
-        if matches!(instruction, Bytecode::Eq | Bytecode::Neq) {
-            let bool_const = operand_stack.last().and_then(value_is_bool);
-            if bool_const.is_some() &&
-                (matches!(lhs, SymbolValue::Value(l) if int_has_variable(l) == Some(false)) ||
-                    matches!(rhs, SymbolValue::Value(r) if int_has_variable(r) == Some(false)))
-            {
-                loss = true;
-            }
-        }
+    /// Where the last execution aborted, if it did inside a traced frame.
+    pub fn take_abort_site(&mut self) -> Option<AbortSite> {
+        self.abort_site.take()
+    }
 
-        if loss {
-            let message = format!("Unnecessary bool judgement at {}::{} (pc {})", module, function, pc);
-            self.record_issue(RuntimeIssue::new(
-                RuntimeIssueKind::BoolJudgement,
-                module.to_string(),
-                function.to_string(),
-                pc,
-                message,
-            ));
-        }
+    pub fn take_shift_losses(&mut self) -> Vec<ShiftLoss> {
+        std::mem::take(&mut self.shift_losses)
     }
 
-    fn check_infinite_loop(&mut self, function_key: &str, pc: u16, module: &str, function: &str) {
-        if let Some(SymbolValue::Value(cond)) = self.stack.last() {
-            let cond_hash = hash_string(&cond.to_string());
-            let entry = self
-                .branch_counts
-                .entry(function_key.to_string())
-                .or_default()
-                .entry(pc)
-                .or_default();
-            if entry.last_hash != Some(cond_hash) {
-                entry.last_hash = Some(cond_hash);
-                entry.count = 1;
-            } else {
-                entry.count += 1;
-                if entry.count >= INFINITE_LOOP_THRESHOLD {
-                    entry.count = 0;
-                    let message = format!("Potential infinite loop at {}::{} (pc {})", module, function, pc);
-                    self.record_issue(RuntimeIssue::new(
-                        RuntimeIssueKind::InfiniteLoop,
-                        module.to_string(),
-                        function.to_string(),
-                        pc,
-                        message,
-                    ));
-                }
-            }
-        }
+    pub fn take_call_returns(&mut self) -> Vec<Vec<Option<U256>>> {
+        std::mem::take(&mut self.call_returns)
     }
 
-    pub fn take_issues(&mut self) -> Vec<RuntimeIssue> {
-        std::mem::take(&mut self.runtime_issues)
+    pub fn take_locations(&mut self) -> Vec<TracedLocation> {
+        std::mem::take(&mut self.locations)
     }
-}
 
-pub struct SymbolicMoveTracer {
-    state: ConcolicState,
-}
+    /// Times each conditional branch of the last execution went false and
+    /// true.
+    pub fn take_branch_outcomes(&mut self) -> HashMap<BranchKey, [u64; 2]> {
+        std::mem::take(&mut self.branch_outcomes)
+    }
 
-impl SymbolicMoveTracer {
-    pub fn new() -> Self {
-        Self {
-            state: ConcolicState::new(),
-        }
+    /// Global storage the last execution read and wrote, in order.
+    pub fn take_global_accesses(&mut self) -> Vec<GlobalAccess> {
+        std::mem::take(&mut self.global_accesses)
     }
 
-    pub fn state(&self) -> &ConcolicState {
-        &self.state
+    /// (module, function) of a function hash the tracer has seen.
+    pub fn function_name(&self, function: u64) -> Option<&(String, String)> {
+        self.function_names.get(&function)
     }
 
-    pub fn state_mut(&mut self) -> &mut ConcolicState {
-        &mut self.state
+    pub fn take_branch_distances(&mut self) -> HashMap<(u64, u16), u64> {
+        std::mem::take(&mut self.branch_distances)
     }
 
-    pub fn reset(&mut self) {
-        self.state = ConcolicState::new();
+    /// Record a `Shl` about to drop set high bits of its operand. Shifts by
+    /// the operand's width or more abort instead.
+    fn record_shift_loss(&mut self, ctx: &MoveTracerInstructionContext<'_>) {
+        let values = ctx.operand_stack.values();
+        if values.len() < 2 {
+            return;
+        }
+        let operand = &values[values.len() - 2];
+        let (Ok(width), Ok(value), Ok(amount)) = (
+            value_bitwidth(operand),
+            value_to_u256(operand),
+            value_to_u256(&values[values.len() - 1]),
+        ) else {
+            return;
+        };
+        let amount = amount.unchecked_as_u32();
+        if amount >= width {
+            return;
+        }
+        // The high `amount` bits are shifted out; count those set
+        let mut shifted_out = value.checked_shr(width - amount).unwrap_or(U256::zero());
+        let mut lost_bits = 0;
+        while shifted_out != U256::zero() {
+            if shifted_out & U256::one() == U256::one() {
+                lost_bits += 1;
+            }
+            shifted_out = shifted_out.checked_shr(1).unwrap_or(U256::zero());
+        }
+        let Some((module, function)) = self.frames.last() else {
+            return;
+        };
+        if lost_bits > 0 {
+            self.shift_losses.push(ShiftLoss {
+                module: module.clone(),
+                function: function.clone(),
+                pc: ctx.pc,
+                width,
+                amount,
+                lost_bits,
+            });
+        }
     }
 
-    pub fn take_issues(&mut self) -> Vec<RuntimeIssue> {
-        self.state.take_issues()
+    /// Record the resource a global storage instruction reads or writes,
+    /// with the address on top of the stack, or of the signer below the
+    /// value for `move_to`.
+    fn record_global_access(&mut self, ctx: &MoveTracerInstructionContext<'_>) {
+        let Some((resource, op)) = global_op(ctx.instruction) else {
+            return;
+        };
+        let values = ctx.operand_stack.values();
+        let address = match op {
+            GlobalOp::MoveTo => values.len().checked_sub(2).and_then(|idx| signer_address(&values[idx])),
+            _ => match values.last().map(|value| &value.0) {
+                Some(ValueImpl::Address(address)) => Some(AccountAddress::clone(address)),
+                _ => None,
+            },
+        };
+        self.global_accesses.push(GlobalAccess {
+            call: self.sequence_calls.saturating_sub(1),
+            module: ctx.frame.function.module_or_script_id().clone(),
+            resource,
+            address,
+            op,
+        });
+    }
+
+    fn record_branch_distance(&mut self, site: (u64, u16), ctx: &MoveTracerInstructionContext<'_>) {
+        let values = ctx.operand_stack.values();
+        if values.len() < 2 {
+            return;
+        }
+        let (Some(lhs), Some(rhs)) = (
+            primitive_to_u256(&values[values.len() - 2]),
+            primitive_to_u256(&values[values.len() - 1]),
+        ) else {
+            return;
+        };
+        let diff = if lhs > rhs { lhs - rhs } else { rhs - lhs };
+        let distance = if diff > U256::from(u64::MAX) {
+            u64::MAX
+        } else {
+            diff.unchecked_as_u64()
+        };
+        let entry = self.branch_distances.entry(site).or_insert(u64::MAX);
+        *entry = (*entry).min(distance);
+    }
+
+    fn run_detectors(&mut self, ctx: &MoveTracerInstructionContext<'_>) {
+        let Some((module, function)) = self.frames.last() else {
+            return;
+        };
+        let operand_stack = ctx.operand_stack.values();
+        let symbolic_stack = if self.state.stack.len() == operand_stack.len() {
+            &self.state.stack[..]
+        } else {
+            &[]
+        };
+        let taint_stack = if self.taint.stack().len() == operand_stack.len() {
+            self.taint.stack()
+        } else {
+            &[]
+        };
+        let detector_ctx = InstructionContext {
+            module,
+            function,
+            pc: ctx.pc,
+            instruction: ctx.instruction,
+            operand_stack,
+            symbolic_stack,
+            taint_stack,
+        };
+        for detector in self.builtin_detectors.iter_mut().chain(&mut self.detectors) {
+            detector.on_instruction(&detector_ctx, &mut self.runtime_issues);
+        }
     }
 }
 
@@ -1190,23 +695,135 @@ impl Default for SymbolicMoveTracer {
 
 impl MoveTracer for SymbolicMoveTracer {
     fn open_frame(&mut self, frame: &MoveTracerFrameInfo<'_>) {
+        let module = format_module_name(frame.function.module_or_script_id());
+        let function = frame.function.name().to_string();
+        for detector in self.builtin_detectors.iter_mut().chain(&mut self.detectors) {
+            detector.on_frame_open(&module, &function);
+        }
+        if let Some(trace) = self.trace.as_mut() {
+            trace.events.push(TraceEvent::OpenFrame {
+                module: module.clone(),
+                function: function.clone(),
+            });
+        }
+        self.function_names
+            .entry(function_hash(
+                frame.function.module_or_script_id(),
+                frame.function.name(),
+            ))
+            .or_insert_with(|| (module.clone(), function.clone()));
+        if self.frames.len() == 1 {
+            self.sequence_calls += 1;
+        }
+        self.frames.push((module, function));
+        self.frame_pcs.push(None);
         self.state.open_frame(frame);
+        self.guidance.open_frame(frame, self.frames.len(), &mut self.state);
+        self.taint.open_frame(frame);
     }
 
-    fn close_frame(&mut self, _frame: &MoveTracerFrameInfo<'_>) {
-        self.state.close_frame();
+    fn close_frame(&mut self, frame: &MoveTracerFrameInfo<'_>) {
+        if let Some((module, function)) = self.frames.pop() {
+            for detector in self.builtin_detectors.iter_mut().chain(&mut self.detectors) {
+                detector.on_frame_close(&module, &function);
+            }
+            if let Some(trace) = self.trace.as_mut() {
+                trace.events.push(TraceEvent::CloseFrame { module, function });
+            }
+        }
+        self.frame_pcs.pop();
+        self.state.close_frame(frame);
+        self.taint.close_frame(frame);
     }
 
     fn before_instruction(&mut self, instruction: &MoveTracerInstructionContext<'_>) {
+        self.enforce_budget();
+        let function = &instruction.frame.function;
+        let site = (
+            function_hash(function.module_or_script_id(), function.name()),
+            instruction.pc,
+        );
+        let prev_pc = self.frame_pcs.last_mut().and_then(|last| last.replace(instruction.pc));
+        self.locations.push((site.0, site.1, prev_pc));
+        if let Some(trace) = self.trace.as_mut() {
+            let values = instruction.operand_stack.values();
+            trace.events.push(TraceEvent::Instruction {
+                pc: instruction.pc,
+                instruction: format!("{:?}", instruction.instruction),
+                operands: values[values.len().saturating_sub(TRACE_OPERANDS)..]
+                    .iter()
+                    .map(summarize_operand)
+                    .collect(),
+            });
+        }
+        if matches!(
+            instruction.instruction,
+            Bytecode::Eq | Bytecode::Neq | Bytecode::Lt | Bytecode::Le | Bytecode::Gt | Bytecode::Ge
+        ) {
+            self.record_branch_distance(site, instruction);
+        }
+        if matches!(instruction.instruction, Bytecode::Shl) {
+            self.record_shift_loss(instruction);
+        }
+        self.record_global_access(instruction);
+        if matches!(instruction.instruction, Bytecode::Ret) && self.frames.len() == 2 {
+            let values = instruction.operand_stack.values();
+            self.call_returns.push(values.iter().map(primitive_to_u256).collect());
+        }
+        if matches!(instruction.instruction, Bytecode::BrTrue(_) | Bytecode::BrFalse(_)) {
+            if let Some(condition) = instruction.operand_stack.values().last().and_then(value_is_bool) {
+                self.branch_outcomes.entry(site).or_insert([0, 0])[condition as usize] += 1;
+            }
+        }
+        self.guidance
+            .before_instruction(site, instruction, &self.state, self.frames.last());
+        self.run_detectors(instruction);
+        self.taint.before_instruction(instruction);
         self.state.before_instruction(instruction);
     }
 }
 
-fn format_module_name(module_id: &ModuleId) -> String {
+pub(crate) fn format_module_name(module_id: &ModuleId) -> String {
     format!("{}::{}", module_id.address().to_hex_literal(), module_id.name())
 }
 
-fn value_is_bool(value: &Value) -> Option<bool> {
+/// Concrete value of a non-reference primitive, without panicking on anything
+/// else.
+pub(crate) fn primitive_to_u256(value: &Value) -> Option<U256> {
+    match &value.0 {
+        ValueImpl::Bool(b) => Some(if *b { U256::one() } else { U256::zero() }),
+        ValueImpl::U8(u) => Some(U256::from(*u)),
+        ValueImpl::U16(u) => Some(U256::from(*u)),
+        ValueImpl::U32(u) => Some(U256::from(*u)),
+        ValueImpl::U64(u) => Some(U256::from(*u)),
+        ValueImpl::U128(u) => Some(U256::from(*u)),
+        ValueImpl::U256(u) => Some(*u),
+        _ => None,
+    }
+}
+
+/// Short form of an operand for traces: primitives and addresses as is.
+fn summarize_operand(value: &Value) -> String {
+    match &value.0 {
+        ValueImpl::Address(address) => address.to_hex_literal(),
+        _ => primitive_to_u256(value).map_or_else(|| "_".to_string(), |value| value.to_string()),
+    }
+}
+
+/// Address of a `&signer` operand: the address field of the signer struct
+/// it points to.
+fn signer_address(value: &Value) -> Option<AccountAddress> {
+    let ValueImpl::ContainerRef(ContainerRef::Local(Container::Struct(fields))) = &value.0 else {
+        return None;
+    };
+    let fields = fields.borrow();
+    fields.iter().find_map(|field| match field {
+        ValueImpl::Address(address) => Some(AccountAddress::clone(address)),
+        _ => None,
+    })
+}
+
+pub(crate) fn value_is_bool(value: &Value) -> Option<bool> {
     if let ValueImpl::Bool(b) = &value.0 {
         Some(*b)
     } else {
@@ -1214,47 +831,88 @@ fn value_is_bool(value: &Value) -> Option<bool> {
     }
 }
 
-fn hash_string(value: &str) -> u64 {
+pub(crate) fn hash_string(value: &str) -> u64 {
     let mut hasher = std::collections::hash_map::DefaultHasher::new();
     value.hash(&mut hasher);
     hasher.finish()
 }
 
-fn contains_division(expr: &Int) -> bool {
-    let mut stack = vec![Dynamic::from(expr.clone())];
-    let mut count = 0;
-    while let Some(node) = stack.pop() {
-        count += 1;
-        if count > 10_000 {
-            break;
+/// Stand-ins for the Z3-backed `symbolic` module when built without the
+/// `concolic` feature: nothing is tracked symbolically and every value is
+/// left to the concrete operand stack.
+#[cfg(not(feature = "concolic"))]
+mod inert {
+    use std::fmt;
+
+    use aptos_move_core_types::vm_status::AbortLocation;
+    use aptos_move_vm_runtime::{MoveTracerFrameInfo, MoveTracerInstructionContext};
+
+    use super::SymbolValue;
+    use crate::branches::BranchKey;
+
+    /// Stands in for z3's integer terms. It has no values, so
+    /// `SymbolValue::Value` never occurs and the detectors reasoning about
+    /// symbols stay quiet.
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub enum Int {}
+
+    impl fmt::Display for Int {
+        fn fmt(&self, _: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match *self {}
         }
-        if let Ok(decl) = node.safe_decl() {
-            match decl.kind() {
-                DeclKind::DIV | DeclKind::IDIV => return true,
-                _ => {}
-            }
+    }
+
+    #[derive(Clone, Debug, Default)]
+    pub struct ConcolicState {
+        pub stack: Vec<SymbolValue>,
+    }
+
+    impl ConcolicState {
+        pub fn new() -> Self {
+            Self::default()
         }
-        stack.extend(node.children());
+
+        pub fn clear(&mut self) {}
+
+        pub fn open_frame(&mut self, _frame: &MoveTracerFrameInfo<'_>) {}
+
+        pub fn close_frame(&mut self, _frame: &MoveTracerFrameInfo<'_>) {}
+
+        pub fn before_instruction(&mut self, _ctx: &MoveTracerInstructionContext<'_>) {}
     }
-    false
-}
 
-fn int_has_variable(expr: &Int) -> Option<bool> {
-    let mut stack = vec![Dynamic::from(expr.clone())];
-    let mut count = 0;
-    while let Some(node) = stack.pop() {
-        count += 1;
-        if count > 10_000 {
-            return None;
+    /// No path constraints are collected, so no solver queries are made.
+    #[derive(Debug, Default)]
+    pub struct PathGuidance;
+
+    impl PathGuidance {
+        pub(crate) fn reset(&mut self) {}
+
+        pub(crate) fn open_frame(
+            &mut self,
+            _frame: &MoveTracerFrameInfo<'_>,
+            _depth: usize,
+            _state: &mut ConcolicState,
+        ) {
         }
-        if node.is_const() {
-            if let Ok(decl) = node.safe_decl() {
-                if decl.kind() == DeclKind::UNINTERPRETED {
-                    return Some(true);
-                }
-            }
+
+        pub(crate) fn on_abort(&mut self, _location: &AbortLocation, _code: u64, _frame: Option<&(String, String)>) {}
+
+        pub(crate) fn before_instruction(
+            &mut self,
+            _site: BranchKey,
+            _ctx: &MoveTracerInstructionContext<'_>,
+            _state: &ConcolicState,
+            _frame: Option<&(String, String)>,
+        ) {
         }
-        stack.extend(node.children());
     }
-    Some(false)
+
+    pub(crate) fn contains_division(expr: &Int) -> bool {
+        match *expr {}
+    }
+
+    pub(crate) fn int_has_variable(expr: &Int) -> Option<bool> {
+        match *expr {}
+    }
 }
@@ -0,0 +1,299 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use serde::Deserialize;
+
+use crate::concolic::Severity;
+use crate::executor::Backend;
+use crate::scheduler::Fairness;
+use crate::sender::SenderStrategy;
+
+/// Campaign settings read from a TOML file. Every field is optional, and
+/// flags given on the command line take precedence, lists included:
+///
+/// ```toml
+/// [target]
+/// modules_dir = "build/pool/bytecode_modules"
+/// state_fixture = "fixture.toml"
+/// admin = "0xcafe"
+/// watch = true
+/// arg_constraints = ["pool::withdraw.0 in 1..=1000000", "pool::transfer.0 pool"]
+///
+/// [campaign]
+/// timeout = 3600
+/// plateau_seconds = 600
+///
+/// [scheduler]
+/// fairness = "budgeted"
+/// min_function_share = 0.25
+///
+/// [corpus]
+/// seeds_dir = "seeds"
+/// solutions_dir = "solutions"
+/// bisect_solutions = true
+/// transactions = "mainnet_txns.json"
+/// sync_dir = "/mnt/shared/sync"
+///
+/// [execution]
+/// timeout_ms = 100
+/// max_instructions = 1000000
+/// sender = "rotate"
+/// pinned_senders = ["pool::set_fee=0xcafe"]
+///
+/// [mutator]
+/// max_stack_pow = 6
+/// max_sequence_len = 8
+/// crossover_rate = 4
+///
+/// [detectors]
+/// disabled = ["UnusedConstant", "UnusedStruct"]
+/// runtime_disabled = ["BoolJudgement"]
+/// infinite_loop_threshold = 5000
+/// unbounded_loop_instructions = 200000
+/// severities = { PrecisionLoss = "high" }
+/// suppress = ["pool::swap::PrecisionLoss", "pool::*::UnusedConstant"]
+///
+/// [objectives]
+/// allow_aborts = ["pool::1"]
+/// conserve_coins = ["0x1::aptos_coin::AptosCoin"]
+/// return_properties = ["pool::withdraw.0 == pool::deposit.0"]
+/// round_trips = ["pool::deposit -> pool::withdraw"]
+/// asset_issuers = ["pool::claim_rewards"]
+/// signer_ownership = true
+/// resource_leaks = true
+/// access_control = true
+/// view_functions = true
+/// monotonic_views = ["pool::total_supply >="]
+/// ```
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FuzzerConfig {
+    pub target: TargetConfig,
+    pub campaign: CampaignConfig,
+    pub scheduler: SchedulerConfig,
+    pub corpus: CorpusConfig,
+    pub execution: ExecutionConfig,
+    pub mutator: MutatorConfig,
+    pub detectors: DetectorConfig,
+    pub objectives: ObjectiveConfig,
+}
+
+/// What to fuzz and the state it starts from.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TargetConfig {
+    pub modules_dir: Option<PathBuf>,
+    /// Move source package to compile instead of `modules_dir`
+    pub package_path: Option<PathBuf>,
+    /// `name=address` assignments passed to the compiler
+    pub named_addresses: Vec<String>,
+    pub state_fixture: Option<PathBuf>,
+    pub fork_url: Option<String>,
+    pub fork_version: Option<u64>,
+    pub fork_cache_dir: Option<PathBuf>,
+    /// `0xADDR::module::function` run instead of the detected initializers
+    pub initializer: Option<String>,
+    pub initializer_args: Vec<String>,
+    pub admin: Option<String>,
+    pub run_initializers: Option<bool>,
+    /// Also fuzz public functions found to always abort in the initial state
+    pub fuzz_unreachable: Option<bool>,
+    /// Reload the modules, or rebuild the package, whenever they change
+    pub watch: Option<bool>,
+    /// Compiled modules of a new version of the targets, published as an
+    /// upgrade after `upgrade_after` seconds
+    pub upgrade_to: Option<PathBuf>,
+    pub upgrade_after: Option<u64>,
+    /// Values callers pass as arguments of the target functions, e.g.
+    /// `"pool::withdraw.0 in 1..=1000000"`, see `ArgConstraint`
+    pub arg_constraints: Vec<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CampaignConfig {
+    /// Seconds to fuzz for, 0 for no limit
+    pub timeout: Option<u64>,
+    pub map_size: Option<usize>,
+    pub stop_after_objectives: Option<usize>,
+    pub stop_on_first_crash: Option<bool>,
+    /// Seconds without new edges or solutions before stopping
+    pub plateau_seconds: Option<u64>,
+    pub rng_seed: Option<u64>,
+    /// Respawn the fuzzer process when it panics, resuming from the last
+    /// checkpoint
+    pub restart_on_crash: Option<bool>,
+}
+
+/// How corpus entries are picked, see `FairScheduler`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SchedulerConfig {
+    /// How corpus entries are scheduled across the target functions:
+    /// `queue`, `round-robin` or `budgeted`
+    pub fairness: Option<Fairness>,
+    /// Under `budgeted`, the fraction of an even split of the executions a
+    /// function may fall below before it is scheduled first
+    pub min_function_share: Option<f64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CorpusConfig {
+    /// Directory of JSON-encoded inputs added to the initial corpus
+    pub seeds_dir: Option<PathBuf>,
+    /// Directory solutions are written to as JSON when fuzzing stops
+    pub solutions_dir: Option<PathBuf>,
+    /// Bisect each solution for the calls and arguments it needs before
+    /// reporting it
+    pub bisect_solutions: Option<bool>,
+    /// Transaction JSON from the REST API whose entry function calls into
+    /// the target modules are added as seeds
+    pub transactions: Option<PathBuf>,
+    /// Directory to exchange corpus entries through with other instances
+    pub sync_dir: Option<PathBuf>,
+    /// Subdirectory of `sync_dir` this instance exports to, unique per
+    /// instance; defaults to one derived from the RNG seed
+    pub sync_name: Option<String>,
+    /// Seconds between checkpoints of the campaign to `checkpoint_dir`
+    pub checkpoint_interval: Option<u64>,
+    /// Directory checkpoints are written to and resumed from
+    pub checkpoint_dir: Option<PathBuf>,
+    /// Directory inputs the fuzzer itself panicked on are quarantined to
+    pub fuzzer_crashes_dir: Option<PathBuf>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ExecutionConfig {
+    /// `aptos` or `move-vm`
+    pub backend: Option<Backend>,
+    pub timeout_ms: Option<u64>,
+    pub max_instructions: Option<u64>,
+    pub solver_timeout_ms: Option<u64>,
+    pub solver_queries_per_iteration: Option<usize>,
+    /// One-sided branches from the top of the worklist to ask the solver to
+    /// flip, 0 for none
+    pub solve_branches: Option<usize>,
+    pub differential: Option<bool>,
+    pub reference_enable_features: Vec<u64>,
+    pub reference_disable_features: Vec<u64>,
+    /// `fixed`, `deployer`, `random` or `rotate`
+    pub sender: Option<SenderStrategy>,
+    /// `[<address>::]<module>::<function>=<sender>` entries
+    pub pinned_senders: Vec<String>,
+}
+
+/// Havoc stack size, vector and sequence length caps and how often (one in N)
+/// each mutation is tried, see `MutationRates`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MutatorConfig {
+    /// Mutate the bytecode of script inputs, see `--fuzz-vm`
+    pub fuzz_vm: Option<bool>,
+    /// Fuzz publishing mutants of the target modules, see `--fuzz-publish`
+    pub fuzz_publish: Option<bool>,
+    pub max_stack_pow: Option<u32>,
+    pub max_vector_len: Option<usize>,
+    pub max_sequence_len: Option<usize>,
+    pub block_context_rate: Option<u64>,
+    pub crossover_rate: Option<u64>,
+    pub interesting_value_rate: Option<u64>,
+    pub sender_rate: Option<u64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DetectorConfig {
+    /// Run static analysis before fuzzing (default: true)
+    pub enabled: Option<bool>,
+    /// Analyze the targets together with every deployed module, flagging
+    /// what they expose outside their package
+    pub whole_program: Option<bool>,
+    /// Finding kinds not to report, e.g. `UnusedConstant`
+    pub disabled: Vec<String>,
+    /// Runtime detectors to turn off, e.g. `BoolJudgement`
+    pub runtime_disabled: Vec<String>,
+    pub infinite_loop_threshold: Option<usize>,
+    /// Instructions a loop may run in one execution before it is reported,
+    /// at most half of `execution.max_instructions`
+    pub unbounded_loop_instructions: Option<u64>,
+    /// Report inputs with runtime issues as solutions (default: true)
+    pub runtime_issues_objective: Option<bool>,
+    /// Least severity of a runtime issue reported as a solution: `info`,
+    /// `low`, `medium` or `high` (default)
+    pub runtime_objective_severity: Option<Severity>,
+    /// Severity of static finding and runtime issue kinds, overriding their
+    /// defaults, e.g. `{ UnusedConstant = "low" }`
+    pub severities: BTreeMap<String, Severity>,
+    /// Known findings not to report, as
+    /// `[<address>::]<module>::<function>::<kind>`, `*` for any function
+    pub suppress: Vec<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ObjectiveConfig {
+    /// Report executions that exhaust their budget
+    pub budget: Option<bool>,
+    /// Call `invariant_*`/`check_*` functions after each execution
+    pub invariant_functions: Option<bool>,
+    /// Expected aborts, as `[<address>::]<module>::<code>`
+    pub allow_aborts: Vec<String>,
+    pub conserve_coins: Vec<String>,
+    pub never_delete: Vec<String>,
+    /// Properties of sequence call returns, e.g.
+    /// `"pool::withdraw.0 == pool::deposit.0"`
+    pub return_properties: Vec<String>,
+    /// Function pairs that undo each other, e.g.
+    /// `"pool::deposit -> pool::withdraw"`
+    pub round_trips: Vec<String>,
+    /// Report transactions creating or destroying coins or fungible assets
+    /// outside mint and burn functions; on by default
+    pub asset_conservation: Option<bool>,
+    /// Functions allowed to mint and burn besides those named so, e.g.
+    /// `"pool::claim_rewards"`
+    pub asset_issuers: Vec<String>,
+    /// Report transactions that change resources another account owns
+    pub signer_ownership: Option<bool>,
+    /// Report sequences orphaning resources or creating them twice
+    pub resource_leaks: Option<bool>,
+    /// Report admin transactions an unprivileged sender can replay with the
+    /// same effect on admin-owned resources
+    pub access_control: Option<bool>,
+    /// Report view functions that start aborting after a transaction
+    pub view_functions: Option<bool>,
+    /// How view function results may change across a transaction, e.g.
+    /// `"pool::total_supply >="`
+    pub monotonic_views: Vec<String>,
+}
+
+impl FuzzerConfig {
+    /// Parse `path`; relative paths in it are resolved against its directory.
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let contents = fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+        let mut config: Self =
+            toml::from_str(&contents).with_context(|| format!("invalid config {}", path.display()))?;
+        let base = path.parent().unwrap_or_else(|| Path::new("."));
+        for path in [
+            &mut config.target.modules_dir,
+            &mut config.target.package_path,
+            &mut config.target.state_fixture,
+            &mut config.target.fork_cache_dir,
+            &mut config.corpus.seeds_dir,
+            &mut config.corpus.solutions_dir,
+            &mut config.corpus.transactions,
+            &mut config.corpus.sync_dir,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            if path.is_relative() {
+                *path = base.join(&*path);
+            }
+        }
+        Ok(config)
+    }
+}
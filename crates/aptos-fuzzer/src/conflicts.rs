@@ -0,0 +1,493 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+
+use aptos_move_binary_format::access::ModuleAccess;
+use aptos_move_binary_format::file_format::Bytecode;
+use aptos_move_binary_format::CompiledModule;
+use aptos_move_core_types::account_address::AccountAddress;
+use aptos_move_core_types::language_storage::ModuleId;
+use bytes::Bytes;
+use serde::{Deserialize, Serialize};
+
+/// The struct a global storage instruction names, as indexed in its module
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ResourceRef {
+    Definition(u16),
+    Instantiation(u16),
+}
+
+/// A global storage access traced in one execution.
+#[derive(Clone, Debug)]
+pub struct GlobalAccess {
+    /// Ordinal of the script sequence call it happened in
+    pub call: usize,
+    pub module: ModuleId,
+    pub resource: ResourceRef,
+    /// Unknown when the operand couldn't be read
+    pub address: Option<AccountAddress>,
+    pub op: GlobalOp,
+}
+
+/// Global storage instructions, generic or not
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GlobalOp {
+    Exists,
+    Borrow,
+    BorrowMut,
+    MoveFrom,
+    MoveTo,
+}
+
+/// The resource and operation of a global storage instruction.
+pub fn global_op(instruction: &Bytecode) -> Option<(ResourceRef, GlobalOp)> {
+    Some(match instruction {
+        Bytecode::Exists(idx) => (ResourceRef::Definition(idx.0), GlobalOp::Exists),
+        Bytecode::ImmBorrowGlobal(idx) => (ResourceRef::Definition(idx.0), GlobalOp::Borrow),
+        Bytecode::MutBorrowGlobal(idx) => (ResourceRef::Definition(idx.0), GlobalOp::BorrowMut),
+        Bytecode::MoveFrom(idx) => (ResourceRef::Definition(idx.0), GlobalOp::MoveFrom),
+        Bytecode::MoveTo(idx) => (ResourceRef::Definition(idx.0), GlobalOp::MoveTo),
+        Bytecode::ExistsGeneric(idx) => (ResourceRef::Instantiation(idx.0), GlobalOp::Exists),
+        Bytecode::ImmBorrowGlobalGeneric(idx) => (ResourceRef::Instantiation(idx.0), GlobalOp::Borrow),
+        Bytecode::MutBorrowGlobalGeneric(idx) => (ResourceRef::Instantiation(idx.0), GlobalOp::BorrowMut),
+        Bytecode::MoveFromGeneric(idx) => (ResourceRef::Instantiation(idx.0), GlobalOp::MoveFrom),
+        Bytecode::MoveToGeneric(idx) => (ResourceRef::Instantiation(idx.0), GlobalOp::MoveTo),
+        _ => return None,
+    })
+}
+
+impl GlobalOp {
+    /// Whether it changes the resource or its presence. A mutable borrow
+    /// counts as a change, as it mostly leads to one.
+    pub fn is_write(&self) -> bool {
+        matches!(self, Self::BorrowMut | Self::MoveFrom | Self::MoveTo)
+    }
+}
+
+/// A resource at an address, the unit Block-STM tracks conflicts at.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct ResourceKey {
+    /// `<address>::<module>::<struct>`, without type arguments
+    pub resource: String,
+    pub address: Option<AccountAddress>,
+}
+
+impl ResourceKey {
+    /// Whether both may be the same storage slot, an unknown address
+    /// matching any.
+    pub fn overlaps(&self, other: &ResourceKey) -> bool {
+        self.resource == other.resource &&
+            (self.address.is_none() || other.address.is_none() || self.address == other.address)
+    }
+}
+
+impl fmt::Display for ResourceKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.address {
+            Some(address) => write!(f, "{} at {}", self.resource, address.to_hex_literal()),
+            None => write!(f, "{} at an unknown address", self.resource),
+        }
+    }
+}
+
+/// What one call of a transaction read and wrote in global storage.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct CallAccessSet {
+    /// `<address>::<module>::<function>`
+    pub function: String,
+    pub reads: BTreeSet<ResourceKey>,
+    pub writes: BTreeSet<ResourceKey>,
+}
+
+/// Read and write sets of one transaction, call by call.
+#[derive(Clone, Debug, Default)]
+pub struct AccessSets {
+    pub calls: Vec<CallAccessSet>,
+}
+
+impl AccessSets {
+    /// Read set of the whole transaction.
+    pub fn reads(&self) -> BTreeSet<&ResourceKey> {
+        self.calls.iter().flat_map(|call| &call.reads).collect()
+    }
+
+    /// Write set of the whole transaction.
+    pub fn writes(&self) -> BTreeSet<&ResourceKey> {
+        self.calls.iter().flat_map(|call| &call.writes).collect()
+    }
+
+    /// Pairs of calls of different functions where the later one reads or
+    /// writes what the earlier one wrote, so swapping them may change what
+    /// either does.
+    pub fn conflicts(&self) -> BTreeSet<OrderingConflict> {
+        let mut conflicts = BTreeSet::new();
+        for (idx, first) in self.calls.iter().enumerate() {
+            for second in self.calls[idx + 1..]
+                .iter()
+                .filter(|call| call.function != first.function)
+            {
+                for written in &first.writes {
+                    for (accessed, kind) in second
+                        .reads
+                        .iter()
+                        .map(|key| (key, ConflictKind::ReadAfterWrite))
+                        .chain(second.writes.iter().map(|key| (key, ConflictKind::WriteAfterWrite)))
+                    {
+                        if written.overlaps(accessed) {
+                            conflicts.insert(OrderingConflict {
+                                first: first.function.clone(),
+                                second: second.function.clone(),
+                                resource: written.resource.clone(),
+                                kind,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+        conflicts
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ConflictKind {
+    ReadAfterWrite,
+    WriteAfterWrite,
+}
+
+impl ConflictKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::ReadAfterWrite => "read-after-write",
+            Self::WriteAfterWrite => "write-after-write",
+        }
+    }
+}
+
+/// A call of `second` touching a resource an earlier call of `first` in
+/// the same transaction wrote.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct OrderingConflict {
+    pub first: String,
+    pub second: String,
+    pub resource: String,
+    pub kind: ConflictKind,
+}
+
+impl fmt::Display for OrderingConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} then {}: {} on {}",
+            self.first,
+            self.second,
+            self.kind.as_str(),
+            self.resource
+        )
+    }
+}
+
+/// A resource written by some target function and accessed by others: in a
+/// block, transactions calling them conflict under Block-STM and run again.
+#[derive(Clone, Debug)]
+pub struct ConflictHotspot {
+    pub resource: String,
+    pub writers: Vec<String>,
+    /// Functions only reading it
+    pub readers: Vec<String>,
+}
+
+/// Resources each function read and wrote over the campaign.
+#[derive(Clone, Debug, Default)]
+struct Footprint {
+    reads: BTreeSet<String>,
+    writes: BTreeSet<String>,
+}
+
+/// Read and write sets of every execution, kept per function, and the
+/// ordering-sensitive call pairs seen within script sequences.
+#[derive(Debug, Default)]
+pub struct ConflictAnalysis {
+    /// Resource named by each struct index of a module, once resolved
+    names: HashMap<(ModuleId, ResourceRef), Option<String>>,
+    footprints: BTreeMap<String, Footprint>,
+    /// Ordering conflicts, with the number of executions they were seen in
+    observed: BTreeMap<OrderingConflict, u64>,
+}
+
+impl ConflictAnalysis {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Group the traced `accesses` of an execution into the read and write
+    /// sets of its calls, named `functions` in order, naming resources from
+    /// `modules`. Accesses of structs that can't be resolved are dropped.
+    pub fn access_sets(
+        &mut self,
+        accesses: &[GlobalAccess],
+        functions: &[String],
+        modules: &HashMap<ModuleId, Bytes>,
+    ) -> AccessSets {
+        let mut sets = AccessSets {
+            calls: functions
+                .iter()
+                .map(|function| CallAccessSet {
+                    function: function.clone(),
+                    ..Default::default()
+                })
+                .collect(),
+        };
+        if sets.calls.is_empty() {
+            return sets;
+        }
+        let last = sets.calls.len() - 1;
+        for access in accesses {
+            let Some(resource) = self.resource_name(&access.module, access.resource, modules) else {
+                continue;
+            };
+            let key = ResourceKey {
+                resource,
+                address: access.address,
+            };
+            let call = &mut sets.calls[access.call.min(last)];
+            if access.op.is_write() {
+                call.writes.insert(key);
+            } else {
+                call.reads.insert(key);
+            }
+        }
+        sets
+    }
+
+    /// Add an execution's access sets to the campaign's.
+    pub fn record(&mut self, sets: &AccessSets) {
+        for call in &sets.calls {
+            let footprint = self.footprints.entry(call.function.clone()).or_default();
+            footprint
+                .reads
+                .extend(call.reads.iter().map(|key| key.resource.clone()));
+            footprint
+                .writes
+                .extend(call.writes.iter().map(|key| key.resource.clone()));
+        }
+        for conflict in sets.conflicts() {
+            *self.observed.entry(conflict).or_insert(0) += 1;
+        }
+    }
+
+    /// Ordering conflicts seen within script sequences, with how many
+    /// executions showed each.
+    pub fn observed(&self) -> &BTreeMap<OrderingConflict, u64> {
+        &self.observed
+    }
+
+    /// Resources written by at least one function and accessed by at least
+    /// two, most accessing functions first.
+    pub fn hotspots(&self) -> Vec<ConflictHotspot> {
+        let mut by_resource: BTreeMap<&String, (Vec<String>, Vec<String>)> = BTreeMap::new();
+        for (function, footprint) in &self.footprints {
+            for resource in &footprint.writes {
+                by_resource.entry(resource).or_default().0.push(function.clone());
+            }
+            for resource in footprint.reads.difference(&footprint.writes) {
+                by_resource.entry(resource).or_default().1.push(function.clone());
+            }
+        }
+        let mut hotspots: Vec<ConflictHotspot> = by_resource
+            .into_iter()
+            .filter(|(_, (writers, readers))| !writers.is_empty() && writers.len() + readers.len() >= 2)
+            .map(|(resource, (writers, readers))| ConflictHotspot {
+                resource: resource.clone(),
+                writers,
+                readers,
+            })
+            .collect();
+        hotspots.sort_by_key(|hotspot| std::cmp::Reverse(hotspot.writers.len() + hotspot.readers.len()));
+        hotspots
+    }
+
+    fn resource_name(
+        &mut self,
+        module_id: &ModuleId,
+        resource: ResourceRef,
+        modules: &HashMap<ModuleId, Bytes>,
+    ) -> Option<String> {
+        self.names
+            .entry((module_id.clone(), resource))
+            .or_insert_with(|| {
+                let module = CompiledModule::deserialize(modules.get(module_id)?).ok()?;
+                let def = match resource {
+                    ResourceRef::Definition(idx) => idx,
+                    ResourceRef::Instantiation(idx) => module.struct_instantiations().get(idx as usize)?.def.0,
+                };
+                let def = module.struct_defs().get(def as usize)?;
+                let name = module.identifier_at(module.struct_handle_at(def.struct_handle).name);
+                Some(format!("{}::{}", module_id, name))
+            })
+            .clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeSet, HashMap};
+
+    use aptos_move_binary_format::file_format::{Bytecode, StructDefInstantiationIndex, StructDefinitionIndex};
+    use aptos_move_core_types::account_address::AccountAddress;
+    use aptos_move_core_types::identifier::Identifier;
+    use aptos_move_core_types::language_storage::ModuleId;
+
+    use super::{
+        global_op, AccessSets, CallAccessSet, ConflictAnalysis, ConflictKind, GlobalAccess, GlobalOp, OrderingConflict,
+        ResourceKey, ResourceRef,
+    };
+
+    fn module() -> ModuleId {
+        ModuleId::new(
+            AccountAddress::from_hex_literal("0xcafe").unwrap(),
+            Identifier::new("m").unwrap(),
+        )
+    }
+
+    fn key(resource: &str, address: Option<u8>) -> ResourceKey {
+        ResourceKey {
+            resource: resource.to_string(),
+            address: address.map(|byte| AccountAddress::from_hex_literal(&format!("0x{:x}", byte)).unwrap()),
+        }
+    }
+
+    fn call(function: &str, reads: &[ResourceKey], writes: &[ResourceKey]) -> CallAccessSet {
+        CallAccessSet {
+            function: function.to_string(),
+            reads: reads.iter().cloned().collect(),
+            writes: writes.iter().cloned().collect(),
+        }
+    }
+
+    fn conflict(first: &str, second: &str, kind: ConflictKind) -> OrderingConflict {
+        OrderingConflict {
+            first: first.to_string(),
+            second: second.to_string(),
+            resource: "Pool".to_string(),
+            kind,
+        }
+    }
+
+    #[test]
+    fn global_ops_name_their_struct() {
+        assert_eq!(
+            global_op(&Bytecode::MutBorrowGlobal(StructDefinitionIndex(3))),
+            Some((ResourceRef::Definition(3), GlobalOp::BorrowMut))
+        );
+        assert_eq!(
+            global_op(&Bytecode::MoveToGeneric(StructDefInstantiationIndex(1))),
+            Some((ResourceRef::Instantiation(1), GlobalOp::MoveTo))
+        );
+        assert_eq!(global_op(&Bytecode::Pop), None);
+        assert!(GlobalOp::BorrowMut.is_write() && GlobalOp::MoveFrom.is_write());
+        assert!(!GlobalOp::Borrow.is_write() && !GlobalOp::Exists.is_write());
+    }
+
+    #[test]
+    fn unknown_addresses_overlap_any() {
+        assert!(key("Pool", Some(1)).overlaps(&key("Pool", Some(1))));
+        assert!(!key("Pool", Some(1)).overlaps(&key("Pool", Some(2))));
+        assert!(key("Pool", None).overlaps(&key("Pool", Some(2))));
+        assert!(!key("Pool", None).overlaps(&key("Vault", None)));
+    }
+
+    #[test]
+    fn later_calls_touching_what_earlier_ones_wrote_conflict() {
+        let sets = AccessSets {
+            calls: vec![
+                call("swap", &[], &[key("Pool", Some(1))]),
+                call("swap", &[key("Pool", Some(1))], &[]),
+                call("quote", &[key("Pool", Some(1))], &[]),
+                call("add_liquidity", &[], &[key("Pool", None)]),
+                call("remove_liquidity", &[], &[key("Pool", Some(2))]),
+            ],
+        };
+        let conflicts: BTreeSet<OrderingConflict> = [
+            conflict("swap", "quote", ConflictKind::ReadAfterWrite),
+            conflict("swap", "add_liquidity", ConflictKind::WriteAfterWrite),
+            conflict("add_liquidity", "remove_liquidity", ConflictKind::WriteAfterWrite),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(sets.conflicts(), conflicts);
+    }
+
+    #[test]
+    fn access_sets_group_accesses_by_call() {
+        let mut analysis = ConflictAnalysis::new();
+        analysis
+            .names
+            .insert((module(), ResourceRef::Definition(0)), Some("Pool".to_string()));
+        let access = |call: usize, resource: u16, op: GlobalOp| GlobalAccess {
+            call,
+            module: module(),
+            resource: ResourceRef::Definition(resource),
+            address: None,
+            op,
+        };
+        let accesses = [
+            access(0, 0, GlobalOp::Borrow),
+            access(1, 0, GlobalOp::MoveTo),
+            // Calls past the last one count as the last, unresolved structs
+            // are dropped
+            access(5, 0, GlobalOp::Exists),
+            access(0, 1, GlobalOp::MoveFrom),
+        ];
+        let functions = vec!["quote".to_string(), "swap".to_string()];
+        let sets = analysis.access_sets(&accesses, &functions, &HashMap::new());
+        assert_eq!(sets.calls.len(), 2);
+        assert_eq!(sets.calls[0].reads, [key("Pool", None)].into_iter().collect());
+        assert!(sets.calls[0].writes.is_empty());
+        assert_eq!(sets.calls[1].reads, [key("Pool", None)].into_iter().collect());
+        assert_eq!(sets.calls[1].writes, [key("Pool", None)].into_iter().collect());
+        assert!(analysis.access_sets(&accesses, &[], &HashMap::new()).calls.is_empty());
+    }
+
+    #[test]
+    fn hotspots_are_written_and_shared() {
+        let mut analysis = ConflictAnalysis::new();
+        analysis.record(&AccessSets {
+            calls: vec![
+                call("swap", &[key("Pool", None)], &[key("Pool", None)]),
+                call("quote", &[key("Pool", None), key("Config", None)], &[]),
+            ],
+        });
+        analysis.record(&AccessSets {
+            calls: vec![
+                call("set_fee", &[], &[key("Config", None)]),
+                call("claim", &[], &[key("Rewards", None)]),
+            ],
+        });
+        analysis.record(&AccessSets {
+            calls: vec![
+                call("quote", &[key("Oracle", None)], &[]),
+                call("peek", &[key("Oracle", None)], &[]),
+            ],
+        });
+        let hotspots: Vec<(String, Vec<String>, Vec<String>)> = analysis
+            .hotspots()
+            .into_iter()
+            .map(|hotspot| (hotspot.resource, hotspot.writers, hotspot.readers))
+            .collect();
+        assert_eq!(
+            hotspots,
+            vec![
+                (
+                    "Config".to_string(),
+                    vec!["set_fee".to_string()],
+                    vec!["quote".to_string()]
+                ),
+                ("Pool".to_string(), vec!["swap".to_string()], vec!["quote".to_string()]),
+            ]
+        );
+        assert_eq!(
+            analysis.observed().iter().collect::<Vec<_>>(),
+            vec![(&conflict("swap", "quote", ConflictKind::ReadAfterWrite), &1)]
+        );
+    }
+}
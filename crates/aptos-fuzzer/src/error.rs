@@ -0,0 +1,72 @@
+use std::fmt;
+use std::path::PathBuf;
+
+use thiserror::Error;
+
+/// Part of the fuzzer an error came from, for counting the errors it
+/// recovered from without stopping.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Subsystem {
+    ModuleLoading,
+    SeedGeneration,
+    SequenceCompilation,
+}
+
+impl Subsystem {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Subsystem::ModuleLoading => "module-loading",
+            Subsystem::SeedGeneration => "seed-generation",
+            Subsystem::SequenceCompilation => "sequence-compilation",
+        }
+    }
+}
+
+impl fmt::Display for Subsystem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Why loading the targets, generating a seed or compiling a script
+/// sequence failed.
+#[derive(Debug, Error)]
+pub enum FuzzerError {
+    #[error("failed to list directory {}: {source}", path.display())]
+    ListDirectory { path: PathBuf, source: std::io::Error },
+    #[error("failed to read module {}: {source}", path.display())]
+    ReadModule { path: PathBuf, source: std::io::Error },
+    #[error("failed to deserialize module {}: {reason}; was it compiled for this Aptos version?", path.display())]
+    DeserializeModule { path: PathBuf, reason: String },
+    #[error("no default value for an argument of type {ty} to {function}")]
+    SeedArgument { function: String, ty: String },
+    #[error("failed to load module {module} into the script composer: {reason}")]
+    ComposerModule { module: String, reason: String },
+    #[error("failed to rebuild the arguments of {function}")]
+    SequenceArguments { function: String },
+    #[error("failed to add a call to {function} to the script: {reason}")]
+    BatchedCall { function: String, reason: String },
+    #[error("failed to encode the transaction arguments of the sequence")]
+    TransactionArguments,
+    #[error("failed to generate the script of the sequence: {0}")]
+    ScriptGeneration(String),
+    #[error("failed to decode the generated script: {0}")]
+    ScriptDecoding(String),
+}
+
+impl FuzzerError {
+    pub fn subsystem(&self) -> Subsystem {
+        match self {
+            FuzzerError::ListDirectory { .. } |
+            FuzzerError::ReadModule { .. } |
+            FuzzerError::DeserializeModule { .. } => Subsystem::ModuleLoading,
+            FuzzerError::SeedArgument { .. } => Subsystem::SeedGeneration,
+            FuzzerError::ComposerModule { .. } |
+            FuzzerError::SequenceArguments { .. } |
+            FuzzerError::BatchedCall { .. } |
+            FuzzerError::TransactionArguments |
+            FuzzerError::ScriptGeneration(_) |
+            FuzzerError::ScriptDecoding(_) => Subsystem::SequenceCompilation,
+        }
+    }
+}
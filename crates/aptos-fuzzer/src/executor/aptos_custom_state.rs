@@ -1,4 +1,5 @@
-use std::collections::{BTreeMap, HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 
 use aptos_aggregator::bounded_math::SignedU128;
@@ -22,6 +23,8 @@ use aptos_move_vm_types::code::{Code, ScriptCache};
 use aptos_move_vm_types::delayed_values::delayed_field_id::DelayedFieldID;
 use aptos_move_vm_types::resolver::ResourceResolver;
 use aptos_native_interface::SafeNativeBuilder;
+use aptos_types::access_path::Path;
+use aptos_types::account_address::create_object_address;
 use aptos_types::chain_id::ChainId;
 use aptos_types::error::{PanicError, PanicOr};
 use aptos_types::on_chain_config::{ConfigStorage, Features, TimedFeaturesBuilder};
@@ -42,6 +45,15 @@ use aptos_vm_types::resolver::{
 };
 use bytes::Bytes;
 use dashmap::DashMap;
+use log::warn;
+
+use super::custom_state_view::ModuleStateView;
+use super::edge_layout::EdgeLayout;
+use super::forked_state_view::ForkedStateView;
+use super::types::DifferentialConfig;
+use crate::input::BlockContext;
+use crate::state::MAP_SIZE;
+use crate::struct_layout::framework_struct;
 
 #[derive(Clone)]
 pub struct AptosCustomState {
@@ -50,9 +62,55 @@ pub struct AptosCustomState {
     modules: HashMap<ModuleId, Bytes>,
     scripts_deser: DashMap<[u8; 32], Arc<CompiledScript>>,
     scripts_verified: DashMap<[u8; 32], Arc<Script>>,
+    /// Modules already deserialized for the VM, dropped when republished
+    deserialized_modules: DashMap<ModuleId, Arc<CompiledModule>>,
+    /// Changes whenever a module is published or removed, so code storages
+    /// built from this state can tell they are stale
+    module_generation: u64,
     runtime_environment: RuntimeEnvironment,
+    edge_layout: Arc<EdgeLayout>,
+    /// Network state read through when a key is not held locally
+    fork: Option<Arc<ForkedStateView>>,
+    /// Keys deleted locally that must not be read through to the fork
+    deleted: HashSet<StateKey>,
+    /// Addresses holding a `0x1::object::ObjectCore`, i.e. objects created
+    /// locally (forked objects are not indexed)
+    objects: BTreeSet<AccountAddress>,
+    /// Previous values of everything written while a snapshot is open,
+    /// oldest first
+    journal: Vec<JournalEntry>,
+    open_snapshots: usize,
+}
+
+/// Undo record for one write to `AptosCustomState`.
+#[derive(Clone)]
+enum JournalEntry {
+    Kv(StateKey, Option<StateValue>),
+    Table((TableHandle, Vec<u8>), Option<Bytes>),
+    Module(ModuleId, Option<Bytes>),
+    Deleted(StateKey, bool),
+    Object(AccountAddress, bool),
 }
 
+/// A point `AptosCustomState::restore` can roll back to.
+#[must_use]
+#[derive(Debug)]
+pub struct StateSnapshot {
+    journal_len: usize,
+    depth: usize,
+}
+
+/// Source of module generations. Global, so two states only share one when
+/// their modules are the same.
+static NEXT_MODULE_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+fn next_module_generation() -> u64 {
+    NEXT_MODULE_GENERATION.fetch_add(1, Ordering::Relaxed)
+}
+
+/// `object::INIT_GUID_CREATION_NUM`: first GUID creation number of an object.
+const OBJECT_INIT_GUID_CREATION_NUM: u64 = 0x4000000000000;
+
 macro_rules! unknown_status {
     () => {
         PartialVMError::new(aptos_types::vm_status::StatusCode::UNKNOWN_STATUS)
@@ -65,8 +123,8 @@ impl TAggregatorV1View for AptosCustomState {
     type Identifier = StateKey;
 
     fn get_aggregator_v1_state_value(&self, id: &StateKey) -> PartialVMResult<Option<StateValue>> {
-        match self.kv_state.get(id) {
-            Some(v) => Ok(Some(v.clone())),
+        match self.lookup(id) {
+            Some(v) => Ok(Some(v)),
             None => Err(unknown_status!()),
         }
     }
@@ -123,7 +181,7 @@ impl TDelayedFieldView for AptosCustomState {
 
 impl ConfigStorage for AptosCustomState {
     fn fetch_config_bytes(&self, state_key: &StateKey) -> Option<Bytes> {
-        self.kv_state.get(state_key).map(|v| v.bytes().clone())
+        self.lookup(state_key).map(|v| v.bytes().clone())
     }
 }
 
@@ -137,7 +195,7 @@ impl ResourceResolver for AptosCustomState {
     ) -> PartialVMResult<(Option<Bytes>, usize)> {
         let state_key = StateKey::resource(address, struct_tag).map_err(|_| unknown_status!())?;
 
-        match self.kv_state.get(&state_key) {
+        match self.lookup(&state_key) {
             Some(state_value) => {
                 let bytes = state_value.bytes();
                 let size = bytes.len();
@@ -176,7 +234,7 @@ impl StateStorageView for AptosCustomState {
     }
 
     fn read_state_value(&self, state_key: &StateKey) -> Result<(), StateViewError> {
-        match self.kv_state.get(state_key) {
+        match self.lookup(state_key) {
             Some(_) => Ok(()),
             None => Err(StateViewError::NotFound(format!("Key not found: {:?}", state_key))),
         }
@@ -194,11 +252,7 @@ impl TableResolver for AptosCustomState {
         key: &[u8],
         _maybe_layout: Option<&MoveTypeLayout>,
     ) -> Result<Option<Bytes>, PartialVMError> {
-        let table_key = (*handle, key.to_vec());
-        match self.tables.get(&table_key) {
-            Some(bytes) => Ok(Some(bytes.clone())),
-            None => Ok(None),
-        }
+        Ok(self.lookup_table_entry(handle, key))
     }
 }
 
@@ -217,28 +271,28 @@ impl TResourceView for AptosCustomState {
         state_key: &StateKey,
         _maybe_layout: Option<&MoveTypeLayout>,
     ) -> PartialVMResult<Option<StateValue>> {
-        match self.kv_state.get(state_key) {
-            Some(state_value) => Ok(Some(state_value.clone())),
+        match self.lookup(state_key) {
+            Some(state_value) => Ok(Some(state_value)),
             None => Ok(None),
         }
     }
 
     fn get_resource_state_value_metadata(&self, state_key: &StateKey) -> PartialVMResult<Option<StateValueMetadata>> {
-        match self.kv_state.get(state_key) {
+        match self.lookup(state_key) {
             Some(state_value) => Ok(Some(state_value.metadata().clone())),
             None => Ok(None),
         }
     }
 
     fn get_resource_state_value_size(&self, state_key: &StateKey) -> PartialVMResult<u64> {
-        match self.kv_state.get(state_key) {
+        match self.lookup(state_key) {
             Some(state_value) => Ok(state_value.bytes().len() as u64),
             None => Ok(0),
         }
     }
 
     fn resource_exists(&self, state_key: &StateKey) -> PartialVMResult<bool> {
-        Ok(self.kv_state.contains_key(state_key))
+        Ok(self.lookup(state_key).is_some())
     }
 }
 
@@ -255,7 +309,7 @@ impl TResourceGroupView for AptosCustomState {
     type Layout = MoveTypeLayout;
 
     fn resource_group_size(&self, group_key: &StateKey) -> PartialVMResult<ResourceGroupSize> {
-        match self.kv_state.get(group_key) {
+        match self.lookup(group_key) {
             Some(state_value) => Ok(ResourceGroupSize::Concrete(state_value.bytes().len() as u64)),
             None => Ok(ResourceGroupSize::Concrete(0)),
         }
@@ -267,7 +321,7 @@ impl TResourceGroupView for AptosCustomState {
         resource_tag: &StructTag,
         _maybe_layout: Option<&MoveTypeLayout>,
     ) -> PartialVMResult<Option<Bytes>> {
-        let maybe_bytes = self.kv_state.get(group_key).map(|sv| sv.bytes().clone());
+        let maybe_bytes = self.lookup(group_key).map(|sv| sv.bytes().clone());
         if let Some(blob) = maybe_bytes {
             let map: BTreeMap<StructTag, Bytes> = bcs::from_bytes(&blob).map_err(|_| unknown_status!())?;
             Ok(map.get(resource_tag).cloned())
@@ -277,7 +331,7 @@ impl TResourceGroupView for AptosCustomState {
     }
 
     fn resource_size_in_group(&self, group_key: &StateKey, resource_tag: &StructTag) -> PartialVMResult<usize> {
-        let maybe_bytes = self.kv_state.get(group_key).map(|sv| sv.bytes().clone());
+        let maybe_bytes = self.lookup(group_key).map(|sv| sv.bytes().clone());
         if let Some(blob) = maybe_bytes {
             let map: BTreeMap<StructTag, Bytes> = bcs::from_bytes(&blob).map_err(|_| unknown_status!())?;
             Ok(map.get(resource_tag).map_or(0, |v| v.len()))
@@ -287,7 +341,7 @@ impl TResourceGroupView for AptosCustomState {
     }
 
     fn resource_exists_in_group(&self, group_key: &StateKey, resource_tag: &StructTag) -> PartialVMResult<bool> {
-        let maybe_bytes = self.kv_state.get(group_key).map(|sv| sv.bytes().clone());
+        let maybe_bytes = self.lookup(group_key).map(|sv| sv.bytes().clone());
         if let Some(blob) = maybe_bytes {
             let map: BTreeMap<StructTag, Bytes> = bcs::from_bytes(&blob).map_err(|_| unknown_status!())?;
             Ok(map.contains_key(resource_tag))
@@ -310,7 +364,7 @@ impl AptosModuleStorage for AptosCustomState {
     ) -> PartialVMResult<Option<StateValueMetadata>> {
         let state_key = StateKey::module(address, module_name);
 
-        match self.kv_state.get(&state_key) {
+        match self.lookup(&state_key) {
             Some(state_value) => Ok(Some(state_value.metadata().clone())),
             None => Ok(None),
         }
@@ -324,7 +378,7 @@ impl ModuleStorage for AptosCustomState {
     #[doc = " Note: this API is not metered!"]
     fn unmetered_check_module_exists(&self, address: &AccountAddress, module_name: &IdentStr) -> VMResult<bool> {
         let module_id = ModuleId::new(*address, module_name.to_owned());
-        Ok(self.modules.contains_key(&module_id))
+        Ok(self.lookup_module(&module_id).is_some())
     }
 
     #[doc = " Returns module bytes if module exists, or [None] otherwise. An error is returned if there"]
@@ -333,7 +387,7 @@ impl ModuleStorage for AptosCustomState {
     #[doc = " Note: this API is not metered!"]
     fn unmetered_get_module_bytes(&self, address: &AccountAddress, module_name: &IdentStr) -> VMResult<Option<Bytes>> {
         let module_id = ModuleId::new(*address, module_name.to_owned());
-        Ok(self.modules.get(&module_id).cloned())
+        Ok(self.lookup_module(&module_id))
     }
 
     #[doc = " Returns the size of a module in bytes, or [None] otherwise. An error is returned if the"]
@@ -343,7 +397,7 @@ impl ModuleStorage for AptosCustomState {
     #[doc = " can actually be implemented before loading a module."]
     fn unmetered_get_module_size(&self, address: &AccountAddress, module_name: &IdentStr) -> VMResult<Option<usize>> {
         let module_id = ModuleId::new(*address, module_name.to_owned());
-        Ok(self.modules.get(&module_id).map(|bytes| bytes.len()))
+        Ok(self.lookup_module(&module_id).map(|bytes| bytes.len()))
     }
 
     #[doc = " Returns the metadata in the module, or [None] otherwise. An error is returned if there is"]
@@ -356,13 +410,9 @@ impl ModuleStorage for AptosCustomState {
         module_name: &IdentStr,
     ) -> VMResult<Option<Vec<Metadata>>> {
         let module_id = ModuleId::new(*address, module_name.to_owned());
-        match self.modules.get(&module_id) {
-            Some(bytes) => match CompiledModule::deserialize(bytes) {
-                Ok(module) => Ok(Some(module.metadata)),
-                Err(_) => Ok(None),
-            },
-            None => Ok(None),
-        }
+        Ok(self
+            .deserialized_module(&module_id)
+            .map(|module| module.metadata.clone()))
     }
 
     #[doc = " Returns the deserialized module, or [None] otherwise. An error is returned if:"]
@@ -376,13 +426,7 @@ impl ModuleStorage for AptosCustomState {
         module_name: &IdentStr,
     ) -> VMResult<Option<Arc<CompiledModule>>> {
         let module_id = ModuleId::new(*address, module_name.to_owned());
-        match self.modules.get(&module_id) {
-            Some(bytes) => match CompiledModule::deserialize(bytes) {
-                Ok(module) => Ok(Some(Arc::new(module))),
-                Err(_) => Ok(None),
-            },
-            None => Ok(None),
-        }
+        Ok(self.deserialized_module(&module_id))
     }
 
     #[doc = " Returns the verified module if it exists, or [None] otherwise. The existing module can be"]
@@ -504,24 +548,7 @@ impl AptosCustomState {
         // missing.
         let chain_id = ChainId::test();
         let features = Features::default();
-        let timed_features = TimedFeaturesBuilder::new(chain_id, 0).build();
-        let gas_feature_version = 0u64;
-        let mut builder = SafeNativeBuilder::new(
-            gas_feature_version,
-            NativeGasParameters::zeros(),
-            MiscGasParameters::zeros(),
-            timed_features.clone(),
-            features.clone(),
-            None,
-        );
-        let natives = aptos_natives_with_builder(&mut builder, false);
-        let vm_config = aptos_prod_vm_config(
-            gas_feature_version,
-            &features,
-            &timed_features,
-            aptos_default_ty_builder(),
-        );
-        let runtime_environment = RuntimeEnvironment::new_with_config(natives, vm_config);
+        let runtime_environment = Self::build_runtime_environment(chain_id, &features, None);
 
         // Seed essential on-chain config state with sane defaults works.
         let mut kv_state: HashMap<StateKey, StateValue> = HashMap::new();
@@ -543,7 +570,15 @@ impl AptosCustomState {
             modules: HashMap::new(),
             scripts_deser: DashMap::new(),
             scripts_verified: DashMap::new(),
+            deserialized_modules: DashMap::new(),
+            module_generation: next_module_generation(),
             runtime_environment,
+            edge_layout: Arc::new(EdgeLayout::new(MAP_SIZE)),
+            fork: None,
+            deleted: HashSet::new(),
+            objects: BTreeSet::new(),
+            journal: Vec::new(),
+            open_snapshots: 0,
         };
 
         // Load and deploy Aptos framework bundle (includes move-stdlib, aptos-stdlib,
@@ -557,6 +592,117 @@ impl AptosCustomState {
         this
     }
 
+    /// State backed by a live network: nothing is seeded locally, so the
+    /// framework, on-chain configs and deployed protocols are all read from
+    /// `fork` on first use.
+    pub fn new_forked(fork: ForkedStateView) -> Self {
+        let mut this = Self {
+            kv_state: HashMap::new(),
+            tables: HashMap::new(),
+            modules: HashMap::new(),
+            scripts_deser: DashMap::new(),
+            scripts_verified: DashMap::new(),
+            deserialized_modules: DashMap::new(),
+            module_generation: next_module_generation(),
+            runtime_environment: Self::build_runtime_environment(ChainId::test(), &Features::default(), None),
+            edge_layout: Arc::new(EdgeLayout::new(MAP_SIZE)),
+            fork: Some(Arc::new(fork)),
+            deleted: HashSet::new(),
+            objects: BTreeSet::new(),
+            journal: Vec::new(),
+            open_snapshots: 0,
+        };
+        let chain_id = StateKey::on_chain_config::<ChainId>()
+            .ok()
+            .and_then(|state_key| this.lookup(&state_key))
+            .and_then(|value| bcs::from_bytes::<ChainId>(value.bytes()).ok())
+            .unwrap_or_else(ChainId::test);
+        let features = this.current_features();
+        this.runtime_environment = Self::build_runtime_environment(chain_id, &features, None);
+        this
+    }
+
+    fn current_features(&self) -> Features {
+        StateKey::on_chain_config::<Features>()
+            .ok()
+            .and_then(|state_key| self.lookup(&state_key))
+            .and_then(|value| bcs::from_bytes::<Features>(value.bytes()).ok())
+            .unwrap_or_default()
+    }
+
+    /// `paranoid_type_checks` overrides the production VM config when set.
+    fn build_runtime_environment(
+        chain_id: ChainId,
+        features: &Features,
+        paranoid_type_checks: Option<bool>,
+    ) -> RuntimeEnvironment {
+        let timed_features = TimedFeaturesBuilder::new(chain_id, 0).build();
+        let gas_feature_version = 0u64;
+        let mut builder = SafeNativeBuilder::new(
+            gas_feature_version,
+            NativeGasParameters::zeros(),
+            MiscGasParameters::zeros(),
+            timed_features.clone(),
+            features.clone(),
+            None,
+        );
+        let natives = aptos_natives_with_builder(&mut builder, false);
+        let mut vm_config = aptos_prod_vm_config(
+            gas_feature_version,
+            features,
+            &timed_features,
+            aptos_default_ty_builder(),
+        );
+        if let Some(paranoid_type_checks) = paranoid_type_checks {
+            vm_config.paranoid_type_checks = paranoid_type_checks;
+        }
+        RuntimeEnvironment::new_with_config(natives, vm_config)
+    }
+
+    /// Copy of this state running under a different VM configuration, used as
+    /// the reference side of differential execution.
+    pub fn reconfigured(&self, config: &DifferentialConfig) -> Self {
+        let mut features = self.current_features();
+        for &flag in &config.enable_features {
+            Self::set_feature_bit(&mut features, flag, true);
+        }
+        for &flag in &config.disable_features {
+            Self::set_feature_bit(&mut features, flag, false);
+        }
+
+        let mut this = self.clone();
+        if let Ok(state_key) = StateKey::on_chain_config::<Features>() {
+            let bytes = bcs::to_bytes(&features).expect("serialize Features");
+            this.kv_state.insert(state_key, StateValue::new_legacy(bytes.into()));
+        }
+        this.runtime_environment =
+            Self::build_runtime_environment(ChainId::test(), &features, Some(config.paranoid_type_checks));
+        // Cached scripts were verified against the old environment
+        this.scripts_deser = DashMap::new();
+        this.scripts_verified = DashMap::new();
+        this.module_generation = next_module_generation();
+        this
+    }
+
+    fn set_feature_bit(features: &mut Features, flag: u64, enabled: bool) {
+        let byte = (flag / 8) as usize;
+        let mask = 1u8 << (flag % 8);
+        if features.features.len() <= byte {
+            features.features.resize(byte + 1, 0);
+        }
+        if enabled {
+            features.features[byte] |= mask;
+        } else {
+            features.features[byte] &= !mask;
+        }
+    }
+
+    /// Environment for a VM executing against this state.
+    pub fn env(&self) -> aptos_vm_environment::environment::AptosEnvironment {
+        let view = crate::executor::custom_state_view::CustomStateView::new(self);
+        aptos_vm_environment::environment::AptosEnvironment::new(&view)
+    }
+
     pub fn default_env() -> aptos_vm_environment::environment::AptosEnvironment {
         let tmp = Self::new_default();
         let view = crate::executor::custom_state_view::CustomStateView::new(&tmp);
@@ -568,57 +714,376 @@ impl AptosCustomState {
     }
 
     pub fn get_state_value(&self, state_key: &StateKey) -> Option<StateValue> {
-        self.kv_state.get(state_key).cloned()
+        self.lookup(state_key)
+    }
+
+    /// Write `value` under `state_key` directly, outside of any transaction.
+    pub fn set_state_value(&mut self, state_key: StateKey, value: StateValue) {
+        self.track_deletion(&state_key, false);
+        self.index_object(&state_key, Some(value.bytes()));
+        self.write_kv(state_key, Some(value));
+    }
+
+    /// Addresses holding a resource or module in the local state.
+    pub fn local_addresses(&self) -> BTreeSet<AccountAddress> {
+        self.kv_state
+            .keys()
+            .filter_map(|state_key| match state_key.inner() {
+                StateKeyInner::AccessPath(access_path) => Some(access_path.address),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Resources held in the local state with their bytes. Resource groups'
+    /// members are not listed.
+    pub fn resources(&self) -> Vec<(AccountAddress, StructTag, Bytes)> {
+        self.kv_state
+            .iter()
+            .filter_map(|(state_key, value)| match state_key.inner() {
+                StateKeyInner::AccessPath(access_path) => Some((
+                    access_path.address,
+                    access_path.get_struct_tag()?,
+                    value.bytes().clone(),
+                )),
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn fork(&self) -> Option<&ForkedStateView> {
+        self.fork.as_deref()
+    }
+
+    /// Local value of `state_key`, falling back to the fork when there is one.
+    fn lookup(&self, state_key: &StateKey) -> Option<StateValue> {
+        if let Some(value) = self.kv_state.get(state_key) {
+            return Some(value.clone());
+        }
+        let fork = self.fork.as_ref()?;
+        if self.deleted.contains(state_key) {
+            return None;
+        }
+        fork.get_state_value(state_key)
+    }
+
+    fn lookup_module(&self, module_id: &ModuleId) -> Option<Bytes> {
+        if let Some(bytes) = self.modules.get(module_id) {
+            return Some(bytes.clone());
+        }
+        self.lookup(&StateKey::module(module_id.address(), module_id.name()))
+            .map(|value| value.bytes().clone())
+    }
+
+    /// `module_id` deserialized, cached until the module is republished.
+    fn deserialized_module(&self, module_id: &ModuleId) -> Option<Arc<CompiledModule>> {
+        if let Some(module) = self.deserialized_modules.get(module_id) {
+            return Some(module.clone());
+        }
+        let module = Arc::new(CompiledModule::deserialize(&self.lookup_module(module_id)?).ok()?);
+        self.deserialized_modules.insert(module_id.clone(), module.clone());
+        Some(module)
+    }
+
+    /// Changes whenever a module is published or removed.
+    pub fn module_generation(&self) -> u64 {
+        self.module_generation
+    }
+
+    /// Copy of the module keys of this state for a code storage that
+    /// outlives borrows of it.
+    pub(crate) fn module_view(&self) -> ModuleStateView {
+        let is_code = |state_key: &StateKey| matches!(state_key.inner(), StateKeyInner::AccessPath(access_path) if access_path.is_code());
+        let modules = self
+            .kv_state
+            .iter()
+            .filter(|(state_key, _)| is_code(state_key))
+            .map(|(state_key, value)| (state_key.clone(), value.clone()))
+            .collect();
+        let deleted = self
+            .deleted
+            .iter()
+            .filter(|state_key| is_code(state_key))
+            .cloned()
+            .collect();
+        ModuleStateView::new(modules, deleted, self.fork.clone(), self.runtime_environment.clone())
+    }
+
+    fn lookup_table_entry(&self, handle: &TableHandle, key: &[u8]) -> Option<Bytes> {
+        if let Some(bytes) = self.tables.get(&(*handle, key.to_vec())) {
+            return Some(bytes.clone());
+        }
+        let state_key = StateKey::table_item(&aptos_types::state_store::table::TableHandle(handle.0), key);
+        self.lookup(&state_key).map(|value| value.bytes().clone())
+    }
+
+    /// Remember that `state_key` is gone so reads don't fall through to the
+    /// fork, or forget it once the key is written again.
+    fn track_deletion(&mut self, state_key: &StateKey, deleted: bool) {
+        if self.fork.is_none() {
+            return;
+        }
+        let was_deleted = if deleted {
+            !self.deleted.insert(state_key.clone())
+        } else {
+            self.deleted.remove(state_key)
+        };
+        if was_deleted != deleted {
+            self.record(|| JournalEntry::Deleted(state_key.clone(), was_deleted));
+        }
+    }
+
+    /// Mark the current state so later writes can be undone with `restore`.
+    /// Snapshots nest: restoring one also undoes those taken after it. Writes
+    /// are only journaled while a snapshot is open, so taking none costs
+    /// nothing.
+    pub fn snapshot(&mut self) -> StateSnapshot {
+        let snapshot = StateSnapshot {
+            journal_len: self.journal.len(),
+            depth: self.open_snapshots,
+        };
+        self.open_snapshots += 1;
+        snapshot
+    }
+
+    /// Undo every write made since `snapshot` was taken.
+    pub fn restore(&mut self, snapshot: StateSnapshot) {
+        let depth = snapshot.depth;
+        // Undo without journaling the undo itself
+        self.open_snapshots = 0;
+        while self.journal.len() > snapshot.journal_len {
+            match self.journal.pop().expect("journal entry") {
+                JournalEntry::Kv(state_key, value) => {
+                    self.write_kv(state_key, value);
+                }
+                JournalEntry::Table(key, bytes) => self.write_table(key, bytes),
+                JournalEntry::Module(module_id, bytes) => self.write_module(module_id, bytes),
+                JournalEntry::Deleted(state_key, true) => {
+                    self.deleted.insert(state_key);
+                }
+                JournalEntry::Deleted(state_key, false) => {
+                    self.deleted.remove(&state_key);
+                }
+                JournalEntry::Object(address, true) => {
+                    self.objects.insert(address);
+                }
+                JournalEntry::Object(address, false) => {
+                    self.objects.remove(&address);
+                }
+            }
+        }
+        self.open_snapshots = depth;
+    }
+
+    /// Keep every write made since `snapshot` and stop tracking it.
+    pub fn discard(&mut self, snapshot: StateSnapshot) {
+        self.open_snapshots = snapshot.depth;
+        if self.open_snapshots == 0 {
+            self.journal.clear();
+        }
+    }
+
+    fn record(&mut self, entry: impl FnOnce() -> JournalEntry) {
+        if self.open_snapshots > 0 {
+            self.journal.push(entry());
+        }
+    }
+
+    /// Set or remove a local key, returning its previous local value.
+    fn write_kv(&mut self, state_key: StateKey, value: Option<StateValue>) -> Option<StateValue> {
+        let previous = match value {
+            Some(value) => self.kv_state.insert(state_key.clone(), value),
+            None => self.kv_state.remove(&state_key),
+        };
+        self.record(|| JournalEntry::Kv(state_key, previous.clone()));
+        previous
+    }
+
+    fn write_table(&mut self, key: (TableHandle, Vec<u8>), bytes: Option<Bytes>) {
+        let previous = match bytes {
+            Some(bytes) => self.tables.insert(key.clone(), bytes),
+            None => self.tables.remove(&key),
+        };
+        self.record(|| JournalEntry::Table(key, previous));
+    }
+
+    fn write_module(&mut self, module_id: ModuleId, bytes: Option<Bytes>) {
+        self.deserialized_modules.remove(&module_id);
+        self.module_generation = next_module_generation();
+        let previous = match bytes {
+            Some(bytes) => self.modules.insert(module_id.clone(), bytes),
+            None => self.modules.remove(&module_id),
+        };
+        self.record(|| JournalEntry::Module(module_id, previous));
+    }
+
+    /// Overwrite the on-chain timestamp, epoch and chain id with the values set
+    /// in `block_context`. Returns the previous values so the caller can put
+    /// them back with `restore_state_values` once the transaction has run.
+    pub fn apply_block_context(&mut self, block_context: &BlockContext) -> Vec<(StateKey, Option<StateValue>)> {
+        let mut overrides: Vec<(StateKey, Vec<u8>)> = Vec::new();
+        if let Some(timestamp_us) = block_context.timestamp_us {
+            // 0x1::timestamp::CurrentTimeMicroseconds { microseconds: u64 }
+            if let Ok(state_key) = StateKey::resource(
+                &AccountAddress::ONE,
+                &framework_struct("timestamp", "CurrentTimeMicroseconds", vec![]),
+            ) {
+                overrides.push((state_key, bcs::to_bytes(&timestamp_us).expect("serialize u64")));
+            }
+        }
+        if let Some(epoch) = block_context.epoch {
+            // 0x1::reconfiguration::Configuration starts with `epoch: u64`; keep the
+            // rest of an existing resource, or build one with an empty event handle.
+            if let Ok(state_key) = StateKey::resource(
+                &AccountAddress::ONE,
+                &framework_struct("reconfiguration", "Configuration", vec![]),
+            ) {
+                let bytes = match self.lookup(&state_key) {
+                    Some(value) if value.bytes().len() >= 8 => {
+                        let mut bytes = value.bytes().to_vec();
+                        bytes[..8].copy_from_slice(&epoch.to_le_bytes());
+                        bytes
+                    }
+                    _ => {
+                        // epoch, last_reconfiguration_time, EventHandle { counter, GUID { creation_num,
+                        // addr } }
+                        bcs::to_bytes(&(epoch, 0u64, 0u64, 0u64, AccountAddress::ONE)).expect("serialize Configuration")
+                    }
+                };
+                overrides.push((state_key, bytes));
+            }
+        }
+        if let Some(chain_id) = block_context.chain_id {
+            if let Ok(state_key) = StateKey::on_chain_config::<ChainId>() {
+                overrides.push((
+                    state_key,
+                    bcs::to_bytes(&ChainId::new(chain_id)).expect("serialize ChainId"),
+                ));
+            }
+        }
+
+        overrides
+            .into_iter()
+            .map(|(state_key, bytes)| {
+                let previous = self.write_kv(state_key.clone(), Some(StateValue::new_legacy(bytes.into())));
+                (state_key, previous)
+            })
+            .collect()
+    }
+
+    /// Undo `apply_block_context`.
+    pub fn restore_state_values(&mut self, saved: Vec<(StateKey, Option<StateValue>)>) {
+        for (state_key, previous) in saved.into_iter().rev() {
+            self.write_kv(state_key, previous);
+        }
+    }
+
+    /// Objects in the pool that hold a `resource`, either as a member of the
+    /// object's resource group or as a standalone resource.
+    pub fn object_addresses(&self, resource: &StructTag) -> Vec<AccountAddress> {
+        let group_tag = framework_struct("object", "ObjectGroup", vec![]);
+        self.objects
+            .iter()
+            .filter(|address| {
+                let in_group = StateKey::resource_group(address, &group_tag)
+                    .ok()
+                    .and_then(|group_key| self.lookup(&group_key))
+                    .and_then(|value| bcs::from_bytes::<BTreeMap<StructTag, Bytes>>(value.bytes()).ok())
+                    .is_some_and(|group| group.contains_key(resource));
+                in_group ||
+                    StateKey::resource(address, resource)
+                        .ok()
+                        .is_some_and(|state_key| self.lookup(&state_key).is_some())
+            })
+            .copied()
+            .collect()
+    }
+
+    /// The owner recorded in the `ObjectCore` of `address`, if it is an object
+    /// in the pool.
+    pub fn object_owner(&self, address: &AccountAddress) -> Option<AccountAddress> {
+        if !self.objects.contains(address) {
+            return None;
+        }
+        let group_key = StateKey::resource_group(address, &framework_struct("object", "ObjectGroup", vec![])).ok()?;
+        let group = bcs::from_bytes::<BTreeMap<StructTag, Bytes>>(self.lookup(&group_key)?.bytes()).ok()?;
+        // ObjectCore starts with `guid_creation_num: u64, owner: address`
+        let object_core = group.get(&framework_struct("object", "ObjectCore", vec![]))?;
+        AccountAddress::from_bytes(object_core.get(8..8 + AccountAddress::LENGTH)?).ok()
+    }
+
+    /// Create a bare object owned by `owner`, the way `object::create_object`
+    /// does, and add it to the object pool.
+    pub fn create_object(&mut self, owner: AccountAddress) -> AccountAddress {
+        // Same derivation as `object::create_named_object`, seeded by the pool size
+        let seed = format!("aptos-fuzzer::object::{}", self.objects.len());
+        let address = create_object_address(owner, seed.as_bytes());
+        // ObjectCore { guid_creation_num, owner, allow_ungated_transfer,
+        // transfer_events: EventHandle }
+        let object_core = bcs::to_bytes(&(
+            OBJECT_INIT_GUID_CREATION_NUM + 1,
+            owner,
+            true,
+            (0u64, OBJECT_INIT_GUID_CREATION_NUM, address),
+        ))
+        .expect("serialize ObjectCore");
+        let group: BTreeMap<StructTag, Vec<u8>> =
+            BTreeMap::from([(framework_struct("object", "ObjectCore", vec![]), object_core)]);
+        if let Ok(group_key) = StateKey::resource_group(&address, &framework_struct("object", "ObjectGroup", vec![])) {
+            let bytes = bcs::to_bytes(&group).expect("serialize ObjectGroup");
+            self.set_state_value(group_key, StateValue::new_legacy(bytes.into()));
+        }
+        address
+    }
+
+    /// Keep the object pool in sync with writes to `0x1::object::ObjectGroup`
+    /// resource groups.
+    fn index_object(&mut self, state_key: &StateKey, bytes: Option<&Bytes>) {
+        let StateKeyInner::AccessPath(access_path) = state_key.inner() else {
+            return;
+        };
+        let Path::ResourceGroup(group_tag) = access_path.get_path() else {
+            return;
+        };
+        if group_tag != framework_struct("object", "ObjectGroup", vec![]) {
+            return;
+        }
+        let is_object = bytes
+            .and_then(|bytes| bcs::from_bytes::<BTreeMap<StructTag, Bytes>>(bytes).ok())
+            .is_some_and(|group| group.contains_key(&framework_struct("object", "ObjectCore", vec![])));
+        let was_object = if is_object {
+            !self.objects.insert(access_path.address)
+        } else {
+            self.objects.remove(&access_path.address)
+        };
+        if was_object != is_object {
+            self.record(|| JournalEntry::Object(access_path.address, was_object));
+        }
     }
 
     // Apply WriteSet to in-memory state; mirror modules from code access paths.
     pub fn apply_write_set(&mut self, write_set: &WriteSet) {
         for (state_key, write_op) in write_set.write_op_iter() {
+            self.track_deletion(state_key, write_op.bytes().is_none());
+            self.index_object(state_key, write_op.bytes());
             match state_key.inner() {
                 StateKeyInner::TableItem { handle, key } => {
-                    let table_handle = TableHandle(handle.0);
-                    match write_op.bytes() {
-                        Some(bytes) => {
-                            self.tables.insert((table_handle, key.clone()), bytes.clone());
-                        }
-                        None => {
-                            self.tables.remove(&(table_handle, key.clone()));
-                        }
-                    }
+                    self.write_table((TableHandle(handle.0), key.clone()), write_op.bytes().cloned());
                 }
                 StateKeyInner::AccessPath(access_path) => {
                     // Always update kv_state
-                    match write_op.as_state_value() {
-                        Some(state_value) => {
-                            self.kv_state.insert(state_key.clone(), state_value);
-                        }
-                        None => {
-                            self.kv_state.remove(state_key);
-                        }
-                    }
+                    self.write_kv(state_key.clone(), write_op.as_state_value());
 
                     // If module code, also maintain modules cache
                     if access_path.is_code() {
                         if let Some(module_id) = access_path.try_get_module_id() {
-                            match write_op.bytes() {
-                                Some(bytes) => {
-                                    self.modules.insert(module_id, bytes.clone());
-                                }
-                                None => {
-                                    self.modules.remove(&module_id);
-                                }
-                            }
+                            self.write_module(module_id, write_op.bytes().cloned());
                         }
                     }
                 }
-                StateKeyInner::Raw(_) => match write_op.as_state_value() {
-                    Some(state_value) => {
-                        self.kv_state.insert(state_key.clone(), state_value);
-                    }
-                    None => {
-                        self.kv_state.remove(state_key);
-                    }
-                },
+                StateKeyInner::Raw(_) => {
+                    self.write_kv(state_key.clone(), write_op.as_state_value());
+                }
             }
         }
     }
@@ -626,8 +1091,14 @@ impl AptosCustomState {
     pub fn deploy_module_bytes(&mut self, module_id: ModuleId, code: Vec<u8>) {
         let bytes = Bytes::from(code);
         let state_key = StateKey::module(module_id.address(), module_id.name());
-        self.modules.insert(module_id.clone(), bytes.clone());
-        self.kv_state.insert(state_key, StateValue::new_legacy(bytes));
+        self.write_module(module_id.clone(), Some(bytes.clone()));
+        self.write_kv(state_key, Some(StateValue::new_legacy(bytes)));
+    }
+
+    pub fn remove_module(&mut self, module_id: &ModuleId) {
+        let state_key = StateKey::module(module_id.address(), module_id.name());
+        self.write_module(module_id.clone(), None);
+        self.write_kv(state_key, None);
     }
 
     pub fn module_bytes(&self) -> &HashMap<ModuleId, Bytes> {
@@ -637,8 +1108,8 @@ impl AptosCustomState {
     // Calculate total bytecode instructions across all modules
     pub fn total_bytecode_instructions(&self) -> usize {
         let mut total = 0;
-        for bytes in self.modules.values() {
-            if let Ok(module) = CompiledModule::deserialize(bytes) {
+        for module_id in self.modules.keys() {
+            if let Some(module) = self.deserialized_module(module_id) {
                 for func_def in module.function_defs() {
                     if let Some(code_unit) = &func_def.code {
                         total += code_unit.code.len();
@@ -649,11 +1120,45 @@ impl AptosCustomState {
         total
     }
 
-    // Calculate total possible edges (control flow transitions) across all modules
+    /// Assign collision-free edge ID ranges to every loaded function. Modules
+    /// in `priority` are laid out first so they keep distinct slots even
+    /// when the framework overflows the map.
+    pub fn assign_edge_ids(&mut self, map_size: usize, priority: &[ModuleId]) {
+        let mut layout = EdgeLayout::new(map_size);
+        let mut rest: Vec<&ModuleId> = self.modules.keys().filter(|id| !priority.contains(id)).collect();
+        rest.sort();
+        for module_id in priority.iter().chain(rest) {
+            if !self.modules.contains_key(module_id) {
+                continue;
+            }
+            if let Some(module) = self.deserialized_module(module_id) {
+                layout.assign_module(&module);
+            }
+        }
+        if !layout.is_collision_free() {
+            warn!(
+                "{} edges exceed coverage map size {}; framework edges will collide",
+                layout.total_edges(),
+                map_size
+            );
+        }
+        self.edge_layout = Arc::new(layout);
+    }
+
+    pub fn edge_layout(&self) -> &EdgeLayout {
+        &self.edge_layout
+    }
+
+    // Edge IDs handed out by the layout, minus the edges constant
+    // propagation proves dead; falls back to counting CFG successors when no
+    // layout has been assigned yet
     pub fn total_possible_edges(&self) -> usize {
+        if !self.edge_layout.is_empty() {
+            return self.edge_layout.live_edges();
+        }
         let mut total = 0;
-        for bytes in self.modules.values() {
-            if let Ok(module) = CompiledModule::deserialize(bytes) {
+        for module_id in self.modules.keys() {
+            if let Some(module) = self.deserialized_module(module_id) {
                 for func_def in module.function_defs() {
                     if let Some(code_unit) = &func_def.code {
                         let cfg = VMControlFlowGraph::new(&code_unit.code);
@@ -667,3 +1172,84 @@ impl AptosCustomState {
         total
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use aptos_move_core_types::account_address::AccountAddress;
+    use aptos_move_core_types::identifier::Identifier;
+    use aptos_move_core_types::language_storage::ModuleId;
+    use aptos_types::state_store::state_key::StateKey;
+    use aptos_types::state_store::state_value::StateValue;
+
+    use super::AptosCustomState;
+
+    fn key(name: &str) -> StateKey {
+        StateKey::raw(name.as_bytes())
+    }
+
+    fn value(bytes: &[u8]) -> StateValue {
+        StateValue::new_legacy(bytes.to_vec().into())
+    }
+
+    fn module_id() -> ModuleId {
+        ModuleId::new(
+            AccountAddress::from_hex_literal("0xcafe").unwrap(),
+            Identifier::new("journal").unwrap(),
+        )
+    }
+
+    #[test]
+    fn restore_undoes_writes_since_the_snapshot() {
+        let mut state = AptosCustomState::new_default();
+        state.set_state_value(key("kept"), value(b"before"));
+        let snapshot = state.snapshot();
+        state.set_state_value(key("kept"), value(b"after"));
+        state.set_state_value(key("added"), value(b"new"));
+        state.restore(snapshot);
+        assert_eq!(state.get_state_value(&key("kept")), Some(value(b"before")));
+        assert_eq!(state.get_state_value(&key("added")), None);
+        assert!(state.journal.is_empty());
+    }
+
+    #[test]
+    fn nested_snapshots_restore_independently() {
+        let mut state = AptosCustomState::new_default();
+        let outer = state.snapshot();
+        state.set_state_value(key("outer"), value(b"1"));
+        let inner = state.snapshot();
+        state.set_state_value(key("inner"), value(b"2"));
+        state.restore(inner);
+        assert_eq!(state.get_state_value(&key("outer")), Some(value(b"1")));
+        assert_eq!(state.get_state_value(&key("inner")), None);
+        state.restore(outer);
+        assert_eq!(state.get_state_value(&key("outer")), None);
+    }
+
+    #[test]
+    fn discard_keeps_writes_and_stops_journaling() {
+        let mut state = AptosCustomState::new_default();
+        let snapshot = state.snapshot();
+        state.set_state_value(key("kept"), value(b"1"));
+        state.discard(snapshot);
+        assert_eq!(state.get_state_value(&key("kept")), Some(value(b"1")));
+        assert!(state.journal.is_empty());
+        state.set_state_value(key("kept"), value(b"2"));
+        assert!(state.journal.is_empty());
+    }
+
+    #[test]
+    fn restore_unpublishes_modules() {
+        let mut state = AptosCustomState::new_default();
+        let generation = state.module_generation();
+        let snapshot = state.snapshot();
+        state.deploy_module_bytes(module_id(), b"code".to_vec());
+        assert!(state.module_bytes().contains_key(&module_id()));
+        state.restore(snapshot);
+        assert!(!state.module_bytes().contains_key(&module_id()));
+        assert_eq!(
+            state.get_state_value(&StateKey::module(module_id().address(), module_id().name())),
+            None
+        );
+        assert_ne!(state.module_generation(), generation);
+    }
+}
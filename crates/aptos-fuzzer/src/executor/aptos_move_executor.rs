@@ -1,62 +1,358 @@
-use std::marker::PhantomData;
+use std::collections::{BTreeSet, HashMap, HashSet};
 
 use aptos_move_core_types::account_address::AccountAddress;
+use aptos_move_core_types::language_storage::{ModuleId, TypeTag};
+use aptos_move_core_types::u256::U256;
 use aptos_move_core_types::vm_status::{StatusCode, VMStatus};
-use aptos_types::transaction::{ExecutionStatus, TransactionPayload, TransactionStatus};
+use aptos_types::contract_event::ContractEvent;
+use aptos_types::state_store::state_key::inner::StateKeyInner;
+use aptos_types::transaction::{EntryFunction, ExecutionStatus, TransactionPayload, TransactionStatus};
+use aptos_types::write_set::WriteSet;
 use aptos_vm::aptos_vm::ExecOutcomeKind;
 use aptos_vm::AptosVM;
+use libafl::corpus::Corpus;
 use libafl::executors::{Executor, ExitKind, HasObservers};
 use libafl::observers::map::{HitcountsMapObserver, OwnedMapObserver};
-use libafl::state::HasExecutions;
-use libafl_bolts::tuples::RefIndexable;
+use libafl::observers::{CanTrack, ExplicitTracking};
+use libafl::state::{HasCorpus, HasExecutions};
+use libafl_bolts::tuples::{Handle, Handled, MatchNameRef, RefIndexable};
 use libafl_bolts::AsSliceMut;
-use log::warn;
+use log::{debug, warn};
 
-use crate::concolic::{RuntimeIssue, SymbolicMoveTracer};
+#[cfg(feature = "concolic")]
+use crate::abort_target::AbortGuards;
+#[cfg(feature = "concolic")]
+use crate::arg_constraint::ArgConstraint;
+use crate::bisect::ReplayOutcome;
+use crate::concolic::{RuntimeIssue, SymbolicMoveTracer, TracedLocation};
+use crate::error::FuzzerError;
 use crate::executor::aptos_custom_state::AptosCustomState;
-use crate::executor::custom_state_view::CustomStateView;
-use crate::executor::types::TransactionResult;
-use crate::observers::{AbortCodeObserver, ShiftOverflowObserver};
-use crate::state::MAP_SIZE;
+use crate::executor::custom_state_view::{CustomStateView, PersistentCodeStorage};
+use crate::executor::panic_capture;
+use crate::executor::types::{BudgetExhausted, DifferentialConfig, ExecutionBudget, TransactionResult};
+use crate::input::BlockContext;
+use crate::module_code::publish_package_payload;
+use crate::observers::{
+    AbortCodeObserver, BranchDistanceObserver, DirtyMapObserver, EmittedEvent, EventsObserver,
+    InstructionHistogramObserver, RuntimeIssueObserver, ShiftOverflowObserver, VmStatusObserver, WriteSetObserver,
+    WrittenResource,
+};
+use crate::oracle::Violation;
+use crate::runtime_detector::{RuntimeDetector, RuntimeDetectorConfig};
+use crate::script_sequence::{compile_sequence, ScriptSequence, SequenceCall};
+use crate::state::{FunctionParameter, PublicFunctionTarget, MAP_SIZE};
+use crate::struct_layout::{framework_struct, resource_changes, ResourceChange};
+use crate::supervisor::track_in_flight;
+use crate::trace::{AbortSite, ExecutionTrace};
 use crate::{AptosFuzzerInput, AptosFuzzerState};
 
+/// Gas limit for calls to view-style invariant functions and view functions
+const INVARIANT_MAX_GAS: u64 = 1_000_000;
+
+/// The edge map, tracked so the map feedback attaches `MapIndexesMetadata`
+/// (every slot an input covers) and `MapNoveltiesMetadata` (the slots it
+/// was first to reach) to the testcases it keeps
+pub type EdgesObserver = ExplicitTracking<DirtyMapObserver, true, true>;
+
 // Type aliases to simplify complex observer tuple types
 type AptosObservers = (
-    HitcountsMapObserver<OwnedMapObserver<u8>>,
-    (AbortCodeObserver, (ShiftOverflowObserver, ())),
+    EdgesObserver,
+    (
+        AbortCodeObserver,
+        (
+            ShiftOverflowObserver,
+            (
+                BranchDistanceObserver,
+                (
+                    EventsObserver,
+                    (
+                        WriteSetObserver,
+                        (
+                            RuntimeIssueObserver,
+                            (InstructionHistogramObserver, (VmStatusObserver, ())),
+                        ),
+                    ),
+                ),
+            ),
+        ),
+    ),
 );
 
-pub struct AptosMoveExecutor<EM, Z> {
+/// Handles of the observers after the edge map, which the executor looks up
+/// by name so adding an observer to the tuple doesn't shift the others
+struct ObserverHandles {
+    abort: Handle<AbortCodeObserver>,
+    shift: Handle<ShiftOverflowObserver>,
+    distance: Handle<BranchDistanceObserver>,
+    events: Handle<EventsObserver>,
+    write_set: Handle<WriteSetObserver>,
+    runtime_issues: Handle<RuntimeIssueObserver>,
+    histogram: Handle<InstructionHistogramObserver>,
+    vm_status: Handle<VmStatusObserver>,
+}
+
+/// Reference side of differential execution: a second VM over a copy of the
+/// state configured differently.
+struct ReferenceRunner {
+    vm: AptosVM,
+    state: AptosCustomState,
+    tracer: SymbolicMoveTracer,
+    code_storage: Option<PersistentCodeStorage>,
+}
+
+/// What an execution did, as compared between the two sides of differential
+/// execution.
+#[derive(Debug, PartialEq)]
+enum ExecutionSummary {
+    Executed {
+        write_set: WriteSet,
+        events: Vec<ContractEvent>,
+    },
+    Aborted(u64),
+    Failed(StatusCode),
+}
+
+impl ExecutionSummary {
+    fn from_result(result: &core::result::Result<TransactionResult, VMStatus>) -> Self {
+        match result {
+            Ok(TransactionResult {
+                status: TransactionStatus::Keep(ExecutionStatus::MoveAbort { code, .. }),
+                ..
+            }) => Self::Aborted(*code),
+            Ok(result) => Self::Executed {
+                write_set: result.write_set.clone(),
+                events: result.events.clone(),
+            },
+            Err(VMStatus::MoveAbort(_, code)) => Self::Aborted(*code),
+            Err(status) => Self::Failed(status.status_code()),
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            Self::Executed { events, .. } => format!("executed ({} events)", events.len()),
+            Self::Aborted(code) => format!("aborted with code {}", code),
+            Self::Failed(status) => format!("failed with {:?}", status),
+        }
+    }
+}
+
+pub struct AptosMoveExecutor {
     aptos_vm: AptosVM,
-    _phantom: PhantomData<(EM, Z)>,
+    /// Code storage over the modules of the state inputs run against, kept
+    /// until a module is published or removed
+    code_storage: Option<PersistentCodeStorage>,
     success_count: u64,
     error_count: u64,
     observers: AptosObservers,
-    prev_loc: u64,
+    handles: ObserverHandles,
     total_instructions_executed: u64,
     symbolic_tracer: SymbolicMoveTracer,
     pending_runtime_issues: Vec<RuntimeIssue>,
+    pending_locations: Vec<TracedLocation>,
+    pending_branch_distances: HashMap<(u64, u16), u64>,
+    pending_abort_site: Option<AbortSite>,
+    edge_indices: Vec<usize>,
+    budget_exhausted: bool,
+    /// Set when the last execution panicked inside the VM or the tracer
+    panicked: bool,
+    /// Call the target's `invariant_*`/`check_*` functions after each run
+    invariant_functions_enabled: bool,
+    /// Report view functions that a transaction makes abort
+    view_functions_enabled: bool,
+    /// Re-run admin transactions from an unprivileged sender
+    access_control_enabled: bool,
+    /// Inputs, by the functions they call, already reported for missing
+    /// access control
+    access_control_reported: HashSet<String>,
+    reference: Option<ReferenceRunner>,
+    /// Called with every runtime issue the tracer reports
+    runtime_issue_hook: Option<Box<dyn FnMut(&RuntimeIssue)>>,
+    /// Count executed instructions per function for the profile report
+    instruction_profiling: bool,
+    /// Sites of the symbolic divergences already reported by the self-check
+    #[cfg(feature = "concolic")]
+    divergences_reported: HashSet<(String, String, u16)>,
+    /// Branches from the top of the worklist the solver is asked to flip
+    #[cfg(feature = "concolic")]
+    branch_flips: usize,
+    /// Generation of the branch coverage the flip targets were picked at
+    #[cfg(feature = "concolic")]
+    flip_generation: Option<u64>,
 }
 
-impl<EM, Z> AptosMoveExecutor<EM, Z> {
+impl AptosMoveExecutor {
     pub fn new() -> Self {
+        Self::with_map_size(MAP_SIZE)
+    }
+
+    /// Create an executor whose edge map has `map_size` entries. Must match the
+    /// map size the `AptosFuzzerState` was created with.
+    pub fn with_map_size(map_size: usize) -> Self {
         AptosVM::set_concurrency_level_once(1);
         let env = super::aptos_custom_state::AptosCustomState::default_env();
-        let edges = OwnedMapObserver::new("edges", vec![0u8; MAP_SIZE]);
-        let edges = HitcountsMapObserver::new(edges);
+        let edges = OwnedMapObserver::new("edges", vec![0u8; map_size]);
+        let edges = DirtyMapObserver::new(HitcountsMapObserver::new(edges))
+            .track_indices()
+            .track_novelties();
         let abort_obs = AbortCodeObserver::new();
         let shift_obs = ShiftOverflowObserver::new();
+        let distance_obs = BranchDistanceObserver::new();
+        let events_obs = EventsObserver::new();
+        let write_set_obs = WriteSetObserver::new();
+        let runtime_issue_obs = RuntimeIssueObserver::new();
+        let histogram_obs = InstructionHistogramObserver::new();
+        let vm_status_obs = VmStatusObserver::new();
+        let handles = ObserverHandles {
+            abort: abort_obs.handle(),
+            shift: shift_obs.handle(),
+            distance: distance_obs.handle(),
+            events: events_obs.handle(),
+            write_set: write_set_obs.handle(),
+            runtime_issues: runtime_issue_obs.handle(),
+            histogram: histogram_obs.handle(),
+            vm_status: vm_status_obs.handle(),
+        };
         Self {
             aptos_vm: AptosVM::new_fuzzer(&env),
-            _phantom: PhantomData,
+            code_storage: None,
             success_count: 0,
             error_count: 0,
-            observers: (edges, (abort_obs, (shift_obs, ()))),
-            prev_loc: 0,
+            observers: (
+                edges,
+                (
+                    abort_obs,
+                    (
+                        shift_obs,
+                        (
+                            distance_obs,
+                            (
+                                events_obs,
+                                (write_set_obs, (runtime_issue_obs, (histogram_obs, (vm_status_obs, ())))),
+                            ),
+                        ),
+                    ),
+                ),
+            ),
+            handles,
             total_instructions_executed: 0,
             symbolic_tracer: SymbolicMoveTracer::new(),
             pending_runtime_issues: Vec::new(),
+            pending_locations: Vec::new(),
+            pending_branch_distances: HashMap::new(),
+            pending_abort_site: None,
+            edge_indices: Vec::new(),
+            budget_exhausted: false,
+            panicked: false,
+            invariant_functions_enabled: true,
+            view_functions_enabled: false,
+            access_control_enabled: false,
+            access_control_reported: HashSet::new(),
+            reference: None,
+            runtime_issue_hook: None,
+            instruction_profiling: false,
+            #[cfg(feature = "concolic")]
+            divergences_reported: HashSet::new(),
+            #[cfg(feature = "concolic")]
+            branch_flips: 0,
+            #[cfg(feature = "concolic")]
+            flip_generation: None,
+        }
+    }
+
+    /// Limit every execution to the given wall-clock time and/or number of
+    /// executed instructions. Runs that exceed it are reported as
+    /// `ExitKind::Timeout`.
+    pub fn set_execution_budget(&mut self, budget: ExecutionBudget) {
+        self.symbolic_tracer.set_budget(budget);
+        if let Some(reference) = self.reference.as_mut() {
+            reference.tracer.set_budget(budget);
+        }
+    }
+
+    /// Run every input a second time under `config` and report any difference
+    /// in status, write set or events. `state` must be the state inputs are
+    /// executed against.
+    pub fn enable_differential(&mut self, state: &AptosCustomState, config: &DifferentialConfig) {
+        let reference_state = state.reconfigured(config);
+        let vm = AptosVM::new_fuzzer(&reference_state.env());
+        self.reference = Some(ReferenceRunner {
+            vm,
+            state: reference_state,
+            tracer: SymbolicMoveTracer::new(),
+            code_storage: None,
+        });
+    }
+
+    /// Execute `transaction` on the reference side. Returns `None` when
+    /// differential mode is off or the reference run did not complete.
+    fn run_reference(
+        &mut self,
+        transaction: &TransactionPayload,
+        block_context: Option<&BlockContext>,
+        sender: Option<AccountAddress>,
+    ) -> Option<ExecutionSummary> {
+        let reference = self.reference.as_mut()?;
+        let saved_state_values = block_context.map(|block_context| reference.state.apply_block_context(block_context));
+        let summary = Self::execute_reference(reference, transaction, sender);
+        if let Some(saved) = saved_state_values {
+            reference.state.restore_state_values(saved);
         }
+        summary
+    }
+
+    fn execute_reference(
+        reference: &mut ReferenceRunner,
+        transaction: &TransactionPayload,
+        sender: Option<AccountAddress>,
+    ) -> Option<ExecutionSummary> {
+        let code_storage = PersistentCodeStorage::refresh(&mut reference.code_storage, &reference.state);
+        reference.tracer.reset();
+        let vm = &reference.vm;
+        let reference_state = &reference.state;
+        let tracer = &mut reference.tracer;
+        let (result, _pcs, _shifts, _outcome) = panic_capture::catch(|| {
+            code_storage.with_storage(|code_storage| {
+                vm.execute_user_payload_no_checking_with_tracer(
+                    reference_state,
+                    code_storage,
+                    transaction,
+                    sender,
+                    tracer,
+                )
+            })
+        })
+        .ok()?;
+        Some(match result {
+            Ok((write_set, events)) => ExecutionSummary::Executed { write_set, events },
+            Err(VMStatus::MoveAbort(_, code)) => ExecutionSummary::Aborted(code),
+            Err(status) => ExecutionSummary::Failed(status.status_code()),
+        })
+    }
+
+    /// Compare the primary result with the reference run of the same input.
+    fn check_divergence(
+        &mut self,
+        transaction: &TransactionPayload,
+        block_context: Option<&BlockContext>,
+        sender: Option<AccountAddress>,
+        result: &core::result::Result<TransactionResult, VMStatus>,
+    ) -> Option<String> {
+        if self.reference.is_none() || self.budget_exhausted || self.panicked {
+            return None;
+        }
+        let primary = ExecutionSummary::from_result(result);
+        let reference = self.run_reference(transaction, block_context, sender)?;
+        if primary == reference {
+            return None;
+        }
+        let detail = match (&primary, &reference) {
+            (ExecutionSummary::Executed { .. }, ExecutionSummary::Executed { .. }) => {
+                "write sets or events differ".to_string()
+            }
+            _ => format!("primary {}, reference {}", primary.describe(), reference.describe()),
+        };
+        Some(detail)
     }
 
     pub fn total_instructions_executed(&self) -> u64 {
@@ -85,13 +381,831 @@ impl<EM, Z> AptosMoveExecutor<EM, Z> {
         hash
     }
 
-    pub fn pc_observer(&self) -> &HitcountsMapObserver<OwnedMapObserver<u8>> {
+    pub fn set_invariant_functions_enabled(&mut self, enabled: bool) {
+        self.invariant_functions_enabled = enabled;
+    }
+
+    pub fn set_view_functions_enabled(&mut self, enabled: bool) {
+        self.view_functions_enabled = enabled;
+    }
+
+    pub fn set_access_control_enabled(&mut self, enabled: bool) {
+        self.access_control_enabled = enabled;
+    }
+
+    /// Choose the built-in runtime detectors and their thresholds.
+    pub fn set_runtime_detector_config(&mut self, config: &RuntimeDetectorConfig) {
+        self.symbolic_tracer.configure_detectors(config);
+    }
+
+    /// Run `detector` alongside the built-in runtime detectors.
+    pub fn register_runtime_detector(&mut self, detector: Box<dyn RuntimeDetector>) {
+        self.symbolic_tracer.register_detector(detector);
+    }
+
+    /// Aggregate executed instructions per function and pc into the state's
+    /// profile (see `AptosFuzzerState::hot_functions`). Off by default: it
+    /// costs a hash map update per executed instruction.
+    pub fn set_instruction_profiling(&mut self, enabled: bool) {
+        self.instruction_profiling = enabled;
+    }
+
+    pub fn set_runtime_issue_hook(&mut self, hook: Box<dyn FnMut(&RuntimeIssue)>) {
+        self.runtime_issue_hook = Some(hook);
+    }
+
+    #[cfg(feature = "concolic")]
+    /// Steer executions towards the aborts in `guards`: every run that
+    /// reaches a guarding branch asks the solver for arguments flipping it,
+    /// and the answers are queued in the state's `AbortSearch`.
+    pub fn set_abort_guards(&mut self, guards: Option<AbortGuards>) {
+        self.symbolic_tracer.guidance_mut().set_abort_guards(guards);
+    }
+
+    #[cfg(feature = "concolic")]
+    /// Compare the symbolic stack with the concrete one before every
+    /// instruction, recording the first mismatch of each run in the state
+    /// once per site.
+    pub fn set_concolic_self_check(&mut self, enabled: bool) {
+        self.symbolic_tracer.guidance_mut().set_self_check(enabled);
+    }
+
+    #[cfg(feature = "concolic")]
+    /// Ask the solver to flip the `count` one-sided branches at the top of
+    /// the state's branch worklist whenever a run reaches one of them with a
+    /// symbolic condition, queueing the answers in its `BranchCoverage`.
+    pub fn set_branch_flips(&mut self, count: usize) {
+        self.branch_flips = count;
+        self.flip_generation = None;
+        self.symbolic_tracer.guidance_mut().set_flip_targets(HashSet::new());
+    }
+
+    #[cfg(feature = "concolic")]
+    /// Have solver queries assume the numeric argument constraints of the
+    /// entry function an input calls.
+    pub fn set_arg_constraints(&mut self, constraints: Vec<ArgConstraint>) {
+        self.symbolic_tracer.guidance_mut().set_arg_constraints(constraints);
+    }
+
+    /// Run the `init_module` of each target module not yet initialized, as
+    /// publishing it does, then the state's initializers, and keep their
+    /// effects in the base state. A failing initializer is reported and
+    /// leaves the state as it was. Returns how many succeeded.
+    pub fn run_initializers(&mut self, state: &mut AptosFuzzerState) -> usize {
+        let mut succeeded = 0;
+        for module_id in state.take_init_modules() {
+            let name = format!("{}::init_module", module_id);
+            let Some(code) = state.aptos_state().module_bytes().get(&module_id).cloned() else {
+                continue;
+            };
+            // The VM only runs `init_module` when the module is new, so it is
+            // published again over a state without it, in a package of its
+            // own, from its address
+            let mut unpublished = state.aptos_state().clone();
+            unpublished.remove_module(&module_id);
+            let payload = publish_package_payload(module_id.name().as_str(), &code);
+            let sender = *module_id.address();
+            match self.execute_transaction(payload, &unpublished, Some(sender)).0 {
+                Ok(TransactionResult {
+                    status: TransactionStatus::Keep(ExecutionStatus::Success),
+                    write_set,
+                    ..
+                }) => {
+                    state.aptos_state_mut().apply_write_set(&write_set);
+                    state.observe_write_set(&write_set);
+                    debug!("ran {} as {}", name, sender);
+                    succeeded += 1;
+                }
+                Ok(result) => warn!("{} failed: {:?}", name, result.status),
+                Err(status) => warn!("{} failed: {:?}", name, status),
+            }
+        }
+        for call in state.initializers().to_vec() {
+            let name = format!("{}::{}", call.module_id, call.function);
+            let sender = state.initializer_admin().unwrap_or(*call.module_id.address());
+            let payload = TransactionPayload::EntryFunction(EntryFunction::new(
+                call.module_id.clone(),
+                call.function.clone(),
+                Vec::new(),
+                call.args.clone(),
+            ));
+            match self.execute_transaction(payload, state.aptos_state(), Some(sender)).0 {
+                Ok(TransactionResult {
+                    status: TransactionStatus::Keep(ExecutionStatus::Success),
+                    write_set,
+                    ..
+                }) => {
+                    state.aptos_state_mut().apply_write_set(&write_set);
+                    state.observe_write_set(&write_set);
+                    debug!("ran initializer {} as {}", name, sender);
+                    succeeded += 1;
+                }
+                Ok(result) => warn!("initializer {} failed: {:?}", name, result.status),
+                Err(status) => warn!("initializer {} failed: {:?}", name, status),
+            }
+        }
+
+        // Initializers are not part of any fuzzed execution
+        self.discard_pending();
+        succeeded
+    }
+
+    /// Run the corpus against the base state in order and keep the effects
+    /// of every entry that succeeds, building the state later entries and
+    /// an upgrade of the targets start from. Returns how many were kept.
+    pub fn commit_corpus(&mut self, state: &mut AptosFuzzerState) -> usize {
+        let inputs: Vec<AptosFuzzerInput> = state
+            .corpus()
+            .ids()
+            .filter_map(|id| state.corpus().cloned_input_for_id(id).ok())
+            .collect();
+        let mut committed = 0;
+        for input in inputs {
+            let saved_state_values = input
+                .block_context()
+                .map(|block_context| state.aptos_state_mut().apply_block_context(block_context));
+            let (result, ..) =
+                self.execute_transaction(input.payload().clone(), state.aptos_state(), Some(input.sender()));
+            if let Some(saved) = saved_state_values {
+                state.aptos_state_mut().restore_state_values(saved);
+            }
+            if let Ok(TransactionResult {
+                status: TransactionStatus::Keep(ExecutionStatus::Success),
+                write_set,
+                ..
+            }) = result
+            {
+                state.aptos_state_mut().apply_write_set(&write_set);
+                state.observe_write_set(&write_set);
+                committed += 1;
+            }
+        }
+        self.discard_pending();
+        committed
+    }
+
+    /// Call the target's invariant functions against the base state as it
+    /// is, e.g. right after an upgrade of the targets.
+    pub fn check_invariants(&mut self, state: &mut AptosFuzzerState) -> Vec<Violation> {
+        self.check_invariant_functions(state, &WriteSet::default())
+    }
+
+    /// Make sure the object pool holds an object for every `Object<T>` type
+    /// taken by a fuzzed function, then seed those functions. Objects of
+    /// bare `ObjectCore` are created directly; for any other `T`, entry
+    /// functions callable with default arguments are tried as constructors
+    /// (those of `T`'s own module first) and the first one that creates a `T`
+    /// object is kept. Returns how many object types have no object.
+    pub fn create_objects(&mut self, state: &mut AptosFuzzerState) -> usize {
+        let object_core = framework_struct("object", "ObjectCore", vec![]);
+        let mut missing = 0;
+        for resource in state.object_types() {
+            if !state.aptos_state().object_addresses(&resource).is_empty() {
+                continue;
+            }
+            if resource == object_core {
+                let owner = state.initializer_admin().unwrap_or(AccountAddress::ONE);
+                let address = state.aptos_state_mut().create_object(owner);
+                state.add_pool_address(address);
+                debug!("created object {} owned by {}", address, owner);
+                continue;
+            }
+
+            let mut constructors: Vec<&PublicFunctionTarget> = state
+                .public_functions()
+                .iter()
+                .filter(|function| function.is_entry())
+                .collect();
+            constructors.sort_by_key(|function| {
+                function.module_id().address() != &resource.address ||
+                    function.module_id().name() != resource.module.as_ident_str()
+            });
+            let mut created = None;
+            for function in constructors {
+                let Ok(payload) = AptosFuzzerState::entry_payload_from_function(function) else {
+                    continue;
+                };
+                let sender = state.initializer_admin().unwrap_or(*function.module_id().address());
+                let Ok(TransactionResult {
+                    status: TransactionStatus::Keep(ExecutionStatus::Success),
+                    write_set,
+                    ..
+                }) = self.execute_transaction(payload, state.aptos_state(), Some(sender)).0
+                else {
+                    continue;
+                };
+                let mut trial = state.aptos_state().clone();
+                trial.apply_write_set(&write_set);
+                if !trial.object_addresses(&resource).is_empty() {
+                    let constructor = format!("{}::{}", function.module_id(), function.name());
+                    created = Some((constructor, trial, write_set));
+                    break;
+                }
+            }
+            match created {
+                Some((constructor, trial, write_set)) => {
+                    *state.aptos_state_mut() = trial;
+                    state.observe_write_set(&write_set);
+                    debug!("created {} object with {}", resource.to_canonical_string(), constructor);
+                }
+                None => {
+                    warn!("no constructor found for {} objects", resource.to_canonical_string());
+                    missing += 1;
+                }
+            }
+        }
+        state.add_object_seeds();
+
+        // Constructors are not part of any fuzzed execution
+        self.discard_pending();
+        missing
+    }
+
+    /// Call the target's `invariant_*`/`check_*` functions against the state
+    /// produced by the last transaction. An abort, or `false` returned by a
+    /// view function, is reported as a violation.
+    fn check_invariant_functions(&mut self, state: &mut AptosFuzzerState, write_set: &WriteSet) -> Vec<Violation> {
+        if !self.invariant_functions_enabled || state.invariant_functions().is_empty() {
+            return Vec::new();
+        }
+        let snapshot = state.aptos_state_mut().snapshot();
+        state.aptos_state_mut().apply_write_set(write_set);
+        let post = state.aptos_state();
+
+        let mut violations = Vec::new();
+        let mut suppressed = Vec::new();
+        for function in state.invariant_functions() {
+            let failure = if function.is_view() && function.parameters().is_empty() {
+                let output = AptosVM::execute_view_function(
+                    &CustomStateView::new(post),
+                    function.module_id().clone(),
+                    function.name().clone(),
+                    Vec::new(),
+                    Vec::new(),
+                    INVARIANT_MAX_GAS,
+                );
+                match output.values {
+                    Ok(values) if values.len() == 1 && bcs::from_bytes::<bool>(&values[0]).ok() == Some(false) => {
+                        Some("returned false".to_string())
+                    }
+                    Ok(_) => None,
+                    Err(err) => Some(format!("failed: {:?}", err)),
+                }
+            } else {
+                let payload = match Self::invariant_payload(function, post) {
+                    Some(Ok(payload)) => payload,
+                    Some(Err(err)) => {
+                        suppressed.push(err);
+                        continue;
+                    }
+                    None => continue,
+                };
+                match self.execute_transaction(payload, post, Some(AccountAddress::ONE)).0 {
+                    Ok(TransactionResult {
+                        status: TransactionStatus::Keep(ExecutionStatus::MoveAbort { code, .. }),
+                        ..
+                    }) |
+                    Err(VMStatus::MoveAbort(_, code)) => Some(format!("aborted with code {}", code)),
+                    _ => None,
+                }
+            };
+            if let Some(message) = failure {
+                let name = format!("{}::{}", function.module_id(), function.name());
+                violations.push(Violation {
+                    oracle: format!("invariant {}", name),
+                    message: format!("{} {}", name, message),
+                });
+            }
+        }
+        state.aptos_state_mut().restore(snapshot);
+        for err in &suppressed {
+            state.record_suppressed_error(err);
+        }
+
+        // Invariant calls are not part of the fuzzed execution
+        self.discard_pending();
+        violations
+    }
+
+    /// Call the target's view functions before and after a transaction that
+    /// changed state. A view that only aborts afterwards is reported when
+    /// view checks are enabled, and a change of its results that breaks a
+    /// monotonicity property always is.
+    fn check_view_functions(
+        &self,
+        state: &mut AptosFuzzerState,
+        input: &AptosFuzzerInput,
+        write_set: &WriteSet,
+    ) -> Vec<Violation> {
+        let properties = state.view_properties().to_vec();
+        let views: Vec<PublicFunctionTarget> = state
+            .public_functions()
+            .iter()
+            .filter(|function| {
+                function.is_view() &&
+                    (self.view_functions_enabled ||
+                        properties
+                            .iter()
+                            .any(|property| property.is_about(function.module_id(), function.name())))
+            })
+            .cloned()
+            .collect();
+        if views.is_empty() || write_set.write_op_iter().next().is_none() {
+            return Vec::new();
+        }
+        let sender = input.sender();
+        let before: Vec<_> = views
+            .iter()
+            .map(|view| Self::call_view(state.aptos_state(), view, sender))
+            .collect();
+        let snapshot = state.aptos_state_mut().snapshot();
+        state.aptos_state_mut().apply_write_set(write_set);
+        let post = state.aptos_state();
+
+        let mut violations = Vec::new();
+        for (view, before) in views.iter().zip(before) {
+            let Some(before) = before else {
+                continue;
+            };
+            let Some(after) = Self::call_view(post, view, sender) else {
+                continue;
+            };
+            let name = format!("{}::{}", view.module_id(), view.name());
+            let messages = match (before, after) {
+                (Ok(_), Err(err)) if self.view_functions_enabled => {
+                    vec![format!("{} aborts after the transaction: {}", name, err)]
+                }
+                (Ok(before), Ok(after)) => properties
+                    .iter()
+                    .filter(|property| property.is_about(view.module_id(), view.name()))
+                    .filter_map(|property| property.check(view.return_types(), &before, &after))
+                    .collect(),
+                _ => Vec::new(),
+            };
+            violations.extend(messages.into_iter().map(|message| Violation {
+                oracle: format!("view {}", name),
+                message,
+            }));
+        }
+        state.aptos_state_mut().restore(snapshot);
+        violations
+    }
+
+    /// Re-run a transaction sent by a privileged account, the admin or a
+    /// target module's account, that changed target resources privileged
+    /// accounts own, this time from an unprivileged member of the address
+    /// pool. When that run succeeds and changes those resources the same
+    /// way, the called functions don't check who calls them.
+    fn check_access_control(
+        &mut self,
+        state: &mut AptosFuzzerState,
+        input: &AptosFuzzerInput,
+        write_set: &WriteSet,
+    ) -> Option<Violation> {
+        if !self.access_control_enabled {
+            return None;
+        }
+        let targets: BTreeSet<ModuleId> = state.target_modules().iter().cloned().collect();
+        let mut privileged: BTreeSet<AccountAddress> = targets.iter().map(|module| *module.address()).collect();
+        privileged.extend(state.initializer_admin());
+        let admin = input.sender();
+        let name = input.describe();
+        if !privileged.contains(&admin) || self.access_control_reported.contains(&name) {
+            return None;
+        }
+        let privileged_changes = |pre: &AptosCustomState, write_set: &WriteSet| -> Vec<ResourceChange> {
+            resource_changes(pre, write_set)
+                .into_iter()
+                .filter(|change| {
+                    let owner = pre.object_owner(&change.address).unwrap_or(change.address);
+                    targets.contains(&change.struct_tag.module_id()) && privileged.contains(&owner)
+                })
+                .collect()
+        };
+        let expected = privileged_changes(state.aptos_state(), write_set);
+        if expected.is_empty() {
+            return None;
+        }
+        let user = state
+            .address_pool()
+            .iter()
+            .find(|address| !privileged.contains(address) && !address.is_special())
+            .copied()?;
+
+        let saved_state_values = input
+            .block_context()
+            .map(|block_context| state.aptos_state_mut().apply_block_context(block_context));
+        let (result, ..) = self.execute_transaction(input.payload().clone(), state.aptos_state(), Some(user));
+        let actual = match &result {
+            Ok(TransactionResult {
+                status: TransactionStatus::Keep(ExecutionStatus::Success),
+                write_set,
+                ..
+            }) => Some(privileged_changes(state.aptos_state(), write_set)),
+            _ => None,
+        };
+        if let Some(saved) = saved_state_values {
+            state.aptos_state_mut().restore_state_values(saved);
+        }
+        // The re-run is not part of the fuzzed execution
+        self.discard_pending();
+
+        let same_effect = actual.is_some_and(|actual| {
+            actual.len() == expected.len() &&
+                actual.iter().zip(&expected).all(|(actual, expected)| {
+                    actual.address == expected.address &&
+                        actual.struct_tag == expected.struct_tag &&
+                        actual.after == expected.after
+                })
+        });
+        if !same_effect {
+            return None;
+        }
+        self.access_control_reported.insert(name.clone());
+        let changed: Vec<String> = expected
+            .iter()
+            .map(|change| format!("{} at {}", change.struct_tag.to_canonical_string(), change.address))
+            .collect();
+        Some(Violation {
+            oracle: "AccessControl".to_string(),
+            message: format!(
+                "{} changes {} the same way when sent by {} as by {}",
+                name,
+                changed.join(", "),
+                user,
+                admin
+            ),
+        })
+    }
+
+    /// Results of `view` on `state`, with the sender for address arguments and
+    /// defaults for the rest. `None` when it takes arguments without one.
+    fn call_view(
+        state: &AptosCustomState,
+        view: &PublicFunctionTarget,
+        sender: AccountAddress,
+    ) -> Option<Result<Vec<Vec<u8>>, String>> {
+        let args = view
+            .parameters()
+            .iter()
+            .map(|param| match param {
+                FunctionParameter::Value(TypeTag::Address) => bcs::to_bytes(&sender).ok(),
+                FunctionParameter::Value(tag) => AptosFuzzerState::default_arg_bytes(tag),
+                FunctionParameter::Signer | FunctionParameter::Reference { .. } => None,
+            })
+            .collect::<Option<Vec<_>>>()?;
+        let output = AptosVM::execute_view_function(
+            &CustomStateView::new(state),
+            view.module_id().clone(),
+            view.name().clone(),
+            Vec::new(),
+            args,
+            INVARIANT_MAX_GAS,
+        );
+        Some(output.values.map_err(|err| format!("{:?}", err)))
+    }
+
+    /// Return assertions of `input`'s sequence calls that the values they
+    /// returned break. `call_returns` holds the operand stack as each call
+    /// returned, of which its return values are the top.
+    fn check_return_assertions(
+        state: &AptosFuzzerState,
+        input: &AptosFuzzerInput,
+        mut call_returns: Vec<Vec<Option<U256>>>,
+    ) -> Vec<Violation> {
+        let Some(sequence) = input.script_sequence() else {
+            return Vec::new();
+        };
+        if sequence.calls().iter().all(|call| call.assertions().is_empty()) {
+            return Vec::new();
+        }
+        for (call, stack) in sequence.calls().iter().zip(&mut call_returns) {
+            let returns = state
+                .function_instance(call.module(), call.function(), call.ty_args())
+                .map_or(0, |function| function.return_types().len());
+            stack.drain(..stack.len().saturating_sub(returns));
+        }
+        sequence
+            .check_returns(&call_returns)
+            .into_iter()
+            .map(|message| Violation {
+                oracle: "return assertion".to_string(),
+                message,
+            })
+            .collect()
+    }
+
+    /// Entry functions get their signers from the VM; other invariants must
+    /// take no arguments so they can be wrapped in a script.
+    fn invariant_payload(
+        function: &PublicFunctionTarget,
+        post: &AptosCustomState,
+    ) -> Option<Result<TransactionPayload, FuzzerError>> {
+        if function.is_entry() {
+            let entry = EntryFunction::new(
+                function.module_id().clone(),
+                function.name().clone(),
+                Vec::new(),
+                Vec::new(),
+            );
+            return Some(Ok(TransactionPayload::EntryFunction(entry)));
+        }
+        if !function.parameters().is_empty() {
+            return None;
+        }
+        let mut sequence = ScriptSequence::new();
+        sequence.push_call(SequenceCall::new(
+            function.module_id().clone(),
+            function.name().clone(),
+            Vec::new(),
+            Vec::new(),
+        ));
+        Some(compile_sequence(&sequence, post.module_bytes()).map(TransactionPayload::Script))
+    }
+
+    /// Status code of a kept or discarded transaction that neither succeeded
+    /// nor aborted, e.g. a module publish the verifier rejected.
+    fn failure_status(status: &TransactionStatus) -> Option<u64> {
+        match status {
+            TransactionStatus::Keep(ExecutionStatus::MiscellaneousError(Some(code))) |
+            TransactionStatus::Discard(code) => Some(*code as u64),
+            _ => None,
+        }
+    }
+
+    /// Resources (and resource groups) touched by a write set; modules and
+    /// table items are skipped.
+    fn written_resources(write_set: &WriteSet) -> Vec<WrittenResource> {
+        let mut resources = Vec::new();
+        for (state_key, _write_op) in write_set.write_op_iter() {
+            if let StateKeyInner::AccessPath(access_path) = state_key.inner() {
+                if let Some(struct_tag) = access_path.get_struct_tag() {
+                    resources.push(WrittenResource {
+                        address: access_path.address,
+                        struct_tag: struct_tag.to_canonical_string(),
+                    });
+                }
+            }
+        }
+        resources
+    }
+
+    pub fn pc_observer(&self) -> &EdgesObserver {
         &self.observers.0
     }
-    pub fn pc_observer_mut(&mut self) -> &mut HitcountsMapObserver<OwnedMapObserver<u8>> {
+    pub fn pc_observer_mut(&mut self) -> &mut EdgesObserver {
         &mut self.observers.0
     }
 
+    fn observer<O>(observers: &mut AptosObservers, handle: &Handle<O>) -> &mut O {
+        observers
+            .get_mut(handle)
+            .expect("observers are fixed when the executor is created")
+    }
+
+    fn abort_observer(&mut self) -> &mut AbortCodeObserver {
+        Self::observer(&mut self.observers, &self.handles.abort)
+    }
+
+    fn shift_observer(&mut self) -> &mut ShiftOverflowObserver {
+        Self::observer(&mut self.observers, &self.handles.shift)
+    }
+
+    fn distance_observer(&mut self) -> &mut BranchDistanceObserver {
+        Self::observer(&mut self.observers, &self.handles.distance)
+    }
+
+    fn events_observer(&mut self) -> &mut EventsObserver {
+        Self::observer(&mut self.observers, &self.handles.events)
+    }
+
+    fn write_set_observer(&mut self) -> &mut WriteSetObserver {
+        Self::observer(&mut self.observers, &self.handles.write_set)
+    }
+
+    fn runtime_issue_observer(&mut self) -> &mut RuntimeIssueObserver {
+        Self::observer(&mut self.observers, &self.handles.runtime_issues)
+    }
+
+    fn histogram_observer(&mut self) -> &mut InstructionHistogramObserver {
+        Self::observer(&mut self.observers, &self.handles.histogram)
+    }
+
+    fn vm_status_observer(&mut self) -> &mut VmStatusObserver {
+        Self::observer(&mut self.observers, &self.handles.vm_status)
+    }
+
+    #[cfg(feature = "concolic")]
+    /// Record whether `input` raised the target abort and solve the queries
+    /// its execution produced.
+    fn probe_abort_target(&mut self, state: &mut AptosFuzzerState, input: &AptosFuzzerInput) {
+        if state.abort_search().is_none() {
+            return;
+        }
+        let queries = self.symbolic_tracer.guidance_mut().take_abort_queries();
+        if self.symbolic_tracer.guidance().abort_reached() {
+            if let Some(search) = state.abort_search_mut() {
+                search.record_reached(input);
+            }
+        }
+        if queries.is_empty() || state.abort_search().is_some_and(|search| search.reached.is_some()) {
+            return;
+        }
+        let symbols = self.symbolic_tracer.state().arg_symbols();
+        for query in queries {
+            let outcome = state.solver_mut().solve(&query, &symbols);
+            if let Some(search) = state.abort_search_mut() {
+                search.record_outcome(input, outcome);
+            }
+        }
+    }
+
+    /// Count the ways the last execution took each conditional branch.
+    fn record_branch_outcomes(&mut self, state: &mut AptosFuzzerState) {
+        let outcomes = self.symbolic_tracer.take_branch_outcomes();
+        let tracer = &self.symbolic_tracer;
+        let coverage = state.branch_coverage_mut();
+        for (key, taken) in outcomes {
+            for condition in [false, true] {
+                if taken[condition as usize] > 0 {
+                    coverage.record(key, condition, taken[condition as usize], || {
+                        tracer.function_name(key.0).cloned()
+                    });
+                }
+            }
+        }
+    }
+
+    #[cfg(feature = "concolic")]
+    /// Solve the flips of worklist branches the execution of `input`
+    /// produced, then pick the flip targets again if the worklist changed.
+    fn flip_branches(&mut self, state: &mut AptosFuzzerState, input: &AptosFuzzerInput) {
+        if self.branch_flips == 0 {
+            return;
+        }
+        let queries = self.symbolic_tracer.guidance_mut().take_branch_queries();
+        if !queries.is_empty() {
+            let symbols = self.symbolic_tracer.state().arg_symbols();
+            for (key, query) in queries {
+                let outcome = state.solver_mut().solve(&query, &symbols);
+                state.branch_coverage_mut().record_outcome(key, input, outcome);
+            }
+        }
+        let generation = state.branch_coverage().generation();
+        if self.flip_generation != Some(generation) {
+            self.flip_generation = Some(generation);
+            let targets = state
+                .branch_worklist()
+                .into_iter()
+                .filter(|branch| !branch.site.infeasible)
+                .take(self.branch_flips)
+                .map(|branch| branch.key)
+                .collect();
+            self.symbolic_tracer.guidance_mut().set_flip_targets(targets);
+        }
+    }
+
+    fn record_instruction_histogram(&mut self, state: &mut AptosFuzzerState, locations: &[TracedLocation]) {
+        if !self.instruction_profiling {
+            return;
+        }
+        let histogram = self.histogram_observer();
+        histogram.record(locations);
+        state.record_instruction_histogram(histogram.counts());
+    }
+
+    /// Translate the traced instructions into coverage-map slots, each edge
+    /// going from the previous instruction of the same frame. Functions
+    /// covered by the state's edge layout get collision-free IDs; anything
+    /// else (scripts, late-published modules) falls back to AFL-style hashing
+    /// seeded with `base_id`.
+    fn compute_edge_indices(&mut self, state: &AptosCustomState, base_id: u64, locations: &[TracedLocation]) {
+        let layout = state.edge_layout();
+        let map_len = self.observers.0.as_mut().as_slice_mut().len();
+        self.edge_indices.clear();
+        for &(function, pc, prev_pc) in locations {
+            let idx = match (layout.function(function), prev_pc) {
+                (Some(edges), Some(prev_pc)) => edges.edge_id(prev_pc, pc).map(|id| layout.slot(id)),
+                (Some(edges), None) => Some(layout.slot(edges.entry_id())),
+                (None, prev_pc) => {
+                    let cur_id = base_id ^ function ^ pc as u64;
+                    let prev_id = prev_pc.map_or(0, |prev_pc| (base_id ^ function ^ prev_pc as u64) >> 1);
+                    Some(((cur_id ^ prev_id) as usize) % map_len)
+                }
+            };
+            if let Some(idx) = idx {
+                self.edge_indices.push(idx);
+            }
+        }
+    }
+
+    /// Execution path of a run, from its traced instructions. The VM's pc
+    /// trace has no functions in it, so the calls of a script sequence would
+    /// run together.
+    fn traced_path(locations: &[TracedLocation]) -> Vec<u64> {
+        locations
+            .iter()
+            .map(|&(function, pc, _)| function ^ pc as u64)
+            .collect()
+    }
+
+    /// Run `input` against the state's base state with a structured trace
+    /// recorded. Nothing is reported to the state or the observers.
+    pub fn trace_input(&mut self, state: &mut AptosFuzzerState, input: &AptosFuzzerInput) -> ExecutionTrace {
+        let saved_state_values = input
+            .block_context()
+            .map(|block_context| state.aptos_state_mut().apply_block_context(block_context));
+        self.symbolic_tracer.set_trace_capture(true);
+        let (result, ..) = self.execute_transaction(input.payload().clone(), state.aptos_state(), Some(input.sender()));
+        let mut trace = self.symbolic_tracer.take_trace().unwrap_or_default();
+        self.symbolic_tracer.set_trace_capture(false);
+        let accesses = self.symbolic_tracer.take_global_accesses();
+        trace.accesses = state.access_sets(input, &accesses).calls;
+        if let Some(saved) = saved_state_values {
+            state.aptos_state_mut().restore_state_values(saved);
+        }
+        trace.status = match result {
+            Ok(result) => format!("{:?}", result.status),
+            Err(status) => format!("{:?}", status),
+        };
+
+        // Tracing is not part of any fuzzed execution
+        self.discard_pending();
+        trace
+    }
+
+    /// The resources `input` changes when run against the current state,
+    /// for reports: no coverage or findings are recorded and the state is
+    /// left as it was. Empty when the input doesn't execute.
+    pub fn resource_changes(&mut self, state: &mut AptosFuzzerState, input: &AptosFuzzerInput) -> Vec<ResourceChange> {
+        let saved_state_values = input
+            .block_context()
+            .map(|block_context| state.aptos_state_mut().apply_block_context(block_context));
+        let (result, ..) = self.execute_transaction(input.payload().clone(), state.aptos_state(), Some(input.sender()));
+        let changes = match &result {
+            Ok(result) => resource_changes(state.aptos_state(), &result.write_set),
+            Err(_) => Vec::new(),
+        };
+        if let Some(saved) = saved_state_values {
+            state.aptos_state_mut().restore_state_values(saved);
+        }
+        self.discard_pending();
+        changes
+    }
+
+    /// What `input` ends in when run against the current state, to tell
+    /// whether a variant of a solution still reproduces it. Oracles that run
+    /// functions of their own (invariant and view functions, access control)
+    /// aren't consulted; nothing is recorded and the state is left as it was.
+    pub fn replay_outcome(&mut self, state: &mut AptosFuzzerState, input: &AptosFuzzerInput) -> ReplayOutcome {
+        let saved_state_values = input
+            .block_context()
+            .map(|block_context| state.aptos_state_mut().apply_block_context(block_context));
+        let (result, ..) = self.execute_transaction(input.payload().clone(), state.aptos_state(), Some(input.sender()));
+        let call_returns = self.symbolic_tracer.take_call_returns();
+        if let Some(saved) = saved_state_values {
+            state.aptos_state_mut().restore_state_values(saved);
+        }
+        let mut outcome = ReplayOutcome {
+            abort: self
+                .pending_abort_site
+                .take()
+                .map(|site| (site.module, site.function, site.code)),
+            ..ReplayOutcome::default()
+        };
+        outcome.findings.extend(
+            self.pending_runtime_issues
+                .iter()
+                .map(|issue| format!("{} in {}::{}", issue.kind.as_str(), issue.module, issue.function)),
+        );
+        match &result {
+            Ok(result) if matches!(result.status, TransactionStatus::Keep(ExecutionStatus::Success)) => {
+                let mut violations = state.check_oracles(&result.write_set, &result.events);
+                violations.extend(state.check_round_trips(input, &result.write_set));
+                violations.extend(state.check_signer_ownership(input, &result.write_set));
+                violations.extend(state.check_asset_conservation(input, &result.write_set));
+                violations.extend(Self::check_return_assertions(state, input, call_returns));
+                outcome
+                    .findings
+                    .extend(violations.into_iter().map(|violation| violation.oracle));
+            }
+            Ok(result) if outcome.abort.is_none() => outcome.failure = Some(format!("{:?}", result.status)),
+            Err(status) if outcome.abort.is_none() => outcome.failure = Some(format!("{:?}", status)),
+            _ => {}
+        }
+        self.discard_pending();
+        outcome
+    }
+
+    /// Drop what the tracer collected for a run outside the fuzzing loop.
+    fn discard_pending(&mut self) {
+        self.symbolic_tracer.take_call_returns();
+        self.pending_runtime_issues.clear();
+        self.pending_locations.clear();
+        self.pending_branch_distances.clear();
+        self.pending_abort_site = None;
+    }
+
     pub fn execute_transaction(
         &mut self,
         transaction: TransactionPayload,
@@ -105,20 +1219,75 @@ impl<EM, Z> AptosMoveExecutor<EM, Z> {
     ) {
         match &transaction {
             TransactionPayload::EntryFunction(_) | TransactionPayload::Script(_) => {
-                let view = CustomStateView::new(state);
-                let code_storage =
-                    aptos_vm_types::module_and_script_storage::AsAptosCodeStorage::as_aptos_code_storage(&view, state);
-
                 self.symbolic_tracer.reset();
-                let (result, pcs, shifts, outcome) = self.aptos_vm.execute_user_payload_no_checking_with_tracer(
-                    state,
-                    &code_storage,
-                    &transaction,
-                    sender,
-                    &mut self.symbolic_tracer,
-                );
+                self.budget_exhausted = false;
+                self.panicked = false;
+                let code_storage = PersistentCodeStorage::refresh(&mut self.code_storage, state);
+                let aptos_vm = &self.aptos_vm;
+                let tracer = &mut self.symbolic_tracer;
+                let run = panic_capture::catch(|| {
+                    code_storage.with_storage(|code_storage| {
+                        aptos_vm.execute_user_payload_no_checking_with_tracer(
+                            state,
+                            code_storage,
+                            &transaction,
+                            sender,
+                            tracer,
+                        )
+                    })
+                });
+                let (result, pcs, shifts, outcome) = match run {
+                    Ok(run) => run,
+                    Err((payload, captured)) => {
+                        let budget_exhausted = payload.is::<BudgetExhausted>();
+                        let mut issues = self.symbolic_tracer.take_issues();
+                        let (message, outcome) = if budget_exhausted {
+                            self.budget_exhausted = true;
+                            ("execution budget exhausted".to_string(), ExecOutcomeKind::OutOfGas)
+                        } else {
+                            // Keep fuzzing: report the panic as a runtime issue of this input
+                            self.panicked = true;
+                            let (message, backtrace) = match captured {
+                                Some(captured) => (captured.message, Some(captured.backtrace)),
+                                None => (panic_capture::payload_message(payload.as_ref()), None),
+                            };
+                            let (module, function, pc) =
+                                self.symbolic_tracer
+                                    .current_location()
+                                    .unwrap_or(("<unknown>", "<unknown>", 0));
+                            issues.push(RuntimeIssue::panic(
+                                module.to_string(),
+                                function.to_string(),
+                                pc,
+                                format!("VM panicked: {}", message),
+                                backtrace,
+                            ));
+                            (format!("VM panicked: {}", message), ExecOutcomeKind::Panic)
+                        };
+                        self.pending_runtime_issues = issues;
+                        self.pending_locations = self.symbolic_tracer.take_locations();
+                        self.pending_branch_distances = self.symbolic_tracer.take_branch_distances();
+                        self.pending_abort_site = self.symbolic_tracer.take_abort_site();
+                        return (
+                            Err(VMStatus::Error {
+                                status_code: StatusCode::UNKNOWN_STATUS,
+                                sub_status: None,
+                                message: Some(message),
+                            }),
+                            outcome,
+                            Vec::new(),
+                            Vec::new(),
+                        );
+                    }
+                };
                 // println!("result: {:?}", result);
+                if let Err(VMStatus::MoveAbort(location, code)) = &result {
+                    self.symbolic_tracer.on_abort(location, *code);
+                }
                 self.pending_runtime_issues = self.symbolic_tracer.take_issues();
+                self.pending_locations = self.symbolic_tracer.take_locations();
+                self.pending_branch_distances = self.symbolic_tracer.take_branch_distances();
+                self.pending_abort_site = self.symbolic_tracer.take_abort_site();
                 let shift_losses: Vec<bool> = shifts.iter().map(|ev| ev.lost_high_bits).collect();
 
                 let res = match result {
@@ -137,6 +1306,9 @@ impl<EM, Z> AptosMoveExecutor<EM, Z> {
             }
             _ => {
                 self.pending_runtime_issues.clear();
+                self.pending_locations.clear();
+                self.pending_branch_distances.clear();
+                self.pending_abort_site = None;
                 (
                     Err(VMStatus::Error {
                         status_code: StatusCode::UNKNOWN_STATUS,
@@ -152,13 +1324,13 @@ impl<EM, Z> AptosMoveExecutor<EM, Z> {
     }
 }
 
-impl<EM, Z> Default for AptosMoveExecutor<EM, Z> {
+impl Default for AptosMoveExecutor {
     fn default() -> Self {
         Self::new()
     }
 }
 
-impl<EM, Z> Executor<EM, AptosFuzzerInput, AptosFuzzerState, Z> for AptosMoveExecutor<EM, Z> {
+impl<EM, Z> Executor<EM, AptosFuzzerInput, AptosFuzzerState, Z> for AptosMoveExecutor {
     fn run_target(
         &mut self,
         _fuzzer: &mut Z,
@@ -167,20 +1339,66 @@ impl<EM, Z> Executor<EM, AptosFuzzerInput, AptosFuzzerState, Z> for AptosMoveExe
         input: &AptosFuzzerInput,
     ) -> Result<ExitKind, libafl::Error> {
         state.clear_current_execution_path();
-        let default_sender = Some(AccountAddress::ONE);
+        state.set_last_oracle_violations(Vec::new());
+        track_in_flight(input);
+        state.set_last_sender(input.sender());
+        state.record_function_execution(input);
+        let sender = Some(input.sender());
+        let saved_state_values = input
+            .block_context()
+            .map(|block_context| state.aptos_state_mut().apply_block_context(block_context));
         let (result, outcome, pcs, shift_losses) =
-            self.execute_transaction(input.payload().clone(), state.aptos_state(), default_sender);
+            self.execute_transaction(input.payload().clone(), state.aptos_state(), sender);
+        let call_returns = self.symbolic_tracer.take_call_returns();
+        let shift_details = self.symbolic_tracer.take_shift_losses();
+        self.record_branch_outcomes(state);
+        let accesses = self.symbolic_tracer.take_global_accesses();
+        let succeeded = matches!(
+            &result,
+            Ok(result) if matches!(result.status, TransactionStatus::Keep(ExecutionStatus::Success))
+        );
+        state.check_resource_leaks(input, &accesses, succeeded);
+        let access_sets = state.access_sets(input, &accesses);
+        state.record_access_sets(access_sets);
+        #[cfg(feature = "concolic")]
+        if let Some(divergence) = self.symbolic_tracer.guidance_mut().take_divergence() {
+            let site = (divergence.module.clone(), divergence.function.clone(), divergence.pc);
+            if self.divergences_reported.insert(site) {
+                state.record_symbolic_divergence(divergence);
+            }
+        }
+        if let Some(saved) = saved_state_values {
+            state.aptos_state_mut().restore_state_values(saved);
+        }
+        #[cfg(feature = "concolic")]
+        self.probe_abort_target(state, input);
+        #[cfg(feature = "concolic")]
+        self.flip_branches(state, input);
         let runtime_issues = std::mem::take(&mut self.pending_runtime_issues);
-        let has_runtime_issue = !runtime_issues.is_empty();
-        if has_runtime_issue {
+        if !runtime_issues.is_empty() {
             for issue in &runtime_issues {
-                println!(
+                // Reported once per site in the runtime findings; this runs
+                // on every execution that hits one
+                debug!(
                     "Runtime issue detected: {} ({}::{} @ pc {})",
                     issue.message, issue.module, issue.function, issue.pc
                 );
+                if let Some(backtrace) = &issue.backtrace {
+                    debug!("Backtrace:\n{}", backtrace);
+                }
+                if let Some(hook) = self.runtime_issue_hook.as_mut() {
+                    hook(issue);
+                }
             }
         }
+        self.runtime_issue_observer().set_issues(runtime_issues.clone());
         state.set_last_runtime_issues(runtime_issues);
+        // Branch distances are meaningful on both success and abort paths
+        let distances = std::mem::take(&mut self.pending_branch_distances);
+        self.distance_observer().set_distances(distances);
+
+        let divergence = self.check_divergence(input.payload(), input.block_context(), sender, &result);
+        state.set_last_divergence(divergence);
 
         // Update execution counter (required by Executor trait contract)
         *state.executions_mut() += 1;
@@ -188,11 +1406,6 @@ impl<EM, Z> Executor<EM, AptosFuzzerInput, AptosFuzzerState, Z> for AptosMoveExe
         match result {
             Ok(result) => {
                 self.success_count += 1;
-                let map = self.observers.0.as_slice_mut();
-                for byte in map.iter_mut() {
-                    *byte = 0;
-                }
-                self.prev_loc = 0;
 
                 // Build stable per-function base ID
                 let base_id: u64 = match input.payload() {
@@ -209,51 +1422,100 @@ impl<EM, Z> Executor<EM, AptosFuzzerInput, AptosFuzzerState, Z> for AptosMoveExe
                 };
 
                 self.total_instructions_executed += pcs.len() as u64;
+                state.set_last_instructions(pcs.len() as u64);
+                state.set_last_rejected(false);
 
-                {
-                    let cumulative_map = state.cumulative_coverage_mut();
-                    // Update AFL-style edge coverage in observer and cumulative maps
-                    for &pc in &pcs {
-                        let cur_id = base_id ^ pc;
-                        let idx = ((cur_id ^ self.prev_loc) as usize) & (MAP_SIZE - 1);
-                        map[idx] = map[idx].saturating_add(1);
-                        cumulative_map[idx] = cumulative_map[idx].max(1);
-                        self.prev_loc = cur_id >> 1;
-                    }
+                let locations = std::mem::take(&mut self.pending_locations);
+                self.compute_edge_indices(state.aptos_state(), base_id, &locations);
+                self.record_instruction_histogram(state, &locations);
+                // Update edge coverage in observer and cumulative maps
+                self.observers.0.as_mut().record_hits(&self.edge_indices);
+                let cumulative_map = state.cumulative_coverage_mut();
+                for &idx in &self.edge_indices {
+                    cumulative_map[idx] = cumulative_map[idx].max(1);
                 }
 
-                state.set_current_execution_path(pcs);
+                state.set_current_execution_path(Self::traced_path(&locations));
+
+                let events: Vec<EmittedEvent> = result
+                    .events
+                    .iter()
+                    .map(|event| EmittedEvent {
+                        type_tag: event.type_tag().to_canonical_string(),
+                        size: event.event_data().len(),
+                    })
+                    .collect();
+                state.set_current_events(events.clone());
+                state.observe_write_set(&result.write_set);
+                let mut violations = state.check_oracles(&result.write_set, &result.events);
+                violations.extend(state.check_round_trips(input, &result.write_set));
+                violations.extend(state.check_signer_ownership(input, &result.write_set));
+                violations.extend(state.check_asset_conservation(input, &result.write_set));
+                violations.extend(Self::check_return_assertions(state, input, call_returns));
+                violations.extend(self.check_invariant_functions(state, &result.write_set));
+                violations.extend(self.check_view_functions(state, input, &result.write_set));
+                violations.extend(self.check_access_control(state, input, &result.write_set));
+                state.set_last_oracle_violations(violations);
+                self.events_observer().set_events(events);
+                self.write_set_observer()
+                    .set_resources(Self::written_resources(&result.write_set));
 
                 // Update observers
                 let cause_loss = shift_losses.into_iter().any(|b| b);
-                self.observers.1 .1 .0.set_cause_loss(cause_loss);
-                if let TransactionStatus::Keep(ExecutionStatus::MoveAbort { location: _, code, .. }) = &result.status {
-                    self.observers.1 .0.set_last(Some(*code));
+                self.shift_observer().set_cause_loss(cause_loss);
+                self.shift_observer().set_losses(shift_details);
+                if let TransactionStatus::Keep(ExecutionStatus::MoveAbort { location, code, .. }) = &result.status {
+                    self.abort_observer().set_last(Some(*code));
+                    self.abort_observer().set_last_location(Some(location.clone()));
+                    let site = self.pending_abort_site.take();
+                    self.abort_observer().set_last_site(site);
                 } else {
-                    self.observers.1 .0.set_last(None);
+                    self.abort_observer().set_last(None);
                 }
+                self.vm_status_observer().set_last(Self::failure_status(&result.status));
 
-                let mut exit_kind = ExitKind::Ok;
-                if has_runtime_issue {
-                    exit_kind = ExitKind::Crash;
-                }
-                return Ok(exit_kind);
+                return Ok(ExitKind::Ok);
             }
             Err(vm_status) => {
                 self.error_count += 1;
-                let map = self.observers.0.as_slice_mut();
-                for byte in map.iter_mut() {
-                    *byte = 0;
+                let locations = std::mem::take(&mut self.pending_locations);
+                self.record_instruction_histogram(state, &locations);
+                state.set_last_instructions(locations.len() as u64);
+                state.set_last_rejected(
+                    locations.is_empty() &&
+                        !self.budget_exhausted &&
+                        !self.panicked &&
+                        !matches!(vm_status, VMStatus::MoveAbort(..)),
+                );
+                self.shift_observer().set_cause_loss(false);
+                self.shift_observer().set_losses(Vec::new());
+                self.events_observer().set_events(Vec::new());
+                self.write_set_observer().set_resources(Vec::new());
+                if self.budget_exhausted || self.panicked {
+                    // The VM never returned a trace; identify the run by the
+                    // set of locations it managed to reach instead
+                    let mut path = Self::traced_path(&locations);
+                    path.sort_unstable();
+                    path.dedup();
+                    self.total_instructions_executed += locations.len() as u64;
+                    state.set_current_execution_path(path);
+                } else {
+                    state.set_current_execution_path(Self::traced_path(&locations));
+                }
+                if self.budget_exhausted {
+                    self.abort_observer().set_last(None);
+                    return Ok(ExitKind::Timeout);
                 }
-                self.prev_loc = 0;
-                self.observers.1 .1 .0.set_cause_loss(false);
-                state.set_current_execution_path(pcs);
-                if let VMStatus::MoveAbort(ref _loc, code) = vm_status {
-                    self.observers.1 .0.set_last(Some(code));
+                if let VMStatus::MoveAbort(ref location, code) = vm_status {
+                    self.abort_observer().set_last(Some(code));
+                    self.abort_observer().set_last_location(Some(location.clone()));
+                    let site = self.pending_abort_site.take();
+                    self.abort_observer().set_last_site(site);
                 } else {
-                    self.observers.1 .0.set_last(None);
+                    self.abort_observer().set_last(None);
+                    self.vm_status_observer().set_last(Some(vm_status.status_code() as u64));
                 }
-                let mut exit_kind = match outcome {
+                let exit_kind = match outcome {
                     ExecOutcomeKind::Ok => ExitKind::Ok,
                     ExecOutcomeKind::MoveAbort(_) => ExitKind::Ok,
                     ExecOutcomeKind::OutOfGas => ExitKind::Ok,
@@ -261,16 +1523,13 @@ impl<EM, Z> Executor<EM, AptosFuzzerInput, AptosFuzzerState, Z> for AptosMoveExe
                     ExecOutcomeKind::InvariantViolation => ExitKind::Crash,
                     ExecOutcomeKind::Panic => ExitKind::Crash,
                 };
-                if has_runtime_issue {
-                    exit_kind = ExitKind::Crash;
-                }
                 return Ok(exit_kind);
             }
         }
     }
 }
 
-impl<EM, Z> HasObservers for AptosMoveExecutor<EM, Z> {
+impl HasObservers for AptosMoveExecutor {
     type Observers = AptosObservers;
 
     fn observers(&self) -> RefIndexable<&Self::Observers, Self::Observers> {
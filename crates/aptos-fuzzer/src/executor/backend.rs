@@ -0,0 +1,132 @@
+use std::str::FromStr;
+
+use aptos_move_core_types::account_address::AccountAddress;
+use aptos_move_core_types::vm_status::VMStatus;
+use aptos_types::contract_event::ContractEvent;
+use aptos_types::transaction::{ExecutionStatus, TransactionPayload, TransactionStatus};
+use aptos_types::write_set::WriteSet;
+use serde::{Deserialize, Serialize};
+
+use crate::executor::aptos_custom_state::AptosCustomState;
+use crate::executor::AptosMoveExecutor;
+
+/// Which VM executes inputs.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Backend {
+    /// Full Aptos transactions through `AptosVM`
+    #[default]
+    Aptos,
+    /// Target functions called directly in a Move VM session, without
+    /// transaction validation, prologue/epilogue or gas
+    MoveVm,
+}
+
+impl Backend {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Aptos => "aptos",
+            Self::MoveVm => "move-vm",
+        }
+    }
+
+    /// Fail unless this build can execute inputs on the backend. `MoveVm`
+    /// needs a traced session entry point in the VM fork, which it does not
+    /// expose yet: coverage is only collected by
+    /// `execute_user_payload_no_checking_with_tracer`.
+    pub fn ensure_available(&self) -> anyhow::Result<()> {
+        match self {
+            Self::Aptos => Ok(()),
+            Self::MoveVm => anyhow::bail!("the move-vm backend needs a traced session API in the VM, use aptos"),
+        }
+    }
+}
+
+impl FromStr for Backend {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "aptos" => Ok(Self::Aptos),
+            "move-vm" => Ok(Self::MoveVm),
+            _ => anyhow::bail!("unknown backend {}, expected aptos or move-vm", s),
+        }
+    }
+}
+
+/// How a payload execution ended, independent of the chain it ran on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BackendStatus {
+    Executed,
+    Aborted(u64),
+    Failed(String),
+}
+
+/// What a `MoveExecutorBackend` reports for one payload.
+#[derive(Debug, Clone)]
+pub struct BackendExecution<E> {
+    pub status: BackendStatus,
+    /// Hashed `(function, pc)` locations in execution order
+    pub pcs: Vec<u64>,
+    /// Per executed shift, whether it lost high bits
+    pub shift_losses: Vec<bool>,
+    /// State changes of a successful execution
+    pub effects: Option<E>,
+}
+
+/// A Move VM the fuzzing stack can drive. The mutator, feedbacks and concolic
+/// tracer only depend on what this returns, so a chain is supported by
+/// implementing it over that chain's VM and state.
+///
+/// Only the Aptos backend exists. A Sui backend (object-centric inputs over
+/// the Sui adapter) needs the `sui-*` crates, which are commented out in the
+/// workspace manifest.
+pub trait MoveExecutorBackend {
+    type Payload;
+    type State;
+    type Effects;
+
+    fn name(&self) -> &'static str;
+
+    fn execute_payload(
+        &mut self,
+        payload: Self::Payload,
+        state: &Self::State,
+        sender: Option<AccountAddress>,
+    ) -> BackendExecution<Self::Effects>;
+}
+
+impl MoveExecutorBackend for AptosMoveExecutor {
+    type Payload = TransactionPayload;
+    type State = AptosCustomState;
+    type Effects = (WriteSet, Vec<ContractEvent>);
+
+    fn name(&self) -> &'static str {
+        "aptos"
+    }
+
+    fn execute_payload(
+        &mut self,
+        payload: TransactionPayload,
+        state: &AptosCustomState,
+        sender: Option<AccountAddress>,
+    ) -> BackendExecution<Self::Effects> {
+        let (result, _outcome, pcs, shift_losses) = self.execute_transaction(payload, state, sender);
+        let (status, effects) = match result {
+            Ok(result) => match result.status {
+                TransactionStatus::Keep(ExecutionStatus::MoveAbort { code, .. }) => {
+                    (BackendStatus::Aborted(code), None)
+                }
+                _ => (BackendStatus::Executed, Some((result.write_set, result.events))),
+            },
+            Err(VMStatus::MoveAbort(_, code)) => (BackendStatus::Aborted(code), None),
+            Err(status) => (BackendStatus::Failed(format!("{:?}", status.status_code())), None),
+        };
+        BackendExecution {
+            status,
+            pcs,
+            shift_losses,
+            effects,
+        }
+    }
+}
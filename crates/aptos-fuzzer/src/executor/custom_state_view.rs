@@ -1,8 +1,16 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use aptos_move_vm_runtime::{RuntimeEnvironment, WithRuntimeEnvironment};
 use aptos_types::state_store::state_key::StateKey;
 use aptos_types::state_store::state_storage_usage::StateStorageUsage;
+use aptos_types::state_store::state_value::StateValue;
 use aptos_types::state_store::{StateViewResult, TStateView};
+use aptos_vm_types::module_and_script_storage::{AptosCodeStorageAdapter, AsAptosCodeStorage};
+use ouroboros::self_referencing;
 
 use super::aptos_custom_state::AptosCustomState;
+use super::forked_state_view::ForkedStateView;
 
 /// Minimal StateView wrapper over `AptosCustomState` so we can adapt it to
 /// AptosCodeStorage and reuse Move VM's loader and caches.
@@ -30,3 +38,90 @@ impl<'a> TStateView for CustomStateView<'a> {
         Ok(self.state.get_state_value(state_key))
     }
 }
+
+/// Owned copy of the module keys of an `AptosCustomState`, the only keys a
+/// code storage reads, so the storage does not borrow the state it runs
+/// against.
+pub struct ModuleStateView {
+    modules: HashMap<StateKey, StateValue>,
+    /// Module keys deleted locally that must not be read through to the fork
+    deleted: HashSet<StateKey>,
+    fork: Option<Arc<ForkedStateView>>,
+    runtime_environment: RuntimeEnvironment,
+}
+
+impl ModuleStateView {
+    pub(crate) fn new(
+        modules: HashMap<StateKey, StateValue>,
+        deleted: HashSet<StateKey>,
+        fork: Option<Arc<ForkedStateView>>,
+        runtime_environment: RuntimeEnvironment,
+    ) -> Self {
+        Self {
+            modules,
+            deleted,
+            fork,
+            runtime_environment,
+        }
+    }
+}
+
+impl TStateView for ModuleStateView {
+    type Key = StateKey;
+
+    fn get_usage(&self) -> StateViewResult<StateStorageUsage> {
+        Ok(StateStorageUsage::Untracked)
+    }
+
+    fn get_state_value(&self, state_key: &StateKey) -> StateViewResult<Option<StateValue>> {
+        if let Some(value) = self.modules.get(state_key) {
+            return Ok(Some(value.clone()));
+        }
+        if self.deleted.contains(state_key) {
+            return Ok(None);
+        }
+        Ok(self.fork.as_ref().and_then(|fork| fork.get_state_value(state_key)))
+    }
+}
+
+impl WithRuntimeEnvironment for ModuleStateView {
+    fn runtime_environment(&self) -> &RuntimeEnvironment {
+        &self.runtime_environment
+    }
+}
+
+/// Code storage kept across executions so the modules it loads and verifies
+/// are reused. It is built from the modules of one state and must be rebuilt
+/// once that state's modules change; `refresh` takes care of that.
+#[self_referencing]
+pub struct PersistentCodeStorage {
+    /// `AptosCustomState::module_generation` the storage was built at
+    generation: u64,
+    view: ModuleStateView,
+    #[borrows(view)]
+    #[not_covariant]
+    storage: AptosCodeStorageAdapter<'this, ModuleStateView, ModuleStateView>,
+}
+
+impl PersistentCodeStorage {
+    fn build(state: &AptosCustomState) -> Self {
+        PersistentCodeStorageBuilder {
+            generation: state.module_generation(),
+            view: state.module_view(),
+            storage_builder: |view: &ModuleStateView| view.as_aptos_code_storage(view),
+        }
+        .build()
+    }
+
+    /// The storage in `slot`, first rebuilt from `state` if there is none yet
+    /// or modules were published or removed since it was built.
+    pub fn refresh<'a>(slot: &'a mut Option<Self>, state: &AptosCustomState) -> &'a Self {
+        let stale = slot
+            .as_ref()
+            .is_none_or(|storage| *storage.borrow_generation() != state.module_generation());
+        if stale {
+            *slot = Some(Self::build(state));
+        }
+        slot.as_ref().expect("code storage was just built")
+    }
+}
@@ -0,0 +1,302 @@
+use std::collections::HashMap;
+
+use aptos_move_binary_format::access::ModuleAccess;
+use aptos_move_binary_format::control_flow_graph::{ControlFlowGraph, VMControlFlowGraph};
+use aptos_move_binary_format::file_format::{Bytecode, CodeOffset};
+use aptos_move_binary_format::CompiledModule;
+use aptos_move_core_types::language_storage::ModuleId;
+
+use crate::static_analysis::dead_code;
+
+/// Stable identifier for a function, shared by the edge layout and the tracer
+/// so per-instruction lookups never allocate.
+pub fn function_hash(module_id: &ModuleId, function: &str) -> u64 {
+    // FNV-1a hash
+    let mut hash: u64 = 0xCBF29CE484222325;
+    let bytes = module_id
+        .address()
+        .as_ref()
+        .iter()
+        .chain(module_id.name().as_str().as_bytes())
+        .chain(b"::")
+        .chain(function.as_bytes());
+    for &b in bytes {
+        hash ^= b as u64;
+        hash = hash.wrapping_mul(0x100000001B3);
+    }
+    hash
+}
+
+/// Contiguous range of coverage-map slots owned by a single function.
+///
+/// Slot 0 is the function entry; the remaining slots are the CFG edges of the
+/// function, grouped by the block they leave.
+#[derive(Clone, Debug, Default)]
+pub struct FunctionEdges {
+    base: usize,
+    len: usize,
+    /// Block end offset -> (slot of the first successor, successor block
+    /// starts)
+    blocks: HashMap<CodeOffset, (usize, Vec<CodeOffset>)>,
+    /// Edges constant propagation proves are never taken
+    dead_edges: usize,
+}
+
+impl FunctionEdges {
+    fn from_code(base: usize, code: &[Bytecode]) -> Self {
+        let cfg = VMControlFlowGraph::new(code);
+        let mut blocks = HashMap::new();
+        let mut next_slot = 1;
+        for block_id in cfg.blocks() {
+            let successors = cfg.successors(block_id).clone();
+            let count = successors.len();
+            blocks.insert(cfg.block_end(block_id), (next_slot, successors));
+            next_slot += count;
+        }
+        Self {
+            base,
+            len: next_slot,
+            blocks,
+            dead_edges: dead_code(code).dead_edges.len(),
+        }
+    }
+
+    pub fn base(&self) -> usize {
+        self.base
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn dead_edges(&self) -> usize {
+        self.dead_edges
+    }
+
+    /// Global ID of the function entry.
+    pub fn entry_id(&self) -> usize {
+        self.base
+    }
+
+    /// Global ID of the CFG edge `from -> to`, if `from` ends a block and `to`
+    /// is one of its successors.
+    pub fn edge_id(&self, from: CodeOffset, to: CodeOffset) -> Option<usize> {
+        let (first_slot, successors) = self.blocks.get(&from)?;
+        let pos = successors.iter().position(|succ| *succ == to)?;
+        Some(self.base + first_slot + pos)
+    }
+}
+
+/// Collision-free assignment of edge IDs to every loaded function.
+///
+/// Functions are laid out back to back, so IDs only collide once the total
+/// number of edges exceeds the coverage map size; modules registered first
+/// (the fuzz targets) are always placed at the start of the map.
+#[derive(Clone, Debug)]
+pub struct EdgeLayout {
+    map_size: usize,
+    functions: HashMap<u64, FunctionEdges>,
+    total_edges: usize,
+    dead_edges: usize,
+}
+
+impl EdgeLayout {
+    pub fn new(map_size: usize) -> Self {
+        Self {
+            map_size,
+            functions: HashMap::new(),
+            total_edges: 0,
+            dead_edges: 0,
+        }
+    }
+
+    pub fn assign_module(&mut self, module: &CompiledModule) {
+        let module_id = module.self_id();
+        for func_def in module.function_defs() {
+            let Some(code_unit) = &func_def.code else {
+                continue;
+            };
+            let handle = module.function_handle_at(func_def.function);
+            let name = module.identifier_at(handle.name);
+            let key = function_hash(&module_id, name.as_str());
+            if self.functions.contains_key(&key) {
+                continue;
+            }
+            let edges = FunctionEdges::from_code(self.total_edges, &code_unit.code);
+            self.total_edges += edges.len();
+            self.dead_edges += edges.dead_edges();
+            self.functions.insert(key, edges);
+        }
+    }
+
+    pub fn function(&self, function_hash: u64) -> Option<&FunctionEdges> {
+        self.functions.get(&function_hash)
+    }
+
+    /// Map a global edge ID onto the coverage map.
+    #[inline]
+    pub fn slot(&self, edge_id: usize) -> usize {
+        edge_id % self.map_size
+    }
+
+    pub fn map_size(&self) -> usize {
+        self.map_size
+    }
+
+    pub fn total_edges(&self) -> usize {
+        self.total_edges
+    }
+
+    /// Edges that can be covered: the total minus the provably dead ones.
+    pub fn live_edges(&self) -> usize {
+        self.total_edges - self.dead_edges
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.functions.is_empty()
+    }
+
+    pub fn is_collision_free(&self) -> bool {
+        self.total_edges <= self.map_size
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use aptos_cached_packages::head_release_bundle;
+    use aptos_move_binary_format::file_format::Bytecode;
+    use aptos_move_binary_format::CompiledModule;
+
+    use super::{EdgeLayout, FunctionEdges};
+
+    fn framework_modules() -> Vec<CompiledModule> {
+        head_release_bundle()
+            .code_and_compiled_modules()
+            .into_iter()
+            .map(|(_, module)| module.clone())
+            .take(8)
+            .collect()
+    }
+
+    fn layout(map_size: usize) -> EdgeLayout {
+        let mut layout = EdgeLayout::new(map_size);
+        for module in framework_modules() {
+            layout.assign_module(&module);
+        }
+        layout
+    }
+
+    #[test]
+    fn function_ranges_are_disjoint_and_cover_all_edges() {
+        let layout = layout(usize::MAX);
+        let mut ranges: Vec<(usize, usize)> = layout
+            .functions
+            .values()
+            .map(|edges| (edges.base(), edges.base() + edges.len()))
+            .collect();
+        ranges.sort();
+        let mut next = 0;
+        for (start, end) in ranges {
+            assert_eq!(start, next, "functions must be laid out back to back");
+            next = end;
+        }
+        assert_eq!(next, layout.total_edges());
+    }
+
+    #[test]
+    fn edge_ids_stay_inside_their_function() {
+        let layout = layout(usize::MAX);
+        for edges in layout.functions.values() {
+            for (block_end, (_, successors)) in &edges.blocks {
+                for successor in successors {
+                    let id = edges.edge_id(*block_end, *successor).expect("edge of a block");
+                    assert!(id > edges.entry_id() && id < edges.base() + edges.len());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn modules_added_later_leave_the_others_in_place() {
+        let mut targets = framework_modules();
+        let unrelated = targets
+            .iter()
+            .rposition(|module| module.function_defs.iter().any(|def| def.code.is_some()))
+            .expect("a module with code");
+        let unrelated = targets.remove(unrelated);
+        let mut before = EdgeLayout::new(usize::MAX);
+        let mut after = EdgeLayout::new(usize::MAX);
+        for module in &targets {
+            before.assign_module(module);
+            after.assign_module(module);
+        }
+        after.assign_module(&unrelated);
+        assert!(after.total_edges() > before.total_edges());
+        for (hash, edges) in &before.functions {
+            let reloaded = after.function(*hash).expect("function still laid out");
+            assert_eq!((reloaded.base(), reloaded.len()), (edges.base(), edges.len()));
+        }
+    }
+
+    #[test]
+    fn dead_edges_are_split_from_the_live_ones() {
+        // The branch always falls through, so the edge to 4 is dead
+        let code = vec![
+            Bytecode::LdTrue,
+            Bytecode::BrFalse(4),
+            Bytecode::LdU64(1),
+            Bytecode::Ret,
+            Bytecode::LdU64(2),
+            Bytecode::Ret,
+        ];
+        let edges = FunctionEdges::from_code(10, &code);
+        assert_eq!(edges.len(), 3);
+        assert_eq!(edges.dead_edges(), 1);
+        let mut ids = [edges.edge_id(1, 2).unwrap(), edges.edge_id(1, 4).unwrap()];
+        ids.sort_unstable();
+        assert_eq!(ids, [11, 12]);
+
+        let layout = layout(usize::MAX);
+        let dead: usize = layout.functions.values().map(FunctionEdges::dead_edges).sum();
+        assert_eq!(layout.live_edges(), layout.total_edges() - dead);
+    }
+
+    #[test]
+    fn edges_past_the_map_size_share_slots_deterministically() {
+        let total = layout(usize::MAX).total_edges();
+        assert!(layout(total).is_collision_free());
+        let map_size = total / 2;
+        let (first, second) = (layout(map_size), layout(map_size));
+        assert!(!first.is_collision_free());
+        let mut owners: HashMap<usize, u64> = HashMap::new();
+        let mut shared = 0;
+        for (hash, edges) in &first.functions {
+            let again = second.function(*hash).unwrap();
+            for id in edges.entry_id()..edges.base() + edges.len() {
+                let slot = first.slot(id);
+                assert!(slot < map_size);
+                assert_eq!(slot, second.slot(again.base() + id - edges.base()));
+                if owners.insert(slot, *hash).is_some_and(|owner| owner != *hash) {
+                    shared += 1;
+                }
+            }
+        }
+        assert!(shared > 0, "functions share slots once the map is full");
+    }
+
+    #[test]
+    fn modules_laid_out_twice_keep_their_ids() {
+        let modules = framework_modules();
+        let mut layout = EdgeLayout::new(usize::MAX);
+        layout.assign_module(&modules[0]);
+        let total = layout.total_edges();
+        layout.assign_module(&modules[0]);
+        assert_eq!(layout.total_edges(), total);
+    }
+}
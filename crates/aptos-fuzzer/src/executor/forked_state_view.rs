@@ -0,0 +1,165 @@
+use std::fs;
+use std::io::Read;
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Context};
+use aptos_crypto::HashValue;
+use aptos_types::state_store::state_key::StateKey;
+use aptos_types::state_store::state_storage_usage::StateStorageUsage;
+use aptos_types::state_store::state_value::StateValue;
+use aptos_types::state_store::{StateViewResult, TStateView};
+use dashmap::DashMap;
+use log::warn;
+
+/// Cache file tags: the key does not exist at the pinned version, or it does
+/// and the BCS-encoded `StateValue` follows.
+const CACHE_ABSENT: u8 = 0;
+const CACHE_PRESENT: u8 = 1;
+
+/// Read-only view of a live network's state at a fixed ledger version,
+/// fetched lazily over the REST API one key at a time.
+///
+/// Every value is memoized in memory and, when a cache directory is given,
+/// on disk under `<cache_dir>/<chain_id>/<version>/`, so restarts against the
+/// same version don't refetch anything.
+pub struct ForkedStateView {
+    base_url: String,
+    version: u64,
+    chain_id: u8,
+    cache_dir: Option<PathBuf>,
+    fetched: DashMap<StateKey, Option<StateValue>>,
+}
+
+impl ForkedStateView {
+    /// Connect to the REST endpoint at `url` (e.g. `https://fullnode.mainnet.aptoslabs.com`).
+    /// Without a `version` the current ledger version is pinned so that every
+    /// later read sees the same snapshot.
+    pub fn new(url: &str, version: Option<u64>, cache_dir: Option<PathBuf>) -> anyhow::Result<Self> {
+        let base_url = url.trim_end_matches('/').trim_end_matches("/v1").to_string();
+        let ledger_info: serde_json::Value = ureq::get(&format!("{}/v1", base_url))
+            .call()
+            .with_context(|| format!("failed to reach {}", base_url))?
+            .into_json()
+            .context("invalid ledger info response")?;
+        let chain_id = ledger_info["chain_id"]
+            .as_u64()
+            .ok_or_else(|| anyhow!("ledger info has no chain_id"))? as u8;
+        let version = match version {
+            Some(version) => version,
+            None => ledger_info["ledger_version"]
+                .as_str()
+                .and_then(|version| version.parse().ok())
+                .ok_or_else(|| anyhow!("ledger info has no ledger_version"))?,
+        };
+
+        let cache_dir = cache_dir.map(|dir| dir.join(chain_id.to_string()).join(version.to_string()));
+        if let Some(dir) = &cache_dir {
+            fs::create_dir_all(dir).with_context(|| format!("failed to create {}", dir.display()))?;
+        }
+
+        Ok(Self {
+            base_url,
+            version,
+            chain_id,
+            cache_dir,
+            fetched: DashMap::new(),
+        })
+    }
+
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    pub fn chain_id(&self) -> u8 {
+        self.chain_id
+    }
+
+    /// Value of `state_key` at the pinned version. Network errors are logged
+    /// and read as a missing key, so the transaction fails instead of the
+    /// fuzzer.
+    pub fn get_state_value(&self, state_key: &StateKey) -> Option<StateValue> {
+        if let Some(value) = self.fetched.get(state_key) {
+            return value.clone();
+        }
+        let value = match self.read_cache(state_key) {
+            Some(value) => value,
+            None => match self.fetch(state_key) {
+                Ok(value) => {
+                    self.write_cache(state_key, &value);
+                    value
+                }
+                Err(e) => {
+                    warn!(
+                        "[aptos-fuzzer] failed to fetch {:?} from {}: {}",
+                        state_key, self.base_url, e
+                    );
+                    None
+                }
+            },
+        };
+        self.fetched.insert(state_key.clone(), value.clone());
+        value
+    }
+
+    fn fetch(&self, state_key: &StateKey) -> anyhow::Result<Option<StateValue>> {
+        let key = format!("0x{}", hex::encode(bcs::to_bytes(state_key)?));
+        let url = format!(
+            "{}/v1/experimental/state_values/raw?ledger_version={}",
+            self.base_url, self.version
+        );
+        let response = match ureq::post(&url)
+            .set("Accept", "application/x-bcs")
+            .send_json(serde_json::json!({ "key": key }))
+        {
+            Ok(response) => response,
+            Err(ureq::Error::Status(404, _)) => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let mut bytes = Vec::new();
+        response.into_reader().read_to_end(&mut bytes)?;
+        Ok(Some(bcs::from_bytes(&bytes)?))
+    }
+
+    fn cache_path(&self, state_key: &StateKey) -> Option<PathBuf> {
+        let dir = self.cache_dir.as_ref()?;
+        let key_bytes = bcs::to_bytes(state_key).ok()?;
+        Some(dir.join(HashValue::sha3_256_of(&key_bytes).to_hex()))
+    }
+
+    fn read_cache(&self, state_key: &StateKey) -> Option<Option<StateValue>> {
+        let bytes = fs::read(self.cache_path(state_key)?).ok()?;
+        match bytes.split_first()? {
+            (&CACHE_ABSENT, _) => Some(None),
+            (&CACHE_PRESENT, value) => bcs::from_bytes(value).ok().map(Some),
+            _ => None,
+        }
+    }
+
+    fn write_cache(&self, state_key: &StateKey, value: &Option<StateValue>) {
+        let Some(path) = self.cache_path(state_key) else {
+            return;
+        };
+        let bytes = match value {
+            Some(value) => match bcs::to_bytes(value) {
+                Ok(encoded) => [vec![CACHE_PRESENT], encoded].concat(),
+                Err(_) => return,
+            },
+            None => vec![CACHE_ABSENT],
+        };
+        if let Err(e) = fs::write(&path, bytes) {
+            warn!("[aptos-fuzzer] failed to write fork cache {}: {}", path.display(), e);
+        }
+    }
+}
+
+impl TStateView for ForkedStateView {
+    type Key = StateKey;
+
+    fn get_usage(&self) -> StateViewResult<StateStorageUsage> {
+        Ok(StateStorageUsage::Untracked)
+    }
+
+    fn get_state_value(&self, state_key: &StateKey) -> StateViewResult<Option<StateValue>> {
+        Ok(ForkedStateView::get_state_value(self, state_key))
+    }
+}
@@ -1,7 +1,15 @@
 pub mod aptos_custom_state;
 pub mod aptos_move_executor;
+pub mod backend;
 pub mod custom_state_view;
+pub mod edge_layout;
+pub mod forked_state_view;
+pub mod panic_capture;
 pub mod types;
 
+pub use aptos_custom_state::StateSnapshot;
 pub use aptos_move_executor::AptosMoveExecutor;
-pub use types::TransactionResult;
+pub use backend::{Backend, BackendExecution, BackendStatus, MoveExecutorBackend};
+pub use edge_layout::EdgeLayout;
+pub use forked_state_view::ForkedStateView;
+pub use types::{BudgetExhausted, DifferentialConfig, ExecutionBudget, TransactionResult};
@@ -0,0 +1,64 @@
+use std::any::Any;
+use std::backtrace::Backtrace;
+use std::cell::{Cell, RefCell};
+use std::panic::{self, AssertUnwindSafe, PanicHookInfo};
+use std::sync::Once;
+
+/// Message and backtrace of a panic raised inside `catch`.
+#[derive(Clone, Debug)]
+pub struct CapturedPanic {
+    pub message: String,
+    pub backtrace: String,
+}
+
+thread_local! {
+    static CAPTURING: Cell<bool> = const { Cell::new(false) };
+    static LAST_PANIC: RefCell<Option<CapturedPanic>> = const { RefCell::new(None) };
+}
+
+static INSTALL_HOOK: Once = Once::new();
+
+/// Install a panic hook that records panics raised inside `catch` instead of
+/// printing them. Panics anywhere else go to the previously installed hook.
+fn install_hook() {
+    INSTALL_HOOK.call_once(|| {
+        let previous = panic::take_hook();
+        panic::set_hook(Box::new(move |info: &PanicHookInfo<'_>| {
+            if !CAPTURING.with(|capturing| capturing.get()) {
+                previous(info);
+                return;
+            }
+            let mut message = payload_message(info.payload());
+            if let Some(location) = info.location() {
+                message = format!("{} at {}", message, location);
+            }
+            let captured = CapturedPanic {
+                message,
+                backtrace: Backtrace::force_capture().to_string(),
+            };
+            LAST_PANIC.with(|last| *last.borrow_mut() = Some(captured));
+        }));
+    });
+}
+
+/// Run `f`, turning a panic into an `Err` carrying the original payload and,
+/// for genuine panics (not `resume_unwind`), the captured message and
+/// backtrace.
+pub fn catch<R>(f: impl FnOnce() -> R) -> Result<R, (Box<dyn Any + Send>, Option<CapturedPanic>)> {
+    install_hook();
+    LAST_PANIC.with(|last| last.borrow_mut().take());
+    let was_capturing = CAPTURING.with(|capturing| capturing.replace(true));
+    let result = panic::catch_unwind(AssertUnwindSafe(f));
+    CAPTURING.with(|capturing| capturing.set(was_capturing));
+    result.map_err(|payload| (payload, LAST_PANIC.with(|last| last.borrow_mut().take())))
+}
+
+pub fn payload_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "non-string panic payload".to_string()
+    }
+}
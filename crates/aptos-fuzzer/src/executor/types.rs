@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 use aptos_types::contract_event::ContractEvent;
 use aptos_types::fee_statement::FeeStatement;
 use aptos_types::transaction::TransactionStatus;
@@ -11,3 +13,31 @@ pub struct TransactionResult {
     pub events: Vec<ContractEvent>,
     pub fee_statement: Option<FeeStatement>,
 }
+
+/// Per-execution limits enforced by the tracer. `None` means unlimited.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ExecutionBudget {
+    pub timeout: Option<Duration>,
+    pub max_instructions: Option<u64>,
+}
+
+impl ExecutionBudget {
+    pub fn is_unlimited(&self) -> bool {
+        self.timeout.is_none() && self.max_instructions.is_none()
+    }
+}
+
+/// Unwind payload used by the tracer to abort a VM run that exhausted its
+/// `ExecutionBudget`.
+#[derive(Debug, Clone, Copy)]
+pub struct BudgetExhausted;
+
+/// Second execution configuration used as the reference in differential mode.
+#[derive(Debug, Clone, Default)]
+pub struct DifferentialConfig {
+    pub paranoid_type_checks: bool,
+    /// Feature flags (by number) to turn on in the reference configuration
+    pub enable_features: Vec<u64>,
+    /// Feature flags (by number) to turn off in the reference configuration
+    pub disable_features: Vec<u64>,
+}
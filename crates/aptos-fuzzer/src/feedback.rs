@@ -1,20 +1,150 @@
 use std::borrow::Cow;
-use std::collections::HashSet;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::str::FromStr;
 
+use aptos_move_core_types::account_address::AccountAddress;
+use aptos_move_core_types::identifier::Identifier;
+use aptos_move_core_types::vm_status::AbortLocation;
+use aptos_types::transaction::TransactionPayload;
 use libafl::feedbacks::{Feedback, StateInitializer};
 use libafl::observers::ObserversTuple;
-use libafl::Error;
+use libafl::{Error, HasMetadata};
 use libafl_bolts::tuples::{Handle, MatchNameRef};
 use libafl_bolts::Named;
 use serde::{Deserialize, Serialize};
 
-use crate::observers::{AbortCodeObserver, ShiftOverflowObserver};
+use crate::concolic::{RuntimeIssue, RuntimeIssueKind, Severity};
+use crate::executor::edge_layout::function_hash;
+use crate::observers::{
+    AbortCodeObserver, BranchDistanceObserver, EventsObserver, RuntimeIssueObserver, ShiftOverflowObserver,
+    VmStatusObserver, WriteSetObserver,
+};
+use crate::static_analysis::StaticAnalysisFinding;
 use crate::{AptosFuzzerInput, AptosFuzzerState};
 
+/// Stable key for the function an input calls: the entry function, or the
+/// script bytecode for script payloads.
+fn entry_key(input: &AptosFuzzerInput) -> u64 {
+    match input.payload() {
+        TransactionPayload::EntryFunction(ef) => function_hash(ef.module(), ef.function().as_str()),
+        TransactionPayload::Script(script) => {
+            let mut hasher = DefaultHasher::new();
+            script.code().hash(&mut hasher);
+            hasher.finish()
+        }
+        _ => 0,
+    }
+}
+
+/// An abort the target raises on purpose, e.g. on invalid input, written
+/// `[<address>::]<module>::<code>`. Without an address it matches the module
+/// name at any address.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AllowedAbort {
+    address: Option<AccountAddress>,
+    module: Identifier,
+    code: u64,
+}
+
+impl AllowedAbort {
+    pub fn matches(&self, location: &AbortLocation, code: u64) -> bool {
+        let AbortLocation::Module(module_id) = location else {
+            return false;
+        };
+        self.code == code &&
+            module_id.name() == self.module.as_ident_str() &&
+            self.address.is_none_or(|address| *module_id.address() == address)
+    }
+}
+
+impl FromStr for AllowedAbort {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let parts: Vec<&str> = s.split("::").collect();
+        let (address, module, code) = match parts.as_slice() {
+            [module, code] => (None, *module, *code),
+            [address, module, code] => (Some(AccountAddress::from_hex_literal(address)?), *module, *code),
+            _ => anyhow::bail!("expected [<address>::]<module>::<code>"),
+        };
+        Ok(Self {
+            address,
+            module: Identifier::new(module)?,
+            code: code.parse()?,
+        })
+    }
+}
+
+/// A known finding not to report again, written
+/// `[<address>::]<module>::<function>::<kind>`. Without an address it
+/// matches the module name at any address; a `*` function matches any
+/// function and findings about the module as a whole.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Suppression {
+    address: Option<AccountAddress>,
+    module: Identifier,
+    /// `None` for `*`
+    function: Option<Identifier>,
+    kind: String,
+}
+
+impl Suppression {
+    /// Whether a `kind` finding in `function` of `module`, named
+    /// `<address>::<module>`, is suppressed.
+    pub fn matches(&self, module: &str, function: Option<&str>, kind: &str) -> bool {
+        let (address, name) = match module.rsplit_once("::") {
+            Some((address, name)) => (AccountAddress::from_hex_literal(address).ok(), name),
+            None => (None, module),
+        };
+        self.kind == kind &&
+            self.module.as_str() == name &&
+            self.address.is_none_or(|expected| address == Some(expected)) &&
+            self.function
+                .as_ref()
+                .is_none_or(|expected| function == Some(expected.as_str()))
+    }
+
+    pub fn matches_issue(&self, issue: &RuntimeIssue) -> bool {
+        self.matches(&issue.module, Some(&issue.function), issue.kind.as_str())
+    }
+
+    pub fn matches_finding(&self, finding: &StaticAnalysisFinding) -> bool {
+        self.matches(&finding.module, finding.function.as_deref(), finding.kind.as_str())
+    }
+}
+
+impl FromStr for Suppression {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let parts: Vec<&str> = s.split("::").collect();
+        let (address, module, function, kind) = match parts.as_slice() {
+            [module, function, kind] => (None, *module, *function, *kind),
+            [address, module, function, kind] => (
+                Some(AccountAddress::from_hex_literal(address)?),
+                *module,
+                *function,
+                *kind,
+            ),
+            _ => anyhow::bail!("expected [<address>::]<module>::<function>::<kind>"),
+        };
+        Ok(Self {
+            address,
+            module: Identifier::new(module)?,
+            function: (function != "*").then(|| Identifier::new(function)).transpose()?,
+            kind: kind.to_string(),
+        })
+    }
+}
+
 /// Objective feedback that considers abort codes as objectives
 #[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct AbortCodeObjective {
     target_abort_codes: HashSet<u64>,
+    /// Expected aborts, never reported
+    allowed_aborts: Vec<AllowedAbort>,
     name: Cow<'static, str>,
 }
 
@@ -22,6 +152,7 @@ impl AbortCodeObjective {
     pub fn new() -> Self {
         Self {
             target_abort_codes: HashSet::new(),
+            allowed_aborts: Vec::new(),
             name: Cow::Borrowed("AbortCodeObjective"),
         }
     }
@@ -29,6 +160,7 @@ impl AbortCodeObjective {
     pub fn with_target_codes(codes: &[u64]) -> Self {
         Self {
             target_abort_codes: codes.iter().cloned().collect(),
+            allowed_aborts: Vec::new(),
             name: Cow::Borrowed("AbortCodeObjective"),
         }
     }
@@ -36,9 +168,19 @@ impl AbortCodeObjective {
     pub fn with_name(name: &'static str) -> Self {
         Self {
             target_abort_codes: HashSet::new(),
+            allowed_aborts: Vec::new(),
             name: Cow::Borrowed(name),
         }
     }
+
+    /// Report every abort except the `allowed` ones.
+    pub fn with_allowed_aborts(allowed: Vec<AllowedAbort>) -> Self {
+        Self {
+            target_abort_codes: HashSet::new(),
+            allowed_aborts: allowed,
+            name: Cow::Borrowed("AbortCodeObjective"),
+        }
+    }
 }
 
 impl Named for AbortCodeObjective {
@@ -68,41 +210,62 @@ where
                 if !state.mark_execution_path_seen(path_id) {
                     return Ok(false);
                 }
+                state.crash_paths.insert(path_id);
                 state.record_current_execution_path_for(input);
             }
             return Ok(true);
         }
         // Check if the last execution produced an abort code
         let mut code_opt: Option<u64> = None;
+        let mut location_opt = None;
+        let mut site_opt = None;
         // Access AbortCodeObserver through Handle
         let abort_handle: Handle<AbortCodeObserver> = Handle::new(Cow::Borrowed("AbortCodeObserver"));
         if let Some(obs_ref) = observers.get(&abort_handle) {
             code_opt = obs_ref.last();
+            location_opt = obs_ref.last_location().cloned();
+            site_opt = obs_ref.last_site().cloned();
         }
         if let Some(abort_code) = code_opt {
-            // If we have specific target codes, only those are objectives
-            if !self.target_abort_codes.is_empty() {
-                if self.target_abort_codes.contains(&abort_code) {
-                    if let Some(path_id) = state.current_execution_path_id() {
-                        if !state.mark_execution_path_seen(path_id) {
-                            return Ok(false);
-                        }
-                        state.abort_code_paths.insert(path_id);
-                        state.record_current_execution_path_for(input);
-                    }
-                    return Ok(true);
+            // Expected aborts are neither saved nor marked as seen
+            if let Some(location) = &location_opt {
+                if self
+                    .allowed_aborts
+                    .iter()
+                    .any(|allowed| allowed.matches(location, abort_code))
+                {
+                    return Ok(false);
+                }
+            }
+            // If we have specific target codes, only those are objectives;
+            // otherwise any abort code is
+            if !self.target_abort_codes.is_empty() && !self.target_abort_codes.contains(&abort_code) {
+                return Ok(false);
+            }
+            // The same abort reached through the same calls is reported once,
+            // whichever path led there. The site is only marked once the path
+            // turns out new too, so one first reached on a path already seen
+            // is still reported when another path leads there.
+            if site_opt
+                .as_ref()
+                .is_some_and(|site| state.seen_abort_sites().contains(site))
+            {
+                return Ok(false);
+            }
+            if let Some(path_id) = state.current_execution_path_id() {
+                if !state.mark_execution_path_seen(path_id) {
+                    return Ok(false);
                 }
-            } else {
-                // If no specific targets, any abort code is an objective
-                if let Some(path_id) = state.current_execution_path_id() {
-                    if !state.mark_execution_path_seen(path_id) {
-                        return Ok(false);
-                    }
-                    state.abort_code_paths.insert(path_id);
-                    state.record_current_execution_path_for(input);
+                state.abort_code_paths.insert(path_id);
+                if let Some(site) = &site_opt {
+                    state.abort_site_paths.insert(path_id, site.clone());
                 }
-                return Ok(true);
+                state.record_current_execution_path_for(input);
+            }
+            if let Some(site) = &site_opt {
+                state.mark_abort_site_seen(site);
             }
+            return Ok(true);
         }
 
         Ok(false)
@@ -112,10 +275,13 @@ where
         &mut self,
         _state: &mut AptosFuzzerState,
         _manager: &mut EM,
-        _observers: &OT,
-        _testcase: &mut libafl::corpus::Testcase<AptosFuzzerInput>,
+        observers: &OT,
+        testcase: &mut libafl::corpus::Testcase<AptosFuzzerInput>,
     ) -> Result<(), Error> {
-        // We could add metadata about the abort code to the testcase here
+        let abort_handle: Handle<AbortCodeObserver> = Handle::new(Cow::Borrowed("AbortCodeObserver"));
+        if let Some(site) = observers.get(&abort_handle).and_then(AbortCodeObserver::last_site) {
+            testcase.add_metadata(site.clone());
+        }
         Ok(())
     }
 }
@@ -155,10 +321,12 @@ where
         _exit_kind: &libafl::executors::ExitKind,
     ) -> Result<bool, Error> {
         let mut cause_loss = false;
+        let mut losses = Vec::new();
         // Access ShiftOverflowObserver through Handle
         let shift_handle: Handle<ShiftOverflowObserver> = Handle::new(Cow::Borrowed("ShiftOverflowObserver"));
         if let Some(obs_ref) = observers.get(&shift_handle) {
             cause_loss = obs_ref.cause_loss();
+            losses = obs_ref.losses().to_vec();
         }
 
         if cause_loss {
@@ -166,7 +334,7 @@ where
                 if !state.mark_execution_path_seen(path_id) {
                     return Ok(false);
                 }
-                state.shift_overflow_paths.insert(path_id);
+                state.shift_overflow_paths.insert(path_id, losses);
                 state.record_current_execution_path_for(input);
             }
             return Ok(true);
@@ -174,3 +342,649 @@ where
         Ok(false)
     }
 }
+
+/// Treats executions that exhausted their wall-clock or instruction budget as
+/// bugs (likely unbounded loops or gas-griefing vectors).
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ExecutionBudgetObjective {
+    name: Cow<'static, str>,
+    enabled: bool,
+}
+
+impl ExecutionBudgetObjective {
+    pub fn new(enabled: bool) -> Self {
+        Self {
+            name: Cow::Borrowed("ExecutionBudgetObjective"),
+            enabled,
+        }
+    }
+}
+
+impl Named for ExecutionBudgetObjective {
+    fn name(&self) -> &Cow<'static, str> {
+        &self.name
+    }
+}
+
+impl StateInitializer<AptosFuzzerState> for ExecutionBudgetObjective {}
+
+impl<EM, OT> Feedback<EM, AptosFuzzerInput, OT, AptosFuzzerState> for ExecutionBudgetObjective
+where
+    OT: ObserversTuple<AptosFuzzerInput, AptosFuzzerState>,
+{
+    fn is_interesting(
+        &mut self,
+        state: &mut AptosFuzzerState,
+        _manager: &mut EM,
+        input: &AptosFuzzerInput,
+        _observers: &OT,
+        exit_kind: &libafl::executors::ExitKind,
+    ) -> Result<bool, Error> {
+        if !self.enabled || !matches!(exit_kind, libafl::executors::ExitKind::Timeout) {
+            return Ok(false);
+        }
+        if let Some(path_id) = state.current_execution_path_id() {
+            if !state.mark_execution_path_seen(path_id) {
+                return Ok(false);
+            }
+            state.budget_exhausted_paths.insert(path_id);
+            state.record_current_execution_path_for(input);
+        }
+        Ok(true)
+    }
+}
+
+/// Treats runtime issues at or above a severity threshold as bugs. Every
+/// issue, whatever its severity, goes into the state's runtime findings.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct RuntimeIssueObjective {
+    name: Cow<'static, str>,
+    /// `None` only records issues without reporting any input
+    min_severity: Option<Severity>,
+    /// Overrides of the severity detectors give their issues
+    severities: HashMap<RuntimeIssueKind, Severity>,
+    /// Known issues, neither recorded nor reported
+    suppressions: Vec<Suppression>,
+}
+
+impl RuntimeIssueObjective {
+    /// Report high-severity issues only.
+    pub fn new() -> Self {
+        Self::with_min_severity(Some(Severity::High))
+    }
+
+    pub fn with_min_severity(min_severity: Option<Severity>) -> Self {
+        Self {
+            name: Cow::Borrowed("RuntimeIssueObjective"),
+            min_severity,
+            severities: HashMap::new(),
+            suppressions: Vec::new(),
+        }
+    }
+
+    pub fn with_severity(mut self, kind: RuntimeIssueKind, severity: Severity) -> Self {
+        self.severities.insert(kind, severity);
+        self
+    }
+
+    pub fn with_suppressions(mut self, suppressions: Vec<Suppression>) -> Self {
+        self.suppressions = suppressions;
+        self
+    }
+
+    pub fn severity(&self, issue: &RuntimeIssue) -> Severity {
+        self.severities.get(&issue.kind).copied().unwrap_or(issue.severity)
+    }
+}
+
+impl Named for RuntimeIssueObjective {
+    fn name(&self) -> &Cow<'static, str> {
+        &self.name
+    }
+}
+
+impl StateInitializer<AptosFuzzerState> for RuntimeIssueObjective {}
+
+impl<EM, OT> Feedback<EM, AptosFuzzerInput, OT, AptosFuzzerState> for RuntimeIssueObjective
+where
+    OT: ObserversTuple<AptosFuzzerInput, AptosFuzzerState>,
+{
+    fn is_interesting(
+        &mut self,
+        state: &mut AptosFuzzerState,
+        _manager: &mut EM,
+        input: &AptosFuzzerInput,
+        observers: &OT,
+        _exit_kind: &libafl::executors::ExitKind,
+    ) -> Result<bool, Error> {
+        let handle: Handle<RuntimeIssueObserver> = Handle::new(Cow::Borrowed("RuntimeIssueObserver"));
+        let Some(observer) = observers.get(&handle) else {
+            return Ok(false);
+        };
+        let mut reported = Vec::new();
+        for issue in observer.issues() {
+            if self
+                .suppressions
+                .iter()
+                .any(|suppression| suppression.matches_issue(issue))
+            {
+                continue;
+            }
+            let severity = self.severity(issue);
+            state.record_runtime_finding(issue, severity);
+            if self.min_severity.is_some_and(|min| severity >= min) {
+                reported.push(issue.clone());
+            }
+        }
+        if reported.is_empty() {
+            return Ok(false);
+        }
+        if let Some(path_id) = state.current_execution_path_id() {
+            if !state.mark_execution_path_seen(path_id) {
+                return Ok(false);
+            }
+            state.runtime_issue_paths.insert(path_id, reported);
+            state.record_current_execution_path_for(input);
+        }
+        Ok(true)
+    }
+}
+
+/// Treats any violation reported by the state's invariant oracles as a bug.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct InvariantOracleObjective {
+    name: Cow<'static, str>,
+}
+
+impl InvariantOracleObjective {
+    pub fn new() -> Self {
+        Self {
+            name: Cow::Borrowed("InvariantOracleObjective"),
+        }
+    }
+}
+
+impl Named for InvariantOracleObjective {
+    fn name(&self) -> &Cow<'static, str> {
+        &self.name
+    }
+}
+
+impl StateInitializer<AptosFuzzerState> for InvariantOracleObjective {}
+
+impl<EM, OT> Feedback<EM, AptosFuzzerInput, OT, AptosFuzzerState> for InvariantOracleObjective
+where
+    OT: ObserversTuple<AptosFuzzerInput, AptosFuzzerState>,
+{
+    fn is_interesting(
+        &mut self,
+        state: &mut AptosFuzzerState,
+        _manager: &mut EM,
+        input: &AptosFuzzerInput,
+        _observers: &OT,
+        _exit_kind: &libafl::executors::ExitKind,
+    ) -> Result<bool, Error> {
+        if state.last_oracle_violations().is_empty() {
+            return Ok(false);
+        }
+        if let Some(path_id) = state.current_execution_path_id() {
+            if !state.mark_execution_path_seen(path_id) {
+                return Ok(false);
+            }
+            let violations = state.last_oracle_violations().to_vec();
+            state.oracle_violation_paths.insert(path_id, violations);
+            state.record_current_execution_path_for(input);
+        }
+        Ok(true)
+    }
+}
+
+/// Treats a difference between the primary and the reference execution of an
+/// input as a bug.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct DifferentialObjective {
+    name: Cow<'static, str>,
+}
+
+impl DifferentialObjective {
+    pub fn new() -> Self {
+        Self {
+            name: Cow::Borrowed("DifferentialObjective"),
+        }
+    }
+}
+
+impl Named for DifferentialObjective {
+    fn name(&self) -> &Cow<'static, str> {
+        &self.name
+    }
+}
+
+impl StateInitializer<AptosFuzzerState> for DifferentialObjective {}
+
+impl<EM, OT> Feedback<EM, AptosFuzzerInput, OT, AptosFuzzerState> for DifferentialObjective
+where
+    OT: ObserversTuple<AptosFuzzerInput, AptosFuzzerState>,
+{
+    fn is_interesting(
+        &mut self,
+        state: &mut AptosFuzzerState,
+        _manager: &mut EM,
+        input: &AptosFuzzerInput,
+        _observers: &OT,
+        _exit_kind: &libafl::executors::ExitKind,
+    ) -> Result<bool, Error> {
+        let Some(divergence) = state.last_divergence().map(str::to_string) else {
+            return Ok(false);
+        };
+        if let Some(path_id) = state.current_execution_path_id() {
+            if !state.mark_execution_path_seen(path_id) {
+                return Ok(false);
+            }
+            state.divergence_paths.insert(path_id, divergence);
+            state.record_current_execution_path_for(input);
+        }
+        Ok(true)
+    }
+}
+
+/// Treats an execution orphaning a resource or moving one where it already
+/// exists as a bug.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ResourceLeakObjective {
+    name: Cow<'static, str>,
+}
+
+impl ResourceLeakObjective {
+    pub fn new() -> Self {
+        Self {
+            name: Cow::Borrowed("ResourceLeakObjective"),
+        }
+    }
+}
+
+impl Named for ResourceLeakObjective {
+    fn name(&self) -> &Cow<'static, str> {
+        &self.name
+    }
+}
+
+impl StateInitializer<AptosFuzzerState> for ResourceLeakObjective {}
+
+impl<EM, OT> Feedback<EM, AptosFuzzerInput, OT, AptosFuzzerState> for ResourceLeakObjective
+where
+    OT: ObserversTuple<AptosFuzzerInput, AptosFuzzerState>,
+{
+    fn is_interesting(
+        &mut self,
+        state: &mut AptosFuzzerState,
+        _manager: &mut EM,
+        input: &AptosFuzzerInput,
+        _observers: &OT,
+        _exit_kind: &libafl::executors::ExitKind,
+    ) -> Result<bool, Error> {
+        if state.last_resource_leaks().is_empty() {
+            return Ok(false);
+        }
+        if let Some(path_id) = state.current_execution_path_id() {
+            if !state.mark_execution_path_seen(path_id) {
+                return Ok(false);
+            }
+            let leaks = state.last_resource_leaks().to_vec();
+            state.resource_leak_paths.insert(path_id, leaks);
+            state.record_current_execution_path_for(input);
+        }
+        Ok(true)
+    }
+}
+
+/// Keeps inputs that get numerically closer to flipping a comparison, even
+/// when they don't reach new edges.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct BranchDistanceFeedback {
+    name: Cow<'static, str>,
+    /// Best (smallest) distance seen so far per comparison site
+    best: HashMap<(u64, u16), u64>,
+}
+
+impl BranchDistanceFeedback {
+    pub fn new() -> Self {
+        Self {
+            name: Cow::Borrowed("BranchDistanceFeedback"),
+            best: HashMap::new(),
+        }
+    }
+}
+
+impl Named for BranchDistanceFeedback {
+    fn name(&self) -> &Cow<'static, str> {
+        &self.name
+    }
+}
+
+impl StateInitializer<AptosFuzzerState> for BranchDistanceFeedback {}
+
+impl<EM, OT> Feedback<EM, AptosFuzzerInput, OT, AptosFuzzerState> for BranchDistanceFeedback
+where
+    OT: ObserversTuple<AptosFuzzerInput, AptosFuzzerState>,
+{
+    fn is_interesting(
+        &mut self,
+        _state: &mut AptosFuzzerState,
+        _manager: &mut EM,
+        _input: &AptosFuzzerInput,
+        observers: &OT,
+        _exit_kind: &libafl::executors::ExitKind,
+    ) -> Result<bool, Error> {
+        let distance_handle: Handle<BranchDistanceObserver> = Handle::new(Cow::Borrowed("BranchDistanceObserver"));
+        let Some(obs_ref) = observers.get(&distance_handle) else {
+            return Ok(false);
+        };
+
+        let mut interesting = false;
+        for (site, distance) in obs_ref.distances() {
+            match self.best.get_mut(site) {
+                Some(best) if *distance < *best => {
+                    *best = *distance;
+                    interesting = true;
+                }
+                Some(_) => {}
+                None => {
+                    // First time this comparison is reached; edge coverage already
+                    // rewards that, so just remember the baseline.
+                    self.best.insert(*site, *distance);
+                }
+            }
+        }
+        Ok(interesting)
+    }
+}
+
+/// Corpus feedback that keeps an input the first time its entry function
+/// aborts with a given code: reaching a new abort usually means new logic was
+/// exercised, even when no new edge was hit.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct AbortCodeNoveltyFeedback {
+    name: Cow<'static, str>,
+    /// (entry function hash, abort code) pairs seen so far
+    seen: HashSet<(u64, u64)>,
+}
+
+impl AbortCodeNoveltyFeedback {
+    pub fn new() -> Self {
+        Self {
+            name: Cow::Borrowed("AbortCodeNoveltyFeedback"),
+            seen: HashSet::new(),
+        }
+    }
+}
+
+impl Named for AbortCodeNoveltyFeedback {
+    fn name(&self) -> &Cow<'static, str> {
+        &self.name
+    }
+}
+
+impl StateInitializer<AptosFuzzerState> for AbortCodeNoveltyFeedback {}
+
+impl<EM, OT> Feedback<EM, AptosFuzzerInput, OT, AptosFuzzerState> for AbortCodeNoveltyFeedback
+where
+    OT: ObserversTuple<AptosFuzzerInput, AptosFuzzerState>,
+{
+    fn is_interesting(
+        &mut self,
+        _state: &mut AptosFuzzerState,
+        _manager: &mut EM,
+        input: &AptosFuzzerInput,
+        observers: &OT,
+        _exit_kind: &libafl::executors::ExitKind,
+    ) -> Result<bool, Error> {
+        let abort_handle: Handle<AbortCodeObserver> = Handle::new(Cow::Borrowed("AbortCodeObserver"));
+        let Some(code) = observers.get(&abort_handle).and_then(|obs_ref| obs_ref.last()) else {
+            return Ok(false);
+        };
+        Ok(self.seen.insert((entry_key(input), code)))
+    }
+}
+
+/// Corpus feedback that keeps an input the first time the VM rejects or
+/// fails a run with a given status code. Mutated module publishes all call
+/// the same function and rarely run code, so a new verifier or loader error
+/// is what tells them apart.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct VmStatusNoveltyFeedback {
+    name: Cow<'static, str>,
+    /// Status codes seen so far
+    seen: HashSet<u64>,
+}
+
+impl VmStatusNoveltyFeedback {
+    pub fn new() -> Self {
+        Self {
+            name: Cow::Borrowed("VmStatusNoveltyFeedback"),
+            seen: HashSet::new(),
+        }
+    }
+}
+
+impl Named for VmStatusNoveltyFeedback {
+    fn name(&self) -> &Cow<'static, str> {
+        &self.name
+    }
+}
+
+impl StateInitializer<AptosFuzzerState> for VmStatusNoveltyFeedback {}
+
+impl<EM, OT> Feedback<EM, AptosFuzzerInput, OT, AptosFuzzerState> for VmStatusNoveltyFeedback
+where
+    OT: ObserversTuple<AptosFuzzerInput, AptosFuzzerState>,
+{
+    fn is_interesting(
+        &mut self,
+        _state: &mut AptosFuzzerState,
+        _manager: &mut EM,
+        _input: &AptosFuzzerInput,
+        observers: &OT,
+        _exit_kind: &libafl::executors::ExitKind,
+    ) -> Result<bool, Error> {
+        let status_handle: Handle<VmStatusObserver> = Handle::new(Cow::Borrowed("VmStatusObserver"));
+        let Some(status) = observers.get(&status_handle).and_then(|obs_ref| obs_ref.last()) else {
+            return Ok(false);
+        };
+        Ok(self.seen.insert(status))
+    }
+}
+
+/// Corpus feedback that keeps an input the first time its entry function
+/// emits a given event type.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct NewEventTypeFeedback {
+    name: Cow<'static, str>,
+    /// (entry function hash, event type tag) pairs seen so far
+    seen: HashSet<(u64, String)>,
+}
+
+impl NewEventTypeFeedback {
+    pub fn new() -> Self {
+        Self {
+            name: Cow::Borrowed("NewEventTypeFeedback"),
+            seen: HashSet::new(),
+        }
+    }
+}
+
+impl Named for NewEventTypeFeedback {
+    fn name(&self) -> &Cow<'static, str> {
+        &self.name
+    }
+}
+
+impl StateInitializer<AptosFuzzerState> for NewEventTypeFeedback {}
+
+impl<EM, OT> Feedback<EM, AptosFuzzerInput, OT, AptosFuzzerState> for NewEventTypeFeedback
+where
+    OT: ObserversTuple<AptosFuzzerInput, AptosFuzzerState>,
+{
+    fn is_interesting(
+        &mut self,
+        _state: &mut AptosFuzzerState,
+        _manager: &mut EM,
+        input: &AptosFuzzerInput,
+        observers: &OT,
+        _exit_kind: &libafl::executors::ExitKind,
+    ) -> Result<bool, Error> {
+        let events_handle: Handle<EventsObserver> = Handle::new(Cow::Borrowed("EventsObserver"));
+        let Some(obs_ref) = observers.get(&events_handle) else {
+            return Ok(false);
+        };
+        let key = entry_key(input);
+        let mut interesting = false;
+        for event in obs_ref.events() {
+            if self.seen.insert((key, event.type_tag.clone())) {
+                interesting = true;
+            }
+        }
+        Ok(interesting)
+    }
+}
+
+/// Corpus feedback that rewards inputs writing a resource type no previous
+/// execution has written, a state-coverage signal orthogonal to edges.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ResourceTouchFeedback {
+    name: Cow<'static, str>,
+    /// Resource struct tags written so far
+    written: HashSet<String>,
+}
+
+impl ResourceTouchFeedback {
+    pub fn new() -> Self {
+        Self {
+            name: Cow::Borrowed("ResourceTouchFeedback"),
+            written: HashSet::new(),
+        }
+    }
+
+    pub fn written_types(&self) -> usize {
+        self.written.len()
+    }
+}
+
+impl Named for ResourceTouchFeedback {
+    fn name(&self) -> &Cow<'static, str> {
+        &self.name
+    }
+}
+
+impl StateInitializer<AptosFuzzerState> for ResourceTouchFeedback {}
+
+impl<EM, OT> Feedback<EM, AptosFuzzerInput, OT, AptosFuzzerState> for ResourceTouchFeedback
+where
+    OT: ObserversTuple<AptosFuzzerInput, AptosFuzzerState>,
+{
+    fn is_interesting(
+        &mut self,
+        _state: &mut AptosFuzzerState,
+        _manager: &mut EM,
+        _input: &AptosFuzzerInput,
+        observers: &OT,
+        _exit_kind: &libafl::executors::ExitKind,
+    ) -> Result<bool, Error> {
+        let write_set_handle: Handle<WriteSetObserver> = Handle::new(Cow::Borrowed("WriteSetObserver"));
+        let Some(obs_ref) = observers.get(&write_set_handle) else {
+            return Ok(false);
+        };
+        let mut interesting = false;
+        for resource in obs_ref.resources() {
+            if self.written.insert(resource.struct_tag.clone()) {
+                interesting = true;
+            }
+        }
+        Ok(interesting)
+    }
+}
+
+/// What an execution of a testcase did, kept with every corpus entry and
+/// solution so schedulers, minimizers and reports needn't re-execute it.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ExecutionMetadata {
+    /// The functions called, as `AptosFuzzerInput::describe` names them
+    pub target: String,
+    pub abort_code: Option<u64>,
+    /// Module the abort was raised in, or `script`
+    pub abort_location: Option<String>,
+    /// Bytecode instructions executed. This is not gas: the traced VM entry
+    /// point doesn't meter it, and instructions aren't weighted by cost
+    pub instructions: u64,
+    pub runtime_issues: Vec<RuntimeIssue>,
+}
+
+libafl_bolts::impl_serdeany!(ExecutionMetadata);
+
+/// Never finds an input interesting, but attaches `ExecutionMetadata` to
+/// every testcase the feedbacks it's combined with keep.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ExecutionMetadataFeedback {
+    name: Cow<'static, str>,
+}
+
+impl ExecutionMetadataFeedback {
+    pub fn new() -> Self {
+        Self {
+            name: Cow::Borrowed("ExecutionMetadataFeedback"),
+        }
+    }
+}
+
+impl Named for ExecutionMetadataFeedback {
+    fn name(&self) -> &Cow<'static, str> {
+        &self.name
+    }
+}
+
+impl StateInitializer<AptosFuzzerState> for ExecutionMetadataFeedback {}
+
+impl<EM, OT> Feedback<EM, AptosFuzzerInput, OT, AptosFuzzerState> for ExecutionMetadataFeedback
+where
+    OT: ObserversTuple<AptosFuzzerInput, AptosFuzzerState>,
+{
+    fn is_interesting(
+        &mut self,
+        _state: &mut AptosFuzzerState,
+        _manager: &mut EM,
+        _input: &AptosFuzzerInput,
+        _observers: &OT,
+        _exit_kind: &libafl::executors::ExitKind,
+    ) -> Result<bool, Error> {
+        Ok(false)
+    }
+
+    fn append_metadata(
+        &mut self,
+        state: &mut AptosFuzzerState,
+        _manager: &mut EM,
+        observers: &OT,
+        testcase: &mut libafl::corpus::Testcase<AptosFuzzerInput>,
+    ) -> Result<(), Error> {
+        let abort_handle: Handle<AbortCodeObserver> = Handle::new(Cow::Borrowed("AbortCodeObserver"));
+        let abort = observers.get(&abort_handle);
+        let metadata = ExecutionMetadata {
+            target: testcase
+                .input()
+                .as_ref()
+                .map(AptosFuzzerInput::describe)
+                .unwrap_or_default(),
+            abort_code: abort.and_then(AbortCodeObserver::last),
+            abort_location: abort
+                .and_then(AbortCodeObserver::last_location)
+                .map(|location| match location {
+                    AbortLocation::Module(module_id) => module_id.to_string(),
+                    AbortLocation::Script => "script".to_string(),
+                }),
+            instructions: state.last_instructions(),
+            runtime_issues: state.last_runtime_issues().to_vec(),
+        };
+        testcase.add_metadata(metadata);
+        Ok(())
+    }
+}
@@ -0,0 +1,244 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use anyhow::{anyhow, bail, Context};
+use aptos_move_binary_format::access::ModuleAccess;
+use aptos_move_binary_format::CompiledModule;
+use aptos_move_core_types::account_address::AccountAddress;
+use aptos_move_core_types::language_storage::{StructTag, TypeTag};
+use aptos_types::state_store::state_key::StateKey;
+use aptos_types::state_store::state_value::StateValue;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::executor::aptos_custom_state::AptosCustomState;
+use crate::history::json_value_bytes;
+use crate::state::AptosFuzzerState;
+use crate::struct_layout::{framework_struct, StructLayoutRegistry};
+
+/// Declarative initial state applied before fuzzing, read from a TOML or
+/// JSON file:
+///
+/// ```toml
+/// accounts = ["0xcafe"]
+///
+/// [[coins]]
+/// address = "0xcafe"
+/// coin_type = "0x1::aptos_coin::AptosCoin"
+/// amount = 1000000
+///
+/// [[resources]]
+/// address = "0xcafe"
+/// struct_tag = "0xcafe::pool::Pool"
+/// data = "0x0100000000000000"
+///
+/// [[resources]]
+/// address = "0xcafe"
+/// struct_tag = "0xcafe::pool::Config"
+/// value = { admin = "0xcafe", fee_bps = 30, paused = false }
+///
+/// [[modules]]
+/// path = "deps/oracle.mv"
+/// ```
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StateFixture {
+    /// Accounts to create (`0x1::account::Account`)
+    #[serde(default)]
+    pub accounts: Vec<String>,
+    #[serde(default)]
+    pub coins: Vec<CoinBalance>,
+    #[serde(default)]
+    pub resources: Vec<ResourceFixture>,
+    #[serde(default)]
+    pub modules: Vec<ModuleFixture>,
+}
+
+/// A `0x1::coin::CoinStore<coin_type>` holding `amount`. The coin's total
+/// supply is not adjusted.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CoinBalance {
+    pub address: String,
+    pub coin_type: String,
+    pub amount: u64,
+}
+
+/// A resource published from its BCS encoding, or from its fields encoded
+/// with the layouts of the published and target modules.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ResourceFixture {
+    pub address: String,
+    pub struct_tag: String,
+    /// Hex-encoded BCS bytes, with or without `0x`
+    #[serde(default)]
+    pub data: Option<String>,
+    /// Fields as the REST API renders them: integers as numbers or strings,
+    /// addresses and `vector<u8>` as hex, nested structs as tables
+    #[serde(default)]
+    pub value: Option<Value>,
+}
+
+/// A compiled module (`.mv`) deployed as a dependency, not fuzzed. Relative
+/// paths are resolved against the fixture file.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ModuleFixture {
+    pub path: PathBuf,
+}
+
+impl StateFixture {
+    /// Parse `path` as JSON when it ends in `.json`, TOML otherwise.
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let contents = fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+        let mut fixture: Self = if path.extension().is_some_and(|ext| ext == "json") {
+            serde_json::from_str(&contents).with_context(|| format!("invalid fixture {}", path.display()))?
+        } else {
+            toml::from_str(&contents).with_context(|| format!("invalid fixture {}", path.display()))?
+        };
+        let base = path.parent().unwrap_or_else(|| Path::new("."));
+        for module in &mut fixture.modules {
+            if module.path.is_relative() {
+                module.path = base.join(&module.path);
+            }
+        }
+        Ok(fixture)
+    }
+
+    /// Publish the fixture into `state`; `modules_dir` holds the target
+    /// modules, whose structs resources may be given the fields of.
+    pub fn apply(&self, state: &mut AptosCustomState, modules_dir: &Path) -> anyhow::Result<()> {
+        for module in &self.modules {
+            let bytes = fs::read(&module.path).with_context(|| format!("failed to read {}", module.path.display()))?;
+            let compiled = CompiledModule::deserialize(&bytes)
+                .map_err(|e| anyhow!("invalid module {}: {:?}", module.path.display(), e))?;
+            state.deploy_module_bytes(compiled.self_id(), bytes);
+        }
+
+        for account in &self.accounts {
+            let address = parse_address(account)?;
+            let bytes = bcs::to_bytes(&(
+                address.to_vec(), // authentication_key
+                0u64,             // sequence_number
+                2u64,             // guid_creation_num
+                event_handle(0, address),
+                event_handle(1, address),
+                None::<AccountAddress>, // rotation_capability_offer
+                None::<AccountAddress>, // signer_capability_offer
+            ))?;
+            publish(state, address, framework_struct("account", "Account", vec![]), bytes)?;
+        }
+
+        // Creation numbers of addresses without an account
+        let mut guid_counters = BTreeMap::new();
+        for coin in &self.coins {
+            let address = parse_address(&coin.address)?;
+            let coin_type = StructTag::from_str(&coin.coin_type)
+                .with_context(|| format!("invalid coin type {}", coin.coin_type))?;
+            let coin_store = framework_struct("coin", "CoinStore", vec![TypeTag::Struct(Box::new(coin_type))]);
+            let bytes = bcs::to_bytes(&(
+                coin.amount,
+                false, // frozen
+                event_handle(create_guid(state, address, &mut guid_counters)?, address),
+                event_handle(create_guid(state, address, &mut guid_counters)?, address),
+            ))?;
+            publish(state, address, coin_store, bytes)?;
+        }
+
+        let mut layouts = None;
+        for resource in &self.resources {
+            let address = parse_address(&resource.address)?;
+            let struct_tag = StructTag::from_str(&resource.struct_tag)
+                .with_context(|| format!("invalid struct tag {}", resource.struct_tag))?;
+            let bytes = match (&resource.data, &resource.value) {
+                (Some(data), None) => hex::decode(data.trim_start_matches("0x"))
+                    .with_context(|| format!("invalid hex for {}", resource.struct_tag))?,
+                (None, Some(value)) => {
+                    let layouts = layouts.get_or_insert_with(|| {
+                        let mut layouts = StructLayoutRegistry::from_module_bytes(
+                            state.module_bytes().values().map(|bytes| &bytes[..]),
+                        );
+                        for module in AptosFuzzerState::compiled_modules_in(modules_dir) {
+                            layouts.add_module(&module);
+                        }
+                        layouts
+                    });
+                    let ty = TypeTag::Struct(Box::new(struct_tag.clone()));
+                    json_value_bytes(&ty, value, layouts)
+                        .with_context(|| format!("invalid value for {}", resource.struct_tag))?
+                }
+                _ => bail!("resource {} needs one of data and value", resource.struct_tag),
+            };
+            publish(state, address, struct_tag, bytes)?;
+        }
+        Ok(())
+    }
+}
+
+fn parse_address(address: &str) -> anyhow::Result<AccountAddress> {
+    AccountAddress::from_hex_literal(address).with_context(|| format!("invalid address {}", address))
+}
+
+/// `0x1::event::EventHandle`: counter, then the GUID's creation number and
+/// address.
+type EventHandle = (u64, u64, AccountAddress);
+
+/// `0x1::account::Account`: authentication key, sequence number, GUID
+/// creation number, the two account event handles and the rotation and
+/// signer capability offers.
+type AccountResource = (
+    Vec<u8>,
+    u64,
+    u64,
+    EventHandle,
+    EventHandle,
+    Option<AccountAddress>,
+    Option<AccountAddress>,
+);
+
+/// An empty event handle.
+fn event_handle(creation_num: u64, address: AccountAddress) -> EventHandle {
+    (0, creation_num, address)
+}
+
+/// Take the next GUID creation number of `address` from the counter in its
+/// `0x1::account::Account`, as `account::create_guid` does, so handles
+/// published here don't collide with each other or with those the VM
+/// creates later. Addresses without an account count in `counters`.
+fn create_guid(
+    state: &mut AptosCustomState,
+    address: AccountAddress,
+    counters: &mut BTreeMap<AccountAddress, u64>,
+) -> anyhow::Result<u64> {
+    let account_tag = framework_struct("account", "Account", vec![]);
+    let Ok(state_key) = StateKey::resource(&address, &account_tag) else {
+        bail!("cannot build a state key for {}", account_tag.to_canonical_string());
+    };
+    if let Some(value) = state.get_state_value(&state_key) {
+        let mut account: AccountResource =
+            bcs::from_bytes(value.bytes()).with_context(|| format!("invalid account at {}", address))?;
+        let creation_num = account.2;
+        account.2 += 1;
+        publish(state, address, account_tag, bcs::to_bytes(&account)?)?;
+        return Ok(creation_num);
+    }
+    let counter = counters.entry(address).or_default();
+    *counter += 1;
+    Ok(*counter - 1)
+}
+
+fn publish(
+    state: &mut AptosCustomState,
+    address: AccountAddress,
+    struct_tag: StructTag,
+    bytes: Vec<u8>,
+) -> anyhow::Result<()> {
+    let Ok(state_key) = StateKey::resource(&address, &struct_tag) else {
+        bail!("cannot build a state key for {}", struct_tag.to_canonical_string());
+    };
+    state.set_state_value(state_key, StateValue::new_legacy(bytes.into()));
+    Ok(())
+}
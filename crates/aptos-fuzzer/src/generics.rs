@@ -0,0 +1,248 @@
+use std::collections::{BTreeMap, HashMap};
+
+use aptos_move_binary_format::access::ModuleAccess;
+use aptos_move_binary_format::file_format::{Ability, AbilitySet, FunctionDefinition, SignatureToken};
+use aptos_move_binary_format::CompiledModule;
+use aptos_move_core_types::identifier::Identifier;
+use aptos_move_core_types::language_storage::{ModuleId, StructTag, TypeTag};
+use libafl_bolts::rands::Rand;
+
+use crate::state::{FunctionParameter, PublicFunctionTarget};
+use crate::struct_layout::{field_type, framework_struct, instantiate, FieldType};
+
+/// One in this many instantiations of a function that already has some
+/// tries fresh type arguments instead of reusing a known one.
+const EXPLORE_RATE: u64 = 4;
+/// Finds an instantiation is assumed to have made, so those never tried
+/// again keep a chance of being picked.
+const PRIOR_FINDS: f64 = 1.0;
+
+/// A public function with type parameters, instantiated with concrete type
+/// arguments before it is called.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GenericFunctionTarget {
+    module_id: ModuleId,
+    name: Identifier,
+    /// Abilities each type argument must have
+    type_params: Vec<AbilitySet>,
+    parameters: Vec<GenericParameter>,
+    return_types: Vec<FieldType>,
+    is_entry: bool,
+    is_view: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum GenericParameter {
+    Signer,
+    Value(FieldType),
+    Reference { ty: FieldType, mutable: bool },
+}
+
+impl GenericFunctionTarget {
+    /// The generic function `func_def` of `module`, `None` when it isn't
+    /// generic or takes parameters a transaction can't pass.
+    pub(crate) fn from_definition(
+        module_id: &ModuleId,
+        module: &CompiledModule,
+        func_def: &FunctionDefinition,
+        is_view: bool,
+    ) -> Option<Self> {
+        let handle = module.function_handle_at(func_def.function);
+        if handle.type_parameters.is_empty() {
+            return None;
+        }
+        let parameters = module
+            .signature_at(handle.parameters)
+            .0
+            .iter()
+            .map(|token| match token {
+                SignatureToken::Signer => Some(GenericParameter::Signer),
+                SignatureToken::Reference(inner) | SignatureToken::MutableReference(inner) => {
+                    if matches!(inner.as_ref(), SignatureToken::Signer) {
+                        Some(GenericParameter::Signer)
+                    } else {
+                        field_type(module, inner).map(|ty| GenericParameter::Reference {
+                            ty,
+                            mutable: matches!(token, SignatureToken::MutableReference(_)),
+                        })
+                    }
+                }
+                SignatureToken::Vector(inner) if matches!(inner.as_ref(), SignatureToken::Signer) => None,
+                token => field_type(module, token).map(GenericParameter::Value),
+            })
+            .collect::<Option<Vec<_>>>()?;
+        let return_types = module
+            .signature_at(handle.return_)
+            .0
+            .iter()
+            .map(|token| field_type(module, token))
+            .collect::<Option<Vec<_>>>()?;
+        Some(Self {
+            module_id: module_id.clone(),
+            name: module.identifier_at(handle.name).to_owned(),
+            type_params: handle.type_parameters.clone(),
+            parameters,
+            return_types,
+            is_entry: func_def.is_entry,
+            is_view,
+        })
+    }
+
+    pub fn module_id(&self) -> &ModuleId {
+        &self.module_id
+    }
+
+    pub fn name(&self) -> &Identifier {
+        &self.name
+    }
+
+    pub fn type_params(&self) -> &[AbilitySet] {
+        &self.type_params
+    }
+
+    pub fn is_entry(&self) -> bool {
+        self.is_entry
+    }
+
+    /// The function with `ty_args` substituted for its type parameters,
+    /// `None` for the wrong number of them.
+    pub fn instantiate(&self, ty_args: &[TypeTag]) -> Option<PublicFunctionTarget> {
+        if ty_args.len() != self.type_params.len() {
+            return None;
+        }
+        let parameters = self
+            .parameters
+            .iter()
+            .map(|param| match param {
+                GenericParameter::Signer => Some(FunctionParameter::Signer),
+                GenericParameter::Value(ty) => instantiate(ty, ty_args).map(FunctionParameter::Value),
+                GenericParameter::Reference { ty, mutable } => {
+                    instantiate(ty, ty_args).map(|ty| FunctionParameter::Reference { ty, mutable: *mutable })
+                }
+            })
+            .collect::<Option<Vec<_>>>()?;
+        let return_types = self
+            .return_types
+            .iter()
+            .map(|ty| instantiate(ty, ty_args))
+            .collect::<Option<Vec<_>>>()?;
+        Some(PublicFunctionTarget::new(
+            self.module_id.clone(),
+            self.name.clone(),
+            ty_args.to_vec(),
+            parameters,
+            return_types,
+            self.is_entry,
+            self.is_view,
+        ))
+    }
+}
+
+/// Type arguments generic functions are instantiated with. Every
+/// instantiation run is credited with whether its input was kept, and later
+/// calls favour the instantiations that found new coverage while now and
+/// then trying fresh type arguments.
+#[derive(Clone, Debug, Default)]
+pub struct InstantiationSchedule {
+    /// Types a type argument may be, with their abilities
+    candidates: Vec<(TypeTag, AbilitySet)>,
+    /// Runs and finds of each instantiation tried, by function
+    stats: HashMap<(ModuleId, Identifier), BTreeMap<Vec<TypeTag>, (u64, u64)>>,
+}
+
+impl InstantiationSchedule {
+    /// Primitive types, `vector<u8>`, `0x1::string::String`, `AptosCoin` and
+    /// the given non-generic structs of the target modules.
+    pub fn new(structs: impl IntoIterator<Item = (StructTag, AbilitySet)>) -> Self {
+        let framework =
+            |module: &str, name: &str| TypeTag::Struct(Box::new(framework_struct(module, name, Vec::new())));
+        let mut candidates: Vec<(TypeTag, AbilitySet)> = [
+            TypeTag::Bool,
+            TypeTag::U8,
+            TypeTag::U64,
+            TypeTag::U128,
+            TypeTag::Address,
+            TypeTag::Vector(Box::new(TypeTag::U8)),
+            framework("string", "String"),
+        ]
+        .into_iter()
+        .map(|ty| (ty, AbilitySet::PRIMITIVES))
+        .collect();
+        candidates.push((
+            framework("aptos_coin", "AptosCoin"),
+            AbilitySet::singleton(Ability::Key),
+        ));
+        candidates.extend(
+            structs
+                .into_iter()
+                .map(|(tag, abilities)| (TypeTag::Struct(Box::new(tag)), abilities)),
+        );
+        Self {
+            candidates,
+            stats: HashMap::new(),
+        }
+    }
+
+    /// Type arguments for `function`: fresh ones when none has been tried or
+    /// one time in `EXPLORE_RATE`, otherwise a tried instantiation drawn by
+    /// its find rate.
+    pub fn choose(&self, function: &GenericFunctionTarget, rand: &mut impl Rand) -> Option<Vec<TypeTag>> {
+        let tried = self
+            .stats
+            .get(&(function.module_id.clone(), function.name.clone()))
+            .filter(|tried| !tried.is_empty());
+        let Some(tried) = tried.filter(|_| rand.next() % EXPLORE_RATE != 0) else {
+            return self.fresh(function, rand);
+        };
+        let weights: Vec<u64> = tried
+            .values()
+            .map(|(runs, finds)| ((*finds as f64 + PRIOR_FINDS) / (*runs as f64 + 1.0) * 100.0).ceil() as u64)
+            .collect();
+        let mut pick = rand.next() % weights.iter().sum::<u64>();
+        for (ty_args, weight) in tried.keys().zip(weights) {
+            if pick < weight {
+                return Some(ty_args.clone());
+            }
+            pick -= weight;
+        }
+        tried.keys().next().cloned()
+    }
+
+    /// Random type arguments satisfying `function`'s ability constraints.
+    pub fn fresh(&self, function: &GenericFunctionTarget, rand: &mut impl Rand) -> Option<Vec<TypeTag>> {
+        function
+            .type_params
+            .iter()
+            .map(|constraints| {
+                let fitting: Vec<&TypeTag> = self
+                    .candidates
+                    .iter()
+                    .filter(|(_, abilities)| constraints.is_subset(*abilities))
+                    .map(|(ty, _)| ty)
+                    .collect();
+                (!fitting.is_empty()).then(|| fitting[(rand.next() as usize) % fitting.len()].clone())
+            })
+            .collect()
+    }
+
+    /// Credit a run of `module::function<ty_args>`.
+    pub fn record(&mut self, module_id: &ModuleId, function: &Identifier, ty_args: &[TypeTag], found: bool) {
+        let (runs, finds) = self
+            .stats
+            .entry((module_id.clone(), function.clone()))
+            .or_default()
+            .entry(ty_args.to_vec())
+            .or_insert((0, 0));
+        *runs += 1;
+        *finds += found as u64;
+    }
+
+    /// Runs and finds of every instantiation tried, by function.
+    pub fn instantiations(&self) -> impl Iterator<Item = (&ModuleId, &Identifier, &[TypeTag], u64, u64)> + '_ {
+        self.stats.iter().flat_map(|((module_id, function), tried)| {
+            tried
+                .iter()
+                .map(move |(ty_args, (runs, finds))| (module_id, function, &ty_args[..], *runs, *finds))
+        })
+    }
+}
@@ -0,0 +1,213 @@
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use anyhow::{anyhow, bail, Context};
+use aptos_move_core_types::account_address::AccountAddress;
+use aptos_move_core_types::language_storage::TypeTag;
+use aptos_move_core_types::u256::U256;
+use aptos_types::transaction::{EntryFunction as AptosEntryFunction, TransactionPayload};
+use log::warn;
+use serde_json::Value;
+
+use crate::input::AptosFuzzerInput;
+use crate::mutator::encode_vector;
+use crate::state::{AptosFuzzerState, FrameworkStruct, FunctionParameter};
+use crate::struct_layout::StructLayoutRegistry;
+
+/// Seeds from transactions that already ran on chain, in the JSON the REST
+/// API returns (`/v1/transactions`, `/v1/accounts/{address}/transactions`):
+/// an array of transactions or a single one. Entry function calls into the
+/// target modules become inputs with the same arguments, type arguments and
+/// signers; every other transaction is skipped, as are calls whose
+/// arguments can't be converted. Senders are added to the address pool.
+pub fn load_transactions(path: &Path, state: &mut AptosFuzzerState) -> anyhow::Result<Vec<AptosFuzzerInput>> {
+    let contents = fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+    let json: Value =
+        serde_json::from_str(&contents).with_context(|| format!("invalid transactions {}", path.display()))?;
+    let transactions = match json {
+        Value::Array(transactions) => transactions,
+        transaction => vec![transaction],
+    };
+
+    let mut inputs = Vec::new();
+    for transaction in &transactions {
+        let payload = &transaction["payload"];
+        if payload["type"] != "entry_function_payload" {
+            continue;
+        }
+        match input_from_transaction(transaction, state) {
+            Ok(Some(input)) => {
+                state.add_pool_address(input.sender());
+                inputs.push(input);
+            }
+            Ok(None) => {}
+            Err(e) => warn!(
+                "[aptos-fuzzer] skipping transaction {}: {:#}",
+                transaction["hash"].as_str().unwrap_or("?"),
+                e
+            ),
+        }
+    }
+    Ok(inputs)
+}
+
+/// The input replaying `transaction`, or `None` if it doesn't call an entry
+/// function of the target modules.
+fn input_from_transaction(transaction: &Value, state: &AptosFuzzerState) -> anyhow::Result<Option<AptosFuzzerInput>> {
+    let payload = &transaction["payload"];
+    let function = payload["function"]
+        .as_str()
+        .ok_or_else(|| anyhow!("missing function"))?;
+    let mut parts = function.split("::");
+    let (Some(address), Some(module), Some(name), None) = (parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        bail!("expected 0xADDR::module::function, got {}", function);
+    };
+    let address = AccountAddress::from_hex_literal(address)?;
+    let Some(target) = state.public_functions().iter().find(|target| {
+        target.is_entry() &&
+            *target.module_id().address() == address &&
+            target.module_id().name().as_str() == module &&
+            target.name().as_str() == name
+    }) else {
+        return Ok(None);
+    };
+
+    let ty_args = json_array(&payload["type_arguments"])?
+        .iter()
+        .map(|ty| {
+            let ty = ty.as_str().ok_or_else(|| anyhow!("type argument is not a string"))?;
+            TypeTag::from_str(ty).with_context(|| format!("invalid type argument {}", ty))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let value_types: Vec<&TypeTag> = target
+        .parameters()
+        .iter()
+        .filter_map(|param| match param {
+            FunctionParameter::Value(tag) => Some(tag),
+            FunctionParameter::Signer | FunctionParameter::Reference { .. } => None,
+        })
+        .collect();
+    let arguments = json_array(&payload["arguments"])?;
+    if value_types.len() != arguments.len() {
+        bail!(
+            "{} takes {} arguments, got {}",
+            function,
+            value_types.len(),
+            arguments.len()
+        );
+    }
+    let args = value_types
+        .into_iter()
+        .zip(arguments)
+        .map(|(tag, value)| json_value_bytes(tag, value, state.struct_layouts()))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    let sender = transaction["sender"]
+        .as_str()
+        .ok_or_else(|| anyhow!("missing sender"))?;
+    if let Value::Array(secondary) = &transaction["signature"]["secondary_signer_addresses"] {
+        if !secondary.is_empty() {
+            bail!("multi-agent transactions are not executed");
+        }
+    }
+
+    let entry = AptosEntryFunction::new(target.module_id().clone(), target.name().clone(), ty_args, args);
+    let mut input = AptosFuzzerInput::new(TransactionPayload::EntryFunction(entry));
+    input.set_sender(AccountAddress::from_hex_literal(sender)?);
+    Ok(Some(input))
+}
+
+fn json_array(value: &Value) -> anyhow::Result<&[Value]> {
+    match value {
+        Value::Array(values) => Ok(values),
+        Value::Null => Ok(&[]),
+        _ => bail!("expected an array, got {}", value),
+    }
+}
+
+/// Integers are strings in the REST API above `u32`, numbers below.
+fn json_integer<T: FromStr>(value: &Value) -> anyhow::Result<T>
+where
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    match value {
+        Value::String(value) => Ok(value.parse()?),
+        Value::Number(value) => Ok(value.to_string().parse()?),
+        _ => bail!("expected an integer, got {}", value),
+    }
+}
+
+fn json_address(value: &Value) -> anyhow::Result<AccountAddress> {
+    let value = value
+        .as_str()
+        .ok_or_else(|| anyhow!("expected an address, got {}", value))?;
+    Ok(AccountAddress::from_hex_literal(value)?)
+}
+
+/// BCS encoding of a value as the REST API renders it: `vector<u8>` as
+/// `0x`-prefixed hex, other vectors as arrays, options as `{"vec": [..]}`
+/// and other structs as objects of their fields, laid out by `layouts`.
+pub(crate) fn json_value_bytes(
+    type_tag: &TypeTag,
+    value: &Value,
+    layouts: &StructLayoutRegistry,
+) -> anyhow::Result<Vec<u8>> {
+    let bytes = match type_tag {
+        TypeTag::Bool => bcs::to_bytes(
+            &value
+                .as_bool()
+                .ok_or_else(|| anyhow!("expected a bool, got {}", value))?,
+        )?,
+        TypeTag::U8 => bcs::to_bytes(&json_integer::<u8>(value)?)?,
+        TypeTag::U16 => bcs::to_bytes(&json_integer::<u16>(value)?)?,
+        TypeTag::U32 => bcs::to_bytes(&json_integer::<u32>(value)?)?,
+        TypeTag::U64 => bcs::to_bytes(&json_integer::<u64>(value)?)?,
+        TypeTag::U128 => bcs::to_bytes(&json_integer::<u128>(value)?)?,
+        TypeTag::U256 => bcs::to_bytes(&json_integer::<U256>(value)?)?,
+        TypeTag::Address => bcs::to_bytes(&json_address(value)?)?,
+        TypeTag::Vector(inner) if **inner == TypeTag::U8 && value.is_string() => bcs::to_bytes(&hex::decode(
+            value.as_str().unwrap_or_default().trim_start_matches("0x"),
+        )?)?,
+        TypeTag::Vector(inner) => {
+            let elements = json_array(value)?
+                .iter()
+                .map(|element| json_value_bytes(inner, element, layouts))
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            encode_vector(&elements)
+        }
+        TypeTag::Struct(tag) => match FrameworkStruct::classify(tag) {
+            Some(FrameworkStruct::String) => bcs::to_bytes(
+                value
+                    .as_str()
+                    .ok_or_else(|| anyhow!("expected a string, got {}", value))?,
+            )?,
+            Some(FrameworkStruct::Option(inner)) => {
+                let elements = json_array(&value["vec"])?
+                    .iter()
+                    .map(|element| json_value_bytes(&inner, element, layouts))
+                    .collect::<anyhow::Result<Vec<_>>>()?;
+                encode_vector(&elements)
+            }
+            Some(FrameworkStruct::FixedPoint32) => bcs::to_bytes(&json_integer::<u64>(&value["value"])?)?,
+            Some(FrameworkStruct::FixedPoint64) => bcs::to_bytes(&json_integer::<u128>(&value["value"])?)?,
+            Some(FrameworkStruct::Object(_)) => bcs::to_bytes(&json_address(&value["inner"])?)?,
+            None => {
+                let layout = layouts
+                    .layout(tag)
+                    .ok_or_else(|| anyhow!("unknown struct {}", type_tag.to_canonical_string()))?;
+                let mut bytes = Vec::new();
+                for (name, field) in &layout.fields {
+                    let field_value = value
+                        .get(name.as_str())
+                        .ok_or_else(|| anyhow!("missing field {} of {}", name, tag.name))?;
+                    bytes.extend(json_value_bytes(field, field_value, layouts)?);
+                }
+                bytes
+            }
+        },
+        _ => bail!("unsupported argument type {}", type_tag.to_canonical_string()),
+    };
+    Ok(bytes)
+}
@@ -1,13 +1,43 @@
+use aptos_move_core_types::account_address::AccountAddress;
 use aptos_types::transaction::TransactionPayload;
 use libafl::inputs::Input;
 use serde::{Deserialize, Serialize};
 
+use crate::module_code;
 use crate::script_sequence::ScriptSequence;
 
+/// Optional overrides of the block environment a transaction executes in.
+/// Fields left `None` keep the value from the fuzzing state.
+#[derive(Debug, Clone, Copy, Default, Hash, Eq, PartialEq, Deserialize, Serialize)]
+pub struct BlockContext {
+    /// `0x1::timestamp::CurrentTimeMicroseconds`
+    pub timestamp_us: Option<u64>,
+    /// `0x1::reconfiguration::Configuration::epoch`
+    pub epoch: Option<u64>,
+    /// `0x1::chain_id::ChainId`
+    pub chain_id: Option<u8>,
+}
+
+impl BlockContext {
+    pub fn is_empty(&self) -> bool {
+        self.timestamp_us.is_none() && self.epoch.is_none() && self.chain_id.is_none()
+    }
+}
+
 #[derive(Debug, Clone, Hash, Eq, PartialEq, Deserialize, Serialize)]
 pub struct AptosFuzzerInput {
     payload: TransactionPayload,
     script_sequence: Option<ScriptSequence>,
+    #[serde(default)]
+    block_context: Option<BlockContext>,
+    /// Account the transaction is sent from. `None` for the default
+    /// sender `0x1`
+    #[serde(default)]
+    sender: Option<AccountAddress>,
+    /// Bytes of the module a publish input publishes, which the payload is
+    /// rebuilt from whenever they're mutated
+    #[serde(default)]
+    module_code: Option<Vec<u8>>,
 }
 
 impl Input for AptosFuzzerInput {}
@@ -19,6 +49,9 @@ impl AptosFuzzerInput {
         Self {
             payload,
             script_sequence: None,
+            block_context: None,
+            sender: None,
+            module_code: None,
         }
     }
 
@@ -26,9 +59,20 @@ impl AptosFuzzerInput {
         Self {
             payload,
             script_sequence: Some(sequence),
+            block_context: None,
+            sender: None,
+            module_code: None,
         }
     }
 
+    /// A transaction publishing `code` from the module's own address.
+    pub fn publish(code: Vec<u8>) -> Self {
+        let mut input = Self::new(module_code::publish_payload(&code));
+        input.sender = Some(module_code::publisher(&code));
+        input.module_code = Some(code);
+        input
+    }
+
     pub fn payload(&self) -> &TransactionPayload {
         &self.payload
     }
@@ -48,4 +92,62 @@ impl AptosFuzzerInput {
     pub fn set_script_sequence(&mut self, sequence: Option<ScriptSequence>) {
         self.script_sequence = sequence;
     }
+
+    pub fn module_code(&self) -> Option<&[u8]> {
+        self.module_code.as_deref()
+    }
+
+    /// Publish `code` instead, keeping the sender.
+    pub fn set_module_code(&mut self, code: Vec<u8>) {
+        self.payload = module_code::publish_payload(&code);
+        self.module_code = Some(code);
+    }
+
+    /// The functions this input calls, e.g. `0xcafe::pool::swap`, or
+    /// `a -> b` for a script sequence.
+    pub fn describe(&self) -> String {
+        match self.called_functions() {
+            calls if calls.is_empty() => "script".to_string(),
+            calls => calls.join(" -> "),
+        }
+    }
+
+    /// `<address>::<module>::<function>` of each function this input calls,
+    /// in call order; empty for a script without a sequence.
+    pub fn called_functions(&self) -> Vec<String> {
+        match (&self.payload, &self.script_sequence) {
+            (TransactionPayload::EntryFunction(entry), _) => vec![format!("{}::{}", entry.module(), entry.function())],
+            (_, Some(sequence)) => sequence
+                .calls()
+                .iter()
+                .map(|call| format!("{}::{}", call.module(), call.function()))
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+impl AptosFuzzerInput {
+    pub fn block_context(&self) -> Option<&BlockContext> {
+        self.block_context.as_ref()
+    }
+
+    pub fn set_block_context(&mut self, block_context: Option<BlockContext>) {
+        self.block_context = block_context.filter(|ctx| !ctx.is_empty());
+    }
+
+    /// The account the transaction is sent from.
+    pub fn sender(&self) -> AccountAddress {
+        self.sender.unwrap_or(AccountAddress::ONE)
+    }
+
+    /// The sender set on this input, `None` when it is sent from the
+    /// default `0x1`.
+    pub fn assigned_sender(&self) -> Option<AccountAddress> {
+        self.sender
+    }
+
+    pub fn set_sender(&mut self, sender: AccountAddress) {
+        self.sender = Some(sender);
+    }
 }
@@ -1,16 +1,60 @@
+#[cfg(feature = "concolic")]
+pub mod abort_target;
+pub mod arg_constraint;
+pub mod bisect;
+pub mod branches;
+pub mod check;
+pub mod checkpoint;
 pub mod concolic;
+pub mod config;
+pub mod conflicts;
+pub mod error;
 pub mod executor;
 pub mod feedback;
+pub mod fixture;
+pub mod generics;
+pub mod history;
 pub mod input;
+pub mod module_code;
 pub mod mutator;
 pub mod observers;
+pub mod oracle;
+pub mod package;
+pub mod resource_leak;
+pub mod revalidation;
+pub mod runner;
+pub mod runtime_detector;
+pub mod scheduler;
+pub mod script_code;
 pub mod script_sequence;
+pub mod sender;
+#[cfg(feature = "concolic")]
+pub mod solver;
+pub mod source_location;
 pub mod state;
 pub mod static_analysis;
+pub mod struct_layout;
+pub mod supervisor;
+#[cfg(feature = "concolic")]
+pub mod symbolic;
+pub mod sync;
+pub mod taint;
+pub mod trace;
+pub mod upgrade;
+pub mod watch;
 
-pub use concolic::{RuntimeIssue, RuntimeIssueKind};
+pub use concolic::{RuntimeIssue, RuntimeIssueKind, Severity};
+pub use error::{FuzzerError, Subsystem};
 pub use executor::aptos_move_executor::AptosMoveExecutor;
-pub use feedback::{AbortCodeObjective, ShiftOverflowObjective};
+pub use feedback::{
+    AbortCodeNoveltyFeedback, AbortCodeObjective, AllowedAbort, BranchDistanceFeedback, DifferentialObjective,
+    ExecutionBudgetObjective, ExecutionMetadata, ExecutionMetadataFeedback, InvariantOracleObjective,
+    NewEventTypeFeedback, ResourceLeakObjective, ResourceTouchFeedback, RuntimeIssueObjective, ShiftOverflowObjective,
+    Suppression, VmStatusNoveltyFeedback,
+};
 pub use input::AptosFuzzerInput;
-pub use mutator::AptosFuzzerMutator;
+pub use mutator::{AptosFuzzerMutator, MutationKind, MutationRates, MutationWeights};
+pub use oracle::{InvariantOracle, Violation};
+pub use runtime_detector::{RuntimeDetector, RuntimeDetectorConfig};
+pub use source_location::{SourceLocation, SourceLocator};
 pub use state::{AptosFuzzerState, MAP_SIZE};
@@ -0,0 +1,266 @@
+use aptos_move_binary_format::access::ModuleAccess;
+use aptos_move_binary_format::file_format::{
+    AbilitySet, CompiledModule, SignatureToken, StructFieldInformation, Visibility,
+};
+use aptos_move_core_types::account_address::AccountAddress;
+use aptos_move_core_types::identifier::Identifier;
+use aptos_move_core_types::language_storage::ModuleId;
+use aptos_types::transaction::{EntryFunction, TransactionPayload};
+use libafl_bolts::rands::Rand;
+use serde::Serialize;
+
+use crate::script_code::mutate_code;
+
+/// Name of the package mutated modules are published in
+const PUBLISH_PACKAGE: &str = "FuzzedPackage";
+
+/// Mirror of `0x1::code::PackageMetadata`, serialized to the bytes
+/// `0x1::code::publish_package_txn` takes as its first argument.
+#[derive(Serialize)]
+struct PackageMetadata {
+    name: String,
+    upgrade_policy: UpgradePolicy,
+    upgrade_number: u64,
+    source_digest: String,
+    manifest: Vec<u8>,
+    modules: Vec<ModuleMetadata>,
+    deps: Vec<PackageDep>,
+    extension: Option<Any>,
+}
+
+#[derive(Serialize)]
+struct UpgradePolicy {
+    policy: u8,
+}
+
+#[derive(Serialize)]
+struct ModuleMetadata {
+    name: String,
+    source: Vec<u8>,
+    source_map: Vec<u8>,
+    extension: Option<Any>,
+}
+
+#[derive(Serialize)]
+struct PackageDep {
+    account: AccountAddress,
+    package_name: String,
+}
+
+#[derive(Serialize)]
+struct Any {
+    type_name: String,
+    data: Vec<u8>,
+}
+
+/// A `0x1::code::publish_package_txn` call publishing `code` as the only
+/// module of a package with the compatible upgrade policy. The module is
+/// named after what `code` deserializes to, so mutants that no longer
+/// deserialize still reach the publish path.
+pub fn publish_payload(code: &[u8]) -> TransactionPayload {
+    publish_package_payload(PUBLISH_PACKAGE, code)
+}
+
+/// As `publish_payload`, in the package named `package`, which must not
+/// hold other modules at the publisher's address.
+pub fn publish_package_payload(package: &str, code: &[u8]) -> TransactionPayload {
+    let name = CompiledModule::deserialize(code)
+        .map(|module| module.self_id().name().to_string())
+        .unwrap_or_default();
+    let metadata = PackageMetadata {
+        name: package.to_string(),
+        upgrade_policy: UpgradePolicy { policy: 1 },
+        upgrade_number: 0,
+        source_digest: String::new(),
+        manifest: Vec::new(),
+        modules: vec![ModuleMetadata {
+            name,
+            source: Vec::new(),
+            source_map: Vec::new(),
+            extension: None,
+        }],
+        deps: Vec::new(),
+        extension: None,
+    };
+    let args = vec![
+        bcs::to_bytes(&bcs::to_bytes(&metadata).expect("package metadata serializes")).expect("bytes serialize"),
+        bcs::to_bytes(&vec![code.to_vec()]).expect("module bytes serialize"),
+    ];
+    TransactionPayload::EntryFunction(EntryFunction::new(
+        ModuleId::new(AccountAddress::ONE, Identifier::new("code").expect("valid identifier")),
+        Identifier::new("publish_package_txn").expect("valid identifier"),
+        Vec::new(),
+        args,
+    ))
+}
+
+/// The account a module is published from: its own address, or `0x1` when
+/// `code` doesn't deserialize.
+pub fn publisher(code: &[u8]) -> AccountAddress {
+    CompiledModule::deserialize(code)
+        .map(|module| *module.self_id().address())
+        .unwrap_or(AccountAddress::ONE)
+}
+
+/// `code` with one of its tables or function bodies mutated: an
+/// instruction, identifier, constant, signature token, field type,
+/// visibility or ability. Unlike script mutants these are not verified, as
+/// the point is to find what the verifier and loader let through or choke
+/// on. Now and then a raw byte of the serialized module is flipped to
+/// exercise the deserializer instead. `None` when `code` doesn't
+/// deserialize or the mutant doesn't serialize.
+pub fn mutate_module_code(code: &[u8], rand: &mut impl Rand) -> Option<Vec<u8>> {
+    if rand.next() % 8 == 0 && !code.is_empty() {
+        let mut bytes = code.to_vec();
+        let at = (rand.next() as usize) % bytes.len();
+        bytes[at] ^= 1 << (rand.next() % 8);
+        return Some(bytes);
+    }
+    let mut module = CompiledModule::deserialize(code).ok()?;
+    let mutated = match rand.next() % 6 {
+        0 => mutate_function_code(&mut module, rand),
+        1 => mutate_identifier(&mut module, rand),
+        2 => mutate_constant(&mut module, rand),
+        3 => mutate_signature(&mut module, rand),
+        4 => mutate_field_type(&mut module, rand),
+        _ => mutate_declaration(&mut module, rand),
+    };
+    if !mutated {
+        return None;
+    }
+    let mut bytes = Vec::new();
+    module.serialize(&mut bytes).ok()?;
+    Some(bytes)
+}
+
+fn mutate_function_code(module: &mut CompiledModule, rand: &mut impl Rand) -> bool {
+    let with_code: Vec<usize> = (0..module.function_defs.len())
+        .filter(|&i| module.function_defs[i].code.is_some())
+        .collect();
+    if with_code.is_empty() {
+        return false;
+    }
+    let index = with_code[(rand.next() as usize) % with_code.len()];
+    let handle = module.function_handle_at(module.function_defs[index].function);
+    let parameters = module.signature_at(handle.parameters).len();
+    let locals = match &module.function_defs[index].code {
+        Some(unit) => parameters + module.signature_at(unit.locals).len(),
+        None => return false,
+    };
+    let handles = module.function_handles.len();
+    match &mut module.function_defs[index].code {
+        Some(unit) => mutate_code(&mut unit.code, locals, handles, rand),
+        None => false,
+    }
+}
+
+/// Swap two identifiers, or replace one with another, which renames or
+/// duplicates whatever names it.
+fn mutate_identifier(module: &mut CompiledModule, rand: &mut impl Rand) -> bool {
+    let len = module.identifiers.len();
+    if len < 2 {
+        return false;
+    }
+    let (a, b) = ((rand.next() as usize) % len, (rand.next() as usize) % len);
+    if rand.next() % 2 == 0 {
+        module.identifiers.swap(a, b);
+    } else {
+        module.identifiers[a] = module.identifiers[b].clone();
+    }
+    a != b
+}
+
+/// Flip a byte of a constant's value, or grow or truncate it, so the value
+/// no longer matches its declared type.
+fn mutate_constant(module: &mut CompiledModule, rand: &mut impl Rand) -> bool {
+    let len = module.constant_pool.len();
+    if len == 0 {
+        return false;
+    }
+    let data = &mut module.constant_pool[(rand.next() as usize) % len].data;
+    match rand.next() % 3 {
+        0 if !data.is_empty() => {
+            let at = (rand.next() as usize) % data.len();
+            data[at] ^= 1 << (rand.next() % 8);
+        }
+        1 => data.push(rand.next() as u8),
+        _ => {
+            data.pop();
+        }
+    }
+    true
+}
+
+/// Replace a token of a signature with a random primitive type.
+fn mutate_signature(module: &mut CompiledModule, rand: &mut impl Rand) -> bool {
+    let len = module.signatures.len();
+    if len == 0 {
+        return false;
+    }
+    let tokens = &mut module.signatures[(rand.next() as usize) % len].0;
+    if tokens.is_empty() {
+        return false;
+    }
+    let at = (rand.next() as usize) % tokens.len();
+    tokens[at] = primitive_token(rand);
+    true
+}
+
+/// Replace the type of a struct field with a random primitive type.
+fn mutate_field_type(module: &mut CompiledModule, rand: &mut impl Rand) -> bool {
+    let len = module.struct_defs.len();
+    if len == 0 {
+        return false;
+    }
+    let token = primitive_token(rand);
+    match &mut module.struct_defs[(rand.next() as usize) % len].field_information {
+        StructFieldInformation::Declared(fields) if !fields.is_empty() => {
+            let at = (rand.next() as usize) % fields.len();
+            fields[at].signature.0 = token;
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Change a function's visibility or entry flag, or toggle an ability of a
+/// struct handle.
+fn mutate_declaration(module: &mut CompiledModule, rand: &mut impl Rand) -> bool {
+    if rand.next() % 2 == 0 && !module.struct_handles.is_empty() {
+        let at = (rand.next() as usize) % module.struct_handles.len();
+        let handle = &mut module.struct_handles[at];
+        let toggled = handle.abilities.into_u8() ^ (1 << (rand.next() % 4));
+        return match AbilitySet::from_u8(toggled) {
+            Some(abilities) => {
+                handle.abilities = abilities;
+                true
+            }
+            None => false,
+        };
+    }
+    let len = module.function_defs.len();
+    if len == 0 {
+        return false;
+    }
+    let function = &mut module.function_defs[(rand.next() as usize) % len];
+    match rand.next() % 4 {
+        0 => function.visibility = Visibility::Private,
+        1 => function.visibility = Visibility::Public,
+        2 => function.visibility = Visibility::Friend,
+        _ => function.is_entry = !function.is_entry,
+    }
+    true
+}
+
+fn primitive_token(rand: &mut impl Rand) -> SignatureToken {
+    match rand.next() % 8 {
+        0 => SignatureToken::Bool,
+        1 => SignatureToken::U8,
+        2 => SignatureToken::U64,
+        3 => SignatureToken::U128,
+        4 => SignatureToken::U256,
+        5 => SignatureToken::Address,
+        6 => SignatureToken::Signer,
+        _ => SignatureToken::Vector(Box::new(SignatureToken::U8)),
+    }
+}
@@ -1,34 +1,425 @@
 use std::borrow::Cow;
 use std::cmp;
+use std::collections::{BTreeMap, HashMap, HashSet};
 
-use aptos_move_core_types::language_storage::TypeTag;
+use aptos_move_core_types::account_address::AccountAddress;
+use aptos_move_core_types::identifier::Identifier;
+use aptos_move_core_types::language_storage::{ModuleId, StructTag, TypeTag};
 use aptos_types::transaction::{EntryFunction, Script, TransactionArgument, TransactionPayload};
+use libafl::corpus::{Corpus, CorpusId, HasCurrentCorpusId};
 use libafl::mutators::{MutationResult, Mutator};
-use libafl::state::HasRand;
+use libafl::state::{HasCorpus, HasCurrentTestcase, HasExecutions, HasRand, HasSolutions};
 use libafl_bolts::rands::Rand;
 use libafl_bolts::Named;
 
-use crate::input::AptosFuzzerInput;
-use crate::script_sequence::{compile_sequence, ScriptSequence, SequenceArgument, SequenceCall};
-use crate::state::{AptosFuzzerState, FunctionParameter, PublicFunctionTarget};
+use crate::input::{AptosFuzzerInput, BlockContext};
+use crate::module_code::mutate_module_code;
+use crate::script_code::mutate_script_code;
+use crate::script_sequence::{compile_sequence, ResultOperation, ScriptSequence, SequenceArgument, SequenceCall};
+use crate::state::{AptosFuzzerState, FrameworkStruct, FunctionParameter, PublicFunctionTarget};
 
-#[derive(Default)]
-pub struct AptosFuzzerMutator {}
+/// One in this many mutations perturbs the block context instead of the
+/// payload.
+const BLOCK_CONTEXT_MUTATION_RATE: u64 = 8;
+/// Starting timestamp when an input has no override yet (2024-01-01 UTC).
+const DEFAULT_TIMESTAMP_US: u64 = 1_704_067_200_000_000;
+const SECOND_US: u64 = 1_000_000;
+const HOUR_US: u64 = 3_600 * SECOND_US;
+const DAY_US: u64 = 24 * HOUR_US;
+/// Mainnet epoch interval.
+const EPOCH_INTERVAL_US: u64 = 2 * HOUR_US;
+/// Mainnet, testnet and the test chain id.
+const CHAIN_IDS: [u8; 3] = [1, 2, 4];
+
+/// One in this many mutations sets a numeric argument to a boundary value
+/// instead of random bytes.
+const INTERESTING_VALUE_RATE: u64 = 3;
+/// One in this many mutations crosses the input over with another corpus
+/// entry.
+const CROSSOVER_RATE: u64 = 6;
+/// Corpus entries sampled when looking for a crossover partner.
+const CROSSOVER_ATTEMPTS: usize = 8;
+/// Havoc stacks hold up to `2^DEFAULT_MAX_STACK_POW` mutations.
+const DEFAULT_MAX_STACK_POW: u32 = 5;
+/// Longest vector argument generated by default.
+const DEFAULT_MAX_VECTOR_LEN: usize = 1024;
+/// Most calls a script sequence grows to by default.
+const DEFAULT_MAX_SEQUENCE_LEN: usize = 16;
+/// Sequences of up to this many calls get every iteration they are
+/// scheduled for; longer ones a share shrinking with their length.
+const SHALLOW_SEQUENCE_LEN: usize = 4;
+/// Executions since mutating a corpus entry last added to the corpus during
+/// which it still counts as productive, whatever its length.
+const RECENT_FIND_EXECUTIONS: u64 = 10_000;
+/// Longest vector generated inside another value, e.g. the inner vectors of
+/// a `vector<vector<u8>>`, keeping nested values small.
+const MAX_NESTED_VECTOR_LEN: usize = 8;
+/// One in this many address mutations picks a random address instead of one
+/// from the state's address pool.
+const RANDOM_ADDRESS_RATE: u64 = 4;
+/// Non-ASCII characters appended to `String` arguments, covering two-, three-
+/// and four-byte UTF-8 encodings.
+const MULTIBYTE_CHARS: [char; 4] = ['é', 'ß', '中', '🦀'];
+
+/// One in this many mutations sends the transaction from another account.
+const SENDER_MUTATION_RATE: u64 = 8;
+
+/// One in this many mutations calls a generic function with other type
+/// arguments.
+const TYPE_ARGS_MUTATION_RATE: u64 = 8;
+/// With `--fuzz-vm`, one in this many mutations of a script input mutates
+/// its code instead of its calls.
+const SCRIPT_CODE_MUTATION_RATE: u64 = 2;
+
+/// Iterations each mutation kind is assumed to have taken part in at the
+/// overall find rate, so its first few results don't swing its weight.
+const WEIGHT_PRIOR_USES: f64 = 100.0;
+/// Bounds of a mutation kind's weight: unproductive kinds are still tried
+/// now and then, and productive ones don't crowd out the rest.
+const MIN_MUTATION_WEIGHT: f64 = 0.25;
+const MAX_MUTATION_WEIGHT: f64 = 4.0;
+/// Sequence mutations, in the order they are drawn from.
+const SEQUENCE_KINDS: [MutationKind; 5] = [
+    MutationKind::SequenceAppend,
+    MutationKind::SequenceDelete,
+    MutationKind::SequenceSwap,
+    MutationKind::SequenceDuplicate,
+    MutationKind::SequenceSplice,
+];
+
+/// Kind of a single mutation, counted to help tune the mutator.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum MutationKind {
+    EntryArgs,
+    SequenceAppend,
+    SequenceDelete,
+    SequenceSwap,
+    SequenceDuplicate,
+    SequenceSplice,
+    InterestingValue,
+    Crossover,
+    BlockContext,
+    Sender,
+    TypeArgs,
+    ScriptCode,
+    ModuleCode,
+}
+
+impl MutationKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MutationKind::EntryArgs => "entry-args",
+            MutationKind::SequenceAppend => "seq-append",
+            MutationKind::SequenceDelete => "seq-delete",
+            MutationKind::SequenceSwap => "seq-swap",
+            MutationKind::SequenceDuplicate => "seq-duplicate",
+            MutationKind::SequenceSplice => "seq-splice",
+            MutationKind::InterestingValue => "interesting-value",
+            MutationKind::Crossover => "crossover",
+            MutationKind::BlockContext => "block-context",
+            MutationKind::Sender => "sender",
+            MutationKind::TypeArgs => "type-args",
+            MutationKind::ScriptCode => "script-code",
+            MutationKind::ModuleCode => "module-code",
+        }
+    }
+}
+
+/// How often the mutations tried before argument and sequence mutations
+/// are attempted: one in this many, so larger is rarer. 0 disables one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MutationRates {
+    pub block_context: u64,
+    pub crossover: u64,
+    pub interesting_value: u64,
+    pub sender: u64,
+}
+
+impl Default for MutationRates {
+    fn default() -> Self {
+        Self {
+            block_context: BLOCK_CONTEXT_MUTATION_RATE,
+            crossover: CROSSOVER_RATE,
+            interesting_value: INTERESTING_VALUE_RATE,
+            sender: SENDER_MUTATION_RATE,
+        }
+    }
+}
+
+/// How productive each kind of mutation has been, learned online: for every
+/// kind, the iterations it took part in and how many of those added to the
+/// corpus or found a solution. A kind's weight is its find rate relative to
+/// the overall one, shrunk towards 1 while it has few iterations and clamped
+/// so every kind keeps being explored.
+#[derive(Clone, Debug, Default)]
+pub struct MutationWeights {
+    /// Iterations and finds of each kind tried so far
+    arms: BTreeMap<MutationKind, (u64, u64)>,
+    iterations: u64,
+    finds: u64,
+}
+
+impl MutationWeights {
+    /// Credit an iteration that applied `kinds`, each kind once.
+    pub fn record(&mut self, kinds: &[MutationKind], found: bool) {
+        self.iterations += 1;
+        self.finds += found as u64;
+        for kind in kinds {
+            let (iterations, finds) = self.arms.entry(*kind).or_insert((0, 0));
+            *iterations += 1;
+            *finds += found as u64;
+        }
+    }
+
+    /// How much more often than its base rate `kind` should be picked.
+    pub fn weight(&self, kind: MutationKind) -> f64 {
+        let overall = (self.finds as f64 + 1.0) / (self.iterations as f64 + 1.0);
+        let (iterations, finds) = self.arms.get(&kind).copied().unwrap_or((0, 0));
+        let rate = (finds as f64 + WEIGHT_PRIOR_USES * overall) / (iterations as f64 + WEIGHT_PRIOR_USES);
+        (rate / overall).clamp(MIN_MUTATION_WEIGHT, MAX_MUTATION_WEIGHT)
+    }
+
+    /// Weight, iterations and finds of every kind tried so far.
+    pub fn arms(&self) -> impl Iterator<Item = (MutationKind, f64, u64, u64)> + '_ {
+        self.arms
+            .iter()
+            .map(|(kind, (iterations, finds))| (*kind, self.weight(*kind), *iterations, *finds))
+    }
+}
+
+/// Applies an AFL-style havoc stack of mutations to each input, favouring
+/// the kinds of mutation that have been productive so far.
+pub struct AptosFuzzerMutator {
+    max_stack_pow: u32,
+    max_vector_len: usize,
+    max_sequence_len: usize,
+    rates: MutationRates,
+    /// Executions at which mutating each corpus entry, or adding it, last
+    /// added to the corpus
+    finds: HashMap<CorpusId, u64>,
+    /// Kinds applied to the input being run, credited in `post_exec`
+    applied: Vec<MutationKind>,
+    /// Solutions before the input being run
+    solutions_before: usize,
+    /// Generic calls of the input being run, credited in `post_exec`
+    instantiated: Vec<(ModuleId, Identifier, Vec<TypeTag>)>,
+    /// Mutate the bytecode of script inputs, to fuzz the VM itself
+    fuzz_vm: bool,
+}
+
+impl Default for AptosFuzzerMutator {
+    fn default() -> Self {
+        Self {
+            max_stack_pow: DEFAULT_MAX_STACK_POW,
+            max_vector_len: DEFAULT_MAX_VECTOR_LEN,
+            max_sequence_len: DEFAULT_MAX_SEQUENCE_LEN,
+            rates: MutationRates::default(),
+            finds: HashMap::new(),
+            applied: Vec::new(),
+            solutions_before: 0,
+            instantiated: Vec::new(),
+            fuzz_vm: false,
+        }
+    }
+}
 
 impl AptosFuzzerMutator {
-    fn mutate_entry_function_args(entry_func: &mut EntryFunction, state: &mut AptosFuzzerState) -> bool {
+    /// Stack at most `2^max_stack_pow` mutations per iteration.
+    pub fn with_max_stack_pow(max_stack_pow: u32) -> Self {
+        Self {
+            max_stack_pow: max_stack_pow.max(1),
+            ..Self::default()
+        }
+    }
+
+    /// Never grow vector arguments past `max_vector_len` elements, bounding
+    /// the cost of executions that loop over them.
+    pub fn with_max_vector_len(mut self, max_vector_len: usize) -> Self {
+        self.max_vector_len = max_vector_len;
+        self
+    }
+
+    /// Never grow script sequences past `max_sequence_len` calls; full ones
+    /// only lose calls or have them reordered.
+    pub fn with_max_sequence_len(mut self, max_sequence_len: usize) -> Self {
+        self.max_sequence_len = max_sequence_len.max(1);
+        self
+    }
+
+    pub fn with_rates(mut self, rates: MutationRates) -> Self {
+        self.rates = rates;
+        self
+    }
+
+    /// Also mutate the instructions of script inputs, within what the
+    /// bytecode verifier accepts. The VM and the verifier become the
+    /// target rather than the loaded modules, which only provide the
+    /// functions the scripts call.
+    pub fn with_vm_fuzzing(mut self, enabled: bool) -> Self {
+        self.fuzz_vm = enabled;
+        self
+    }
+
+    /// One-in-`rate` chance, scaled by the learned weight of `kind`; never
+    /// for a rate of 0.
+    fn roll(state: &mut AptosFuzzerState, rate: u64, kind: MutationKind) -> bool {
+        if rate == 0 {
+            return false;
+        }
+        let rate = ((rate as f64 / state.mutation_weights().weight(kind)).round() as u64).max(1);
+        state.rand_mut().next() % rate == 0
+    }
+
+    /// Number of mutations to stack on the current input. The bound grows
+    /// with how often the testcase has been scheduled: once its immediate
+    /// neighbourhood has been explored, larger jumps pay off.
+    fn stack_size(&self, state: &mut AptosFuzzerState) -> usize {
+        let scheduled = state
+            .current_testcase()
+            .map(|testcase| testcase.scheduled_count())
+            .unwrap_or(0);
+        let pow = cmp::min(1 + (scheduled as u64 + 1).ilog2(), self.max_stack_pow);
+        1 + (state.rand_mut().next() as usize) % (1 << pow)
+    }
+
+    /// Whether to skip this iteration of the current input, a sequence
+    /// longer than `SHALLOW_SEQUENCE_LEN` calls, so it gets that many in its
+    /// length of the iterations. Entries that added to the corpus within the
+    /// last `RECENT_FIND_EXECUTIONS` get them all.
+    fn skip_deep_sequence(&self, state: &mut AptosFuzzerState, input: &AptosFuzzerInput) -> bool {
+        let len = input.script_sequence().map_or(0, ScriptSequence::len);
+        if len <= SHALLOW_SEQUENCE_LEN {
+            return false;
+        }
+        let executions = *state.executions();
+        let productive = state
+            .current_corpus_id()
+            .ok()
+            .flatten()
+            .and_then(|id| self.finds.get(&id))
+            .is_some_and(|&found_at| executions.saturating_sub(found_at) < RECENT_FIND_EXECUTIONS);
+        !productive && (state.rand_mut().next() as usize) % len >= SHALLOW_SEQUENCE_LEN
+    }
+
+    fn mutate_once(&self, state: &mut AptosFuzzerState, input: &mut AptosFuzzerInput) -> Option<MutationKind> {
+        // A publish input is only its module: other mutations would stop it
+        // from being a publish of it
+        if input.module_code().is_some() {
+            return Self::mutate_module_code(state, input).then_some(MutationKind::ModuleCode);
+        }
+
+        if Self::roll(state, self.rates.block_context, MutationKind::BlockContext) &&
+            Self::mutate_block_context(state, input)
+        {
+            return Some(MutationKind::BlockContext);
+        }
+
+        if Self::roll(state, self.rates.sender, MutationKind::Sender) && Self::mutate_sender(state, input) {
+            return Some(MutationKind::Sender);
+        }
+
+        if Self::roll(state, self.rates.crossover, MutationKind::Crossover) &&
+            Self::crossover(state, input, self.max_sequence_len)
+        {
+            return Some(MutationKind::Crossover);
+        }
+
+        if Self::roll(state, TYPE_ARGS_MUTATION_RATE, MutationKind::TypeArgs) && Self::mutate_type_args(state, input) {
+            return Some(MutationKind::TypeArgs);
+        }
+
+        if self.fuzz_vm &&
+            Self::roll(state, SCRIPT_CODE_MUTATION_RATE, MutationKind::ScriptCode) &&
+            Self::mutate_script_code(state, input)
+        {
+            return Some(MutationKind::ScriptCode);
+        }
+
+        let inject = Self::roll(state, self.rates.interesting_value, MutationKind::InterestingValue);
+        match input.payload() {
+            TransactionPayload::Script(_) => {
+                if inject && Self::inject_interesting_sequence_value(state, input) {
+                    return Some(MutationKind::InterestingValue);
+                }
+                Self::mutate_sequence(state, input, self.max_vector_len, self.max_sequence_len)
+            }
+            _ => match input.payload_mut() {
+                TransactionPayload::EntryFunction(entry_func) => {
+                    if inject && Self::inject_interesting_entry_value(entry_func, state) {
+                        return Some(MutationKind::InterestingValue);
+                    }
+                    Self::mutate_entry_function_args(entry_func, state, self.max_vector_len)
+                        .then_some(MutationKind::EntryArgs)
+                }
+                _ => None,
+            },
+        }
+    }
+
+    /// Replace the code of a script input with a mutant of it. The input
+    /// stops being a sequence, as its code is no longer what the sequence
+    /// compiles to.
+    fn mutate_script_code(state: &mut AptosFuzzerState, input: &mut AptosFuzzerInput) -> bool {
+        let TransactionPayload::Script(script) = input.payload() else {
+            return false;
+        };
+        let Some(code) = mutate_script_code(script.code(), state.rand_mut()) else {
+            return false;
+        };
+        let script = Script::new(code, script.ty_args().to_vec(), script.args().to_vec());
+        *input.payload_mut() = TransactionPayload::Script(script);
+        input.set_script_sequence(None);
+        true
+    }
+
+    /// Compile `sequence` against the state's modules, counting the failure
+    /// when it doesn't compile.
+    fn compile(state: &mut AptosFuzzerState, sequence: &ScriptSequence) -> Option<Script> {
+        compile_sequence(sequence, state.aptos_state().module_bytes())
+            .map_err(|err| state.record_suppressed_error(&err))
+            .ok()
+    }
+
+    /// Replace the module a publish input publishes with a mutant of it.
+    fn mutate_module_code(state: &mut AptosFuzzerState, input: &mut AptosFuzzerInput) -> bool {
+        let Some(code) = input.module_code() else {
+            return false;
+        };
+        let Some(code) = mutate_module_code(code, state.rand_mut()) else {
+            return false;
+        };
+        input.set_module_code(code);
+        true
+    }
+
+    fn mutate_entry_function_args(
+        entry_func: &mut EntryFunction,
+        state: &mut AptosFuzzerState,
+        max_vector_len: usize,
+    ) -> bool {
         let args = entry_func.args();
         if args.is_empty() {
             return false;
         }
 
+        // Arguments of known type get structured mutations that keep them
+        // decodable; the others get random bytes
+        let value_types: Vec<Option<TypeTag>> = match Self::entry_value_types(entry_func, state) {
+            Some(types) => types.into_iter().map(Some).collect(),
+            None => vec![None; args.len()],
+        };
+
         // Create new mutated arguments
         let mut new_args = Vec::new();
         let mut mutated = false;
 
-        for arg_bytes in args.iter() {
+        for (arg_bytes, ty) in args.iter().zip(&value_types) {
             let mut mutated_arg = arg_bytes.clone();
-            if Self::mutate_byte_vector(&mut mutated_arg, state) {
+            let arg_mutated = match ty {
+                Some(ty) => Self::mutate_value(ty, &mut mutated_arg, max_vector_len, state),
+                None => Self::mutate_byte_vector(&mut mutated_arg, state),
+            };
+            if arg_mutated {
                 mutated = true;
             }
             new_args.push(mutated_arg);
@@ -43,8 +434,202 @@ impl AptosFuzzerMutator {
         mutated
     }
 
-    /// Mutate Script arguments using state's random source (pure random)
-    fn mutate_script_args(script: &mut Script, state: &mut AptosFuzzerState) -> bool {
+    /// Declared types of the non-signer parameters of `entry_func`, if they
+    /// line up with its arguments.
+    fn entry_value_types(entry_func: &EntryFunction, state: &AptosFuzzerState) -> Option<Vec<TypeTag>> {
+        let function = state.function_instance(entry_func.module(), entry_func.function(), entry_func.ty_args())?;
+        let value_types: Vec<TypeTag> = function
+            .parameters()
+            .iter()
+            .filter_map(|param| match param {
+                FunctionParameter::Value(tag) => Some(tag.clone()),
+                FunctionParameter::Signer | FunctionParameter::Reference { .. } => None,
+            })
+            .collect();
+        (value_types.len() == entry_func.args().len()).then_some(value_types)
+    }
+
+    /// An address from the state's address pool, or occasionally a random one.
+    fn mutate_address(state: &mut AptosFuzzerState) -> AccountAddress {
+        if state.rand_mut().next() % RANDOM_ADDRESS_RATE != 0 {
+            if let Some(address) = state.random_pool_address() {
+                return address;
+            }
+        }
+        let mut addr_bytes = [0u8; 32];
+        for byte in addr_bytes.iter_mut() {
+            *byte = (state.rand_mut().next() % 256) as u8;
+        }
+        AccountAddress::new(addr_bytes)
+    }
+
+    /// Mutate the BCS encoding of a framework struct so that it still decodes:
+    /// valid UTF-8 for `String`, a well-formed `none`/`some` for `Option`, and
+    /// 1.0, a pure fraction or a random value for fixed-point numbers, and an
+    /// address from the object pool for `Object<T>`.
+    fn mutate_framework_value(framework: &FrameworkStruct, bytes: &mut Vec<u8>, state: &mut AptosFuzzerState) -> bool {
+        let encoded = match framework {
+            FrameworkStruct::String => {
+                let mut value: String = bcs::from_bytes(bytes).unwrap_or_default();
+                match state.rand_mut().next() % 5 {
+                    0 => value.clear(),
+                    1 => {
+                        value.pop();
+                    }
+                    2 => value.push(Self::random_ascii(state)),
+                    3 => value.push(MULTIBYTE_CHARS[(state.rand_mut().next() as usize) % MULTIBYTE_CHARS.len()]),
+                    _ => {
+                        let len = (state.rand_mut().next() % 32) as usize;
+                        value = (0..len).map(|_| Self::random_ascii(state)).collect();
+                    }
+                }
+                bcs::to_bytes(&value).ok()
+            }
+            FrameworkStruct::Option(inner) => {
+                let is_some = bytes.first() == Some(&1);
+                if is_some && state.rand_mut().next() & 1 == 0 {
+                    // option::none()
+                    Some(vec![0])
+                } else {
+                    let mut value = if is_some {
+                        bytes[1..].to_vec()
+                    } else {
+                        AptosFuzzerState::default_arg_bytes(inner).unwrap_or_default()
+                    };
+                    let inner_mutated = Self::mutate_value(inner, &mut value, MAX_NESTED_VECTOR_LEN, state);
+                    if inner_mutated || is_some {
+                        Some([vec![1], value].concat())
+                    } else {
+                        // No value of the inner type to wrap, e.g. no object in the pool
+                        Some(vec![0])
+                    }
+                }
+            }
+            FrameworkStruct::FixedPoint32 => {
+                let value = match state.rand_mut().next() % 3 {
+                    0 => 1u64 << 32,
+                    1 => state.rand_mut().next() & u32::MAX as u64,
+                    _ => state.rand_mut().next(),
+                };
+                bcs::to_bytes(&value).ok()
+            }
+            FrameworkStruct::FixedPoint64 => {
+                let value = match state.rand_mut().next() % 3 {
+                    0 => 1u128 << 64,
+                    1 => state.rand_mut().next() as u128,
+                    _ => ((state.rand_mut().next() as u128) << 64) | state.rand_mut().next() as u128,
+                };
+                bcs::to_bytes(&value).ok()
+            }
+            FrameworkStruct::Object(resource) => state.object_arg_bytes(resource),
+        };
+        match encoded {
+            Some(encoded) => {
+                *bytes = encoded;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn random_ascii(state: &mut AptosFuzzerState) -> char {
+        (b' ' + (state.rand_mut().next() % 95) as u8) as char
+    }
+
+    /// Set one numeric argument of an entry function to an interesting value
+    /// for its declared type.
+    fn inject_interesting_entry_value(entry_func: &mut EntryFunction, state: &mut AptosFuzzerState) -> bool {
+        let Some(value_types) = Self::entry_value_types(entry_func, state) else {
+            return false;
+        };
+        let numeric: Vec<usize> = (0..value_types.len())
+            .filter(|&idx| bit_width(&value_types[idx]).is_some())
+            .collect();
+        if numeric.is_empty() {
+            return false;
+        }
+        let idx = numeric[(state.rand_mut().next() as usize) % numeric.len()];
+        let Some(bytes) = Self::interesting_value(&value_types[idx], state) else {
+            return false;
+        };
+        let (module, function, ty_args, mut args) = entry_func.clone().into_inner();
+        args[idx] = bytes;
+        *entry_func = EntryFunction::new(module, function, ty_args, args);
+        true
+    }
+
+    /// Set one numeric literal of a script sequence to an interesting value
+    /// and recompile it.
+    fn inject_interesting_sequence_value(state: &mut AptosFuzzerState, input: &mut AptosFuzzerInput) -> bool {
+        let Some(mut sequence) = input.script_sequence().cloned() else {
+            return false;
+        };
+        let numeric: Vec<(usize, usize, TypeTag)> = sequence
+            .raw_arguments()
+            .into_iter()
+            .filter(|(_, _, ty)| bit_width(ty).is_some())
+            .map(|(call_idx, arg_idx, ty)| (call_idx, arg_idx, ty.clone()))
+            .collect();
+        if numeric.is_empty() {
+            return false;
+        }
+        let (call_idx, arg_idx, ty) = numeric[(state.rand_mut().next() as usize) % numeric.len()].clone();
+        let Some(bytes) = Self::interesting_value(&ty, state) else {
+            return false;
+        };
+        sequence.set_raw_argument(call_idx, arg_idx, bytes);
+        let Some(script) = Self::compile(state, &sequence) else {
+            return false;
+        };
+        *input.payload_mut() = TransactionPayload::Script(script);
+        input.set_script_sequence(Some(sequence));
+        true
+    }
+
+    /// BCS encoding of a boundary value of `ty`: 0, 1, 2, MAX, MAX-1, MAX/2,
+    /// MAX/2+1, a power of two (or one less), or a module constant ±1.
+    fn interesting_value(ty: &TypeTag, state: &mut AptosFuzzerState) -> Option<Vec<u8>> {
+        let bits = bit_width(ty)?;
+        let max = Wide::max(bits);
+        let choices = if state.numeric_constants().is_empty() { 8 } else { 9 };
+        let value = match state.rand_mut().next() % choices {
+            0 => Wide::low(0),
+            1 => Wide::low(1),
+            2 => Wide::low(2),
+            3 => max,
+            4 => max.wrapping_sub(1),
+            5 => max.half(),
+            6 => max.half().wrapping_add(1),
+            7 => {
+                let power = Wide::pow2((state.rand_mut().next() % bits as u64) as u32);
+                if state.rand_mut().next() & 1 == 0 {
+                    power
+                } else {
+                    power.wrapping_sub(1)
+                }
+            }
+            _ => {
+                let idx = (state.rand_mut().next() as usize) % state.numeric_constants().len();
+                let constant = Wide::from_le_bytes(&state.numeric_constants()[idx]);
+                match state.rand_mut().next() % 3 {
+                    0 => constant.wrapping_sub(1),
+                    1 => constant,
+                    _ => constant.wrapping_add(1),
+                }
+            }
+        };
+        value.and(max).encode(ty)
+    }
+
+    /// Mutate Script arguments using state's random source. `types` are the
+    /// declared types of the arguments, when known, to keep serialized ones
+    /// decodable.
+    fn mutate_script_args(
+        script: &mut Script,
+        types: &[&TypeTag],
+        state: &mut AptosFuzzerState,
+        max_vector_len: usize,
+    ) -> bool {
         let args = script.args();
         if args.is_empty() {
             return false;
@@ -54,9 +639,9 @@ impl AptosFuzzerMutator {
         let mut new_args = Vec::new();
         let mut mutated = false;
 
-        for arg in args.iter() {
+        for (idx, arg) in args.iter().enumerate() {
             let mut mutated_arg = arg.clone();
-            if Self::mutate_transaction_argument(&mut mutated_arg, state) {
+            if Self::mutate_transaction_argument(&mut mutated_arg, types.get(idx).copied(), state, max_vector_len) {
                 mutated = true;
             }
             new_args.push(mutated_arg);
@@ -71,6 +656,159 @@ impl AptosFuzzerMutator {
         mutated
     }
 
+    /// Mutate the BCS encoding of a `ty` value so that it still decodes.
+    /// Returns false for types that can't be passed as arguments.
+    fn mutate_value(ty: &TypeTag, bytes: &mut Vec<u8>, max_len: usize, state: &mut AptosFuzzerState) -> bool {
+        match ty {
+            TypeTag::Address => {
+                *bytes = Self::mutate_address(state).to_vec();
+                true
+            }
+            TypeTag::Vector(elem) => Self::mutate_vector(elem, bytes, max_len, state),
+            TypeTag::Struct(tag) => match FrameworkStruct::classify(tag) {
+                Some(framework) => Self::mutate_framework_value(&framework, bytes, state),
+                None => Self::mutate_struct(tag, bytes, max_len, state),
+            },
+            _ => match Self::random_element(ty, state) {
+                Some(element) => {
+                    *bytes = element;
+                    true
+                }
+                None => false,
+            },
+        }
+    }
+
+    /// BCS encoding of a random `ty` value, built from the structure of the
+    /// type so that it always decodes: a length and that many elements for
+    /// vectors, valid fields for framework structs and, field by field, for
+    /// the structs of the loaded modules. `None` for types that can't be
+    /// encoded, and for `Object<T>` without objects.
+    fn random_value(ty: &TypeTag, max_len: usize, state: &mut AptosFuzzerState) -> Option<Vec<u8>> {
+        match ty {
+            TypeTag::Vector(elem) => {
+                let len = (state.rand_mut().next() as usize) % (max_len.min(MAX_NESTED_VECTOR_LEN) + 1);
+                let elements = (0..len)
+                    .map(|_| Self::random_element(elem, state))
+                    .collect::<Option<Vec<_>>>()?;
+                Some(encode_vector(&elements))
+            }
+            TypeTag::Struct(tag) => {
+                let Some(framework) = FrameworkStruct::classify(tag) else {
+                    let layout = state.struct_layouts().layout(tag)?;
+                    let mut bytes = Vec::new();
+                    for (_, field) in &layout.fields {
+                        bytes.extend(Self::random_value(field, max_len, state)?);
+                    }
+                    return Some(bytes);
+                };
+                let mut bytes = AptosFuzzerState::default_arg_bytes(ty).unwrap_or_default();
+                Self::mutate_framework_value(&framework, &mut bytes, state).then_some(bytes)
+            }
+            _ if element_width(ty).is_some() => Self::random_element(ty, state),
+            _ => None,
+        }
+    }
+
+    /// Mutate one field of a struct declared by the loaded modules, laid out
+    /// by the state's registry. A value that doesn't decode is replaced by a
+    /// random one.
+    fn mutate_struct(tag: &StructTag, bytes: &mut Vec<u8>, max_len: usize, state: &mut AptosFuzzerState) -> bool {
+        let Some(layout) = state.struct_layouts().layout(tag) else {
+            return false;
+        };
+        let Some(mut fields) = state.struct_layouts().split_fields(tag, bytes) else {
+            let ty = TypeTag::Struct(Box::new(tag.clone()));
+            return match Self::random_value(&ty, max_len, state) {
+                Some(value) => {
+                    *bytes = value;
+                    true
+                }
+                None => false,
+            };
+        };
+        if fields.is_empty() {
+            return false;
+        }
+        let idx = (state.rand_mut().next() as usize) % fields.len();
+        if !Self::mutate_value(&layout.fields[idx].1, &mut fields[idx], max_len, state) {
+            return false;
+        }
+        *bytes = fields.concat();
+        true
+    }
+
+    /// Structured mutation of a BCS-encoded vector of `elem` values, nested
+    /// ones included. An argument that doesn't decode starts over from empty.
+    fn mutate_vector(elem: &TypeTag, bytes: &mut Vec<u8>, max_len: usize, state: &mut AptosFuzzerState) -> bool {
+        let mut elements = decode_elements(elem, bytes).unwrap_or_default();
+        Self::mutate_elements(elem, &mut elements, max_len, state);
+        *bytes = encode_vector(&elements);
+        true
+    }
+
+    /// Push or pop an element, duplicate one, mutate one in place, or resize
+    /// towards `max_len` to stress loops over the vector. Vectors nested in
+    /// `elements` are mutated with the same moves, up to
+    /// `MAX_NESTED_VECTOR_LEN` long.
+    fn mutate_elements(elem: &TypeTag, elements: &mut Vec<Vec<u8>>, max_len: usize, state: &mut AptosFuzzerState) {
+        elements.truncate(max_len);
+        let len = elements.len();
+        let choice = state.rand_mut().next() % 5;
+        match choice {
+            4 if max_len > 0 => {
+                let target = if state.rand_mut().next() & 1 == 0 {
+                    max_len
+                } else {
+                    1 + (state.rand_mut().next() as usize) % max_len
+                };
+                elements.truncate(target);
+                while elements.len() < target {
+                    let Some(element) = Self::random_element(elem, state) else {
+                        break;
+                    };
+                    elements.push(element);
+                }
+            }
+            _ if len == 0 => {
+                if max_len > 0 {
+                    elements.extend(Self::random_element(elem, state));
+                }
+            }
+            0 if len < max_len => {
+                elements.extend(Self::random_element(elem, state));
+            }
+            0 | 1 => {
+                elements.pop();
+            }
+            2 if len < max_len => {
+                let idx = (state.rand_mut().next() as usize) % len;
+                elements.insert(idx, elements[idx].clone());
+            }
+            _ => {
+                let idx = (state.rand_mut().next() as usize) % len;
+                if element_width(elem).is_some() {
+                    elements[idx] = Self::random_element(elem, state).unwrap_or_default();
+                } else {
+                    Self::mutate_value(elem, &mut elements[idx], MAX_NESTED_VECTOR_LEN, state);
+                }
+            }
+        }
+    }
+
+    /// BCS encoding of a random `elem`: random bits for fixed-width types, a
+    /// structured value for the others, see `random_value`.
+    fn random_element(elem: &TypeTag, state: &mut AptosFuzzerState) -> Option<Vec<u8>> {
+        match elem {
+            TypeTag::Bool => Some(vec![(state.rand_mut().next() & 1) as u8]),
+            TypeTag::Address => Some(Self::mutate_address(state).to_vec()),
+            _ => match element_width(elem) {
+                Some(width) => Some((0..width).map(|_| (state.rand_mut().next() & 0xFF) as u8).collect()),
+                None => Self::random_value(elem, MAX_NESTED_VECTOR_LEN, state),
+            },
+        }
+    }
+
     /// Mutate a byte vector using state's random source (pure random bytes)
     fn mutate_byte_vector(bytes: &mut Vec<u8>, state: &mut AptosFuzzerState) -> bool {
         let len = if bytes.is_empty() {
@@ -87,8 +825,17 @@ impl AptosFuzzerMutator {
         true
     }
 
-    /// Mutate a TransactionArgument using state's random source (pure random)
-    fn mutate_transaction_argument(arg: &mut TransactionArgument, state: &mut AptosFuzzerState) -> bool {
+    /// Mutate a TransactionArgument using state's random source: random
+    /// values, structured ones for serialized arguments of known type `ty`
+    fn mutate_transaction_argument(
+        arg: &mut TransactionArgument,
+        ty: Option<&TypeTag>,
+        state: &mut AptosFuzzerState,
+        max_vector_len: usize,
+    ) -> bool {
+        if let (TransactionArgument::Serialized(bytes), Some(ty)) = (&mut *arg, ty) {
+            return Self::mutate_value(ty, bytes, max_vector_len, state);
+        }
         match arg {
             TransactionArgument::U8(val) => {
                 *val = (state.rand_mut().next() & 0xFF) as u8;
@@ -133,25 +880,18 @@ impl AptosFuzzerMutator {
                 *val = (state.rand_mut().next() & 1) == 0;
                 true
             }
-            TransactionArgument::Address(_addr) => {
-                let mut addr_bytes = [0u8; 32];
-                for byte in addr_bytes.iter_mut() {
-                    *byte = (state.rand_mut().next() % 256) as u8;
-                }
-                *_addr = aptos_move_core_types::account_address::AccountAddress::try_from(addr_bytes.to_vec())
-                    .unwrap_or(*_addr);
+            TransactionArgument::Address(addr) => {
+                *addr = Self::mutate_address(state);
                 true
             }
             TransactionArgument::U8Vector(vec) => {
-                let len = (state.rand_mut().next() % 64) as usize;
-                vec.clear();
-                for _ in 0..len {
-                    vec.push((state.rand_mut().next() & 0xFF) as u8);
-                }
+                let mut elements: Vec<Vec<u8>> = vec.iter().map(|byte| vec![*byte]).collect();
+                Self::mutate_elements(&TypeTag::U8, &mut elements, max_vector_len, state);
+                *vec = elements.concat();
                 true
             }
             TransactionArgument::Serialized(bytes) => {
-                let len = (state.rand_mut().next() % 64) as usize;
+                let len = (state.rand_mut().next() % (max_vector_len as u64 + 1)) as usize;
                 bytes.clear();
                 bytes.resize(len, 0);
                 for b in bytes.iter_mut() {
@@ -162,10 +902,531 @@ impl AptosFuzzerMutator {
         }
     }
 
-    fn mutate_sequence(state: &mut AptosFuzzerState, input: &mut AptosFuzzerInput) -> bool {
+    /// Move the block context by a structured jump: small and large time
+    /// steps, epoch boundaries, or a different chain id.
+    fn mutate_block_context(state: &mut AptosFuzzerState, input: &mut AptosFuzzerInput) -> bool {
+        let mut ctx: BlockContext = input.block_context().copied().unwrap_or_default();
+        let timestamp = ctx.timestamp_us.unwrap_or(DEFAULT_TIMESTAMP_US);
+        match state.rand_mut().next() % 9 {
+            0 => ctx.timestamp_us = Some(timestamp.saturating_add(SECOND_US)),
+            1 => ctx.timestamp_us = Some(timestamp.saturating_sub(SECOND_US)),
+            2 => ctx.timestamp_us = Some(timestamp.saturating_add(DAY_US)),
+            3 => ctx.timestamp_us = Some(timestamp.saturating_sub(DAY_US)),
+            4 => {
+                // Land exactly on the next epoch boundary or just before it
+                let boundary = (timestamp / EPOCH_INTERVAL_US + 1).saturating_mul(EPOCH_INTERVAL_US);
+                let offset = state.rand_mut().next() % 2;
+                ctx.timestamp_us = Some(boundary - offset);
+            }
+            5 => ctx.epoch = Some(ctx.epoch.unwrap_or(0).saturating_add(1)),
+            6 => ctx.epoch = Some(ctx.epoch.unwrap_or(1).saturating_sub(1)),
+            7 => {
+                let idx = (state.rand_mut().next() % CHAIN_IDS.len() as u64) as usize;
+                ctx.chain_id = Some(CHAIN_IDS[idx]);
+            }
+            _ => {
+                // Extremes, for overflow in time arithmetic
+                ctx.timestamp_us = Some(if state.rand_mut().next() & 1 == 0 { 0 } else { u64::MAX });
+            }
+        }
+        let changed = input.block_context() != Some(&ctx);
+        input.set_block_context(Some(ctx));
+        changed
+    }
+
+    /// Send the transaction from another account, so that access checks
+    /// run against senders other than the default. Only payloads taking a
+    /// signer are affected.
+    fn mutate_sender(state: &mut AptosFuzzerState, input: &mut AptosFuzzerInput) -> bool {
+        let takes_signer = match (input.payload(), input.script_sequence()) {
+            (TransactionPayload::EntryFunction(entry), _) => state
+                .function_instance(entry.module(), entry.function(), entry.ty_args())
+                .is_some_and(|function| function.signer_count() > 0),
+            (_, Some(sequence)) => sequence.signer_count() > 0,
+            _ => false,
+        };
+        if !takes_signer {
+            return false;
+        }
+        let sender = Self::mutate_address(state);
+        let changed = input.assigned_sender() != Some(sender);
+        input.set_sender(sender);
+        changed
+    }
+
+    /// Call a generic function of the input with the type arguments the
+    /// instantiation schedule picks. Arguments whose type changes are
+    /// generated anew; in a sequence, the whole call is rebuilt.
+    fn mutate_type_args(state: &mut AptosFuzzerState, input: &mut AptosFuzzerInput) -> bool {
+        let generic_idx = |state: &AptosFuzzerState, module_id: &ModuleId, name: &Identifier| {
+            state
+                .generic_functions()
+                .iter()
+                .position(|function| function.module_id() == module_id && function.name() == name)
+        };
+        match input.payload() {
+            TransactionPayload::EntryFunction(entry) => {
+                let entry = entry.clone();
+                let Some(idx) = generic_idx(state, entry.module(), entry.function()) else {
+                    return false;
+                };
+                let Some(function) = state.schedule_instantiation(idx) else {
+                    return false;
+                };
+                if function.ty_args() == entry.ty_args() {
+                    return false;
+                }
+                let old_types: Vec<TypeTag> = Self::entry_value_types(&entry, state).unwrap_or_default();
+                let (module, name, _, old_args) = entry.into_inner();
+                let mut args = Vec::new();
+                for (arg_idx, ty) in function.value_types().into_iter().enumerate() {
+                    let bytes = match old_args.get(arg_idx) {
+                        Some(bytes) if old_types.get(arg_idx) == Some(ty) => bytes.clone(),
+                        _ => match Self::random_value(ty, MAX_NESTED_VECTOR_LEN, state) {
+                            Some(bytes) => bytes,
+                            None => return false,
+                        },
+                    };
+                    args.push(bytes);
+                }
+                *input.payload_mut() = TransactionPayload::EntryFunction(EntryFunction::new(
+                    module,
+                    name,
+                    function.ty_args().to_vec(),
+                    args,
+                ));
+                true
+            }
+            TransactionPayload::Script(_) => {
+                let Some(sequence) = input.script_sequence().cloned() else {
+                    return false;
+                };
+                let generic_calls: Vec<(usize, usize)> = sequence
+                    .calls()
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, call)| !call.ty_args().is_empty())
+                    .filter_map(|(call_idx, call)| {
+                        Some((call_idx, generic_idx(state, call.module(), call.function())?))
+                    })
+                    .collect();
+                if generic_calls.is_empty() {
+                    return false;
+                }
+                let (call_idx, idx) = generic_calls[(state.rand_mut().next() as usize) % generic_calls.len()];
+                let Some(function) = state.schedule_instantiation(idx) else {
+                    return false;
+                };
+                if function.ty_args() == sequence.calls()[call_idx].ty_args() {
+                    return false;
+                }
+                let mut prefix = ScriptSequence::new();
+                for call in &sequence.calls()[..call_idx] {
+                    prefix.push_call(call.clone());
+                }
+                let available_values = Self::collect_available_values(&prefix, state);
+                let Some(call) =
+                    Self::build_sequence_call(&function, &available_values, &prefix.moved_results(), state)
+                else {
+                    return false;
+                };
+                let mut new_sequence = prefix;
+                new_sequence.push_call(call);
+                for call in &sequence.calls()[call_idx + 1..] {
+                    new_sequence.push_call(call.clone());
+                }
+                // Later calls may take results whose types just changed
+                if !Self::repair_sequence(state, &mut new_sequence) {
+                    return false;
+                }
+                let Some(script) = Self::compile(state, &new_sequence) else {
+                    return false;
+                };
+                *input.payload_mut() = TransactionPayload::Script(script);
+                input.set_script_sequence(Some(new_sequence));
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Generic functions the input calls, with their type arguments.
+    fn generic_calls(input: &AptosFuzzerInput) -> Vec<(ModuleId, Identifier, Vec<TypeTag>)> {
+        match (input.payload(), input.script_sequence()) {
+            (TransactionPayload::EntryFunction(entry), _) if !entry.ty_args().is_empty() => vec![(
+                entry.module().clone(),
+                entry.function().to_owned(),
+                entry.ty_args().to_vec(),
+            )],
+            (TransactionPayload::Script(_), Some(sequence)) => sequence
+                .calls()
+                .iter()
+                .filter(|call| !call.ty_args().is_empty())
+                .map(|call| (call.module().clone(), call.function().clone(), call.ty_args().to_vec()))
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    fn mutate_sequence(
+        state: &mut AptosFuzzerState,
+        input: &mut AptosFuzzerInput,
+        max_vector_len: usize,
+        max_len: usize,
+    ) -> Option<MutationKind> {
         let base_sequence = input.script_sequence().cloned().unwrap_or_else(ScriptSequence::new);
-        let available_values = Self::collect_available_values(&base_sequence, state);
-        let function_count = state.public_functions().len();
+        let len = base_sequence.len();
+        let kind = match Self::pick_sequence_kind(state) {
+            // A full sequence may only shrink or be reordered
+            kind if len >= max_len && kind != MutationKind::SequenceSwap => MutationKind::SequenceDelete,
+            kind => kind,
+        };
+        // Calls to keep, as (source, index): source 0 is this sequence, 1 the
+        // splice donor
+        let mut donor = ScriptSequence::new();
+        let order: Option<(MutationKind, Vec<(usize, usize)>)> = match kind {
+            MutationKind::SequenceDelete if len >= 1 => {
+                // Delete a call
+                let victim = (state.rand_mut().next() as usize) % len;
+                let order = (0..len).filter(|&idx| idx != victim).map(|idx| (0, idx)).collect();
+                Some((MutationKind::SequenceDelete, order))
+            }
+            MutationKind::SequenceSwap if len >= 2 => {
+                // Swap two calls
+                let a = (state.rand_mut().next() as usize) % len;
+                let b = (state.rand_mut().next() as usize) % len;
+                let mut order: Vec<(usize, usize)> = (0..len).map(|idx| (0, idx)).collect();
+                order.swap(a, b);
+                (a != b).then_some((MutationKind::SequenceSwap, order))
+            }
+            MutationKind::SequenceDuplicate if len >= 1 => {
+                // Duplicate a call right after itself
+                let original = (state.rand_mut().next() as usize) % len;
+                let mut order: Vec<(usize, usize)> = (0..len).map(|idx| (0, idx)).collect();
+                order.insert(original + 1, (0, original));
+                Some((MutationKind::SequenceDuplicate, order))
+            }
+            MutationKind::SequenceSplice => Self::random_corpus_input(state)
+                .and_then(|other| other.script_sequence().cloned())
+                .filter(|other| !other.is_empty())
+                .map(|other| {
+                    donor = other;
+                    (
+                        MutationKind::SequenceSplice,
+                        Self::splice_order(state, len, donor.len()),
+                    )
+                }),
+            _ => None,
+        };
+
+        if let Some((kind, order)) = order.filter(|(_, order)| order.len() <= max_len) {
+            let rearranged = ScriptSequence::rearrange(&[&base_sequence, &donor], &order, |producer, return_idx| {
+                Self::default_for_result(state, producer, return_idx)
+            });
+            if let Some(mut sequence) = rearranged {
+                if let Some(script) = Self::repair_sequence(state, &mut sequence)
+                    .then(|| Self::compile(state, &sequence))
+                    .flatten()
+                {
+                    *input.payload_mut() = TransactionPayload::Script(script);
+                    input.set_script_sequence(Some(sequence));
+                    return Some(kind);
+                }
+            }
+        }
+
+        (len < max_len && Self::append_call(state, input, base_sequence, max_vector_len, max_len))
+            .then_some(MutationKind::SequenceAppend)
+    }
+
+    /// Draw a sequence mutation with probability proportional to its learned
+    /// weight.
+    fn pick_sequence_kind(state: &mut AptosFuzzerState) -> MutationKind {
+        let weights = SEQUENCE_KINDS.map(|kind| (state.mutation_weights().weight(kind) * 100.0).round() as u64);
+        let mut pick = state.rand_mut().next() % weights.iter().sum::<u64>();
+        for (kind, weight) in SEQUENCE_KINDS.into_iter().zip(weights) {
+            if pick < weight {
+                return kind;
+            }
+            pick -= weight;
+        }
+        MutationKind::SequenceAppend
+    }
+
+    /// Insert a random run of the donor's calls at a random point of a
+    /// sequence of `len` calls.
+    fn splice_order(state: &mut AptosFuzzerState, len: usize, donor_len: usize) -> Vec<(usize, usize)> {
+        let start = (state.rand_mut().next() as usize) % donor_len;
+        let end = start + 1 + (state.rand_mut().next() as usize) % (donor_len - start);
+        let at = (state.rand_mut().next() as usize) % (len + 1);
+        let mut order: Vec<(usize, usize)> = (0..at).map(|idx| (0, idx)).collect();
+        order.extend((start..end).map(|idx| (1, idx)));
+        order.extend((at..len).map(|idx| (0, idx)));
+        order
+    }
+
+    /// Combine the input with another corpus entry: entry functions take a
+    /// random subset of arguments from an input calling the same function,
+    /// sequences keep a prefix and take the other sequence's suffix.
+    fn crossover(state: &mut AptosFuzzerState, input: &mut AptosFuzzerInput, max_sequence_len: usize) -> bool {
+        match input.payload() {
+            TransactionPayload::EntryFunction(entry_func) => {
+                let entry_func = entry_func.clone();
+                let Some(partner) = Self::crossover_partner(state, |other| match other.payload() {
+                    TransactionPayload::EntryFunction(other) => {
+                        other.module() == entry_func.module() &&
+                            other.function() == entry_func.function() &&
+                            other.args().len() == entry_func.args().len() &&
+                            other.args() != entry_func.args()
+                    }
+                    _ => false,
+                }) else {
+                    return false;
+                };
+                let TransactionPayload::EntryFunction(partner) = partner.payload() else {
+                    return false;
+                };
+                let (module, function, ty_args, mut args) = entry_func.into_inner();
+                for (arg, other) in args.iter_mut().zip(partner.args()) {
+                    if state.rand_mut().next() & 1 == 0 {
+                        arg.clone_from(other);
+                    }
+                }
+                *input.payload_mut() =
+                    TransactionPayload::EntryFunction(EntryFunction::new(module, function, ty_args, args));
+                true
+            }
+            TransactionPayload::Script(_) => {
+                let base_sequence = input.script_sequence().cloned().unwrap_or_default();
+                let Some(partner) = Self::crossover_partner(state, |other| {
+                    other
+                        .script_sequence()
+                        .is_some_and(|sequence| !sequence.is_empty() && Some(sequence) != input.script_sequence())
+                }) else {
+                    return false;
+                };
+                let Some(donor) = partner.script_sequence() else {
+                    return false;
+                };
+                let keep = (state.rand_mut().next() as usize) % (base_sequence.len() + 1);
+                let from = (state.rand_mut().next() as usize) % donor.len();
+                let mut order: Vec<(usize, usize)> = (0..keep).map(|idx| (0, idx)).collect();
+                order.extend((from..donor.len()).map(|idx| (1, idx)));
+                order.truncate(max_sequence_len);
+                let Some(mut sequence) =
+                    ScriptSequence::rearrange(&[&base_sequence, donor], &order, |producer, return_idx| {
+                        Self::default_for_result(state, producer, return_idx)
+                    })
+                else {
+                    return false;
+                };
+                if !Self::repair_sequence(state, &mut sequence) {
+                    return false;
+                }
+                let Some(script) = Self::compile(state, &sequence) else {
+                    return false;
+                };
+                *input.payload_mut() = TransactionPayload::Script(script);
+                input.set_script_sequence(Some(sequence));
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// A random corpus entry accepted by `compatible`, if one turns up within
+    /// a few samples.
+    fn crossover_partner(
+        state: &mut AptosFuzzerState,
+        compatible: impl Fn(&AptosFuzzerInput) -> bool,
+    ) -> Option<AptosFuzzerInput> {
+        (0..CROSSOVER_ATTEMPTS)
+            .filter_map(|_| Self::random_corpus_input(state))
+            .find(|other| compatible(other))
+    }
+
+    fn random_corpus_input(state: &mut AptosFuzzerState) -> Option<AptosFuzzerInput> {
+        let count = state.corpus().count();
+        if count == 0 {
+            return None;
+        }
+        let idx = (state.rand_mut().next() as usize) % count;
+        let id = state.corpus().nth(idx);
+        state.corpus().cloned_input_for_id(id).ok()
+    }
+
+    /// Stand-in for a result that is no longer available to a call: the
+    /// default value of the producer's return type.
+    pub(crate) fn default_for_result(
+        state: &AptosFuzzerState,
+        producer: &SequenceCall,
+        return_idx: u16,
+    ) -> Option<SequenceArgument> {
+        let function = state.function_instance(producer.module(), producer.function(), producer.ty_args())?;
+        let ty = function.return_types().get(return_idx as usize)?.clone();
+        let bytes = AptosFuzzerState::default_arg_bytes(&ty)?;
+        Some(SequenceArgument::Raw { bytes, ty })
+    }
+
+    /// Fix the `PreviousResult` arguments of `sequence` that the composer
+    /// would reject once calls were dropped, reordered or spliced in: results
+    /// of no earlier call or of the wrong type, results taken after being
+    /// moved, and operations that don't suit the parameter. A result moved
+    /// and then taken again is copied the first time if its type allows;
+    /// otherwise the later argument falls back to a default value, or for a
+    /// reference to another result to borrow. Returns false when an argument
+    /// is left without one.
+    pub(crate) fn repair_sequence(state: &mut AptosFuzzerState, sequence: &mut ScriptSequence) -> bool {
+        let available_values = Self::collect_available_values(sequence, state);
+        // Where each moved result is moved, as (call, argument)
+        let mut moved: HashMap<(u16, u16), (usize, usize)> = HashMap::new();
+        for call_idx in 0..sequence.len() {
+            let call = &sequence.calls()[call_idx];
+            let Some(parameters) = state
+                .function_instance(call.module(), call.function(), call.ty_args())
+                .map(|function| function.parameters().to_vec())
+            else {
+                continue;
+            };
+            let args = call.args().to_vec();
+            let earlier: Vec<AvailableValue> = available_values
+                .iter()
+                .filter(|value| (value.call_idx as usize) < call_idx)
+                .cloned()
+                .collect();
+            for (arg_idx, param) in parameters.iter().enumerate() {
+                let key = match args.get(arg_idx) {
+                    Some(SequenceArgument::PreviousResult {
+                        call_idx: producer,
+                        return_idx,
+                        ..
+                    }) => Some((*producer, *return_idx)),
+                    _ => None,
+                };
+                let taken = key.and_then(|key| earlier.iter().find(|value| (value.call_idx, value.return_idx) == key));
+                let repaired = match param {
+                    FunctionParameter::Signer => continue,
+                    FunctionParameter::Value(tag) => {
+                        // Literals need no repair
+                        let Some(key) = key else {
+                            continue;
+                        };
+                        let usable = taken.is_some_and(|value| value.ty == *tag) &&
+                            Self::take_again(state, sequence, &mut moved, key, tag);
+                        if usable {
+                            let operation = if state.struct_layouts().is_copyable(tag) {
+                                ResultOperation::Copy
+                            } else {
+                                moved.insert(key, (call_idx, arg_idx));
+                                ResultOperation::Move
+                            };
+                            SequenceArgument::PreviousResult {
+                                call_idx: key.0,
+                                return_idx: key.1,
+                                operation,
+                            }
+                        } else {
+                            let Some(bytes) = AptosFuzzerState::default_arg_bytes(tag) else {
+                                return false;
+                            };
+                            SequenceArgument::Raw { bytes, ty: tag.clone() }
+                        }
+                    }
+                    FunctionParameter::Reference { ty, mutable } => {
+                        let usable = match key {
+                            Some(key) => {
+                                taken.is_some_and(|value| value.ty == *ty) &&
+                                    Self::take_again(state, sequence, &mut moved, key, ty)
+                            }
+                            None => false,
+                        };
+                        let (producer, return_idx) = match key.filter(|_| usable) {
+                            Some(key) => key,
+                            None => {
+                                let gone = moved.keys().copied().collect();
+                                let Some(value) = Self::pick_borrowable(&earlier, &gone, ty, state) else {
+                                    return false;
+                                };
+                                (value.call_idx, value.return_idx)
+                            }
+                        };
+                        SequenceArgument::PreviousResult {
+                            call_idx: producer,
+                            return_idx,
+                            operation: Self::borrow_operation(*mutable),
+                        }
+                    }
+                };
+                sequence.set_argument(call_idx, arg_idx, repaired);
+            }
+        }
+        true
+    }
+
+    /// Whether the result `key` can be taken once more: it wasn't moved, or
+    /// the argument moving it can copy it instead, which it is changed to.
+    fn take_again(
+        state: &AptosFuzzerState,
+        sequence: &mut ScriptSequence,
+        moved: &mut HashMap<(u16, u16), (usize, usize)>,
+        key: (u16, u16),
+        ty: &TypeTag,
+    ) -> bool {
+        let Some(&(call_idx, arg_idx)) = moved.get(&key) else {
+            return true;
+        };
+        if !state.struct_layouts().is_copyable(ty) {
+            return false;
+        }
+        moved.remove(&key);
+        sequence.set_argument(
+            call_idx,
+            arg_idx,
+            SequenceArgument::PreviousResult {
+                call_idx: key.0,
+                return_idx: key.1,
+                operation: ResultOperation::Copy,
+            },
+        )
+    }
+
+    /// A random result of type `ty` not in `moved`, to pass by reference.
+    fn pick_borrowable<'a>(
+        available_values: &'a [AvailableValue],
+        moved: &HashSet<(u16, u16)>,
+        ty: &TypeTag,
+        state: &mut AptosFuzzerState,
+    ) -> Option<&'a AvailableValue> {
+        let matches: Vec<&AvailableValue> = available_values
+            .iter()
+            .filter(|value| value.ty == *ty && !moved.contains(&(value.call_idx, value.return_idx)))
+            .collect();
+        if matches.is_empty() {
+            return None;
+        }
+        Some(matches[(state.rand_mut().next() as usize) % matches.len()])
+    }
+
+    fn borrow_operation(mutable: bool) -> ResultOperation {
+        if mutable {
+            ResultOperation::BorrowMut
+        } else {
+            ResultOperation::Borrow
+        }
+    }
+
+    /// Append a call to a random public function, preceded by a call
+    /// producing what it takes by reference if nothing in the sequence can be
+    /// borrowed for it.
+    fn append_call(
+        state: &mut AptosFuzzerState,
+        input: &mut AptosFuzzerInput,
+        base_sequence: ScriptSequence,
+        max_vector_len: usize,
+        max_len: usize,
+    ) -> bool {
+        let plain_count = state.public_functions().len();
+        let function_count = plain_count + state.generic_functions().len();
         if function_count == 0 {
             return false;
         }
@@ -173,14 +1434,38 @@ impl AptosFuzzerMutator {
         let attempts = cmp::min(8, function_count);
         for _ in 0..attempts {
             let idx = (state.rand_mut().next() as usize) % function_count;
-            let function = state.public_functions()[idx].clone();
-            let Some(call) = Self::build_sequence_call(&function, &available_values, state) else {
+            let function = if idx < plain_count {
+                state.public_functions()[idx].clone()
+            } else {
+                let Some(function) = state.schedule_instantiation(idx - plain_count) else {
+                    continue;
+                };
+                function
+            };
+            let mut sequence = base_sequence.clone();
+            if let Some(ty) = Self::missing_borrow(&function, &sequence, state) {
+                if sequence.len() + 2 > max_len {
+                    continue;
+                }
+                let Some(producer) = Self::producer_call(&ty, &sequence, state) else {
+                    continue;
+                };
+                sequence.push_call(producer);
+            }
+            let available_values = Self::collect_available_values(&sequence, state);
+            let Some(call) = Self::build_sequence_call(&function, &available_values, &sequence.moved_results(), state)
+            else {
                 continue;
             };
-            let mut new_sequence = base_sequence.clone();
-            new_sequence.push_call(call);
-            if let Some(mut script) = compile_sequence(&new_sequence, state.aptos_state().module_bytes()) {
-                Self::mutate_script_args(&mut script, state);
+            let assertions = state
+                .return_properties()
+                .iter()
+                .filter_map(|property| property.assertion_for(&sequence, &call))
+                .collect();
+            let mut new_sequence = sequence;
+            new_sequence.push_call(call.with_assertions(assertions));
+            if let Some(mut script) = Self::compile(state, &new_sequence) {
+                Self::mutate_script_args(&mut script, &new_sequence.script_arg_types(), state, max_vector_len);
                 *input.payload_mut() = TransactionPayload::Script(script);
                 input.set_script_sequence(Some(new_sequence));
                 return true;
@@ -190,10 +1475,48 @@ impl AptosFuzzerMutator {
         false
     }
 
+    /// The type of the first reference parameter of `function` that no
+    /// result of `sequence` can be borrowed for.
+    fn missing_borrow(
+        function: &PublicFunctionTarget,
+        sequence: &ScriptSequence,
+        state: &AptosFuzzerState,
+    ) -> Option<TypeTag> {
+        let available_values = Self::collect_available_values(sequence, state);
+        let moved = sequence.moved_results();
+        function.parameters().iter().find_map(|param| match param {
+            FunctionParameter::Reference { ty, .. }
+                if !available_values
+                    .iter()
+                    .any(|value| value.ty == *ty && !moved.contains(&(value.call_idx, value.return_idx))) =>
+            {
+                Some(ty.clone())
+            }
+            _ => None,
+        })
+    }
+
+    /// A call to a random public function returning a `ty`, with arguments
+    /// from `sequence` or defaults.
+    fn producer_call(ty: &TypeTag, sequence: &ScriptSequence, state: &mut AptosFuzzerState) -> Option<SequenceCall> {
+        let producers: Vec<PublicFunctionTarget> = state
+            .public_functions()
+            .iter()
+            .filter(|function| function.return_types().contains(ty))
+            .cloned()
+            .collect();
+        if producers.is_empty() {
+            return None;
+        }
+        let producer = &producers[(state.rand_mut().next() as usize) % producers.len()];
+        let available_values = Self::collect_available_values(sequence, state);
+        Self::build_sequence_call(producer, &available_values, &sequence.moved_results(), state)
+    }
+
     fn collect_available_values(sequence: &ScriptSequence, state: &AptosFuzzerState) -> Vec<AvailableValue> {
         let mut values = Vec::new();
         for (call_idx, call) in sequence.calls().iter().enumerate() {
-            if let Some(function) = state.public_function(call.module(), call.function()) {
+            if let Some(function) = state.function_instance(call.module(), call.function(), call.ty_args()) {
                 for (return_idx, ty) in function.return_types().iter().enumerate() {
                     values.push(AvailableValue {
                         call_idx: call_idx as u16,
@@ -206,40 +1529,64 @@ impl AptosFuzzerMutator {
         values
     }
 
+    /// A call to `function` with arguments from `available_values` or
+    /// defaults. Results in `moved` are gone; the call copies or borrows a
+    /// result where it can and only moves what can't be copied. `None` when
+    /// a reference parameter has nothing to borrow. Signer parameters all
+    /// take the sender, the only signer of a transaction.
     fn build_sequence_call(
         function: &PublicFunctionTarget,
         available_values: &[AvailableValue],
+        moved: &HashSet<(u16, u16)>,
         state: &mut AptosFuzzerState,
     ) -> Option<SequenceCall> {
+        let mut moved = moved.clone();
         let mut args = Vec::new();
         for param in function.parameters() {
             match param {
-                FunctionParameter::Signer => {
-                    return None;
-                }
+                FunctionParameter::Signer => args.push(SequenceArgument::Signer(0)),
                 FunctionParameter::Value(tag) => {
-                    let matches: Vec<&AvailableValue> =
-                        available_values.iter().filter(|value| value.ty == *tag).collect();
+                    let matches: Vec<&AvailableValue> = available_values
+                        .iter()
+                        .filter(|value| value.ty == *tag && !moved.contains(&(value.call_idx, value.return_idx)))
+                        .collect();
                     let use_previous = !matches.is_empty() && (state.rand_mut().next() & 1) == 0;
                     if use_previous {
                         let index = (state.rand_mut().next() as usize) % matches.len();
                         let value = matches[index];
+                        // Copy when possible so that later calls can take the
+                        // value as well
+                        let operation = if state.struct_layouts().is_copyable(tag) {
+                            ResultOperation::Copy
+                        } else {
+                            moved.insert((value.call_idx, value.return_idx));
+                            ResultOperation::Move
+                        };
                         args.push(SequenceArgument::PreviousResult {
                             call_idx: value.call_idx,
                             return_idx: value.return_idx,
+                            operation,
                         });
                     } else {
                         let bytes = AptosFuzzerState::default_arg_bytes(tag)?;
                         args.push(SequenceArgument::Raw { bytes, ty: tag.clone() });
                     }
                 }
+                FunctionParameter::Reference { ty, mutable } => {
+                    let value = Self::pick_borrowable(available_values, &moved, ty, state)?;
+                    args.push(SequenceArgument::PreviousResult {
+                        call_idx: value.call_idx,
+                        return_idx: value.return_idx,
+                        operation: Self::borrow_operation(*mutable),
+                    });
+                }
             }
         }
 
         Some(SequenceCall::new(
             function.module_id().clone(),
             function.name().clone(),
-            Vec::new(),
+            function.ty_args().to_vec(),
             args,
         ))
     }
@@ -251,15 +1598,24 @@ impl Mutator<AptosFuzzerInput, AptosFuzzerState> for AptosFuzzerMutator {
         state: &mut AptosFuzzerState,
         input: &mut AptosFuzzerInput,
     ) -> Result<MutationResult, libafl::Error> {
-        let mutated = match input.payload() {
-            TransactionPayload::Script(_) => Self::mutate_sequence(state, input),
-            _ => match input.payload_mut() {
-                TransactionPayload::EntryFunction(entry_func) => Self::mutate_entry_function_args(entry_func, state),
-                _ => false,
-            },
-        };
+        self.applied.clear();
+        if self.skip_deep_sequence(state, input) {
+            return Ok(MutationResult::Skipped);
+        }
+        self.solutions_before = state.solutions().count();
+        for _ in 0..self.stack_size(state) {
+            if let Some(kind) = self.mutate_once(state, input) {
+                state.record_mutation(kind);
+                if !self.applied.contains(&kind) {
+                    self.applied.push(kind);
+                }
+            }
+        }
 
-        if mutated {
+        if !self.applied.is_empty() {
+            state.constrain_input(input);
+            state.assign_sender(input);
+            self.instantiated = Self::generic_calls(input);
             Ok(MutationResult::Mutated)
         } else {
             Ok(MutationResult::Skipped)
@@ -268,9 +1624,27 @@ impl Mutator<AptosFuzzerInput, AptosFuzzerState> for AptosFuzzerMutator {
 
     fn post_exec(
         &mut self,
-        _state: &mut AptosFuzzerState,
-        _new_corpus_id: Option<libafl::corpus::CorpusId>,
+        state: &mut AptosFuzzerState,
+        new_corpus_id: Option<libafl::corpus::CorpusId>,
     ) -> Result<(), libafl::Error> {
+        if let Some(id) = new_corpus_id {
+            let executions = *state.executions();
+            if let Ok(Some(parent)) = state.current_corpus_id() {
+                self.finds.insert(parent, executions);
+            }
+            self.finds.insert(id, executions);
+            state.queue_subsumed_prefixes(id);
+        }
+        if !self.applied.is_empty() {
+            let found = new_corpus_id.is_some() || state.solutions().count() > self.solutions_before;
+            state.mutation_weights_mut().record(&self.applied, found);
+            for (module_id, function, ty_args) in self.instantiated.drain(..) {
+                state
+                    .instantiations_mut()
+                    .record(&module_id, &function, &ty_args, found);
+            }
+            self.applied.clear();
+        }
         Ok(())
     }
 }
@@ -288,3 +1662,388 @@ struct AvailableValue {
     return_idx: u16,
     ty: TypeTag,
 }
+
+/// BCS width in bytes of a vector element type, for fixed-width types only.
+fn element_width(ty: &TypeTag) -> Option<usize> {
+    match ty {
+        TypeTag::Bool => Some(1),
+        TypeTag::Address => Some(AccountAddress::LENGTH),
+        _ => bit_width(ty).map(|bits| bits as usize / 8),
+    }
+}
+
+/// Split a BCS vector of `width`-byte elements into its elements.
+fn decode_vector(bytes: &[u8], width: usize) -> Option<Vec<Vec<u8>>> {
+    let (len, offset) = decode_uleb128(bytes)?;
+    let body = &bytes[offset..];
+    if body.len() != len.checked_mul(width)? {
+        return None;
+    }
+    Some(body.chunks(width).map(<[u8]>::to_vec).collect())
+}
+
+/// Split a BCS vector of `elem` values, of any type, into its elements.
+fn decode_elements(elem: &TypeTag, bytes: &[u8]) -> Option<Vec<Vec<u8>>> {
+    if let Some(width) = element_width(elem) {
+        return decode_vector(bytes, width);
+    }
+    let (len, mut offset) = decode_uleb128(bytes)?;
+    let mut elements = Vec::new();
+    for _ in 0..len {
+        let size = value_size(elem, &bytes[offset..])?;
+        elements.push(bytes[offset..offset + size].to_vec());
+        offset += size;
+    }
+    (offset == bytes.len()).then_some(elements)
+}
+
+/// Length of the BCS-encoded `ty` value at the start of `bytes`, if one is
+/// there.
+fn value_size(ty: &TypeTag, bytes: &[u8]) -> Option<usize> {
+    let size = match ty {
+        TypeTag::Vector(elem) => {
+            let (len, mut offset) = decode_uleb128(bytes)?;
+            match element_width(elem) {
+                Some(width) => offset += len.checked_mul(width)?,
+                None => {
+                    for _ in 0..len {
+                        offset += value_size(elem, bytes.get(offset..)?)?;
+                    }
+                }
+            }
+            offset
+        }
+        TypeTag::Struct(tag) => match FrameworkStruct::classify(tag)? {
+            FrameworkStruct::String => {
+                let (len, offset) = decode_uleb128(bytes)?;
+                std::str::from_utf8(bytes.get(offset..offset.checked_add(len)?)?).ok()?;
+                offset + len
+            }
+            FrameworkStruct::Option(inner) => match decode_uleb128(bytes)? {
+                (0, offset) => offset,
+                (1, offset) => offset + value_size(&inner, &bytes[offset..])?,
+                _ => return None,
+            },
+            FrameworkStruct::FixedPoint32 => 8,
+            FrameworkStruct::FixedPoint64 => 16,
+            FrameworkStruct::Object(_) => AccountAddress::LENGTH,
+        },
+        TypeTag::Bool => match bytes.first()? {
+            0 | 1 => 1,
+            _ => return None,
+        },
+        _ => element_width(ty)?,
+    };
+    (size <= bytes.len()).then_some(size)
+}
+
+/// A ULEB128 vector length and the number of bytes it takes.
+pub(crate) fn decode_uleb128(bytes: &[u8]) -> Option<(usize, usize)> {
+    let mut len = 0usize;
+    let mut offset = 0;
+    loop {
+        let byte = *bytes.get(offset)?;
+        len |= ((byte & 0x7F) as usize).checked_shl(7 * offset as u32)?;
+        offset += 1;
+        if byte & 0x80 == 0 {
+            return Some((len, offset));
+        }
+    }
+}
+
+/// BCS encoding of a vector from its encoded elements.
+pub(crate) fn encode_vector(elements: &[Vec<u8>]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    let mut len = elements.len();
+    loop {
+        let byte = (len & 0x7F) as u8;
+        len >>= 7;
+        if len == 0 {
+            bytes.push(byte);
+            break;
+        }
+        bytes.push(byte | 0x80);
+    }
+    for element in elements {
+        bytes.extend_from_slice(element);
+    }
+    bytes
+}
+
+/// Width in bits of an unsigned integer type.
+pub(crate) fn bit_width(ty: &TypeTag) -> Option<u32> {
+    match ty {
+        TypeTag::U8 => Some(8),
+        TypeTag::U16 => Some(16),
+        TypeTag::U32 => Some(32),
+        TypeTag::U64 => Some(64),
+        TypeTag::U128 => Some(128),
+        TypeTag::U256 => Some(256),
+        _ => None,
+    }
+}
+
+/// Unsigned integer of up to 256 bits, as high and low halves.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Wide {
+    hi: u128,
+    lo: u128,
+}
+
+impl Wide {
+    fn low(value: u128) -> Self {
+        Self { hi: 0, lo: value }
+    }
+
+    fn from_le_bytes(bytes: &[u8; 32]) -> Self {
+        Self {
+            lo: u128::from_le_bytes(bytes[..16].try_into().expect("16 bytes")),
+            hi: u128::from_le_bytes(bytes[16..].try_into().expect("16 bytes")),
+        }
+    }
+
+    fn max(bits: u32) -> Self {
+        match bits {
+            0..128 => Self::low((1u128 << bits) - 1),
+            128 => Self::low(u128::MAX),
+            _ => Self {
+                hi: u128::MAX >> (256 - bits.min(256)),
+                lo: u128::MAX,
+            },
+        }
+    }
+
+    fn pow2(exp: u32) -> Self {
+        if exp < 128 {
+            Self::low(1u128 << exp)
+        } else {
+            Self {
+                hi: 1u128 << (exp - 128),
+                lo: 0,
+            }
+        }
+    }
+
+    fn half(self) -> Self {
+        Self {
+            hi: self.hi >> 1,
+            lo: (self.lo >> 1) | (self.hi << 127),
+        }
+    }
+
+    fn wrapping_add(self, value: u128) -> Self {
+        let (lo, carry) = self.lo.overflowing_add(value);
+        Self {
+            hi: self.hi.wrapping_add(carry as u128),
+            lo,
+        }
+    }
+
+    fn wrapping_sub(self, value: u128) -> Self {
+        let (lo, borrow) = self.lo.overflowing_sub(value);
+        Self {
+            hi: self.hi.wrapping_sub(borrow as u128),
+            lo,
+        }
+    }
+
+    fn and(self, mask: Self) -> Self {
+        Self {
+            hi: self.hi & mask.hi,
+            lo: self.lo & mask.lo,
+        }
+    }
+
+    /// BCS encoding as `ty`; the value must already fit.
+    fn encode(self, ty: &TypeTag) -> Option<Vec<u8>> {
+        match ty {
+            TypeTag::U8 => bcs::to_bytes(&(self.lo as u8)).ok(),
+            TypeTag::U16 => bcs::to_bytes(&(self.lo as u16)).ok(),
+            TypeTag::U32 => bcs::to_bytes(&(self.lo as u32)).ok(),
+            TypeTag::U64 => bcs::to_bytes(&(self.lo as u64)).ok(),
+            TypeTag::U128 => bcs::to_bytes(&self.lo).ok(),
+            TypeTag::U256 => {
+                let mut bytes = [0u8; 32];
+                bytes[..16].copy_from_slice(&self.lo.to_le_bytes());
+                bytes[16..].copy_from_slice(&self.hi.to_le_bytes());
+                bcs::to_bytes(&aptos_move_core_types::u256::U256::from_le_bytes(&bytes)).ok()
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use aptos_move_core_types::account_address::AccountAddress;
+    use aptos_move_core_types::identifier::Identifier;
+    use aptos_move_core_types::language_storage::{ModuleId, StructTag, TypeTag};
+
+    use super::AptosFuzzerMutator;
+    use crate::script_sequence::{ResultOperation, ScriptSequence, SequenceArgument, SequenceCall};
+    use crate::state::{AptosFuzzerState, FunctionParameter, PublicFunctionTarget};
+
+    fn module() -> ModuleId {
+        ModuleId::new(
+            AccountAddress::from_hex_literal("0xcafe").unwrap(),
+            Identifier::new("m").unwrap(),
+        )
+    }
+
+    /// `0xcafe::m::Coin`, which has no `copy` ability
+    fn coin() -> TypeTag {
+        TypeTag::Struct(Box::new(StructTag {
+            address: *module().address(),
+            module: module().name().to_owned(),
+            name: Identifier::new("Coin").unwrap(),
+            type_args: Vec::new(),
+        }))
+    }
+
+    /// A state whose targets are `make() -> Coin`, `amount() -> u64`,
+    /// `burn(Coin)`, `add(u64)`, `set(u8)`, `peek(&Coin)` and `mint(&mut
+    /// Coin)`.
+    fn state() -> AptosFuzzerState {
+        let mut state = AptosFuzzerState::new(PathBuf::new());
+        let functions = [
+            ("make", vec![], vec![coin()]),
+            ("amount", vec![], vec![TypeTag::U64]),
+            ("burn", vec![FunctionParameter::Value(coin())], vec![]),
+            ("add", vec![FunctionParameter::Value(TypeTag::U64)], vec![]),
+            ("set", vec![FunctionParameter::Value(TypeTag::U8)], vec![]),
+            (
+                "peek",
+                vec![FunctionParameter::Reference {
+                    ty: coin(),
+                    mutable: false,
+                }],
+                vec![],
+            ),
+            (
+                "mint",
+                vec![FunctionParameter::Reference {
+                    ty: coin(),
+                    mutable: true,
+                }],
+                vec![],
+            ),
+        ];
+        for (name, parameters, return_types) in functions {
+            state.add_public_function(PublicFunctionTarget::new(
+                module(),
+                Identifier::new(name).unwrap(),
+                Vec::new(),
+                parameters,
+                return_types,
+                false,
+                false,
+            ));
+        }
+        state
+    }
+
+    fn call(function: &str, args: Vec<SequenceArgument>) -> SequenceCall {
+        SequenceCall::new(module(), Identifier::new(function).unwrap(), Vec::new(), args)
+    }
+
+    fn result(call_idx: u16, operation: ResultOperation) -> SequenceArgument {
+        SequenceArgument::PreviousResult {
+            call_idx,
+            return_idx: 0,
+            operation,
+        }
+    }
+
+    fn sequence(calls: Vec<SequenceCall>) -> ScriptSequence {
+        let mut sequence = ScriptSequence::new();
+        for call in calls {
+            sequence.push_call(call);
+        }
+        sequence
+    }
+
+    fn repaired(calls: Vec<SequenceCall>) -> Option<ScriptSequence> {
+        let mut sequence = sequence(calls);
+        AptosFuzzerMutator::repair_sequence(&mut state(), &mut sequence).then_some(sequence)
+    }
+
+    #[test]
+    fn copyable_results_taken_twice_are_copied() {
+        let sequence = repaired(vec![
+            call("amount", vec![]),
+            call("add", vec![result(0, ResultOperation::Move)]),
+            call("add", vec![result(0, ResultOperation::Move)]),
+        ])
+        .unwrap();
+        assert_eq!(sequence.calls()[1].args(), [result(0, ResultOperation::Copy)]);
+        assert_eq!(sequence.calls()[2].args(), [result(0, ResultOperation::Copy)]);
+    }
+
+    #[test]
+    fn moving_a_resource_twice_is_rejected() {
+        assert!(repaired(vec![
+            call("make", vec![]),
+            call("burn", vec![result(0, ResultOperation::Move)]),
+            call("burn", vec![result(0, ResultOperation::Move)]),
+        ])
+        .is_none());
+    }
+
+    #[test]
+    fn results_of_the_wrong_type_fall_back_to_a_default() {
+        let sequence = repaired(vec![
+            call("amount", vec![]),
+            call("set", vec![result(0, ResultOperation::Move)]),
+        ])
+        .unwrap();
+        assert_eq!(
+            sequence.calls()[1].args(),
+            [SequenceArgument::Raw {
+                bytes: vec![0],
+                ty: TypeTag::U8,
+            }]
+        );
+    }
+
+    #[test]
+    fn references_get_a_borrow_operation() {
+        let sequence = repaired(vec![
+            call("make", vec![]),
+            call("peek", vec![result(0, ResultOperation::Move)]),
+            call("mint", vec![result(0, ResultOperation::Borrow)]),
+        ])
+        .unwrap();
+        assert_eq!(sequence.calls()[1].args(), [result(0, ResultOperation::Borrow)]);
+        assert_eq!(sequence.calls()[2].args(), [result(0, ResultOperation::BorrowMut)]);
+    }
+
+    #[test]
+    fn borrows_of_a_moved_result_move_to_another_one() {
+        let sequence = repaired(vec![
+            call("make", vec![]),
+            call("make", vec![]),
+            call("burn", vec![result(0, ResultOperation::Move)]),
+            call("peek", vec![result(0, ResultOperation::Borrow)]),
+        ])
+        .unwrap();
+        assert_eq!(sequence.calls()[3].args(), [result(1, ResultOperation::Borrow)]);
+    }
+
+    #[test]
+    fn results_of_later_calls_are_repaired() {
+        let sequence = repaired(vec![
+            call("add", vec![result(1, ResultOperation::Move)]),
+            call("amount", vec![]),
+        ])
+        .unwrap();
+        assert_eq!(
+            sequence.calls()[0].args(),
+            [SequenceArgument::Raw {
+                bytes: vec![0; 8],
+                ty: TypeTag::U64,
+            }]
+        );
+    }
+}
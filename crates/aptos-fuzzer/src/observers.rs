@@ -1,13 +1,26 @@
 use std::borrow::Cow;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
 
+use aptos_move_core_types::account_address::AccountAddress;
+use aptos_move_core_types::vm_status::AbortLocation;
+use libafl::executors::ExitKind;
+use libafl::observers::map::{HitcountsMapObserver, MapObserver, OwnedMapObserver};
 use libafl::observers::Observer;
-use libafl_bolts::Named;
+use libafl_bolts::{AsIter, AsSlice, AsSliceMut, HasLen, Named};
 use serde::{Deserialize, Serialize};
 
+use crate::concolic::{RuntimeIssue, ShiftLoss, TracedLocation};
+use crate::trace::AbortSite;
+
 #[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct AbortCodeObserver {
     name: Cow<'static, str>,
     last: Option<u64>,
+    /// Where the last abort was raised
+    last_location: Option<AbortLocation>,
+    /// Instruction and call stack of the last abort, when traced
+    last_site: Option<AbortSite>,
 }
 
 impl AbortCodeObserver {
@@ -15,6 +28,8 @@ impl AbortCodeObserver {
         Self {
             name: Cow::Borrowed("AbortCodeObserver"),
             last: None,
+            last_location: None,
+            last_site: None,
         }
     }
 
@@ -25,6 +40,22 @@ impl AbortCodeObserver {
     pub fn set_last(&mut self, v: Option<u64>) {
         self.last = v;
     }
+
+    pub fn last_location(&self) -> Option<&AbortLocation> {
+        self.last_location.as_ref()
+    }
+
+    pub fn set_last_location(&mut self, location: Option<AbortLocation>) {
+        self.last_location = location;
+    }
+
+    pub fn last_site(&self) -> Option<&AbortSite> {
+        self.last_site.as_ref()
+    }
+
+    pub fn set_last_site(&mut self, site: Option<AbortSite>) {
+        self.last_site = site;
+    }
 }
 
 impl Named for AbortCodeObserver {
@@ -36,6 +67,8 @@ impl Named for AbortCodeObserver {
 impl<I, S> Observer<I, S> for AbortCodeObserver {
     fn pre_exec(&mut self, _state: &mut S, _input: &I) -> Result<(), libafl::Error> {
         self.last = None;
+        self.last_location = None;
+        self.last_site = None;
         Ok(())
     }
 }
@@ -44,6 +77,8 @@ impl<I, S> Observer<I, S> for AbortCodeObserver {
 pub struct ShiftOverflowObserver {
     name: Cow<'static, str>,
     cause_loss: bool,
+    /// Where the shifts of the last run lost bits, and how many
+    losses: Vec<ShiftLoss>,
 }
 
 impl ShiftOverflowObserver {
@@ -51,6 +86,7 @@ impl ShiftOverflowObserver {
         Self {
             name: Cow::Borrowed("ShiftOverflowObserver"),
             cause_loss: false,
+            losses: Vec::new(),
         }
     }
 
@@ -61,6 +97,14 @@ impl ShiftOverflowObserver {
     pub fn set_cause_loss(&mut self, v: bool) {
         self.cause_loss = v;
     }
+
+    pub fn losses(&self) -> &[ShiftLoss] {
+        &self.losses
+    }
+
+    pub fn set_losses(&mut self, losses: Vec<ShiftLoss>) {
+        self.losses = losses;
+    }
 }
 
 impl Named for ShiftOverflowObserver {
@@ -72,6 +116,407 @@ impl Named for ShiftOverflowObserver {
 impl<I, S> Observer<I, S> for ShiftOverflowObserver {
     fn pre_exec(&mut self, _state: &mut S, _input: &I) -> Result<(), libafl::Error> {
         self.cause_loss = false;
+        self.losses.clear();
+        Ok(())
+    }
+}
+
+/// Minimal |lhs - rhs| observed per comparison site `(function hash, pc)` in
+/// the last run.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct BranchDistanceObserver {
+    name: Cow<'static, str>,
+    distances: HashMap<(u64, u16), u64>,
+}
+
+impl BranchDistanceObserver {
+    pub fn new() -> Self {
+        Self {
+            name: Cow::Borrowed("BranchDistanceObserver"),
+            distances: HashMap::new(),
+        }
+    }
+
+    pub fn distances(&self) -> &HashMap<(u64, u16), u64> {
+        &self.distances
+    }
+
+    pub fn set_distances(&mut self, distances: HashMap<(u64, u16), u64>) {
+        self.distances = distances;
+    }
+}
+
+impl Named for BranchDistanceObserver {
+    fn name(&self) -> &Cow<'static, str> {
+        &self.name
+    }
+}
+
+impl<I, S> Observer<I, S> for BranchDistanceObserver {
+    fn pre_exec(&mut self, _state: &mut S, _input: &I) -> Result<(), libafl::Error> {
+        self.distances.clear();
+        Ok(())
+    }
+}
+
+/// An event emitted by the last execution.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct EmittedEvent {
+    /// Canonical struct tag of the event type
+    pub type_tag: String,
+    /// Size of the BCS-encoded payload in bytes
+    pub size: usize,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct EventsObserver {
+    name: Cow<'static, str>,
+    events: Vec<EmittedEvent>,
+}
+
+impl EventsObserver {
+    pub fn new() -> Self {
+        Self {
+            name: Cow::Borrowed("EventsObserver"),
+            events: Vec::new(),
+        }
+    }
+
+    pub fn events(&self) -> &[EmittedEvent] {
+        &self.events
+    }
+
+    pub fn set_events(&mut self, events: Vec<EmittedEvent>) {
+        self.events = events;
+    }
+}
+
+impl Named for EventsObserver {
+    fn name(&self) -> &Cow<'static, str> {
+        &self.name
+    }
+}
+
+impl<I, S> Observer<I, S> for EventsObserver {
+    fn pre_exec(&mut self, _state: &mut S, _input: &I) -> Result<(), libafl::Error> {
+        self.events.clear();
+        Ok(())
+    }
+}
+
+/// A resource written by the last execution.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct WrittenResource {
+    pub address: AccountAddress,
+    /// Canonical struct tag of the resource (or resource group)
+    pub struct_tag: String,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct WriteSetObserver {
+    name: Cow<'static, str>,
+    resources: Vec<WrittenResource>,
+}
+
+impl WriteSetObserver {
+    pub fn new() -> Self {
+        Self {
+            name: Cow::Borrowed("WriteSetObserver"),
+            resources: Vec::new(),
+        }
+    }
+
+    pub fn resources(&self) -> &[WrittenResource] {
+        &self.resources
+    }
+
+    pub fn set_resources(&mut self, resources: Vec<WrittenResource>) {
+        self.resources = resources;
+    }
+}
+
+impl Named for WriteSetObserver {
+    fn name(&self) -> &Cow<'static, str> {
+        &self.name
+    }
+}
+
+impl<I, S> Observer<I, S> for WriteSetObserver {
+    fn pre_exec(&mut self, _state: &mut S, _input: &I) -> Result<(), libafl::Error> {
+        self.resources.clear();
         Ok(())
     }
 }
+
+/// Runtime issues the detectors reported during the last execution.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct RuntimeIssueObserver {
+    name: Cow<'static, str>,
+    issues: Vec<RuntimeIssue>,
+}
+
+impl RuntimeIssueObserver {
+    pub fn new() -> Self {
+        Self {
+            name: Cow::Borrowed("RuntimeIssueObserver"),
+            issues: Vec::new(),
+        }
+    }
+
+    pub fn issues(&self) -> &[RuntimeIssue] {
+        &self.issues
+    }
+
+    pub fn set_issues(&mut self, issues: Vec<RuntimeIssue>) {
+        self.issues = issues;
+    }
+}
+
+impl Named for RuntimeIssueObserver {
+    fn name(&self) -> &Cow<'static, str> {
+        &self.name
+    }
+}
+
+impl<I, S> Observer<I, S> for RuntimeIssueObserver {
+    fn pre_exec(&mut self, _state: &mut S, _input: &I) -> Result<(), libafl::Error> {
+        self.issues.clear();
+        Ok(())
+    }
+}
+
+/// Instructions executed by the last run, counted per `(function hash, pc)`.
+/// Only filled when instruction profiling is enabled on the executor.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct InstructionHistogramObserver {
+    name: Cow<'static, str>,
+    counts: HashMap<(u64, u16), u64>,
+}
+
+impl InstructionHistogramObserver {
+    pub fn new() -> Self {
+        Self {
+            name: Cow::Borrowed("InstructionHistogramObserver"),
+            counts: HashMap::new(),
+        }
+    }
+
+    pub fn counts(&self) -> &HashMap<(u64, u16), u64> {
+        &self.counts
+    }
+
+    /// Replace the histogram with the counts of `locations`.
+    pub fn record(&mut self, locations: &[TracedLocation]) {
+        self.counts.clear();
+        for &(function, pc, _) in locations {
+            *self.counts.entry((function, pc)).or_insert(0) += 1;
+        }
+    }
+}
+
+impl Named for InstructionHistogramObserver {
+    fn name(&self) -> &Cow<'static, str> {
+        &self.name
+    }
+}
+
+impl<I, S> Observer<I, S> for InstructionHistogramObserver {
+    fn pre_exec(&mut self, _state: &mut S, _input: &I) -> Result<(), libafl::Error> {
+        self.counts.clear();
+        Ok(())
+    }
+}
+
+/// Status code the VM rejected or failed the last run with, other than an
+/// abort: a verifier, loader or invariant error. `None` for runs that
+/// succeeded or aborted.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct VmStatusObserver {
+    name: Cow<'static, str>,
+    last: Option<u64>,
+}
+
+impl VmStatusObserver {
+    pub fn new() -> Self {
+        Self {
+            name: Cow::Borrowed("VmStatusObserver"),
+            last: None,
+        }
+    }
+
+    pub fn last(&self) -> Option<u64> {
+        self.last
+    }
+
+    pub fn set_last(&mut self, status: Option<u64>) {
+        self.last = status;
+    }
+}
+
+impl Named for VmStatusObserver {
+    fn name(&self) -> &Cow<'static, str> {
+        &self.name
+    }
+}
+
+impl<I, S> Observer<I, S> for VmStatusObserver {
+    fn pre_exec(&mut self, _state: &mut S, _input: &I) -> Result<(), libafl::Error> {
+        self.last = None;
+        Ok(())
+    }
+}
+
+type HitcountsObserver = HitcountsMapObserver<OwnedMapObserver<u8>>;
+
+/// The edge map, reset between runs by zeroing only the slots the last run
+/// hit rather than the whole map. The executor records hits through
+/// `record_hits`, which remembers each slot it sets; hit counts are only
+/// bucketed afterwards, and a bucket is never zero, so those are all the
+/// slots that need clearing.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DirtyMapObserver {
+    base: HitcountsObserver,
+    /// Slots set since the last reset
+    dirty: Vec<usize>,
+}
+
+impl DirtyMapObserver {
+    pub fn new(base: HitcountsObserver) -> Self {
+        Self {
+            base,
+            dirty: Vec::new(),
+        }
+    }
+
+    /// Count one hit of each slot in `slots`.
+    pub fn record_hits(&mut self, slots: &[usize]) {
+        let mut map = self.base.as_slice_mut();
+        for &slot in slots {
+            if map[slot] == 0 {
+                self.dirty.push(slot);
+            }
+            map[slot] = map[slot].saturating_add(1);
+        }
+    }
+
+    /// Zero the slots set since the last reset.
+    pub fn clear_dirty(&mut self) {
+        let mut map = self.base.as_slice_mut();
+        for slot in self.dirty.drain(..) {
+            map[slot] = 0;
+        }
+    }
+}
+
+impl Named for DirtyMapObserver {
+    fn name(&self) -> &Cow<'static, str> {
+        self.base.name()
+    }
+}
+
+impl HasLen for DirtyMapObserver {
+    fn len(&self) -> usize {
+        self.base.len()
+    }
+}
+
+impl Hash for DirtyMapObserver {
+    fn hash<H: Hasher>(&self, hasher: &mut H) {
+        self.base.hash(hasher);
+    }
+}
+
+impl AsRef<Self> for DirtyMapObserver {
+    fn as_ref(&self) -> &Self {
+        self
+    }
+}
+
+impl AsMut<Self> for DirtyMapObserver {
+    fn as_mut(&mut self) -> &mut Self {
+        self
+    }
+}
+
+impl MapObserver for DirtyMapObserver {
+    type Entry = u8;
+
+    fn get(&self, idx: usize) -> u8 {
+        self.base.get(idx)
+    }
+
+    fn set(&mut self, idx: usize, val: u8) {
+        if val != 0 && self.base.get(idx) == 0 {
+            self.dirty.push(idx);
+        }
+        self.base.set(idx, val);
+    }
+
+    fn usable_count(&self) -> usize {
+        self.base.usable_count()
+    }
+
+    fn count_bytes(&self) -> u64 {
+        self.base.count_bytes()
+    }
+
+    fn initial(&self) -> u8 {
+        self.base.initial()
+    }
+
+    fn reset_map(&mut self) -> Result<(), libafl::Error> {
+        self.dirty.clear();
+        self.base.reset_map()
+    }
+
+    fn to_vec(&self) -> Vec<u8> {
+        self.base.to_vec()
+    }
+
+    fn how_many_set(&self, indexes: &[usize]) -> usize {
+        self.base.how_many_set(indexes)
+    }
+}
+
+impl<'a> AsSlice<'a> for DirtyMapObserver {
+    type Entry = u8;
+    type SliceRef = <HitcountsObserver as AsSlice<'a>>::SliceRef;
+
+    fn as_slice(&'a self) -> Self::SliceRef {
+        self.base.as_slice()
+    }
+}
+
+impl<'a> AsSliceMut<'a> for DirtyMapObserver {
+    type SliceRefMut = <HitcountsObserver as AsSliceMut<'a>>::SliceRefMut;
+
+    /// Writes through the slice aren't tracked; use `record_hits`.
+    fn as_slice_mut(&'a mut self) -> Self::SliceRefMut {
+        self.base.as_slice_mut()
+    }
+}
+
+impl<'it> AsIter<'it> for DirtyMapObserver {
+    type Item = u8;
+    type Ref = <HitcountsObserver as AsIter<'it>>::Ref;
+    type IntoIter = <HitcountsObserver as AsIter<'it>>::IntoIter;
+
+    fn as_iter(&'it self) -> Self::IntoIter {
+        self.base.as_iter()
+    }
+}
+
+impl<I, S> Observer<I, S> for DirtyMapObserver
+where
+    HitcountsObserver: Observer<I, S>,
+{
+    /// Replaces the base map's reset, which zeroes every slot.
+    fn pre_exec(&mut self, _state: &mut S, _input: &I) -> Result<(), libafl::Error> {
+        self.clear_dirty();
+        Ok(())
+    }
+
+    fn post_exec(&mut self, state: &mut S, input: &I, exit_kind: &ExitKind) -> Result<(), libafl::Error> {
+        self.base.post_exec(state, input, exit_kind)
+    }
+}
@@ -0,0 +1,780 @@
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::str::FromStr;
+
+use aptos_move_core_types::account_address::AccountAddress;
+use aptos_move_core_types::identifier::{IdentStr, Identifier};
+use aptos_move_core_types::language_storage::{ModuleId, StructTag, TypeTag};
+use aptos_move_core_types::u256::U256;
+use aptos_types::contract_event::ContractEvent;
+use aptos_types::state_store::state_key::inner::StateKeyInner;
+use aptos_types::state_store::state_key::StateKey;
+use aptos_types::write_set::{TransactionWrite, WriteSet};
+
+use crate::executor::aptos_custom_state::AptosCustomState;
+use crate::mutator::decode_uleb128;
+use crate::script_sequence::{Comparison, ReturnAssertion, ReturnOperand, ScriptSequence, SequenceCall};
+use crate::struct_layout::{framework_struct, resource_changes, StructLayoutRegistry};
+
+/// An invariant broken by a single execution.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Violation {
+    /// Name of the oracle that reported the violation
+    pub oracle: String,
+    pub message: String,
+}
+
+/// A property checked after every successful execution, against the state
+/// before the transaction and the changes it produced.
+pub trait InvariantOracle {
+    fn name(&self) -> &str;
+
+    fn check(&self, pre: &AptosCustomState, write_set: &WriteSet, events: &[ContractEvent]) -> Option<Violation>;
+}
+
+/// Resources written by `write_set` whose struct tag is `tag`, with their new
+/// bytes (`None` when deleted).
+fn resource_writes<'a>(
+    write_set: &'a WriteSet,
+    tag: &'a StructTag,
+) -> impl Iterator<Item = (AccountAddress, Option<&'a [u8]>)> + 'a {
+    write_set.write_op_iter().filter_map(move |(state_key, write_op)| {
+        let StateKeyInner::AccessPath(access_path) = state_key.inner() else {
+            return None;
+        };
+        if access_path.get_struct_tag().as_ref() != Some(tag) {
+            return None;
+        }
+        Some((access_path.address, write_op.bytes().map(|bytes| bytes.as_ref())))
+    })
+}
+
+/// The sum of `0x1::coin::CoinStore<X>` balances over all accounts touched by
+/// a transaction must not change: coins may move, but never appear or vanish.
+pub struct CoinConservationOracle {
+    name: String,
+    coin_store: StructTag,
+}
+
+impl CoinConservationOracle {
+    pub fn new(coin_type: StructTag) -> Self {
+        let name = format!("CoinConservation<{}>", coin_type.to_canonical_string());
+        let coin_store = framework_struct("coin", "CoinStore", vec![TypeTag::Struct(Box::new(coin_type))]);
+        Self { name, coin_store }
+    }
+
+    pub fn from_type_str(coin_type: &str) -> anyhow::Result<Self> {
+        Ok(Self::new(StructTag::from_str(coin_type)?))
+    }
+
+    /// `CoinStore` starts with `coin: Coin { value: u64 }`.
+    fn balance(bytes: Option<&[u8]>) -> u128 {
+        bytes
+            .and_then(|bytes| bytes.get(..8))
+            .map(|value| u64::from_le_bytes(value.try_into().expect("8 bytes")) as u128)
+            .unwrap_or(0)
+    }
+}
+
+impl InvariantOracle for CoinConservationOracle {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn check(&self, pre: &AptosCustomState, write_set: &WriteSet, _events: &[ContractEvent]) -> Option<Violation> {
+        let mut before: u128 = 0;
+        let mut after: u128 = 0;
+        for (address, bytes) in resource_writes(write_set, &self.coin_store) {
+            let previous = StateKey::resource(&address, &self.coin_store)
+                .ok()
+                .and_then(|key| pre.get_state_value(&key));
+            before += Self::balance(previous.as_ref().map(|value| value.bytes().as_ref()));
+            after += Self::balance(bytes);
+        }
+        if before == after {
+            return None;
+        }
+        Some(Violation {
+            oracle: self.name.clone(),
+            message: format!("total balance of touched accounts changed from {} to {}", before, after),
+        })
+    }
+}
+
+/// A coin type or, by its metadata object, a fungible asset.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Asset {
+    Coin(TypeTag),
+    FungibleAsset(AccountAddress),
+}
+
+impl fmt::Display for Asset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Asset::Coin(coin_type) => write!(f, "{}", coin_type.to_canonical_string()),
+            Asset::FungibleAsset(metadata) => write!(f, "fungible asset {}", metadata.to_hex_literal()),
+        }
+    }
+}
+
+/// What a transaction wrote of an asset, before and after.
+#[derive(Debug, Default)]
+struct AssetTally {
+    /// Over the written stores and resources holding it
+    held: [u128; 2],
+    /// Set when the supply was written, `None` inside when it isn't tracked
+    /// as a readable integer
+    supply: Option<[Option<u128>; 2]>,
+}
+
+/// The assets a transaction wrote.
+#[derive(Debug, Default)]
+struct AssetTallies {
+    assets: BTreeMap<Asset, AssetTally>,
+    /// Something that may hold coins was written but couldn't be read, e.g.
+    /// a table item, so balances don't add up to what is held
+    opaque: bool,
+}
+
+impl AssetTallies {
+    /// Account for a standalone resource going from `sides[0]` to
+    /// `sides[1]`.
+    fn resource(&mut self, tag: &StructTag, sides: [Option<&[u8]>; 2], layouts: &StructLayoutRegistry) {
+        if tag.address != AccountAddress::ONE {
+            self.coins(tag, sides, layouts);
+            return;
+        }
+        let [coin_type] = tag.type_args.as_slice() else {
+            return;
+        };
+        let asset = Asset::Coin(coin_type.clone());
+        match (tag.module.as_str(), tag.name.as_str()) {
+            ("coin", "CoinStore") => {
+                let tally = self.assets.entry(asset).or_default();
+                for (held, bytes) in tally.held.iter_mut().zip(sides) {
+                    *held += CoinConservationOracle::balance(bytes);
+                }
+            }
+            ("coin", "CoinInfo") => {
+                // A coin initialized by the transaction had none
+                let supply = sides.map(|bytes| bytes.map_or(Some(0), coin_supply));
+                self.assets.entry(asset).or_default().supply = Some(supply);
+            }
+            _ => {}
+        }
+    }
+
+    /// Account for a member of the object group at `address` going from
+    /// `sides[0]` to `sides[1]`, in groups `groups[0]` and `groups[1]`.
+    fn group_member(
+        &mut self,
+        address: AccountAddress,
+        tag: &StructTag,
+        sides: [Option<&[u8]>; 2],
+        groups: [&BTreeMap<StructTag, Vec<u8>>; 2],
+        layouts: &StructLayoutRegistry,
+    ) {
+        if tag.address != AccountAddress::ONE {
+            self.coins(tag, sides, layouts);
+            return;
+        }
+        // FungibleStore { metadata: Object<Metadata>, balance: u64, frozen: bool }
+        let store_tag = framework_struct("fungible_asset", "FungibleStore", vec![]);
+        let metadata = groups
+            .iter()
+            .find_map(|group| group.get(&store_tag)?.get(..AccountAddress::LENGTH))
+            .and_then(|bytes| AccountAddress::from_bytes(bytes).ok());
+        let (balance_offset, supply) = match (tag.module.as_str(), tag.name.as_str()) {
+            ("fungible_asset", "FungibleStore") => (AccountAddress::LENGTH, false),
+            // ConcurrentFungibleBalance { balance: Aggregator<u64> }, the
+            // balance of the group's store once set
+            ("fungible_asset", "ConcurrentFungibleBalance") => (0, false),
+            // Supply { current: u128, .. } and ConcurrentSupply { current:
+            // Aggregator<u128> } of the metadata object
+            ("fungible_asset", "Supply" | "ConcurrentSupply") => (0, true),
+            _ => return,
+        };
+        if supply {
+            let tally = self.assets.entry(Asset::FungibleAsset(address)).or_default();
+            // Upgrading to a concurrent supply deletes the other one, the
+            // sum of both is the supply
+            let totals = tally.supply.get_or_insert([Some(0), Some(0)]);
+            for (total, bytes) in totals.iter_mut().zip(sides) {
+                *total = total.map(|total| total + le_u128(bytes, 16).unwrap_or(0));
+            }
+        } else if let Some(metadata) = metadata {
+            let tally = self.assets.entry(Asset::FungibleAsset(metadata)).or_default();
+            for (held, bytes) in tally.held.iter_mut().zip(sides) {
+                *held += le_u128(bytes.and_then(|bytes| bytes.get(balance_offset..)), 8).unwrap_or(0);
+            }
+        }
+    }
+
+    /// Account for the coins a resource of a module outside the framework
+    /// holds.
+    fn coins(&mut self, tag: &StructTag, sides: [Option<&[u8]>; 2], layouts: &StructLayoutRegistry) {
+        let [Some(before), Some(after)] = sides.map(|bytes| match bytes {
+            Some(bytes) => layouts.coins(tag, bytes),
+            None => Some(BTreeMap::new()),
+        }) else {
+            self.opaque = true;
+            return;
+        };
+        for (side, coins) in [before, after].into_iter().enumerate() {
+            for (coin_type, value) in coins {
+                self.assets.entry(Asset::Coin(coin_type)).or_default().held[side] += value;
+            }
+        }
+    }
+}
+
+/// Coins and fungible assets are only created by mint functions and only
+/// destroyed by burn functions: the supply a transaction writes must not
+/// change unless it calls one. For assets whose supply isn't tracked, the
+/// sum of the balances it writes (`CoinStore`s, `FungibleStore`s and coins
+/// in resources outside the framework) stands in for it, unless it writes
+/// holders it can't read, such as table items. Mint and burn functions are
+/// those named so and the declared issuers.
+#[derive(Default)]
+pub struct AssetConservationOracle {
+    issuers: Vec<ReturnSource>,
+}
+
+impl AssetConservationOracle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Let `function` (`[<address>::]<module>::<function>`) mint and burn,
+    /// e.g. one paying out staking rewards.
+    pub fn add_issuer(&mut self, function: &str) -> anyhow::Result<()> {
+        self.issuers.push(function.parse()?);
+        Ok(())
+    }
+
+    /// Check the `write_set` of a transaction calling `functions`, named
+    /// `<address>::<module>::<function>`.
+    pub fn check(
+        &self,
+        pre: &AptosCustomState,
+        write_set: &WriteSet,
+        functions: &[String],
+        layouts: &StructLayoutRegistry,
+    ) -> Option<Violation> {
+        let mut tallies = AssetTallies::default();
+        let object_group = framework_struct("object", "ObjectGroup", vec![]);
+        for (state_key, write_op) in write_set.write_op_iter() {
+            let access_path = match state_key.inner() {
+                StateKeyInner::AccessPath(access_path) => access_path,
+                StateKeyInner::TableItem { .. } => {
+                    tallies.opaque = true;
+                    continue;
+                }
+                _ => continue,
+            };
+            let Some(tag) = access_path.get_struct_tag() else {
+                continue;
+            };
+            let previous = pre.get_state_value(state_key);
+            let before = previous.as_ref().map(|value| value.bytes().as_ref());
+            let after = write_op.bytes().map(|bytes| bytes.as_ref());
+            if tag != object_group {
+                tallies.resource(&tag, [before, after], layouts);
+                continue;
+            }
+            let groups = [before, after].map(|bytes| {
+                bytes
+                    .and_then(|bytes| bcs::from_bytes::<BTreeMap<StructTag, Vec<u8>>>(bytes).ok())
+                    .unwrap_or_default()
+            });
+            let members: BTreeSet<&StructTag> = groups.iter().flat_map(|group| group.keys()).collect();
+            for member in members {
+                let sides = [groups[0].get(member), groups[1].get(member)].map(|bytes| bytes.map(Vec::as_slice));
+                if sides[0] != sides[1] {
+                    tallies.group_member(access_path.address, member, sides, [&groups[0], &groups[1]], layouts);
+                }
+            }
+        }
+
+        let breaches: Vec<String> = tallies
+            .assets
+            .into_iter()
+            .filter_map(|(asset, tally)| {
+                let [before, after] = match tally.supply {
+                    Some([Some(before), Some(after)]) => [before, after],
+                    _ if tallies.opaque => return None,
+                    None if supply_tracked(pre, &asset) => return None,
+                    _ => tally.held,
+                };
+                let (amount, verb, kind) = match after.cmp(&before) {
+                    Ordering::Equal => return None,
+                    Ordering::Greater => (after - before, "created", "mint"),
+                    Ordering::Less => (before - after, "destroyed", "burn"),
+                };
+                if functions.iter().any(|function| self.is_issuer(function, kind)) {
+                    return None;
+                }
+                Some(format!("{} of {} {}", amount, asset, verb))
+            })
+            .collect();
+        if breaches.is_empty() {
+            return None;
+        }
+        Some(Violation {
+            oracle: "AssetConservation".to_string(),
+            message: format!(
+                "{} outside mint and burn functions, by {}",
+                breaches.join(", "),
+                if functions.is_empty() {
+                    "a script".to_string()
+                } else {
+                    functions.join(", ")
+                }
+            ),
+        })
+    }
+
+    /// Whether `function` may `kind` ("mint" or "burn") assets.
+    fn is_issuer(&self, function: &str, kind: &str) -> bool {
+        let parts: Vec<&str> = function.split("::").collect();
+        let [address, module, name] = parts.as_slice() else {
+            return false;
+        };
+        if name.to_lowercase().contains(kind) {
+            return true;
+        }
+        let (Ok(address), Ok(module), Ok(name)) = (
+            AccountAddress::from_str(address),
+            Identifier::new(*module),
+            Identifier::new(*name),
+        ) else {
+            return false;
+        };
+        let module = ModuleId::new(address, module);
+        self.issuers
+            .iter()
+            .any(|issuer| issuer.matches_function(&module, name.as_ident_str()))
+    }
+}
+
+/// Little-endian unsigned integer of `width` bytes at the start of `bytes`.
+fn le_u128(bytes: Option<&[u8]>, width: usize) -> Option<u128> {
+    let mut le = [0u8; 16];
+    le[..width].copy_from_slice(bytes?.get(..width)?);
+    Some(u128::from_le_bytes(le))
+}
+
+/// `CoinInfo { name: String, symbol: String, decimals: u8, supply:
+/// Option<OptionalAggregator> }`, the supply when tracked as an `Integer {
+/// value: u128, limit: u128 }` rather than by a parallelizable aggregator.
+fn coin_supply(bytes: &[u8]) -> Option<u128> {
+    let mut offset = 0;
+    for _ in 0..2 {
+        let (len, size) = decode_uleb128(bytes.get(offset..)?)?;
+        offset += size + len;
+    }
+    offset += 1;
+    // Some(OptionalAggregator { aggregator: None, integer: Some(..) })
+    match bytes.get(offset..offset + 3)? {
+        [1, 0, 1] => le_u128(bytes.get(offset + 3..), 16),
+        _ => None,
+    }
+}
+
+/// Whether the supply of `asset` is tracked in `pre` as a readable integer,
+/// so that it not being written means none was created or destroyed.
+fn supply_tracked(pre: &AptosCustomState, asset: &Asset) -> bool {
+    match asset {
+        Asset::Coin(TypeTag::Struct(coin_type)) => {
+            let coin_info = StructTag {
+                type_args: vec![TypeTag::Struct(coin_type.clone())],
+                ..framework_struct("coin", "CoinInfo", vec![])
+            };
+            StateKey::resource(&coin_type.address, &coin_info)
+                .ok()
+                .and_then(|state_key| pre.get_state_value(&state_key))
+                .is_some_and(|value| coin_supply(value.bytes()).is_some())
+        }
+        Asset::Coin(_) => false,
+        Asset::FungibleAsset(metadata) => {
+            StateKey::resource_group(metadata, &framework_struct("object", "ObjectGroup", vec![]))
+                .ok()
+                .and_then(|group_key| pre.get_state_value(&group_key))
+                .and_then(|value| bcs::from_bytes::<BTreeMap<StructTag, Vec<u8>>>(value.bytes()).ok())
+                .is_some_and(|group| {
+                    group.contains_key(&framework_struct("fungible_asset", "Supply", vec![])) ||
+                        group.contains_key(&framework_struct("fungible_asset", "ConcurrentSupply", vec![]))
+                })
+        }
+    }
+}
+
+/// A resource of the given type must never be deleted once published.
+pub struct ResourceNeverDeletedOracle {
+    name: String,
+    resource: StructTag,
+}
+
+impl ResourceNeverDeletedOracle {
+    pub fn new(resource: StructTag) -> Self {
+        let name = format!("ResourceNeverDeleted<{}>", resource.to_canonical_string());
+        Self { name, resource }
+    }
+
+    pub fn from_type_str(resource: &str) -> anyhow::Result<Self> {
+        Ok(Self::new(StructTag::from_str(resource)?))
+    }
+}
+
+impl InvariantOracle for ResourceNeverDeletedOracle {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn check(&self, _pre: &AptosCustomState, write_set: &WriteSet, _events: &[ContractEvent]) -> Option<Violation> {
+        let (address, _) = resource_writes(write_set, &self.resource).find(|(_, bytes)| bytes.is_none())?;
+        Some(Violation {
+            oracle: self.name.clone(),
+            message: format!("{} deleted from {}", self.resource.to_canonical_string(), address),
+        })
+    }
+}
+
+/// Runtime access control: a transaction must not change or delete a
+/// resource of a target module held by another account, or by an object
+/// another account owns, unless the resource names the sender, e.g. as an
+/// approved operator: an address field holding it when the resource's
+/// layout is known, its bytes anywhere otherwise. Complements the static
+/// capability leak detector. Resources at module addresses are shared state
+/// and not checked.
+pub struct SignerOwnershipOracle {
+    modules: BTreeSet<ModuleId>,
+    module_addresses: BTreeSet<AccountAddress>,
+}
+
+impl SignerOwnershipOracle {
+    pub fn new(modules: &[ModuleId]) -> Self {
+        Self {
+            modules: modules.iter().cloned().collect(),
+            module_addresses: modules.iter().map(|module| *module.address()).collect(),
+        }
+    }
+
+    pub fn check(
+        &self,
+        pre: &AptosCustomState,
+        write_set: &WriteSet,
+        sender: AccountAddress,
+        layouts: &StructLayoutRegistry,
+    ) -> Option<Violation> {
+        let breaches: Vec<String> = resource_changes(pre, write_set)
+            .into_iter()
+            .filter_map(|change| {
+                let tag = &change.struct_tag;
+                if !self.modules.contains(&tag.module_id()) {
+                    return None;
+                }
+                let holder = change.address;
+                let owner = pre.object_owner(&holder).unwrap_or(holder);
+                if owner == sender || self.module_addresses.contains(&owner) {
+                    return None;
+                }
+                // Created resources had no owner yet
+                let previous = change.before.as_deref()?;
+                let approved = match layouts.decode(&TypeTag::Struct(Box::new(tag.clone())), previous) {
+                    Some(value) => value.addresses().contains(&sender),
+                    None => previous
+                        .windows(AccountAddress::LENGTH)
+                        .any(|window| window == sender.as_ref()),
+                };
+                if approved {
+                    return None;
+                }
+                let breach = format!(
+                    "{} of {} at {}",
+                    if change.after.is_some() { "modified" } else { "deleted" },
+                    tag.to_canonical_string(),
+                    if owner == holder {
+                        owner.to_string()
+                    } else {
+                        format!("{} (owned by {})", holder, owner)
+                    }
+                );
+                Some(match change.after {
+                    Some(_) => format!("{} [{}]", breach, layouts.describe_change(&change)),
+                    None => breach,
+                })
+            })
+            .collect();
+        if breaches.is_empty() {
+            return None;
+        }
+        Some(Violation {
+            oracle: "SignerOwnership".to_string(),
+            message: format!("transaction signed by {} {}", sender, breaches.join(", ")),
+        })
+    }
+}
+
+/// A property of the values sequence calls return, e.g.
+/// `pool::withdraw.0 == pool::deposit.0` or `pool::deposit.1 > 0`. Every
+/// generated call of the left-hand function is asserted against the
+/// constant, or against the latest earlier call of the right-hand function.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ReturnProperty {
+    call: ReturnSource,
+    comparison: Comparison,
+    expected: PropertyOperand,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum PropertyOperand {
+    Constant(U256),
+    Return(ReturnSource),
+}
+
+/// `[<address>::]<module>::<function>[.<return index>]`
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct ReturnSource {
+    address: Option<AccountAddress>,
+    module: Identifier,
+    function: Identifier,
+    return_idx: u16,
+}
+
+impl ReturnSource {
+    fn matches(&self, call: &SequenceCall) -> bool {
+        self.matches_function(call.module(), call.function())
+    }
+
+    fn matches_function(&self, module: &ModuleId, function: &IdentStr) -> bool {
+        self.address.is_none_or(|address| address == *module.address()) &&
+            module.name() == self.module.as_ident_str() &&
+            function == self.function.as_ident_str()
+    }
+}
+
+impl FromStr for ReturnSource {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let (path, return_idx) = match s.rsplit_once('.') {
+            Some((path, return_idx)) => (path, return_idx.parse()?),
+            None => (s, 0),
+        };
+        let parts: Vec<&str> = path.split("::").collect();
+        let (address, module, function) = match parts.as_slice() {
+            [module, function] => (None, *module, *function),
+            [address, module, function] => (Some(AccountAddress::from_hex_literal(address)?), *module, *function),
+            _ => anyhow::bail!("expected [<address>::]<module>::<function>[.<return index>]"),
+        };
+        Ok(Self {
+            address,
+            module: Identifier::new(module)?,
+            function: Identifier::new(function)?,
+            return_idx,
+        })
+    }
+}
+
+impl ReturnProperty {
+    /// The assertion to attach to `call` when it is appended to `sequence`,
+    /// if the property is about it and what it compares against is there.
+    pub fn assertion_for(&self, sequence: &ScriptSequence, call: &SequenceCall) -> Option<ReturnAssertion> {
+        if !self.call.matches(call) {
+            return None;
+        }
+        let expected = match &self.expected {
+            PropertyOperand::Constant(constant) => ReturnOperand::Constant(*constant),
+            PropertyOperand::Return(source) => {
+                let call_idx = sequence.calls().iter().rposition(|earlier| source.matches(earlier))?;
+                ReturnOperand::Return {
+                    call_idx: call_idx as u16,
+                    return_idx: source.return_idx,
+                }
+            }
+        };
+        Some(ReturnAssertion {
+            return_idx: self.call.return_idx,
+            comparison: self.comparison,
+            expected,
+        })
+    }
+}
+
+impl FromStr for ReturnProperty {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let [call, comparison, expected] = s.split_whitespace().collect::<Vec<_>>()[..] else {
+            anyhow::bail!("expected <function>[.<return>] <comparison> <function>[.<return>]|<number>");
+        };
+        let expected = match expected {
+            "true" => PropertyOperand::Constant(U256::one()),
+            "false" => PropertyOperand::Constant(U256::zero()),
+            _ => match U256::from_str(expected) {
+                Ok(constant) => PropertyOperand::Constant(constant),
+                Err(_) => PropertyOperand::Return(expected.parse()?),
+            },
+        };
+        Ok(Self {
+            call: call.parse()?,
+            comparison: comparison.parse()?,
+            expected,
+        })
+    }
+}
+
+/// A pair of functions that undo each other, e.g. `pool::deposit ->
+/// pool::withdraw`. A sequence of forward calls each followed by an inverse
+/// call must not leave the sender with more of any coin than it started
+/// with.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RoundTrip {
+    forward: ReturnSource,
+    inverse: ReturnSource,
+}
+
+impl RoundTrip {
+    pub fn is_forward(&self, module: &ModuleId, function: &IdentStr) -> bool {
+        self.forward.matches_function(module, function)
+    }
+
+    pub fn is_inverse(&self, module: &ModuleId, function: &IdentStr) -> bool {
+        self.inverse.matches_function(module, function)
+    }
+
+    pub fn name(&self) -> String {
+        format!(
+            "RoundTrip<{}::{}, {}::{}>",
+            self.forward.module, self.forward.function, self.inverse.module, self.inverse.function
+        )
+    }
+
+    /// Whether `sequence` only calls the pair, with every inverse call
+    /// undoing an earlier forward one.
+    pub fn applies_to(&self, sequence: &ScriptSequence) -> bool {
+        let mut open = 0usize;
+        let mut closed = 0usize;
+        for call in sequence.calls() {
+            if self.forward.matches(call) {
+                open += 1;
+            } else if self.inverse.matches(call) && open > 0 {
+                open -= 1;
+                closed += 1;
+            } else {
+                return false;
+            }
+        }
+        closed > 0
+    }
+
+    /// Compare the `CoinStore` balances of `sender` before and after the
+    /// sequence ran.
+    pub fn check(&self, pre: &AptosCustomState, write_set: &WriteSet, sender: AccountAddress) -> Option<Violation> {
+        let gains: Vec<String> = write_set
+            .write_op_iter()
+            .filter_map(|(state_key, write_op)| {
+                let StateKeyInner::AccessPath(access_path) = state_key.inner() else {
+                    return None;
+                };
+                let tag = access_path.get_struct_tag()?;
+                if access_path.address != sender || !is_coin_store(&tag) {
+                    return None;
+                }
+                let before = pre
+                    .get_state_value(state_key)
+                    .map(|value| CoinConservationOracle::balance(Some(value.bytes().as_ref())))
+                    .unwrap_or(0);
+                let after = CoinConservationOracle::balance(write_op.bytes().map(|bytes| bytes.as_ref()));
+                (after > before).then(|| format!("{} went from {} to {}", tag.to_canonical_string(), before, after))
+            })
+            .collect();
+        if gains.is_empty() {
+            return None;
+        }
+        Some(Violation {
+            oracle: self.name(),
+            message: format!("sender {} gained from the round trip: {}", sender, gains.join(", ")),
+        })
+    }
+}
+
+fn is_coin_store(tag: &StructTag) -> bool {
+    tag.address == AccountAddress::ONE && tag.module.as_str() == "coin" && tag.name.as_str() == "CoinStore"
+}
+
+impl FromStr for RoundTrip {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let Some((forward, inverse)) = s.split_once("->") else {
+            anyhow::bail!("expected [<address>::]<module>::<function> -> [<address>::]<module>::<function>");
+        };
+        Ok(Self {
+            forward: forward.trim().parse()?,
+            inverse: inverse.trim().parse()?,
+        })
+    }
+}
+
+/// How a view function's result may change across a transaction, e.g.
+/// `pool::total_supply >=` (never decreases) or `pool::fee_rate.1 ==`
+/// (never changes): the value after must compare to the value before as
+/// stated.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ViewMonotonicity {
+    view: ReturnSource,
+    comparison: Comparison,
+}
+
+impl ViewMonotonicity {
+    pub fn is_about(&self, module: &ModuleId, function: &IdentStr) -> bool {
+        self.view.matches_function(module, function)
+    }
+
+    /// Message for the values returned before and after the transaction,
+    /// `None` if they keep to the property or aren't numbers.
+    pub fn check(&self, return_types: &[TypeTag], before: &[Vec<u8>], after: &[Vec<u8>]) -> Option<String> {
+        let idx = self.view.return_idx as usize;
+        let ty = return_types.get(idx)?;
+        let before = numeric_value(ty, before.get(idx)?)?;
+        let after = numeric_value(ty, after.get(idx)?)?;
+        if self.comparison.holds(after, before) {
+            return None;
+        }
+        Some(format!(
+            "{}::{} return {} went from {} to {}, expected {} {}",
+            self.view.module, self.view.function, idx, before, after, self.comparison, before
+        ))
+    }
+}
+
+impl FromStr for ViewMonotonicity {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let [view, comparison] = s.split_whitespace().collect::<Vec<_>>()[..] else {
+            anyhow::bail!("expected <function>[.<return>] <comparison>");
+        };
+        Ok(Self {
+            view: view.parse()?,
+            comparison: comparison.parse()?,
+        })
+    }
+}
+
+/// BCS-encoded `bytes` of a `bool` or unsigned integer type as a number.
+fn numeric_value(ty: &TypeTag, bytes: &[u8]) -> Option<U256> {
+    Some(match ty {
+        TypeTag::Bool => U256::from(bcs::from_bytes::<bool>(bytes).ok()? as u8),
+        TypeTag::U8 => U256::from(bcs::from_bytes::<u8>(bytes).ok()?),
+        TypeTag::U16 => U256::from(bcs::from_bytes::<u16>(bytes).ok()?),
+        TypeTag::U32 => U256::from(bcs::from_bytes::<u32>(bytes).ok()?),
+        TypeTag::U64 => U256::from(bcs::from_bytes::<u64>(bytes).ok()?),
+        TypeTag::U128 => U256::from(bcs::from_bytes::<u128>(bytes).ok()?),
+        TypeTag::U256 => bcs::from_bytes::<U256>(bytes).ok()?,
+        _ => return None,
+    })
+}
@@ -0,0 +1,89 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use anyhow::{bail, Context};
+use serde::Deserialize;
+
+/// CLI used to compile packages when none is given
+pub const DEFAULT_COMPILER: &str = "aptos";
+
+/// How to compile a Move source package.
+#[derive(Clone, Debug)]
+pub struct PackageBuildOptions {
+    /// `aptos` CLI binary; `aptos move compile` does the build
+    pub compiler: PathBuf,
+    /// `name=address` assignments for named addresses left open in
+    /// `Move.toml`
+    pub named_addresses: Vec<String>,
+}
+
+impl Default for PackageBuildOptions {
+    fn default() -> Self {
+        Self {
+            compiler: PathBuf::from(DEFAULT_COMPILER),
+            named_addresses: Vec::new(),
+        }
+    }
+}
+
+/// Output of a package build.
+#[derive(Clone, Debug)]
+pub struct BuiltPackage {
+    pub name: String,
+    /// `build/<name>/bytecode_modules`, dependencies under `dependencies/`
+    pub bytecode_modules: PathBuf,
+    /// `build/<name>/source_maps`, one `<module>.mvsm` per module
+    pub source_maps: PathBuf,
+    /// The package's own `sources` directory, which the source maps refer to
+    pub sources: PathBuf,
+}
+
+#[derive(Deserialize)]
+struct Manifest {
+    package: ManifestPackage,
+}
+
+#[derive(Deserialize)]
+struct ManifestPackage {
+    name: String,
+}
+
+/// Compile the package at `path` (the directory holding `Move.toml`).
+pub fn build_package(path: &Path, options: &PackageBuildOptions) -> anyhow::Result<BuiltPackage> {
+    let manifest_path = path.join("Move.toml");
+    let manifest =
+        fs::read_to_string(&manifest_path).with_context(|| format!("failed to read {}", manifest_path.display()))?;
+    let manifest: Manifest =
+        toml::from_str(&manifest).with_context(|| format!("invalid manifest {}", manifest_path.display()))?;
+
+    let mut command = Command::new(&options.compiler);
+    command
+        .args(["move", "compile", "--skip-fetch-latest-git-deps", "--package-dir"])
+        .arg(path);
+    if !options.named_addresses.is_empty() {
+        command.arg("--named-addresses").arg(options.named_addresses.join(","));
+    }
+    let output = command
+        .output()
+        .with_context(|| format!("failed to run {}", options.compiler.display()))?;
+    if !output.status.success() {
+        bail!(
+            "compiling {} failed:\n{}",
+            path.display(),
+            String::from_utf8_lossy(&output.stderr).trim_end()
+        );
+    }
+
+    let build_dir = path.join("build").join(&manifest.package.name);
+    let bytecode_modules = build_dir.join("bytecode_modules");
+    if !bytecode_modules.is_dir() {
+        bail!("compiler produced no {}", bytecode_modules.display());
+    }
+    Ok(BuiltPackage {
+        name: manifest.package.name,
+        bytecode_modules,
+        source_maps: build_dir.join("source_maps"),
+        sources: path.join("sources"),
+    })
+}
@@ -0,0 +1,492 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fmt;
+
+use aptos_move_binary_format::access::ModuleAccess;
+use aptos_move_binary_format::CompiledModule;
+use aptos_move_core_types::account_address::AccountAddress;
+use aptos_move_core_types::identifier::Identifier;
+use aptos_move_core_types::language_storage::{ModuleId, StructTag};
+use aptos_types::state_store::state_key::StateKey;
+
+use crate::conflicts::{global_op, GlobalAccess, GlobalOp, ResourceRef};
+use crate::executor::aptos_custom_state::AptosCustomState;
+
+/// Module initializer, the one function allowed to create a resource that
+/// nothing can create again
+const INIT_MODULE: &str = "init_module";
+
+/// A resource a transaction left behind or tried to create twice.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ResourceLeak {
+    /// `resource` is left at `address` while `guard`, which every function
+    /// changing or removing it borrows and none but `init_module` creates,
+    /// was removed from there
+    Orphaned {
+        resource: String,
+        address: AccountAddress,
+        guard: String,
+        removed_by: String,
+    },
+    /// `move_to` of `resource` at `address`, which already held one
+    DoubleCreation {
+        resource: String,
+        address: AccountAddress,
+        /// Earlier call of the sequence that created it, `None` when it
+        /// existed before the transaction
+        created_by: Option<String>,
+        by: String,
+    },
+}
+
+impl fmt::Display for ResourceLeak {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Orphaned {
+                resource,
+                address,
+                guard,
+                removed_by,
+            } => write!(
+                f,
+                "{} at {} can no longer be changed or removed: {} removed {}, which every function doing so borrows",
+                resource,
+                address.to_hex_literal(),
+                removed_by,
+                guard
+            ),
+            Self::DoubleCreation {
+                resource,
+                address,
+                created_by,
+                by,
+            } => write!(
+                f,
+                "{} moves {} to {}, which already holds one ({})",
+                by,
+                resource,
+                address.to_hex_literal(),
+                match created_by {
+                    Some(call) => format!("created by {} earlier in the sequence", call),
+                    None => "created before the transaction".to_string(),
+                }
+            ),
+        }
+    }
+}
+
+/// How the functions of a module use its resources.
+#[derive(Debug, Default)]
+struct ModuleResources {
+    /// Name of each struct definition
+    names: Vec<Identifier>,
+    /// Resources every function changing or removing a resource borrows,
+    /// by struct definition
+    guards: HashMap<u16, BTreeSet<u16>>,
+    /// Resources a function other than `init_module` moves to an address
+    recreatable: HashSet<u16>,
+}
+
+impl ModuleResources {
+    fn new(module: &CompiledModule) -> Self {
+        let def_of = |resource: ResourceRef| match resource {
+            ResourceRef::Definition(idx) => Some(idx),
+            ResourceRef::Instantiation(idx) => module.struct_instantiations().get(idx as usize).map(|inst| inst.def.0),
+        };
+        let names = module
+            .struct_defs()
+            .iter()
+            .map(|def| {
+                module
+                    .identifier_at(module.struct_handle_at(def.struct_handle).name)
+                    .to_owned()
+            })
+            .collect();
+        let functions = module.function_defs().iter().filter_map(|function| {
+            let code = function.code.as_ref()?;
+            let name = module.identifier_at(module.function_handle_at(function.function).name);
+            let ops = code
+                .code
+                .iter()
+                .filter_map(|instruction| {
+                    let (resource, op) = global_op(instruction)?;
+                    Some((def_of(resource)?, op))
+                })
+                .collect();
+            Some((name.as_str(), ops))
+        });
+        Self::from_functions(names, functions)
+    }
+
+    /// Usage of the structs named `names` by functions given by name, with
+    /// the global storage operations in their code by struct definition.
+    fn from_functions<'a>(
+        names: Vec<Identifier>,
+        functions: impl IntoIterator<Item = (&'a str, Vec<(u16, GlobalOp)>)>,
+    ) -> Self {
+        let mut resources = Self {
+            names,
+            ..Default::default()
+        };
+        let mut changers: HashMap<u16, Vec<BTreeSet<u16>>> = HashMap::new();
+        for (name, ops) in functions {
+            let mut borrowed = BTreeSet::new();
+            let mut changed = BTreeSet::new();
+            for (def, op) in ops {
+                match op {
+                    GlobalOp::Exists => {}
+                    GlobalOp::Borrow => {
+                        borrowed.insert(def);
+                    }
+                    GlobalOp::BorrowMut | GlobalOp::MoveFrom => {
+                        borrowed.insert(def);
+                        changed.insert(def);
+                    }
+                    GlobalOp::MoveTo => {
+                        if name != INIT_MODULE {
+                            resources.recreatable.insert(def);
+                        }
+                    }
+                }
+            }
+            for def in changed {
+                changers.entry(def).or_default().push(borrowed.clone());
+            }
+        }
+        for (def, borrowed) in changers {
+            let mut common = borrowed[0].clone();
+            for set in &borrowed[1..] {
+                common.retain(|guard| set.contains(guard));
+            }
+            common.remove(&def);
+            if !common.is_empty() {
+                resources.guards.insert(def, common);
+            }
+        }
+        resources
+    }
+}
+
+/// Presence of a resource at an address while replaying a transaction's
+/// accesses, with the call that last changed it.
+#[derive(Clone, Copy, Debug)]
+struct Presence {
+    exists: bool,
+    changed_by: Option<usize>,
+}
+
+/// Replays the `move_to`/`move_from` of each execution across its sequence
+/// calls, against the presence of resources before it, to find resources
+/// created twice or left where nothing can reach them again. Generic
+/// resources are skipped, as their type arguments aren't traced.
+#[derive(Debug, Default)]
+pub struct ResourceLeakOracle {
+    modules: HashMap<ModuleId, Option<ModuleResources>>,
+}
+
+impl ResourceLeakOracle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Check the traced `accesses` of an execution whose calls are
+    /// `functions`, run against `pre`. Orphaned resources are only looked
+    /// for when the execution `succeeded`, as otherwise nothing was kept.
+    pub fn check(
+        &mut self,
+        pre: &AptosCustomState,
+        accesses: &[GlobalAccess],
+        functions: &[String],
+        succeeded: bool,
+    ) -> Vec<ResourceLeak> {
+        let call_name = |call: usize| {
+            functions
+                .get(call)
+                .or(functions.last())
+                .cloned()
+                .unwrap_or_else(|| "script".to_string())
+        };
+        let mut leaks = Vec::new();
+        let mut presence: HashMap<(ModuleId, u16, AccountAddress), Presence> = HashMap::new();
+        for access in accesses {
+            let (ResourceRef::Definition(def), Some(address)) = (access.resource, access.address) else {
+                continue;
+            };
+            if !matches!(access.op, GlobalOp::MoveTo | GlobalOp::MoveFrom) {
+                continue;
+            }
+            let key = (access.module.clone(), def, address);
+            let before = match presence.get(&key) {
+                Some(presence) => *presence,
+                None => Presence {
+                    exists: self.exists_before(pre, &access.module, def, address),
+                    changed_by: None,
+                },
+            };
+            if access.op == GlobalOp::MoveTo && before.exists {
+                if let Some(resource) = self.resource_name(pre, &access.module, def) {
+                    leaks.push(ResourceLeak::DoubleCreation {
+                        resource,
+                        address,
+                        created_by: before.changed_by.map(call_name),
+                        by: call_name(access.call),
+                    });
+                }
+            }
+            presence.insert(
+                key,
+                Presence {
+                    exists: access.op == GlobalOp::MoveTo,
+                    changed_by: Some(access.call),
+                },
+            );
+        }
+        if !succeeded {
+            return leaks;
+        }
+
+        let removed: Vec<((ModuleId, u16, AccountAddress), usize)> = presence
+            .iter()
+            .filter(|(_, presence)| !presence.exists)
+            .filter_map(|(key, presence)| Some((key.clone(), presence.changed_by?)))
+            .collect();
+        for ((module_id, guard, address), call) in removed {
+            self.load(pre, &module_id);
+            let Some(Some(resources)) = self.modules.get(&module_id) else {
+                continue;
+            };
+            if resources.recreatable.contains(&guard) {
+                continue;
+            }
+            let guarded: Vec<u16> = resources
+                .guards
+                .iter()
+                .filter(|(_, guards)| guards.contains(&guard))
+                .map(|(&def, _)| def)
+                .collect();
+            for def in guarded {
+                let left = match presence.get(&(module_id.clone(), def, address)) {
+                    Some(presence) => presence.exists,
+                    None => self.exists_before(pre, &module_id, def, address),
+                };
+                if !left {
+                    continue;
+                }
+                if let (Some(resource), Some(guard)) = (
+                    self.resource_name(pre, &module_id, def),
+                    self.resource_name(pre, &module_id, guard),
+                ) {
+                    leaks.push(ResourceLeak::Orphaned {
+                        resource,
+                        address,
+                        guard,
+                        removed_by: call_name(call),
+                    });
+                }
+            }
+        }
+        leaks
+    }
+
+    fn load(&mut self, pre: &AptosCustomState, module_id: &ModuleId) {
+        self.modules.entry(module_id.clone()).or_insert_with(|| {
+            let bytes = pre.module_bytes().get(module_id)?;
+            CompiledModule::deserialize(bytes)
+                .ok()
+                .map(|module| ModuleResources::new(&module))
+        });
+    }
+
+    fn struct_tag(&mut self, pre: &AptosCustomState, module_id: &ModuleId, def: u16) -> Option<StructTag> {
+        self.load(pre, module_id);
+        let name = self.modules.get(module_id)?.as_ref()?.names.get(def as usize)?;
+        Some(StructTag {
+            address: *module_id.address(),
+            module: module_id.name().to_owned(),
+            name: name.clone(),
+            type_args: Vec::new(),
+        })
+    }
+
+    fn resource_name(&mut self, pre: &AptosCustomState, module_id: &ModuleId, def: u16) -> Option<String> {
+        self.struct_tag(pre, module_id, def)
+            .map(|tag| tag.to_canonical_string())
+    }
+
+    fn exists_before(
+        &mut self,
+        pre: &AptosCustomState,
+        module_id: &ModuleId,
+        def: u16,
+        address: AccountAddress,
+    ) -> bool {
+        self.struct_tag(pre, module_id, def)
+            .and_then(|tag| StateKey::resource(&address, &tag).ok())
+            .is_some_and(|state_key| pre.get_state_value(&state_key).is_some())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use aptos_move_core_types::account_address::AccountAddress;
+    use aptos_move_core_types::identifier::Identifier;
+    use aptos_move_core_types::language_storage::{ModuleId, StructTag};
+    use aptos_types::state_store::state_key::StateKey;
+    use aptos_types::state_store::state_value::StateValue;
+
+    use super::{ModuleResources, ResourceLeak, ResourceLeakOracle};
+    use crate::conflicts::{GlobalAccess, GlobalOp, ResourceRef};
+    use crate::executor::aptos_custom_state::AptosCustomState;
+
+    const ADMIN: u16 = 0;
+    const VAULT: u16 = 1;
+
+    fn cafe() -> AccountAddress {
+        AccountAddress::from_hex_literal("0xcafe").unwrap()
+    }
+
+    fn module() -> ModuleId {
+        ModuleId::new(cafe(), Identifier::new("m").unwrap())
+    }
+
+    fn tag(def: u16) -> StructTag {
+        StructTag {
+            address: cafe(),
+            module: Identifier::new("m").unwrap(),
+            name: Identifier::new(["Admin", "Vault"][def as usize]).unwrap(),
+            type_args: Vec::new(),
+        }
+    }
+
+    fn resource(def: u16) -> String {
+        tag(def).to_canonical_string()
+    }
+
+    /// `Admin` and `Vault`, created by `init_module` only, where every
+    /// function changing `Vault` borrows `Admin` and `remove_admin` removes
+    /// `Admin`.
+    fn vault_module() -> ModuleResources {
+        ModuleResources::from_functions(
+            vec![Identifier::new("Admin").unwrap(), Identifier::new("Vault").unwrap()],
+            [
+                (
+                    "init_module",
+                    vec![(ADMIN, GlobalOp::MoveTo), (VAULT, GlobalOp::MoveTo)],
+                ),
+                (
+                    "withdraw",
+                    vec![(ADMIN, GlobalOp::Borrow), (VAULT, GlobalOp::BorrowMut)],
+                ),
+                ("close", vec![(ADMIN, GlobalOp::Borrow), (VAULT, GlobalOp::MoveFrom)]),
+                ("remove_admin", vec![(ADMIN, GlobalOp::MoveFrom)]),
+                ("is_open", vec![(VAULT, GlobalOp::Exists)]),
+            ],
+        )
+    }
+
+    fn oracle() -> ResourceLeakOracle {
+        let mut oracle = ResourceLeakOracle::new();
+        oracle.modules.insert(module(), Some(vault_module()));
+        oracle
+    }
+
+    /// The framework state with `defs` at `0xcafe`.
+    fn state_with(defs: &[u16]) -> AptosCustomState {
+        let mut state = AptosCustomState::new_default();
+        for def in defs {
+            let state_key = StateKey::resource(&cafe(), &tag(*def)).unwrap();
+            state.set_state_value(state_key, StateValue::new_legacy(vec![0].into()));
+        }
+        state
+    }
+
+    fn access(call: usize, def: u16, op: GlobalOp) -> GlobalAccess {
+        GlobalAccess {
+            call,
+            module: module(),
+            resource: ResourceRef::Definition(def),
+            address: Some(cafe()),
+            op,
+        }
+    }
+
+    fn functions() -> Vec<String> {
+        vec!["0xcafe::m::remove_admin".to_string(), "0xcafe::m::close".to_string()]
+    }
+
+    #[test]
+    fn guards_are_borrowed_by_every_function_changing_a_resource() {
+        let resources = vault_module();
+        assert_eq!(resources.guards.len(), 1);
+        assert_eq!(resources.guards[&VAULT].iter().collect::<Vec<_>>(), vec![&ADMIN]);
+        assert!(resources.recreatable.is_empty());
+
+        let resources = ModuleResources::from_functions(
+            vec![Identifier::new("Admin").unwrap(), Identifier::new("Vault").unwrap()],
+            [
+                (
+                    "withdraw",
+                    vec![(ADMIN, GlobalOp::Borrow), (VAULT, GlobalOp::BorrowMut)],
+                ),
+                ("deposit", vec![(VAULT, GlobalOp::BorrowMut)]),
+                ("open", vec![(VAULT, GlobalOp::MoveTo)]),
+            ],
+        );
+        assert!(resources.guards.is_empty());
+        assert!(resources.recreatable.contains(&VAULT));
+    }
+
+    #[test]
+    fn removing_a_guard_orphans_what_it_guards() {
+        let pre = state_with(&[ADMIN, VAULT]);
+        let accesses = [access(0, ADMIN, GlobalOp::MoveFrom)];
+        assert_eq!(
+            oracle().check(&pre, &accesses, &functions(), true),
+            vec![ResourceLeak::Orphaned {
+                resource: resource(VAULT),
+                address: cafe(),
+                guard: resource(ADMIN),
+                removed_by: functions()[0].clone(),
+            }]
+        );
+        assert!(oracle().check(&pre, &accesses, &functions(), false).is_empty());
+    }
+
+    #[test]
+    fn nothing_is_orphaned_when_the_guarded_resource_goes_too() {
+        let accesses = [
+            access(0, ADMIN, GlobalOp::MoveFrom),
+            access(1, VAULT, GlobalOp::MoveFrom),
+        ];
+        assert!(oracle()
+            .check(&state_with(&[ADMIN, VAULT]), &accesses, &functions(), true)
+            .is_empty());
+        let accesses = [access(0, ADMIN, GlobalOp::MoveFrom)];
+        assert!(oracle()
+            .check(&state_with(&[ADMIN]), &accesses, &functions(), true)
+            .is_empty());
+    }
+
+    #[test]
+    fn moving_to_a_held_resource_is_a_double_creation() {
+        let accesses = [access(1, VAULT, GlobalOp::MoveTo)];
+        assert_eq!(
+            oracle().check(&state_with(&[VAULT]), &accesses, &functions(), false),
+            vec![ResourceLeak::DoubleCreation {
+                resource: resource(VAULT),
+                address: cafe(),
+                created_by: None,
+                by: functions()[1].clone(),
+            }]
+        );
+        let accesses = [access(0, VAULT, GlobalOp::MoveTo), access(1, VAULT, GlobalOp::MoveTo)];
+        let leaks = oracle().check(&state_with(&[]), &accesses, &functions(), false);
+        assert!(matches!(
+            &leaks[..],
+            [ResourceLeak::DoubleCreation { created_by: Some(call), .. }] if *call == functions()[0]
+        ));
+        let accesses = [access(0, VAULT, GlobalOp::MoveFrom), access(1, VAULT, GlobalOp::MoveTo)];
+        assert!(oracle()
+            .check(&state_with(&[VAULT]), &accesses, &functions(), false)
+            .is_empty());
+    }
+}
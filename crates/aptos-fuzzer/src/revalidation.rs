@@ -0,0 +1,54 @@
+use libafl::corpus::Corpus;
+use libafl::stages::{Restartable, Stage};
+use libafl::state::HasCorpus;
+use libafl::{Error, Evaluator};
+
+use crate::input::AptosFuzzerInput;
+use crate::state::AptosFuzzerState;
+
+/// Re-runs the whole corpus once the target state changed under it, e.g.
+/// after `--watch` republished the modules, when
+/// `AptosFuzzerState::request_revalidation` asks for it. Every entry is
+/// added again, so the feedbacks see its coverage and attach fresh metadata,
+/// and entries the VM now rejects before running any of them are dropped.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RevalidationStage;
+
+impl<E, EM, Z> Stage<E, EM, AptosFuzzerState, Z> for RevalidationStage
+where
+    Z: Evaluator<E, EM, AptosFuzzerInput, AptosFuzzerState>,
+{
+    fn perform(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut AptosFuzzerState,
+        manager: &mut EM,
+    ) -> Result<(), Error> {
+        if !state.take_revalidation_request() {
+            return Ok(());
+        }
+        let (mut kept, mut dropped) = (0, 0);
+        for input in state.take_initial_inputs() {
+            let id = fuzzer.add_input(state, executor, manager, input)?;
+            if state.last_rejected() {
+                state.corpus_mut().remove(id)?;
+                dropped += 1;
+            } else {
+                kept += 1;
+            }
+        }
+        state.set_revalidated(kept, dropped);
+        Ok(())
+    }
+}
+
+impl Restartable<AptosFuzzerState> for RevalidationStage {
+    fn should_restart(&mut self, _state: &mut AptosFuzzerState) -> Result<bool, Error> {
+        Ok(true)
+    }
+
+    fn clear_progress(&mut self, _state: &mut AptosFuzzerState) -> Result<(), Error> {
+        Ok(())
+    }
+}
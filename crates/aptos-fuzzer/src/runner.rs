@@ -0,0 +1,1074 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, bail, Context};
+use libafl::corpus::{Corpus, Testcase};
+use libafl::events::SimpleEventManager;
+use libafl::feedbacks::{EagerOrFeedback, MaxMapFeedback, StateInitializer};
+use libafl::fuzzer::{Fuzzer, HasFeedback};
+use libafl::monitors::NopMonitor;
+use libafl::stages::StdMutationalStage;
+use libafl::state::{HasCorpus, HasExecutions, HasSolutions};
+use libafl::{Evaluator, StdFuzzer};
+use libafl_bolts::current_nanos;
+use libafl_bolts::tuples::tuple_list;
+use log::debug;
+
+#[cfg(feature = "concolic")]
+use crate::abort_target::{AbortSearch, AbortTarget};
+use crate::arg_constraint::ArgConstraint;
+use crate::checkpoint::{Checkpointer, Resumed};
+use crate::config::{
+    CampaignConfig, CorpusConfig, DetectorConfig, ExecutionConfig, FuzzerConfig, MutatorConfig, ObjectiveConfig,
+    SchedulerConfig, TargetConfig,
+};
+use crate::executor::aptos_custom_state::AptosCustomState;
+use crate::executor::{DifferentialConfig, ExecutionBudget, ForkedStateView};
+use crate::fixture::StateFixture;
+use crate::history::load_transactions;
+use crate::oracle::{
+    AssetConservationOracle, CoinConservationOracle, ResourceNeverDeletedOracle, ReturnProperty, RoundTrip,
+    ViewMonotonicity,
+};
+use crate::package::{build_package, PackageBuildOptions, DEFAULT_COMPILER};
+use crate::revalidation::RevalidationStage;
+use crate::scheduler::{FairScheduler, DEFAULT_MIN_FUNCTION_SHARE};
+use crate::sender::{PinnedSender, SenderPolicy};
+#[cfg(feature = "concolic")]
+use crate::solver::SolverConfig;
+use crate::static_analysis::{
+    find_unreachable_functions, run_static_analysis_with_sources, run_whole_program_analysis, StaticAnalysisFinding,
+};
+#[cfg(feature = "concolic")]
+use crate::symbolic::SymbolicDivergence;
+use crate::sync::CorpusSync;
+use crate::upgrade::{ModuleUpgrade, PendingUpgrade, DEFAULT_UPGRADE_AFTER};
+use crate::watch::ModuleWatcher;
+use crate::{
+    AbortCodeNoveltyFeedback, AbortCodeObjective, AllowedAbort, AptosFuzzerInput, AptosFuzzerMutator, AptosFuzzerState,
+    AptosMoveExecutor, BranchDistanceFeedback, DifferentialObjective, ExecutionBudgetObjective,
+    ExecutionMetadataFeedback, InvariantOracleObjective, MutationRates, NewEventTypeFeedback, ResourceLeakObjective,
+    ResourceTouchFeedback, RuntimeDetector, RuntimeDetectorConfig, RuntimeIssue, RuntimeIssueKind,
+    RuntimeIssueObjective, Severity, ShiftOverflowObjective, Suppression, Violation, VmStatusNoveltyFeedback, MAP_SIZE,
+};
+
+/// Havoc stack size when the config sets none, as for the CLI
+const DEFAULT_MAX_STACK_POW: u32 = 5;
+/// Checkpoint directory when the config sets none, as for the CLI
+const DEFAULT_CHECKPOINT_DIR: &str = "checkpoint";
+/// How often a `CampaignMonitor` is shown the campaign's progress
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(500);
+
+type InputCallback = Box<dyn FnMut(&AptosFuzzerInput)>;
+
+/// Sets up and runs a fuzzing campaign, for the CLI and for tools and tests
+/// that embed the fuzzer:
+///
+/// ```ignore
+/// let report = FuzzerBuilder::new("build/pool/bytecode_modules")
+///     .with_max_executions(10_000)
+///     .on_solution(|input| println!("found {}", input.describe()))
+///     .run()?;
+/// ```
+///
+/// Settings come from a `FuzzerConfig`; the campaign stops on its timeout
+/// and stop conditions, after `with_max_executions`, or when the stop flag
+/// is set. `build` sets up the targets without fuzzing them, to inspect the
+/// `Campaign` or run it with a `CampaignMonitor`.
+pub struct FuzzerBuilder {
+    config: FuzzerConfig,
+    aptos_state: Option<AptosCustomState>,
+    compiler: PathBuf,
+    instruction_profiling: bool,
+    #[cfg(feature = "concolic")]
+    concolic_self_check: bool,
+    #[cfg(feature = "concolic")]
+    abort_target: Option<AbortTarget>,
+    max_executions: Option<u64>,
+    stop: Option<Arc<AtomicBool>>,
+    on_new_coverage: Option<InputCallback>,
+    on_solution: Option<InputCallback>,
+    on_runtime_issue: Option<Box<dyn FnMut(&RuntimeIssue)>>,
+    detectors: Vec<Box<dyn RuntimeDetector>>,
+}
+
+/// Outcome of a campaign run by `FuzzerBuilder::run` or `Campaign::run`.
+pub struct CampaignReport {
+    /// Why fuzzing stopped, `None` if there was nothing to fuzz
+    pub stop_reason: Option<StopReason>,
+    pub executions: u64,
+    pub corpus_size: usize,
+    pub solutions: Vec<AptosFuzzerInput>,
+    pub static_findings: Vec<StaticAnalysisFinding>,
+    /// Final state, e.g. to look up the execution path of a solution
+    pub state: AptosFuzzerState,
+    /// Executor, e.g. to replay solutions against the final state
+    pub executor: AptosMoveExecutor,
+}
+
+/// Why a campaign stopped.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StopReason {
+    /// The stop flag was set
+    Interrupted,
+    Timeout,
+    MaxExecutions,
+    /// `stop_after_objectives` solutions were found, not counting those
+    /// resumed from a checkpoint
+    Objectives(usize),
+    /// An input crashed the VM under `stop_on_first_crash`
+    Crash,
+    /// No new edges or solutions for `plateau_seconds`
+    Plateau(Duration),
+    /// Fuzzing failed, see the preceding `CampaignEvent::Failed`
+    Failed,
+}
+
+/// Something that happened to a running campaign, for a `CampaignMonitor`.
+#[derive(Debug)]
+pub enum CampaignEvent<'a> {
+    SeedsLoaded {
+        source: &'a Path,
+        count: usize,
+    },
+    /// Fuzzing starts from this many initial inputs
+    Started {
+        inputs: usize,
+    },
+    Resumed {
+        dir: &'a Path,
+        resumed: Resumed,
+    },
+    /// The watched targets changed and were reloaded
+    Reloaded {
+        root: &'a Path,
+        dropped: usize,
+        added: usize,
+    },
+    /// The concolic self-check found the symbolic stack disagreeing with the
+    /// concrete one, reported once per site
+    #[cfg(feature = "concolic")]
+    SymbolicDivergence(&'a SymbolicDivergence),
+    /// The corpus was re-run against the changed target state, dropping the
+    /// entries that no longer execute
+    Revalidated {
+        kept: usize,
+        dropped: usize,
+    },
+    /// The targets were upgraded over the state `committed` corpus entries
+    /// built
+    Upgraded {
+        modules_dir: &'a Path,
+        committed: usize,
+        upgrade: &'a ModuleUpgrade,
+        violations: &'a [Violation],
+    },
+    /// A step failed; the campaign goes on unless it stops with
+    /// `StopReason::Failed` next
+    Failed(&'a anyhow::Error),
+    Stopped(StopReason),
+}
+
+/// Follows a campaign run by `Campaign::run_with`, e.g. to draw a dashboard.
+pub trait CampaignMonitor {
+    /// Called every `PROGRESS_INTERVAL` while fuzzing and once when it stops.
+    fn progress(&mut self, _state: &AptosFuzzerState, _executor: &AptosMoveExecutor) {}
+
+    fn event(&mut self, _event: &CampaignEvent) {}
+}
+
+impl CampaignMonitor for () {}
+
+impl FuzzerBuilder {
+    pub fn new(modules_dir: impl Into<PathBuf>) -> Self {
+        let mut config = FuzzerConfig::default();
+        config.target.modules_dir = Some(modules_dir.into());
+        Self::from_config(config)
+    }
+
+    pub fn from_config(config: FuzzerConfig) -> Self {
+        Self {
+            config,
+            aptos_state: None,
+            compiler: PathBuf::from(DEFAULT_COMPILER),
+            instruction_profiling: false,
+            #[cfg(feature = "concolic")]
+            concolic_self_check: false,
+            #[cfg(feature = "concolic")]
+            abort_target: None,
+            max_executions: None,
+            stop: None,
+            on_new_coverage: None,
+            on_solution: None,
+            on_runtime_issue: None,
+            detectors: Vec::new(),
+        }
+    }
+
+    /// Replace the settings; the modules directory is kept unless `config`
+    /// sets one.
+    pub fn with_config(mut self, mut config: FuzzerConfig) -> Self {
+        if config.target.modules_dir.is_none() {
+            config.target.modules_dir = self.config.target.modules_dir.take();
+        }
+        self.config = config;
+        self
+    }
+
+    /// Fuzz on top of `aptos_state` instead of a fresh or forked one. Targets
+    /// reloaded by `target.watch` start over from a fresh or forked one.
+    pub fn with_aptos_state(mut self, aptos_state: AptosCustomState) -> Self {
+        self.aptos_state = Some(aptos_state);
+        self
+    }
+
+    /// `aptos` CLI the package is compiled with.
+    pub fn with_compiler(mut self, compiler: impl Into<PathBuf>) -> Self {
+        self.compiler = compiler.into();
+        self
+    }
+
+    /// Count executed instructions per function, see
+    /// `AptosFuzzerState::hot_functions`.
+    pub fn with_instruction_profiling(mut self, enabled: bool) -> Self {
+        self.instruction_profiling = enabled;
+        self
+    }
+
+    /// Check the concolic engine against the VM on every instruction.
+    #[cfg(feature = "concolic")]
+    pub fn with_concolic_self_check(mut self, enabled: bool) -> Self {
+        self.concolic_self_check = enabled;
+        self
+    }
+
+    /// Steer the concolic engine towards `target`, see `AbortSearch`.
+    #[cfg(feature = "concolic")]
+    pub fn with_abort_target(mut self, target: AbortTarget) -> Self {
+        self.abort_target = Some(target);
+        self
+    }
+
+    pub fn with_max_executions(mut self, max_executions: u64) -> Self {
+        self.max_executions = Some(max_executions);
+        self
+    }
+
+    /// Stop the campaign once `stop` is set, e.g. from a signal handler.
+    pub fn with_stop_flag(mut self, stop: Arc<AtomicBool>) -> Self {
+        self.stop = Some(stop);
+        self
+    }
+
+    /// Called with each input added to the corpus.
+    pub fn on_new_coverage(mut self, callback: impl FnMut(&AptosFuzzerInput) + 'static) -> Self {
+        self.on_new_coverage = Some(Box::new(callback));
+        self
+    }
+
+    pub fn on_solution(mut self, callback: impl FnMut(&AptosFuzzerInput) + 'static) -> Self {
+        self.on_solution = Some(Box::new(callback));
+        self
+    }
+
+    pub fn on_runtime_issue(mut self, callback: impl FnMut(&RuntimeIssue) + 'static) -> Self {
+        self.on_runtime_issue = Some(Box::new(callback));
+        self
+    }
+
+    /// Run `detector` on every execution, alongside the built-in ones.
+    pub fn with_runtime_detector(mut self, detector: Box<dyn RuntimeDetector>) -> Self {
+        self.detectors.push(detector);
+        self
+    }
+
+    /// Set up the targets: build the package, publish the modules into the
+    /// base state, run the initializers and configure the executor.
+    pub fn build(mut self) -> anyhow::Result<Campaign> {
+        let FuzzerConfig {
+            target,
+            campaign,
+            scheduler,
+            corpus,
+            execution,
+            mutator,
+            detectors,
+            objectives,
+        } = std::mem::take(&mut self.config);
+        if target.watch == Some(true) && target.upgrade_to.is_some() {
+            bail!("watching the targets and upgrading them can't be combined");
+        }
+        let (modules_dir, package_dirs) = target_modules(&target, &self.compiler)?;
+        let map_size = campaign.map_size.unwrap_or(MAP_SIZE);
+
+        execution.backend.unwrap_or_default().ensure_available()?;
+        let mut executor = AptosMoveExecutor::with_map_size(map_size);
+        executor.set_execution_budget(ExecutionBudget {
+            timeout: execution.timeout_ms.filter(|&ms| ms > 0).map(Duration::from_millis),
+            max_instructions: execution.max_instructions.filter(|&max| max > 0),
+        });
+        executor.set_invariant_functions_enabled(objectives.invariant_functions != Some(false));
+        executor.set_view_functions_enabled(objectives.view_functions == Some(true));
+        executor.set_access_control_enabled(objectives.access_control == Some(true));
+        executor.set_instruction_profiling(self.instruction_profiling);
+        let pinned_senders = execution
+            .pinned_senders
+            .iter()
+            .map(|pinned| {
+                pinned
+                    .parse::<PinnedSender>()
+                    .with_context(|| format!("invalid pinned sender {}", pinned))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let mut runtime_detectors = RuntimeDetectorConfig::default();
+        for name in &detectors.runtime_disabled {
+            runtime_detectors.disable(name)?;
+        }
+        if let Some(threshold) = detectors.infinite_loop_threshold {
+            runtime_detectors.infinite_loop_threshold = threshold;
+        }
+        if let Some(instructions) = detectors.unbounded_loop_instructions {
+            runtime_detectors.unbounded_loop_instructions = instructions;
+        }
+        runtime_detectors.fit_instruction_budget(execution.max_instructions.filter(|&max| max > 0));
+        executor.set_runtime_detector_config(&runtime_detectors);
+        for detector in self.detectors.drain(..) {
+            executor.register_runtime_detector(detector);
+        }
+        if let Some(hook) = self.on_runtime_issue.take() {
+            executor.set_runtime_issue_hook(hook);
+        }
+        let allowed_aborts = objectives
+            .allow_aborts
+            .iter()
+            .map(|abort| {
+                abort
+                    .parse::<AllowedAbort>()
+                    .with_context(|| format!("invalid allowed abort {}", abort))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let suppressions = detectors
+            .suppress
+            .iter()
+            .map(|entry| {
+                entry
+                    .parse::<Suppression>()
+                    .with_context(|| format!("invalid suppression {}", entry))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        let aptos_state = base_aptos_state(&target, &modules_dir, self.aptos_state.take())?;
+        let mut state = AptosFuzzerState::with_aptos_state(modules_dir, map_size, aptos_state);
+        if let Some((source_maps, sources)) = &package_dirs {
+            load_package_sources(&mut state, source_maps, sources)?;
+        }
+        state.set_sender_policy(SenderPolicy::new(execution.sender.unwrap_or_default(), pinned_senders));
+        let rng_seed = campaign.rng_seed.unwrap_or_else(current_nanos);
+        state.set_rng_seed(rng_seed);
+        #[cfg(feature = "concolic")]
+        {
+            let solver = SolverConfig::default();
+            state.set_solver_config(SolverConfig {
+                query_timeout: execution
+                    .solver_timeout_ms
+                    .map_or(solver.query_timeout, Duration::from_millis),
+                queries_per_iteration: execution
+                    .solver_queries_per_iteration
+                    .unwrap_or(solver.queries_per_iteration),
+            });
+        }
+        for coin_type in &objectives.conserve_coins {
+            state.register_oracle(Box::new(CoinConservationOracle::from_type_str(coin_type)?));
+        }
+        for resource in &objectives.never_delete {
+            state.register_oracle(Box::new(ResourceNeverDeletedOracle::from_type_str(resource)?));
+        }
+        for property in &objectives.return_properties {
+            let parsed = property
+                .parse::<ReturnProperty>()
+                .with_context(|| format!("invalid return property {}", property))?;
+            state.add_return_property(parsed);
+        }
+        for round_trip in &objectives.round_trips {
+            let parsed = round_trip
+                .parse::<RoundTrip>()
+                .with_context(|| format!("invalid round trip {}", round_trip))?;
+            state
+                .add_round_trip(parsed)
+                .with_context(|| format!("invalid round trip {}", round_trip))?;
+        }
+        for property in &objectives.monotonic_views {
+            let parsed = property
+                .parse::<ViewMonotonicity>()
+                .with_context(|| format!("invalid monotonic view {}", property))?;
+            state.add_view_property(parsed);
+        }
+        for constraint in &target.arg_constraints {
+            let parsed = constraint
+                .parse::<ArgConstraint>()
+                .with_context(|| format!("invalid argument constraint {}", constraint))?;
+            state
+                .add_arg_constraint(parsed)
+                .with_context(|| format!("invalid argument constraint {}", constraint))?;
+        }
+        #[cfg(feature = "concolic")]
+        {
+            executor.set_arg_constraints(state.arg_constraints().to_vec());
+            executor.set_branch_flips(execution.solve_branches.unwrap_or(0));
+            executor.set_concolic_self_check(self.concolic_self_check);
+            if let Some(target) = &self.abort_target {
+                let guards = state
+                    .abort_guards(target)
+                    .with_context(|| format!("cannot solve for abort {}", target))?;
+                executor.set_abort_guards(Some(guards));
+                state.set_abort_search(Some(AbortSearch::new()));
+            }
+        }
+        if objectives.asset_conservation != Some(false) {
+            let mut oracle = AssetConservationOracle::new();
+            for issuer in &objectives.asset_issuers {
+                oracle
+                    .add_issuer(issuer)
+                    .with_context(|| format!("invalid asset issuer {}", issuer))?;
+            }
+            state.enable_asset_conservation(oracle);
+        }
+        if objectives.signer_ownership == Some(true) {
+            state.enable_signer_ownership();
+        }
+        if objectives.resource_leaks == Some(true) {
+            state.enable_resource_leaks();
+        }
+        prepare_targets(&target, &execution, &mut executor, &mut state)?;
+
+        Ok(Campaign {
+            executor,
+            state,
+            target,
+            campaign,
+            scheduler,
+            corpus,
+            execution,
+            mutator,
+            detectors,
+            objectives,
+            compiler: self.compiler,
+            allowed_aborts,
+            suppressions,
+            rng_seed,
+            max_executions: self.max_executions,
+            stop: self.stop,
+            on_new_coverage: self.on_new_coverage,
+            on_solution: self.on_solution,
+            static_findings: None,
+        })
+    }
+
+    /// Build the campaign, analyze the targets and fuzz them.
+    pub fn run(self) -> anyhow::Result<CampaignReport> {
+        self.build()?.run()
+    }
+}
+
+/// A campaign whose targets are set up, ready to be analyzed and fuzzed.
+pub struct Campaign {
+    executor: AptosMoveExecutor,
+    state: AptosFuzzerState,
+    target: TargetConfig,
+    campaign: CampaignConfig,
+    scheduler: SchedulerConfig,
+    corpus: CorpusConfig,
+    execution: ExecutionConfig,
+    mutator: MutatorConfig,
+    detectors: DetectorConfig,
+    objectives: ObjectiveConfig,
+    compiler: PathBuf,
+    allowed_aborts: Vec<AllowedAbort>,
+    suppressions: Vec<Suppression>,
+    rng_seed: u64,
+    max_executions: Option<u64>,
+    stop: Option<Arc<AtomicBool>>,
+    on_new_coverage: Option<InputCallback>,
+    on_solution: Option<InputCallback>,
+    static_findings: Option<Vec<StaticAnalysisFinding>>,
+}
+
+impl Campaign {
+    pub fn rng_seed(&self) -> u64 {
+        self.rng_seed
+    }
+
+    pub fn state(&self) -> &AptosFuzzerState {
+        &self.state
+    }
+
+    /// The executor and the set-up state, e.g. to replay an input before
+    /// fuzzing.
+    pub fn parts_mut(&mut self) -> (&mut AptosMoveExecutor, &mut AptosFuzzerState) {
+        (&mut self.executor, &mut self.state)
+    }
+
+    /// Add `seeds` to the initial corpus, alongside the configured ones.
+    pub fn add_seeds(&mut self, seeds: impl IntoIterator<Item = AptosFuzzerInput>) -> anyhow::Result<()> {
+        for seed in seeds {
+            self.state.corpus_mut().add(Testcase::new(seed))?;
+        }
+        Ok(())
+    }
+
+    /// Run static analysis over the targets, once, keeping the findings
+    /// neither disabled nor suppressed.
+    pub fn analyze(&mut self) -> &[StaticAnalysisFinding] {
+        if self.static_findings.is_none() {
+            let state = &self.state;
+            let mut findings = if self.detectors.enabled == Some(false) {
+                Vec::new()
+            } else if self.detectors.whole_program == Some(true) {
+                run_whole_program_analysis(state.aptos_state(), state.target_modules(), state.source_locator())
+            } else {
+                run_static_analysis_with_sources(state.aptos_state(), state.target_modules(), state.source_locator())
+            };
+            findings.retain(|finding| {
+                !self.detectors.disabled.iter().any(|kind| kind == finding.kind.as_str()) &&
+                    !self
+                        .suppressions
+                        .iter()
+                        .any(|suppression| suppression.matches_finding(finding))
+            });
+            for finding in &mut findings {
+                if let Some(severity) = self.detectors.severities.get(finding.kind.as_str()) {
+                    finding.severity = *severity;
+                }
+            }
+            self.state.set_static_findings(findings.clone());
+            self.static_findings = Some(findings);
+        }
+        self.static_findings.as_deref().unwrap_or_default()
+    }
+
+    /// Fuzz the targets until a stop condition holds.
+    pub fn run(self) -> anyhow::Result<CampaignReport> {
+        self.run_with(&mut ())
+    }
+
+    /// Fuzz the targets until a stop condition holds, showing `monitor` the
+    /// progress. Fails only on a broken setup, e.g. an unreadable seed; later
+    /// failures are reported to `monitor` and end the campaign with
+    /// `StopReason::Failed` or, if they don't affect fuzzing, not at all.
+    pub fn run_with(mut self, monitor: &mut impl CampaignMonitor) -> anyhow::Result<CampaignReport> {
+        let static_findings = self.analyze().to_vec();
+        let Campaign {
+            mut executor,
+            mut state,
+            target,
+            campaign,
+            scheduler,
+            corpus,
+            execution,
+            mutator,
+            detectors,
+            objectives,
+            compiler,
+            allowed_aborts,
+            suppressions,
+            rng_seed,
+            max_executions,
+            stop,
+            mut on_new_coverage,
+            mut on_solution,
+            ..
+        } = self;
+
+        let runtime_objective_severity = (detectors.runtime_issues_objective != Some(false))
+            .then(|| detectors.runtime_objective_severity.unwrap_or(Severity::High));
+        let runtime_issue_objective = detectors.severities.iter().fold(
+            RuntimeIssueObjective::with_min_severity(runtime_objective_severity).with_suppressions(suppressions),
+            |objective, (kind, severity)| objective.with_severity(RuntimeIssueKind::from_name(kind), *severity),
+        );
+        let mut feedback = EagerOrFeedback::new(
+            MaxMapFeedback::new(executor.pc_observer()),
+            EagerOrFeedback::new(
+                BranchDistanceFeedback::new(),
+                EagerOrFeedback::new(
+                    AbortCodeNoveltyFeedback::new(),
+                    EagerOrFeedback::new(
+                        NewEventTypeFeedback::new(),
+                        EagerOrFeedback::new(
+                            ResourceTouchFeedback::new(),
+                            EagerOrFeedback::new(VmStatusNoveltyFeedback::new(), ExecutionMetadataFeedback::new()),
+                        ),
+                    ),
+                ),
+            ),
+        );
+        let objective = EagerOrFeedback::new(
+            ShiftOverflowObjective::new(),
+            EagerOrFeedback::new(
+                AbortCodeObjective::with_allowed_aborts(allowed_aborts),
+                EagerOrFeedback::new(
+                    ExecutionBudgetObjective::new(objectives.budget == Some(true)),
+                    EagerOrFeedback::new(
+                        InvariantOracleObjective::new(),
+                        EagerOrFeedback::new(
+                            runtime_issue_objective,
+                            EagerOrFeedback::new(
+                                DifferentialObjective::new(),
+                                EagerOrFeedback::new(ResourceLeakObjective::new(), ExecutionMetadataFeedback::new()),
+                            ),
+                        ),
+                    ),
+                ),
+            ),
+        );
+        let mut mgr = SimpleEventManager::new(NopMonitor::new());
+
+        let _ = feedback.init_state(&mut state);
+        if let Some(dir) = &corpus.seeds_dir {
+            let seeds = load_seeds(dir)?;
+            monitor.event(&CampaignEvent::SeedsLoaded {
+                source: dir,
+                count: seeds.len(),
+            });
+            for seed in seeds {
+                state.corpus_mut().add(Testcase::new(seed))?;
+            }
+        }
+        if let Some(path) = &corpus.transactions {
+            let seeds = load_transactions(path, &mut state)?;
+            monitor.event(&CampaignEvent::SeedsLoaded {
+                source: path,
+                count: seeds.len(),
+            });
+            for seed in seeds {
+                state.corpus_mut().add(Testcase::new(seed))?;
+            }
+        }
+        if state.corpus().count() == 0 {
+            return Ok(CampaignReport {
+                stop_reason: None,
+                executions: 0,
+                corpus_size: 0,
+                solutions: Vec::new(),
+                static_findings,
+                state,
+                executor,
+            });
+        }
+
+        let defaults = MutationRates::default();
+        let rates = MutationRates {
+            block_context: mutator.block_context_rate.unwrap_or(defaults.block_context),
+            sender: mutator.sender_rate.unwrap_or(defaults.sender),
+            crossover: mutator.crossover_rate.unwrap_or(defaults.crossover),
+            interesting_value: mutator.interesting_value_rate.unwrap_or(defaults.interesting_value),
+        };
+        let mut fuzzer = StdFuzzer::new(
+            FairScheduler::new(scheduler.fairness.unwrap_or_default())
+                .with_min_share(scheduler.min_function_share.unwrap_or(DEFAULT_MIN_FUNCTION_SHARE)),
+            feedback,
+            objective,
+        );
+        let mut mutational =
+            AptosFuzzerMutator::with_max_stack_pow(mutator.max_stack_pow.unwrap_or(DEFAULT_MAX_STACK_POW))
+                .with_rates(rates)
+                .with_vm_fuzzing(mutator.fuzz_vm == Some(true));
+        if let Some(max_vector_len) = mutator.max_vector_len {
+            mutational = mutational.with_max_vector_len(max_vector_len);
+        }
+        if let Some(max_sequence_len) = mutator.max_sequence_len {
+            mutational = mutational.with_max_sequence_len(max_sequence_len);
+        }
+        let mut stages = tuple_list!(StdMutationalStage::new(mutational), RevalidationStage);
+        let initial_inputs = if mutator.fuzz_publish == Some(true) {
+            state.take_publish_inputs()
+        } else {
+            state.take_initial_inputs()
+        };
+        monitor.event(&CampaignEvent::Started {
+            inputs: initial_inputs.len(),
+        });
+        for input in initial_inputs {
+            fuzzer.add_input(&mut state, &mut executor, &mut mgr, input)?;
+        }
+
+        let mut checkpointer = corpus
+            .checkpoint_interval
+            .map(|secs| {
+                let dir = corpus
+                    .checkpoint_dir
+                    .clone()
+                    .unwrap_or_else(|| PathBuf::from(DEFAULT_CHECKPOINT_DIR));
+                Checkpointer::new(dir, Duration::from_secs(secs))
+            })
+            .transpose()
+            .context("failed to set up checkpoint directory")?;
+        // Crashes and solutions replayed from the checkpoint were found by
+        // an earlier run
+        let crashes_before_resume = state.crash_paths.len();
+        let solutions_before_resume = state.solutions().count();
+        if let Some(checkpointer) = checkpointer.as_mut() {
+            let resumed = checkpointer
+                .resume(&mut fuzzer, &mut executor, &mut mgr, &mut state)
+                .context("failed to resume from checkpoint")?;
+            if let Some(resumed) = resumed {
+                monitor.event(&CampaignEvent::Resumed {
+                    dir: checkpointer.dir(),
+                    resumed,
+                });
+            }
+        }
+        let resumed_crashes = state.crash_paths.len() - crashes_before_resume;
+        let resumed_solutions = state.solutions().count() - solutions_before_resume;
+        let mut sync = corpus
+            .sync_dir
+            .clone()
+            .map(|dir| CorpusSync::new(dir, corpus.sync_name.clone().unwrap_or_else(|| sync_name(rng_seed))))
+            .transpose()
+            .context("failed to set up sync directory")?;
+        let mut watcher = match (&target.package_path, &target.modules_dir) {
+            _ if target.watch != Some(true) => None,
+            (Some(path), _) => Some(ModuleWatcher::package(path.clone())),
+            (None, dir) => dir.clone().map(ModuleWatcher::modules),
+        };
+        let mut pending_upgrade = target.upgrade_to.clone().map(|dir| {
+            PendingUpgrade::new(
+                dir,
+                Duration::from_secs(target.upgrade_after.unwrap_or(DEFAULT_UPGRADE_AFTER)),
+            )
+        });
+
+        let start_time = Instant::now();
+        let timeout = campaign.timeout.filter(|&secs| secs > 0).map(Duration::from_secs);
+        let plateau = campaign
+            .plateau_seconds
+            .filter(|&secs| secs > 0)
+            .map(Duration::from_secs);
+        let covered_edges =
+            |state: &AptosFuzzerState| state.cumulative_coverage().iter().filter(|&&hit| hit > 0).count();
+        let mut last_progress = (covered_edges(&state), state.solutions().count());
+        let mut last_progress_time = Instant::now();
+        let mut last_report_time = Instant::now();
+        let mut corpus_seen = state.corpus().count();
+        let mut solutions_seen = state.solutions().count();
+        let stop_reason = loop {
+            if stop.as_ref().is_some_and(|stop| stop.load(Ordering::SeqCst)) {
+                break StopReason::Interrupted;
+            }
+            if timeout.is_some_and(|timeout| start_time.elapsed() >= timeout) {
+                break StopReason::Timeout;
+            }
+            if max_executions.is_some_and(|max| *state.executions() >= max) {
+                break StopReason::MaxExecutions;
+            }
+            #[cfg(feature = "concolic")]
+            state.solver_mut().begin_iteration();
+            if let Err(e) = fuzzer.fuzz_one(&mut stages, &mut executor, &mut state, &mut mgr) {
+                monitor.event(&CampaignEvent::Failed(&anyhow!("fuzzing failed: {}", e)));
+                break StopReason::Failed;
+            }
+            #[cfg(feature = "concolic")]
+            for divergence in state.take_symbolic_divergences() {
+                monitor.event(&CampaignEvent::SymbolicDivergence(&divergence));
+            }
+            if let Some((kept, dropped)) = state.take_revalidated() {
+                monitor.event(&CampaignEvent::Revalidated { kept, dropped });
+            }
+            // Execute the inputs solved for the abort target, which also
+            // checks that they raise it
+            #[cfg(feature = "concolic")]
+            {
+                let witnesses = state
+                    .abort_search_mut()
+                    .map(AbortSearch::take_witnesses)
+                    .unwrap_or_default();
+                let executed = witnesses
+                    .into_iter()
+                    .try_for_each(|witness| fuzzer.add_input(&mut state, &mut executor, &mut mgr, witness).map(drop));
+                if let Err(e) = executed {
+                    let e = anyhow!("failed to execute a solved input: {}", e);
+                    monitor.event(&CampaignEvent::Failed(&e));
+                    break StopReason::Failed;
+                }
+            }
+            // and those solved to flip branches of the worklist, kept if they
+            // do
+            #[cfg(feature = "concolic")]
+            {
+                let witnesses = state.branch_coverage_mut().take_witnesses();
+                let executed = witnesses.iter().try_for_each(|witness| {
+                    fuzzer
+                        .evaluate_input(&mut state, &mut executor, &mut mgr, witness)
+                        .map(drop)
+                });
+                if let Err(e) = executed {
+                    let e = anyhow!("failed to execute a solved input: {}", e);
+                    monitor.event(&CampaignEvent::Failed(&e));
+                    break StopReason::Failed;
+                }
+            }
+
+            if let Some(watcher) = watcher.as_mut().filter(|watcher| watcher.changed()) {
+                match reload_targets(&target, &execution, &compiler, &mut executor, &mut state) {
+                    Ok((dropped, added)) => {
+                        monitor.event(&CampaignEvent::Reloaded {
+                            root: watcher.root(),
+                            dropped,
+                            added,
+                        });
+                        // Edge ids changed, so coverage starts over from the
+                        // migrated corpus, re-run by the revalidation stage
+                        let _ = fuzzer.feedback_mut().init_state(&mut state);
+                    }
+                    Err(e) => {
+                        let e = e.context(format!("failed to reload {}", watcher.root().display()));
+                        monitor.event(&CampaignEvent::Failed(&e));
+                    }
+                }
+            }
+            if let Some(upgrade) = pending_upgrade.take_if(|upgrade| upgrade.is_due()) {
+                let committed = executor.commit_corpus(&mut state);
+                let report = state.upgrade_modules(upgrade.modules_dir().to_path_buf());
+                setup_targets(&target, &execution, &mut executor, &mut state);
+                let violations = executor.check_invariants(&mut state);
+                monitor.event(&CampaignEvent::Upgraded {
+                    modules_dir: upgrade.modules_dir(),
+                    committed,
+                    upgrade: &report,
+                    violations: &violations,
+                });
+                // As for a reload, coverage starts over from the migrated
+                // corpus, re-run against the upgraded state
+                let _ = fuzzer.feedback_mut().init_state(&mut state);
+            }
+            if let Some(sync) = sync.as_mut().filter(|sync| sync.is_due()) {
+                if let Err(e) = sync.sync(&mut fuzzer, &mut executor, &mut mgr, &mut state) {
+                    let e = e.context(format!("failed to sync with {}", sync.dir().display()));
+                    monitor.event(&CampaignEvent::Failed(&e));
+                }
+            }
+            if let Some(checkpointer) = checkpointer.as_mut().filter(|checkpointer| checkpointer.is_due()) {
+                if let Err(e) = checkpointer.write(&state) {
+                    monitor.event(&CampaignEvent::Failed(&e.context("failed to write checkpoint")));
+                }
+            }
+            if let Some(callback) = on_new_coverage.as_mut() {
+                for id in state.corpus().ids().skip(corpus_seen).collect::<Vec<_>>() {
+                    if let Ok(input) = state.corpus().cloned_input_for_id(id) {
+                        callback(&input);
+                    }
+                }
+            }
+            corpus_seen = state.corpus().count();
+            if let Some(callback) = on_solution.as_mut() {
+                for id in state.solutions().ids().skip(solutions_seen).collect::<Vec<_>>() {
+                    if let Ok(input) = state.solutions().cloned_input_for_id(id) {
+                        callback(&input);
+                    }
+                }
+            }
+            solutions_seen = state.solutions().count();
+
+            let solutions_found = solutions_seen - resumed_solutions;
+            if campaign
+                .stop_after_objectives
+                .is_some_and(|limit| solutions_found >= limit)
+            {
+                break StopReason::Objectives(solutions_found);
+            }
+            if campaign.stop_on_first_crash == Some(true) && state.crash_paths.len() > resumed_crashes {
+                break StopReason::Crash;
+            }
+            if last_report_time.elapsed() >= PROGRESS_INTERVAL {
+                monitor.progress(&state, &executor);
+                last_report_time = Instant::now();
+                if let Some(plateau) = plateau {
+                    let progress = (covered_edges(&state), solutions_seen);
+                    if progress != last_progress {
+                        last_progress = progress;
+                        last_progress_time = Instant::now();
+                    } else if last_progress_time.elapsed() >= plateau {
+                        break StopReason::Plateau(plateau);
+                    }
+                }
+            }
+        };
+        monitor.progress(&state, &executor);
+        monitor.event(&CampaignEvent::Stopped(stop_reason));
+        if let Some(checkpointer) = checkpointer.as_mut() {
+            if let Err(e) = checkpointer.write(&state) {
+                monitor.event(&CampaignEvent::Failed(&e.context("failed to write checkpoint")));
+            }
+        }
+
+        Ok(CampaignReport {
+            stop_reason: Some(stop_reason),
+            executions: *state.executions(),
+            corpus_size: state.corpus().count(),
+            solutions: state.take_solutions(),
+            static_findings,
+            state,
+            executor,
+        })
+    }
+}
+
+/// The directory of the modules to fuzz, building the package first, and
+/// the package's source map and source directories.
+fn target_modules(target: &TargetConfig, compiler: &Path) -> anyhow::Result<(PathBuf, Option<(PathBuf, PathBuf)>)> {
+    match (&target.package_path, &target.modules_dir) {
+        (Some(path), _) => {
+            let options = PackageBuildOptions {
+                compiler: compiler.to_path_buf(),
+                named_addresses: target.named_addresses.clone(),
+            };
+            let package =
+                build_package(path, &options).with_context(|| format!("failed to build package {}", path.display()))?;
+            debug!("built package {} from {}", package.name, path.display());
+            Ok((package.bytecode_modules, Some((package.source_maps, package.sources))))
+        }
+        (None, Some(dir)) => Ok((dir.clone(), None)),
+        (None, None) => bail!("no modules directory or package path"),
+    }
+}
+
+/// The state modules are published into: `aptos_state`, else forked or the
+/// default genesis, with the state fixture applied.
+fn base_aptos_state(
+    target: &TargetConfig,
+    modules_dir: &Path,
+    aptos_state: Option<AptosCustomState>,
+) -> anyhow::Result<AptosCustomState> {
+    let mut aptos_state = match (aptos_state, &target.fork_url) {
+        (Some(aptos_state), _) => aptos_state,
+        (None, Some(url)) => {
+            let fork = ForkedStateView::new(url, target.fork_version, target.fork_cache_dir.clone())
+                .with_context(|| format!("failed to fork {}", url))?;
+            debug!(
+                "forking chain {} at version {} from {}",
+                fork.chain_id(),
+                fork.version(),
+                url
+            );
+            AptosCustomState::new_forked(fork)
+        }
+        (None, None) => AptosCustomState::new_default(),
+    };
+    if let Some(path) = &target.state_fixture {
+        StateFixture::load(path)
+            .and_then(|fixture| fixture.apply(&mut aptos_state, modules_dir))
+            .context("failed to apply state fixture")?;
+    }
+    Ok(aptos_state)
+}
+
+fn load_package_sources(state: &mut AptosFuzzerState, source_maps: &Path, sources: &Path) -> anyhow::Result<()> {
+    let maps = state.load_source_maps(source_maps)?;
+    let files = state.load_sources(sources)?;
+    debug!("loaded {} source maps over {} source files", maps, files);
+    Ok(())
+}
+
+/// Apply the initializer settings, then set up the targets.
+fn prepare_targets(
+    target: &TargetConfig,
+    execution: &ExecutionConfig,
+    executor: &mut AptosMoveExecutor,
+    state: &mut AptosFuzzerState,
+) -> anyhow::Result<()> {
+    if target.run_initializers == Some(false) {
+        state.clear_initializers();
+    }
+    if let Some(function) = &target.initializer {
+        state
+            .set_initializer(function, &target.initializer_args)
+            .with_context(|| format!("invalid initializer {}", function))?;
+    }
+    if let Some(admin) = &target.admin {
+        state
+            .set_initializer_admin(admin)
+            .with_context(|| format!("invalid admin address {}", admin))?;
+    }
+    setup_targets(target, execution, executor, state);
+    Ok(())
+}
+
+/// Run the state's initializers, create the objects fuzzed functions take,
+/// drop the functions that can't be reached and set up differential
+/// execution.
+fn setup_targets(
+    target: &TargetConfig,
+    execution: &ExecutionConfig,
+    executor: &mut AptosMoveExecutor,
+    state: &mut AptosFuzzerState,
+) {
+    executor.run_initializers(state);
+    executor.create_objects(state);
+    if target.fuzz_unreachable != Some(true) {
+        let unreachable: Vec<_> =
+            find_unreachable_functions(state.aptos_state(), state.target_modules(), state.address_pool())
+                .into_iter()
+                .map(|unreachable| {
+                    debug!(
+                        "skipping {}: always aborts, no {} exists or can be published",
+                        unreachable.function, unreachable.resource
+                    );
+                    unreachable.function
+                })
+                .collect();
+        state.remove_public_functions(&unreachable);
+    }
+    if execution.differential == Some(true) {
+        let config = DifferentialConfig {
+            paranoid_type_checks: true,
+            enable_features: execution.reference_enable_features.clone(),
+            disable_features: execution.reference_disable_features.clone(),
+        };
+        executor.enable_differential(state.aptos_state(), &config);
+    }
+}
+
+/// Rebuild the targets after the watcher saw them change. Returns how many
+/// corpus entries were dropped and added.
+fn reload_targets(
+    target: &TargetConfig,
+    execution: &ExecutionConfig,
+    compiler: &Path,
+    executor: &mut AptosMoveExecutor,
+    state: &mut AptosFuzzerState,
+) -> anyhow::Result<(usize, usize)> {
+    let (modules_dir, package_dirs) = target_modules(target, compiler)?;
+    let aptos_state = base_aptos_state(target, &modules_dir, None)?;
+    let migrated = state.reload_modules(modules_dir, aptos_state);
+    if let Some((source_maps, sources)) = &package_dirs {
+        load_package_sources(state, source_maps, sources)?;
+    }
+    prepare_targets(target, execution, executor, state)?;
+    Ok(migrated)
+}
+
+/// Name of the sync subdirectory of an instance seeded with `rng_seed`.
+pub fn sync_name(rng_seed: u64) -> String {
+    format!("fuzzer-{:016x}", rng_seed)
+}
+
+/// Read every `*.json` input in `dir`, in file name order.
+pub fn load_seeds(dir: &Path) -> anyhow::Result<Vec<AptosFuzzerInput>> {
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir).with_context(|| format!("failed to read {}", dir.display()))? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|ext| ext == "json") {
+            files.push(path);
+        }
+    }
+    files.sort();
+    files.iter().map(|path| load_input(path)).collect()
+}
+
+/// Read a JSON-encoded input, e.g. a solution written by a campaign.
+pub fn load_input(path: &Path) -> anyhow::Result<AptosFuzzerInput> {
+    let contents = fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+    serde_json::from_str(&contents).with_context(|| format!("invalid input {}", path.display()))
+}
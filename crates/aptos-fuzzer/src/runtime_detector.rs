@@ -0,0 +1,479 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use aptos_move_binary_format::file_format::Bytecode;
+use aptos_move_core_types::vm_status::AbortLocation;
+use aptos_move_vm_types::values::Value;
+
+use crate::concolic::{
+    contains_division, hash_string, int_has_variable, value_is_bool, RuntimeIssue, RuntimeIssueKind, SymbolValue,
+};
+use crate::taint::{tainting_arguments, Taint};
+
+/// The instruction about to execute, as seen by a `RuntimeDetector`.
+pub struct InstructionContext<'a> {
+    pub module: &'a str,
+    pub function: &'a str,
+    pub pc: u16,
+    pub instruction: &'a Bytecode,
+    /// Concrete operand stack, top last
+    pub operand_stack: &'a [Value],
+    /// Symbolic operand stack, parallel to `operand_stack`. Empty when the
+    /// tracer lost track of it.
+    pub symbolic_stack: &'a [SymbolValue],
+    /// Transaction arguments each operand derives from, parallel to
+    /// `operand_stack`. Empty when the tracer lost track of it.
+    pub taint_stack: &'a [Taint],
+}
+
+/// A check run alongside the concolic tracer on every execution. Issues
+/// pushed to `issues` are reported for the input being executed.
+pub trait RuntimeDetector {
+    fn name(&self) -> &str;
+
+    fn on_instruction(&mut self, _ctx: &InstructionContext<'_>, _issues: &mut Vec<RuntimeIssue>) {}
+
+    fn on_frame_open(&mut self, _module: &str, _function: &str) {}
+
+    fn on_frame_close(&mut self, _module: &str, _function: &str) {}
+
+    /// The execution aborted with `code`; `current` is the innermost frame
+    /// still open, if any.
+    fn on_abort(
+        &mut self,
+        _location: &AbortLocation,
+        _code: u64,
+        _current: Option<(&str, &str, u16)>,
+        _issues: &mut Vec<RuntimeIssue>,
+    ) {
+    }
+
+    /// Forget per-execution state before the next input runs.
+    fn reset(&mut self) {}
+}
+
+/// Which built-in detectors run and their thresholds.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RuntimeDetectorConfig {
+    pub precision_loss: bool,
+    pub bool_judgement: bool,
+    pub infinite_loop: bool,
+    pub infinite_loop_threshold: usize,
+    pub unbounded_loop: bool,
+    pub unbounded_loop_instructions: u64,
+    pub tainted_sink: bool,
+}
+
+impl Default for RuntimeDetectorConfig {
+    fn default() -> Self {
+        Self {
+            precision_loss: true,
+            bool_judgement: true,
+            infinite_loop: true,
+            infinite_loop_threshold: INFINITE_LOOP_THRESHOLD,
+            unbounded_loop: true,
+            unbounded_loop_instructions: UNBOUNDED_LOOP_INSTRUCTIONS,
+            tainted_sink: true,
+        }
+    }
+}
+
+impl RuntimeDetectorConfig {
+    /// Turn off the built-in detector called `name`, e.g. `BoolJudgement`.
+    pub fn disable(&mut self, name: &str) -> anyhow::Result<()> {
+        match name {
+            "PrecisionLoss" => self.precision_loss = false,
+            "BoolJudgement" => self.bool_judgement = false,
+            "InfiniteLoop" => self.infinite_loop = false,
+            "UnboundedLoop" => self.unbounded_loop = false,
+            "TaintedSink" => self.tainted_sink = false,
+            _ => anyhow::bail!("unknown runtime detector {}", name),
+        }
+        Ok(())
+    }
+
+    /// Keep the unbounded loop threshold at most half of `max_instructions`,
+    /// so loops are reported before the budget stops the run they're in.
+    pub fn fit_instruction_budget(&mut self, max_instructions: Option<u64>) {
+        if let Some(max) = max_instructions {
+            self.unbounded_loop_instructions = self.unbounded_loop_instructions.min(max / 2);
+        }
+    }
+}
+
+/// The built-in detectors enabled in `config`.
+pub fn builtin_detectors(config: &RuntimeDetectorConfig) -> Vec<Box<dyn RuntimeDetector>> {
+    let mut detectors: Vec<Box<dyn RuntimeDetector>> = Vec::new();
+    if config.precision_loss {
+        detectors.push(Box::new(PrecisionLossDetector));
+    }
+    if config.bool_judgement {
+        detectors.push(Box::new(BoolJudgementDetector));
+    }
+    if config.infinite_loop {
+        detectors.push(Box::new(InfiniteLoopDetector::with_threshold(
+            config.infinite_loop_threshold,
+        )));
+    }
+    if config.unbounded_loop {
+        detectors.push(Box::new(UnboundedLoopDetector::with_threshold(
+            config.unbounded_loop_instructions,
+        )));
+    }
+    if config.tainted_sink {
+        detectors.push(Box::new(TaintedSinkDetector::new()));
+    }
+    detectors
+}
+
+fn issue(kind: RuntimeIssueKind, ctx: &InstructionContext<'_>, what: &str) -> RuntimeIssue {
+    let message = format!("{} at {}::{} (pc {})", what, ctx.module, ctx.function, ctx.pc);
+    RuntimeIssue::new(kind, ctx.module.to_string(), ctx.function.to_string(), ctx.pc, message)
+}
+
+/// The two symbolic operands of a binary instruction, if both are tracked.
+fn symbolic_operands<'a>(ctx: &InstructionContext<'a>) -> Option<(&'a SymbolValue, &'a SymbolValue)> {
+    let stack = ctx.symbolic_stack;
+    if stack.len() < 2 {
+        return None;
+    }
+    Some((&stack[stack.len() - 2], &stack[stack.len() - 1]))
+}
+
+/// Multiplication of a value that was already divided: `a / b * c` loses
+/// precision compared to `a * c / b`.
+pub struct PrecisionLossDetector;
+
+impl RuntimeDetector for PrecisionLossDetector {
+    fn name(&self) -> &str {
+        "PrecisionLoss"
+    }
+
+    fn on_instruction(&mut self, ctx: &InstructionContext<'_>, issues: &mut Vec<RuntimeIssue>) {
+        if !matches!(ctx.instruction, Bytecode::Mul) {
+            return;
+        }
+        let Some((lhs, rhs)) = symbolic_operands(ctx) else {
+            return;
+        };
+        let divided = |value: &SymbolValue| matches!(value, SymbolValue::Value(v) if contains_division(v));
+        if divided(lhs) || divided(rhs) {
+            issues.push(issue(RuntimeIssueKind::PrecisionLoss, ctx, "Precision loss detected"));
+        }
+    }
+}
+
+/// Comparisons whose outcome doesn't depend on the input, e.g. two constants
+/// or `x == true`.
+pub struct BoolJudgementDetector;
+
+impl RuntimeDetector for BoolJudgementDetector {
+    fn name(&self) -> &str {
+        "BoolJudgement"
+    }
+
+    fn on_instruction(&mut self, ctx: &InstructionContext<'_>, issues: &mut Vec<RuntimeIssue>) {
+        if !matches!(
+            ctx.instruction,
+            Bytecode::Eq | Bytecode::Neq | Bytecode::Lt | Bytecode::Le | Bytecode::Gt | Bytecode::Ge
+        ) {
+            return;
+        }
+        let Some((lhs, rhs)) = symbolic_operands(ctx) else {
+            return;
+        };
+        let constant =
+            |value: &SymbolValue| matches!(value, SymbolValue::Value(v) if int_has_variable(v) == Some(false));
+        let mut loss = constant(lhs) && constant(rhs);
+        if matches!(ctx.instruction, Bytecode::Eq | Bytecode::Neq) {
+            let bool_const = ctx.operand_stack.last().and_then(value_is_bool);
+            if bool_const.is_some() && (constant(lhs) || constant(rhs)) {
+                loss = true;
+            }
+        }
+        if loss {
+            issues.push(issue(
+                RuntimeIssueKind::BoolJudgement,
+                ctx,
+                "Unnecessary bool judgement",
+            ));
+        }
+    }
+}
+
+/// Number of consecutive times a branch may test the same symbolic condition
+/// before it is reported as a potential infinite loop.
+pub const INFINITE_LOOP_THRESHOLD: usize = 1000;
+
+#[derive(Clone, Debug, Default)]
+struct BranchCounter {
+    last_hash: Option<u64>,
+    count: usize,
+}
+
+/// A branch that keeps testing the same symbolic condition: the loop makes no
+/// progress towards its exit.
+pub struct InfiniteLoopDetector {
+    threshold: usize,
+    branch_counts: HashMap<String, HashMap<u16, BranchCounter>>,
+}
+
+impl InfiniteLoopDetector {
+    pub fn new() -> Self {
+        Self::with_threshold(INFINITE_LOOP_THRESHOLD)
+    }
+
+    pub fn with_threshold(threshold: usize) -> Self {
+        Self {
+            threshold,
+            branch_counts: HashMap::new(),
+        }
+    }
+}
+
+impl Default for InfiniteLoopDetector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RuntimeDetector for InfiniteLoopDetector {
+    fn name(&self) -> &str {
+        "InfiniteLoop"
+    }
+
+    fn on_frame_open(&mut self, module: &str, function: &str) {
+        self.branch_counts.remove(&format!("{}::{}", module, function));
+    }
+
+    fn on_instruction(&mut self, ctx: &InstructionContext<'_>, issues: &mut Vec<RuntimeIssue>) {
+        if !matches!(ctx.instruction, Bytecode::BrTrue(_) | Bytecode::BrFalse(_)) {
+            return;
+        }
+        let Some(SymbolValue::Value(cond)) = ctx.symbolic_stack.last() else {
+            return;
+        };
+        let cond_hash = hash_string(&cond.to_string());
+        let entry = self
+            .branch_counts
+            .entry(format!("{}::{}", ctx.module, ctx.function))
+            .or_default()
+            .entry(ctx.pc)
+            .or_default();
+        if entry.last_hash != Some(cond_hash) {
+            entry.last_hash = Some(cond_hash);
+            entry.count = 1;
+        } else {
+            entry.count += 1;
+            if entry.count >= self.threshold {
+                entry.count = 0;
+                issues.push(issue(RuntimeIssueKind::InfiniteLoop, ctx, "Potential infinite loop"));
+            }
+        }
+    }
+
+    fn reset(&mut self) {
+        self.branch_counts.clear();
+    }
+}
+
+/// Instructions a loop body may execute in one run, over all its iterations
+/// and with the functions it calls, before it is reported as unbounded.
+pub const UNBOUNDED_LOOP_INSTRUCTIONS: u64 = 100_000;
+
+/// A loop of a frame, from its head to the last branch seen jumping back to
+/// it.
+#[derive(Clone, Debug, Default)]
+struct LoopBody {
+    tail: u16,
+    instructions: u64,
+    /// Arguments the conditions of the body's branches derive from
+    taint: Taint,
+    reported: bool,
+}
+
+#[derive(Clone, Debug)]
+struct LoopFrame {
+    module: String,
+    function: String,
+    /// Last pc executed in the frame, its call site while a callee runs
+    pc: u16,
+    /// Loops by head pc
+    loops: BTreeMap<u16, LoopBody>,
+}
+
+/// A loop running more instructions in one execution than the threshold: a
+/// gas bomb when the caller picks its bound. Loops are found by their back
+/// edges and reported at their head, with the arguments the body's branch
+/// conditions derive from. Complements the instruction budget, which stops
+/// such runs without saying where the instructions went.
+pub struct UnboundedLoopDetector {
+    threshold: u64,
+    frames: Vec<LoopFrame>,
+}
+
+impl UnboundedLoopDetector {
+    pub fn new() -> Self {
+        Self::with_threshold(UNBOUNDED_LOOP_INSTRUCTIONS)
+    }
+
+    pub fn with_threshold(threshold: u64) -> Self {
+        Self {
+            threshold,
+            frames: Vec::new(),
+        }
+    }
+}
+
+impl Default for UnboundedLoopDetector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RuntimeDetector for UnboundedLoopDetector {
+    fn name(&self) -> &str {
+        "UnboundedLoop"
+    }
+
+    fn on_frame_open(&mut self, module: &str, function: &str) {
+        self.frames.push(LoopFrame {
+            module: module.to_string(),
+            function: function.to_string(),
+            pc: 0,
+            loops: BTreeMap::new(),
+        });
+    }
+
+    fn on_frame_close(&mut self, _module: &str, _function: &str) {
+        self.frames.pop();
+    }
+
+    fn on_instruction(&mut self, ctx: &InstructionContext<'_>, issues: &mut Vec<RuntimeIssue>) {
+        let Some(frame) = self.frames.last_mut() else {
+            return;
+        };
+        frame.pc = ctx.pc;
+        match ctx.instruction {
+            Bytecode::Branch(target) | Bytecode::BrTrue(target) | Bytecode::BrFalse(target) if *target <= ctx.pc => {
+                let body = frame.loops.entry(*target).or_default();
+                body.tail = body.tail.max(ctx.pc);
+            }
+            _ => {}
+        }
+        if matches!(ctx.instruction, Bytecode::BrTrue(_) | Bytecode::BrFalse(_)) {
+            let taint = ctx.taint_stack.last().copied().unwrap_or(0);
+            for body in frame.loops.range_mut(..=ctx.pc).map(|(_, body)| body) {
+                if ctx.pc <= body.tail {
+                    body.taint |= taint;
+                }
+            }
+        }
+
+        // Callees run on behalf of the loops around their call sites
+        for frame in &mut self.frames {
+            let pc = frame.pc;
+            for (&head, body) in frame.loops.range_mut(..=pc) {
+                if pc > body.tail {
+                    continue;
+                }
+                body.instructions += 1;
+                if body.instructions <= self.threshold || body.reported {
+                    continue;
+                }
+                body.reported = true;
+                let arguments = tainting_arguments(body.taint);
+                let bound = if arguments.is_empty() {
+                    "no argument controls its bound".to_string()
+                } else {
+                    let arguments: Vec<String> = arguments.iter().map(|idx| idx.to_string()).collect();
+                    format!("its bound depends on argument {}", arguments.join(", "))
+                };
+                let message = format!(
+                    "Loop at {}::{} (pc {}) ran over {} instructions; {}",
+                    frame.module, frame.function, head, self.threshold, bound
+                );
+                issues.push(RuntimeIssue::new(
+                    RuntimeIssueKind::UnboundedLoop,
+                    frame.module.clone(),
+                    frame.function.clone(),
+                    head,
+                    message,
+                ));
+            }
+        }
+    }
+
+    fn reset(&mut self) {
+        self.frames.clear();
+    }
+}
+
+/// Iterations a branch with an argument-derived condition may run in one
+/// execution before it is reported as a caller-controlled loop bound.
+pub const TAINTED_LOOP_THRESHOLD: usize = 100;
+
+/// A transaction argument reaching an operand the caller should not control
+/// directly: a global storage address, a shift amount, a division
+/// denominator or the bound of a long-running loop. Works from the taint
+/// stack alone, so it reports even where symbolic tracking gave up.
+#[derive(Default)]
+pub struct TaintedSinkDetector {
+    /// Executions of each argument-dependent branch in this run
+    branch_counts: HashMap<(String, u16), usize>,
+    /// Sites already reported in this run
+    reported: HashSet<(String, u16)>,
+}
+
+impl TaintedSinkDetector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl RuntimeDetector for TaintedSinkDetector {
+    fn name(&self) -> &str {
+        "TaintedSink"
+    }
+
+    fn on_instruction(&mut self, ctx: &InstructionContext<'_>, issues: &mut Vec<RuntimeIssue>) {
+        let Some(&taint) = ctx.taint_stack.last() else {
+            return;
+        };
+        if taint == 0 {
+            return;
+        }
+        let function = format!("{}::{}", ctx.module, ctx.function);
+        let sink = match ctx.instruction {
+            Bytecode::Div | Bytecode::Mod => "division denominator",
+            Bytecode::Shl | Bytecode::Shr => "shift amount",
+            Bytecode::MutBorrowGlobal(_) |
+            Bytecode::ImmBorrowGlobal(_) |
+            Bytecode::MutBorrowGlobalGeneric(_) |
+            Bytecode::ImmBorrowGlobalGeneric(_) |
+            Bytecode::Exists(_) |
+            Bytecode::ExistsGeneric(_) |
+            Bytecode::MoveFrom(_) |
+            Bytecode::MoveFromGeneric(_) => "global storage address",
+            Bytecode::BrTrue(_) | Bytecode::BrFalse(_) => {
+                let count = self.branch_counts.entry((function.clone(), ctx.pc)).or_default();
+                *count += 1;
+                if *count < TAINTED_LOOP_THRESHOLD {
+                    return;
+                }
+                "loop bound"
+            }
+            _ => return,
+        };
+        if !self.reported.insert((function, ctx.pc)) {
+            return;
+        }
+        let arguments: Vec<String> = tainting_arguments(taint).iter().map(|idx| idx.to_string()).collect();
+        let what = format!("Argument {} reaches a {}", arguments.join(", "), sink);
+        issues.push(issue(RuntimeIssueKind::TaintedSink, ctx, &what));
+    }
+
+    fn reset(&mut self) {
+        self.branch_counts.clear();
+        self.reported.clear();
+    }
+}
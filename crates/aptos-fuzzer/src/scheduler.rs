@@ -0,0 +1,302 @@
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+use anyhow::bail;
+use libafl::corpus::{Corpus, CorpusId};
+use libafl::schedulers::{QueueScheduler, Scheduler};
+use libafl::state::HasCorpus;
+use libafl::Error;
+use serde::{Deserialize, Serialize};
+
+use crate::input::AptosFuzzerInput;
+use crate::state::AptosFuzzerState;
+
+/// Share of a fair split of the executions a function may fall below
+/// before `Budgeted` schedules it ahead of the queue
+pub const DEFAULT_MIN_FUNCTION_SHARE: f64 = 0.5;
+
+/// How corpus entries are picked, so one function with a huge input space
+/// doesn't starve the others.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Fairness {
+    /// Corpus order, whatever the entries call
+    #[default]
+    Queue,
+    /// The functions with corpus entries in turn, one entry each
+    RoundRobin,
+    /// Corpus order, except that a function with less than its minimum
+    /// share of the executions goes first
+    Budgeted,
+}
+
+impl Fairness {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Queue => "queue",
+            Self::RoundRobin => "round-robin",
+            Self::Budgeted => "budgeted",
+        }
+    }
+}
+
+impl FromStr for Fairness {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "queue" => Ok(Self::Queue),
+            "round-robin" => Ok(Self::RoundRobin),
+            "budgeted" => Ok(Self::Budgeted),
+            _ => bail!("unknown fairness policy {}, expected queue, round-robin or budgeted", s),
+        }
+    }
+}
+
+/// A `QueueScheduler` that also tracks which functions each corpus entry
+/// calls, to pick entries by function under the `Fairness` policy, and
+/// drops the sequence prefixes `AptosFuzzerState` found subsumed.
+/// Executions are counted per function by the executor, in
+/// `AptosFuzzerState::function_executions`.
+#[derive(Debug)]
+pub struct FairScheduler {
+    queue: QueueScheduler,
+    fairness: Fairness,
+    /// Fraction of `total / functions` executions below which `Budgeted`
+    /// favors a function
+    min_share: f64,
+    /// Corpus entries calling each function, in the order they were added
+    entries: BTreeMap<String, Vec<CorpusId>>,
+    /// Position of the next entry to pick for each function
+    cursors: BTreeMap<String, usize>,
+    /// Turn of the next function under `RoundRobin`
+    turn: usize,
+    /// Corpus ids below this one are already in `entries`
+    indexed_up_to: usize,
+}
+
+impl FairScheduler {
+    pub fn new(fairness: Fairness) -> Self {
+        Self {
+            queue: QueueScheduler::new(),
+            fairness,
+            min_share: DEFAULT_MIN_FUNCTION_SHARE,
+            entries: BTreeMap::new(),
+            cursors: BTreeMap::new(),
+            turn: 0,
+            indexed_up_to: 0,
+        }
+    }
+
+    pub fn with_min_share(mut self, min_share: f64) -> Self {
+        self.min_share = min_share;
+        self
+    }
+
+    /// Look up the functions called by the corpus entries added since the
+    /// last call, including seeds added to the corpus directly rather than
+    /// through the fuzzer. Corpus ids are handed out in increasing order, so
+    /// only those from the last one indexed on are looked at.
+    fn index(&mut self, state: &AptosFuzzerState) {
+        let free_id = state.corpus().peek_free_id().0;
+        for id in (self.indexed_up_to..free_id).map(CorpusId) {
+            let Ok(input) = state.corpus().cloned_input_for_id(id) else {
+                continue;
+            };
+            for function in input.called_functions() {
+                self.entries.entry(function).or_default().push(id);
+            }
+        }
+        self.indexed_up_to = self.indexed_up_to.max(free_id);
+    }
+
+    /// The function whose turn it is under `RoundRobin`.
+    fn next_in_turn(&mut self) -> Option<String> {
+        if self.entries.is_empty() {
+            return None;
+        }
+        let function = self.entries.keys().nth(self.turn % self.entries.len()).cloned();
+        self.turn += 1;
+        function
+    }
+
+    /// The function furthest below its minimum share of the executions,
+    /// if any is.
+    fn most_starved(&self, state: &AptosFuzzerState) -> Option<String> {
+        if self.entries.len() < 2 {
+            return None;
+        }
+        let executions = state.function_executions();
+        let count = |function: &String| executions.get(function).copied().unwrap_or(0);
+        let total: u64 = self.entries.keys().map(count).sum();
+        let floor = total as f64 / self.entries.len() as f64 * self.min_share;
+        self.entries
+            .keys()
+            .filter(|function| (count(function) as f64) < floor)
+            .min_by_key(|function| count(function))
+            .cloned()
+    }
+
+    /// The next entry calling `function` still in the corpus, forgetting
+    /// those removed since.
+    fn next_entry(&mut self, state: &AptosFuzzerState, function: &str) -> Option<CorpusId> {
+        let ids = self.entries.get_mut(function)?;
+        ids.retain(|&id| state.corpus().get(id).is_ok());
+        if ids.is_empty() {
+            self.entries.remove(function);
+            self.cursors.remove(function);
+            return None;
+        }
+        let cursor = self.cursors.entry(function.to_string()).or_insert(0);
+        let id = ids[*cursor % ids.len()];
+        *cursor += 1;
+        Some(id)
+    }
+}
+
+impl Scheduler<AptosFuzzerInput, AptosFuzzerState> for FairScheduler {
+    fn on_add(&mut self, state: &mut AptosFuzzerState, id: CorpusId) -> Result<(), Error> {
+        if self.fairness != Fairness::Queue {
+            self.index(state);
+        }
+        self.queue.on_add(state, id)
+    }
+
+    fn next(&mut self, state: &mut AptosFuzzerState) -> Result<CorpusId, Error> {
+        if self.fairness != Fairness::Queue {
+            self.index(state);
+        }
+        let function = match self.fairness {
+            Fairness::Queue => None,
+            Fairness::RoundRobin => self.next_in_turn(),
+            Fairness::Budgeted => self.most_starved(state),
+        };
+        let id = match function.and_then(|function| self.next_entry(state, &function)) {
+            Some(id) => {
+                self.set_current_scheduled(state, Some(id))?;
+                id
+            }
+            None => self.queue.next(state)?,
+        };
+        // Only once the queue moved past them, so it doesn't start over
+        state.prune_subsumed_prefixes(id);
+        Ok(id)
+    }
+
+    fn set_current_scheduled(&mut self, state: &mut AptosFuzzerState, next_id: Option<CorpusId>) -> Result<(), Error> {
+        self.queue.set_current_scheduled(state, next_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use aptos_move_core_types::account_address::AccountAddress;
+    use aptos_move_core_types::identifier::Identifier;
+    use aptos_move_core_types::language_storage::ModuleId;
+    use aptos_types::transaction::{EntryFunction, TransactionPayload};
+    use libafl::corpus::{Corpus, CorpusId, Testcase};
+    use libafl::schedulers::Scheduler;
+    use libafl::state::HasCorpus;
+
+    use super::{FairScheduler, Fairness};
+    use crate::input::AptosFuzzerInput;
+    use crate::state::AptosFuzzerState;
+
+    fn module() -> ModuleId {
+        ModuleId::new(
+            AccountAddress::from_hex_literal("0xcafe").unwrap(),
+            Identifier::new("m").unwrap(),
+        )
+    }
+
+    fn name(function: &str) -> String {
+        format!("{}::{}", module(), function)
+    }
+
+    fn input(function: &str) -> AptosFuzzerInput {
+        AptosFuzzerInput::new(TransactionPayload::EntryFunction(EntryFunction::new(
+            module(),
+            Identifier::new(function).unwrap(),
+            Vec::new(),
+            Vec::new(),
+        )))
+    }
+
+    /// A scheduler under `fairness` and a corpus of entries calling
+    /// `functions`, one each, added through the scheduler.
+    fn setup(fairness: Fairness, functions: &[&str]) -> (FairScheduler, AptosFuzzerState) {
+        let mut scheduler = FairScheduler::new(fairness);
+        let mut state = AptosFuzzerState::new(PathBuf::new());
+        for function in functions {
+            let id = state.corpus_mut().add(Testcase::new(input(function))).unwrap();
+            scheduler.on_add(&mut state, id).unwrap();
+        }
+        (scheduler, state)
+    }
+
+    fn picks(scheduler: &mut FairScheduler, state: &mut AptosFuzzerState, count: usize) -> Vec<usize> {
+        (0..count).map(|_| scheduler.next(state).unwrap().0).collect()
+    }
+
+    #[test]
+    fn fairness_names_round_trip() {
+        for fairness in [Fairness::Queue, Fairness::RoundRobin, Fairness::Budgeted] {
+            assert_eq!(fairness.as_str().parse::<Fairness>().unwrap(), fairness);
+        }
+        assert!("fair".parse::<Fairness>().is_err());
+    }
+
+    #[test]
+    fn queue_follows_the_corpus_order() {
+        let (mut scheduler, mut state) = setup(Fairness::Queue, &["f", "f", "f", "g"]);
+        assert_eq!(picks(&mut scheduler, &mut state, 4), vec![0, 1, 2, 3]);
+        assert!(scheduler.entries.is_empty(), "the queue doesn't look at functions");
+    }
+
+    #[test]
+    fn round_robin_takes_the_functions_in_turn() {
+        let (mut scheduler, mut state) = setup(Fairness::RoundRobin, &["f", "f", "f", "g"]);
+        assert_eq!(picks(&mut scheduler, &mut state, 6), vec![0, 3, 1, 3, 2, 3]);
+    }
+
+    #[test]
+    fn round_robin_indexes_entries_added_outside_the_scheduler() {
+        let (mut scheduler, mut state) = setup(Fairness::RoundRobin, &["f"]);
+        state.corpus_mut().add(Testcase::new(input("g"))).unwrap();
+        assert_eq!(picks(&mut scheduler, &mut state, 2), vec![0, 1]);
+    }
+
+    #[test]
+    fn budgeted_favors_the_function_below_its_share() {
+        let (mut scheduler, mut state) = setup(Fairness::Budgeted, &["f", "g"]);
+        for _ in 0..10 {
+            state.record_function_execution(&input("f"));
+        }
+        assert_eq!(scheduler.most_starved(&state), Some(name("g")));
+        assert_eq!(scheduler.next(&mut state).unwrap(), CorpusId(1));
+        for _ in 0..4 {
+            state.record_function_execution(&input("g"));
+        }
+        // 4 of 14 executions is above half of a fair share
+        assert_eq!(scheduler.most_starved(&state), None);
+        // but below a whole one
+        let mut strict = FairScheduler::new(Fairness::Budgeted).with_min_share(1.0);
+        strict.index(&state);
+        assert_eq!(strict.most_starved(&state), Some(name("g")));
+    }
+
+    #[test]
+    fn next_entry_forgets_removed_entries() {
+        let (mut scheduler, mut state) = setup(Fairness::RoundRobin, &["f", "f"]);
+        state.corpus_mut().remove(CorpusId(0)).unwrap();
+        assert_eq!(scheduler.next_entry(&state, &name("f")), Some(CorpusId(1)));
+        assert_eq!(scheduler.next_entry(&state, &name("f")), Some(CorpusId(1)));
+        assert_eq!(scheduler.entries[&name("f")], vec![CorpusId(1)]);
+        state.corpus_mut().remove(CorpusId(1)).unwrap();
+        assert_eq!(scheduler.next_entry(&state, &name("f")), None);
+        assert!(!scheduler.entries.contains_key(&name("f")));
+        assert!(!scheduler.cursors.contains_key(&name("f")));
+    }
+}
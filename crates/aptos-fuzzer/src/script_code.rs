@@ -0,0 +1,159 @@
+use aptos_move_binary_format::access::ScriptAccess;
+use aptos_move_binary_format::file_format::{Bytecode, CodeOffset, CompiledScript, FunctionHandleIndex, LocalIndex};
+use aptos_move_bytecode_verifier::verify_script;
+use libafl_bolts::rands::Rand;
+
+/// Mutants of a script tried before giving up on finding one that verifies
+const VERIFY_ATTEMPTS: usize = 16;
+
+/// `code` with one of its instructions mutated: an operand, the opcode
+/// within its class, or the instruction duplicated, deleted or swapped with
+/// the next. Only mutants the bytecode verifier accepts are returned, so
+/// runs reach the VM's runtime with code no compiler would emit. `None`
+/// when `code` doesn't deserialize or no mutant verifies.
+pub fn mutate_script_code(code: &[u8], rand: &mut impl Rand) -> Option<Vec<u8>> {
+    let script = CompiledScript::deserialize(code).ok()?;
+    for _ in 0..VERIFY_ATTEMPTS {
+        let mut mutant = script.clone();
+        if !mutate_instructions(&mut mutant, rand) || verify_script(&mutant).is_err() {
+            continue;
+        }
+        let mut bytes = Vec::new();
+        if mutant.serialize(&mut bytes).is_ok() {
+            return Some(bytes);
+        }
+    }
+    None
+}
+
+fn mutate_instructions(script: &mut CompiledScript, rand: &mut impl Rand) -> bool {
+    let locals = script.signature_at(script.parameters).len() + script.signature_at(script.code.locals).len();
+    let handles = script.function_handles.len();
+    mutate_code(&mut script.code.code, locals, handles, rand)
+}
+
+/// Mutate one instruction of a code unit with `locals` locals in a binary
+/// with `handles` function handles. Shared with module mutation, which
+/// doesn't verify its mutants.
+pub(crate) fn mutate_code(code: &mut Vec<Bytecode>, locals: usize, handles: usize, rand: &mut impl Rand) -> bool {
+    if code.is_empty() {
+        return false;
+    }
+    let len = code.len();
+    let at = (rand.next() as usize) % len;
+    match rand.next() % 5 {
+        0 => mutate_operand(&mut code[at], locals, handles, len, rand),
+        1 => swap_opcode(&mut code[at], rand),
+        2 => {
+            let copy = code[at].clone();
+            shift_branches(code, at, 1);
+            code.insert(at + 1, copy);
+            true
+        }
+        3 if len > 1 => {
+            code.remove(at);
+            shift_branches(code, at, -1);
+            true
+        }
+        4 if at + 1 < len => {
+            code.swap(at, at + 1);
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Move the targets of branches past `at` by `delta` after an instruction
+/// was inserted after or removed at `at`.
+fn shift_branches(code: &mut [Bytecode], at: usize, delta: isize) {
+    for instruction in code {
+        if let Bytecode::Branch(target) | Bytecode::BrTrue(target) | Bytecode::BrFalse(target) = instruction {
+            if *target as usize > at {
+                *target = (*target as isize + delta) as CodeOffset;
+            }
+        }
+    }
+}
+
+/// Replace the operand of `instruction`: a constant with a boundary value,
+/// a local, branch target or called function with another one.
+fn mutate_operand(instruction: &mut Bytecode, locals: usize, handles: usize, len: usize, rand: &mut impl Rand) -> bool {
+    match instruction {
+        Bytecode::LdU8(value) => *value = boundary(rand, u8::MAX as u128) as u8,
+        Bytecode::LdU16(value) => *value = boundary(rand, u16::MAX as u128) as u16,
+        Bytecode::LdU32(value) => *value = boundary(rand, u32::MAX as u128) as u32,
+        Bytecode::LdU64(value) => *value = boundary(rand, u64::MAX as u128) as u64,
+        Bytecode::LdU128(value) => *value = boundary(rand, u128::MAX),
+        Bytecode::LdTrue => *instruction = Bytecode::LdFalse,
+        Bytecode::LdFalse => *instruction = Bytecode::LdTrue,
+        Bytecode::CopyLoc(local) |
+        Bytecode::MoveLoc(local) |
+        Bytecode::StLoc(local) |
+        Bytecode::MutBorrowLoc(local) |
+        Bytecode::ImmBorrowLoc(local)
+            if locals > 0 =>
+        {
+            *local = (rand.next() as usize % locals) as LocalIndex
+        }
+        Bytecode::Branch(target) | Bytecode::BrTrue(target) | Bytecode::BrFalse(target) => {
+            *target = (rand.next() as usize % len) as CodeOffset
+        }
+        Bytecode::Call(handle) if handles > 0 => *handle = FunctionHandleIndex((rand.next() as usize % handles) as u16),
+        _ => return false,
+    }
+    true
+}
+
+/// 0, 1, `max`, `max - 1` or a random value up to `max`.
+fn boundary(rand: &mut impl Rand, max: u128) -> u128 {
+    match rand.next() % 5 {
+        0 => 0,
+        1 => 1,
+        2 => max,
+        3 => max - 1,
+        _ => (rand.next() as u128) & max,
+    }
+}
+
+/// Replace `instruction` with another of its class, taking and leaving the
+/// same number of stack values.
+fn swap_opcode(instruction: &mut Bytecode, rand: &mut impl Rand) -> bool {
+    const ARITHMETIC: [Bytecode; 8] = [
+        Bytecode::Add,
+        Bytecode::Sub,
+        Bytecode::Mul,
+        Bytecode::Div,
+        Bytecode::Mod,
+        Bytecode::BitAnd,
+        Bytecode::BitOr,
+        Bytecode::Xor,
+    ];
+    const COMPARISONS: [Bytecode; 4] = [Bytecode::Lt, Bytecode::Le, Bytecode::Gt, Bytecode::Ge];
+    const CASTS: [Bytecode; 6] = [
+        Bytecode::CastU8,
+        Bytecode::CastU16,
+        Bytecode::CastU32,
+        Bytecode::CastU64,
+        Bytecode::CastU128,
+        Bytecode::CastU256,
+    ];
+    let class: &[Bytecode] = match instruction {
+        Bytecode::Shl | Bytecode::Shr => &[Bytecode::Shl, Bytecode::Shr],
+        Bytecode::Eq | Bytecode::Neq => &[Bytecode::Eq, Bytecode::Neq],
+        Bytecode::And | Bytecode::Or => &[Bytecode::And, Bytecode::Or],
+        Bytecode::BrTrue(target) => {
+            *instruction = Bytecode::BrFalse(*target);
+            return true;
+        }
+        Bytecode::BrFalse(target) => {
+            *instruction = Bytecode::BrTrue(*target);
+            return true;
+        }
+        _ if ARITHMETIC.contains(instruction) => &ARITHMETIC,
+        _ if COMPARISONS.contains(instruction) => &COMPARISONS,
+        _ if CASTS.contains(instruction) => &CASTS,
+        _ => return false,
+    };
+    *instruction = class[(rand.next() as usize) % class.len()].clone();
+    true
+}
@@ -1,4 +1,6 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
 
 use aptos_dynamic_transaction_composer::{ArgumentOperation, CallArgument, PreviousResult, TransactionComposer};
 use aptos_move_core_types::account_address::AccountAddress;
@@ -8,9 +10,10 @@ use aptos_move_core_types::u256::U256;
 use aptos_types::transaction::{Script, TransactionArgument};
 use bcs;
 use bytes::Bytes;
-use log::warn;
 use serde::{Deserialize, Serialize};
 
+use crate::error::FuzzerError;
+
 #[derive(Clone, Debug, Default, Hash, Eq, PartialEq, Serialize, Deserialize)]
 pub struct ScriptSequence {
     calls: Vec<SequenceCall>,
@@ -36,6 +39,284 @@ impl ScriptSequence {
     pub fn is_empty(&self) -> bool {
         self.calls.is_empty()
     }
+
+    /// Signers the compiled script takes: one more than the highest signer
+    /// index any call uses.
+    pub fn signer_count(&self) -> u16 {
+        self.calls
+            .iter()
+            .flat_map(|call| call.args.iter())
+            .filter_map(|arg| match arg {
+                SequenceArgument::Signer(idx) => Some(idx + 1),
+                _ => None,
+            })
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Positions and types of all literal arguments, as `(call, argument,
+    /// type)`.
+    pub fn raw_arguments(&self) -> Vec<(usize, usize, &TypeTag)> {
+        let mut raw = Vec::new();
+        for (call_idx, call) in self.calls.iter().enumerate() {
+            for (arg_idx, arg) in call.args.iter().enumerate() {
+                if let SequenceArgument::Raw { ty, .. } = arg {
+                    raw.push((call_idx, arg_idx, ty));
+                }
+            }
+        }
+        raw
+    }
+
+    /// Types of the compiled script's arguments, in order: every literal
+    /// argument, repeated for each `SameAs` argument standing for it.
+    pub fn script_arg_types(&self) -> Vec<&TypeTag> {
+        self.calls
+            .iter()
+            .flat_map(|call| &call.args)
+            .filter_map(|arg| match arg {
+                SequenceArgument::Raw { ty, .. } => Some(ty),
+                SequenceArgument::SameAs { call_idx, arg_idx } => {
+                    self.resolve_argument(*call_idx, *arg_idx).map(|(_, ty)| ty)
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Replace the bytes of a literal argument. Returns false when the
+    /// position does not hold one.
+    pub fn set_raw_argument(&mut self, call_idx: usize, arg_idx: usize, new_bytes: Vec<u8>) -> bool {
+        match self.calls.get_mut(call_idx).and_then(|call| call.args.get_mut(arg_idx)) {
+            Some(SequenceArgument::Raw { bytes, .. }) => {
+                *bytes = new_bytes;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// The sequence with its literals set to `script_args`, the arguments of
+    /// a script compiled from it, which argument mutations change without
+    /// touching the sequence. A `SameAs` argument whose script argument no
+    /// longer matches its literal becomes a literal of its own. `None` when
+    /// the arguments don't line up with the literals.
+    pub fn with_script_args(&self, script_args: &[TransactionArgument]) -> Option<ScriptSequence> {
+        let mut values = script_args.iter();
+        let mut synced = self.clone();
+        for call_idx in 0..self.calls.len() {
+            for arg_idx in 0..self.calls[call_idx].args.len() {
+                let linked = match &self.calls[call_idx].args[arg_idx] {
+                    SequenceArgument::Raw { .. } => None,
+                    SequenceArgument::SameAs {
+                        call_idx: linked_call,
+                        arg_idx: linked_arg,
+                    } => Some(synced.resolve_argument(*linked_call, *linked_arg)?),
+                    _ => continue,
+                };
+                let bytes = transaction_argument_bytes(values.next()?)?;
+                let arg = match linked {
+                    Some((linked_bytes, _)) if linked_bytes == bytes.as_slice() => continue,
+                    Some((_, ty)) => SequenceArgument::Raw { bytes, ty: ty.clone() },
+                    None => {
+                        synced.set_raw_argument(call_idx, arg_idx, bytes);
+                        continue;
+                    }
+                };
+                synced.set_argument(call_idx, arg_idx, arg);
+            }
+        }
+        values.next().is_none().then_some(synced)
+    }
+
+    /// The literal a `SameAs` argument stands for, following chains of them.
+    pub fn resolve_argument(&self, call_idx: u16, arg_idx: u16) -> Option<(&[u8], &TypeTag)> {
+        let (mut call_idx, mut arg_idx) = (call_idx, arg_idx);
+        for _ in 0..self.calls.len() {
+            match self.calls.get(call_idx as usize)?.args.get(arg_idx as usize)? {
+                SequenceArgument::Raw { bytes, ty } => return Some((bytes, ty)),
+                SequenceArgument::SameAs {
+                    call_idx: next_call,
+                    arg_idx: next_arg,
+                } if *next_call < call_idx => (call_idx, arg_idx) = (*next_call, *next_arg),
+                _ => return None,
+            }
+        }
+        None
+    }
+
+    /// Build a new sequence from calls picked out of `sources`, in `order`
+    /// (pairs of source index and call index; a call may appear more than
+    /// once).
+    ///
+    /// `PreviousResult` arguments are re-pointed at the producing call's new
+    /// position. A result that is no longer produced by an earlier call is
+    /// replaced by `replace(producer, return_idx)`; `None` from it aborts the
+    /// rearrangement. Whether the result is still there to be taken, i.e.
+    /// wasn't moved by an earlier call, is left to the caller to repair.
+    /// `SameAs` arguments are re-pointed the same way, or become a copy of
+    /// the literal they stood for.
+    pub fn rearrange(
+        sources: &[&ScriptSequence],
+        order: &[(usize, usize)],
+        mut replace: impl FnMut(&SequenceCall, u16) -> Option<SequenceArgument>,
+    ) -> Option<ScriptSequence> {
+        let mut positions: HashMap<(usize, usize), u16> = HashMap::new();
+        let mut rearranged = ScriptSequence::new();
+        for (position, &(source, call_idx)) in order.iter().enumerate() {
+            let sequence = sources.get(source)?;
+            let call = sequence.calls.get(call_idx)?;
+            let mut args = Vec::with_capacity(call.args.len());
+            for arg in &call.args {
+                if let SequenceArgument::SameAs {
+                    call_idx: source_idx,
+                    arg_idx,
+                } = arg
+                {
+                    match positions.get(&(source, *source_idx as usize)) {
+                        Some(new_idx) => args.push(SequenceArgument::SameAs {
+                            call_idx: *new_idx,
+                            arg_idx: *arg_idx,
+                        }),
+                        None => {
+                            let (bytes, ty) = sequence.resolve_argument(*source_idx, *arg_idx)?;
+                            args.push(SequenceArgument::Raw {
+                                bytes: bytes.to_vec(),
+                                ty: ty.clone(),
+                            });
+                        }
+                    }
+                    continue;
+                }
+                let SequenceArgument::PreviousResult {
+                    call_idx: producer_idx,
+                    return_idx,
+                    operation,
+                } = arg
+                else {
+                    args.push(arg.clone());
+                    continue;
+                };
+                match positions.get(&(source, *producer_idx as usize)) {
+                    Some(new_idx) => {
+                        args.push(SequenceArgument::PreviousResult {
+                            call_idx: *new_idx,
+                            return_idx: *return_idx,
+                            operation: *operation,
+                        });
+                    }
+                    None => {
+                        let producer = sequence.calls.get(*producer_idx as usize)?;
+                        args.push(replace(producer, *return_idx)?);
+                    }
+                }
+            }
+            // Assertions against a call that didn't come along are dropped
+            let assertions = call
+                .assertions
+                .iter()
+                .filter_map(|assertion| match assertion.expected {
+                    ReturnOperand::Constant(_) => Some(assertion.clone()),
+                    ReturnOperand::Return { call_idx, return_idx } => {
+                        let new_idx = positions.get(&(source, call_idx as usize))?;
+                        Some(ReturnAssertion {
+                            expected: ReturnOperand::Return {
+                                call_idx: *new_idx,
+                                return_idx,
+                            },
+                            ..assertion.clone()
+                        })
+                    }
+                })
+                .collect();
+            positions.entry((source, call_idx)).or_insert(position as u16);
+            rearranged.push_call(SequenceCall {
+                args,
+                assertions,
+                ..call.clone()
+            });
+        }
+        Some(rearranged)
+    }
+
+    /// Results that some argument moves, as `(call, return)`.
+    pub fn moved_results(&self) -> HashSet<(u16, u16)> {
+        self.calls
+            .iter()
+            .flat_map(|call| &call.args)
+            .filter_map(|arg| match arg {
+                SequenceArgument::PreviousResult {
+                    call_idx,
+                    return_idx,
+                    operation: ResultOperation::Move,
+                } => Some((*call_idx, *return_idx)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Replace an argument of a call. Returns false when there is no such
+    /// argument.
+    pub fn set_argument(&mut self, call_idx: usize, arg_idx: usize, new_arg: SequenceArgument) -> bool {
+        match self.calls.get_mut(call_idx).and_then(|call| call.args.get_mut(arg_idx)) {
+            Some(arg) => {
+                *arg = new_arg;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Messages for the return assertions that `returns`, the primitive
+    /// values each call returned (`None` for any other value), break.
+    pub fn check_returns(&self, returns: &[Vec<Option<U256>>]) -> Vec<String> {
+        let value = |call_idx: u16, return_idx: u16| {
+            returns
+                .get(call_idx as usize)
+                .and_then(|values| values.get(return_idx as usize).copied().flatten())
+        };
+        let mut failures = Vec::new();
+        for (call_idx, call) in self.calls.iter().enumerate() {
+            for assertion in &call.assertions {
+                let Some(actual) = value(call_idx as u16, assertion.return_idx) else {
+                    continue;
+                };
+                let (expected, described) = match assertion.expected {
+                    ReturnOperand::Constant(constant) => (Some(constant), constant.to_string()),
+                    ReturnOperand::Return { call_idx, return_idx } => {
+                        let Some(producer) = self.calls.get(call_idx as usize) else {
+                            continue;
+                        };
+                        (
+                            value(call_idx, return_idx),
+                            format!(
+                                "{}::{} return {}",
+                                producer.module.name(),
+                                producer.function,
+                                return_idx
+                            ),
+                        )
+                    }
+                };
+                let Some(expected) = expected else {
+                    continue;
+                };
+                if !assertion.comparison.holds(actual, expected) {
+                    failures.push(format!(
+                        "{}::{} return {} is {}, expected {} {} ({})",
+                        call.module.name(),
+                        call.function,
+                        assertion.return_idx,
+                        actual,
+                        assertion.comparison,
+                        described,
+                        expected
+                    ));
+                }
+            }
+        }
+        failures
+    }
 }
 
 #[derive(Serialize, Deserialize)]
@@ -45,11 +326,16 @@ struct PreviousResultRepr {
     operation_type: ArgumentOperation,
 }
 
-fn build_previous_result(call_idx: u16, return_idx: u16) -> Option<PreviousResult> {
+fn build_previous_result(call_idx: u16, return_idx: u16, operation: ResultOperation) -> Option<PreviousResult> {
     let repr = PreviousResultRepr {
         call_idx,
         return_idx,
-        operation_type: ArgumentOperation::Move,
+        operation_type: match operation {
+            ResultOperation::Move => ArgumentOperation::Move,
+            ResultOperation::Copy => ArgumentOperation::Copy,
+            ResultOperation::Borrow => ArgumentOperation::Borrow,
+            ResultOperation::BorrowMut => ArgumentOperation::BorrowMut,
+        },
     };
     let bytes = bcs::to_bytes(&repr).ok()?;
     bcs::from_bytes(&bytes).ok()
@@ -71,6 +357,10 @@ fn collect_txn_args(sequence: &ScriptSequence) -> Option<Vec<TransactionArgument
                     // Previous results don't introduce new serialized
                     // parameters.
                 }
+                SequenceArgument::SameAs { call_idx, arg_idx } => {
+                    let (bytes, ty) = sequence.resolve_argument(*call_idx, *arg_idx)?;
+                    args.push(bytes_to_transaction_argument(ty, bytes)?);
+                }
             }
         }
     }
@@ -100,12 +390,31 @@ fn bytes_to_transaction_argument(ty: &TypeTag, bytes: &[u8]) -> Option<Transacti
     }
 }
 
+/// BCS encoding of the value of a script argument.
+fn transaction_argument_bytes(arg: &TransactionArgument) -> Option<Vec<u8>> {
+    match arg {
+        TransactionArgument::Bool(value) => bcs::to_bytes(value).ok(),
+        TransactionArgument::U8(value) => bcs::to_bytes(value).ok(),
+        TransactionArgument::U16(value) => bcs::to_bytes(value).ok(),
+        TransactionArgument::U32(value) => bcs::to_bytes(value).ok(),
+        TransactionArgument::U64(value) => bcs::to_bytes(value).ok(),
+        TransactionArgument::U128(value) => bcs::to_bytes(value).ok(),
+        TransactionArgument::U256(value) => bcs::to_bytes(value).ok(),
+        TransactionArgument::Address(value) => bcs::to_bytes(value).ok(),
+        TransactionArgument::U8Vector(value) => bcs::to_bytes(value).ok(),
+        TransactionArgument::Serialized(bytes) => Some(bytes.clone()),
+    }
+}
+
 #[derive(Clone, Debug, Hash, Eq, PartialEq, Serialize, Deserialize)]
 pub struct SequenceCall {
     module: ModuleId,
     function: Identifier,
     ty_args: Vec<TypeTag>,
     args: Vec<SequenceArgument>,
+    /// Checked against the values the call returns once the sequence ran
+    #[serde(default)]
+    assertions: Vec<ReturnAssertion>,
 }
 
 impl SequenceCall {
@@ -115,9 +424,19 @@ impl SequenceCall {
             function,
             ty_args,
             args,
+            assertions: Vec::new(),
         }
     }
 
+    pub fn with_assertions(mut self, assertions: Vec<ReturnAssertion>) -> Self {
+        self.assertions = assertions;
+        self
+    }
+
+    pub fn assertions(&self) -> &[ReturnAssertion] {
+        &self.assertions
+    }
+
     pub fn module(&self) -> &ModuleId {
         &self.module
     }
@@ -138,79 +457,322 @@ impl SequenceCall {
 #[derive(Clone, Debug, Hash, Eq, PartialEq, Serialize, Deserialize)]
 pub enum SequenceArgument {
     Signer(u16),
-    Raw { bytes: Vec<u8>, ty: TypeTag },
-    PreviousResult { call_idx: u16, return_idx: u16 },
+    Raw {
+        bytes: Vec<u8>,
+        ty: TypeTag,
+    },
+    PreviousResult {
+        call_idx: u16,
+        return_idx: u16,
+        #[serde(default)]
+        operation: ResultOperation,
+    },
+    /// The literal argument `arg_idx` of the earlier call `call_idx`, so
+    /// that mutating one mutates both
+    SameAs {
+        call_idx: u16,
+        arg_idx: u16,
+    },
 }
 
-pub fn compile_sequence(sequence: &ScriptSequence, modules: &HashMap<ModuleId, Bytes>) -> Option<Script> {
-    let mut composer = TransactionComposer::multi_signer(0);
-    for (module_id, bytes) in modules {
-        if let Err(err) = composer.store_module(bytes.to_vec()) {
-            warn!(
-                "[aptos-fuzzer] failed to load module {} into composer: {}",
-                module_id, err
-            );
-            return None;
+impl SequenceArgument {
+    /// Whether the argument is a reference to an earlier call's result, as
+    /// passed to a `&T` or `&mut T` parameter.
+    pub fn is_borrow(&self) -> bool {
+        matches!(
+            self,
+            SequenceArgument::PreviousResult {
+                operation: ResultOperation::Borrow | ResultOperation::BorrowMut,
+                ..
+            }
+        )
+    }
+}
+
+/// How a call takes an earlier call's result: by value, which uses it up
+/// unless it is copied, or by reference, which leaves it to later calls.
+#[derive(Clone, Copy, Debug, Default, Hash, Eq, PartialEq, Serialize, Deserialize)]
+pub enum ResultOperation {
+    #[default]
+    Move,
+    /// Only for values whose type has the `copy` ability
+    Copy,
+    Borrow,
+    BorrowMut,
+}
+
+/// An expected relation between a return value of a call and a constant or
+/// a return value of an earlier call.
+#[derive(Clone, Debug, Hash, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ReturnAssertion {
+    pub return_idx: u16,
+    pub comparison: Comparison,
+    pub expected: ReturnOperand,
+}
+
+#[derive(Clone, Copy, Debug, Hash, Eq, PartialEq, Serialize, Deserialize)]
+pub enum ReturnOperand {
+    Constant(U256),
+    Return { call_idx: u16, return_idx: u16 },
+}
+
+#[derive(Clone, Copy, Debug, Hash, Eq, PartialEq, Serialize, Deserialize)]
+pub enum Comparison {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Comparison {
+    pub fn holds(self, lhs: U256, rhs: U256) -> bool {
+        match self {
+            Self::Eq => lhs == rhs,
+            Self::Ne => lhs != rhs,
+            Self::Lt => lhs < rhs,
+            Self::Le => lhs <= rhs,
+            Self::Gt => lhs > rhs,
+            Self::Ge => lhs >= rhs,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Eq => "==",
+            Self::Ne => "!=",
+            Self::Lt => "<",
+            Self::Le => "<=",
+            Self::Gt => ">",
+            Self::Ge => ">=",
         }
     }
+}
+
+impl fmt::Display for Comparison {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Comparison {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        Ok(match s {
+            "==" => Self::Eq,
+            "!=" => Self::Ne,
+            "<" => Self::Lt,
+            "<=" => Self::Le,
+            ">" => Self::Gt,
+            ">=" => Self::Ge,
+            _ => anyhow::bail!("unknown comparison {}, expected ==, !=, <, <=, > or >=", s),
+        })
+    }
+}
+
+/// Compile `sequence` into a script calling its functions in order, with
+/// `modules` as the composer's view of the chain.
+pub fn compile_sequence(sequence: &ScriptSequence, modules: &HashMap<ModuleId, Bytes>) -> Result<Script, FuzzerError> {
+    let mut composer = TransactionComposer::multi_signer(sequence.signer_count());
+    for (module_id, bytes) in modules {
+        composer
+            .store_module(bytes.to_vec())
+            .map_err(|err| FuzzerError::ComposerModule {
+                module: module_id.to_string(),
+                reason: err.to_string(),
+            })?;
+    }
 
     for call in sequence.calls() {
+        let function = format!("{}::{}", call.module(), call.function());
         let args = call
             .args()
             .iter()
             .map(|arg| match arg {
                 SequenceArgument::Signer(idx) => Some(CallArgument::new_signer(*idx)),
                 SequenceArgument::Raw { bytes, .. } => Some(CallArgument::new_bytes(bytes.clone())),
-                SequenceArgument::PreviousResult { call_idx, return_idx } => {
-                    build_previous_result(*call_idx, *return_idx).map(CallArgument::PreviousResult)
-                }
+                SequenceArgument::PreviousResult {
+                    call_idx,
+                    return_idx,
+                    operation,
+                } => build_previous_result(*call_idx, *return_idx, *operation).map(CallArgument::PreviousResult),
+                SequenceArgument::SameAs { call_idx, arg_idx } => sequence
+                    .resolve_argument(*call_idx, *arg_idx)
+                    .map(|(bytes, _)| CallArgument::new_bytes(bytes.to_vec())),
             })
-            .collect::<Option<Vec<_>>>();
-        let Some(args) = args else {
-            warn!(
-                "[aptos-fuzzer] failed to rebuild arguments for {}::{}",
-                call.module(),
-                call.function()
-            );
-            return None;
-        };
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(|| FuzzerError::SequenceArguments {
+                function: function.clone(),
+            })?;
 
         let ty_args: Vec<String> = call.ty_args().iter().map(|tag| tag.to_canonical_string()).collect();
 
         let module_str = call.module().short_str_lossless();
-        if let Err(err) = composer.add_batched_call(module_str, call.function().to_string(), ty_args, args) {
-            warn!(
-                "[aptos-fuzzer] failed to add batched call {}::{}: {}",
-                call.module(),
-                call.function(),
-                err
-            );
-            return None;
-        }
+        composer
+            .add_batched_call(module_str, call.function().to_string(), ty_args, args)
+            .map_err(|err| FuzzerError::BatchedCall {
+                function,
+                reason: err.to_string(),
+            })?;
     }
 
-    let txn_args = match collect_txn_args(sequence) {
-        Some(args) => args,
-        None => return None,
+    let txn_args = collect_txn_args(sequence).ok_or(FuzzerError::TransactionArguments)?;
+
+    let bytes = composer
+        .generate_batched_calls(true)
+        .map_err(|err| FuzzerError::ScriptGeneration(err.to_string()))?;
+    let script = bcs::from_bytes::<Script>(&bytes).map_err(|err| FuzzerError::ScriptDecoding(err.to_string()))?;
+    let (code, ty_args, _old_args) = script.into_inner();
+    Ok(Script::new(code, ty_args, txn_args))
+}
+
+#[cfg(test)]
+mod tests {
+    use aptos_move_core_types::account_address::AccountAddress;
+    use aptos_move_core_types::identifier::Identifier;
+    use aptos_move_core_types::language_storage::{ModuleId, TypeTag};
+    use aptos_move_core_types::u256::U256;
+
+    use super::{
+        Comparison, ResultOperation, ReturnAssertion, ReturnOperand, ScriptSequence, SequenceArgument, SequenceCall,
     };
 
-    match composer.generate_batched_calls(true) {
-        Ok(bytes) => match bcs::from_bytes::<Script>(&bytes) {
-            Ok(script) => {
-                let (code, ty_args, _old_args) = script.into_inner();
-                Some(Script::new(code, ty_args, txn_args))
-            }
-            Err(err) => {
-                warn!("[aptos-fuzzer] failed to decode generated script payload: {}", err);
-                None
-            }
-        },
-        Err(err) => {
-            warn!(
-                "[aptos-fuzzer] failed to serialize script sequence into script: {}",
-                err
-            );
-            None
+    fn call(function: &str, args: Vec<SequenceArgument>) -> SequenceCall {
+        let module = ModuleId::new(
+            AccountAddress::from_hex_literal("0xcafe").unwrap(),
+            Identifier::new("m").unwrap(),
+        );
+        SequenceCall::new(module, Identifier::new(function).unwrap(), Vec::new(), args)
+    }
+
+    fn raw(value: u64) -> SequenceArgument {
+        SequenceArgument::Raw {
+            bytes: value.to_le_bytes().to_vec(),
+            ty: TypeTag::U64,
+        }
+    }
+
+    fn result(call_idx: u16, operation: ResultOperation) -> SequenceArgument {
+        SequenceArgument::PreviousResult {
+            call_idx,
+            return_idx: 0,
+            operation,
         }
     }
+
+    fn sequence(calls: Vec<SequenceCall>) -> ScriptSequence {
+        let mut sequence = ScriptSequence::new();
+        for call in calls {
+            sequence.push_call(call);
+        }
+        sequence
+    }
+
+    /// `make() -> take(&make) -> take(make)`
+    fn producer_and_consumers() -> ScriptSequence {
+        sequence(vec![
+            call("make", vec![raw(1)]),
+            call("borrow", vec![result(0, ResultOperation::Borrow)]),
+            call("take", vec![result(0, ResultOperation::Move)]),
+        ])
+    }
+
+    fn functions(sequence: &ScriptSequence) -> Vec<&str> {
+        sequence.calls().iter().map(|call| call.function().as_str()).collect()
+    }
+
+    #[test]
+    fn swap_repoints_results_at_the_producer() {
+        let base = sequence(vec![
+            call("make", vec![raw(1)]),
+            call("other", vec![]),
+            call("take", vec![result(0, ResultOperation::Move)]),
+        ]);
+        let swapped = ScriptSequence::rearrange(&[&base], &[(0, 1), (0, 0), (0, 2)], |_, _| None).unwrap();
+        assert_eq!(functions(&swapped), ["other", "make", "take"]);
+        assert_eq!(swapped.calls()[2].args(), [result(1, ResultOperation::Move)]);
+    }
+
+    #[test]
+    fn deleting_the_producer_replaces_its_results() {
+        let base = producer_and_consumers();
+        let deleted = ScriptSequence::rearrange(&[&base], &[(0, 1), (0, 2)], |producer, return_idx| {
+            assert_eq!(producer.function().as_str(), "make");
+            assert_eq!(return_idx, 0);
+            Some(raw(7))
+        })
+        .unwrap();
+        assert_eq!(functions(&deleted), ["borrow", "take"]);
+        assert_eq!(deleted.calls()[0].args(), [raw(7)]);
+        assert_eq!(deleted.calls()[1].args(), [raw(7)]);
+    }
+
+    #[test]
+    fn rearrangement_aborts_without_a_replacement() {
+        let base = producer_and_consumers();
+        assert!(ScriptSequence::rearrange(&[&base], &[(0, 2)], |_, _| None).is_none());
+    }
+
+    #[test]
+    fn duplicated_consumers_both_take_the_result() {
+        let base = producer_and_consumers();
+        let order = [(0, 0), (0, 1), (0, 2), (0, 2)];
+        let duplicated = ScriptSequence::rearrange(&[&base], &order, |_, _| None).unwrap();
+        assert_eq!(functions(&duplicated), ["make", "borrow", "take", "take"]);
+        // Moved twice: left for the repair step to fix
+        assert_eq!(duplicated.calls()[2].args(), duplicated.calls()[3].args());
+        assert_eq!(duplicated.moved_results().into_iter().collect::<Vec<_>>(), [(0, 0)]);
+    }
+
+    #[test]
+    fn splice_keeps_donor_results_within_the_donor_calls() {
+        let base = sequence(vec![call("first", vec![]), call("last", vec![])]);
+        let donor = producer_and_consumers();
+        let order = [(0, 0), (1, 0), (1, 2), (0, 1)];
+        let spliced = ScriptSequence::rearrange(&[&base, &donor], &order, |_, _| None).unwrap();
+        assert_eq!(functions(&spliced), ["first", "make", "take", "last"]);
+        assert_eq!(spliced.calls()[2].args(), [result(1, ResultOperation::Move)]);
+    }
+
+    #[test]
+    fn same_as_becomes_a_literal_once_its_call_is_gone() {
+        let base = sequence(vec![
+            call("a", vec![raw(5)]),
+            call(
+                "b",
+                vec![SequenceArgument::SameAs {
+                    call_idx: 0,
+                    arg_idx: 0,
+                }],
+            ),
+        ]);
+        let rearranged = ScriptSequence::rearrange(&[&base], &[(0, 1)], |_, _| None).unwrap();
+        assert_eq!(rearranged.calls()[0].args(), [raw(5)]);
+    }
+
+    #[test]
+    fn assertions_on_dropped_calls_are_dropped() {
+        let assertion = |expected| ReturnAssertion {
+            return_idx: 0,
+            comparison: Comparison::Eq,
+            expected,
+        };
+        let base = sequence(vec![
+            call("a", vec![]),
+            call("b", vec![]).with_assertions(vec![
+                assertion(ReturnOperand::Return {
+                    call_idx: 0,
+                    return_idx: 0,
+                }),
+                assertion(ReturnOperand::Constant(U256::from(3u64))),
+            ]),
+        ]);
+        let rearranged = ScriptSequence::rearrange(&[&base], &[(0, 1)], |_, _| None).unwrap();
+        assert_eq!(
+            rearranged.calls()[0].assertions(),
+            [assertion(ReturnOperand::Constant(U256::from(3u64)))]
+        );
+    }
 }
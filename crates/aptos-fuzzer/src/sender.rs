@@ -0,0 +1,280 @@
+use std::str::FromStr;
+
+use anyhow::{anyhow, bail, Context};
+use aptos_move_core_types::account_address::AccountAddress;
+use aptos_move_core_types::identifier::{IdentStr, Identifier};
+use aptos_move_core_types::language_storage::ModuleId;
+use aptos_types::transaction::TransactionPayload;
+use serde::{Deserialize, Serialize};
+
+use crate::input::AptosFuzzerInput;
+use crate::state::AptosFuzzerState;
+
+/// Who sends the transactions of inputs that don't name their sender.
+/// Targets often behave completely differently for their admin and for
+/// other users.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SenderStrategy {
+    /// Always `0x1`
+    #[default]
+    Fixed,
+    /// The account the called module is published at
+    Deployer,
+    /// A member of the state's address pool drawn for every input
+    Random,
+    /// The members of the state's address pool in turn, one per input
+    Rotate,
+}
+
+impl SenderStrategy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Fixed => "fixed",
+            Self::Deployer => "deployer",
+            Self::Random => "random",
+            Self::Rotate => "rotate",
+        }
+    }
+}
+
+impl FromStr for SenderStrategy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "fixed" => Ok(Self::Fixed),
+            "deployer" => Ok(Self::Deployer),
+            "random" => Ok(Self::Random),
+            "rotate" => Ok(Self::Rotate),
+            _ => bail!(
+                "unknown sender strategy {}, expected fixed, deployer, random or rotate",
+                s
+            ),
+        }
+    }
+}
+
+/// The sender of every call into one function, e.g. `pool::set_fee=0xcafe`
+/// for the admin, whatever the strategy and the input's own sender.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PinnedSender {
+    address: Option<AccountAddress>,
+    module: Identifier,
+    function: Identifier,
+    sender: AccountAddress,
+}
+
+impl PinnedSender {
+    pub fn applies_to(&self, module: &ModuleId, function: &IdentStr) -> bool {
+        self.address.is_none_or(|address| address == *module.address()) &&
+            module.name() == self.module.as_ident_str() &&
+            function == self.function.as_ident_str()
+    }
+
+    pub fn sender(&self) -> AccountAddress {
+        self.sender
+    }
+}
+
+impl FromStr for PinnedSender {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let (path, sender) = s
+            .trim()
+            .split_once('=')
+            .ok_or_else(|| anyhow!("expected [<address>::]<module>::<function>=<sender>, got {}", s))?;
+        let parts: Vec<&str> = path.trim().split("::").collect();
+        let (address, module, function) = match parts.as_slice() {
+            [module, function] => (None, *module, *function),
+            [address, module, function] => (Some(AccountAddress::from_hex_literal(address)?), *module, *function),
+            _ => bail!("expected [<address>::]<module>::<function>=<sender>, got {}", s),
+        };
+        let sender = sender.trim();
+        Ok(Self {
+            address,
+            module: Identifier::new(module)?,
+            function: Identifier::new(function)?,
+            sender: AccountAddress::from_hex_literal(sender).with_context(|| format!("invalid sender {}", sender))?,
+        })
+    }
+}
+
+/// Picks the sender of each input: the pinned sender of the first function
+/// it calls that has one, else the input's own sender, else the one
+/// the strategy picks. The sender is stored on the input, so corpus entries
+/// and solutions replay with the sender they were found with.
+#[derive(Clone, Debug, Default)]
+pub struct SenderPolicy {
+    strategy: SenderStrategy,
+    pinned: Vec<PinnedSender>,
+    /// Inputs sent so far under `Rotate`
+    rotation: usize,
+}
+
+impl SenderPolicy {
+    pub fn new(strategy: SenderStrategy, pinned: Vec<PinnedSender>) -> Self {
+        Self {
+            strategy,
+            pinned,
+            rotation: 0,
+        }
+    }
+
+    pub fn strategy(&self) -> SenderStrategy {
+        self.strategy
+    }
+
+    /// Make the sender this policy picks the sender of `input`.
+    pub fn assign(&mut self, input: &mut AptosFuzzerInput, state: &mut AptosFuzzerState) {
+        let sender = self.sender(input, state);
+        input.set_sender(sender);
+    }
+
+    fn sender(&mut self, input: &AptosFuzzerInput, state: &mut AptosFuzzerState) -> AccountAddress {
+        let called = Self::called_functions(input);
+        if let Some(pinned) = called
+            .iter()
+            .find_map(|(module, function)| self.pinned.iter().find(|pinned| pinned.applies_to(module, function)))
+        {
+            return pinned.sender;
+        }
+        if let Some(sender) = input.assigned_sender() {
+            return sender;
+        }
+        let sender = match self.strategy {
+            SenderStrategy::Fixed => None,
+            SenderStrategy::Deployer => called.first().map(|(module, _)| *module.address()),
+            SenderStrategy::Random => state.random_pool_address(),
+            SenderStrategy::Rotate => {
+                let sender = state.pool_address(self.rotation);
+                self.rotation = self.rotation.wrapping_add(1);
+                sender
+            }
+        };
+        sender.unwrap_or_else(|| input.sender())
+    }
+
+    /// The entry function `input` calls, or the calls of its sequence.
+    fn called_functions(input: &AptosFuzzerInput) -> Vec<(&ModuleId, &IdentStr)> {
+        match (input.payload(), input.script_sequence()) {
+            (TransactionPayload::EntryFunction(entry), _) => vec![(entry.module(), entry.function())],
+            (_, Some(sequence)) => sequence
+                .calls()
+                .iter()
+                .map(|call| (call.module(), call.function().as_ident_str()))
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use aptos_move_core_types::account_address::AccountAddress;
+    use aptos_move_core_types::identifier::Identifier;
+    use aptos_move_core_types::language_storage::ModuleId;
+    use aptos_types::transaction::{EntryFunction, TransactionPayload};
+
+    use super::{PinnedSender, SenderPolicy, SenderStrategy};
+    use crate::input::AptosFuzzerInput;
+    use crate::state::AptosFuzzerState;
+
+    fn cafe() -> AccountAddress {
+        AccountAddress::from_hex_literal("0xcafe").unwrap()
+    }
+
+    fn input(function: &str) -> AptosFuzzerInput {
+        AptosFuzzerInput::new(TransactionPayload::EntryFunction(EntryFunction::new(
+            ModuleId::new(cafe(), Identifier::new("pool").unwrap()),
+            Identifier::new(function).unwrap(),
+            Vec::new(),
+            Vec::new(),
+        )))
+    }
+
+    /// The sender `policy` assigns to a fresh input calling `function`.
+    fn assign(policy: &mut SenderPolicy, state: &mut AptosFuzzerState, function: &str) -> AccountAddress {
+        let mut input = input(function);
+        policy.assign(&mut input, state);
+        input.sender()
+    }
+
+    #[test]
+    fn strategy_names_round_trip() {
+        for strategy in [
+            SenderStrategy::Fixed,
+            SenderStrategy::Deployer,
+            SenderStrategy::Random,
+            SenderStrategy::Rotate,
+        ] {
+            assert_eq!(strategy.as_str().parse::<SenderStrategy>().unwrap(), strategy);
+        }
+        assert!("admin".parse::<SenderStrategy>().is_err());
+    }
+
+    #[test]
+    fn parses_pinned_senders() {
+        let pinned: PinnedSender = "pool::set_fee = 0xbeef".parse().unwrap();
+        assert_eq!(pinned.sender(), AccountAddress::from_hex_literal("0xbeef").unwrap());
+        let module = ModuleId::new(cafe(), Identifier::new("pool").unwrap());
+        assert!(pinned.applies_to(&module, Identifier::new("set_fee").unwrap().as_ident_str()));
+        assert!(!pinned.applies_to(&module, Identifier::new("swap").unwrap().as_ident_str()));
+        let elsewhere: PinnedSender = "0x1::pool::set_fee=0xbeef".parse().unwrap();
+        assert!(!elsewhere.applies_to(&module, Identifier::new("set_fee").unwrap().as_ident_str()));
+        for s in ["pool::set_fee", "set_fee=0xbeef", "pool::set_fee=admin"] {
+            assert!(s.parse::<PinnedSender>().is_err(), "{}", s);
+        }
+    }
+
+    #[test]
+    fn fixed_sends_from_0x1_unless_the_input_has_a_sender() {
+        let mut state = AptosFuzzerState::new(PathBuf::new());
+        let mut policy = SenderPolicy::new(SenderStrategy::Fixed, Vec::new());
+        assert_eq!(assign(&mut policy, &mut state, "swap"), AccountAddress::ONE);
+        let mut sent = input("swap");
+        sent.set_sender(cafe());
+        policy.assign(&mut sent, &mut state);
+        assert_eq!(sent.sender(), cafe());
+    }
+
+    #[test]
+    fn deployer_sends_from_the_module_address() {
+        let mut state = AptosFuzzerState::new(PathBuf::new());
+        let mut policy = SenderPolicy::new(SenderStrategy::Deployer, Vec::new());
+        assert_eq!(assign(&mut policy, &mut state, "swap"), cafe());
+    }
+
+    #[test]
+    fn random_and_rotate_draw_from_the_address_pool() {
+        let mut state = AptosFuzzerState::new(PathBuf::new());
+        state.add_pool_address(cafe());
+        let mut random = SenderPolicy::new(SenderStrategy::Random, Vec::new());
+        for _ in 0..16 {
+            let sender = assign(&mut random, &mut state, "swap");
+            assert!(state.address_pool().contains(&sender));
+        }
+        let mut rotate = SenderPolicy::new(SenderStrategy::Rotate, Vec::new());
+        let pool_size = state.address_pool().len();
+        for idx in 0..pool_size * 2 {
+            let expected = state.pool_address(idx).unwrap();
+            assert_eq!(assign(&mut rotate, &mut state, "swap"), expected);
+        }
+    }
+
+    #[test]
+    fn pinned_senders_override_everything_else() {
+        let mut state = AptosFuzzerState::new(PathBuf::new());
+        let admin = AccountAddress::from_hex_literal("0xbeef").unwrap();
+        let mut policy = SenderPolicy::new(SenderStrategy::Deployer, vec!["pool::set_fee=0xbeef".parse().unwrap()]);
+        assert_eq!(assign(&mut policy, &mut state, "set_fee"), admin);
+        assert_eq!(assign(&mut policy, &mut state, "swap"), cafe());
+        let mut sent = input("set_fee");
+        sent.set_sender(cafe());
+        policy.assign(&mut sent, &mut state);
+        assert_eq!(sent.sender(), admin);
+    }
+}
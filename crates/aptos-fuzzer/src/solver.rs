@@ -0,0 +1,159 @@
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use z3::ast::{Ast, Bool, Int};
+use z3::{Params, SatResult, Solver};
+
+/// Wall-clock limit of a single solver query
+pub const DEFAULT_QUERY_TIMEOUT: Duration = Duration::from_millis(200);
+/// Queries allowed per fuzzing iteration
+pub const DEFAULT_QUERIES_PER_ITERATION: usize = 32;
+/// Answers kept in the cache before the oldest are evicted
+const CACHE_ENTRIES: usize = 4096;
+
+/// Limits on constraint solving.
+#[derive(Clone, Copy, Debug)]
+pub struct SolverConfig {
+    pub query_timeout: Duration,
+    pub queries_per_iteration: usize,
+}
+
+impl Default for SolverConfig {
+    fn default() -> Self {
+        Self {
+            query_timeout: DEFAULT_QUERY_TIMEOUT,
+            queries_per_iteration: DEFAULT_QUERIES_PER_ITERATION,
+        }
+    }
+}
+
+/// Solver usage over the campaign.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct SolverStats {
+    /// Queries sent to the solver, excluding cache hits
+    pub queries: u64,
+    pub sat: u64,
+    pub unsat: u64,
+    /// Timed out or gave up
+    pub unknown: u64,
+    pub cache_hits: u64,
+    /// Queries refused because the iteration's budget was used up
+    pub over_budget: u64,
+    /// Time spent in the solver
+    pub time: Duration,
+}
+
+/// Result of a query. `Sat` holds the value of each requested variable,
+/// in decimal, by name.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SolveOutcome {
+    Sat(Vec<(String, String)>),
+    Unsat,
+    Unknown,
+    OverBudget,
+}
+
+/// Answers path-constraint queries for the concolic stage, within a
+/// per-query timeout and a per-iteration query budget. Answers are cached by
+/// the simplified constraint, so the same branch condition reached from
+/// different inputs is only solved once, up to `CACHE_ENTRIES` answers.
+pub struct ConcolicSolver {
+    config: SolverConfig,
+    solver: Solver,
+    cache: HashMap<String, SolveOutcome>,
+    /// Cached keys, oldest first
+    cache_order: VecDeque<String>,
+    remaining: usize,
+    stats: SolverStats,
+}
+
+impl ConcolicSolver {
+    pub fn new(config: SolverConfig) -> Self {
+        let solver = Solver::new();
+        let mut params = Params::new();
+        params.set_u32("timeout", config.query_timeout.as_millis().min(u32::MAX as u128) as u32);
+        solver.set_params(&params);
+        Self {
+            config,
+            solver,
+            cache: HashMap::new(),
+            cache_order: VecDeque::new(),
+            remaining: config.queries_per_iteration,
+            stats: SolverStats::default(),
+        }
+    }
+
+    pub fn config(&self) -> &SolverConfig {
+        &self.config
+    }
+
+    pub fn stats(&self) -> &SolverStats {
+        &self.stats
+    }
+
+    /// Refill the query budget; call once per fuzzing iteration.
+    pub fn begin_iteration(&mut self) {
+        self.remaining = self.config.queries_per_iteration;
+    }
+
+    /// Find values of `variables` satisfying `constraint`.
+    pub fn solve(&mut self, constraint: &Bool, variables: &[Int]) -> SolveOutcome {
+        let names: Vec<String> = variables.iter().map(|variable| variable.to_string()).collect();
+        let key = format!("{}|{}", constraint.simplify(), names.join(","));
+        if let Some(outcome) = self.cache.get(&key) {
+            self.stats.cache_hits += 1;
+            return outcome.clone();
+        }
+        if self.remaining == 0 {
+            self.stats.over_budget += 1;
+            return SolveOutcome::OverBudget;
+        }
+        self.remaining -= 1;
+
+        let start = Instant::now();
+        self.solver.push();
+        self.solver.assert(constraint);
+        let outcome = match self.solver.check() {
+            SatResult::Sat => {
+                let model = self.solver.get_model();
+                let values = variables
+                    .iter()
+                    .zip(names)
+                    .filter_map(|(variable, name)| {
+                        let value = model.as_ref()?.eval(variable, true)?;
+                        Some((name, value.to_string()))
+                    })
+                    .collect();
+                SolveOutcome::Sat(values)
+            }
+            SatResult::Unsat => SolveOutcome::Unsat,
+            SatResult::Unknown => SolveOutcome::Unknown,
+        };
+        self.solver.pop(1);
+        self.stats.time += start.elapsed();
+        self.stats.queries += 1;
+        match outcome {
+            SolveOutcome::Sat(_) => self.stats.sat += 1,
+            SolveOutcome::Unsat => self.stats.unsat += 1,
+            _ => self.stats.unknown += 1,
+        }
+        // A timeout may succeed with more budget later; only cache answers
+        if !matches!(outcome, SolveOutcome::Unknown) {
+            if self.cache.len() >= CACHE_ENTRIES {
+                if let Some(oldest) = self.cache_order.pop_front() {
+                    self.cache.remove(&oldest);
+                }
+            }
+            self.cache_order.push_back(key.clone());
+            self.cache.insert(key, outcome.clone());
+        }
+        outcome
+    }
+}
+
+impl Default for ConcolicSolver {
+    fn default() -> Self {
+        Self::new(SolverConfig::default())
+    }
+}
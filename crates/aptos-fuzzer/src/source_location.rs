@@ -0,0 +1,141 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::{fmt, fs};
+
+use anyhow::Context;
+use aptos_move_binary_format::access::ModuleAccess;
+use aptos_move_binary_format::file_format::{CodeOffset, FunctionDefinitionIndex};
+use aptos_move_binary_format::CompiledModule;
+use aptos_move_core_types::language_storage::ModuleId;
+use move_bytecode_source_map::source_map::SourceMap;
+use move_command_line_common::files::FileHash;
+use move_ir_types::location::Loc;
+use serde::{Deserialize, Serialize};
+
+/// A position in a Move source file.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SourceLocation {
+    pub file: PathBuf,
+    /// 1-based
+    pub line: usize,
+}
+
+impl fmt::Display for SourceLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.file.display(), self.line)
+    }
+}
+
+struct ModuleSourceMap {
+    source_map: SourceMap,
+    functions: HashMap<String, FunctionDefinitionIndex>,
+}
+
+struct SourceFile {
+    path: PathBuf,
+    /// Byte offset at which each line starts
+    line_starts: Vec<u32>,
+}
+
+/// Maps bytecode positions of modules built from source back to `file:line`.
+/// Empty (resolving nothing) unless source maps and sources were loaded.
+#[derive(Default)]
+pub struct SourceLocator {
+    modules: HashMap<ModuleId, ModuleSourceMap>,
+    files: HashMap<FileHash, SourceFile>,
+}
+
+impl SourceLocator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_source_map(&mut self, module: &CompiledModule, source_map: SourceMap) {
+        let functions = module
+            .function_defs()
+            .iter()
+            .enumerate()
+            .map(|(idx, def)| {
+                let name = module.identifier_at(module.function_handle_at(def.function).name);
+                (name.to_string(), FunctionDefinitionIndex(idx as u16))
+            })
+            .collect();
+        self.modules
+            .insert(module.self_id(), ModuleSourceMap { source_map, functions });
+    }
+
+    /// Index every `.move` file under `dir` so source map locations, which
+    /// refer to files by content hash, can be turned into paths. Returns how
+    /// many files were indexed.
+    pub fn load_sources(&mut self, dir: &Path) -> anyhow::Result<usize> {
+        let mut loaded = 0;
+        for entry in fs::read_dir(dir).with_context(|| format!("failed to read {}", dir.display()))? {
+            let path = entry?.path();
+            if path.is_dir() {
+                loaded += self.load_sources(&path)?;
+                continue;
+            }
+            if path.extension().is_none_or(|ext| ext != "move") {
+                continue;
+            }
+            let text = fs::read_to_string(&path).with_context(|| format!("failed to read {}", path.display()))?;
+            let line_starts = std::iter::once(0)
+                .chain(text.match_indices('\n').map(|(idx, _)| idx as u32 + 1))
+                .collect();
+            self.files
+                .insert(FileHash::new(&text), SourceFile { path, line_starts });
+            loaded += 1;
+        }
+        Ok(loaded)
+    }
+
+    pub fn source_map(&self, module_id: &ModuleId) -> Option<&SourceMap> {
+        self.modules.get(module_id).map(|module| &module.source_map)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.modules.is_empty()
+    }
+
+    /// Where the module is declared.
+    pub fn module_location(&self, module_id: &ModuleId) -> Option<SourceLocation> {
+        self.resolve(self.modules.get(module_id)?.source_map.definition_location)
+    }
+
+    /// Where `function` is declared.
+    pub fn function_location(&self, module_id: &ModuleId, function: &str) -> Option<SourceLocation> {
+        let module = self.modules.get(module_id)?;
+        let idx = module.functions.get(function)?;
+        let function_map = module.source_map.get_function_source_map(*idx).ok()?;
+        self.resolve(function_map.definition_location)
+    }
+
+    /// Names of the parameters of `function`, signers included, in order.
+    pub fn parameter_names(&self, module_id: &ModuleId, function: &str) -> Option<Vec<String>> {
+        let module = self.modules.get(module_id)?;
+        let idx = module.functions.get(function)?;
+        let function_map = module.source_map.get_function_source_map(*idx).ok()?;
+        Some(function_map.parameters.iter().map(|(name, _)| name.clone()).collect())
+    }
+
+    /// The source of the instruction at `pc` in `function`, falling back to
+    /// the function declaration when the instruction has no entry.
+    pub fn code_location(&self, module_id: &ModuleId, function: &str, pc: CodeOffset) -> Option<SourceLocation> {
+        let module = self.modules.get(module_id)?;
+        let idx = module.functions.get(function)?;
+        let function_map = module.source_map.get_function_source_map(*idx).ok()?;
+        let loc = function_map
+            .get_code_location(pc)
+            .unwrap_or(function_map.definition_location);
+        self.resolve(loc)
+    }
+
+    fn resolve(&self, loc: Loc) -> Option<SourceLocation> {
+        let file = self.files.get(&loc.file_hash())?;
+        let line = file.line_starts.partition_point(|start| *start <= loc.start());
+        Some(SourceLocation {
+            file: file.path.clone(),
+            line,
+        })
+    }
+}
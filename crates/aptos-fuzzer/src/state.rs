@@ -1,36 +1,77 @@
+use std::borrow::Cow;
 use std::cell::{Ref, RefMut};
-use std::collections::{HashMap, HashSet};
-use std::fs;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::path::{Component, Path, PathBuf};
 use std::time::Duration;
+use std::{fmt, fs};
 
+use anyhow::Context;
 use aptos_move_binary_format::access::ModuleAccess;
 use aptos_move_binary_format::file_format::{SignatureToken, StructHandleIndex, Visibility};
 use aptos_move_binary_format::CompiledModule;
 use aptos_move_core_types::account_address::AccountAddress;
-use aptos_move_core_types::identifier::Identifier;
+use aptos_move_core_types::identifier::{IdentStr, Identifier};
 use aptos_move_core_types::language_storage::{ModuleId, StructTag, TypeTag};
 use aptos_move_core_types::u256::U256;
+use aptos_types::contract_event::ContractEvent;
+use aptos_types::state_store::state_key::inner::StateKeyInner;
 use aptos_types::transaction::{EntryFunction as AptosEntryFunction, TransactionPayload};
+use aptos_types::vm::module_metadata::get_metadata_from_compiled_code;
+use aptos_types::write_set::WriteSet;
 use libafl::corpus::{Corpus, CorpusId, HasCurrentCorpusId, HasTestcase, InMemoryCorpus, Testcase};
+use libafl::feedbacks::MapIndexesMetadata;
 use libafl::stages::StageId;
 use libafl::state::{
     HasCorpus, HasCurrentStageId, HasExecutions, HasImported, HasLastFoundTime, HasLastReportTime, HasRand,
     HasSolutions, HasStartTime, StageStack, Stoppable,
 };
 use libafl::{HasMetadata, HasNamedMetadata};
-use libafl_bolts::rands::StdRand;
+use libafl_bolts::rands::{Rand, StdRand};
 use libafl_bolts::serdeany::{NamedSerdeAnyMap, SerdeAnyMap};
-
-use crate::concolic::RuntimeIssue;
+use log::debug;
+use move_bytecode_source_map::source_map::SourceMap;
+
+#[cfg(feature = "concolic")]
+use crate::abort_target::{AbortGuards, AbortSearch, AbortTarget};
+use crate::arg_constraint::ArgConstraint;
+use crate::branches::{BranchCoverage, RankedBranch};
+use crate::concolic::{RuntimeIssue, RuntimeIssueKind, Severity, ShiftLoss};
+use crate::conflicts::{AccessSets, ConflictAnalysis, GlobalAccess};
+use crate::error::{FuzzerError, Subsystem};
 use crate::executor::aptos_custom_state::AptosCustomState;
+use crate::executor::edge_layout::function_hash;
+use crate::generics::{GenericFunctionTarget, InstantiationSchedule};
 use crate::input::AptosFuzzerInput;
-use crate::script_sequence::{compile_sequence, ScriptSequence};
-use crate::static_analysis::StaticAnalysisFinding;
-
-// AFL-style map size constant
+use crate::mutator::{encode_vector, MutationKind, MutationWeights};
+use crate::observers::EmittedEvent;
+use crate::oracle::{
+    AssetConservationOracle, InvariantOracle, ReturnProperty, RoundTrip, SignerOwnershipOracle, ViewMonotonicity,
+    Violation,
+};
+use crate::resource_leak::{ResourceLeak, ResourceLeakOracle};
+use crate::script_sequence::{compile_sequence, ResultOperation, ScriptSequence, SequenceArgument, SequenceCall};
+use crate::sender::SenderPolicy;
+#[cfg(feature = "concolic")]
+use crate::solver::{ConcolicSolver, SolverConfig, SolverStats};
+use crate::source_location::{SourceLocation, SourceLocator};
+use crate::static_analysis::{FunctionRef, StaticAnalysisFinding};
+use crate::struct_layout::StructLayoutRegistry;
+#[cfg(feature = "concolic")]
+use crate::symbolic::SymbolicDivergence;
+use crate::trace::AbortSite;
+use crate::upgrade::ModuleUpgrade;
+
+// Default AFL-style map size
 pub const MAP_SIZE: usize = 1 << 16;
 
+/// Upper bound on the address pool, so write sets of long campaigns don't
+/// grow it forever.
+const MAX_ADDRESS_POOL: usize = 4096;
+/// Last byte of the framework addresses always in the address pool: `0x0`,
+/// `0x1`, `0x3`, `0x4` and `0xa` (the APT fungible asset metadata).
+const FRAMEWORK_ADDRESS_BYTES: [u8; 5] = [0x0, 0x1, 0x3, 0x4, 0xa];
+
 // Similar to libafl::state::StdState
 pub struct AptosFuzzerState {
     // RNG instance
@@ -75,35 +116,203 @@ pub struct AptosFuzzerState {
     cumulative_coverage: Vec<u8>,
     /// Execution path IDs that triggered abort-code objectives
     pub abort_code_paths: HashSet<u64>,
-    /// Execution path IDs that triggered shift overflow objectives
-    pub shift_overflow_paths: HashSet<u64>,
+    /// Execution path IDs that crashed the VM
+    pub crash_paths: HashSet<u64>,
+    /// Execution path IDs that triggered shift overflow objectives, with
+    /// the shifts that lost bits
+    pub shift_overflow_paths: HashMap<u64, Vec<ShiftLoss>>,
+    /// Execution path IDs that exhausted the per-execution budget
+    pub budget_exhausted_paths: HashSet<u64>,
+    /// Oracle violations reported by each execution path that triggered them
+    pub oracle_violation_paths: HashMap<u64, Vec<Violation>>,
+    /// Runtime issues of each execution path reported as a solution
+    pub runtime_issue_paths: HashMap<u64, Vec<RuntimeIssue>>,
+    /// Every distinct runtime issue seen, including those below the
+    /// objective's severity threshold
+    runtime_findings: Vec<RuntimeFinding>,
+    runtime_finding_index: HashMap<(RuntimeIssueKind, String, String, u16), usize>,
+    /// Differential-execution divergences by the execution path that caused
+    /// them
+    pub divergence_paths: HashMap<u64, String>,
+    /// Orphaned and double-created resources of each execution path
+    /// reported as a solution
+    pub resource_leak_paths: HashMap<u64, Vec<ResourceLeak>>,
+    /// Abort site and call stack of each execution path reported as an abort
+    pub abort_site_paths: HashMap<u64, AbortSite>,
+    /// Abort sites already reported, call stacks included
+    seen_abort_sites: HashSet<AbortSite>,
     /// Modules explicitly loaded for fuzzing
     target_modules: Vec<ModuleId>,
+    /// Source maps of target modules built from source
+    source_locator: SourceLocator,
     /// Static analysis findings discovered before fuzzing
     static_findings: Vec<StaticAnalysisFinding>,
     last_runtime_issues: Vec<RuntimeIssue>,
+    /// Instructions the last execution ran
+    last_instructions: u64,
+    /// Whether the VM rejected the last transaction before running any of
+    /// it, e.g. for a missing function or arguments that don't decode
+    last_rejected: bool,
+    /// Account the last transaction was sent from
+    last_sender: AccountAddress,
+    /// Picks the sender stored on new and mutated inputs
+    sender_policy: SenderPolicy,
+    /// Set when the target state changed under the corpus, cleared by the
+    /// revalidation stage
+    revalidation_requested: bool,
+    /// Corpus entries the last revalidation kept and dropped, until taken
+    revalidated: Option<(usize, usize)>,
+    /// Invariant oracles checked after every successful execution
+    oracles: Vec<Box<dyn InvariantOracle>>,
+    /// Asserted on the returns of the sequence calls the mutator generates
+    return_properties: Vec<ReturnProperty>,
+    /// Function pairs whose round trips must not profit the sender
+    round_trips: Vec<RoundTrip>,
+    /// How the results of view functions may change across a transaction
+    view_properties: Vec<ViewMonotonicity>,
+    /// Values callers pass as arguments of the target functions
+    arg_constraints: Vec<ArgConstraint>,
+    /// Checks that transactions only touch resources their sender owns
+    signer_ownership: Option<SignerOwnershipOracle>,
+    /// Checks that only mint and burn functions change asset supplies
+    asset_conservation: Option<AssetConservationOracle>,
+    /// Checks that sequences neither orphan resources nor create them twice
+    resource_leaks: Option<ResourceLeakOracle>,
+    last_resource_leaks: Vec<ResourceLeak>,
+    last_oracle_violations: Vec<Violation>,
+    last_divergence: Option<String>,
+    /// Events emitted by the current execution, kept with recorded paths
+    current_events: Vec<EmittedEvent>,
+    /// Field layouts of the structs declared by the published modules
+    struct_layouts: StructLayoutRegistry,
     /// Public functions discovered from loaded modules
     public_functions: Vec<PublicFunctionTarget>,
+    /// Public functions with type parameters, instantiated when called
+    generic_functions: Vec<GenericFunctionTarget>,
+    /// Type arguments the generic functions are called with
+    instantiations: InstantiationSchedule,
     /// Lookup table for module::function -> public function index
     function_lookup: HashMap<String, usize>,
+    /// `invariant_*`/`check_*` functions called after every execution
+    invariant_functions: Vec<PublicFunctionTarget>,
+    /// Public functions of the targets that aren't fuzzed, with why
+    skipped_functions: Vec<SkippedFunction>,
+    /// Calls run once against the base state before fuzzing
+    initializers: Vec<InitializerCall>,
+    /// Target modules with an `init_module` not yet run, which publishing
+    /// them runs
+    init_modules: Vec<ModuleId>,
+    /// Signer of initializer calls; the module's address when unset
+    initializer_admin: Option<AccountAddress>,
+    /// How often each kind of mutation has been applied
+    mutation_counts: BTreeMap<MutationKind, u64>,
+    /// Learned productivity of each kind of mutation, steering the mutator
+    mutation_weights: MutationWeights,
+    /// How many errors each subsystem recovered from by skipping what failed
+    suppressed_errors: BTreeMap<Subsystem, u64>,
+    /// Executions calling each function, by `<address>::<module>::<function>`
+    function_executions: BTreeMap<String, u64>,
+    /// Ways each conditional branch was taken over the campaign
+    branch_coverage: BranchCoverage,
+    /// Read and write sets of the executions, by function
+    conflict_analysis: ConflictAnalysis,
+    /// Read and write sets of the last execution
+    last_access_sets: AccessSets,
+    /// Corpus entries whose sequence a later entry extends covering all they
+    /// do, removed when the scheduler moves on
+    subsumed_prefixes: Vec<CorpusId>,
+    /// Corpus entries removed that way
+    pruned_prefixes: u64,
+    /// Constraint solver shared by the concolic stages
+    #[cfg(feature = "concolic")]
+    solver: ConcolicSolver,
+    /// Set by `--solve-abort`
+    #[cfg(feature = "concolic")]
+    abort_search: Option<AbortSearch>,
+    /// Divergences the concolic self-check found, until taken
+    #[cfg(feature = "concolic")]
+    symbolic_divergences: Vec<SymbolicDivergence>,
+    /// Instructions executed over the campaign, by function hash and pc
+    instruction_profile: HashMap<u64, HashMap<u16, u64>>,
+    /// Integer constants of the target modules, little-endian and
+    /// zero-extended to 256 bits
+    numeric_constants: Vec<[u8; 32]>,
+    /// Addresses known to matter: framework and deployer addresses, address
+    /// constants, accounts in the pre-state and addresses written to
+    address_pool: BTreeSet<AccountAddress>,
+    /// `address_pool` in insertion order, for picking one at random
+    address_list: Vec<AccountAddress>,
+}
+
+/// Edges of one fuzzed function covered so far.
+#[derive(Clone, Debug)]
+pub struct FunctionCoverage {
+    pub module_id: ModuleId,
+    pub function: Identifier,
+    pub covered: usize,
+    pub total: usize,
+}
+
+/// A function's share of the instructions executed over the campaign.
+#[derive(Clone, Debug)]
+pub struct HotFunction {
+    /// `0xADDR::module::function`, or the function hash when unknown
+    pub name: String,
+    pub instructions: u64,
+    /// Most executed pcs with their counts, hottest first; a loop shows up
+    /// as a run of pcs with about the same count
+    pub hottest_pcs: Vec<(u16, u64)>,
+}
+
+/// A distinct runtime issue seen while fuzzing: its first occurrence and how
+/// many executions reported it.
+#[derive(Clone, Debug)]
+pub struct RuntimeFinding {
+    pub issue: RuntimeIssue,
+    pub severity: Severity,
+    pub count: u64,
 }
 
 #[derive(Clone)]
 struct ExecutionPathRecord {
     id: u64,
     path: Vec<u64>,
+    events: Vec<EmittedEvent>,
 }
 
 #[derive(Clone, Debug)]
 pub struct PublicFunctionTarget {
     module_id: ModuleId,
     name: Identifier,
+    /// Type arguments of an instantiated generic function
+    ty_args: Vec<TypeTag>,
     parameters: Vec<FunctionParameter>,
     return_types: Vec<TypeTag>,
     is_entry: bool,
+    is_view: bool,
 }
 
 impl PublicFunctionTarget {
+    pub(crate) fn new(
+        module_id: ModuleId,
+        name: Identifier,
+        ty_args: Vec<TypeTag>,
+        parameters: Vec<FunctionParameter>,
+        return_types: Vec<TypeTag>,
+        is_entry: bool,
+        is_view: bool,
+    ) -> Self {
+        Self {
+            module_id,
+            name,
+            ty_args,
+            parameters,
+            return_types,
+            is_entry,
+            is_view,
+        }
+    }
+
     pub fn module_id(&self) -> &ModuleId {
         &self.module_id
     }
@@ -112,6 +321,10 @@ impl PublicFunctionTarget {
         &self.name
     }
 
+    pub fn ty_args(&self) -> &[TypeTag] {
+        &self.ty_args
+    }
+
     pub fn parameters(&self) -> &[FunctionParameter] {
         &self.parameters
     }
@@ -123,12 +336,148 @@ impl PublicFunctionTarget {
     pub fn is_entry(&self) -> bool {
         self.is_entry
     }
+
+    pub fn is_view(&self) -> bool {
+        self.is_view
+    }
+
+    /// Types of the parameters other than signers, in the order of an entry
+    /// function's arguments.
+    pub fn value_types(&self) -> Vec<&TypeTag> {
+        self.parameters
+            .iter()
+            .filter_map(|param| match param {
+                FunctionParameter::Value(tag) => Some(tag),
+                FunctionParameter::Signer | FunctionParameter::Reference { .. } => None,
+            })
+            .collect()
+    }
+
+    /// Signers the function takes.
+    pub fn signer_count(&self) -> usize {
+        self.parameters
+            .iter()
+            .filter(|param| matches!(param, FunctionParameter::Signer))
+            .count()
+    }
+
+    /// Echidna-style property: a public `invariant_*`/`check_*` function that
+    /// takes at most signers and returns nothing or a single `bool`.
+    pub fn is_invariant(&self) -> bool {
+        let name = self.name.as_str();
+        (name.starts_with("invariant_") || name.starts_with("check_")) &&
+            self.parameters
+                .iter()
+                .all(|param| matches!(param, FunctionParameter::Signer)) &&
+            (self.return_types.is_empty() || self.return_types == [TypeTag::Bool])
+    }
+}
+
+/// A function run once before fuzzing so that the target's state is set up,
+/// e.g. an `initialize` entry function. `init_module` is run by publishing
+/// its module instead.
+#[derive(Clone, Debug)]
+pub struct InitializerCall {
+    pub module_id: ModuleId,
+    pub function: Identifier,
+    /// BCS-encoded non-signer arguments
+    pub args: Vec<Vec<u8>>,
+}
+
+/// Framework structs that entry functions accept as arguments.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FrameworkStruct {
+    /// `0x1::string::String`, encoded as its UTF-8 bytes
+    String,
+    /// `0x1::option::Option<T>`, encoded as a vector of at most one `T`
+    Option(TypeTag),
+    /// `0x1::fixed_point32::FixedPoint32 { value: u64 }`
+    FixedPoint32,
+    /// `0x1::fixed_point64::FixedPoint64 { value: u128 }`
+    FixedPoint64,
+    /// `0x1::object::Object<T> { inner: address }`
+    Object(TypeTag),
+}
+
+impl FrameworkStruct {
+    pub fn classify(tag: &StructTag) -> Option<Self> {
+        if tag.address != AccountAddress::ONE {
+            return None;
+        }
+        match (tag.module.as_str(), tag.name.as_str()) {
+            ("string", "String") => Some(Self::String),
+            ("option", "Option") => tag.type_args.first().cloned().map(Self::Option),
+            ("fixed_point32", "FixedPoint32") => Some(Self::FixedPoint32),
+            ("fixed_point64", "FixedPoint64") => Some(Self::FixedPoint64),
+            ("object", "Object") => tag.type_args.first().cloned().map(Self::Object),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum FunctionParameter {
     Signer,
     Value(TypeTag),
+    /// `&T` or `&mut T` of a non-signer `T`, only passable in a sequence as
+    /// a borrowed result of an earlier call
+    Reference {
+        ty: TypeTag,
+        mutable: bool,
+    },
+}
+
+/// Why a public function of the targets isn't fuzzed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SkipReason {
+    /// Generic, with type parameters no instantiation could be found for
+    Generic,
+    /// A parameter the fuzzer can't pass, e.g. `vector<signer>`
+    Parameter(String),
+    /// A return type the fuzzer can't represent
+    Return(String),
+    /// An `invariant_*`/`check_*` function, checked after each run instead
+    Invariant,
+    /// Not reachable from an entry function, see `--fuzz-unreachable`
+    Unreachable,
+    /// No seed could be built for it, e.g. a struct parameter without a
+    /// default value
+    NoSeed(String),
+}
+
+impl SkipReason {
+    /// Short name of the kind of reason, for summaries.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            SkipReason::Generic => "generic",
+            SkipReason::Parameter(_) => "unsupported parameter",
+            SkipReason::Return(_) => "unsupported return type",
+            SkipReason::Invariant => "invariant",
+            SkipReason::Unreachable => "unreachable",
+            SkipReason::NoSeed(_) => "no seed",
+        }
+    }
+}
+
+impl fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SkipReason::Generic => write!(f, "generic, no instantiation of its type parameters"),
+            SkipReason::Parameter(ty) => write!(f, "unsupported parameter {}", ty),
+            SkipReason::Return(ty) => write!(f, "unsupported return type {}", ty),
+            SkipReason::Invariant => write!(f, "invariant, checked after each run"),
+            SkipReason::Unreachable => write!(f, "unreachable from entry functions"),
+            SkipReason::NoSeed(reason) => write!(f, "{}", reason),
+        }
+    }
+}
+
+/// A public function of the targets left out of `public_functions`.
+#[derive(Clone, Debug)]
+pub struct SkippedFunction {
+    pub module_id: ModuleId,
+    pub name: Identifier,
+    pub reason: SkipReason,
 }
 
 struct LoadedModule {
@@ -139,10 +488,22 @@ struct LoadedModule {
 
 impl AptosFuzzerState {
     pub fn new(modules_dir: PathBuf) -> Self {
-        let loaded_modules = Self::load_modules_from_path(&modules_dir);
+        Self::with_map_size(modules_dir, MAP_SIZE)
+    }
+
+    /// Create a state whose cumulative coverage map has `map_size` entries.
+    /// Must match the map size the executor was created with.
+    pub fn with_map_size(modules_dir: PathBuf, map_size: usize) -> Self {
+        Self::with_aptos_state(modules_dir, map_size, AptosCustomState::new_default())
+    }
+
+    /// Create a state that publishes the modules in `modules_dir` on top of
+    /// `aptos_state`, e.g. one forked from a live network.
+    pub fn with_aptos_state(modules_dir: PathBuf, map_size: usize, aptos_state: AptosCustomState) -> Self {
+        let mut load_errors = Vec::new();
+        let loaded_modules = Self::load_modules_from_path(&modules_dir, &mut load_errors);
         let mut state = Self {
-            // TODO: replace me with actual aptos state
-            aptos_state: AptosCustomState::new_default(),
+            aptos_state,
             rand: StdRand::new(),
             executions: 0,
             start_time: Duration::from_secs(0),
@@ -154,7 +515,17 @@ impl AptosFuzzerState {
             execution_paths_by_input: HashMap::new(),
             seen_execution_paths: HashSet::new(),
             abort_code_paths: HashSet::new(),
-            shift_overflow_paths: HashSet::new(),
+            crash_paths: HashSet::new(),
+            shift_overflow_paths: HashMap::new(),
+            budget_exhausted_paths: HashSet::new(),
+            oracle_violation_paths: HashMap::new(),
+            runtime_issue_paths: HashMap::new(),
+            runtime_findings: Vec::new(),
+            runtime_finding_index: HashMap::new(),
+            divergence_paths: HashMap::new(),
+            resource_leak_paths: HashMap::new(),
+            abort_site_paths: HashMap::new(),
+            seen_abort_sites: HashSet::new(),
             metadata: SerdeAnyMap::new(),
             named_metadata: NamedSerdeAnyMap::new(),
             last_found_time: Duration::from_secs(0),
@@ -162,169 +533,1620 @@ impl AptosFuzzerState {
             corpus_id: None,
             stop_requested: false,
             stage_stack: StageStack::default(),
-            cumulative_coverage: vec![0u8; MAP_SIZE],
+            cumulative_coverage: vec![0u8; map_size],
             target_modules: Vec::new(),
+            source_locator: SourceLocator::new(),
             static_findings: Vec::new(),
             last_runtime_issues: Vec::new(),
+            last_instructions: 0,
+            last_rejected: false,
+            last_sender: AccountAddress::ONE,
+            sender_policy: SenderPolicy::default(),
+            revalidation_requested: false,
+            revalidated: None,
+            oracles: Vec::new(),
+            return_properties: Vec::new(),
+            round_trips: Vec::new(),
+            signer_ownership: None,
+            asset_conservation: None,
+            resource_leaks: None,
+            last_resource_leaks: Vec::new(),
+            view_properties: Vec::new(),
+            arg_constraints: Vec::new(),
+            last_oracle_violations: Vec::new(),
+            last_divergence: None,
+            current_events: Vec::new(),
+            struct_layouts: StructLayoutRegistry::default(),
             public_functions: Vec::new(),
+            generic_functions: Vec::new(),
+            instantiations: InstantiationSchedule::default(),
             function_lookup: HashMap::new(),
+            invariant_functions: Vec::new(),
+            skipped_functions: Vec::new(),
+            initializers: Vec::new(),
+            init_modules: Vec::new(),
+            initializer_admin: None,
+            mutation_counts: BTreeMap::new(),
+            mutation_weights: MutationWeights::default(),
+            suppressed_errors: BTreeMap::new(),
+            function_executions: BTreeMap::new(),
+            branch_coverage: BranchCoverage::new(),
+            conflict_analysis: ConflictAnalysis::new(),
+            last_access_sets: AccessSets::default(),
+            subsumed_prefixes: Vec::new(),
+            pruned_prefixes: 0,
+            #[cfg(feature = "concolic")]
+            solver: ConcolicSolver::default(),
+            #[cfg(feature = "concolic")]
+            abort_search: None,
+            #[cfg(feature = "concolic")]
+            symbolic_divergences: Vec::new(),
+            instruction_profile: HashMap::new(),
+            numeric_constants: Vec::new(),
+            address_pool: BTreeSet::new(),
+            address_list: Vec::new(),
+        };
+
+        for err in load_errors {
+            eprintln!("[aptos-fuzzer] {}", err);
+            state.record_suppressed_error(&err);
+        }
+
+        let mut entry_payloads = Vec::new();
+        let mut numeric_constants = BTreeSet::new();
+        for byte in FRAMEWORK_ADDRESS_BYTES {
+            let mut address = [0u8; 32];
+            address[31] = byte;
+            state.insert_pool_address(AccountAddress::new(address));
+        }
+        for address in state.aptos_state.local_addresses() {
+            state.insert_pool_address(address);
+        }
+        for loaded in loaded_modules {
+            numeric_constants.extend(Self::numeric_constants_of(&loaded.module));
+            state.insert_pool_address(*loaded.module_id.address());
+            for address in Self::address_constants_of(&loaded.module) {
+                state.insert_pool_address(address);
+            }
+            state
+                .aptos_state
+                .deploy_module_bytes(loaded.module_id.clone(), loaded.bytes);
+            state.target_modules.push(loaded.module_id.clone());
+            state
+                .initializers
+                .extend(Self::detect_initializers(&loaded.module_id, &loaded.module));
+            if Self::has_init_module(&loaded.module) {
+                state.init_modules.push(loaded.module_id.clone());
+            }
+
+            let functions =
+                Self::extract_public_functions(&loaded.module_id, &loaded.module, &mut state.skipped_functions);
+            for function in functions {
+                if function.is_invariant() {
+                    // Properties are checked, not fuzzed
+                    state.skipped_functions.push(SkippedFunction {
+                        module_id: function.module_id().clone(),
+                        name: function.name().clone(),
+                        reason: SkipReason::Invariant,
+                    });
+                    state.invariant_functions.push(function);
+                    continue;
+                }
+                if function.is_entry() {
+                    match Self::entry_payload_from_function(&function) {
+                        Ok(payload) => entry_payloads.push(payload),
+                        Err(err) => state.record_suppressed_error(&err),
+                    }
+                }
+                let key = Self::function_key(function.module_id(), function.name());
+                state.function_lookup.insert(key, state.public_functions.len());
+                state.public_functions.push(function);
+            }
+            state.generic_functions.extend(Self::extract_generic_functions(
+                &loaded.module_id,
+                &loaded.module,
+                &mut state.skipped_functions,
+            ));
+        }
+
+        state.numeric_constants = numeric_constants.into_iter().collect();
+        state.struct_layouts =
+            StructLayoutRegistry::from_module_bytes(state.aptos_state.module_bytes().values().map(|bytes| &bytes[..]));
+        state.instantiations = InstantiationSchedule::new(
+            state
+                .target_modules
+                .iter()
+                .flat_map(|module_id| state.struct_layouts.structs_of(module_id)),
+        );
+        // Generic entry functions are seeded with one instantiation; the
+        // mutator schedules the others
+        for idx in 0..state.generic_functions.len() {
+            if !state.generic_functions[idx].is_entry() {
+                continue;
+            }
+            let Some(function) = state.schedule_instantiation(idx) else {
+                continue;
+            };
+            match Self::entry_payload_from_function(&function) {
+                Ok(payload) => entry_payloads.push(payload),
+                Err(err) => state.record_suppressed_error(&err),
+            }
+        }
+        let targets = state.target_modules.clone();
+        state.aptos_state.assign_edge_ids(map_size, &targets);
+
+        for payload in entry_payloads {
+            let _ = state.corpus.add(Testcase::new(AptosFuzzerInput::new(payload)));
+        }
+
+        match Self::make_empty_script_seed(state.aptos_state()) {
+            Ok(script_input) => {
+                let _ = state.corpus.add(Testcase::new(script_input));
+            }
+            Err(err) => state.record_suppressed_error(&err),
+        }
+        state
+    }
+
+    /// Corpus entries whose edge-map slots, per the `MapIndexesMetadata` the
+    /// map feedback attached, are all covered by entries covering more
+    /// slots, picked greedily from the largest. Entries never run through
+    /// the feedback, such as seeds not yet fuzzed, carry no metadata and are
+    /// never redundant.
+    pub fn redundant_corpus_entries(&self) -> Vec<CorpusId> {
+        let mut entries: Vec<(CorpusId, Vec<usize>)> = self
+            .corpus
+            .ids()
+            .filter_map(|id| {
+                let testcase = self.corpus.get(id).ok()?.borrow();
+                let indexes = testcase.metadata::<MapIndexesMetadata>().ok()?.list.clone();
+                Some((id, indexes))
+            })
+            .collect();
+        entries.sort_by_key(|(_, indexes)| Reverse(indexes.len()));
+        let mut covered = HashSet::new();
+        entries
+            .into_iter()
+            .filter_map(|(id, indexes)| {
+                let mut new = false;
+                for idx in indexes {
+                    new |= covered.insert(idx);
+                }
+                (!new).then_some(id)
+            })
+            .collect()
+    }
+
+    /// Queue for removal the corpus entries whose sequence is a proper,
+    /// non-empty prefix of the one of entry `id` and whose edge-map slots it
+    /// all covers: extending them already found everything they did.
+    pub fn queue_subsumed_prefixes(&mut self, id: CorpusId) {
+        let indexes = |testcase: &Testcase<AptosFuzzerInput>| {
+            let sequence = testcase.input().as_ref()?.script_sequence()?.clone();
+            let indexes: HashSet<usize> = testcase
+                .metadata::<MapIndexesMetadata>()
+                .ok()?
+                .list
+                .iter()
+                .copied()
+                .collect();
+            Some((sequence, indexes))
+        };
+        let Some((sequence, covered)) = self
+            .corpus
+            .get(id)
+            .ok()
+            .and_then(|testcase| indexes(&testcase.borrow()))
+        else {
+            return;
+        };
+        for other in self.corpus.ids() {
+            if other == id || self.subsumed_prefixes.contains(&other) {
+                continue;
+            }
+            let Some((prefix, indexes)) = self
+                .corpus
+                .get(other)
+                .ok()
+                .and_then(|testcase| indexes(&testcase.borrow()))
+            else {
+                continue;
+            };
+            if !prefix.is_empty() &&
+                prefix.len() < sequence.len() &&
+                sequence.calls().starts_with(prefix.calls()) &&
+                indexes.is_subset(&covered)
+            {
+                self.subsumed_prefixes.push(other);
+            }
+        }
+    }
+
+    /// Remove the corpus entries queued by `queue_subsumed_prefixes`, but
+    /// `keep`, the entry about to be fuzzed.
+    pub fn prune_subsumed_prefixes(&mut self, keep: CorpusId) {
+        for id in std::mem::take(&mut self.subsumed_prefixes) {
+            if id == keep {
+                self.subsumed_prefixes.push(id);
+            } else if self.corpus.remove(id).is_ok() {
+                self.pruned_prefixes += 1;
+            }
+        }
+    }
+
+    pub fn pruned_prefixes(&self) -> u64 {
+        self.pruned_prefixes
+    }
+
+    /// Drain current corpus entries into a vector of inputs and clear the
+    /// corpus. Useful to re-insert seeds via fuzzer.add_input so
+    /// events/feedback are fired.
+    pub fn take_initial_inputs(&mut self) -> Vec<AptosFuzzerInput> {
+        let ids: Vec<_> = self.corpus().ids().collect();
+        let mut inputs = Vec::with_capacity(ids.len());
+        for id in ids {
+            if let Ok(mut input) = self.corpus().cloned_input_for_id(id) {
+                self.constrain_input(&mut input);
+                self.assign_sender(&mut input);
+                inputs.push(input);
+            }
+        }
+        // Clear existing entries
+        while let Some(id) = self.corpus().ids().next() {
+            let _ = self.corpus_mut().remove(id);
+        }
+        inputs
+    }
+
+    /// Clear the corpus and return an input publishing each target module
+    /// instead, for fuzzing the publish path with module mutants.
+    pub fn take_publish_inputs(&mut self) -> Vec<AptosFuzzerInput> {
+        self.take_initial_inputs();
+        let module_bytes = self.aptos_state.module_bytes();
+        self.target_modules
+            .iter()
+            .filter_map(|module| module_bytes.get(module))
+            .map(|code| AptosFuzzerInput::publish(code.to_vec()))
+            .collect()
+    }
+
+    pub fn aptos_state(&self) -> &AptosCustomState {
+        &self.aptos_state
+    }
+
+    pub fn aptos_state_mut(&mut self) -> &mut AptosCustomState {
+        &mut self.aptos_state
+    }
+
+    pub fn cumulative_coverage(&self) -> &[u8] {
+        &self.cumulative_coverage
+    }
+
+    pub fn cumulative_coverage_mut(&mut self) -> &mut [u8] {
+        &mut self.cumulative_coverage
+    }
+
+    pub fn map_size(&self) -> usize {
+        self.cumulative_coverage.len()
+    }
+
+    pub fn take_solutions(&self) -> Vec<AptosFuzzerInput> {
+        let solutions = self.solutions();
+        let mut seen_ids = HashSet::new();
+        let mut inputs = Vec::new();
+        for id in solutions.ids() {
+            if let Ok(input) = solutions.cloned_input_for_id(id) {
+                if let Some(record) = self.execution_paths_by_input.get(&input) {
+                    if seen_ids.insert(record.id) {
+                        inputs.push(input);
+                    }
+                }
+            }
+        }
+        inputs
+    }
+
+    pub fn clear_current_execution_path(&mut self) {
+        self.current_execution_path = None;
+        self.current_execution_path_id = None;
+        self.current_events.clear();
+    }
+
+    pub fn set_current_execution_path(&mut self, execution_path: Vec<u64>) {
+        let id = Self::compute_execution_path_id(&execution_path);
+        self.current_execution_path = Some(execution_path);
+        self.current_execution_path_id = Some(id);
+    }
+
+    pub fn current_execution_path_id(&self) -> Option<u64> {
+        self.current_execution_path_id
+    }
+
+    pub fn set_last_runtime_issues(&mut self, issues: Vec<RuntimeIssue>) {
+        self.last_runtime_issues = issues;
+    }
+
+    pub fn last_runtime_issues(&self) -> &[RuntimeIssue] {
+        &self.last_runtime_issues
+    }
+
+    pub fn set_last_instructions(&mut self, instructions: u64) {
+        self.last_instructions = instructions;
+    }
+
+    pub fn last_instructions(&self) -> u64 {
+        self.last_instructions
+    }
+
+    pub fn set_last_rejected(&mut self, rejected: bool) {
+        self.last_rejected = rejected;
+    }
+
+    pub fn last_rejected(&self) -> bool {
+        self.last_rejected
+    }
+
+    pub fn set_last_sender(&mut self, sender: AccountAddress) {
+        self.last_sender = sender;
+    }
+
+    pub fn last_sender(&self) -> AccountAddress {
+        self.last_sender
+    }
+
+    /// Send inputs that don't name their signers as `policy` picks, and
+    /// calls into pinned functions always from their pinned sender.
+    pub fn set_sender_policy(&mut self, policy: SenderPolicy) {
+        self.sender_policy = policy;
+    }
+
+    /// Store the sender the policy picks on `input`, see `SenderPolicy`.
+    pub fn assign_sender(&mut self, input: &mut AptosFuzzerInput) {
+        let mut policy = std::mem::take(&mut self.sender_policy);
+        policy.assign(input, self);
+        self.sender_policy = policy;
+    }
+
+    /// Have the revalidation stage re-run the corpus against the current
+    /// target state.
+    pub fn request_revalidation(&mut self) {
+        self.revalidation_requested = true;
+    }
+
+    /// Whether revalidation was requested, clearing the request.
+    pub fn take_revalidation_request(&mut self) -> bool {
+        std::mem::take(&mut self.revalidation_requested)
+    }
+
+    /// Record that revalidation kept `kept` corpus entries and dropped
+    /// `dropped` that no longer execute.
+    pub fn set_revalidated(&mut self, kept: usize, dropped: usize) {
+        self.revalidated = Some((kept, dropped));
+    }
+
+    /// Entries kept and dropped by the revalidation since the last call.
+    pub fn take_revalidated(&mut self) -> Option<(usize, usize)> {
+        self.revalidated.take()
+    }
+
+    /// Add `issue` to the findings report, or count it again if the same
+    /// kind of issue was already seen at the same location.
+    pub fn record_runtime_finding(&mut self, issue: &RuntimeIssue, severity: Severity) {
+        let key = (
+            issue.kind.clone(),
+            issue.module.clone(),
+            issue.function.clone(),
+            issue.pc,
+        );
+        match self.runtime_finding_index.get(&key) {
+            Some(&idx) => self.runtime_findings[idx].count += 1,
+            None => {
+                self.runtime_finding_index.insert(key, self.runtime_findings.len());
+                self.runtime_findings.push(RuntimeFinding {
+                    issue: issue.clone(),
+                    severity,
+                    count: 1,
+                });
+            }
+        }
+    }
+
+    pub fn runtime_findings(&self) -> &[RuntimeFinding] {
+        &self.runtime_findings
+    }
+
+    pub fn register_oracle(&mut self, oracle: Box<dyn InvariantOracle>) {
+        self.oracles.push(oracle);
+    }
+
+    pub fn add_return_property(&mut self, property: ReturnProperty) {
+        self.return_properties.push(property);
+    }
+
+    pub fn return_properties(&self) -> &[ReturnProperty] {
+        &self.return_properties
+    }
+
+    /// Check `round_trip` from now on and seed the corpus with a sequence
+    /// calling its forward function, then its inverse on what the forward
+    /// call returned or with the same arguments.
+    pub fn add_round_trip(&mut self, round_trip: RoundTrip) -> anyhow::Result<()> {
+        let find = |is_match: &dyn Fn(&ModuleId, &IdentStr) -> bool| {
+            self.public_functions
+                .iter()
+                .find(|function| is_match(function.module_id(), function.name()))
+                .cloned()
         };
+        let forward = find(&|module, function| round_trip.is_forward(module, function))
+            .with_context(|| format!("no public function matches the forward call of {}", round_trip.name()))?;
+        let inverse = find(&|module, function| round_trip.is_inverse(module, function))
+            .with_context(|| format!("no public function matches the inverse call of {}", round_trip.name()))?;
+
+        let mut forward_args = Vec::new();
+        let mut signer_idx = 0;
+        for param in forward.parameters() {
+            forward_args.push(match param {
+                FunctionParameter::Signer => {
+                    signer_idx += 1;
+                    SequenceArgument::Signer(signer_idx - 1)
+                }
+                FunctionParameter::Value(tag) => SequenceArgument::Raw {
+                    bytes: Self::default_arg_bytes(tag)
+                        .with_context(|| format!("no default value for {}", tag.to_canonical_string()))?,
+                    ty: tag.clone(),
+                },
+                FunctionParameter::Reference { ty, .. } => {
+                    anyhow::bail!("the forward call takes a reference to {}", ty.to_canonical_string())
+                }
+            });
+        }
+        // Link each inverse argument to a forward return, else to a forward
+        // argument, of the same type
+        let mut returns: Vec<_> = forward.return_types().iter().enumerate().collect();
+        let mut linked_args: Vec<_> = forward
+            .parameters()
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, param)| match param {
+                FunctionParameter::Value(tag) => Some((idx, tag)),
+                FunctionParameter::Signer | FunctionParameter::Reference { .. } => None,
+            })
+            .collect();
+        let mut inverse_args = Vec::new();
+        let mut signer_idx = 0;
+        for param in inverse.parameters() {
+            let tag = match param {
+                FunctionParameter::Signer => {
+                    signer_idx += 1;
+                    inverse_args.push(SequenceArgument::Signer(signer_idx - 1));
+                    continue;
+                }
+                // A borrowed return stays available to the arguments after it
+                FunctionParameter::Reference { ty, mutable } => {
+                    let (return_idx, _) = returns
+                        .iter()
+                        .find(|(_, tag)| *tag == ty)
+                        .with_context(|| format!("no forward return to borrow as {}", ty.to_canonical_string()))?;
+                    inverse_args.push(SequenceArgument::PreviousResult {
+                        call_idx: 0,
+                        return_idx: *return_idx as u16,
+                        operation: if *mutable {
+                            ResultOperation::BorrowMut
+                        } else {
+                            ResultOperation::Borrow
+                        },
+                    });
+                    continue;
+                }
+                FunctionParameter::Value(tag) => tag,
+            };
+            if let Some(pos) = returns.iter().position(|(_, ty)| *ty == tag) {
+                let (return_idx, _) = returns.remove(pos);
+                inverse_args.push(SequenceArgument::PreviousResult {
+                    call_idx: 0,
+                    return_idx: return_idx as u16,
+                    operation: ResultOperation::Move,
+                });
+            } else if let Some(pos) = linked_args.iter().position(|(_, ty)| *ty == tag) {
+                let (arg_idx, _) = linked_args.remove(pos);
+                inverse_args.push(SequenceArgument::SameAs {
+                    call_idx: 0,
+                    arg_idx: arg_idx as u16,
+                });
+            } else {
+                inverse_args.push(SequenceArgument::Raw {
+                    bytes: Self::default_arg_bytes(tag)
+                        .with_context(|| format!("no default value for {}", tag.to_canonical_string()))?,
+                    ty: tag.clone(),
+                });
+            }
+        }
+
+        let mut sequence = ScriptSequence::new();
+        for (function, args) in [(&forward, forward_args), (&inverse, inverse_args)] {
+            sequence.push_call(SequenceCall::new(
+                function.module_id().clone(),
+                function.name().clone(),
+                Vec::new(),
+                args,
+            ));
+        }
+        let script = compile_sequence(&sequence, self.aptos_state.module_bytes())
+            .with_context(|| format!("failed to compile the seed of {}", round_trip.name()))?;
+        let input = AptosFuzzerInput::with_script(TransactionPayload::Script(script), sequence);
+        let _ = self.corpus.add(Testcase::new(input));
+        self.round_trips.push(round_trip);
+        Ok(())
+    }
+
+    pub fn add_view_property(&mut self, property: ViewMonotonicity) {
+        self.view_properties.push(property);
+    }
+
+    pub fn view_properties(&self) -> &[ViewMonotonicity] {
+        &self.view_properties
+    }
+
+    /// Respect `constraint` in the arguments of the inputs generated from
+    /// now on. Fails unless it names an argument of a public function that
+    /// can satisfy it.
+    pub fn add_arg_constraint(&mut self, constraint: ArgConstraint) -> anyhow::Result<()> {
+        let function = self
+            .public_functions
+            .iter()
+            .find(|function| constraint.applies_to(function.module_id(), function.name()))
+            .with_context(|| format!("no public function matches {}", constraint.name()))?;
+        let value_types = function.value_types();
+        let ty = value_types.get(constraint.arg_idx()).with_context(|| {
+            format!(
+                "{} takes {} arguments besides signers",
+                function.name(),
+                value_types.len()
+            )
+        })?;
+        constraint.check_type(ty)?;
+        self.arg_constraints.push(constraint);
+        Ok(())
+    }
+
+    pub fn arg_constraints(&self) -> &[ArgConstraint] {
+        &self.arg_constraints
+    }
+
+    /// Replace the arguments of `input` breaking an argument constraint with
+    /// values satisfying it. Sequence arguments taken from earlier calls are
+    /// left alone. Returns whether any argument was replaced.
+    pub fn constrain_input(&mut self, input: &mut AptosFuzzerInput) -> bool {
+        if self.arg_constraints.is_empty() {
+            return false;
+        }
+        let constraints = std::mem::take(&mut self.arg_constraints);
+        let changed = match input.payload() {
+            TransactionPayload::EntryFunction(_) => self.constrain_entry_function(&constraints, input),
+            TransactionPayload::Script(_) => self.constrain_sequence(&constraints, input),
+            _ => false,
+        };
+        self.arg_constraints = constraints;
+        changed
+    }
+
+    fn constrain_entry_function(&mut self, constraints: &[ArgConstraint], input: &mut AptosFuzzerInput) -> bool {
+        let TransactionPayload::EntryFunction(entry) = input.payload() else {
+            return false;
+        };
+        let Some(function) = self.function_instance(entry.module(), entry.function(), entry.ty_args()) else {
+            return false;
+        };
+        let value_types: Vec<TypeTag> = function.value_types().into_iter().cloned().collect();
+        let (module, name, ty_args, mut args) = entry.clone().into_inner();
+        let mut changed = false;
+        for constraint in constraints {
+            let idx = constraint.arg_idx();
+            if !constraint.applies_to(&module, &name) || idx >= args.len() || idx >= value_types.len() {
+                continue;
+            }
+            if constraint.admits(&value_types[idx], &args[idx], self) {
+                continue;
+            }
+            if let Some(bytes) = constraint.sample(&value_types[idx], self) {
+                args[idx] = bytes;
+                changed = true;
+            }
+        }
+        if changed {
+            *input.payload_mut() =
+                TransactionPayload::EntryFunction(AptosEntryFunction::new(module, name, ty_args, args));
+        }
+        changed
+    }
+
+    fn constrain_sequence(&mut self, constraints: &[ArgConstraint], input: &mut AptosFuzzerInput) -> bool {
+        let Some(mut sequence) = input.script_sequence().cloned() else {
+            return false;
+        };
+        let mut constrained = false;
+        let mut changed = false;
+        for call_idx in 0..sequence.calls().len() {
+            let call = &sequence.calls()[call_idx];
+            // Positions of the call's non-signer, non-reference arguments
+            let positions: Vec<usize> = call
+                .args()
+                .iter()
+                .enumerate()
+                .filter(|(_, arg)| !matches!(arg, SequenceArgument::Signer(_)) && !arg.is_borrow())
+                .map(|(pos, _)| pos)
+                .collect();
+            let (module, function) = (call.module().clone(), call.function().clone());
+            for constraint in constraints
+                .iter()
+                .filter(|constraint| constraint.applies_to(&module, &function))
+            {
+                let Some(&pos) = positions.get(constraint.arg_idx()) else {
+                    continue;
+                };
+                let Some(SequenceArgument::Raw { bytes, ty }) = sequence.calls()[call_idx].args().get(pos) else {
+                    continue;
+                };
+                constrained = true;
+                if constraint.admits(ty, bytes, self) {
+                    continue;
+                }
+                let ty = ty.clone();
+                if let Some(bytes) = constraint.sample(&ty, self) {
+                    sequence.set_raw_argument(call_idx, pos, bytes);
+                    changed = true;
+                }
+            }
+        }
+        // Appending a call mutates the compiled script's arguments as well,
+        // so recompile whenever a constrained argument is in there
+        if !constrained {
+            return false;
+        }
+        let script = match compile_sequence(&sequence, self.aptos_state.module_bytes()) {
+            Ok(script) => script,
+            Err(err) => {
+                self.record_suppressed_error(&err);
+                return false;
+            }
+        };
+        *input.payload_mut() = TransactionPayload::Script(script);
+        input.set_script_sequence(Some(sequence));
+        changed
+    }
+
+    pub fn round_trips(&self) -> &[RoundTrip] {
+        &self.round_trips
+    }
+
+    pub fn oracles(&self) -> &[Box<dyn InvariantOracle>] {
+        &self.oracles
+    }
+
+    /// Run every registered oracle against the outcome of the current
+    /// execution; the state itself still holds the pre-transaction view.
+    pub fn check_oracles(&self, write_set: &WriteSet, events: &[ContractEvent]) -> Vec<Violation> {
+        self.oracles
+            .iter()
+            .filter_map(|oracle| oracle.check(&self.aptos_state, write_set, events))
+            .collect()
+    }
+
+    /// Report executions that change resources of the target modules owned
+    /// by another account than the sender.
+    pub fn enable_signer_ownership(&mut self) {
+        self.signer_ownership = Some(SignerOwnershipOracle::new(&self.target_modules));
+    }
+
+    pub fn check_signer_ownership(&self, input: &AptosFuzzerInput, write_set: &WriteSet) -> Option<Violation> {
+        self.signer_ownership
+            .as_ref()?
+            .check(&self.aptos_state, write_set, input.sender(), &self.struct_layouts)
+    }
+
+    /// Report transactions creating or destroying coins or fungible assets
+    /// without calling a mint or burn function.
+    pub fn enable_asset_conservation(&mut self, oracle: AssetConservationOracle) {
+        self.asset_conservation = Some(oracle);
+    }
+
+    pub fn check_asset_conservation(&self, input: &AptosFuzzerInput, write_set: &WriteSet) -> Option<Violation> {
+        self.asset_conservation.as_ref()?.check(
+            &self.aptos_state,
+            write_set,
+            &input.called_functions(),
+            &self.struct_layouts,
+        )
+    }
+
+    /// Report executions leaving a resource nothing can change or remove
+    /// anymore, or moving one to an address already holding it.
+    pub fn enable_resource_leaks(&mut self) {
+        self.resource_leaks = Some(ResourceLeakOracle::new());
+    }
+
+    /// Check the global storage `accesses` of an execution of `input`,
+    /// keeping what was found as the last execution's.
+    pub fn check_resource_leaks(&mut self, input: &AptosFuzzerInput, accesses: &[GlobalAccess], succeeded: bool) {
+        self.last_resource_leaks = match &mut self.resource_leaks {
+            Some(oracle) => oracle.check(&self.aptos_state, accesses, &input.called_functions(), succeeded),
+            None => Vec::new(),
+        };
+    }
+
+    pub fn last_resource_leaks(&self) -> &[ResourceLeak] {
+        &self.last_resource_leaks
+    }
+
+    /// Violations of the round trips `input`'s sequence consists of.
+    pub fn check_round_trips(&self, input: &AptosFuzzerInput, write_set: &WriteSet) -> Vec<Violation> {
+        let Some(sequence) = input.script_sequence() else {
+            return Vec::new();
+        };
+        self.round_trips
+            .iter()
+            .filter(|round_trip| round_trip.applies_to(sequence))
+            .filter_map(|round_trip| round_trip.check(&self.aptos_state, write_set, input.sender()))
+            .collect()
+    }
+
+    pub fn set_last_oracle_violations(&mut self, violations: Vec<Violation>) {
+        self.last_oracle_violations = violations;
+    }
+
+    pub fn last_oracle_violations(&self) -> &[Violation] {
+        &self.last_oracle_violations
+    }
+
+    pub fn set_last_divergence(&mut self, divergence: Option<String>) {
+        self.last_divergence = divergence;
+    }
+
+    pub fn last_divergence(&self) -> Option<&str> {
+        self.last_divergence.as_deref()
+    }
+
+    pub fn set_current_events(&mut self, events: Vec<EmittedEvent>) {
+        self.current_events = events;
+    }
+
+    pub fn record_current_execution_path_for(&mut self, input: &AptosFuzzerInput) -> Option<u64> {
+        match (self.current_execution_path_id, self.current_execution_path.as_ref()) {
+            (Some(id), Some(path)) => {
+                self.execution_paths_by_input
+                    .entry(input.clone())
+                    .or_insert_with(|| ExecutionPathRecord {
+                        id,
+                        path: path.clone(),
+                        events: self.current_events.clone(),
+                    });
+                Some(id)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn mark_execution_path_seen(&mut self, path_id: u64) -> bool {
+        self.seen_execution_paths.insert(path_id)
+    }
+
+    /// Returns whether `site` had not been reported yet.
+    pub fn mark_abort_site_seen(&mut self, site: &AbortSite) -> bool {
+        self.seen_abort_sites.insert(site.clone())
+    }
+
+    pub fn has_seen_execution_path(&self, path_id: u64) -> bool {
+        self.seen_execution_paths.contains(&path_id)
+    }
+
+    pub fn seen_execution_paths(&self) -> &HashSet<u64> {
+        &self.seen_execution_paths
+    }
+
+    pub fn seen_abort_sites(&self) -> &HashSet<AbortSite> {
+        &self.seen_abort_sites
+    }
+
+    pub fn get_solution_execution_path(&self, input: &AptosFuzzerInput) -> Option<Vec<u64>> {
+        self.execution_paths_by_input
+            .get(input)
+            .map(|record| record.path.clone())
+    }
+
+    pub fn get_solution_execution_path_id(&self, input: &AptosFuzzerInput) -> Option<u64> {
+        self.execution_paths_by_input.get(input).map(|record| record.id)
+    }
+
+    pub fn get_solution_events(&self, input: &AptosFuzzerInput) -> Option<&[EmittedEvent]> {
+        self.execution_paths_by_input
+            .get(input)
+            .map(|record| record.events.as_slice())
+    }
+
+    pub fn compute_execution_path_id(execution_path: &[u64]) -> u64 {
+        const FNV_OFFSET: u64 = 0xcbf29ce484222325;
+        const FNV_PRIME: u64 = 0x100000001b3;
+        execution_path.iter().fold(FNV_OFFSET, |hash, value| {
+            let hash = hash ^ *value;
+            hash.wrapping_mul(FNV_PRIME)
+        })
+    }
+
+    pub fn target_modules(&self) -> &[ModuleId] {
+        &self.target_modules
+    }
+
+    /// Load the `<module>.mvsm` source maps in `dir` for the target modules
+    /// of the same name. Returns how many were loaded.
+    pub fn load_source_maps(&mut self, dir: &Path) -> anyhow::Result<usize> {
+        let mut loaded = 0;
+        for entry in fs::read_dir(dir).with_context(|| format!("failed to read {}", dir.display()))? {
+            let path = entry?.path();
+            if path.extension().is_none_or(|ext| ext != "mvsm") {
+                continue;
+            }
+            let Some(name) = path.file_stem().and_then(|stem| stem.to_str()) else {
+                continue;
+            };
+            let Some(module_id) = self
+                .target_modules
+                .iter()
+                .find(|module_id| module_id.name().as_str() == name)
+                .cloned()
+            else {
+                continue;
+            };
+            let Some(module) = self
+                .aptos_state
+                .module_bytes()
+                .get(&module_id)
+                .and_then(|bytes| CompiledModule::deserialize(bytes).ok())
+            else {
+                continue;
+            };
+            let bytes = fs::read(&path).with_context(|| format!("failed to read {}", path.display()))?;
+            let source_map: SourceMap =
+                bcs::from_bytes(&bytes).with_context(|| format!("invalid source map {}", path.display()))?;
+            self.source_locator.add_source_map(&module, source_map);
+            loaded += 1;
+        }
+        Ok(loaded)
+    }
+
+    /// Index the `.move` files the loaded source maps refer to, so findings
+    /// can be reported as `file:line`.
+    pub fn load_sources(&mut self, dir: &Path) -> anyhow::Result<usize> {
+        self.source_locator.load_sources(dir)
+    }
+
+    pub fn source_map(&self, module_id: &ModuleId) -> Option<&SourceMap> {
+        self.source_locator.source_map(module_id)
+    }
+
+    pub fn source_locator(&self) -> &SourceLocator {
+        &self.source_locator
+    }
+
+    /// Source location of the instruction a runtime issue was raised at.
+    pub fn runtime_issue_location(&self, issue: &RuntimeIssue) -> Option<SourceLocation> {
+        let module_id = self
+            .target_modules
+            .iter()
+            .find(|id| format!("{}::{}", id.address().to_hex_literal(), id.name()) == issue.module)?;
+        self.source_locator.code_location(module_id, &issue.function, issue.pc)
+    }
+
+    pub fn public_functions(&self) -> &[PublicFunctionTarget] {
+        &self.public_functions
+    }
+
+    /// Cumulative edge coverage of each fuzzed function.
+    pub fn function_coverage(&self) -> Vec<FunctionCoverage> {
+        self.public_functions
+            .iter()
+            .filter_map(|function| {
+                let (covered, total) = self.edge_coverage(function.module_id(), function.name().as_str())?;
+                Some(FunctionCoverage {
+                    module_id: function.module_id().clone(),
+                    function: function.name().clone(),
+                    covered,
+                    total,
+                })
+            })
+            .collect()
+    }
+
+    /// Covered and total edges of any function in the edge layout.
+    pub fn edge_coverage(&self, module_id: &ModuleId, function: &str) -> Option<(usize, usize)> {
+        let layout = self.aptos_state.edge_layout();
+        let edges = layout.function(function_hash(module_id, function))?;
+        let covered = (edges.base()..edges.base() + edges.len())
+            .filter(|&edge_id| {
+                self.cumulative_coverage
+                    .get(layout.slot(edge_id))
+                    .is_some_and(|&hit| hit > 0)
+            })
+            .count();
+        Some((covered, edges.len()))
+    }
+
+    /// Public functions of the targets left out of `public_functions`, with
+    /// why: unsupported parameter or return types, uninstantiable type
+    /// parameters, invariants and, unless fuzzed anyway, functions no entry
+    /// function reaches.
+    pub fn skipped_functions(&self) -> &[SkippedFunction] {
+        &self.skipped_functions
+    }
+
+    /// The reason `name` of `module_id` is skipped, if it is.
+    pub fn skip_reason(&self, module_id: &ModuleId, name: &IdentStr) -> Option<&SkipReason> {
+        self.skipped_functions
+            .iter()
+            .find(|skipped| &skipped.module_id == module_id && skipped.name.as_ident_str() == name)
+            .map(|skipped| &skipped.reason)
+    }
+
+    pub fn invariant_functions(&self) -> &[PublicFunctionTarget] {
+        &self.invariant_functions
+    }
+
+    pub fn record_mutation(&mut self, kind: MutationKind) {
+        *self.mutation_counts.entry(kind).or_insert(0) += 1;
+    }
+
+    /// Count an error recovered from by skipping what failed, e.g. a
+    /// mutant whose sequence doesn't compile.
+    pub fn record_suppressed_error(&mut self, err: &FuzzerError) {
+        debug!("[aptos-fuzzer] {}", err);
+        *self.suppressed_errors.entry(err.subsystem()).or_insert(0) += 1;
+    }
+
+    pub fn suppressed_errors(&self) -> &BTreeMap<Subsystem, u64> {
+        &self.suppressed_errors
+    }
+
+    /// Count an execution of `input` for each function it calls.
+    pub fn record_function_execution(&mut self, input: &AptosFuzzerInput) {
+        for function in input.called_functions() {
+            *self.function_executions.entry(function).or_insert(0) += 1;
+        }
+    }
+
+    pub fn function_executions(&self) -> &BTreeMap<String, u64> {
+        &self.function_executions
+    }
+
+    pub fn branch_coverage(&self) -> &BranchCoverage {
+        &self.branch_coverage
+    }
+
+    pub fn branch_coverage_mut(&mut self) -> &mut BranchCoverage {
+        &mut self.branch_coverage
+    }
+
+    /// Group the global storage accesses of an execution of `input` into
+    /// the read and write sets of its calls.
+    pub fn access_sets(&mut self, input: &AptosFuzzerInput, accesses: &[GlobalAccess]) -> AccessSets {
+        self.conflict_analysis
+            .access_sets(accesses, &input.called_functions(), self.aptos_state.module_bytes())
+    }
+
+    /// Keep `sets` as the last execution's and add them to the campaign's.
+    pub fn record_access_sets(&mut self, sets: AccessSets) {
+        self.conflict_analysis.record(&sets);
+        self.last_access_sets = sets;
+    }
+
+    pub fn last_access_sets(&self) -> &AccessSets {
+        &self.last_access_sets
+    }
+
+    pub fn conflict_analysis(&self) -> &ConflictAnalysis {
+        &self.conflict_analysis
+    }
+
+    /// The branches only one way of which was taken, ranked by how close
+    /// they are to the aborts and runtime issues found so far, then by how
+    /// often they ran.
+    pub fn branch_worklist(&self) -> Vec<RankedBranch<'_>> {
+        let findings: HashSet<(String, String)> = self
+            .seen_abort_sites
+            .iter()
+            .map(|site| (site.module.clone(), site.function.clone()))
+            .chain(
+                self.runtime_findings
+                    .iter()
+                    .map(|finding| (finding.issue.module.clone(), finding.issue.function.clone())),
+            )
+            .collect();
+        self.branch_coverage.worklist(&findings)
+    }
+
+    pub fn mutation_counts(&self) -> &BTreeMap<MutationKind, u64> {
+        &self.mutation_counts
+    }
 
-        let mut entry_payloads = Vec::new();
-        for loaded in loaded_modules {
-            state
-                .aptos_state
-                .deploy_module_bytes(loaded.module_id.clone(), loaded.bytes);
-            state.target_modules.push(loaded.module_id.clone());
+    pub fn mutation_weights(&self) -> &MutationWeights {
+        &self.mutation_weights
+    }
 
-            for function in Self::extract_public_functions(&loaded.module_id, &loaded.module) {
-                if function.is_entry() {
-                    if let Some(payload) = Self::entry_payload_from_function(&function) {
-                        entry_payloads.push(payload);
-                    }
+    pub fn mutation_weights_mut(&mut self) -> &mut MutationWeights {
+        &mut self.mutation_weights
+    }
+
+    #[cfg(feature = "concolic")]
+    /// Replace the solver, dropping its cache and statistics.
+    pub fn set_solver_config(&mut self, config: SolverConfig) {
+        self.solver = ConcolicSolver::new(config);
+    }
+
+    #[cfg(feature = "concolic")]
+    pub fn solver_mut(&mut self) -> &mut ConcolicSolver {
+        &mut self.solver
+    }
+
+    #[cfg(feature = "concolic")]
+    pub fn solver_stats(&self) -> &SolverStats {
+        self.solver.stats()
+    }
+
+    #[cfg(feature = "concolic")]
+    /// Locate `target`'s aborts and guarding branches in the loaded modules.
+    pub fn abort_guards(&self, target: &AbortTarget) -> anyhow::Result<AbortGuards> {
+        let bytes = self
+            .aptos_state
+            .module_bytes()
+            .get(&target.module)
+            .with_context(|| format!("module {} is not loaded", target.module))?;
+        let module = CompiledModule::deserialize(bytes).map_err(|e| anyhow::anyhow!("{:?}", e))?;
+        target.guards(&module)
+    }
+
+    #[cfg(feature = "concolic")]
+    /// Start tracking a `--solve-abort` search.
+    pub fn set_abort_search(&mut self, search: Option<AbortSearch>) {
+        self.abort_search = search;
+    }
+
+    #[cfg(feature = "concolic")]
+    pub fn abort_search(&self) -> Option<&AbortSearch> {
+        self.abort_search.as_ref()
+    }
+
+    #[cfg(feature = "concolic")]
+    pub fn abort_search_mut(&mut self) -> Option<&mut AbortSearch> {
+        self.abort_search.as_mut()
+    }
+
+    #[cfg(feature = "concolic")]
+    pub fn record_symbolic_divergence(&mut self, divergence: SymbolicDivergence) {
+        self.symbolic_divergences.push(divergence);
+    }
+
+    #[cfg(feature = "concolic")]
+    /// Divergences found by the concolic self-check since the last call.
+    pub fn take_symbolic_divergences(&mut self) -> Vec<SymbolicDivergence> {
+        std::mem::take(&mut self.symbolic_divergences)
+    }
+
+    /// Add one execution's per-`(function hash, pc)` instruction counts to
+    /// the campaign profile.
+    pub fn record_instruction_histogram(&mut self, counts: &HashMap<(u64, u16), u64>) {
+        for (&(function, pc), &count) in counts {
+            *self
+                .instruction_profile
+                .entry(function)
+                .or_default()
+                .entry(pc)
+                .or_insert(0) += count;
+        }
+    }
+
+    /// The `limit` functions that executed the most instructions, each with
+    /// its `pcs` hottest instructions.
+    pub fn hot_functions(&self, limit: usize, pcs: usize) -> Vec<HotFunction> {
+        let mut totals: Vec<(u64, u64)> = self
+            .instruction_profile
+            .iter()
+            .map(|(&function, counts)| (function, counts.values().sum()))
+            .collect();
+        totals.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        totals.truncate(limit);
+
+        let wanted: HashSet<u64> = totals.iter().map(|&(function, _)| function).collect();
+        let mut names = HashMap::new();
+        for (module_id, bytes) in self.aptos_state.module_bytes() {
+            let Ok(module) = CompiledModule::deserialize(bytes) else {
+                continue;
+            };
+            for def in module.function_defs() {
+                let name = module.identifier_at(module.function_handle_at(def.function).name);
+                let hash = function_hash(module_id, name.as_str());
+                if wanted.contains(&hash) {
+                    names.insert(hash, format!("{}::{}", module_id, name));
                 }
-                let key = Self::function_key(function.module_id(), function.name());
-                state.function_lookup.insert(key, state.public_functions.len());
-                state.public_functions.push(function);
             }
         }
 
-        for payload in entry_payloads {
-            let input = AptosFuzzerInput::new(payload);
-            let _ = state.corpus.add(Testcase::new(input));
+        totals
+            .into_iter()
+            .map(|(function, instructions)| {
+                let mut hottest_pcs: Vec<(u16, u64)> = self.instruction_profile[&function]
+                    .iter()
+                    .map(|(&pc, &count)| (pc, count))
+                    .collect();
+                hottest_pcs.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+                hottest_pcs.truncate(pcs);
+                HotFunction {
+                    name: names
+                        .remove(&function)
+                        .unwrap_or_else(|| format!("<function {:016x}>", function)),
+                    instructions,
+                    hottest_pcs,
+                }
+            })
+            .collect()
+    }
+
+    pub fn numeric_constants(&self) -> &[[u8; 32]] {
+        &self.numeric_constants
+    }
+
+    pub fn address_pool(&self) -> &BTreeSet<AccountAddress> {
+        &self.address_pool
+    }
+
+    pub fn add_pool_address(&mut self, address: AccountAddress) {
+        if self.address_pool.len() < MAX_ADDRESS_POOL {
+            self.insert_pool_address(address);
         }
+    }
 
-        if let Some(script_input) = Self::make_empty_script_seed(state.aptos_state()) {
-            let _ = state.corpus.add(Testcase::new(script_input));
+    fn insert_pool_address(&mut self, address: AccountAddress) {
+        if self.address_pool.insert(address) {
+            self.address_list.push(address);
         }
-        state
     }
 
-    /// Drain current corpus entries into a vector of inputs and clear the
-    /// corpus. Useful to re-insert seeds via fuzzer.add_input so
-    /// events/feedback are fired.
-    pub fn take_initial_inputs(&mut self) -> Vec<AptosFuzzerInput> {
-        let ids: Vec<_> = self.corpus().ids().collect();
-        let mut inputs = Vec::with_capacity(ids.len());
-        for id in ids {
-            if let Ok(input) = self.corpus().cloned_input_for_id(id) {
-                inputs.push(input);
+    /// The `idx`th address added to the pool, wrapping around.
+    pub fn pool_address(&self, idx: usize) -> Option<AccountAddress> {
+        if self.address_list.is_empty() {
+            return None;
+        }
+        Some(self.address_list[idx % self.address_list.len()])
+    }
+
+    /// Add the addresses `write_set` stores resources or modules under.
+    pub fn observe_write_set(&mut self, write_set: &WriteSet) {
+        for (state_key, _) in write_set.write_op_iter() {
+            if let StateKeyInner::AccessPath(access_path) = state_key.inner() {
+                self.add_pool_address(access_path.address);
             }
         }
-        // Clear existing entries
-        while let Some(id) = self.corpus().ids().next() {
-            let _ = self.corpus_mut().remove(id);
+    }
+
+    pub fn random_pool_address(&mut self) -> Option<AccountAddress> {
+        if self.address_pool.is_empty() {
+            return None;
         }
-        inputs
+        let idx = self.rand.next() as usize;
+        self.pool_address(idx)
     }
 
-    pub fn aptos_state(&self) -> &AptosCustomState {
-        &self.aptos_state
+    pub fn initializers(&self) -> &[InitializerCall] {
+        &self.initializers
     }
 
-    pub fn aptos_state_mut(&mut self) -> &mut AptosCustomState {
-        &mut self.aptos_state
+    pub fn clear_initializers(&mut self) {
+        self.initializers.clear();
+        self.init_modules.clear();
     }
 
-    pub fn cumulative_coverage(&self) -> &[u8] {
-        &self.cumulative_coverage
+    /// The target modules whose `init_module` has yet to run, which are then
+    /// taken as run.
+    pub fn take_init_modules(&mut self) -> Vec<ModuleId> {
+        std::mem::take(&mut self.init_modules)
     }
 
-    pub fn cumulative_coverage_mut(&mut self) -> &mut [u8] {
-        &mut self.cumulative_coverage
+    /// Replace the detected initializers with a single call to `function`
+    /// (`0xADDR::module::function`), parsing `args` against its non-signer
+    /// parameter types.
+    pub fn set_initializer(&mut self, function: &str, args: &[String]) -> anyhow::Result<()> {
+        let mut parts = function.split("::");
+        let (Some(address), Some(module), Some(name), None) = (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            anyhow::bail!("expected 0xADDR::module::function, got {}", function);
+        };
+        let module_id = ModuleId::new(AccountAddress::from_hex_literal(address)?, Identifier::new(module)?);
+        let name = Identifier::new(name)?;
+        let bytes = self
+            .aptos_state
+            .module_bytes()
+            .get(&module_id)
+            .ok_or_else(|| anyhow::anyhow!("module {} is not loaded", module_id))?;
+        let module = CompiledModule::deserialize(bytes).map_err(|e| anyhow::anyhow!("{:?}", e))?;
+        let handle = module
+            .function_defs
+            .iter()
+            .map(|func_def| module.function_handle_at(func_def.function))
+            .find(|handle| module.identifier_at(handle.name) == name.as_ident_str())
+            .ok_or_else(|| anyhow::anyhow!("function {} not found", function))?;
+        let parameters = Self::parameters_from_signature(&module, &module.signature_at(handle.parameters).0)
+            .ok_or_else(|| anyhow::anyhow!("unsupported parameter types in {}", function))?;
+        let value_types: Vec<&TypeTag> = parameters
+            .iter()
+            .filter_map(|param| match param {
+                FunctionParameter::Value(tag) => Some(tag),
+                FunctionParameter::Signer | FunctionParameter::Reference { .. } => None,
+            })
+            .collect();
+        if value_types.len() != args.len() {
+            anyhow::bail!("{} takes {} arguments, got {}", function, value_types.len(), args.len());
+        }
+        let args = value_types
+            .into_iter()
+            .zip(args)
+            .map(|(tag, value)| Self::parse_arg_bytes(tag, value))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        self.initializers = vec![InitializerCall {
+            module_id,
+            function: name,
+            args,
+        }];
+        Ok(())
     }
 
-    pub fn take_solutions(&self) -> Vec<AptosFuzzerInput> {
-        let solutions = self.solutions();
-        let mut seen_ids = HashSet::new();
-        let mut inputs = Vec::new();
-        for id in solutions.ids() {
-            if let Ok(input) = solutions.cloned_input_for_id(id) {
-                if let Some(record) = self.execution_paths_by_input.get(&input) {
-                    if seen_ids.insert(record.id) {
-                        inputs.push(input);
+    /// Reseed the random source behind every scheduling and mutation
+    /// decision, making the campaign reproducible.
+    pub fn set_rng_seed(&mut self, seed: u64) {
+        self.rand.set_seed(seed);
+    }
+
+    pub fn set_initializer_admin(&mut self, admin: &str) -> anyhow::Result<()> {
+        let admin = AccountAddress::from_hex_literal(admin)?;
+        self.initializer_admin = Some(admin);
+        self.add_pool_address(admin);
+        Ok(())
+    }
+
+    pub fn initializer_admin(&self) -> Option<AccountAddress> {
+        self.initializer_admin
+    }
+
+    /// Resource types `T` of the `Object<T>` parameters taken by fuzzed
+    /// functions.
+    pub fn object_types(&self) -> Vec<StructTag> {
+        let mut types = BTreeSet::new();
+        for function in &self.public_functions {
+            for param in function.parameters() {
+                if let FunctionParameter::Value(TypeTag::Struct(tag)) = param {
+                    if let Some(FrameworkStruct::Object(TypeTag::Struct(inner))) = FrameworkStruct::classify(tag) {
+                        types.insert(*inner);
                     }
                 }
             }
         }
-        inputs
+        types.into_iter().collect()
     }
 
-    pub fn clear_current_execution_path(&mut self) {
-        self.current_execution_path = None;
-        self.current_execution_path_id = None;
+    /// BCS encoding of an `Object<T>` argument pointing at a random pool
+    /// object holding `T`, or `None` when there is none.
+    pub fn object_arg_bytes(&mut self, resource: &TypeTag) -> Option<Vec<u8>> {
+        let TypeTag::Struct(resource) = resource else {
+            return None;
+        };
+        let addresses = self.aptos_state.object_addresses(resource);
+        if addresses.is_empty() {
+            return None;
+        }
+        let address = addresses[(self.rand.next() as usize) % addresses.len()];
+        bcs::to_bytes(&address).ok()
     }
 
-    pub fn set_current_execution_path(&mut self, execution_path: Vec<u64>) {
-        let id = Self::compute_execution_path_id(&execution_path);
-        self.current_execution_path = Some(execution_path);
-        self.current_execution_path_id = Some(id);
+    /// Seed the corpus with the entry functions that could not get a default
+    /// input because they take `Object<T>` parameters, now that the object
+    /// pool may hold such objects. Returns the number of seeds added.
+    pub fn add_object_seeds(&mut self) -> usize {
+        let functions: Vec<PublicFunctionTarget> = self
+            .public_functions
+            .iter()
+            .filter(|function| function.is_entry() && Self::entry_payload_from_function(function).is_err())
+            .cloned()
+            .collect();
+        let mut added = 0;
+        'functions: for function in functions {
+            let mut args = Vec::new();
+            for param in function.parameters() {
+                let FunctionParameter::Value(tag) = param else {
+                    continue;
+                };
+                let bytes = match tag {
+                    TypeTag::Struct(struct_tag) => match FrameworkStruct::classify(struct_tag) {
+                        Some(FrameworkStruct::Object(resource)) => self.object_arg_bytes(&resource),
+                        _ => Self::default_arg_bytes(tag),
+                    },
+                    _ => Self::default_arg_bytes(tag),
+                };
+                match bytes {
+                    Some(bytes) => args.push(bytes),
+                    None => continue 'functions,
+                }
+            }
+            let entry =
+                AptosEntryFunction::new(function.module_id().clone(), function.name().clone(), Vec::new(), args);
+            let input = AptosFuzzerInput::new(TransactionPayload::EntryFunction(entry));
+            if self.corpus.add(Testcase::new(input)).is_ok() {
+                added += 1;
+            }
+        }
+        added
     }
 
-    pub fn current_execution_path_id(&self) -> Option<u64> {
-        self.current_execution_path_id
+    pub fn struct_layouts(&self) -> &StructLayoutRegistry {
+        &self.struct_layouts
     }
 
-    pub fn set_last_runtime_issues(&mut self, issues: Vec<RuntimeIssue>) {
-        self.last_runtime_issues = issues;
+    /// Register `function` as if a target module declared it.
+    #[cfg(test)]
+    pub(crate) fn add_public_function(&mut self, function: PublicFunctionTarget) {
+        let key = Self::function_key(function.module_id(), function.name());
+        self.function_lookup.insert(key, self.public_functions.len());
+        self.public_functions.push(function);
     }
 
-    pub fn last_runtime_issues(&self) -> &[RuntimeIssue] {
-        &self.last_runtime_issues
+    pub fn public_function(&self, module_id: &ModuleId, name: &Identifier) -> Option<&PublicFunctionTarget> {
+        let key = Self::function_key(module_id, name);
+        self.function_lookup
+            .get(&key)
+            .and_then(|idx| self.public_functions.get(*idx))
     }
 
-    pub fn record_current_execution_path_for(&mut self, input: &AptosFuzzerInput) -> Option<u64> {
-        match (self.current_execution_path_id, self.current_execution_path.as_ref()) {
-            (Some(id), Some(path)) => {
-                self.execution_paths_by_input
-                    .entry(input.clone())
-                    .or_insert_with(|| ExecutionPathRecord { id, path: path.clone() });
-                Some(id)
-            }
-            _ => None,
+    /// The public function a call with `ty_args` runs: a non-generic one, or
+    /// a generic one instantiated with them.
+    pub fn function_instance(
+        &self,
+        module_id: &ModuleId,
+        name: &Identifier,
+        ty_args: &[TypeTag],
+    ) -> Option<Cow<'_, PublicFunctionTarget>> {
+        if ty_args.is_empty() {
+            return self.public_function(module_id, name).map(Cow::Borrowed);
         }
+        self.generic_function(module_id, name)?
+            .instantiate(ty_args)
+            .map(Cow::Owned)
     }
 
-    pub fn mark_execution_path_seen(&mut self, path_id: u64) -> bool {
-        self.seen_execution_paths.insert(path_id)
+    pub fn generic_functions(&self) -> &[GenericFunctionTarget] {
+        &self.generic_functions
     }
 
-    pub fn has_seen_execution_path(&self, path_id: u64) -> bool {
-        self.seen_execution_paths.contains(&path_id)
+    pub fn generic_function(&self, module_id: &ModuleId, name: &Identifier) -> Option<&GenericFunctionTarget> {
+        self.generic_functions
+            .iter()
+            .find(|function| function.module_id() == module_id && function.name() == name)
     }
 
-    pub fn get_solution_execution_path(&self, input: &AptosFuzzerInput) -> Option<Vec<u64>> {
-        self.execution_paths_by_input
-            .get(input)
-            .map(|record| record.path.clone())
+    /// The generic function at `idx` instantiated with type arguments the
+    /// schedule picks.
+    pub fn schedule_instantiation(&mut self, idx: usize) -> Option<PublicFunctionTarget> {
+        let function = self.generic_functions.get(idx)?;
+        let ty_args = self.instantiations.choose(function, &mut self.rand)?;
+        function.instantiate(&ty_args)
     }
 
-    pub fn get_solution_execution_path_id(&self, input: &AptosFuzzerInput) -> Option<u64> {
-        self.execution_paths_by_input.get(input).map(|record| record.id)
+    pub fn instantiations(&self) -> &InstantiationSchedule {
+        &self.instantiations
     }
 
-    pub fn compute_execution_path_id(execution_path: &[u64]) -> u64 {
-        const FNV_OFFSET: u64 = 0xcbf29ce484222325;
-        const FNV_PRIME: u64 = 0x100000001b3;
-        execution_path.iter().fold(FNV_OFFSET, |hash, value| {
-            let hash = hash ^ *value;
-            hash.wrapping_mul(FNV_PRIME)
-        })
+    pub fn instantiations_mut(&mut self) -> &mut InstantiationSchedule {
+        &mut self.instantiations
     }
 
-    pub fn target_modules(&self) -> &[ModuleId] {
-        &self.target_modules
-    }
+    /// Stop fuzzing `functions`: they are no longer picked for calls and
+    /// their entry seeds leave the corpus.
+    pub fn remove_public_functions(&mut self, functions: &[FunctionRef]) {
+        let removed = |module_id: &ModuleId, name: &IdentStr| {
+            functions
+                .iter()
+                .any(|function| &function.module == module_id && function.name == name.as_str())
+        };
+        let unreachable: Vec<SkippedFunction> = self
+            .public_functions
+            .iter()
+            .map(|function| (function.module_id(), function.name()))
+            .chain(
+                self.generic_functions
+                    .iter()
+                    .map(|function| (function.module_id(), function.name())),
+            )
+            .filter(|(module_id, name)| removed(module_id, name))
+            .map(|(module_id, name)| SkippedFunction {
+                module_id: module_id.clone(),
+                name: name.clone(),
+                reason: SkipReason::Unreachable,
+            })
+            .collect();
+        self.skipped_functions.extend(unreachable);
+        self.public_functions
+            .retain(|function| !removed(function.module_id(), function.name()));
+        self.generic_functions
+            .retain(|function| !removed(function.module_id(), function.name()));
+        self.function_lookup = self
+            .public_functions
+            .iter()
+            .enumerate()
+            .map(|(idx, function)| (Self::function_key(function.module_id(), function.name()), idx))
+            .collect();
 
-    pub fn public_functions(&self) -> &[PublicFunctionTarget] {
-        &self.public_functions
+        let ids: Vec<_> = self.corpus.ids().collect();
+        for id in ids {
+            let seed = self.corpus.cloned_input_for_id(id).ok();
+            if let Some(TransactionPayload::EntryFunction(entry)) = seed.as_ref().map(AptosFuzzerInput::payload) {
+                if removed(entry.module(), entry.function()) {
+                    let _ = self.corpus.remove(id);
+                }
+            }
+        }
     }
 
-    pub fn public_function(&self, module_id: &ModuleId, name: &Identifier) -> Option<&PublicFunctionTarget> {
-        let key = Self::function_key(module_id, name);
-        self.function_lookup
-            .get(&key)
-            .and_then(|idx| self.public_functions.get(*idx))
+    /// Replace the target modules with those in `modules_dir`, published
+    /// into the fresh `aptos_state`, keeping the campaign's oracles,
+    /// properties and solutions. Targets and edge ids are rebuilt, so the
+    /// cumulative coverage starts over. Corpus entries calling a function
+    /// that was removed or whose parameters changed are dropped, and entry
+    /// seeds are added for new functions, and the rest of the corpus is left
+    /// to the revalidation stage to re-run. Returns how many entries were
+    /// dropped and added; initializers are detected again and still have to
+    /// be run.
+    pub fn reload_modules(&mut self, modules_dir: PathBuf, aptos_state: AptosCustomState) -> (usize, usize) {
+        let fresh = Self::with_aptos_state(modules_dir, self.map_size(), aptos_state);
+        let unchanged = |module_id: &ModuleId, name: &Identifier| {
+            let key = Self::function_key(module_id, name);
+            match (self.function_lookup.get(&key), fresh.function_lookup.get(&key)) {
+                (Some(&old), Some(&new)) => {
+                    self.public_functions[old].parameters() == fresh.public_functions[new].parameters()
+                }
+                _ => match (
+                    self.generic_function(module_id, name),
+                    fresh.generic_function(module_id, name),
+                ) {
+                    (Some(old), Some(new)) => old == new,
+                    _ => false,
+                },
+            }
+        };
+        let calls = |input: &AptosFuzzerInput| -> Vec<(ModuleId, Identifier)> {
+            match (input.payload(), input.script_sequence()) {
+                (TransactionPayload::EntryFunction(entry), _) => {
+                    vec![(entry.module().clone(), entry.function().to_owned())]
+                }
+                (_, Some(sequence)) => sequence
+                    .calls()
+                    .iter()
+                    .map(|call| (call.module().clone(), call.function().clone()))
+                    .collect(),
+                _ => Vec::new(),
+            }
+        };
+
+        let mut dropped = Vec::new();
+        for id in self.corpus.ids() {
+            let Ok(input) = self.corpus.cloned_input_for_id(id) else {
+                continue;
+            };
+            if !calls(&input).iter().all(|(module_id, name)| unchanged(module_id, name)) {
+                dropped.push(id);
+            }
+        }
+        let mut added = Vec::new();
+        for id in fresh.corpus.ids() {
+            let Ok(input) = fresh.corpus.cloned_input_for_id(id) else {
+                continue;
+            };
+            let calls = calls(&input);
+            if !calls.is_empty() &&
+                calls.iter().all(|(module_id, name)| {
+                    !self.function_lookup.contains_key(&Self::function_key(module_id, name)) &&
+                        self.generic_function(module_id, name).is_none()
+                })
+            {
+                added.push(input);
+            }
+        }
+        for &id in &dropped {
+            let _ = self.corpus.remove(id);
+        }
+        let added_count = added.len();
+        for input in added {
+            let _ = self.corpus.add(Testcase::new(input));
+        }
+
+        self.aptos_state = fresh.aptos_state;
+        self.target_modules = fresh.target_modules;
+        self.public_functions = fresh.public_functions;
+        self.generic_functions = fresh.generic_functions;
+        self.instantiations = fresh.instantiations;
+        self.function_lookup = fresh.function_lookup;
+        self.invariant_functions = fresh.invariant_functions;
+        self.skipped_functions = fresh.skipped_functions;
+        self.initializers = fresh.initializers;
+        self.init_modules = fresh.init_modules;
+        self.numeric_constants = fresh.numeric_constants;
+        self.struct_layouts = fresh.struct_layouts;
+        for address in fresh.address_list {
+            self.insert_pool_address(address);
+        }
+        self.cumulative_coverage = fresh.cumulative_coverage;
+        if self.signer_ownership.is_some() {
+            self.enable_signer_ownership();
+        }
+        self.request_revalidation();
+        (dropped.len(), added_count)
+    }
+
+    /// Publish the modules in `modules_dir` as an upgrade of the targets over
+    /// the current base state, resources the campaign built included, and
+    /// migrate the corpus as `reload_modules` does. Initializers are not run
+    /// again; as on chain, only the `init_module` of modules the upgrade adds
+    /// is. Reports what a compatible
+    /// upgrade may not change and the target resources the new modules
+    /// can't read.
+    pub fn upgrade_modules(&mut self, modules_dir: PathBuf) -> ModuleUpgrade {
+        let old_layouts = self.struct_layouts.clone();
+        let old_targets = self.target_modules.clone();
+        let function_names = |state: &Self| -> BTreeSet<String> {
+            state
+                .public_functions
+                .iter()
+                .map(|function| Self::function_key(function.module_id(), function.name()))
+                .chain(
+                    state
+                        .generic_functions
+                        .iter()
+                        .map(|function| Self::function_key(function.module_id(), function.name())),
+                )
+                .collect()
+        };
+        let old_functions = function_names(self);
+        let built_state = self.aptos_state.clone();
+        let (dropped, added) = self.reload_modules(modules_dir, built_state);
+        self.initializers.clear();
+        self.init_modules.retain(|module_id| !old_targets.contains(module_id));
+
+        let mut incompatibilities = Vec::new();
+        for module_id in &old_targets {
+            if self.target_modules.contains(module_id) {
+                incompatibilities.extend(old_layouts.incompatible_changes(&self.struct_layouts, module_id));
+            } else {
+                incompatibilities.push(format!("module {} was removed", module_id));
+            }
+        }
+        let new_functions = function_names(self);
+        incompatibilities.extend(
+            old_functions
+                .difference(&new_functions)
+                .map(|name| format!("function {} was removed or changed", name)),
+        );
+        let mut unreadable: Vec<String> = self
+            .aptos_state
+            .resources()
+            .into_iter()
+            .filter(|(_, tag, bytes)| {
+                self.target_modules.contains(&tag.module_id()) &&
+                    self.struct_layouts
+                        .decode(&TypeTag::Struct(Box::new(tag.clone())), bytes)
+                        .is_none()
+            })
+            .map(|(address, tag, _)| format!("{} at {}", tag.to_canonical_string(), address))
+            .collect();
+        unreadable.sort();
+        ModuleUpgrade {
+            dropped,
+            added,
+            incompatibilities,
+            unreadable,
+        }
     }
 
     pub fn set_static_findings(&mut self, findings: Vec<StaticAnalysisFinding>) {
@@ -498,15 +2320,25 @@ impl HasStartTime for AptosFuzzerState {
 }
 
 impl AptosFuzzerState {
-    fn make_empty_script_seed(state: &AptosCustomState) -> Option<AptosFuzzerInput> {
+    fn make_empty_script_seed(state: &AptosCustomState) -> Result<AptosFuzzerInput, FuzzerError> {
         let sequence = ScriptSequence::new();
         compile_sequence(&sequence, state.module_bytes())
             .map(|script| AptosFuzzerInput::with_script(TransactionPayload::Script(script), sequence))
     }
 
-    fn load_modules_from_path(path: &Path) -> Vec<LoadedModule> {
+    /// The modules under `path` as `with_aptos_state` loads them.
+    pub(crate) fn compiled_modules_in(path: &Path) -> Vec<CompiledModule> {
+        Self::load_modules_from_path(path, &mut Vec::new())
+            .into_iter()
+            .map(|loaded| loaded.module)
+            .collect()
+    }
+
+    /// Modules under `path`, skipping the files that fail to load, which are
+    /// added to `errors`.
+    fn load_modules_from_path(path: &Path, errors: &mut Vec<FuzzerError>) -> Vec<LoadedModule> {
         let mut files = Vec::new();
-        Self::collect_module_files(path, &mut files);
+        Self::collect_module_files(path, &mut files, errors);
         files.sort();
 
         let mut loaded = Vec::new();
@@ -514,15 +2346,18 @@ impl AptosFuzzerState {
         for file in files {
             let bytes = match fs::read(&file) {
                 Ok(bytes) => bytes,
-                Err(err) => {
-                    eprintln!("[aptos-fuzzer] failed to read module {}: {err}", file.display());
+                Err(source) => {
+                    errors.push(FuzzerError::ReadModule { path: file, source });
                     continue;
                 }
             };
             let module = match CompiledModule::deserialize(bytes.as_slice()) {
                 Ok(module) => module,
                 Err(err) => {
-                    eprintln!("[aptos-fuzzer] failed to deserialize module {}: {err}", file.display());
+                    errors.push(FuzzerError::DeserializeModule {
+                        path: file,
+                        reason: err.to_string(),
+                    });
                     continue;
                 }
             };
@@ -538,21 +2373,24 @@ impl AptosFuzzerState {
         loaded
     }
 
-    fn collect_module_files(path: &Path, files: &mut Vec<PathBuf>) {
+    fn collect_module_files(path: &Path, files: &mut Vec<PathBuf>, errors: &mut Vec<FuzzerError>) {
         if Self::is_dependency_path(path) {
             return;
         }
         if path.is_dir() {
             let entries = match fs::read_dir(path) {
                 Ok(entries) => entries,
-                Err(err) => {
-                    eprintln!("[aptos-fuzzer] failed to list directory {}: {err}", path.display());
+                Err(source) => {
+                    errors.push(FuzzerError::ListDirectory {
+                        path: path.to_path_buf(),
+                        source,
+                    });
                     return;
                 }
             };
             for entry in entries {
                 if let Ok(dir_entry) = entry {
-                    Self::collect_module_files(&dir_entry.path(), files);
+                    Self::collect_module_files(&dir_entry.path(), files, errors);
                 }
             }
             return;
@@ -568,7 +2406,12 @@ impl AptosFuzzerState {
             .any(|component| matches!(component, Component::Normal(name) if name.to_str() == Some("dependencies")))
     }
 
-    fn extract_public_functions(module_id: &ModuleId, module: &CompiledModule) -> Vec<PublicFunctionTarget> {
+    fn extract_public_functions(
+        module_id: &ModuleId,
+        module: &CompiledModule,
+        skipped: &mut Vec<SkippedFunction>,
+    ) -> Vec<PublicFunctionTarget> {
+        let metadata = get_metadata_from_compiled_code(module);
         let mut functions = Vec::new();
         for func_def in &module.function_defs {
             if func_def.visibility != Visibility::Public {
@@ -579,40 +2422,187 @@ impl AptosFuzzerState {
                 continue;
             }
 
+            let name = module.identifier_at(handle.name).to_owned();
+            let mut skip = |reason| {
+                skipped.push(SkippedFunction {
+                    module_id: module_id.clone(),
+                    name: name.clone(),
+                    reason,
+                })
+            };
             let params_sig = module.signature_at(handle.parameters);
             let parameters = match Self::parameters_from_signature(module, &params_sig.0) {
                 Some(params) => params,
-                None => continue,
+                None => {
+                    let token = params_sig
+                        .0
+                        .iter()
+                        .find(|token| Self::parameters_from_signature(module, std::slice::from_ref(*token)).is_none());
+                    // The whole signature when no single token is to blame
+                    skip(SkipReason::Parameter(token.map_or_else(
+                        || {
+                            let tokens: Vec<String> = params_sig
+                                .0
+                                .iter()
+                                .map(|token| Self::describe_token(module, token))
+                                .collect();
+                            format!("({})", tokens.join(", "))
+                        },
+                        |token| Self::describe_token(module, token),
+                    )));
+                    continue;
+                }
             };
 
             let returns_sig = module.signature_at(handle.return_);
             let return_types = match Self::signature_tokens_to_typetags(module, &returns_sig.0) {
                 Some(types) => types,
-                None => continue,
+                None => {
+                    let token = returns_sig
+                        .0
+                        .iter()
+                        .find(|token| Self::signature_token_to_type_tag(module, token).is_none());
+                    skip(SkipReason::Return(
+                        token.map_or_else(String::new, |token| Self::describe_token(module, token)),
+                    ));
+                    continue;
+                }
             };
 
-            let name = module.identifier_at(handle.name).to_owned();
+            let is_view = metadata
+                .as_ref()
+                .and_then(|metadata| metadata.fun_attributes.get(name.as_str()))
+                .is_some_and(|attrs| attrs.iter().any(|attr| attr.is_view_function()));
             functions.push(PublicFunctionTarget {
                 module_id: module_id.clone(),
                 name,
+                ty_args: Vec::new(),
                 parameters,
                 return_types,
                 is_entry: func_def.is_entry,
+                is_view,
             });
         }
         functions
     }
 
+    fn extract_generic_functions(
+        module_id: &ModuleId,
+        module: &CompiledModule,
+        skipped: &mut Vec<SkippedFunction>,
+    ) -> Vec<GenericFunctionTarget> {
+        let metadata = get_metadata_from_compiled_code(module);
+        let mut functions = Vec::new();
+        for func_def in &module.function_defs {
+            if func_def.visibility != Visibility::Public {
+                continue;
+            }
+            let handle = module.function_handle_at(func_def.function);
+            let name = module.identifier_at(handle.name);
+            let is_view = metadata
+                .as_ref()
+                .and_then(|metadata| metadata.fun_attributes.get(name.as_str()))
+                .is_some_and(|attrs| attrs.iter().any(|attr| attr.is_view_function()));
+            match GenericFunctionTarget::from_definition(module_id, module, func_def, is_view) {
+                Some(function) => functions.push(function),
+                None if !handle.type_parameters.is_empty() => skipped.push(SkippedFunction {
+                    module_id: module_id.clone(),
+                    name: name.to_owned(),
+                    reason: SkipReason::Generic,
+                }),
+                None => {}
+            }
+        }
+        functions
+    }
+
+    fn numeric_constants_of(module: &CompiledModule) -> Vec<[u8; 32]> {
+        module
+            .constant_pool
+            .iter()
+            .filter(|constant| {
+                matches!(
+                    constant.type_,
+                    SignatureToken::U8 |
+                        SignatureToken::U16 |
+                        SignatureToken::U32 |
+                        SignatureToken::U64 |
+                        SignatureToken::U128 |
+                        SignatureToken::U256
+                )
+            })
+            .filter(|constant| constant.data.len() <= 32)
+            .map(|constant| {
+                let mut value = [0u8; 32];
+                value[..constant.data.len()].copy_from_slice(&constant.data);
+                value
+            })
+            .collect()
+    }
+
+    fn address_constants_of(module: &CompiledModule) -> Vec<AccountAddress> {
+        module
+            .constant_pool
+            .iter()
+            .filter(|constant| constant.type_ == SignatureToken::Address)
+            .filter_map(|constant| AccountAddress::from_bytes(&constant.data).ok())
+            .collect()
+    }
+
+    /// `initialize`/`init` entry functions taking only signers.
+    fn detect_initializers(module_id: &ModuleId, module: &CompiledModule) -> Vec<InitializerCall> {
+        let mut calls = Vec::new();
+        for func_def in &module.function_defs {
+            let handle = module.function_handle_at(func_def.function);
+            if !handle.type_parameters.is_empty() || !func_def.is_entry {
+                continue;
+            }
+            let name = module.identifier_at(handle.name);
+            if !matches!(name.as_str(), "initialize" | "init") {
+                continue;
+            }
+            let signers_only = Self::parameters_from_signature(module, &module.signature_at(handle.parameters).0)
+                .is_some_and(|params| params.iter().all(|param| matches!(param, FunctionParameter::Signer)));
+            if signers_only {
+                calls.push(InitializerCall {
+                    module_id: module_id.clone(),
+                    function: name.to_owned(),
+                    args: Vec::new(),
+                });
+            }
+        }
+        calls
+    }
+
+    /// Whether `module` defines `init_module`, which the VM runs with the
+    /// publisher's signer when the module is first published.
+    fn has_init_module(module: &CompiledModule) -> bool {
+        module.function_defs.iter().any(|func_def| {
+            module
+                .identifier_at(module.function_handle_at(func_def.function).name)
+                .as_str() ==
+                "init_module"
+        })
+    }
+
+    /// Parameters of a callable function. `signer`/`&signer` all take the
+    /// sender; `vector<signer>` can't be passed to a transaction and rejects
+    /// the function. Other references can only be taken from earlier calls
+    /// of a sequence.
     fn parameters_from_signature(module: &CompiledModule, tokens: &[SignatureToken]) -> Option<Vec<FunctionParameter>> {
         let mut params = Vec::new();
         for token in tokens {
             match token {
                 SignatureToken::Signer => params.push(FunctionParameter::Signer),
+                SignatureToken::Vector(inner) if matches!(inner.as_ref(), SignatureToken::Signer) => return None,
                 SignatureToken::Reference(inner) | SignatureToken::MutableReference(inner) => {
                     if matches!(inner.as_ref(), SignatureToken::Signer) {
                         params.push(FunctionParameter::Signer);
                     } else {
-                        return None;
+                        params.push(FunctionParameter::Reference {
+                            ty: Self::signature_token_to_type_tag(module, inner)?,
+                            mutable: matches!(token, SignatureToken::MutableReference(_)),
+                        });
                     }
                 }
                 _ => {
@@ -659,6 +2649,20 @@ impl AptosFuzzerState {
         })
     }
 
+    /// Move syntax of `token`, with structs by their name in `module`.
+    fn describe_token(module: &CompiledModule, token: &SignatureToken) -> String {
+        match token {
+            SignatureToken::Reference(inner) => format!("&{}", Self::describe_token(module, inner)),
+            SignatureToken::MutableReference(inner) => format!("&mut {}", Self::describe_token(module, inner)),
+            SignatureToken::Vector(inner) => format!("vector<{}>", Self::describe_token(module, inner)),
+            SignatureToken::Struct(idx) | SignatureToken::StructInstantiation(idx, _) => {
+                module.identifier_at(module.struct_handle_at(*idx).name).to_string()
+            }
+            SignatureToken::TypeParameter(idx) => format!("T{}", idx),
+            other => format!("{:?}", other).to_lowercase(),
+        }
+    }
+
     fn struct_tag_from_handle(
         module: &CompiledModule,
         handle_idx: StructHandleIndex,
@@ -680,23 +2684,57 @@ impl AptosFuzzerState {
         })
     }
 
-    fn entry_payload_from_function(function: &PublicFunctionTarget) -> Option<TransactionPayload> {
+    pub(crate) fn entry_payload_from_function(
+        function: &PublicFunctionTarget,
+    ) -> Result<TransactionPayload, FuzzerError> {
         let mut args = Vec::new();
         for param in function.parameters() {
             if let FunctionParameter::Value(tag) = param {
-                let bytes = Self::default_arg_bytes(tag)?;
+                let bytes = Self::default_arg_bytes(tag).ok_or_else(|| FuzzerError::SeedArgument {
+                    function: format!("{}::{}", function.module_id(), function.name()),
+                    ty: tag.to_canonical_string(),
+                })?;
                 args.push(bytes);
             }
         }
 
-        let entry = AptosEntryFunction::new(function.module_id().clone(), function.name().clone(), Vec::new(), args);
-        Some(TransactionPayload::EntryFunction(entry))
+        let entry = AptosEntryFunction::new(
+            function.module_id().clone(),
+            function.name().clone(),
+            function.ty_args().to_vec(),
+            args,
+        );
+        Ok(TransactionPayload::EntryFunction(entry))
     }
 
     fn function_key(module_id: &ModuleId, name: &Identifier) -> String {
         format!("{}::{}", module_id, name)
     }
 
+    /// BCS encoding of a command-line argument: numbers, `true`/`false`,
+    /// addresses, `0x`-prefixed hex for `vector<u8>` and plain text for
+    /// `0x1::string::String`.
+    fn parse_arg_bytes(type_tag: &TypeTag, value: &str) -> anyhow::Result<Vec<u8>> {
+        let bytes = match type_tag {
+            TypeTag::Bool => bcs::to_bytes(&value.parse::<bool>()?)?,
+            TypeTag::U8 => bcs::to_bytes(&value.parse::<u8>()?)?,
+            TypeTag::U16 => bcs::to_bytes(&value.parse::<u16>()?)?,
+            TypeTag::U32 => bcs::to_bytes(&value.parse::<u32>()?)?,
+            TypeTag::U64 => bcs::to_bytes(&value.parse::<u64>()?)?,
+            TypeTag::U128 => bcs::to_bytes(&value.parse::<u128>()?)?,
+            TypeTag::U256 => bcs::to_bytes(&value.parse::<U256>()?)?,
+            TypeTag::Address => bcs::to_bytes(&AccountAddress::from_hex_literal(value)?)?,
+            TypeTag::Vector(inner) if **inner == TypeTag::U8 => {
+                bcs::to_bytes(&hex::decode(value.trim_start_matches("0x"))?)?
+            }
+            TypeTag::Struct(tag) if FrameworkStruct::classify(tag) == Some(FrameworkStruct::String) => {
+                bcs::to_bytes(value)?
+            }
+            _ => anyhow::bail!("unsupported argument type {}", type_tag.to_canonical_string()),
+        };
+        Ok(bytes)
+    }
+
     pub(crate) fn default_arg_bytes(type_tag: &TypeTag) -> Option<Vec<u8>> {
         match type_tag {
             TypeTag::Bool => bcs::to_bytes(&false).ok(),
@@ -707,16 +2745,23 @@ impl AptosFuzzerState {
             TypeTag::U128 => bcs::to_bytes(&0u128).ok(),
             TypeTag::U256 => bcs::to_bytes(&U256::from(0u8)).ok(),
             TypeTag::Address => bcs::to_bytes(&AccountAddress::ZERO).ok(),
-            TypeTag::Vector(inner) => match &**inner {
-                TypeTag::Bool => bcs::to_bytes::<Vec<bool>>(&Vec::new()).ok(),
-                TypeTag::U8 => bcs::to_bytes::<Vec<u8>>(&Vec::new()).ok(),
-                TypeTag::U16 => bcs::to_bytes::<Vec<u16>>(&Vec::new()).ok(),
-                TypeTag::U32 => bcs::to_bytes::<Vec<u32>>(&Vec::new()).ok(),
-                TypeTag::U64 => bcs::to_bytes::<Vec<u64>>(&Vec::new()).ok(),
-                TypeTag::U128 => bcs::to_bytes::<Vec<u128>>(&Vec::new()).ok(),
-                TypeTag::U256 => bcs::to_bytes::<Vec<U256>>(&Vec::new()).ok(),
-                TypeTag::Address => bcs::to_bytes::<Vec<AccountAddress>>(&Vec::new()).ok(),
-                _ => None,
+            // The empty vector, for any element type an argument can hold:
+            // nested vectors and framework structs included
+            TypeTag::Vector(inner) => {
+                let is_object = matches!(
+                    &**inner,
+                    TypeTag::Struct(tag) if matches!(FrameworkStruct::classify(tag), Some(FrameworkStruct::Object(_)))
+                );
+                (is_object || Self::default_arg_bytes(inner).is_some()).then(|| encode_vector(&[]))
+            }
+            TypeTag::Struct(tag) => match FrameworkStruct::classify(tag)? {
+                FrameworkStruct::String => bcs::to_bytes("").ok(),
+                // `option::none()`
+                FrameworkStruct::Option(_) => bcs::to_bytes::<Vec<u8>>(&Vec::new()).ok(),
+                FrameworkStruct::FixedPoint32 => bcs::to_bytes(&0u64).ok(),
+                FrameworkStruct::FixedPoint64 => bcs::to_bytes(&0u128).ok(),
+                // Needs an existing object, see `object_arg_bytes`
+                FrameworkStruct::Object(_) => None,
             },
             _ => None,
         }
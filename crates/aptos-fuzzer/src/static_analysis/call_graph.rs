@@ -0,0 +1,215 @@
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::fmt;
+use std::fmt::Write;
+
+use aptos_move_binary_format::access::ModuleAccess;
+use aptos_move_binary_format::file_format::{Bytecode, FunctionHandleIndex};
+use aptos_move_binary_format::CompiledModule;
+use aptos_move_core_types::language_storage::ModuleId;
+
+/// A function, by defining module and name.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FunctionRef {
+    pub module: ModuleId,
+    pub name: String,
+}
+
+impl FunctionRef {
+    /// The function `handle` refers to in `module`.
+    pub fn from_handle(module: &CompiledModule, handle: FunctionHandleIndex) -> Self {
+        let handle = module.function_handle_at(handle);
+        Self {
+            module: module.module_id_for_handle(module.module_handle_at(handle.module)),
+            name: module.identifier_at(handle.name).to_string(),
+        }
+    }
+}
+
+impl fmt::Display for FunctionRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}::{}::{}",
+            self.module.address().to_hex_literal(),
+            self.module.name(),
+            self.name
+        )
+    }
+}
+
+/// Calls between the functions defined in a set of modules. Functions
+/// outside the set only appear as callees.
+#[derive(Debug, Default)]
+pub struct CallGraph {
+    /// Call sites of each defined function, as (pc, callee)
+    calls: BTreeMap<FunctionRef, Vec<(u16, FunctionRef)>>,
+    /// Functions that move, remove or mutably borrow a resource themselves
+    writers: BTreeSet<FunctionRef>,
+}
+
+impl CallGraph {
+    pub fn build<'a>(modules: impl IntoIterator<Item = &'a CompiledModule>) -> Self {
+        let mut graph = Self::default();
+        for module in modules {
+            for def in module.function_defs() {
+                let function = FunctionRef::from_handle(module, def.function);
+                let mut calls = Vec::new();
+                for (pc, instruction) in def.code.iter().flat_map(|code| code.code.iter().enumerate()) {
+                    let callee = match instruction {
+                        Bytecode::Call(handle) => *handle,
+                        Bytecode::CallGeneric(idx) => module.function_instantiation_at(*idx).handle,
+                        instruction => {
+                            if writes_global_state(instruction) {
+                                graph.writers.insert(function.clone());
+                            }
+                            continue;
+                        }
+                    };
+                    calls.push((pc as u16, FunctionRef::from_handle(module, callee)));
+                }
+                graph.calls.insert(function, calls);
+            }
+        }
+        graph
+    }
+
+    /// Functions defined in the loaded modules.
+    pub fn functions(&self) -> impl Iterator<Item = &FunctionRef> {
+        self.calls.keys()
+    }
+
+    pub fn call_sites(&self, function: &FunctionRef) -> &[(u16, FunctionRef)] {
+        self.calls.get(function).map_or(&[], Vec::as_slice)
+    }
+
+    /// Whether calling `function` can update global storage of the loaded
+    /// modules, directly or through its callees.
+    pub fn writes_state(&self, function: &FunctionRef) -> bool {
+        let mut seen = BTreeSet::from([function]);
+        let mut queue = VecDeque::from([function]);
+        while let Some(function) = queue.pop_front() {
+            if self.writers.contains(function) {
+                return true;
+            }
+            for (_, callee) in self.call_sites(function) {
+                if seen.insert(callee) {
+                    queue.push_back(callee);
+                }
+            }
+        }
+        false
+    }
+
+    /// Groups of functions that call themselves, directly or through each
+    /// other: the strongly connected components with a cycle, each sorted.
+    pub fn recursive_groups(&self) -> Vec<Vec<FunctionRef>> {
+        let mut tarjan = Tarjan {
+            graph: self,
+            index: BTreeMap::new(),
+            low: BTreeMap::new(),
+            stack: Vec::new(),
+            on_stack: BTreeSet::new(),
+            groups: Vec::new(),
+        };
+        for function in self.functions() {
+            if !tarjan.index.contains_key(function) {
+                tarjan.visit(function);
+            }
+        }
+        tarjan.groups
+    }
+
+    /// The graph in Graphviz DOT, with `attributes(function)`, e.g.
+    /// `fillcolor="red"`, added to each function's node. Functions outside
+    /// the loaded modules appear as the callees they are.
+    pub fn to_dot(&self, mut attributes: impl FnMut(&FunctionRef) -> String) -> String {
+        let mut nodes = BTreeSet::new();
+        for (function, calls) in &self.calls {
+            nodes.insert(function);
+            nodes.extend(calls.iter().map(|(_, callee)| callee));
+        }
+        let mut dot = String::from("digraph call_graph {\n    node [shape=box, style=filled, fillcolor=white];\n");
+        for node in nodes {
+            let extra = attributes(node);
+            let separator = if extra.is_empty() { "" } else { ", " };
+            let _ = writeln!(
+                dot,
+                "    \"{}\" [label=\"{}::{}\"{}{}];",
+                node,
+                node.module.name(),
+                node.name,
+                separator,
+                extra
+            );
+        }
+        for (caller, calls) in &self.calls {
+            let callees: BTreeSet<&FunctionRef> = calls.iter().map(|(_, callee)| callee).collect();
+            for callee in callees {
+                let _ = writeln!(dot, "    \"{}\" -> \"{}\";", caller, callee);
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+struct Tarjan<'a> {
+    graph: &'a CallGraph,
+    index: BTreeMap<&'a FunctionRef, usize>,
+    low: BTreeMap<&'a FunctionRef, usize>,
+    stack: Vec<&'a FunctionRef>,
+    on_stack: BTreeSet<&'a FunctionRef>,
+    groups: Vec<Vec<FunctionRef>>,
+}
+
+impl<'a> Tarjan<'a> {
+    fn visit(&mut self, function: &'a FunctionRef) {
+        let index = self.index.len();
+        self.index.insert(function, index);
+        self.low.insert(function, index);
+        self.stack.push(function);
+        self.on_stack.insert(function);
+
+        let graph = self.graph;
+        let mut calls_itself = false;
+        for (_, callee) in graph.call_sites(function) {
+            calls_itself |= callee == function;
+            if !self.index.contains_key(callee) {
+                self.visit(callee);
+                let low = self.low[function].min(self.low[callee]);
+                self.low.insert(function, low);
+            } else if self.on_stack.contains(callee) {
+                let low = self.low[function].min(self.index[callee]);
+                self.low.insert(function, low);
+            }
+        }
+
+        if self.low[function] != index {
+            return;
+        }
+        let mut group = Vec::new();
+        while let Some(member) = self.stack.pop() {
+            self.on_stack.remove(member);
+            group.push(member.clone());
+            if member == function {
+                break;
+            }
+        }
+        if group.len() > 1 || calls_itself {
+            group.sort();
+            self.groups.push(group);
+        }
+    }
+}
+
+pub(super) fn writes_global_state(instruction: &Bytecode) -> bool {
+    matches!(
+        instruction,
+        Bytecode::MoveTo(_) |
+            Bytecode::MoveToGeneric(_) |
+            Bytecode::MoveFrom(_) |
+            Bytecode::MoveFromGeneric(_) |
+            Bytecode::MutBorrowGlobal(_) |
+            Bytecode::MutBorrowGlobalGeneric(_)
+    )
+}
@@ -0,0 +1,353 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use aptos_move_binary_format::control_flow_graph::{BlockId, ControlFlowGraph, VMControlFlowGraph};
+use aptos_move_binary_format::file_format::{Bytecode, CodeOffset};
+use aptos_move_core_types::u256::U256;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Const {
+    Bool(bool),
+    Int(U256),
+}
+
+/// Known value of each local, `None` when it may differ between runs.
+type Locals = Vec<Option<Const>>;
+
+/// What constant propagation proves never executes in a function.
+#[derive(Clone, Debug, Default)]
+pub struct DeadCode {
+    /// Conditional branches whose condition is always the same, with its
+    /// value
+    pub constant_branches: Vec<(CodeOffset, bool)>,
+    /// First and last offset of each unreachable block
+    pub dead_blocks: Vec<(CodeOffset, CodeOffset)>,
+    /// CFG edges that are never taken, as (end of the block left, start of
+    /// the block entered)
+    pub dead_edges: BTreeSet<(CodeOffset, CodeOffset)>,
+}
+
+impl DeadCode {
+    pub fn is_empty(&self) -> bool {
+        self.constant_branches.is_empty() && self.dead_edges.is_empty()
+    }
+}
+
+/// Propagate constants through the locals and the operand stack of `code`
+/// to find the branches that always go the same way and what they cut off.
+/// Runs on VM bytecode rather than the stackless form so that the dead edges
+/// line up with the coverage map's.
+pub fn dead_code(code: &[Bytecode]) -> DeadCode {
+    let cfg = VMControlFlowGraph::new(code);
+    let entry = cfg.entry_block_id();
+    let mut states: BTreeMap<BlockId, Locals> = BTreeMap::from([(entry, Vec::new())]);
+    let mut worklist = vec![entry];
+    while let Some(block) = worklist.pop() {
+        let (exit, condition) = run_block(code, &cfg, block, &states[&block]);
+        for next in live_successors(code, &cfg, block, condition) {
+            let changed = match states.get_mut(&next) {
+                Some(state) => join(state, &exit),
+                None => {
+                    states.insert(next, exit.clone());
+                    true
+                }
+            };
+            if changed {
+                worklist.push(next);
+            }
+        }
+    }
+
+    let mut dead = DeadCode::default();
+    for block in cfg.blocks() {
+        let end = cfg.block_end(block);
+        let Some(state) = states.get(&block) else {
+            dead.dead_blocks.push((cfg.block_start(block), end));
+            for next in cfg.successors(block) {
+                dead.dead_edges.insert((end, *next));
+            }
+            continue;
+        };
+        let (_, condition) = run_block(code, &cfg, block, state);
+        if let Some(condition) = condition {
+            dead.constant_branches.push((end, condition));
+            let live = live_successors(code, &cfg, block, Some(condition));
+            for next in cfg.successors(block) {
+                if !live.contains(next) {
+                    dead.dead_edges.insert((end, *next));
+                }
+            }
+        }
+    }
+    dead.dead_blocks.sort_unstable();
+    dead
+}
+
+/// Lower each local of `state` to unknown where `other` disagrees. Returns
+/// whether `state` changed.
+fn join(state: &mut Locals, other: &Locals) -> bool {
+    let mut changed = false;
+    if state.len() < other.len() {
+        state.resize(other.len(), None);
+    }
+    for (idx, value) in state.iter_mut().enumerate() {
+        if value.is_some() && *value != other.get(idx).copied().flatten() {
+            *value = None;
+            changed = true;
+        }
+    }
+    changed
+}
+
+/// Successors of `block` that can run, given the value of its branch
+/// condition if that is known.
+fn live_successors(
+    code: &[Bytecode],
+    cfg: &VMControlFlowGraph,
+    block: BlockId,
+    condition: Option<bool>,
+) -> Vec<BlockId> {
+    let end = cfg.block_end(block);
+    let taken = match (&code[end as usize], condition) {
+        (Bytecode::BrTrue(target), Some(condition)) => Some(if condition { *target } else { end + 1 }),
+        (Bytecode::BrFalse(target), Some(condition)) => Some(if condition { end + 1 } else { *target }),
+        _ => None,
+    };
+    match taken {
+        Some(target) => vec![target],
+        None => cfg.successors(block).clone(),
+    }
+}
+
+/// Locals at the end of `block` and the value of its branch condition, if
+/// it ends in a conditional branch on a known value. The operand stack is
+/// assumed unknown on entry.
+fn run_block(code: &[Bytecode], cfg: &VMControlFlowGraph, block: BlockId, entry: &Locals) -> (Locals, Option<bool>) {
+    let mut locals = entry.clone();
+    let mut stack: Vec<Option<Const>> = Vec::new();
+    let mut condition = None;
+    for pc in cfg.block_start(block)..=cfg.block_end(block) {
+        let mut pop = || stack.pop().flatten();
+        let value = match &code[pc as usize] {
+            Bytecode::LdTrue => Some(Const::Bool(true)),
+            Bytecode::LdFalse => Some(Const::Bool(false)),
+            Bytecode::LdU8(value) => Some(Const::Int(U256::from(*value))),
+            Bytecode::LdU16(value) => Some(Const::Int(U256::from(*value))),
+            Bytecode::LdU32(value) => Some(Const::Int(U256::from(*value))),
+            Bytecode::LdU64(value) => Some(Const::Int(U256::from(*value))),
+            Bytecode::LdU128(value) => Some(Const::Int(U256::from(*value))),
+            Bytecode::LdU256(value) => Some(Const::Int(*value)),
+            Bytecode::CopyLoc(idx) | Bytecode::MoveLoc(idx) => locals.get(*idx as usize).copied().flatten(),
+            Bytecode::StLoc(idx) => {
+                let value = pop();
+                let idx = *idx as usize;
+                if locals.len() <= idx {
+                    locals.resize(idx + 1, None);
+                }
+                locals[idx] = value;
+                continue;
+            }
+            Bytecode::MutBorrowLoc(idx) => {
+                // Writes through the reference are not followed
+                if let Some(local) = locals.get_mut(*idx as usize) {
+                    *local = None;
+                }
+                None
+            }
+            Bytecode::ImmBorrowLoc(_) => None,
+            Bytecode::Pop => {
+                pop();
+                continue;
+            }
+            Bytecode::BrTrue(_) | Bytecode::BrFalse(_) => {
+                if let Some(Const::Bool(value)) = pop() {
+                    condition = Some(value);
+                }
+                continue;
+            }
+            Bytecode::Not => match pop() {
+                Some(Const::Bool(value)) => Some(Const::Bool(!value)),
+                _ => None,
+            },
+            Bytecode::And | Bytecode::Or => {
+                let rhs = pop();
+                let lhs = pop();
+                let is_and = matches!(code[pc as usize], Bytecode::And);
+                match (lhs, rhs) {
+                    (Some(Const::Bool(lhs)), Some(Const::Bool(rhs))) => {
+                        Some(Const::Bool(if is_and { lhs && rhs } else { lhs || rhs }))
+                    }
+                    // One side alone decides the result
+                    (Some(Const::Bool(value)), _) | (_, Some(Const::Bool(value))) if value != is_and => {
+                        Some(Const::Bool(value))
+                    }
+                    _ => None,
+                }
+            }
+            Bytecode::Eq | Bytecode::Neq => {
+                let rhs = pop();
+                let lhs = pop();
+                let is_eq = matches!(code[pc as usize], Bytecode::Eq);
+                match (lhs, rhs) {
+                    (Some(lhs), Some(rhs)) => Some(Const::Bool((lhs == rhs) == is_eq)),
+                    _ => None,
+                }
+            }
+            instruction @ (Bytecode::Lt | Bytecode::Gt | Bytecode::Le | Bytecode::Ge) => {
+                let rhs = pop();
+                let lhs = pop();
+                match (lhs, rhs) {
+                    (Some(Const::Int(lhs)), Some(Const::Int(rhs))) => Some(Const::Bool(match instruction {
+                        Bytecode::Lt => lhs < rhs,
+                        Bytecode::Gt => lhs > rhs,
+                        Bytecode::Le => lhs <= rhs,
+                        _ => lhs >= rhs,
+                    })),
+                    _ => None,
+                }
+            }
+            Bytecode::Add |
+            Bytecode::Sub |
+            Bytecode::Mul |
+            Bytecode::Div |
+            Bytecode::Mod |
+            Bytecode::BitOr |
+            Bytecode::BitAnd |
+            Bytecode::Xor |
+            Bytecode::Shl |
+            Bytecode::Shr => {
+                pop();
+                pop();
+                None
+            }
+            instruction @ (Bytecode::CastU8 |
+            Bytecode::CastU16 |
+            Bytecode::CastU32 |
+            Bytecode::CastU64 |
+            Bytecode::CastU128 |
+            Bytecode::CastU256) => {
+                let max = match instruction {
+                    Bytecode::CastU8 => U256::from(u8::MAX),
+                    Bytecode::CastU16 => U256::from(u16::MAX),
+                    Bytecode::CastU32 => U256::from(u32::MAX),
+                    Bytecode::CastU64 => U256::from(u64::MAX),
+                    Bytecode::CastU128 => U256::from(u128::MAX),
+                    _ => U256::max_value(),
+                };
+                // A cast either keeps the value or aborts
+                pop().filter(|value| matches!(value, Const::Int(value) if *value <= max))
+            }
+            _ => {
+                // Unmodeled: whatever it pops and pushes is unknown
+                stack.clear();
+                continue;
+            }
+        };
+        stack.push(value);
+    }
+    (locals, condition)
+}
+
+#[cfg(test)]
+mod tests {
+    use aptos_move_binary_format::file_format::Bytecode;
+
+    use super::dead_code;
+
+    #[test]
+    fn constant_condition_cuts_off_the_other_branch() {
+        let code = vec![
+            Bytecode::LdTrue,
+            Bytecode::BrFalse(4),
+            Bytecode::LdU64(1),
+            Bytecode::Ret,
+            Bytecode::LdU64(2),
+            Bytecode::Ret,
+        ];
+        let dead = dead_code(&code);
+        assert_eq!(dead.constant_branches, vec![(1, true)]);
+        assert_eq!(dead.dead_blocks, vec![(4, 5)]);
+        assert_eq!(dead.dead_edges.into_iter().collect::<Vec<_>>(), vec![(1, 4)]);
+    }
+
+    #[test]
+    fn comparisons_of_constant_locals_are_folded() {
+        let code = vec![
+            Bytecode::LdU64(5),
+            Bytecode::StLoc(0),
+            Bytecode::CopyLoc(0),
+            Bytecode::LdU64(3),
+            Bytecode::Lt,
+            Bytecode::BrTrue(7),
+            Bytecode::Ret,
+            Bytecode::Ret,
+        ];
+        let dead = dead_code(&code);
+        assert_eq!(dead.constant_branches, vec![(5, false)]);
+        assert_eq!(dead.dead_blocks, vec![(7, 7)]);
+        assert!(dead.dead_edges.contains(&(5, 7)));
+    }
+
+    #[test]
+    fn parameters_are_unknown() {
+        let code = vec![Bytecode::CopyLoc(0), Bytecode::BrTrue(3), Bytecode::Ret, Bytecode::Ret];
+        assert!(dead_code(&code).is_empty());
+    }
+
+    #[test]
+    fn one_known_operand_decides_and() {
+        let code = vec![
+            Bytecode::CopyLoc(0),
+            Bytecode::LdFalse,
+            Bytecode::And,
+            Bytecode::BrTrue(5),
+            Bytecode::Ret,
+            Bytecode::Ret,
+        ];
+        assert_eq!(dead_code(&code).constant_branches, vec![(3, false)]);
+    }
+
+    /// Stores `then` or `otherwise` in local 1 depending on the parameter,
+    /// then branches on local 1.
+    fn branch_on_merged_local(then: Bytecode, otherwise: Bytecode) -> Vec<Bytecode> {
+        vec![
+            Bytecode::CopyLoc(0),
+            Bytecode::BrTrue(5),
+            otherwise,
+            Bytecode::StLoc(1),
+            Bytecode::Branch(7),
+            then,
+            Bytecode::StLoc(1),
+            Bytecode::CopyLoc(1),
+            Bytecode::BrTrue(10),
+            Bytecode::Ret,
+            Bytecode::Ret,
+        ]
+    }
+
+    #[test]
+    fn paths_that_agree_keep_the_constant() {
+        let dead = dead_code(&branch_on_merged_local(Bytecode::LdTrue, Bytecode::LdTrue));
+        assert_eq!(dead.constant_branches, vec![(8, true)]);
+        assert_eq!(dead.dead_blocks, vec![(9, 9)]);
+    }
+
+    #[test]
+    fn paths_that_disagree_lose_the_constant() {
+        assert!(dead_code(&branch_on_merged_local(Bytecode::LdTrue, Bytecode::LdFalse)).is_empty());
+    }
+
+    #[test]
+    fn mutable_borrows_forget_the_local() {
+        let code = vec![
+            Bytecode::LdTrue,
+            Bytecode::StLoc(0),
+            Bytecode::MutBorrowLoc(0),
+            Bytecode::Pop,
+            Bytecode::CopyLoc(0),
+            Bytecode::BrTrue(7),
+            Bytecode::Ret,
+            Bytecode::Ret,
+        ];
+        assert!(dead_code(&code).is_empty());
+    }
+}
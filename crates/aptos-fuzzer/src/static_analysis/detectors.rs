@@ -1,14 +1,20 @@
-use std::collections::{BTreeMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
 
 use aptos_move_binary_format::access::ModuleAccess;
 use aptos_move_binary_format::internals::ModuleIndex;
 use aptos_move_binary_format::CompiledModule;
+use aptos_move_core_types::account_address::AccountAddress;
+use aptos_move_core_types::language_storage::ModuleId;
 use move_model::model::ModuleEnv;
 use move_model::symbol::SymbolPool;
 use move_model::ty::{PrimitiveType, Type};
 use move_stackless_bytecode::stackless_bytecode::{Bytecode, Constant, Operation};
 
 use super::bytecode::{collect_function_infos, get_def_bytecode, FunctionInfo};
+use super::call_graph::{writes_global_state, CallGraph, FunctionRef};
+use super::constant_propagation::dead_code;
+use crate::concolic::Severity;
+use crate::source_location::SourceLocation;
 
 #[derive(Debug, Clone)]
 pub enum FindingKind {
@@ -21,6 +27,12 @@ pub enum FindingKind {
     UnusedPrivateFunction,
     UnusedFriendFunction,
     UnusedStruct,
+    Recursion,
+    CallBeforeStateUpdate,
+    DeadCode,
+    FriendOutsidePackage,
+    CapabilityLeak,
+    UnusedTypeParameter,
 }
 
 impl FindingKind {
@@ -35,6 +47,29 @@ impl FindingKind {
             FindingKind::UnusedPrivateFunction => "UnusedPrivateFunction",
             FindingKind::UnusedFriendFunction => "UnusedFriendFunction",
             FindingKind::UnusedStruct => "UnusedStruct",
+            FindingKind::Recursion => "Recursion",
+            FindingKind::CallBeforeStateUpdate => "CallBeforeStateUpdate",
+            FindingKind::DeadCode => "DeadCode",
+            FindingKind::FriendOutsidePackage => "FriendOutsidePackage",
+            FindingKind::CapabilityLeak => "CapabilityLeak",
+            FindingKind::UnusedTypeParameter => "UnusedTypeParameter",
+        }
+    }
+
+    pub fn default_severity(&self) -> Severity {
+        match self {
+            FindingKind::BoolJudgement |
+            FindingKind::TypeConversion |
+            FindingKind::UnusedConst |
+            FindingKind::UnusedPrivateFunction |
+            FindingKind::UnusedFriendFunction |
+            FindingKind::UnusedStruct |
+            FindingKind::UnusedTypeParameter => Severity::Info,
+            FindingKind::UncheckedReturn | FindingKind::Recursion | FindingKind::DeadCode => Severity::Low,
+            FindingKind::InfiniteLoop | FindingKind::PrecisionLoss | FindingKind::FriendOutsidePackage => {
+                Severity::Medium
+            }
+            FindingKind::CallBeforeStateUpdate | FindingKind::CapabilityLeak => Severity::High,
         }
     }
 }
@@ -42,18 +77,24 @@ impl FindingKind {
 #[derive(Debug, Clone)]
 pub struct StaticAnalysisFinding {
     pub kind: FindingKind,
+    /// The kind's default, unless overridden by the configuration
+    pub severity: Severity,
     pub module: String,
     pub function: Option<String>,
     pub detail: String,
+    /// Declaration of `function` (or of the module), when built from source
+    pub location: Option<SourceLocation>,
 }
 
 impl StaticAnalysisFinding {
     pub fn new(kind: FindingKind, module: String, function: Option<String>, detail: String) -> Self {
         Self {
+            severity: kind.default_severity(),
             kind,
             module,
             function,
             detail,
+            location: None,
         }
     }
 }
@@ -62,6 +103,7 @@ pub fn analyze_module(
     module_env: &ModuleEnv,
     compiled: &CompiledModule,
     symbol_pool: &SymbolPool,
+    call_graph: &CallGraph,
 ) -> Vec<StaticAnalysisFinding> {
     let mut findings = Vec::new();
     let module_name = module_env.get_full_name_str().to_string();
@@ -77,10 +119,29 @@ pub fn analyze_module(
     analyze_unused_const(&module_name, compiled, &mut findings);
     analyze_unused_private_fun(&module_name, compiled, &mut findings);
     analyze_unused_struct(&module_name, compiled, &mut findings);
+    analyze_recursion(&module_name, compiled, call_graph, &mut findings);
+    analyze_call_before_state_update(&module_name, compiled, call_graph, &mut findings);
+    analyze_dead_code(&module_name, compiled, &mut findings);
+    analyze_capability_leak(&module_name, compiled, &mut findings);
+    analyze_unused_type_params(&module_name, module_env, compiled, symbol_pool, &mut findings);
 
     findings
 }
 
+/// Whole-program findings for `compiled`, one of the modules in `package`:
+/// what it exposes to modules outside of the package.
+pub fn analyze_exposure(
+    module_env: &ModuleEnv,
+    compiled: &CompiledModule,
+    package: &BTreeSet<ModuleId>,
+    call_graph: &CallGraph,
+) -> Vec<StaticAnalysisFinding> {
+    let mut findings = Vec::new();
+    let module_name = module_env.get_full_name_str().to_string();
+    analyze_friends_outside_package(&module_name, compiled, package, call_graph, &mut findings);
+    findings
+}
+
 fn analyze_unused_private_fun(module_name: &str, compiled: &CompiledModule, findings: &mut Vec<StaticAnalysisFinding>) {
     use aptos_move_binary_format::file_format::Visibility;
     let mut private = BTreeMap::new();
@@ -197,6 +258,520 @@ fn analyze_unused_struct(module_name: &str, compiled: &CompiledModule, findings:
     }
 }
 
+fn analyze_dead_code(module_name: &str, compiled: &CompiledModule, findings: &mut Vec<StaticAnalysisFinding>) {
+    for def in compiled.function_defs() {
+        let Some(code) = &def.code else {
+            continue;
+        };
+        let dead = dead_code(&code.code);
+        if dead.is_empty() {
+            continue;
+        }
+        let branches = dead
+            .constant_branches
+            .iter()
+            .map(|(offset, value)| format!("{} (always {})", offset, value))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let blocks = dead
+            .dead_blocks
+            .iter()
+            .map(|(start, end)| format!("{}-{}", start, end))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let detail = match (branches.is_empty(), blocks.is_empty()) {
+            (false, false) => format!(
+                "Constant branch condition at offset {}; unreachable offsets {}",
+                branches, blocks
+            ),
+            (false, true) => format!("Constant branch condition at offset {}", branches),
+            _ => format!("Unreachable offsets {}", blocks),
+        };
+        let handle = compiled.function_handle_at(def.function);
+        findings.push(StaticAnalysisFinding::new(
+            FindingKind::DeadCode,
+            module_name.to_string(),
+            Some(compiled.identifier_at(handle.name).to_string()),
+            detail,
+        ));
+    }
+}
+
+fn analyze_friends_outside_package(
+    module_name: &str,
+    compiled: &CompiledModule,
+    package: &BTreeSet<ModuleId>,
+    call_graph: &CallGraph,
+    findings: &mut Vec<StaticAnalysisFinding>,
+) {
+    use aptos_move_binary_format::file_format::Visibility;
+    let module_id = compiled.self_id();
+    // `public(package)` functions are friend functions too: the compiler
+    // befriends every module of the package
+    let exposed: BTreeSet<String> = compiled
+        .function_defs()
+        .iter()
+        .filter(|def| def.visibility == Visibility::Friend)
+        .map(|def| {
+            compiled
+                .identifier_at(compiled.function_handle_at(def.function).name)
+                .to_string()
+        })
+        .collect();
+    if exposed.is_empty() {
+        return;
+    }
+    let names: Vec<String> = exposed.iter().map(|name| format!("`{}`", name)).collect();
+    for friend in compiled.immediate_friends() {
+        if package.contains(&friend) {
+            continue;
+        }
+        let callers: Vec<String> = call_graph
+            .functions()
+            .filter(|function| function.module == friend)
+            .filter(|function| {
+                call_graph
+                    .call_sites(function)
+                    .iter()
+                    .any(|(_, callee)| callee.module == module_id && exposed.contains(&callee.name))
+            })
+            .map(|function| format!("`{}`", function))
+            .collect();
+        let friend_name = format!("{}::{}", friend.address().to_hex_literal(), friend.name());
+        let mut detail = format!(
+            "Friend functions {} are callable from `{}`, outside the package",
+            names.join(", "),
+            friend_name
+        );
+        if !callers.is_empty() {
+            detail.push_str(&format!("; called by {}", callers.join(", ")));
+        } else if !call_graph.functions().any(|function| function.module == friend) {
+            detail.push_str(&format!("; `{}` is not deployed", friend_name));
+        }
+        findings.push(StaticAnalysisFinding::new(
+            FindingKind::FriendOutsidePackage,
+            module_name.to_string(),
+            None,
+            detail,
+        ));
+    }
+}
+
+/// Framework structs that grant the power to mint, move or sign for what
+/// they were created for.
+const FRAMEWORK_CAPABILITIES: &[(&str, &str)] = &[
+    ("account", "SignerCapability"),
+    ("coin", "MintCapability"),
+    ("coin", "BurnCapability"),
+    ("coin", "FreezeCapability"),
+    ("fungible_asset", "MintRef"),
+    ("fungible_asset", "TransferRef"),
+    ("fungible_asset", "BurnRef"),
+    ("fungible_asset", "MutateMetadataRef"),
+    ("object", "ConstructorRef"),
+    ("object", "ExtendRef"),
+    ("object", "TransferRef"),
+    ("object", "DeleteRef"),
+];
+
+/// Flag public functions that hand a signer or a capability-like struct, by
+/// value or by reference, to any caller without checking a signer first.
+fn analyze_capability_leak(module_name: &str, compiled: &CompiledModule, findings: &mut Vec<StaticAnalysisFinding>) {
+    use aptos_move_binary_format::file_format::{Bytecode as MoveBytecode, SignatureToken, Visibility};
+    for def in compiled.function_defs() {
+        if def.visibility != Visibility::Public {
+            continue;
+        }
+        let handle = compiled.function_handle_at(def.function);
+        // A signer parameter the body reads is taken as an authorization check
+        let signers: Vec<u8> = compiled
+            .signature_at(handle.parameters)
+            .0
+            .iter()
+            .enumerate()
+            .filter(|(_, token)| match token {
+                SignatureToken::Reference(inner) => **inner == SignatureToken::Signer,
+                token => *token == SignatureToken::Signer,
+            })
+            .map(|(idx, _)| idx as u8)
+            .collect();
+        let checks_signer = def.code.as_ref().is_some_and(|code| {
+            code.code.iter().any(|instruction| match instruction {
+                MoveBytecode::CopyLoc(idx) | MoveBytecode::MoveLoc(idx) | MoveBytecode::ImmBorrowLoc(idx) => {
+                    signers.contains(idx)
+                }
+                _ => false,
+            })
+        });
+        if checks_signer {
+            continue;
+        }
+        let Some(capability) = compiled
+            .signature_at(handle.return_)
+            .0
+            .iter()
+            .find_map(|token| capability_in(compiled, token))
+        else {
+            continue;
+        };
+        let name = compiled.identifier_at(handle.name);
+        findings.push(StaticAnalysisFinding::new(
+            FindingKind::CapabilityLeak,
+            module_name.to_string(),
+            Some(name.to_string()),
+            format!("Public function `{}` returns `{}`", name, capability),
+        ));
+    }
+}
+
+/// The first signer or capability-like struct in `token`. Outside the
+/// framework, a struct looks like a capability if it has `key` or `store`
+/// and is named `*Cap`, `*Capability` or `*Auth`, or is a witness: a struct
+/// of this module with no fields but a flag or an address.
+fn capability_in(
+    compiled: &CompiledModule,
+    token: &aptos_move_binary_format::file_format::SignatureToken,
+) -> Option<String> {
+    use aptos_move_binary_format::file_format::SignatureToken;
+    let handle = match token {
+        SignatureToken::Signer => return Some("signer".to_string()),
+        SignatureToken::Vector(inner) | SignatureToken::Reference(inner) | SignatureToken::MutableReference(inner) => {
+            return capability_in(compiled, inner)
+        }
+        SignatureToken::Struct(idx) => compiled.struct_handle_at(*idx),
+        SignatureToken::StructInstantiation(idx, args) => {
+            if let Some(capability) = args.iter().find_map(|arg| capability_in(compiled, arg)) {
+                return Some(capability);
+            }
+            compiled.struct_handle_at(*idx)
+        }
+        _ => return None,
+    };
+    let module = compiled.module_id_for_handle(compiled.module_handle_at(handle.module));
+    let name = compiled.identifier_at(handle.name).as_str();
+    let is_capability = if module.address() == &AccountAddress::ONE {
+        FRAMEWORK_CAPABILITIES
+            .iter()
+            .any(|(framework_module, capability)| module.name().as_str() == *framework_module && name == *capability)
+    } else {
+        (handle.abilities.has_key() || handle.abilities.has_store()) &&
+            (name.ends_with("Cap") ||
+                name.ends_with("Capability") ||
+                name.ends_with("Auth") ||
+                is_witness(compiled, handle))
+    };
+    is_capability.then(|| format!("{}::{}::{}", module.address().to_hex_literal(), module.name(), name))
+}
+
+fn is_witness(compiled: &CompiledModule, handle: &aptos_move_binary_format::file_format::StructHandle) -> bool {
+    use aptos_move_binary_format::file_format::{SignatureToken, StructFieldInformation};
+    let Some(def) = compiled
+        .struct_defs()
+        .iter()
+        .find(|def| compiled.struct_handle_at(def.struct_handle) == handle)
+    else {
+        return false;
+    };
+    match &def.field_information {
+        StructFieldInformation::Declared(fields) => match fields.as_slice() {
+            [] => true,
+            [field] => matches!(field.signature.0, SignatureToken::Bool | SignatureToken::Address),
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+/// Framework functions that run code chosen by someone else: the
+/// dispatchable fungible asset hooks of the asset being moved.
+const CALL_OUTS: &[(&str, &str)] = &[
+    ("dispatchable_fungible_asset", "withdraw"),
+    ("dispatchable_fungible_asset", "deposit"),
+    ("dispatchable_fungible_asset", "transfer"),
+    ("dispatchable_fungible_asset", "transfer_assert_minimum_deposit"),
+    ("dispatchable_fungible_asset", "derived_balance"),
+    ("dispatchable_fungible_asset", "derived_supply"),
+    ("primary_fungible_store", "withdraw"),
+    ("primary_fungible_store", "deposit"),
+    ("primary_fungible_store", "transfer"),
+    ("primary_fungible_store", "transfer_assert_minimum_deposit"),
+];
+
+fn is_call_out(function: &FunctionRef) -> bool {
+    function.module.address() == &AccountAddress::ONE &&
+        CALL_OUTS
+            .iter()
+            .any(|(module, name)| function.module.name().as_str() == *module && function.name == *name)
+}
+
+fn analyze_recursion(
+    module_name: &str,
+    compiled: &CompiledModule,
+    call_graph: &CallGraph,
+    findings: &mut Vec<StaticAnalysisFinding>,
+) {
+    let module_id = compiled.self_id();
+    for group in call_graph.recursive_groups() {
+        let Some(first) = group.iter().find(|function| function.module == module_id) else {
+            continue;
+        };
+        let detail = if group.len() == 1 {
+            format!("`{}` calls itself", first.name)
+        } else {
+            let others: Vec<String> = group
+                .iter()
+                .filter(|function| *function != first)
+                .map(|function| format!("`{}`", function))
+                .collect();
+            format!("`{}` is mutually recursive with {}", first.name, others.join(", "))
+        };
+        findings.push(StaticAnalysisFinding::new(
+            FindingKind::Recursion,
+            module_name.to_string(),
+            Some(first.name.clone()),
+            detail,
+        ));
+    }
+}
+
+/// Flag functions that may update their own global state after calling
+/// out to code they don't control, which could call back into the module
+/// and observe or act on the stale state.
+fn analyze_call_before_state_update(
+    module_name: &str,
+    compiled: &CompiledModule,
+    call_graph: &CallGraph,
+    findings: &mut Vec<StaticAnalysisFinding>,
+) {
+    use aptos_move_binary_format::file_format::Bytecode as MoveBytecode;
+    let module_id = compiled.self_id();
+    for def in compiled.function_defs() {
+        let Some(code) = &def.code else {
+            continue;
+        };
+        let function = FunctionRef::from_handle(compiled, def.function);
+        let callee_at = |pc: usize| {
+            call_graph
+                .call_sites(&function)
+                .iter()
+                .find(|(site, _)| *site as usize == pc)
+                .map(|(_, callee)| callee)
+        };
+        let updates_state = |pc: usize| {
+            writes_global_state(&code.code[pc]) ||
+                callee_at(pc).is_some_and(|callee| callee.module == module_id && call_graph.writes_state(callee))
+        };
+        let successors = |pc: usize| -> Vec<usize> {
+            match &code.code[pc] {
+                MoveBytecode::Branch(offset) => vec![*offset as usize],
+                MoveBytecode::BrTrue(offset) | MoveBytecode::BrFalse(offset) => vec![*offset as usize, pc + 1],
+                MoveBytecode::Ret | MoveBytecode::Abort => Vec::new(),
+                _ => vec![pc + 1],
+            }
+        };
+        let call_out = (0..code.code.len()).find_map(|pc| {
+            let target = callee_at(pc).filter(|callee| is_call_out(callee));
+            if target.is_none() && !matches!(code.code[pc], MoveBytecode::CallClosure(_)) {
+                return None;
+            }
+            // First state update reachable after the call
+            let mut seen = HashSet::new();
+            let mut queue: Vec<usize> = successors(pc);
+            while let Some(next) = queue.pop() {
+                if next >= code.code.len() || !seen.insert(next) {
+                    continue;
+                }
+                if updates_state(next) {
+                    return Some((pc, target, next));
+                }
+                queue.extend(successors(next));
+            }
+            None
+        });
+        if let Some((pc, target, update)) = call_out {
+            let callee = target.map_or("a function value".to_string(), |callee| format!("`{}`", callee));
+            findings.push(StaticAnalysisFinding::new(
+                FindingKind::CallBeforeStateUpdate,
+                module_name.to_string(),
+                Some(function.name.clone()),
+                format!(
+                    "Calls {} at offset {} before updating global state at offset {}",
+                    callee, pc, update
+                ),
+            ));
+        }
+    }
+}
+
+fn analyze_unused_type_params(
+    module_name: &str,
+    module_env: &ModuleEnv,
+    compiled: &CompiledModule,
+    symbol_pool: &SymbolPool,
+    findings: &mut Vec<StaticAnalysisFinding>,
+) {
+    use aptos_move_binary_format::file_format::{Bytecode as MoveBytecode, StructFieldInformation};
+    // Source names of the type parameters, when the module was built with them
+    let function_params: BTreeMap<String, Vec<String>> = module_env
+        .get_functions()
+        .map(|fun_env| {
+            let names = fun_env
+                .get_type_parameters()
+                .iter()
+                .map(|param| symbol_pool.string(param.0).to_string())
+                .collect();
+            (fun_env.get_name_str().to_string(), names)
+        })
+        .collect();
+    let struct_params: BTreeMap<String, Vec<String>> = module_env
+        .get_structs()
+        .map(|struct_env| {
+            let names = struct_env
+                .get_type_parameters()
+                .iter()
+                .map(|param| symbol_pool.string(param.0).to_string())
+                .collect();
+            (symbol_pool.string(struct_env.get_name()).to_string(), names)
+        })
+        .collect();
+    let describe = |idx: usize, names: Option<&Vec<String>>| match names.and_then(|names| names.get(idx)) {
+        Some(name) => format!("Type parameter {} (`{}`)", idx, name),
+        None => format!("Type parameter {}", idx),
+    };
+
+    for def in compiled.function_defs() {
+        let handle = compiled.function_handle_at(def.function);
+        if handle.type_parameters.is_empty() {
+            continue;
+        }
+        let mut used = vec![false; handle.type_parameters.len()];
+        let mut mark = |idx: aptos_move_binary_format::file_format::SignatureIndex| {
+            for token in &compiled.signature_at(idx).0 {
+                mark_type_params_in_signature(token, &mut used);
+            }
+        };
+        mark(handle.parameters);
+        mark(handle.return_);
+        if let Some(code) = &def.code {
+            mark(code.locals);
+            for instruction in &code.code {
+                match instruction {
+                    MoveBytecode::CallGeneric(idx) | MoveBytecode::PackClosureGeneric(idx, _) => {
+                        mark(compiled.function_instantiation_at(*idx).type_parameters)
+                    }
+                    MoveBytecode::PackGeneric(idx) |
+                    MoveBytecode::UnpackGeneric(idx) |
+                    MoveBytecode::ExistsGeneric(idx) |
+                    MoveBytecode::MoveFromGeneric(idx) |
+                    MoveBytecode::MoveToGeneric(idx) |
+                    MoveBytecode::MutBorrowGlobalGeneric(idx) |
+                    MoveBytecode::ImmBorrowGlobalGeneric(idx) => {
+                        mark(compiled.struct_instantiation_at(*idx).type_parameters)
+                    }
+                    MoveBytecode::ImmBorrowFieldGeneric(idx) | MoveBytecode::MutBorrowFieldGeneric(idx) => {
+                        mark(compiled.field_instantiation_at(*idx).type_parameters)
+                    }
+                    MoveBytecode::PackVariantGeneric(idx) |
+                    MoveBytecode::UnpackVariantGeneric(idx) |
+                    MoveBytecode::TestVariantGeneric(idx) => {
+                        mark(compiled.struct_variant_instantiation_at(*idx).type_parameters)
+                    }
+                    MoveBytecode::ImmBorrowVariantFieldGeneric(idx) |
+                    MoveBytecode::MutBorrowVariantFieldGeneric(idx) => {
+                        mark(compiled.variant_field_instantiation_at(*idx).type_parameters)
+                    }
+                    MoveBytecode::VecPack(idx, _) |
+                    MoveBytecode::VecLen(idx) |
+                    MoveBytecode::VecImmBorrow(idx) |
+                    MoveBytecode::VecMutBorrow(idx) |
+                    MoveBytecode::VecPushBack(idx) |
+                    MoveBytecode::VecPopBack(idx) |
+                    MoveBytecode::VecUnpack(idx, _) |
+                    MoveBytecode::VecSwap(idx) |
+                    MoveBytecode::CallClosure(idx) => mark(*idx),
+                    _ => {}
+                }
+            }
+        }
+        let name = compiled.identifier_at(handle.name).to_string();
+        for (idx, used) in used.into_iter().enumerate() {
+            if !used {
+                findings.push(StaticAnalysisFinding::new(
+                    FindingKind::UnusedTypeParameter,
+                    module_name.to_string(),
+                    Some(name.clone()),
+                    format!(
+                        "{} of function `{}` is never used",
+                        describe(idx, function_params.get(&name)),
+                        name
+                    ),
+                ));
+            }
+        }
+    }
+
+    for def in compiled.struct_defs() {
+        let handle = compiled.struct_handle_at(def.struct_handle);
+        let mut used: Vec<bool> = handle.type_parameters.iter().map(|param| param.is_phantom).collect();
+        if used.iter().all(|used| *used) {
+            continue;
+        }
+        match &def.field_information {
+            StructFieldInformation::Native => continue,
+            StructFieldInformation::Declared(fields) => {
+                for field in fields {
+                    mark_type_params_in_signature(&field.signature.0, &mut used);
+                }
+            }
+            StructFieldInformation::DeclaredVariants(variants) => {
+                for field in variants.iter().flat_map(|variant| &variant.fields) {
+                    mark_type_params_in_signature(&field.signature.0, &mut used);
+                }
+            }
+        }
+        let name = compiled.identifier_at(handle.name).to_string();
+        for (idx, used) in used.into_iter().enumerate() {
+            if !used {
+                findings.push(StaticAnalysisFinding::new(
+                    FindingKind::UnusedTypeParameter,
+                    module_name.to_string(),
+                    None,
+                    format!(
+                        "{} of struct `{}` is never used; declare it `phantom` if it only tags the type",
+                        describe(idx, struct_params.get(&name)),
+                        name
+                    ),
+                ));
+            }
+        }
+    }
+}
+
+fn mark_type_params_in_signature(token: &aptos_move_binary_format::file_format::SignatureToken, used: &mut [bool]) {
+    use aptos_move_binary_format::file_format::SignatureToken;
+    match token {
+        SignatureToken::TypeParameter(idx) => {
+            if let Some(used) = used.get_mut(*idx as usize) {
+                *used = true;
+            }
+        }
+        SignatureToken::Vector(inner) | SignatureToken::Reference(inner) | SignatureToken::MutableReference(inner) => {
+            mark_type_params_in_signature(inner, used)
+        }
+        SignatureToken::StructInstantiation(_, args) => {
+            for arg in args {
+                mark_type_params_in_signature(arg, used);
+            }
+        }
+        SignatureToken::Function(args, results, _) => {
+            for token in args.iter().chain(results) {
+                mark_type_params_in_signature(token, used);
+            }
+        }
+        _ => {}
+    }
+}
+
 fn mark_struct_in_signature(token: &aptos_move_binary_format::file_format::SignatureToken, used: &mut [bool]) {
     use aptos_move_binary_format::file_format::SignatureToken;
     match token {
@@ -1,24 +1,37 @@
 mod bytecode;
+mod call_graph;
+mod constant_propagation;
 mod detectors;
+mod reachability;
 
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
 
 use aptos_move_binary_format::CompiledModule;
+use aptos_move_core_types::account_address::AccountAddress;
 use aptos_move_core_types::language_storage::ModuleId;
 pub use bytecode::{
     build_targets_for_module, collect_function_infos, dummy_source_map, get_def_bytecode, FunctionInfo,
 };
+pub use call_graph::{CallGraph, FunctionRef};
+pub use constant_propagation::{dead_code, DeadCode};
 pub use detectors::{analyze_module, FindingKind, StaticAnalysisFinding};
 use move_model::model::GlobalEnv;
+pub use reachability::UnreachableFunction;
 
 use crate::executor::aptos_custom_state::AptosCustomState;
+use crate::source_location::SourceLocator;
 
-fn build_compiled_map(state: &AptosCustomState, targets: &[ModuleId]) -> HashMap<ModuleId, CompiledModule> {
-    let target_set: std::collections::BTreeSet<_> = targets.iter().cloned().collect();
+/// The deployed modules among `targets`, or all of them when `targets` is
+/// `None`.
+fn build_compiled_map(state: &AptosCustomState, targets: Option<&[ModuleId]>) -> HashMap<ModuleId, CompiledModule> {
+    let target_set: Option<BTreeSet<_>> = targets.map(|targets| targets.iter().cloned().collect());
     let mut compiled = HashMap::new();
     for (_module_id, bytes) in state.module_bytes() {
         if let Ok(module) = CompiledModule::deserialize(bytes.as_ref()) {
-            if target_set.contains(&module.self_id()) {
+            if target_set
+                .as_ref()
+                .is_none_or(|targets| targets.contains(&module.self_id()))
+            {
                 compiled.insert(module.self_id(), module);
             }
         }
@@ -26,6 +39,36 @@ fn build_compiled_map(state: &AptosCustomState, targets: &[ModuleId]) -> HashMap
     compiled
 }
 
+/// The modules of `compiled`, each after the ones it depends on.
+fn dependency_order(compiled: &HashMap<ModuleId, CompiledModule>) -> Vec<&CompiledModule> {
+    fn visit<'a>(
+        module_id: &ModuleId,
+        compiled: &'a HashMap<ModuleId, CompiledModule>,
+        seen: &mut BTreeSet<ModuleId>,
+        order: &mut Vec<&'a CompiledModule>,
+    ) {
+        let Some(module) = compiled.get(module_id) else {
+            return;
+        };
+        if !seen.insert(module_id.clone()) {
+            return;
+        }
+        for dependency in module.immediate_dependencies() {
+            visit(&dependency, compiled, seen, order);
+        }
+        order.push(module);
+    }
+
+    let mut module_ids: Vec<&ModuleId> = compiled.keys().collect();
+    module_ids.sort();
+    let mut seen = BTreeSet::new();
+    let mut order = Vec::with_capacity(compiled.len());
+    for module_id in module_ids {
+        visit(module_id, compiled, &mut seen, &mut order);
+    }
+    order
+}
+
 fn find_module_env<'env>(env: &'env GlobalEnv, module_id: &ModuleId) -> Option<move_model::model::ModuleEnv<'env>> {
     for module in env.get_modules() {
         let module_name = module.get_name();
@@ -46,13 +89,61 @@ fn find_module_env<'env>(env: &'env GlobalEnv, module_id: &ModuleId) -> Option<m
 }
 
 pub fn run_static_analysis(state: &AptosCustomState, targets: &[ModuleId]) -> Vec<StaticAnalysisFinding> {
-    let compiled_map = build_compiled_map(state, targets);
-    if compiled_map.is_empty() {
+    run_static_analysis_with_sources(state, targets, &SourceLocator::new())
+}
+
+/// `run_static_analysis`, attaching source locations from `locator` to the
+/// findings.
+pub fn run_static_analysis_with_sources(
+    state: &AptosCustomState,
+    targets: &[ModuleId],
+    locator: &SourceLocator,
+) -> Vec<StaticAnalysisFinding> {
+    analyze(build_compiled_map(state, Some(targets)), targets, locator, false)
+}
+
+/// `run_static_analysis_with_sources` with every deployed module loaded
+/// alongside `targets`, so that calls and friend declarations between them
+/// resolve. `targets` are taken to be one package: the exposure detectors
+/// also flag what it makes reachable from modules outside of it.
+pub fn run_whole_program_analysis(
+    state: &AptosCustomState,
+    targets: &[ModuleId],
+    locator: &SourceLocator,
+) -> Vec<StaticAnalysisFinding> {
+    analyze(build_compiled_map(state, None), targets, locator, true)
+}
+
+/// Public functions of `targets` that always abort in the current state of
+/// `state`, because a resource they need before anything else is nowhere
+/// in it (looked up under `addresses`) and none of their code publishes it.
+pub fn find_unreachable_functions(
+    state: &AptosCustomState,
+    targets: &[ModuleId],
+    addresses: &BTreeSet<AccountAddress>,
+) -> Vec<UnreachableFunction> {
+    reachability::unreachable_functions(&build_compiled_map(state, Some(targets)), state, addresses)
+}
+
+/// Calls between the functions of `targets`.
+pub fn build_call_graph(state: &AptosCustomState, targets: &[ModuleId]) -> CallGraph {
+    CallGraph::build(build_compiled_map(state, Some(targets)).values())
+}
+
+fn analyze(
+    compiled_map: HashMap<ModuleId, CompiledModule>,
+    targets: &[ModuleId],
+    locator: &SourceLocator,
+    whole_program: bool,
+) -> Vec<StaticAnalysisFinding> {
+    if !targets.iter().any(|target| compiled_map.contains_key(target)) {
         return Vec::new();
     }
+    let package: BTreeSet<ModuleId> = targets.iter().cloned().collect();
 
+    let call_graph = CallGraph::build(compiled_map.values());
     let mut env = GlobalEnv::new();
-    for module in compiled_map.values() {
+    for module in dependency_order(&compiled_map) {
         if let Ok(source_map) = dummy_source_map(module) {
             // ignore errors; we only care about successful loads
             let _ = env.load_compiled_module(true, module.clone(), source_map);
@@ -63,7 +154,23 @@ pub fn run_static_analysis(state: &AptosCustomState, targets: &[ModuleId]) -> Ve
     for target in targets {
         if let Some(compiled) = compiled_map.get(target) {
             if let Some(module_env) = find_module_env(&env, &compiled.self_id()) {
-                findings.extend(detectors::analyze_module(&module_env, compiled, env.symbol_pool()));
+                let mut module_findings =
+                    detectors::analyze_module(&module_env, compiled, env.symbol_pool(), &call_graph);
+                if whole_program {
+                    module_findings.extend(detectors::analyze_exposure(
+                        &module_env,
+                        compiled,
+                        &package,
+                        &call_graph,
+                    ));
+                }
+                for finding in &mut module_findings {
+                    finding.location = match &finding.function {
+                        Some(function) => locator.function_location(target, function),
+                        None => locator.module_location(target),
+                    };
+                }
+                findings.extend(module_findings);
             }
         }
     }
@@ -0,0 +1,166 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use aptos_move_binary_format::access::ModuleAccess;
+use aptos_move_binary_format::control_flow_graph::{BlockId, ControlFlowGraph, VMControlFlowGraph};
+use aptos_move_binary_format::file_format::{Bytecode, FunctionDefinition, StructDefinitionIndex, Visibility};
+use aptos_move_binary_format::CompiledModule;
+use aptos_move_core_types::account_address::AccountAddress;
+use aptos_move_core_types::language_storage::{ModuleId, StructTag};
+use aptos_types::state_store::state_key::StateKey;
+
+use super::call_graph::FunctionRef;
+use crate::executor::aptos_custom_state::AptosCustomState;
+
+/// A public function that aborts on every call: before anything else it
+/// borrows or moves a resource that no loaded module can publish and no
+/// known address holds.
+#[derive(Clone, Debug)]
+pub struct UnreachableFunction {
+    pub function: FunctionRef,
+    pub resource: StructTag,
+}
+
+/// The public functions of `compiled` that can't get past their first access
+/// to global storage. Resources are looked up under `addresses` and in the
+/// object pool of `state`.
+pub(super) fn unreachable_functions(
+    compiled: &HashMap<ModuleId, CompiledModule>,
+    state: &AptosCustomState,
+    addresses: &BTreeSet<AccountAddress>,
+) -> Vec<UnreachableFunction> {
+    let missing = missing_resources(compiled, state, addresses);
+    if missing.is_empty() {
+        return Vec::new();
+    }
+    let mut functions = BTreeMap::new();
+    for module in compiled.values() {
+        for def in module.function_defs() {
+            functions.insert(FunctionRef::from_handle(module, def.function), (module, def));
+        }
+    }
+
+    let mut requires = BTreeMap::new();
+    let mut unreachable = Vec::new();
+    for (function, (_, def)) in &functions {
+        if def.visibility != Visibility::Public {
+            continue;
+        }
+        if let Some(resource) = first_missing_access(function, &functions, &missing, &mut requires) {
+            unreachable.push(UnreachableFunction {
+                function: function.clone(),
+                resource,
+            });
+        }
+    }
+    unreachable
+}
+
+/// Non-generic resources of `compiled` that none of its code moves to
+/// storage and that the state doesn't already hold, by defining module and
+/// name.
+fn missing_resources(
+    compiled: &HashMap<ModuleId, CompiledModule>,
+    state: &AptosCustomState,
+    addresses: &BTreeSet<AccountAddress>,
+) -> BTreeMap<(ModuleId, String), StructTag> {
+    let mut missing = BTreeMap::new();
+    for module in compiled.values() {
+        let mut published = BTreeSet::new();
+        for instruction in module
+            .function_defs()
+            .iter()
+            .filter_map(|def| def.code.as_ref())
+            .flat_map(|code| code.code.iter())
+        {
+            match instruction {
+                Bytecode::MoveTo(idx) => {
+                    published.insert(*idx);
+                }
+                Bytecode::MoveToGeneric(idx) => {
+                    published.insert(module.struct_instantiation_at(*idx).def);
+                }
+                _ => {}
+            }
+        }
+
+        for (idx, def) in module.struct_defs().iter().enumerate() {
+            let handle = module.struct_handle_at(def.struct_handle);
+            if !handle.abilities.has_key() ||
+                !handle.type_parameters.is_empty() ||
+                published.contains(&StructDefinitionIndex(idx as u16))
+            {
+                continue;
+            }
+            let name = module.identifier_at(handle.name).to_owned();
+            let tag = StructTag {
+                address: *module.address(),
+                module: module.name().to_owned(),
+                name: name.clone(),
+                type_args: Vec::new(),
+            };
+            let held = addresses.iter().any(|address| {
+                StateKey::resource(address, &tag)
+                    .ok()
+                    .is_some_and(|state_key| state.get_state_value(&state_key).is_some())
+            });
+            if !held && state.object_addresses(&tag).is_empty() {
+                missing.insert((module.self_id(), name.into_string()), tag);
+            }
+        }
+    }
+    missing
+}
+
+/// The missing resource `function` always accesses before it can branch on
+/// anything but an abort, following calls into the loaded modules.
+fn first_missing_access(
+    function: &FunctionRef,
+    functions: &BTreeMap<FunctionRef, (&CompiledModule, &FunctionDefinition)>,
+    missing: &BTreeMap<(ModuleId, String), StructTag>,
+    requires: &mut BTreeMap<FunctionRef, Option<StructTag>>,
+) -> Option<StructTag> {
+    if let Some(resource) = requires.get(function) {
+        return resource.clone();
+    }
+    // Recursive calls are taken to pass
+    requires.insert(function.clone(), None);
+    let (module, def) = functions.get(function)?;
+    let code = &def.code.as_ref()?.code;
+    let cfg = VMControlFlowGraph::new(code);
+    let aborts = |block: BlockId| matches!(code[cfg.block_end(block) as usize], Bytecode::Abort);
+
+    let mut resource = None;
+    let mut seen = BTreeSet::new();
+    let mut block = cfg.entry_block_id();
+    'blocks: while seen.insert(block) {
+        for instruction in &code[cfg.block_start(block) as usize..=cfg.block_end(block) as usize] {
+            resource = match instruction {
+                Bytecode::BorrowGlobal(idx) | Bytecode::MutBorrowGlobal(idx) | Bytecode::MoveFrom(idx) => {
+                    let handle = module.struct_handle_at(module.struct_def_at(*idx).struct_handle);
+                    let name = module.identifier_at(handle.name).to_string();
+                    missing.get(&(module.self_id(), name)).cloned()
+                }
+                Bytecode::Call(handle) => {
+                    let callee = FunctionRef::from_handle(module, *handle);
+                    first_missing_access(&callee, functions, missing, requires)
+                }
+                Bytecode::CallGeneric(idx) => {
+                    let callee = FunctionRef::from_handle(module, module.function_instantiation_at(*idx).handle);
+                    first_missing_access(&callee, functions, missing, requires)
+                }
+                _ => None,
+            };
+            if resource.is_some() {
+                break 'blocks;
+            }
+        }
+        // Only follow the path every run takes, seeing through `assert!`
+        let mut next = cfg.successors(block).iter().filter(|next| !aborts(**next));
+        match (next.next(), next.next()) {
+            (Some(next), None) => block = *next,
+            _ => break,
+        }
+    }
+    requires.insert(function.clone(), resource.clone());
+    resource
+}
@@ -0,0 +1,825 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+use aptos_move_binary_format::access::ModuleAccess;
+use aptos_move_binary_format::file_format::{AbilitySet, SignatureToken, StructFieldInformation, StructHandleIndex};
+use aptos_move_binary_format::CompiledModule;
+use aptos_move_core_types::account_address::AccountAddress;
+use aptos_move_core_types::identifier::Identifier;
+use aptos_move_core_types::language_storage::{ModuleId, StructTag, TypeTag};
+use aptos_move_core_types::u256::U256;
+use aptos_types::state_store::state_key::inner::StateKeyInner;
+use aptos_types::write_set::{TransactionWrite, WriteSet};
+
+use crate::executor::aptos_custom_state::AptosCustomState;
+use crate::mutator::{bit_width, decode_uleb128};
+use crate::state::FrameworkStruct;
+
+/// Field layouts of the structs declared by the loaded modules, kept per
+/// generic struct and instantiated on lookup. Enums and native structs have
+/// no fixed fields and are left out; the framework structs of
+/// `FrameworkStruct` are known even when the framework isn't loaded, e.g.
+/// on a fork.
+#[derive(Clone, Debug, Default)]
+pub struct StructLayoutRegistry {
+    structs: HashMap<(ModuleId, Identifier), GenericStruct>,
+}
+
+#[derive(Clone, Debug)]
+struct GenericStruct {
+    abilities: AbilitySet,
+    type_params: usize,
+    fields: Vec<(Identifier, FieldType)>,
+}
+
+/// A field type as declared, possibly naming the struct's type parameters
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum FieldType {
+    Type(TypeTag),
+    Param(u16),
+    Vector(Box<FieldType>),
+    Struct(StructTag, Vec<FieldType>),
+}
+
+/// Fields of a struct instantiated with concrete type arguments, in
+/// declaration order, which is also their BCS order.
+#[derive(Clone, Debug)]
+pub struct StructLayout {
+    pub abilities: AbilitySet,
+    pub fields: Vec<(Identifier, TypeTag)>,
+}
+
+/// A Move value decoded from BCS with the registry's layouts.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DecodedValue {
+    Bool(bool),
+    Number(U256),
+    Address(AccountAddress),
+    /// `0x1::string::String`
+    String(String),
+    /// `vector<u8>`
+    Bytes(Vec<u8>),
+    Vector(Vec<DecodedValue>),
+    Struct(Identifier, Vec<(Identifier, DecodedValue)>),
+}
+
+/// A resource written by a transaction, with its bytes before and after
+/// (`None` when it didn't exist or was deleted).
+#[derive(Clone, Debug)]
+pub struct ResourceChange {
+    pub address: AccountAddress,
+    pub struct_tag: StructTag,
+    pub before: Option<Vec<u8>>,
+    pub after: Option<Vec<u8>>,
+}
+
+impl StructLayoutRegistry {
+    /// Registry of the structs declared by `modules`, skipping the ones that
+    /// don't deserialize.
+    pub fn from_module_bytes<'a>(modules: impl IntoIterator<Item = &'a [u8]>) -> Self {
+        let mut registry = Self::default();
+        for bytes in modules {
+            if let Ok(module) = CompiledModule::deserialize(bytes) {
+                registry.add_module(&module);
+            }
+        }
+        registry
+    }
+
+    pub fn add_module(&mut self, module: &CompiledModule) {
+        let module_id = module.self_id();
+        for def in &module.struct_defs {
+            let StructFieldInformation::Declared(fields) = &def.field_information else {
+                continue;
+            };
+            let Some(fields) = fields
+                .iter()
+                .map(|field| {
+                    let ty = field_type(module, &field.signature.0)?;
+                    Some((module.identifier_at(field.name).to_owned(), ty))
+                })
+                .collect::<Option<Vec<_>>>()
+            else {
+                continue;
+            };
+            let handle = module.struct_handle_at(def.struct_handle);
+            self.structs.insert(
+                (module_id.clone(), module.identifier_at(handle.name).to_owned()),
+                GenericStruct {
+                    abilities: handle.abilities,
+                    type_params: handle.type_parameters.len(),
+                    fields,
+                },
+            );
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.structs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.structs.is_empty()
+    }
+
+    /// Non-generic structs declared by `module_id`, with their abilities.
+    pub fn structs_of(&self, module_id: &ModuleId) -> Vec<(StructTag, AbilitySet)> {
+        let mut structs: Vec<(StructTag, AbilitySet)> = self
+            .structs
+            .iter()
+            .filter(|((module, _), generic)| module == module_id && generic.type_params == 0)
+            .map(|((module, name), generic)| {
+                let tag = StructTag {
+                    address: *module.address(),
+                    module: module.name().to_owned(),
+                    name: name.clone(),
+                    type_args: Vec::new(),
+                };
+                (tag, generic.abilities)
+            })
+            .collect();
+        structs.sort_by(|a, b| a.0.name.cmp(&b.0.name));
+        structs
+    }
+
+    /// Structs of `module_id` that `upgraded` removes, or whose fields or
+    /// type parameters it changes or abilities it drops, none of which a
+    /// compatible upgrade may do.
+    pub fn incompatible_changes(&self, upgraded: &Self, module_id: &ModuleId) -> Vec<String> {
+        let mut changes: Vec<String> = self
+            .structs
+            .iter()
+            .filter(|((module, _), _)| module == module_id)
+            .filter_map(|(key, old)| {
+                let change = match upgraded.structs.get(key) {
+                    None => "was removed",
+                    Some(new) if new.type_params != old.type_params => "changed its type parameters",
+                    Some(new) if new.fields != old.fields => "changed its fields",
+                    Some(new) if !old.abilities.is_subset(new.abilities) => "dropped abilities",
+                    Some(_) => return None,
+                };
+                Some(format!("struct {}::{} {}", key.0, key.1, change))
+            })
+            .collect();
+        changes.sort();
+        changes
+    }
+
+    /// Layout of `tag` with its type arguments substituted, `None` for
+    /// structs of modules not loaded and for wrong type argument counts.
+    pub fn layout(&self, tag: &StructTag) -> Option<StructLayout> {
+        let generic = self.structs.get(&(tag.module_id(), tag.name.clone()))?;
+        if generic.type_params != tag.type_args.len() {
+            return None;
+        }
+        let fields = generic
+            .fields
+            .iter()
+            .map(|(name, ty)| Some((name.clone(), instantiate(ty, &tag.type_args)?)))
+            .collect::<Option<Vec<_>>>()?;
+        Some(StructLayout {
+            abilities: generic.abilities,
+            fields,
+        })
+    }
+
+    /// Whether values of `ty` have the `copy` ability. Type arguments count
+    /// even when phantom, and structs of modules not loaded are copyable
+    /// only as the framework structs of `FrameworkStruct`.
+    pub fn is_copyable(&self, ty: &TypeTag) -> bool {
+        match ty {
+            TypeTag::Signer => false,
+            TypeTag::Vector(inner) => self.is_copyable(inner),
+            TypeTag::Struct(tag) => match self.structs.get(&(tag.module_id(), tag.name.clone())) {
+                Some(generic) => generic.abilities.has_copy() && tag.type_args.iter().all(|arg| self.is_copyable(arg)),
+                None => match FrameworkStruct::classify(tag) {
+                    Some(FrameworkStruct::Option(inner)) => self.is_copyable(&inner),
+                    Some(_) => true,
+                    None => false,
+                },
+            },
+            _ => true,
+        }
+    }
+
+    /// Fields of `tag`, from the loaded modules or, for the framework
+    /// structs of `FrameworkStruct`, from their known definitions.
+    pub fn fields(&self, tag: &StructTag) -> Option<Vec<(Identifier, TypeTag)>> {
+        if let Some(layout) = self.layout(tag) {
+            return Some(layout.fields);
+        }
+        let field = |name: &str, ty: TypeTag| (Identifier::new(name).expect("valid identifier"), ty);
+        Some(match FrameworkStruct::classify(tag)? {
+            FrameworkStruct::String => vec![field("bytes", TypeTag::Vector(Box::new(TypeTag::U8)))],
+            FrameworkStruct::Option(inner) => vec![field("vec", TypeTag::Vector(Box::new(inner)))],
+            FrameworkStruct::FixedPoint32 => vec![field("value", TypeTag::U64)],
+            FrameworkStruct::FixedPoint64 => vec![field("value", TypeTag::U128)],
+            FrameworkStruct::Object(_) => vec![field("inner", TypeTag::Address)],
+        })
+    }
+
+    /// Decode `bytes` as a `ty` value, all of them.
+    pub fn decode(&self, ty: &TypeTag, bytes: &[u8]) -> Option<DecodedValue> {
+        let (value, size) = self.decode_prefix(ty, bytes)?;
+        (size == bytes.len()).then_some(value)
+    }
+
+    /// BCS encodings of the fields of a `tag` value, in order.
+    pub fn split_fields(&self, tag: &StructTag, bytes: &[u8]) -> Option<Vec<Vec<u8>>> {
+        let mut offset = 0;
+        let mut fields = Vec::new();
+        for (_, ty) in self.fields(tag)? {
+            let (_, size) = self.decode_prefix(&ty, bytes.get(offset..)?)?;
+            fields.push(bytes[offset..offset + size].to_vec());
+            offset += size;
+        }
+        (offset == bytes.len()).then_some(fields)
+    }
+
+    /// Total value of the `0x1::coin::Coin`s a `tag` value holds, nested
+    /// ones included, by coin type.
+    pub fn coins(&self, tag: &StructTag, bytes: &[u8]) -> Option<BTreeMap<TypeTag, u128>> {
+        let mut coins = BTreeMap::new();
+        let size = self.coins_prefix(&TypeTag::Struct(Box::new(tag.clone())), bytes, &mut coins)?;
+        (size == bytes.len()).then_some(coins)
+    }
+
+    /// What happened to a resource on one line, for messages: its changed
+    /// fields as `diff_lines` lists them.
+    pub fn describe_change(&self, change: &ResourceChange) -> String {
+        let lines = self.diff_lines(change);
+        if lines.is_empty() {
+            "rewritten unchanged".to_string()
+        } else {
+            lines.join(", ")
+        }
+    }
+
+    /// Field-level diff of a resource: `reserve: 100 -> 40` for modified
+    /// fields, nested ones by path (`coin.value`), and every field prefixed
+    /// with `+ ` when created or `- ` when deleted. A version that doesn't
+    /// decode is shown by its size.
+    pub fn diff_lines(&self, change: &ResourceChange) -> Vec<String> {
+        let ty = TypeTag::Struct(Box::new(change.struct_tag.clone()));
+        let decode = |bytes: &Option<Vec<u8>>| bytes.as_deref().map(|bytes| self.decode(&ty, bytes).ok_or(bytes.len()));
+        let mut lines = Vec::new();
+        match (decode(&change.before), decode(&change.after)) {
+            (Some(Ok(before)), Some(Ok(after))) => diff_values("", &before, &after, &mut lines),
+            (None, Some(Ok(after))) => field_lines("+ ", "", &after, &mut lines),
+            (Some(Ok(before)), None) => field_lines("- ", "", &before, &mut lines),
+            (before, after) => {
+                let render = |version: Option<Result<DecodedValue, usize>>| match version {
+                    Some(Ok(value)) => value.to_string(),
+                    Some(Err(len)) => format!("{} bytes", len),
+                    None => "none".to_string(),
+                };
+                lines.push(format!("{} -> {}", render(before), render(after)));
+            }
+        }
+        lines
+    }
+
+    /// A state diff for reports: the changes grouped by account, each
+    /// resource indented below its account and its `diff_lines` below it.
+    pub fn format_changes(&self, changes: &[ResourceChange]) -> Vec<String> {
+        let mut by_account: BTreeMap<AccountAddress, Vec<&ResourceChange>> = BTreeMap::new();
+        for change in changes {
+            by_account.entry(change.address).or_default().push(change);
+        }
+        let mut lines = Vec::new();
+        for (address, changes) in by_account {
+            lines.push(address.to_hex_literal());
+            for change in changes {
+                let status = match (&change.before, &change.after) {
+                    (None, Some(_)) => " (created)",
+                    (_, None) => " (deleted)",
+                    _ => "",
+                };
+                lines.push(format!("  {}{}", change.struct_tag.to_canonical_string(), status));
+                lines.extend(self.diff_lines(change).into_iter().map(|line| format!("    {}", line)));
+            }
+        }
+        lines
+    }
+
+    /// The `ty` value at the start of `bytes` and the number of bytes it
+    /// takes.
+    fn decode_prefix(&self, ty: &TypeTag, bytes: &[u8]) -> Option<(DecodedValue, usize)> {
+        let fixed = |width: usize| bytes.get(..width);
+        Some(match ty {
+            TypeTag::Bool => match bytes.first()? {
+                0 => (DecodedValue::Bool(false), 1),
+                1 => (DecodedValue::Bool(true), 1),
+                _ => return None,
+            },
+            TypeTag::U8 | TypeTag::U16 | TypeTag::U32 | TypeTag::U64 | TypeTag::U128 | TypeTag::U256 => {
+                let width = bit_width(ty)? as usize / 8;
+                let mut le = [0u8; 32];
+                le[..width].copy_from_slice(fixed(width)?);
+                (DecodedValue::Number(U256::from_le_bytes(&le)), width)
+            }
+            TypeTag::Address => (
+                DecodedValue::Address(AccountAddress::from_bytes(fixed(AccountAddress::LENGTH)?).ok()?),
+                AccountAddress::LENGTH,
+            ),
+            TypeTag::Vector(elem) => {
+                let (len, mut offset) = decode_uleb128(bytes)?;
+                if **elem == TypeTag::U8 {
+                    let end = offset.checked_add(len)?;
+                    return Some((DecodedValue::Bytes(bytes.get(offset..end)?.to_vec()), end));
+                }
+                let mut elements = Vec::new();
+                for _ in 0..len {
+                    let (element, size) = self.decode_prefix(elem, bytes.get(offset..)?)?;
+                    elements.push(element);
+                    offset += size;
+                }
+                (DecodedValue::Vector(elements), offset)
+            }
+            TypeTag::Struct(tag) => {
+                if let Some(FrameworkStruct::String) = FrameworkStruct::classify(tag) {
+                    let (len, offset) = decode_uleb128(bytes)?;
+                    let end = offset.checked_add(len)?;
+                    let string = std::str::from_utf8(bytes.get(offset..end)?).ok()?;
+                    return Some((DecodedValue::String(string.to_string()), end));
+                }
+                let mut offset = 0;
+                let mut fields = Vec::new();
+                for (name, field_ty) in self.fields(tag)? {
+                    let (value, size) = self.decode_prefix(&field_ty, bytes.get(offset..)?)?;
+                    fields.push((name, value));
+                    offset += size;
+                }
+                (DecodedValue::Struct(tag.name.clone(), fields), offset)
+            }
+            _ => return None,
+        })
+    }
+
+    fn coins_prefix(&self, ty: &TypeTag, bytes: &[u8], coins: &mut BTreeMap<TypeTag, u128>) -> Option<usize> {
+        match ty {
+            // Coin { value: u64 }, known whether or not the framework is loaded
+            TypeTag::Struct(tag) if is_coin(tag) => {
+                let value = u64::from_le_bytes(bytes.get(..8)?.try_into().ok()?);
+                *coins.entry(tag.type_args[0].clone()).or_insert(0) += value as u128;
+                Some(8)
+            }
+            TypeTag::Struct(tag) if !matches!(FrameworkStruct::classify(tag), Some(FrameworkStruct::String)) => {
+                let mut offset = 0;
+                for (_, field_ty) in self.fields(tag)? {
+                    offset += self.coins_prefix(&field_ty, bytes.get(offset..)?, coins)?;
+                }
+                Some(offset)
+            }
+            TypeTag::Vector(elem) if **elem != TypeTag::U8 => {
+                let (len, mut offset) = decode_uleb128(bytes)?;
+                for _ in 0..len {
+                    offset += self.coins_prefix(elem, bytes.get(offset..)?, coins)?;
+                }
+                Some(offset)
+            }
+            _ => self.decode_prefix(ty, bytes).map(|(_, size)| size),
+        }
+    }
+}
+
+fn is_coin(tag: &StructTag) -> bool {
+    tag.address == AccountAddress::ONE &&
+        tag.module.as_str() == "coin" &&
+        tag.name.as_str() == "Coin" &&
+        tag.type_args.len() == 1
+}
+
+impl DecodedValue {
+    /// Every address in the value, nested ones included.
+    pub fn addresses(&self) -> Vec<AccountAddress> {
+        let mut addresses = Vec::new();
+        self.collect_addresses(&mut addresses);
+        addresses
+    }
+
+    fn collect_addresses(&self, addresses: &mut Vec<AccountAddress>) {
+        match self {
+            DecodedValue::Address(address) => addresses.push(*address),
+            DecodedValue::Vector(elements) => {
+                for element in elements {
+                    element.collect_addresses(addresses);
+                }
+            }
+            DecodedValue::Struct(_, fields) => {
+                for (_, value) in fields {
+                    value.collect_addresses(addresses);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl fmt::Display for DecodedValue {
+    /// `Pool { reserve: 100, owner: @0xcafe, name: "a", data: 0x0102 }`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodedValue::Bool(value) => write!(f, "{}", value),
+            DecodedValue::Number(value) => write!(f, "{}", value),
+            DecodedValue::Address(address) => write!(f, "@{}", address.to_hex_literal()),
+            DecodedValue::String(string) => write!(f, "{:?}", string),
+            DecodedValue::Bytes(bytes) => write!(f, "0x{}", hex::encode(bytes)),
+            DecodedValue::Vector(elements) => {
+                write!(f, "[")?;
+                for (idx, element) in elements.iter().enumerate() {
+                    if idx > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", element)?;
+                }
+                write!(f, "]")
+            }
+            DecodedValue::Struct(name, fields) => {
+                write!(f, "{} {{", name)?;
+                for (idx, (field, value)) in fields.iter().enumerate() {
+                    let separator = if idx > 0 { "," } else { "" };
+                    write!(f, "{} {}: {}", separator, field, value)?;
+                }
+                write!(f, " }}")
+            }
+        }
+    }
+}
+
+/// The resources `write_set` changes, with their bytes in `pre`. Modules,
+/// tables and resource groups' members are not resources here.
+pub fn resource_changes(pre: &AptosCustomState, write_set: &WriteSet) -> Vec<ResourceChange> {
+    write_set
+        .write_op_iter()
+        .filter_map(|(state_key, write_op)| {
+            let StateKeyInner::AccessPath(access_path) = state_key.inner() else {
+                return None;
+            };
+            let struct_tag = access_path.get_struct_tag()?;
+            Some(ResourceChange {
+                address: access_path.address,
+                struct_tag,
+                before: pre.get_state_value(state_key).map(|value| value.bytes().to_vec()),
+                after: write_op.bytes().map(|bytes| bytes.to_vec()),
+            })
+        })
+        .collect()
+}
+
+/// Lines for the fields of `after` that differ from `before`, descending
+/// into structs on both sides.
+fn diff_values(path: &str, before: &DecodedValue, after: &DecodedValue, lines: &mut Vec<String>) {
+    match (before, after) {
+        (DecodedValue::Struct(_, old), DecodedValue::Struct(_, new)) if old.len() == new.len() => {
+            for ((name, old), (_, new)) in old.iter().zip(new) {
+                diff_values(&field_path(path, name), old, new, lines);
+            }
+        }
+        _ if before != after => lines.push(format!("{}: {} -> {}", path, before, after)),
+        _ => {}
+    }
+}
+
+/// A line for each field of `value`, nested structs' fields by path.
+fn field_lines(prefix: &str, path: &str, value: &DecodedValue, lines: &mut Vec<String>) {
+    match value {
+        DecodedValue::Struct(_, fields) => {
+            for (name, value) in fields {
+                field_lines(prefix, &field_path(path, name), value, lines);
+            }
+        }
+        _ => lines.push(format!("{}{}: {}", prefix, path, value)),
+    }
+}
+
+fn field_path(path: &str, name: &Identifier) -> String {
+    if path.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}.{}", path, name)
+    }
+}
+
+pub(crate) fn field_type(module: &CompiledModule, token: &SignatureToken) -> Option<FieldType> {
+    let closed = |ty: FieldType| match ty {
+        FieldType::Type(ty) => Some(ty),
+        _ => None,
+    };
+    Some(match token {
+        SignatureToken::Bool => FieldType::Type(TypeTag::Bool),
+        SignatureToken::U8 => FieldType::Type(TypeTag::U8),
+        SignatureToken::U16 => FieldType::Type(TypeTag::U16),
+        SignatureToken::U32 => FieldType::Type(TypeTag::U32),
+        SignatureToken::U64 => FieldType::Type(TypeTag::U64),
+        SignatureToken::U128 => FieldType::Type(TypeTag::U128),
+        SignatureToken::U256 => FieldType::Type(TypeTag::U256),
+        SignatureToken::Address => FieldType::Type(TypeTag::Address),
+        SignatureToken::TypeParameter(idx) => FieldType::Param(*idx),
+        SignatureToken::Vector(inner) => match field_type(module, inner)? {
+            FieldType::Type(inner) => FieldType::Type(TypeTag::Vector(Box::new(inner))),
+            inner => FieldType::Vector(Box::new(inner)),
+        },
+        SignatureToken::Struct(handle_idx) => {
+            FieldType::Type(TypeTag::Struct(Box::new(struct_tag(module, *handle_idx))))
+        }
+        SignatureToken::StructInstantiation(handle_idx, tys) => {
+            let args = tys
+                .iter()
+                .map(|ty| field_type(module, ty))
+                .collect::<Option<Vec<_>>>()?;
+            let mut tag = struct_tag(module, *handle_idx);
+            match args.iter().cloned().map(closed).collect::<Option<Vec<_>>>() {
+                Some(type_args) => {
+                    tag.type_args = type_args;
+                    FieldType::Type(TypeTag::Struct(Box::new(tag)))
+                }
+                None => FieldType::Struct(tag, args),
+            }
+        }
+        _ => return None,
+    })
+}
+
+/// Tag of a struct handle, without type arguments.
+fn struct_tag(module: &CompiledModule, handle_idx: StructHandleIndex) -> StructTag {
+    let handle = module.struct_handle_at(handle_idx);
+    let module_handle = module.module_handle_at(handle.module);
+    StructTag {
+        address: *module.address_identifier_at(module_handle.address),
+        module: module.identifier_at(module_handle.name).to_owned(),
+        name: module.identifier_at(handle.name).to_owned(),
+        type_args: Vec::new(),
+    }
+}
+
+/// `0x1::<module>::<name>` instantiated with `type_args`.
+pub fn framework_struct(module: &str, name: &str, type_args: Vec<TypeTag>) -> StructTag {
+    StructTag {
+        address: AccountAddress::ONE,
+        module: Identifier::new(module).expect("valid identifier"),
+        name: Identifier::new(name).expect("valid identifier"),
+        type_args,
+    }
+}
+
+pub(crate) fn instantiate(ty: &FieldType, type_args: &[TypeTag]) -> Option<TypeTag> {
+    Some(match ty {
+        FieldType::Type(ty) => ty.clone(),
+        FieldType::Param(idx) => type_args.get(*idx as usize)?.clone(),
+        FieldType::Vector(inner) => TypeTag::Vector(Box::new(instantiate(inner, type_args)?)),
+        FieldType::Struct(tag, args) => {
+            let mut tag = tag.clone();
+            tag.type_args = args
+                .iter()
+                .map(|arg| instantiate(arg, type_args))
+                .collect::<Option<Vec<_>>>()?;
+            TypeTag::Struct(Box::new(tag))
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use aptos_cached_packages::head_release_bundle;
+    use aptos_move_binary_format::file_format::{Ability, AbilitySet};
+    use aptos_move_core_types::account_address::AccountAddress;
+    use aptos_move_core_types::identifier::Identifier;
+    use aptos_move_core_types::language_storage::{ModuleId, StructTag, TypeTag};
+    use aptos_move_core_types::u256::U256;
+
+    use super::{
+        framework_struct, instantiate, DecodedValue, FieldType, GenericStruct, ResourceChange, StructLayoutRegistry,
+    };
+
+    fn cafe() -> AccountAddress {
+        AccountAddress::from_hex_literal("0xcafe").unwrap()
+    }
+
+    fn module() -> ModuleId {
+        ModuleId::new(cafe(), Identifier::new("m").unwrap())
+    }
+
+    fn tag(name: &str, type_args: Vec<TypeTag>) -> StructTag {
+        StructTag {
+            address: cafe(),
+            module: Identifier::new("m").unwrap(),
+            name: Identifier::new(name).unwrap(),
+            type_args,
+        }
+    }
+
+    fn aptos_coin() -> TypeTag {
+        TypeTag::Struct(Box::new(framework_struct("aptos_coin", "AptosCoin", vec![])))
+    }
+
+    fn string() -> TypeTag {
+        TypeTag::Struct(Box::new(framework_struct("string", "String", vec![])))
+    }
+
+    /// `Pool { reserve: u64, owner: address, name: String, data: vector<u8>,
+    /// coin: Coin<AptosCoin> }` and `Box<T> has copy { items: vector<T> }`
+    /// of `0xcafe::m`, and `0x1::coin::Coin<T> { value: u64 }`.
+    fn registry() -> StructLayoutRegistry {
+        let field = |name: &str, ty: FieldType| (Identifier::new(name).unwrap(), ty);
+        let mut registry = StructLayoutRegistry::default();
+        registry.structs.insert(
+            (module(), Identifier::new("Pool").unwrap()),
+            GenericStruct {
+                abilities: AbilitySet::singleton(Ability::Key),
+                type_params: 0,
+                fields: vec![
+                    field("reserve", FieldType::Type(TypeTag::U64)),
+                    field("owner", FieldType::Type(TypeTag::Address)),
+                    field("name", FieldType::Type(string())),
+                    field("data", FieldType::Type(TypeTag::Vector(Box::new(TypeTag::U8)))),
+                    field(
+                        "coin",
+                        FieldType::Type(TypeTag::Struct(Box::new(framework_struct(
+                            "coin",
+                            "Coin",
+                            vec![aptos_coin()],
+                        )))),
+                    ),
+                ],
+            },
+        );
+        registry.structs.insert(
+            (module(), Identifier::new("Box").unwrap()),
+            GenericStruct {
+                abilities: AbilitySet::singleton(Ability::Copy),
+                type_params: 1,
+                fields: vec![field("items", FieldType::Vector(Box::new(FieldType::Param(0))))],
+            },
+        );
+        registry.structs.insert(
+            (
+                ModuleId::new(AccountAddress::ONE, Identifier::new("coin").unwrap()),
+                Identifier::new("Coin").unwrap(),
+            ),
+            GenericStruct {
+                abilities: AbilitySet::singleton(Ability::Store),
+                type_params: 1,
+                fields: vec![field("value", FieldType::Type(TypeTag::U64))],
+            },
+        );
+        registry
+    }
+
+    fn pool_bytes(reserve: u64, coin: u64) -> Vec<u8> {
+        bcs::to_bytes(&(reserve, cafe(), "a".to_string(), vec![1u8, 2], coin)).unwrap()
+    }
+
+    fn pool_change(before: Option<Vec<u8>>, after: Option<Vec<u8>>) -> ResourceChange {
+        ResourceChange {
+            address: cafe(),
+            struct_tag: tag("Pool", vec![]),
+            before,
+            after,
+        }
+    }
+
+    #[test]
+    fn layouts_substitute_type_arguments() {
+        let registry = registry();
+        let layout = registry.layout(&tag("Box", vec![TypeTag::U8])).unwrap();
+        assert_eq!(
+            layout.fields,
+            vec![(
+                Identifier::new("items").unwrap(),
+                TypeTag::Vector(Box::new(TypeTag::U8))
+            )]
+        );
+        assert!(registry.layout(&tag("Box", vec![])).is_none());
+        assert!(registry.layout(&tag("Missing", vec![])).is_none());
+    }
+
+    #[test]
+    fn instantiate_fills_parameters_of_nested_structs() {
+        let ty = FieldType::Struct(
+            tag("Box", vec![]),
+            vec![FieldType::Vector(Box::new(FieldType::Param(1)))],
+        );
+        assert_eq!(
+            instantiate(&ty, &[TypeTag::Bool, TypeTag::U64]),
+            Some(TypeTag::Struct(Box::new(tag(
+                "Box",
+                vec![TypeTag::Vector(Box::new(TypeTag::U64))]
+            ))))
+        );
+        assert_eq!(instantiate(&ty, &[TypeTag::Bool]), None);
+    }
+
+    #[test]
+    fn framework_modules_register_their_structs() {
+        let mut registry = StructLayoutRegistry::default();
+        for (_, module) in head_release_bundle().code_and_compiled_modules() {
+            registry.add_module(module);
+        }
+        let coin_store = framework_struct("coin", "CoinStore", vec![aptos_coin()]);
+        let fields = registry.layout(&coin_store).unwrap().fields;
+        assert_eq!(fields[0].0.as_str(), "coin");
+        assert_eq!(
+            fields[0].1,
+            TypeTag::Struct(Box::new(framework_struct("coin", "Coin", vec![aptos_coin()])))
+        );
+    }
+
+    #[test]
+    fn copyability_follows_abilities_and_type_arguments() {
+        let registry = registry();
+        let boxed = |arg: TypeTag| TypeTag::Struct(Box::new(tag("Box", vec![arg])));
+        assert!(registry.is_copyable(&boxed(TypeTag::U64)));
+        assert!(registry.is_copyable(&boxed(string())));
+        assert!(!registry.is_copyable(&boxed(TypeTag::Struct(Box::new(tag("Pool", vec![]))))));
+        assert!(!registry.is_copyable(&TypeTag::Vector(Box::new(TypeTag::Signer))));
+        // Structs of modules not loaded are unknown
+        assert!(!registry.is_copyable(&TypeTag::Struct(Box::new(tag("Missing", vec![])))));
+    }
+
+    #[test]
+    fn framework_structs_have_fields_without_their_modules() {
+        let registry = StructLayoutRegistry::default();
+        let option = framework_struct("option", "Option", vec![TypeTag::U64]);
+        let bytes = bcs::to_bytes(&Some(5u64)).unwrap();
+        assert_eq!(
+            registry.decode(&TypeTag::Struct(Box::new(option)), &bytes),
+            Some(DecodedValue::Struct(
+                Identifier::new("Option").unwrap(),
+                vec![(
+                    Identifier::new("vec").unwrap(),
+                    DecodedValue::Vector(vec![DecodedValue::Number(U256::from(5u8))])
+                )]
+            ))
+        );
+    }
+
+    #[test]
+    fn decodes_and_displays_whole_values_only() {
+        let registry = registry();
+        let pool = TypeTag::Struct(Box::new(tag("Pool", vec![])));
+        let bytes = pool_bytes(100, 7);
+        let value = registry.decode(&pool, &bytes).unwrap();
+        assert_eq!(
+            value.to_string(),
+            "Pool { reserve: 100, owner: @0xcafe, name: \"a\", data: 0x0102, coin: Coin { value: 7 } }"
+        );
+        assert_eq!(value.addresses(), vec![cafe()]);
+        assert!(registry.decode(&pool, &bytes[..bytes.len() - 1]).is_none());
+        let mut longer = bytes.clone();
+        longer.push(0);
+        assert!(registry.decode(&pool, &longer).is_none());
+    }
+
+    #[test]
+    fn split_fields_and_coins_walk_the_layout() {
+        let registry = registry();
+        let fields = registry
+            .split_fields(&tag("Pool", vec![]), &pool_bytes(100, 7))
+            .unwrap();
+        assert_eq!(fields.len(), 5);
+        assert_eq!(fields[0], 100u64.to_le_bytes());
+        assert_eq!(fields[3], vec![2, 1, 2]);
+        let coins = registry.coins(&tag("Pool", vec![]), &pool_bytes(100, 7)).unwrap();
+        assert_eq!(coins.into_iter().collect::<Vec<_>>(), vec![(aptos_coin(), 7)]);
+    }
+
+    #[test]
+    fn diffs_show_changed_created_and_deleted_fields() {
+        let registry = registry();
+        let modified = pool_change(Some(pool_bytes(100, 7)), Some(pool_bytes(40, 8)));
+        assert_eq!(
+            registry.diff_lines(&modified),
+            vec!["reserve: 100 -> 40".to_string(), "coin.value: 7 -> 8".to_string()]
+        );
+        let created = pool_change(None, Some(pool_bytes(1, 2)));
+        assert_eq!(registry.diff_lines(&created)[0], "+ reserve: 1");
+        let deleted = pool_change(Some(pool_bytes(1, 2)), None);
+        assert_eq!(registry.diff_lines(&deleted)[4], "- coin.value: 2");
+        let garbled = pool_change(Some(vec![0; 3]), Some(pool_bytes(1, 2)));
+        assert!(registry.diff_lines(&garbled)[0].starts_with("3 bytes -> Pool {"));
+        let unchanged = pool_change(Some(pool_bytes(1, 2)), Some(pool_bytes(1, 2)));
+        assert_eq!(registry.describe_change(&unchanged), "rewritten unchanged");
+    }
+
+    #[test]
+    fn upgrades_may_not_change_struct_layouts() {
+        let old = registry();
+        let mut upgraded = registry();
+        upgraded.structs.remove(&(module(), Identifier::new("Box").unwrap()));
+        upgraded
+            .structs
+            .get_mut(&(module(), Identifier::new("Pool").unwrap()))
+            .unwrap()
+            .fields
+            .pop();
+        assert_eq!(
+            old.incompatible_changes(&upgraded, &module()),
+            vec![
+                format!("struct {}::Box was removed", module()),
+                format!("struct {}::Pool changed its fields", module())
+            ]
+        );
+        assert!(old.incompatible_changes(&old, &module()).is_empty());
+    }
+}
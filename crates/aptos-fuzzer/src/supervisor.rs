@@ -0,0 +1,138 @@
+use std::collections::hash_map::DefaultHasher;
+use std::ffi::OsString;
+use std::hash::{Hash, Hasher};
+use std::panic::{self, PanicHookInfo};
+use std::path::PathBuf;
+use std::process::{Command, ExitStatus};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use std::{env, fs};
+
+use anyhow::{bail, Context};
+
+use crate::executor::panic_capture::payload_message;
+use crate::input::AptosFuzzerInput;
+
+/// Set in the environment of the fuzzer processes `supervise` spawns, to
+/// the seconds left of the campaign's timeout, or 0 for none
+pub const SUPERVISED_ENV: &str = "APTOS_FUZZER_SUPERVISED";
+
+/// Exit code of a process that died of a panic
+const PANIC_EXIT_CODE: i32 = 101;
+
+/// A process dying this soon after being spawned is crashing on start up
+/// rather than on an input
+const QUICK_CRASH: Duration = Duration::from_secs(10);
+
+/// Quick crashes in a row after which the supervisor gives up
+const MAX_QUICK_CRASHES: usize = 3;
+
+static QUARANTINE_DIR: OnceLock<PathBuf> = OnceLock::new();
+static IN_FLIGHT: Mutex<Option<AptosFuzzerInput>> = Mutex::new(None);
+
+/// In a fuzzer process spawned by `supervise`, the seconds of the timeout
+/// left (0 for no timeout).
+pub fn supervised_timeout() -> Option<u64> {
+    env::var(SUPERVISED_ENV).ok()?.parse().ok()
+}
+
+/// Run this executable with the same arguments, respawning it whenever it
+/// dies of a panic or a signal, so it resumes from its last checkpoint.
+/// Stops when it exits otherwise, `running` is cleared, `timeout` has
+/// passed since the first spawn, or it crashes within seconds of starting
+/// several times in a row. `on_respawn` is told how each process that gets
+/// respawned died. Returns how the last process exited.
+pub fn supervise(
+    timeout: Option<Duration>,
+    running: &AtomicBool,
+    mut on_respawn: impl FnMut(ExitStatus),
+) -> anyhow::Result<ExitStatus> {
+    let exe = env::current_exe().context("failed to locate the fuzzer executable")?;
+    let args: Vec<OsString> = env::args_os().skip(1).collect();
+    let start = Instant::now();
+    let mut quick_crashes = 0;
+    loop {
+        let left = timeout.map_or(0, |timeout| timeout.saturating_sub(start.elapsed()).as_secs().max(1));
+        let spawned = Instant::now();
+        let status = Command::new(&exe)
+            .args(&args)
+            .env(SUPERVISED_ENV, left.to_string())
+            .status()
+            .with_context(|| format!("failed to spawn {}", exe.display()))?;
+        let crashed = status.code().is_none_or(|code| code == PANIC_EXIT_CODE);
+        if !crashed || !running.load(Ordering::SeqCst) || timeout.is_some_and(|timeout| start.elapsed() >= timeout) {
+            return Ok(status);
+        }
+        if spawned.elapsed() < QUICK_CRASH {
+            quick_crashes += 1;
+            if quick_crashes >= MAX_QUICK_CRASHES {
+                bail!(
+                    "the fuzzer process crashed {} times in a row within {}s of starting",
+                    quick_crashes,
+                    QUICK_CRASH.as_secs()
+                );
+            }
+        } else {
+            quick_crashes = 0;
+        }
+        on_respawn(status);
+    }
+}
+
+/// On a panic of this process outside the executor's own capture, write
+/// the input last handed to the executor to `dir` as `<hash>.json`, which
+/// is the culprit unless the panic came from mutating it, with the panic
+/// message and backtrace next to it as `<hash>.txt`, for a bug report
+/// against the fuzzer. `on_quarantine` is handed the path written, or why
+/// writing it failed. Install it before the executor runs anything, so
+/// panics it captures never reach this hook.
+pub fn install_quarantine(
+    dir: PathBuf,
+    on_quarantine: impl Fn(anyhow::Result<PathBuf>) + Send + Sync + 'static,
+) -> anyhow::Result<()> {
+    fs::create_dir_all(&dir).with_context(|| format!("failed to create {}", dir.display()))?;
+    if QUARANTINE_DIR.set(dir).is_err() {
+        return Ok(());
+    }
+    let previous = panic::take_hook();
+    panic::set_hook(Box::new(move |info: &PanicHookInfo<'_>| {
+        if let Some(quarantined) = quarantine(info) {
+            on_quarantine(quarantined);
+        }
+        previous(info);
+    }));
+    Ok(())
+}
+
+/// Record `input` as the one being executed, once quarantining is installed.
+pub fn track_in_flight(input: &AptosFuzzerInput) {
+    if QUARANTINE_DIR.get().is_some() {
+        if let Ok(mut in_flight) = IN_FLIGHT.lock() {
+            *in_flight = Some(input.clone());
+        }
+    }
+}
+
+/// Write the input in flight and the panic report, returning the input's
+/// path; `None` when there is no input to quarantine.
+fn quarantine(info: &PanicHookInfo<'_>) -> Option<anyhow::Result<PathBuf>> {
+    let (Some(dir), Ok(in_flight)) = (QUARANTINE_DIR.get(), IN_FLIGHT.try_lock()) else {
+        return None;
+    };
+    let input = in_flight.as_ref()?;
+    let mut hasher = DefaultHasher::new();
+    input.hash(&mut hasher);
+    let path = dir.join(format!("{:016x}.json", hasher.finish()));
+    let mut report = payload_message(info.payload());
+    if let Some(location) = info.location() {
+        report = format!("{} at {}", report, location);
+    }
+    report = format!("{}\n\n{}\n", report, std::backtrace::Backtrace::force_capture());
+    let written = serde_json::to_string_pretty(input)
+        .map_err(anyhow::Error::from)
+        .and_then(|json| Ok(fs::write(&path, json)?))
+        .and_then(|()| Ok(fs::write(path.with_extension("txt"), report)?))
+        .with_context(|| format!("failed to quarantine {}", path.display()));
+    Some(written.map(|()| path))
+}
@@ -0,0 +1,1665 @@
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
+
+use aptos_move_binary_format::file_format::Bytecode;
+use aptos_move_core_types::identifier::IdentStr;
+use aptos_move_core_types::u256::U256;
+use aptos_move_core_types::vm_status::AbortLocation;
+use aptos_move_vm_runtime::{MoveTracerExtraInfo, MoveTracerFrameInfo, MoveTracerInstructionContext};
+use aptos_move_vm_types::values::{Value, ValueImpl};
+use log::{trace, warn};
+pub use z3::ast::Int;
+use z3::ast::{Ast, Bool, Dynamic};
+use z3::DeclKind;
+
+use crate::abort_target::AbortGuards;
+use crate::arg_constraint::{AllowedValues, ArgConstraint};
+use crate::branches::BranchKey;
+use crate::concolic::{
+    compare_value, extract_primitive_value, format_module_name, primitive_to_u256, unsupported, value_bitwidth,
+    value_to_u256, SymbolValue, UnsupportedValue,
+};
+use crate::taint::is_signer;
+
+/// Upper bound of the length symbol of a vector argument, as the mutator's
+/// default vector length cap
+const MAX_SYMBOLIC_VECTOR_LEN: u64 = 1024;
+
+/// Symbolic semantics of a native function whose results are simple
+/// functions of its arguments.
+#[derive(Clone, Copy, Debug)]
+enum NativeSummary {
+    /// Returns argument `n` unchanged
+    Argument(usize),
+    /// Returns the value argument `n` refers to
+    ReadArgument(usize),
+    /// `(a: &mut T, b: &mut T)`: exchanges the values behind the references
+    Swap,
+    /// Returns the length of the vector argument `n` refers to
+    Length(usize),
+    /// Returns the BCS encoding of the value argument `n` refers to, whose
+    /// length is known for fixed-size values
+    Serialize(usize),
+}
+
+/// Natives modeled symbolically, by module and function name; any other
+/// native returns `Unknown`s. Framework helpers written in Move, such as
+/// `math64::min`, need no entry: they are traced instruction by instruction.
+const NATIVE_SUMMARIES: &[(&str, &str, NativeSummary)] = &[
+    ("0x1::mem", "swap", NativeSummary::Swap),
+    // Usually compiled to `VecLen`, but scripts may call the native
+    ("0x1::vector", "length", NativeSummary::Length(0)),
+    ("0x1::bcs", "to_bytes", NativeSummary::Serialize(0)),
+    // What `signer::address_of` reads through
+    ("0x1::signer", "borrow_address", NativeSummary::Argument(0)),
+    // A snapshot of an integer is tracked as the integer itself
+    ("0x1::aggregator_v2", "create_snapshot", NativeSummary::Argument(0)),
+    ("0x1::aggregator_v2", "copy_snapshot", NativeSummary::ReadArgument(0)),
+    ("0x1::aggregator_v2", "read_snapshot", NativeSummary::ReadArgument(0)),
+];
+
+#[derive(Clone, Debug)]
+pub struct ConcolicState {
+    pub stack: Vec<SymbolValue>,
+    pub locals: Vec<Vec<SymbolValue>>,
+    pub args: Vec<BTreeMap<usize, Int>>,
+    /// Length symbols of vector arguments, by call and parameter index
+    pub arg_lengths: Vec<BTreeMap<usize, Int>>,
+    pub disable: bool,
+    /// Range of each argument symbol, from its type
+    pub arg_bounds: Vec<Bool>,
+    /// Height of the shared stack below each open frame's values
+    frame_bases: Vec<usize>,
+}
+
+/// A stack slot whose symbolic value, free of argument symbols, evaluated
+/// to something other than the concrete operand in the same slot: a bug in
+/// the symbolic semantics of an instruction run before.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SymbolicDivergence {
+    pub module: String,
+    pub function: String,
+    pub pc: u16,
+    /// Instruction about to execute
+    pub instruction: String,
+    /// Position on the operand stack, from the bottom
+    pub slot: usize,
+    pub symbolic: String,
+    pub concrete: U256,
+}
+
+impl fmt::Display for SymbolicDivergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}::{} @ pc {} before {}: stack slot {} is {} symbolically but {} concretely",
+            self.module, self.function, self.pc, self.instruction, self.slot, self.symbolic, self.concrete
+        )
+    }
+}
+
+fn int_two_pow(bits: u32) -> Int {
+    let v = U256::one() << bits;
+    Int::from_str(&v.to_string()).unwrap()
+}
+
+/// `2^exponent` for a symbolic exponent, as a case split over the shift
+/// amounts valid for a `bits`-wide operand. Larger amounts abort in the VM,
+/// so their value is irrelevant and taken as 0.
+fn int_two_pow_symbolic(exponent: &Int, bits: u32) -> Int {
+    (0..bits).rev().fold(Int::from_u64(0), |rest, amount| {
+        exponent
+            ._eq(&Int::from_u64(amount as u64))
+            .ite(&int_two_pow(amount), &rest)
+    })
+}
+
+fn int_mod_2n(x: &Int, bits: u32) -> Int {
+    x.modulo(int_two_pow(bits))
+}
+
+/// Convert U256 to Int numeral.
+fn int_from_u256(u: U256) -> Int {
+    Int::from_str(&u.to_string()).unwrap()
+}
+
+/// Integer-only AND with a constant bitmask:
+/// Returns (x & mask) under w-bit semantics, using only Int + div/mod by powers
+/// of two. Implementation uses "run decomposition": split mask's 1-bits into
+/// contiguous runs [a..=b], and for each run extract that window from x, then
+/// place it back.
+pub fn int_bvand_const(x: &Int, mask: U256, bits: u32) -> Int {
+    // Normalize x to w-bit domain (BitVec semantics).
+    let x0 = int_mod_2n(x, bits);
+
+    // Quick exits.
+    if mask == U256::zero() {
+        return Int::from_u64(0);
+    }
+    // Restrict mask to w bits (BV mask)
+    let mask_w = mask & ((U256::one() << bits) - U256::one());
+
+    // Iterate over runs of 1s in mask_w.
+    let mut m = mask_w;
+    let mut i: u32 = 0;
+    let mut terms: Vec<Int> = Vec::new();
+
+    while m != U256::zero() {
+        // skip zeros
+        while m != U256::zero() && (m & U256::one()) == U256::zero() {
+            m = m.checked_shr(1).unwrap();
+            i += 1;
+        }
+        if m == U256::zero() {
+            break;
+        }
+        // start of a run
+        let a = i;
+        // consume ones
+        while (m & U256::one()) == U256::one() {
+            m = m.checked_shr(1).unwrap();
+            i += 1;
+        }
+        let b = i - 1; // inclusive end
+        let L = b - a + 1;
+
+        // term(a,b) = (((x0 mod 2^(b+1)) div 2^a) mod 2^L) * 2^a
+        let term = (x0.clone() % int_two_pow(b + 1) / int_two_pow(a)) % int_two_pow(L) * int_two_pow(a);
+        terms.push(term);
+    }
+
+    // Sum all terms (if no runs, it's zero which we handled above).
+    let mut acc = Int::from_u64(0);
+    for t in terms {
+        acc += t;
+    }
+    acc
+}
+
+/// Integer-only OR with a constant bitmask:
+/// Returns (x | mask) under w-bit semantics, using only Int ops.
+/// Uses identity: x | M = (x & ~M_w) + M_w, where ~M_w is bitwise-not of M
+/// within w bits. We reuse int_bvand_const for the "clear then add" pattern.
+pub fn int_bvor_const(x: &Int, mask: U256, bits: u32) -> Int {
+    // mask limited to w bits
+    let full = (U256::one() << bits) - U256::one();
+    let mask_w = mask & full;
+    let not_mask_w = full ^ mask_w;
+
+    // Keep x's bits where mask is 0, then force-on mask bits by addition.
+    let kept = int_bvand_const(x, not_mask_w, bits);
+    kept + int_from_u256(mask_w)
+}
+
+/// Integer-only NOT under w-bit semantics:
+/// r = ~x  (within w bits)  ==  (2^w - 1) - (x mod 2^w)
+pub fn int_bvnot(x: &Int, bits: u32) -> Int {
+    let x0 = int_mod_2n(x, bits);
+    let full = int_two_pow(bits) - 1;
+    full - x0
+}
+
+/// Integer-only XOR with a constant mask under w-bit semantics:
+/// r = x ^ mask = (x & ~mask_w) + (~x & mask_w)
+pub fn int_bvxor_const(x: &Int, mask: U256, bits: u32) -> Int {
+    let full = (U256::one() << bits) - U256::one();
+    let mask_w = mask & full;
+    let not_mask_w = full ^ mask_w;
+
+    let part_keep = int_bvand_const(x, not_mask_w, bits);
+    let x_not = int_bvnot(x, bits);
+    let part_flip = int_bvand_const(&x_not, mask_w, bits);
+
+    // Disjoint bit regions; sum is exact. Normalize just in case.
+    let sum = part_keep + part_flip;
+    int_mod_2n(&sum, bits)
+}
+
+impl Default for ConcolicState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ConcolicState {
+    pub fn new() -> Self {
+        Self {
+            stack: Vec::new(),
+            locals: Vec::new(),
+            args: Vec::new(),
+            arg_lengths: Vec::new(),
+            disable: false,
+            arg_bounds: Vec::new(),
+            frame_bases: Vec::new(),
+        }
+    }
+
+    /// Back to the state of `new()`, keeping the allocated buffers.
+    pub fn clear(&mut self) {
+        self.stack.clear();
+        self.locals.clear();
+        self.args.clear();
+        self.arg_lengths.clear();
+        self.disable = false;
+        self.arg_bounds.clear();
+        self.frame_bases.clear();
+    }
+
+    /// Symbols of the last execution's arguments, named `<call>.<param>`,
+    /// and of the lengths of its vector arguments, named
+    /// `<call>.<param>.len.<element size>`.
+    pub fn arg_symbols(&self) -> Vec<Int> {
+        self.args
+            .iter()
+            .chain(&self.arg_lengths)
+            .flat_map(|args| args.values().cloned())
+            .collect()
+    }
+
+    pub fn open_frame(&mut self, frame: &MoveTracerFrameInfo<'_>) {
+        trace!(
+            "Open frame: {}::{}",
+            frame.function.module_or_script_id(),
+            frame.function.name()
+        );
+        if self.locals.is_empty() {
+            let locals = frame
+                .param_tys
+                .iter()
+                .enumerate()
+                .map(|(i, ty)| Self::resolve_arg(self.args.len(), i, ty))
+                .collect::<Vec<_>>();
+            for (value, ty) in locals.iter().zip(frame.param_tys.iter()) {
+                match (value, Self::type_bits(ty)) {
+                    (SymbolValue::Value(int), Some(bits)) => self
+                        .arg_bounds
+                        .push(Bool::and(&[int.ge(Int::from_u64(0)), int.le(Self::max_u_bits(bits))])),
+                    (SymbolValue::Vector { len }, _) => self.arg_bounds.push(Bool::and(&[
+                        len.ge(Int::from_u64(0)),
+                        len.le(Int::from_u64(MAX_SYMBOLIC_VECTOR_LEN)),
+                    ])),
+                    _ => {}
+                }
+            }
+            self.arg_lengths.push(
+                locals
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(i, v)| match v {
+                        SymbolValue::Vector { len } => Some((i, len.clone())),
+                        _ => None,
+                    })
+                    .collect(),
+            );
+            self.args.push(
+                locals
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(i, v)| match v {
+                        SymbolValue::Value(bv) => Some((i, bv.clone())),
+                        _ => None,
+                    })
+                    .collect(),
+            );
+            self.locals.push(locals);
+            self.frame_bases.push(self.stack.len());
+            trace!("args: {:?}", self.args);
+        } else {
+            if frame.param_tys.len() > self.stack.len() {
+                self.stack.clear();
+                self.locals.clear();
+                self.frame_bases.clear();
+                self.disable = true;
+                warn!("Symbolic stack underflow when opening frame");
+                return;
+            }
+            let skip_idx = self.stack.len() - frame.param_tys.len();
+            self.locals.push(self.stack.drain(skip_idx..).collect());
+            self.stack.truncate(skip_idx);
+            self.frame_bases.push(skip_idx);
+            if frame.is_native {
+                let returns = self.native_returns(frame);
+                self.stack.extend(returns);
+            }
+        }
+    }
+
+    /// Return symbols of the native `frame`, whose arguments are the current
+    /// locals, applying its effects on referenced locals.
+    fn native_returns(&mut self, frame: &MoveTracerFrameInfo<'_>) -> Vec<SymbolValue> {
+        let unknown = vec![SymbolValue::Unknown; frame.return_tys.len()];
+        let module = format_module_name(frame.function.module_or_script_id());
+        let Some(summary) = NATIVE_SUMMARIES
+            .iter()
+            .find(|(m, f, _)| *m == module && *f == frame.function.name())
+            .map(|(_, _, summary)| *summary)
+        else {
+            return unknown;
+        };
+        let arg = |n: usize| {
+            self.locals
+                .last()
+                .and_then(|args| args.get(n))
+                .cloned()
+                .unwrap_or(SymbolValue::Unknown)
+        };
+        let returns = match summary {
+            NativeSummary::Argument(n) => vec![arg(n)],
+            NativeSummary::ReadArgument(n) => vec![self.deref(arg(n))],
+            NativeSummary::Swap => {
+                let (a, b) = (arg(0), arg(1));
+                let (value_a, value_b) = (self.deref(a.clone()), self.deref(b.clone()));
+                self.write_ref(&a, value_b);
+                self.write_ref(&b, value_a);
+                Vec::new()
+            }
+            NativeSummary::Length(n) => vec![Self::length_of(self.deref(arg(n)))],
+            NativeSummary::Serialize(n) => {
+                use aptos_move_vm_types::loaded_data::runtime_types::Type;
+                let size = frame.param_tys.get(n).and_then(|ty| match ty {
+                    Type::Reference(inner) => Self::fixed_size(inner),
+                    ty => Self::fixed_size(ty),
+                });
+                vec![size.map_or(SymbolValue::Unknown, |size| SymbolValue::Vector {
+                    len: Int::from_u64(size as u64),
+                })]
+            }
+        };
+        if returns.len() == unknown.len() {
+            returns
+        } else {
+            unknown
+        }
+    }
+
+    /// Leave exactly the callee's return symbols above the caller's stack,
+    /// so constraints built inside a helper function keep flowing into the
+    /// caller. Anything else the callee left behind is dropped, and
+    /// references into the closed frame degrade to `Unknown`.
+    pub fn close_frame(&mut self, frame: &MoveTracerFrameInfo<'_>) {
+        trace!("Close frame");
+        self.locals.pop();
+        let Some(base) = self.frame_bases.pop() else {
+            return;
+        };
+        let returns = frame.return_tys.len();
+        let mut values = if self.stack.len() >= base + returns {
+            self.stack.split_off(self.stack.len() - returns)
+        } else {
+            warn!("Symbolic stack underflow when closing frame");
+            vec![SymbolValue::Unknown; returns]
+        };
+        for value in values.iter_mut() {
+            if matches!(value, SymbolValue::Ref { frame, .. } if *frame >= self.locals.len()) {
+                *value = SymbolValue::Unknown;
+            }
+        }
+        self.stack.truncate(base);
+        self.stack.extend(values);
+    }
+
+    /// Store `value` in the local `reference` points to; a no-op for
+    /// anything but a local reference.
+    fn write_ref(&mut self, reference: &SymbolValue, value: SymbolValue) {
+        if let SymbolValue::Ref { frame, local } = *reference {
+            if let Some(slot) = self.locals.get_mut(frame).and_then(|locals| locals.get_mut(local)) {
+                *slot = value;
+            }
+        }
+    }
+
+    /// The length symbol of `vector`, `Unknown` if it isn't tracked.
+    fn length_of(vector: SymbolValue) -> SymbolValue {
+        match vector {
+            SymbolValue::Vector { len } => SymbolValue::Value(len),
+            _ => SymbolValue::Unknown,
+        }
+    }
+
+    /// Add `delta` to the length of the vector `reference` points to, if
+    /// tracked.
+    fn resize_ref(&mut self, reference: &SymbolValue, delta: i64) {
+        if let SymbolValue::Vector { len } = self.deref(reference.clone()) {
+            self.write_ref(
+                reference,
+                SymbolValue::Vector {
+                    len: len + Int::from_i64(delta),
+                },
+            );
+        }
+    }
+
+    /// The symbol a reference points to; other values are returned as is.
+    fn deref(&self, value: SymbolValue) -> SymbolValue {
+        match value {
+            SymbolValue::Ref { frame, local } => self
+                .locals
+                .get(frame)
+                .and_then(|locals| locals.get(local))
+                .cloned()
+                .unwrap_or(SymbolValue::Unknown),
+            value => value,
+        }
+    }
+
+    pub fn before_instruction(&mut self, ctx: &MoveTracerInstructionContext<'_>) -> Option<Bool> {
+        // if self.disable {
+        //     return None;
+        // }
+        let s = &ctx.operand_stack;
+        let extra = ctx.extra;
+        let instruction = ctx.instruction;
+        let pc = ctx.pc;
+
+        if self.stack.len() != s.values.len() && s.values.is_empty() {
+            self.stack.clear();
+        }
+        assert_eq!(
+            self.stack.len(),
+            s.values.len(),
+            "stack: {:?}, stack from trace: {:?}",
+            self.stack,
+            s.values,
+        );
+
+        let mut process_binary_op = || {
+            let (rhs, lhs) = (self.stack.pop().unwrap(), self.stack.pop().unwrap());
+            let stack_iter = s.last_n(2).unwrap();
+            let true_lhs = stack_iter.get(0).unwrap();
+            let true_rhs = stack_iter.get(1).unwrap();
+            let (new_l, new_r) = match (lhs, rhs) {
+                (SymbolValue::Value(l), SymbolValue::Value(r)) => (l, r),
+                (SymbolValue::Value(l), SymbolValue::Unknown) => {
+                    let Ok(new_r) = Self::resolve_value(true_rhs) else {
+                        self.disable = true;
+                        return None;
+                    };
+                    (l, new_r)
+                }
+                (SymbolValue::Unknown, SymbolValue::Value(r)) => {
+                    let Ok(new_l) = Self::resolve_value(true_lhs) else {
+                        self.disable = true;
+                        return None;
+                    };
+                    (new_l, r)
+                }
+                _ => {
+                    return None;
+                }
+            };
+            Some((new_l, new_r))
+        };
+
+        match instruction {
+            Bytecode::Pop | Bytecode::BrTrue(_) | Bytecode::BrFalse(_) | Bytecode::Abort => {
+                self.stack.pop();
+            }
+            // Element references are not modeled: accesses through them
+            // read Unknown and writes are dropped
+            Bytecode::VecImmBorrow(_) | Bytecode::VecMutBorrow(_) => {
+                self.stack.pop();
+                if let Some(top) = self.stack.last_mut() {
+                    *top = SymbolValue::Unknown;
+                }
+            }
+            Bytecode::ImmBorrowField(_) |
+            Bytecode::MutBorrowField(_) |
+            Bytecode::ImmBorrowFieldGeneric(_) |
+            Bytecode::MutBorrowFieldGeneric(_) |
+            Bytecode::ImmBorrowVariantField(_) |
+            Bytecode::MutBorrowVariantField(_) |
+            Bytecode::ImmBorrowVariantFieldGeneric(_) |
+            Bytecode::MutBorrowVariantFieldGeneric(_) => {
+                if let Some(top) = self.stack.last_mut() {
+                    *top = SymbolValue::Unknown;
+                }
+            }
+            Bytecode::LdU8(_) |
+            Bytecode::LdU16(_) |
+            Bytecode::LdU32(_) |
+            Bytecode::LdU64(_) |
+            Bytecode::LdU128(_) |
+            Bytecode::LdU256(_) |
+            Bytecode::LdConst(_) => {
+                self.stack.push(SymbolValue::Unknown);
+            }
+            Bytecode::LdFalse => {
+                self.stack.push(SymbolValue::Value(Int::from_u64(0)));
+            }
+            Bytecode::LdTrue => {
+                self.stack.push(SymbolValue::Value(Int::from_u64(1)));
+            }
+            Bytecode::CastU8 => {
+                if let Some(v) = self.stack.last() {
+                    if let SymbolValue::Value(int) = v {
+                        return Some(int.le(Self::max_u_bits(8)));
+                    }
+                } else {
+                    warn!("Stack underflow at pc {}", pc);
+                }
+            }
+            Bytecode::CastU16 => {
+                if let Some(v) = self.stack.last() {
+                    if let SymbolValue::Value(int) = v {
+                        return Some(int.le(Self::max_u_bits(16)));
+                    }
+                } else {
+                    warn!("Stack underflow at pc {}", pc);
+                }
+            }
+            Bytecode::CastU32 => {
+                if let Some(v) = self.stack.last() {
+                    if let SymbolValue::Value(int) = v {
+                        return Some(int.le(Self::max_u_bits(32)));
+                    }
+                } else {
+                    warn!("Stack underflow at pc {}", pc);
+                }
+            }
+            Bytecode::CastU64 => {
+                if let Some(v) = self.stack.last() {
+                    if let SymbolValue::Value(int) = v {
+                        return Some(int.le(Self::max_u_bits(64)));
+                    }
+                } else {
+                    warn!("Stack underflow at pc {}", pc);
+                }
+            }
+            Bytecode::CastU128 => {
+                if let Some(v) = self.stack.last() {
+                    if let SymbolValue::Value(int) = v {
+                        return Some(int.le(Self::max_u_bits(128)));
+                    }
+                } else {
+                    warn!("Stack underflow at pc {}", pc);
+                }
+            }
+            Bytecode::Add => {
+                if let Some((l, r)) = process_binary_op() {
+                    // overflow check not implemented yet
+                    let sum = l + r;
+                    self.stack.push(SymbolValue::Value(sum));
+                } else {
+                    self.stack.push(SymbolValue::Unknown);
+                }
+            }
+            Bytecode::Sub => {
+                if let Some((l, r)) = process_binary_op() {
+                    // overflow check not implemented yet
+                    let diff = l - r;
+                    self.stack.push(SymbolValue::Value(diff));
+                } else {
+                    self.stack.push(SymbolValue::Unknown);
+                }
+            }
+            Bytecode::Mul => {
+                if let Some((l, r)) = process_binary_op() {
+                    let prod = l * r;
+                    self.stack.push(SymbolValue::Value(prod));
+                } else {
+                    self.stack.push(SymbolValue::Unknown);
+                }
+            }
+            Bytecode::Div => {
+                if let Some((l, r)) = process_binary_op() {
+                    // overflow check not implemented yet
+                    let quot = l / r;
+                    self.stack.push(SymbolValue::Value(quot));
+                } else {
+                    self.stack.push(SymbolValue::Unknown);
+                }
+            }
+            Bytecode::Mod => {
+                if let Some((l, r)) = process_binary_op() {
+                    // overflow check not implemented yet
+                    let rem = l % r;
+                    self.stack.push(SymbolValue::Value(rem));
+                } else {
+                    self.stack.push(SymbolValue::Unknown);
+                }
+            }
+            Bytecode::And | Bytecode::BitAnd => {
+                let (rhs, lhs) = (self.stack.pop().unwrap(), self.stack.pop().unwrap());
+                let stack_iter = s.last_n(2).unwrap();
+                let true_lhs = stack_iter.get(0).unwrap();
+                let true_rhs = stack_iter.get(1).unwrap();
+
+                let (bit_width, true_l, true_r) = match (
+                    value_bitwidth(true_lhs),
+                    value_to_u256(true_lhs),
+                    value_to_u256(true_rhs),
+                ) {
+                    (Ok(bit_width), Ok(true_l), Ok(true_r)) => (bit_width, true_l, true_r),
+                    (Err(err), _, _) | (_, Err(err), _) | (_, _, Err(err)) => {
+                        self.unsupported_value(pc, err);
+                        return None;
+                    }
+                };
+                match (lhs, rhs) {
+                    (SymbolValue::Value(l), SymbolValue::Value(r)) => {
+                        self.stack.push(SymbolValue::Unknown);
+                    }
+                    (SymbolValue::Value(l), SymbolValue::Unknown) => {
+                        let and = int_bvand_const(&l, true_r, bit_width);
+                        self.stack.push(SymbolValue::Value(and));
+                    }
+                    (SymbolValue::Unknown, SymbolValue::Value(r)) => {
+                        let and = int_bvand_const(&r, true_l, bit_width);
+                        self.stack.push(SymbolValue::Value(and));
+                    }
+                    _ => {
+                        self.stack.push(SymbolValue::Unknown);
+                    }
+                }
+            }
+            Bytecode::Or | Bytecode::BitOr => {
+                let (rhs, lhs) = (self.stack.pop().unwrap(), self.stack.pop().unwrap());
+                let stack_iter = s.last_n(2).unwrap();
+                let true_lhs = stack_iter.get(0).unwrap();
+                let true_rhs = stack_iter.get(1).unwrap();
+
+                let (bit_width, true_l, true_r) = match (
+                    value_bitwidth(true_lhs),
+                    value_to_u256(true_lhs),
+                    value_to_u256(true_rhs),
+                ) {
+                    (Ok(bit_width), Ok(true_l), Ok(true_r)) => (bit_width, true_l, true_r),
+                    (Err(err), _, _) | (_, Err(err), _) | (_, _, Err(err)) => {
+                        self.unsupported_value(pc, err);
+                        return None;
+                    }
+                };
+                match (lhs, rhs) {
+                    (SymbolValue::Value(l), SymbolValue::Value(r)) => {
+                        self.stack.push(SymbolValue::Unknown);
+                    }
+                    (SymbolValue::Value(l), SymbolValue::Unknown) => {
+                        let or = int_bvor_const(&l, true_r, bit_width);
+                        self.stack.push(SymbolValue::Value(or));
+                    }
+                    (SymbolValue::Unknown, SymbolValue::Value(r)) => {
+                        let or = int_bvor_const(&r, true_l, bit_width);
+                        self.stack.push(SymbolValue::Value(or));
+                    }
+                    _ => {
+                        self.stack.push(SymbolValue::Unknown);
+                    }
+                }
+            }
+            Bytecode::Xor => {
+                let (rhs, lhs) = (self.stack.pop().unwrap(), self.stack.pop().unwrap());
+                let stack_iter = s.last_n(2).unwrap();
+                let true_lhs = stack_iter.get(0).unwrap();
+                let true_rhs = stack_iter.get(1).unwrap();
+
+                let (bit_width, true_l, true_r) = match (
+                    value_bitwidth(true_lhs),
+                    value_to_u256(true_lhs),
+                    value_to_u256(true_rhs),
+                ) {
+                    (Ok(bit_width), Ok(true_l), Ok(true_r)) => (bit_width, true_l, true_r),
+                    (Err(err), _, _) | (_, Err(err), _) | (_, _, Err(err)) => {
+                        self.unsupported_value(pc, err);
+                        return None;
+                    }
+                };
+                match (lhs, rhs) {
+                    (SymbolValue::Value(l), SymbolValue::Value(r)) => {
+                        self.stack.push(SymbolValue::Unknown);
+                    }
+                    (SymbolValue::Value(l), SymbolValue::Unknown) => {
+                        let xor = int_bvxor_const(&l, true_r, bit_width);
+                        self.stack.push(SymbolValue::Value(xor));
+                    }
+                    (SymbolValue::Unknown, SymbolValue::Value(r)) => {
+                        let xor = int_bvxor_const(&r, true_l, bit_width);
+                        self.stack.push(SymbolValue::Value(xor));
+                    }
+                    _ => {
+                        self.stack.push(SymbolValue::Unknown);
+                    }
+                }
+            }
+            Bytecode::Shl => {
+                let (rhs, lhs) = (self.stack.pop().unwrap(), self.stack.pop().unwrap());
+                let stack_iter = s.last_n(2).unwrap();
+                let true_lhs = stack_iter.get(0).unwrap();
+                let true_rhs = stack_iter.get(1).unwrap();
+                let (bit_width, true_r) = match (value_bitwidth(true_lhs), value_to_u256(true_rhs)) {
+                    (Ok(bit_width), Ok(true_r)) => (bit_width, true_r.unchecked_as_u32()),
+                    (Err(err), _) | (_, Err(err)) => {
+                        self.unsupported_value(pc, err);
+                        return None;
+                    }
+                };
+                let threshold = Self::max_u_bits(bit_width);
+                match (lhs, rhs) {
+                    (SymbolValue::Value(l), SymbolValue::Unknown) => {
+                        let shl = l * int_two_pow(true_r);
+                        let shl_mod = shl.modulo(int_two_pow(bit_width));
+                        self.stack.push(SymbolValue::Value(shl_mod));
+                        return Some(shl.gt(&threshold)); // cause overflow
+                    }
+                    (lhs, SymbolValue::Value(r)) => {
+                        // Input-controlled shift count
+                        let l = match lhs {
+                            SymbolValue::Value(l) => l,
+                            _ => match value_to_u256(true_lhs) {
+                                Ok(true_l) => int_from_u256(true_l),
+                                Err(err) => {
+                                    self.unsupported_value(pc, err);
+                                    return None;
+                                }
+                            },
+                        };
+                        let shl = l * int_two_pow_symbolic(&r, bit_width);
+                        let shl_mod = shl.modulo(int_two_pow(bit_width));
+                        self.stack.push(SymbolValue::Value(shl_mod));
+                        return Some(shl.gt(&threshold)); // cause overflow
+                    }
+                    _ => {
+                        self.stack.push(SymbolValue::Unknown);
+                    }
+                }
+            }
+            Bytecode::Shr => {
+                let (rhs, lhs) = (self.stack.pop().unwrap(), self.stack.pop().unwrap());
+                let stack_iter = s.last_n(2).unwrap();
+                let true_lhs = stack_iter.get(0).unwrap();
+                let true_rhs = stack_iter.get(1).unwrap();
+
+                let true_r = match value_to_u256(true_rhs) {
+                    Ok(true_r) => true_r.unchecked_as_u32(),
+                    Err(err) => {
+                        self.unsupported_value(pc, err);
+                        return None;
+                    }
+                };
+                match (lhs, rhs) {
+                    (SymbolValue::Value(l), SymbolValue::Unknown) => {
+                        let shr = l / int_two_pow(true_r);
+                        self.stack.push(SymbolValue::Value(shr));
+                    }
+                    (lhs, SymbolValue::Value(r)) => {
+                        // Input-controlled shift count
+                        let (bit_width, l) = match (value_bitwidth(true_lhs), &lhs) {
+                            (Ok(bit_width), SymbolValue::Value(l)) => (bit_width, l.clone()),
+                            (Ok(bit_width), _) => match value_to_u256(true_lhs) {
+                                Ok(true_l) => (bit_width, int_from_u256(true_l)),
+                                Err(err) => {
+                                    self.unsupported_value(pc, err);
+                                    return None;
+                                }
+                            },
+                            (Err(err), _) => {
+                                self.unsupported_value(pc, err);
+                                return None;
+                            }
+                        };
+                        let shr = l / int_two_pow_symbolic(&r, bit_width);
+                        self.stack.push(SymbolValue::Value(shr));
+                    }
+                    _ => {
+                        self.stack.push(SymbolValue::Unknown);
+                    }
+                }
+            }
+            Bytecode::Not => {
+                if let Some(v) = self.stack.pop() {
+                    match v {
+                        SymbolValue::Value(n) => match value_bitwidth(s.last_n(1).unwrap().get(0).unwrap()) {
+                            Ok(bit_width) => {
+                                let not_n = int_bvnot(&n, bit_width);
+                                self.stack.push(SymbolValue::Value(not_n));
+                            }
+                            Err(err) => self.unsupported_value(pc, err),
+                        },
+                        _ => {
+                            self.stack.push(SymbolValue::Unknown);
+                        }
+                    }
+                } else {
+                    warn!("Stack underflow at pc {}", pc);
+                }
+            }
+            Bytecode::MutBorrowLoc(idx) | Bytecode::ImmBorrowLoc(idx) => {
+                if self.locals.is_empty() {
+                    warn!("No locals available at pc {}", pc);
+                    self.stack.push(SymbolValue::Unknown);
+                } else {
+                    self.stack.push(SymbolValue::Ref {
+                        frame: self.locals.len() - 1,
+                        local: *idx as usize,
+                    });
+                }
+            }
+            Bytecode::ReadRef => {
+                if let Some(top) = self.stack.pop() {
+                    let value = self.deref(top);
+                    self.stack.push(value);
+                } else {
+                    warn!("Stack underflow at pc {}", pc);
+                }
+            }
+            Bytecode::WriteRef => {
+                if let (Some(reference), Some(value)) = (self.stack.pop(), self.stack.pop()) {
+                    self.write_ref(&reference, value);
+                }
+            }
+            Bytecode::CopyLoc(idx) => {
+                if let Some(locals) = self.locals.last() {
+                    if let Some(v) = locals.get(*idx as usize) {
+                        self.stack.push(v.clone());
+                    } else {
+                        warn!("Local index out of bounds at pc {}", pc);
+                        self.stack.push(SymbolValue::Unknown);
+                    }
+                } else {
+                    warn!("No locals available at pc {}", pc);
+                    self.stack.push(SymbolValue::Unknown);
+                }
+            }
+            Bytecode::MoveLoc(idx) => {
+                if let Some(locals) = self.locals.last_mut() {
+                    if let Some(v) = locals.get(*idx as usize) {
+                        self.stack.push(v.clone());
+                        locals[*idx as usize] = SymbolValue::Unknown; // moved-from
+                    } else {
+                        warn!("Local index out of bounds at pc {}", pc);
+                        self.stack.push(SymbolValue::Unknown);
+                    }
+                } else {
+                    warn!("No locals available at pc {}", pc);
+                    self.stack.push(SymbolValue::Unknown);
+                }
+            }
+            Bytecode::StLoc(idx) => {
+                if let Some(v) = self.stack.pop() {
+                    if let Some(locals) = self.locals.last_mut() {
+                        if let Some(slot) = locals.get_mut(*idx as usize) {
+                            *slot = v;
+                        } else {
+                            for _ in locals.len()..=*idx as usize {
+                                locals.push(SymbolValue::Unknown);
+                            }
+                            locals[*idx as usize] = v;
+                        }
+                    } else {
+                        warn!("No locals available at pc {}", pc);
+                    }
+                } else {
+                    warn!("Stack underflow at pc {}", pc);
+                }
+            }
+            Bytecode::VecPushBack(_) => {
+                self.stack.pop();
+                if let Some(reference) = self.stack.pop() {
+                    self.resize_ref(&reference, 1);
+                }
+            }
+            Bytecode::VecPopBack(_) => {
+                if let Some(reference) = self.stack.pop() {
+                    self.resize_ref(&reference, -1);
+                }
+                self.stack.push(SymbolValue::Unknown);
+            }
+            Bytecode::VecLen(_) => {
+                let len = self
+                    .stack
+                    .pop()
+                    .map_or(SymbolValue::Unknown, |reference| Self::length_of(self.deref(reference)));
+                self.stack.push(len);
+            }
+            Bytecode::Eq => {
+                let (rhs, lhs) = (self.stack.pop().unwrap(), self.stack.pop().unwrap());
+                // References compare by the values they point to
+                let (rhs, lhs) = (self.deref(rhs), self.deref(lhs));
+                let stack_iter = s.last_n(2).unwrap();
+                let true_lhs = stack_iter.get(0).unwrap();
+                let true_rhs = stack_iter.get(1).unwrap();
+                let (new_l, new_r) = match (lhs, rhs) {
+                    (SymbolValue::Value(l), SymbolValue::Value(r)) => (l, r),
+                    (SymbolValue::Value(l), SymbolValue::Unknown) => match Self::resolve_value(true_rhs) {
+                        Ok(new_r) => (l, new_r),
+                        Err(err) => {
+                            self.unsupported_value(pc, err);
+                            return None;
+                        }
+                    },
+                    (SymbolValue::Unknown, SymbolValue::Value(r)) => match Self::resolve_value(true_lhs) {
+                        Ok(new_l) => (new_l, r),
+                        Err(err) => {
+                            self.unsupported_value(pc, err);
+                            return None;
+                        }
+                    },
+                    _ => {
+                        self.stack.push(SymbolValue::Unknown);
+                        return None;
+                    }
+                };
+                let ordering = match compare_value(true_lhs, true_rhs) {
+                    Ok(ordering) => ordering,
+                    Err(err) => {
+                        self.unsupported_value(pc, err);
+                        return None;
+                    }
+                };
+                if matches!(ordering, Ordering::Equal) {
+                    let eq = new_l._eq(&new_r);
+                    let int = eq.ite(&Int::from_u64(1), &Int::from_u64(0));
+                    self.stack.push(SymbolValue::Value(int));
+                    return Some(eq);
+                } else {
+                    // different values are not equal
+                    let neq = new_l._eq(&new_r).not();
+                    let int = neq.ite(&Int::from_u64(1), &Int::from_u64(0));
+                    self.stack.push(SymbolValue::Value(int));
+                    return Some(neq);
+                }
+            }
+            Bytecode::Neq => {
+                let (rhs, lhs) = (self.stack.pop().unwrap(), self.stack.pop().unwrap());
+                // References compare by the values they point to
+                let (rhs, lhs) = (self.deref(rhs), self.deref(lhs));
+                let stack_iter = s.last_n(2).unwrap();
+                let true_lhs = stack_iter.get(0).unwrap();
+                let true_rhs = stack_iter.get(1).unwrap();
+                let (new_l, new_r) = match (lhs, rhs) {
+                    (SymbolValue::Value(l), SymbolValue::Value(r)) => (l, r),
+                    (SymbolValue::Value(l), SymbolValue::Unknown) => match Self::resolve_value(true_rhs) {
+                        Ok(new_r) => (l, new_r),
+                        Err(err) => {
+                            self.unsupported_value(pc, err);
+                            return None;
+                        }
+                    },
+                    (SymbolValue::Unknown, SymbolValue::Value(r)) => match Self::resolve_value(true_lhs) {
+                        Ok(new_l) => (new_l, r),
+                        Err(err) => {
+                            self.unsupported_value(pc, err);
+                            return None;
+                        }
+                    },
+                    _ => {
+                        self.stack.push(SymbolValue::Unknown);
+                        return None;
+                    }
+                };
+                let ordering = match compare_value(true_lhs, true_rhs) {
+                    Ok(ordering) => ordering,
+                    Err(err) => {
+                        self.unsupported_value(pc, err);
+                        return None;
+                    }
+                };
+                if !matches!(ordering, Ordering::Equal) {
+                    let neq = new_l._eq(&new_r).not();
+                    let bv = neq.ite(&Int::from_u64(1), &Int::from_u64(0));
+                    self.stack.push(SymbolValue::Value(bv));
+                    return Some(neq);
+                } else {
+                    // same values are equal
+                    let eq = new_l._eq(&new_r);
+                    let bv = eq.ite(&Int::from_u64(1), &Int::from_u64(0));
+                    self.stack.push(SymbolValue::Value(bv));
+                    return Some(eq.not());
+                }
+            }
+            Bytecode::Lt => {
+                let (rhs, lhs) = (self.stack.pop().unwrap(), self.stack.pop().unwrap());
+                let stack_iter = s.last_n(2).unwrap();
+                let true_lhs = stack_iter.get(0).unwrap();
+                let true_rhs = stack_iter.get(1).unwrap();
+                let (new_l, new_r) = match (lhs, rhs) {
+                    (SymbolValue::Value(l), SymbolValue::Value(r)) => (l, r),
+                    (SymbolValue::Value(l), SymbolValue::Unknown) => match Self::resolve_value(true_rhs) {
+                        Ok(new_r) => (l, new_r),
+                        Err(err) => {
+                            self.unsupported_value(pc, err);
+                            return None;
+                        }
+                    },
+                    (SymbolValue::Unknown, SymbolValue::Value(r)) => match Self::resolve_value(true_lhs) {
+                        Ok(new_l) => (new_l, r),
+                        Err(err) => {
+                            self.unsupported_value(pc, err);
+                            return None;
+                        }
+                    },
+                    _ => {
+                        self.stack.push(SymbolValue::Unknown);
+                        return None;
+                    }
+                };
+                let ordering = match compare_value(true_lhs, true_rhs) {
+                    Ok(ordering) => ordering,
+                    Err(err) => {
+                        self.unsupported_value(pc, err);
+                        return None;
+                    }
+                };
+                if matches!(ordering, Ordering::Less) {
+                    let lt = new_l.lt(&new_r);
+                    let int = lt.ite(&Int::from_u64(1), &Int::from_u64(0));
+                    self.stack.push(SymbolValue::Value(int));
+                    return Some(lt);
+                } else {
+                    // not less than
+                    let nlt = new_l.lt(&new_r).not();
+                    let int = nlt.ite(&Int::from_u64(1), &Int::from_u64(0));
+                    self.stack.push(SymbolValue::Value(int));
+                    return Some(nlt);
+                }
+            }
+            Bytecode::Le => {
+                let (rhs, lhs) = (self.stack.pop().unwrap(), self.stack.pop().unwrap());
+                let stack_iter = s.last_n(2).unwrap();
+                let true_lhs = stack_iter.get(0).unwrap();
+                let true_rhs = stack_iter.get(1).unwrap();
+                let (new_l, new_r) = match (lhs, rhs) {
+                    (SymbolValue::Value(l), SymbolValue::Value(r)) => (l, r),
+                    (SymbolValue::Value(l), SymbolValue::Unknown) => match Self::resolve_value(true_rhs) {
+                        Ok(new_r) => (l, new_r),
+                        Err(err) => {
+                            self.unsupported_value(pc, err);
+                            return None;
+                        }
+                    },
+                    (SymbolValue::Unknown, SymbolValue::Value(r)) => match Self::resolve_value(true_lhs) {
+                        Ok(new_l) => (new_l, r),
+                        Err(err) => {
+                            self.unsupported_value(pc, err);
+                            return None;
+                        }
+                    },
+                    _ => {
+                        self.stack.push(SymbolValue::Unknown);
+                        return None;
+                    }
+                };
+                let ordering = match compare_value(true_lhs, true_rhs) {
+                    Ok(ordering) => ordering,
+                    Err(err) => {
+                        self.unsupported_value(pc, err);
+                        return None;
+                    }
+                };
+                if !matches!(ordering, Ordering::Greater) {
+                    let lt = new_l.le(&new_r);
+                    let int = lt.ite(&Int::from_u64(1), &Int::from_u64(0));
+                    self.stack.push(SymbolValue::Value(int));
+                    return Some(lt);
+                } else {
+                    // not less than
+                    let nlt = new_l.le(&new_r).not();
+                    let int = nlt.ite(&Int::from_u64(1), &Int::from_u64(0));
+                    self.stack.push(SymbolValue::Value(int));
+                    return Some(nlt);
+                }
+            }
+            Bytecode::Gt => {
+                let (rhs, lhs) = (self.stack.pop().unwrap(), self.stack.pop().unwrap());
+                let stack_iter = s.last_n(2).unwrap();
+                let true_lhs = stack_iter.get(0).unwrap();
+                let true_rhs = stack_iter.get(1).unwrap();
+                let (new_l, new_r) = match (lhs, rhs) {
+                    (SymbolValue::Value(l), SymbolValue::Value(r)) => (l, r),
+                    (SymbolValue::Value(l), SymbolValue::Unknown) => match Self::resolve_value(true_rhs) {
+                        Ok(new_r) => (l, new_r),
+                        Err(err) => {
+                            self.unsupported_value(pc, err);
+                            return None;
+                        }
+                    },
+                    (SymbolValue::Unknown, SymbolValue::Value(r)) => match Self::resolve_value(true_lhs) {
+                        Ok(new_l) => (new_l, r),
+                        Err(err) => {
+                            self.unsupported_value(pc, err);
+                            return None;
+                        }
+                    },
+                    _ => {
+                        self.stack.push(SymbolValue::Unknown);
+                        return None;
+                    }
+                };
+                let ordering = match compare_value(true_lhs, true_rhs) {
+                    Ok(ordering) => ordering,
+                    Err(err) => {
+                        self.unsupported_value(pc, err);
+                        return None;
+                    }
+                };
+                if matches!(ordering, Ordering::Greater) {
+                    let lt = new_l.gt(&new_r);
+                    let int = lt.ite(&Int::from_u64(1), &Int::from_u64(0));
+                    self.stack.push(SymbolValue::Value(int));
+                    return Some(lt);
+                } else {
+                    // not less than
+                    let nlt = new_l.gt(&new_r).not();
+                    let int = nlt.ite(&Int::from_u64(1), &Int::from_u64(0));
+                    self.stack.push(SymbolValue::Value(int));
+                    return Some(nlt);
+                }
+            }
+            Bytecode::Ge => {
+                let (rhs, lhs) = (self.stack.pop().unwrap(), self.stack.pop().unwrap());
+                let stack_iter = s.last_n(2).unwrap();
+                let true_lhs = stack_iter.get(0).unwrap();
+                let true_rhs = stack_iter.get(1).unwrap();
+                let (new_l, new_r) = match (lhs, rhs) {
+                    (SymbolValue::Value(l), SymbolValue::Value(r)) => (l, r),
+                    (SymbolValue::Value(l), SymbolValue::Unknown) => match Self::resolve_value(true_rhs) {
+                        Ok(new_r) => (l, new_r),
+                        Err(err) => {
+                            self.unsupported_value(pc, err);
+                            return None;
+                        }
+                    },
+                    (SymbolValue::Unknown, SymbolValue::Value(r)) => match Self::resolve_value(true_lhs) {
+                        Ok(new_l) => (new_l, r),
+                        Err(err) => {
+                            self.unsupported_value(pc, err);
+                            return None;
+                        }
+                    },
+                    _ => {
+                        self.stack.push(SymbolValue::Unknown);
+                        return None;
+                    }
+                };
+                let ordering = match compare_value(true_lhs, true_rhs) {
+                    Ok(ordering) => ordering,
+                    Err(err) => {
+                        self.unsupported_value(pc, err);
+                        return None;
+                    }
+                };
+                if !matches!(ordering, Ordering::Less) {
+                    let lt = new_l.ge(&new_r);
+                    let int = lt.ite(&Int::from_u64(1), &Int::from_u64(0));
+                    self.stack.push(SymbolValue::Value(int));
+                    return Some(lt);
+                } else {
+                    // not less than
+                    let nlt = new_l.ge(&new_r).not();
+                    let int = nlt.ite(&Int::from_u64(1), &Int::from_u64(0));
+                    self.stack.push(SymbolValue::Value(int));
+                    return Some(nlt);
+                }
+            }
+            Bytecode::VecPack(_, len) => {
+                for _ in 0..*len {
+                    self.stack.pop();
+                }
+                // Elements are not tracked, only the length
+                self.stack.push(SymbolValue::Vector {
+                    len: Int::from_u64(*len),
+                });
+            }
+            Bytecode::VecUnpack(_, len) => {
+                self.stack.pop();
+                for _ in 0..*len {
+                    self.stack.push(SymbolValue::Unknown); // represent each
+                                                           // element as unknown
+                }
+            }
+            Bytecode::VecSwap(_) => {
+                self.stack.pop();
+                self.stack.pop();
+                self.stack.pop();
+            }
+            Bytecode::Pack(_) | Bytecode::PackGeneric(_) => {
+                if let Some(extra_info) = extra {
+                    match extra_info {
+                        MoveTracerExtraInfo::Pack(count) | MoveTracerExtraInfo::PackGeneric(count) => {
+                            for _ in 0..count {
+                                self.stack.pop();
+                            }
+                            self.stack.push(SymbolValue::Unknown); // represent the struct as unknown
+                        }
+                        _ => {}
+                    }
+                } else {
+                    warn!("Missing extra info for pack at pc {}", pc);
+                    self.disable = true;
+                    return None;
+                }
+            }
+            Bytecode::Unpack(_) | Bytecode::UnpackGeneric(_) => {
+                self.stack.pop();
+                if let Some(extra_info) = extra {
+                    match extra_info {
+                        MoveTracerExtraInfo::Unpack(count) | MoveTracerExtraInfo::UnpackGeneric(count) => {
+                            for _ in 0..count {
+                                self.stack.push(SymbolValue::Unknown); // represent each field as unknown
+                            }
+                        }
+                        _ => {}
+                    }
+                } else {
+                    warn!("Missing extra info for unpack at pc {}", pc);
+                    self.disable = true;
+                    return None;
+                }
+            }
+            Bytecode::PackVariant(_) | Bytecode::PackVariantGeneric(_) => {
+                if let Some(extra_info) = extra {
+                    match extra_info {
+                        MoveTracerExtraInfo::PackVariant(count) | MoveTracerExtraInfo::PackVariantGeneric(count) => {
+                            for _ in 0..count {
+                                self.stack.pop();
+                            }
+                            self.stack.push(SymbolValue::Unknown); // represent the enum as unknown
+                        }
+                        _ => {}
+                    }
+                } else {
+                    warn!("Missing extra info for pack variant at pc {}", pc);
+                    self.disable = true;
+                    return None;
+                }
+            }
+            Bytecode::UnpackVariant(_) | Bytecode::UnpackVariantGeneric(_) => {
+                self.stack.pop();
+                if let Some(extra_info) = extra {
+                    match extra_info {
+                        MoveTracerExtraInfo::UnpackVariant(count) |
+                        MoveTracerExtraInfo::UnpackVariantGeneric(count) => {
+                            for _ in 0..count {
+                                self.stack.push(SymbolValue::Unknown); // represent each field as unknown
+                            }
+                        }
+                        _ => {}
+                    }
+                } else {
+                    warn!("Missing extra info for unpack variant at pc {}", pc);
+                    self.disable = true;
+                    return None;
+                }
+            }
+            _ => {}
+        }
+        None
+    }
+
+    fn resolve_arg(
+        cmd_index: usize,
+        param_index: usize,
+        ty: &aptos_move_vm_types::loaded_data::runtime_types::Type,
+    ) -> SymbolValue {
+        use aptos_move_vm_types::loaded_data::runtime_types::Type;
+        match ty {
+            Type::Bool | Type::U8 | Type::U16 | Type::U32 | Type::U64 | Type::U128 | Type::U256 => {
+                let name = format!("{}.{}", cmd_index, param_index);
+                let int = Int::new_const(name);
+                SymbolValue::Value(int)
+            }
+            // Named after the element size too, so a solved length can be
+            // turned back into a vector argument
+            Type::Vector(element) => match Self::fixed_size(element) {
+                Some(size) => SymbolValue::Vector {
+                    len: Int::new_const(format!("{}.{}.len.{}", cmd_index, param_index, size)),
+                },
+                None => SymbolValue::Unknown,
+            },
+            _ => SymbolValue::Unknown,
+        }
+    }
+
+    /// Size of the BCS encoding of every value of type `ty`, if fixed.
+    fn fixed_size(ty: &aptos_move_vm_types::loaded_data::runtime_types::Type) -> Option<usize> {
+        use aptos_move_vm_types::loaded_data::runtime_types::Type;
+        match ty {
+            Type::Bool | Type::U8 => Some(1),
+            Type::U16 => Some(2),
+            Type::U32 => Some(4),
+            Type::U64 => Some(8),
+            Type::U128 => Some(16),
+            Type::U256 | Type::Address | Type::Signer => Some(32),
+            _ => None,
+        }
+    }
+
+    /// Width of the integer a symbolic argument of type `ty` stands for.
+    fn type_bits(ty: &aptos_move_vm_types::loaded_data::runtime_types::Type) -> Option<u32> {
+        use aptos_move_vm_types::loaded_data::runtime_types::Type;
+        match ty {
+            Type::Bool => Some(1),
+            Type::U8 => Some(8),
+            Type::U16 => Some(16),
+            Type::U32 => Some(32),
+            Type::U64 => Some(64),
+            Type::U128 => Some(128),
+            Type::U256 => Some(256),
+            _ => None,
+        }
+    }
+
+    fn resolve_value(value: &Value) -> Result<Int, UnsupportedValue> {
+        Self::resolve_value_impl(&value.0)
+    }
+
+    fn resolve_value_impl(value_impl: &ValueImpl) -> Result<Int, UnsupportedValue> {
+        let primitive_value = extract_primitive_value(value_impl)?;
+        match primitive_value {
+            ValueImpl::Bool(b) => {
+                let int_val = if b { 1 } else { 0 };
+                Ok(Int::from_u64(int_val))
+            }
+            ValueImpl::U8(u) => Ok(Int::from_u64(u as u64)),
+            ValueImpl::U16(u) => Ok(Int::from_u64(u as u64)),
+            ValueImpl::U32(u) => Ok(Int::from_u64(u as u64)),
+            ValueImpl::U64(u) => Ok(Int::from_u64(u)),
+            ValueImpl::U128(u) => Ok(int_from_u256(U256::from(u))),
+            ValueImpl::U256(u) => Ok(int_from_u256(u)),
+            _ => unsupported(value_impl),
+        }
+    }
+
+    /// Give up on a value the tracer cannot model: the result slot becomes
+    /// `Unknown` and symbolic tracking is disabled for the rest of the frame.
+    fn unsupported_value(&mut self, pc: u16, err: UnsupportedValue) {
+        trace!("{} at pc {}", err, pc);
+        self.stack.push(SymbolValue::Unknown);
+        self.disable = true;
+    }
+
+    #[inline]
+    fn max_u_bits(n: u32) -> Int {
+        if n <= 63 {
+            Int::from_u64((1u64 << n) - 1)
+        } else {
+            let two_pow_n_minus_1 = match n {
+                64 => "18446744073709551615",
+                128 => "340282366920938463463374607431768211455",
+                256 => "115792089237316195423570985008687907853269984665640564039457584007913129639935",
+                _ => unreachable!("add more cases or compute big ints as needed"),
+            };
+            Int::from_str(two_pow_n_minus_1).unwrap()
+        }
+    }
+}
+
+/// What the tracer collects for the solver besides the symbolic state: the
+/// path condition of the run, the queries flipping its branches towards a
+/// target abort or the one-sided branches of the worklist, and the
+/// self-check of the symbolic stack.
+#[derive(Debug, Default)]
+pub struct PathGuidance {
+    /// Set by `--solve-abort`: the abort to steer executions towards
+    abort_guards: Option<AbortGuards>,
+    /// Conditions of the branches taken so far, kept while an abort target
+    /// or flip targets are set
+    path: Vec<Bool>,
+    /// Path prefixes with a guarding branch flipped towards the target abort
+    abort_queries: Vec<Bool>,
+    abort_reached: bool,
+    /// One-sided branches of the worklist to ask the solver to flip
+    flip_targets: HashSet<BranchKey>,
+    /// Path prefixes with one of `flip_targets` flipped, at most one per
+    /// branch and run
+    branch_queries: Vec<(BranchKey, Bool)>,
+    /// Assumed on the arguments of the outermost frame
+    arg_constraints: Vec<ArgConstraint>,
+    /// Compare the symbolic stack with the concrete one before every
+    /// instruction
+    self_check: bool,
+    /// First mismatch the self-check found in this run; the ones after it
+    /// mostly carry the same wrong value along
+    divergence: Option<SymbolicDivergence>,
+}
+
+impl PathGuidance {
+    /// Collect path constraints and ask for the branches guarding `guards`'
+    /// aborts to be flipped.
+    pub fn set_abort_guards(&mut self, guards: Option<AbortGuards>) {
+        self.abort_guards = guards;
+    }
+
+    /// Collect path constraints and ask for the branches at `targets` to be
+    /// flipped the way they were never taken.
+    pub fn set_flip_targets(&mut self, targets: HashSet<BranchKey>) {
+        self.flip_targets = targets;
+    }
+
+    /// Constraints whose models take the last execution's path up to one of
+    /// the flip targets and then the other way, with the branch flipped.
+    pub fn take_branch_queries(&mut self) -> Vec<(BranchKey, Bool)> {
+        std::mem::take(&mut self.branch_queries)
+    }
+
+    /// Restrict the symbols of entry function arguments to the values their
+    /// constraints allow, so solved inputs respect them.
+    pub fn set_arg_constraints(&mut self, constraints: Vec<ArgConstraint>) {
+        self.arg_constraints = constraints;
+    }
+
+    /// Check the symbolic semantics against the concrete execution: before
+    /// every instruction, each stack value whose symbolic expression has no
+    /// argument symbols left is evaluated and compared with the concrete
+    /// operand. Slows execution down, so is meant for validating the
+    /// tracer rather than fuzzing.
+    pub fn set_self_check(&mut self, enabled: bool) {
+        self.self_check = enabled;
+    }
+
+    /// First mismatch the self-check found in the last execution.
+    pub fn take_divergence(&mut self) -> Option<SymbolicDivergence> {
+        self.divergence.take()
+    }
+
+    /// Whether the last execution raised the target abort.
+    pub fn abort_reached(&self) -> bool {
+        self.abort_reached
+    }
+
+    /// Constraints whose models take the last execution's path up to a
+    /// guarding branch and then towards the target abort.
+    pub fn take_abort_queries(&mut self) -> Vec<Bool> {
+        std::mem::take(&mut self.abort_queries)
+    }
+
+    /// Forget the last execution, keeping the targets and settings.
+    pub(crate) fn reset(&mut self) {
+        self.path.clear();
+        self.abort_queries.clear();
+        self.abort_reached = false;
+        self.branch_queries.clear();
+        self.divergence = None;
+    }
+
+    /// Called once `frame` is open, `depth` frames deep, and `state` has
+    /// made symbols of its arguments.
+    pub(crate) fn open_frame(&mut self, frame: &MoveTracerFrameInfo<'_>, depth: usize, state: &mut ConcolicState) {
+        if depth == 1 && !self.arg_constraints.is_empty() {
+            self.assume_arg_constraints(frame, state);
+        }
+    }
+
+    /// Record whether the abort the last execution ended with, raised in
+    /// the innermost open `frame`, is the target one.
+    pub(crate) fn on_abort(&mut self, location: &AbortLocation, code: u64, frame: Option<&(String, String)>) {
+        if let (Some(guards), AbortLocation::Module(module_id), Some((_, function))) =
+            (&self.abort_guards, location, frame)
+        {
+            self.abort_reached |=
+                code == guards.code && format_module_name(module_id) == guards.module && *function == guards.function;
+        }
+    }
+
+    /// Called before `state` sees the instruction at `site`, which runs in
+    /// the innermost open `frame`.
+    pub(crate) fn before_instruction(
+        &mut self,
+        site: BranchKey,
+        ctx: &MoveTracerInstructionContext<'_>,
+        state: &ConcolicState,
+        frame: Option<&(String, String)>,
+    ) {
+        if matches!(ctx.instruction, Bytecode::BrTrue(_) | Bytecode::BrFalse(_)) {
+            self.record_branch_constraint(site, ctx, state, frame);
+        }
+        if self.self_check {
+            self.check_symbolic_stack(ctx, state, frame);
+        }
+    }
+
+    /// Add the numeric argument constraints of the function `frame` runs to
+    /// the argument bounds. Only called for the outermost frame, whose
+    /// arguments are the symbols.
+    fn assume_arg_constraints(&self, frame: &MoveTracerFrameInfo<'_>, state: &mut ConcolicState) {
+        let Ok(function) = IdentStr::new(frame.function.name()) else {
+            return;
+        };
+        let module = frame.function.module_or_script_id();
+        // Parameter index of each argument besides signers
+        let value_params: Vec<usize> = frame
+            .param_tys
+            .iter()
+            .enumerate()
+            .filter(|(_, ty)| !is_signer(ty))
+            .map(|(idx, _)| idx)
+            .collect();
+        for constraint in &self.arg_constraints {
+            if !constraint.applies_to(module, function) {
+                continue;
+            }
+            let Some(symbol) = value_params
+                .get(constraint.arg_idx())
+                .and_then(|param| state.args.first()?.get(param))
+            else {
+                continue;
+            };
+            let assumption = match constraint.allowed() {
+                AllowedValues::Range(min, max) => {
+                    Bool::and(&[symbol.ge(int_from_u256(*min)), symbol.le(int_from_u256(*max))])
+                }
+                AllowedValues::Numbers(values) => {
+                    let options: Vec<Bool> = values.iter().map(|value| symbol._eq(&int_from_u256(*value))).collect();
+                    Bool::or(&options)
+                }
+                AllowedValues::Addresses(_) | AllowedValues::Pool => continue,
+            };
+            state.arg_bounds.push(assumption);
+        }
+    }
+
+    fn record_branch_constraint(
+        &mut self,
+        site: BranchKey,
+        ctx: &MoveTracerInstructionContext<'_>,
+        state: &ConcolicState,
+        frame: Option<&(String, String)>,
+    ) {
+        if self.abort_guards.is_none() && self.flip_targets.is_empty() {
+            return;
+        }
+        let Some(SymbolValue::Value(condition)) = state.stack.last() else {
+            return;
+        };
+        let Some(taken) = ctx.operand_stack.values().last().and_then(primitive_to_u256) else {
+            return;
+        };
+        let taken = !taken.is_zero();
+        let goes = |way: bool| condition._eq(&Int::from_u64(way as u64));
+        let flipped = |path: &[Bool], bounds: &[Bool]| {
+            let mut query = path.to_vec();
+            query.extend(bounds.iter().cloned());
+            query.push(goes(!taken));
+            Bool::and(&query)
+        };
+        if let Some(guards) = &self.abort_guards {
+            let in_target =
+                frame.is_some_and(|(module, function)| *module == guards.module && *function == guards.function);
+            if in_target && guards.leads_to_abort(ctx.pc, !taken) && !guards.leads_to_abort(ctx.pc, taken) {
+                self.abort_queries.push(flipped(&self.path, &state.arg_bounds));
+            }
+        }
+        if self.flip_targets.contains(&site) && !self.branch_queries.iter().any(|(key, _)| *key == site) {
+            self.branch_queries.push((site, flipped(&self.path, &state.arg_bounds)));
+        }
+        self.path.push(goes(taken));
+    }
+
+    fn check_symbolic_stack(
+        &mut self,
+        ctx: &MoveTracerInstructionContext<'_>,
+        state: &ConcolicState,
+        frame: Option<&(String, String)>,
+    ) {
+        let values = ctx.operand_stack.values();
+        if self.divergence.is_some() || state.disable || state.stack.len() != values.len() {
+            return;
+        }
+        for (slot, (symbol, value)) in state.stack.iter().zip(values).enumerate() {
+            let SymbolValue::Value(expr) = symbol else {
+                continue;
+            };
+            let Some(concrete) = primitive_to_u256(value) else {
+                continue;
+            };
+            if int_has_variable(expr) != Some(false) {
+                continue;
+            }
+            let symbolic = expr.simplify().to_string();
+            if symbolic == concrete.to_string() {
+                continue;
+            }
+            let (module, function) = frame.cloned().unwrap_or_default();
+            self.divergence = Some(SymbolicDivergence {
+                module,
+                function,
+                pc: ctx.pc,
+                instruction: format!("{:?}", ctx.instruction),
+                slot,
+                symbolic,
+                concrete,
+            });
+            return;
+        }
+    }
+}
+
+pub(crate) fn contains_division(expr: &Int) -> bool {
+    let mut stack = vec![Dynamic::from(expr.clone())];
+    let mut count = 0;
+    while let Some(node) = stack.pop() {
+        count += 1;
+        if count > 10_000 {
+            break;
+        }
+        if let Ok(decl) = node.safe_decl() {
+            match decl.kind() {
+                DeclKind::DIV | DeclKind::IDIV => return true,
+                _ => {}
+            }
+        }
+        stack.extend(node.children());
+    }
+    false
+}
+
+pub(crate) fn int_has_variable(expr: &Int) -> Option<bool> {
+    let mut stack = vec![Dynamic::from(expr.clone())];
+    let mut count = 0;
+    while let Some(node) = stack.pop() {
+        count += 1;
+        if count > 10_000 {
+            return None;
+        }
+        if node.is_const() {
+            if let Ok(decl) = node.safe_decl() {
+                if decl.kind() == DeclKind::UNINTERPRETED {
+                    return Some(true);
+                }
+            }
+        }
+        stack.extend(node.children());
+    }
+    Some(false)
+}
@@ -0,0 +1,268 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashSet;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use anyhow::Context;
+use libafl::corpus::{Corpus, CorpusId};
+use libafl::state::{HasCorpus, HasImported};
+use libafl::Evaluator;
+use log::warn;
+
+use crate::input::AptosFuzzerInput;
+use crate::runner::load_input;
+use crate::state::AptosFuzzerState;
+
+/// Time between two syncs with the sync directory
+pub const SYNC_INTERVAL: Duration = Duration::from_secs(30);
+
+/// A directory shared by fuzzer instances, on one machine or a network
+/// share, for loose cooperation without LLMP. Each instance exports its new
+/// corpus entries as JSON to its own subdirectory and imports the entries
+/// of every other subdirectory, or left at the top level by other tools.
+pub struct CorpusSync {
+    dir: PathBuf,
+    instance: String,
+    /// Corpus entries already exported or checked
+    exported_ids: HashSet<CorpusId>,
+    /// Hashes of the inputs exported or imported, so neither goes back
+    known: HashSet<u64>,
+    /// Files already imported
+    imported_files: HashSet<PathBuf>,
+    last_sync: Option<Instant>,
+}
+
+impl CorpusSync {
+    /// Sync through `dir`, exporting to `<dir>/<instance>`.
+    pub fn new(dir: PathBuf, instance: String) -> anyhow::Result<Self> {
+        let own = dir.join(&instance);
+        fs::create_dir_all(&own).with_context(|| format!("failed to create {}", own.display()))?;
+        Ok(Self {
+            dir,
+            instance,
+            exported_ids: HashSet::new(),
+            known: HashSet::new(),
+            imported_files: HashSet::new(),
+            last_sync: None,
+        })
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Run the inputs imported from the others, keeping the interesting ones
+    /// and counting them as imported in the state, then export the new
+    /// corpus entries. Returns how many inputs were kept and exported.
+    pub fn sync<Z, E, EM>(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        mgr: &mut EM,
+        state: &mut AptosFuzzerState,
+    ) -> anyhow::Result<(usize, usize)>
+    where
+        Z: Evaluator<E, EM, AptosFuzzerInput, AptosFuzzerState>,
+    {
+        let mut kept = 0;
+        for input in self.import()? {
+            let (_, corpus_id) = fuzzer
+                .evaluate_input(state, executor, mgr, &input)
+                .map_err(|e| anyhow::anyhow!("failed to run imported input: {}", e))?;
+            if corpus_id.is_some() {
+                kept += 1;
+            }
+        }
+        *state.imported_mut() += kept;
+        Ok((kept, self.export(state)?))
+    }
+
+    /// Whether `SYNC_INTERVAL` has passed since the last sync.
+    pub fn is_due(&self) -> bool {
+        self.last_sync.is_none_or(|last| last.elapsed() >= SYNC_INTERVAL)
+    }
+
+    /// Write the corpus entries added since the last export. Returns how
+    /// many were written.
+    pub fn export(&mut self, state: &AptosFuzzerState) -> anyhow::Result<usize> {
+        self.last_sync = Some(Instant::now());
+        let own = self.dir.join(&self.instance);
+        let mut written = 0;
+        let ids: Vec<CorpusId> = state.corpus().ids().collect();
+        for id in ids {
+            if !self.exported_ids.insert(id) {
+                continue;
+            }
+            let Ok(input) = state.corpus().cloned_input_for_id(id) else {
+                continue;
+            };
+            let hash = input_hash(&input);
+            if !self.known.insert(hash) {
+                continue;
+            }
+            // Write then rename, so readers never see a partial file
+            let path = own.join(format!("{:016x}.json", hash));
+            let partial = path.with_extension("json.tmp");
+            fs::write(&partial, serde_json::to_string_pretty(&input)?)
+                .with_context(|| format!("failed to write {}", partial.display()))?;
+            fs::rename(&partial, &path).with_context(|| format!("failed to write {}", path.display()))?;
+            written += 1;
+        }
+        Ok(written)
+    }
+
+    /// Inputs the other instances and tools wrote since the last import, in
+    /// file name order. Files that don't hold an input are skipped.
+    pub fn import(&mut self) -> anyhow::Result<Vec<AptosFuzzerInput>> {
+        self.last_sync = Some(Instant::now());
+        let mut files = Vec::new();
+        for entry in fs::read_dir(&self.dir).with_context(|| format!("failed to read {}", self.dir.display()))? {
+            let path = entry?.path();
+            if path.is_dir() {
+                if path.file_name().is_some_and(|name| *name == *self.instance) {
+                    continue;
+                }
+                let Ok(entries) = fs::read_dir(&path) else {
+                    continue;
+                };
+                files.extend(entries.filter_map(|entry| entry.ok().map(|entry| entry.path())));
+            } else {
+                files.push(path);
+            }
+        }
+        files.retain(|path| path.extension().is_some_and(|ext| ext == "json"));
+        files.sort();
+
+        let mut inputs = Vec::new();
+        for path in files {
+            if !self.imported_files.insert(path.clone()) {
+                continue;
+            }
+            match load_input(&path) {
+                Ok(input) => {
+                    if self.known.insert(input_hash(&input)) {
+                        inputs.push(input);
+                    }
+                }
+                Err(e) => warn!("[aptos-fuzzer] skipping {}: {:#}", path.display(), e),
+            }
+        }
+        Ok(inputs)
+    }
+}
+
+fn input_hash(input: &AptosFuzzerInput) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    input.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::{fs, process};
+
+    use aptos_move_core_types::account_address::AccountAddress;
+    use aptos_move_core_types::identifier::Identifier;
+    use aptos_move_core_types::language_storage::ModuleId;
+    use aptos_types::transaction::{EntryFunction, TransactionPayload};
+    use libafl::corpus::{Corpus, Testcase};
+    use libafl::state::HasCorpus;
+
+    use super::CorpusSync;
+    use crate::input::AptosFuzzerInput;
+    use crate::state::AptosFuzzerState;
+
+    fn input(function: &str) -> AptosFuzzerInput {
+        let module = ModuleId::new(
+            AccountAddress::from_hex_literal("0xcafe").unwrap(),
+            Identifier::new("m").unwrap(),
+        );
+        AptosFuzzerInput::new(TransactionPayload::EntryFunction(EntryFunction::new(
+            module,
+            Identifier::new(function).unwrap(),
+            Vec::new(),
+            Vec::new(),
+        )))
+    }
+
+    fn state(functions: &[&str]) -> AptosFuzzerState {
+        let mut state = AptosFuzzerState::new(PathBuf::new());
+        for function in functions {
+            state.corpus_mut().add(Testcase::new(input(function))).unwrap();
+        }
+        state
+    }
+
+    /// A fresh sync directory, removed when dropped.
+    struct SyncDir(PathBuf);
+
+    impl SyncDir {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!("aptos-fuzzer-sync-{}-{}", process::id(), name));
+            let _ = fs::remove_dir_all(&dir);
+            Self(dir)
+        }
+
+        fn instance(&self, name: &str) -> CorpusSync {
+            CorpusSync::new(self.0.clone(), name.to_string()).unwrap()
+        }
+    }
+
+    impl Drop for SyncDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn instances_import_what_the_others_export_once() {
+        let dir = SyncDir::new("exchange");
+        let mut a = dir.instance("a");
+        let mut b = dir.instance("b");
+        assert!(a.is_due());
+        assert_eq!(a.export(&state(&["deposit", "withdraw"])).unwrap(), 2);
+        assert!(!a.is_due());
+        assert!(
+            a.import().unwrap().is_empty(),
+            "an instance never imports its own entries"
+        );
+
+        let imported = b.import().unwrap();
+        assert_eq!(imported.len(), 2);
+        assert!(imported.contains(&input("deposit")) && imported.contains(&input("withdraw")));
+        assert!(b.import().unwrap().is_empty());
+        // Imported inputs kept in the corpus aren't exported back
+        assert_eq!(b.export(&state(&["deposit", "swap"])).unwrap(), 1);
+    }
+
+    #[test]
+    fn entries_are_exported_once() {
+        let dir = SyncDir::new("export");
+        let mut sync = dir.instance("a");
+        let mut state = state(&["deposit"]);
+        assert_eq!(sync.export(&state).unwrap(), 1);
+        assert_eq!(sync.export(&state).unwrap(), 0);
+        // The same input added again is a new entry but not a new input
+        state.corpus_mut().add(Testcase::new(input("deposit"))).unwrap();
+        state.corpus_mut().add(Testcase::new(input("swap"))).unwrap();
+        assert_eq!(sync.export(&state).unwrap(), 1);
+        assert_eq!(fs::read_dir(dir.0.join("a")).unwrap().count(), 2);
+    }
+
+    #[test]
+    fn other_tools_can_drop_inputs_at_the_top_level() {
+        let dir = SyncDir::new("top-level");
+        let mut sync = dir.instance("a");
+        fs::write(
+            dir.0.join("seed.json"),
+            serde_json::to_string(&input("deposit")).unwrap(),
+        )
+        .unwrap();
+        fs::write(dir.0.join("broken.json"), "not an input").unwrap();
+        fs::write(dir.0.join("notes.txt"), "not json").unwrap();
+        assert_eq!(sync.import().unwrap(), vec![input("deposit")]);
+    }
+}
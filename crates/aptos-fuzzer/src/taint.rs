@@ -0,0 +1,466 @@
+use aptos_move_binary_format::file_format::Bytecode;
+use aptos_move_vm_runtime::{MoveTracerExtraInfo, MoveTracerFrameInfo, MoveTracerInstructionContext};
+use aptos_move_vm_types::loaded_data::runtime_types::Type;
+
+/// The transaction arguments a value derives from: bit `i` stands for
+/// argument `i`. Arguments past the 63rd share the last bit.
+pub type Taint = u64;
+
+pub fn argument_taint(idx: usize) -> Taint {
+    1 << idx.min(63)
+}
+
+/// Indices of the arguments in `taint`.
+pub fn tainting_arguments(taint: Taint) -> Vec<usize> {
+    (0..64).filter(|idx| taint & (1 << idx) != 0).collect()
+}
+
+/// The local a reference points into.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct RefTarget {
+    frame: usize,
+    local: usize,
+    /// The reference is to the whole local, not a field or element of it
+    whole: bool,
+}
+
+type Slot = (Taint, Option<RefTarget>);
+
+/// Which transaction arguments every operand stack slot and local derives
+/// from, maintained next to `ConcolicState`. Much cheaper than symbolic
+/// reasoning and independent of it, so it keeps working where the solver
+/// gave up. Signer arguments are not tracked: they are chosen by the chain,
+/// not the caller.
+#[derive(Clone, Debug, Default)]
+pub struct TaintState {
+    stack: Vec<Taint>,
+    /// The local each stack slot refers to, if it is a reference to one
+    targets: Vec<Option<RefTarget>>,
+    locals: Vec<Vec<Slot>>,
+    /// Height of the stack below each open frame's values
+    frame_bases: Vec<usize>,
+}
+
+impl TaintState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn clear(&mut self) {
+        self.stack.clear();
+        self.targets.clear();
+        self.locals.clear();
+        self.frame_bases.clear();
+    }
+
+    /// Taint of each operand stack slot, top last.
+    pub fn stack(&self) -> &[Taint] {
+        &self.stack
+    }
+
+    pub fn open_frame(&mut self, frame: &MoveTracerFrameInfo<'_>) {
+        if self.locals.is_empty() {
+            let locals = frame
+                .param_tys
+                .iter()
+                .enumerate()
+                .map(|(idx, ty)| {
+                    if is_signer(ty) {
+                        (0, None)
+                    } else {
+                        (argument_taint(idx), None)
+                    }
+                })
+                .collect();
+            self.frame_bases.push(self.stack.len());
+            self.locals.push(locals);
+            return;
+        }
+        let base = self.stack.len().saturating_sub(frame.param_tys.len());
+        let args: Vec<Slot> = self.stack.drain(base..).zip(self.targets.drain(base..)).collect();
+        let all_args = args.iter().fold(0, |taint, (arg, _)| taint | arg);
+        self.frame_bases.push(base);
+        self.locals.push(args);
+        if frame.is_native {
+            // Natives are not traced: their results may depend on any argument
+            for _ in 0..frame.return_tys.len() {
+                self.push((all_args, None));
+            }
+        }
+    }
+
+    /// Leave only the callee's return values above the caller's stack.
+    pub fn close_frame(&mut self, frame: &MoveTracerFrameInfo<'_>) {
+        self.locals.pop();
+        let Some(base) = self.frame_bases.pop() else {
+            return;
+        };
+        let returns = frame.return_tys.len().min(self.stack.len().saturating_sub(base));
+        let split = self.stack.len() - returns;
+        let values: Vec<Slot> = self.stack.drain(split..).zip(self.targets.drain(split..)).collect();
+        self.stack.truncate(base);
+        self.targets.truncate(base);
+        for (taint, target) in values {
+            let target = target.filter(|target| target.frame < self.locals.len());
+            self.push((taint, target));
+        }
+    }
+
+    pub fn before_instruction(&mut self, ctx: &MoveTracerInstructionContext<'_>) {
+        self.execute(ctx.instruction, ctx.operand_stack.values().len(), ctx.extra);
+    }
+
+    /// Apply `instruction`, about to run with `height` values on the operand
+    /// stack.
+    fn execute(&mut self, instruction: &Bytecode, height: usize, extra: Option<MoveTracerExtraInfo>) {
+        // Instructions without a modeled stack effect are caught up with here
+        self.stack.resize(height, 0);
+        self.targets.resize(height, None);
+
+        let frame = self.locals.len().saturating_sub(1);
+        match instruction {
+            Bytecode::CopyLoc(idx) => {
+                let (taint, target) = self.local(*idx as usize);
+                self.push((taint, target));
+            }
+            Bytecode::MoveLoc(idx) => {
+                let slot = self.local(*idx as usize);
+                self.set_local(frame, *idx as usize, (0, None));
+                self.push(slot);
+            }
+            Bytecode::MutBorrowLoc(idx) | Bytecode::ImmBorrowLoc(idx) => {
+                let (taint, _) = self.local(*idx as usize);
+                let target = RefTarget {
+                    frame,
+                    local: *idx as usize,
+                    whole: true,
+                };
+                self.push((taint, Some(target)));
+            }
+            Bytecode::StLoc(idx) => {
+                let slot = self.pop();
+                self.set_local(frame, *idx as usize, slot);
+            }
+            Bytecode::ReadRef => {
+                let (taint, target) = self.pop();
+                let pointee = target.map_or(0, |target| self.target_taint(target));
+                let taint = match target {
+                    Some(target) if target.whole => pointee,
+                    _ => taint | pointee,
+                };
+                self.push((taint, None));
+            }
+            Bytecode::WriteRef => {
+                let (_, target) = self.pop();
+                let (taint, _) = self.pop();
+                if let Some(target) = target {
+                    self.write(target, taint);
+                }
+            }
+            Bytecode::FreezeRef => {}
+            Bytecode::ImmBorrowField(_) |
+            Bytecode::MutBorrowField(_) |
+            Bytecode::ImmBorrowFieldGeneric(_) |
+            Bytecode::MutBorrowFieldGeneric(_) |
+            Bytecode::ImmBorrowVariantField(_) |
+            Bytecode::MutBorrowVariantField(_) |
+            Bytecode::ImmBorrowVariantFieldGeneric(_) |
+            Bytecode::MutBorrowVariantFieldGeneric(_) => {
+                let (taint, target) = self.pop();
+                self.push((taint, target.map(RefTarget::part)));
+            }
+            Bytecode::VecImmBorrow(_) | Bytecode::VecMutBorrow(_) => {
+                let (index, _) = self.pop();
+                let (taint, target) = self.pop();
+                self.push((taint | index, target.map(RefTarget::part)));
+            }
+            Bytecode::VecPushBack(_) => {
+                let (element, _) = self.pop();
+                let (_, target) = self.pop();
+                if let Some(target) = target {
+                    self.write(target.part(), element);
+                }
+            }
+            Bytecode::VecLen(_) | Bytecode::VecPopBack(_) => {
+                let (taint, target) = self.pop();
+                let pointee = target.map_or(0, |target| self.target_taint(target));
+                self.push((taint | pointee, None));
+            }
+            Bytecode::VecSwap(_) => {
+                for _ in 0..3 {
+                    self.pop();
+                }
+            }
+            Bytecode::Pop | Bytecode::BrTrue(_) | Bytecode::BrFalse(_) | Bytecode::Abort => {
+                self.pop();
+            }
+            Bytecode::MoveTo(_) | Bytecode::MoveToGeneric(_) => {
+                self.pop();
+                self.pop();
+            }
+            // Arguments and return values move between frames in
+            // `open_frame` and `close_frame`
+            Bytecode::Call(_) | Bytecode::CallGeneric(_) | Bytecode::Ret | Bytecode::Branch(_) | Bytecode::Nop => {}
+            instruction => {
+                if let Some((pops, pushes)) = stack_effect(instruction, extra) {
+                    let taint = (0..pops).fold(0, |taint, _| taint | self.pop().0);
+                    for _ in 0..pushes {
+                        self.push((taint, None));
+                    }
+                }
+            }
+        }
+    }
+
+    fn push(&mut self, (taint, target): Slot) {
+        self.stack.push(taint);
+        self.targets.push(target);
+    }
+
+    fn pop(&mut self) -> Slot {
+        (self.stack.pop().unwrap_or(0), self.targets.pop().flatten())
+    }
+
+    fn local(&self, idx: usize) -> Slot {
+        self.locals
+            .last()
+            .and_then(|locals| locals.get(idx))
+            .copied()
+            .unwrap_or((0, None))
+    }
+
+    fn set_local(&mut self, frame: usize, idx: usize, slot: Slot) {
+        let Some(locals) = self.locals.get_mut(frame) else {
+            return;
+        };
+        if locals.len() <= idx {
+            locals.resize(idx + 1, (0, None));
+        }
+        locals[idx] = slot;
+    }
+
+    fn target_taint(&self, target: RefTarget) -> Taint {
+        self.locals
+            .get(target.frame)
+            .and_then(|locals| locals.get(target.local))
+            .map_or(0, |(taint, _)| *taint)
+    }
+
+    /// Store through a reference: replaces the local's taint when the whole
+    /// local is written, adds to it when only part of it is.
+    fn write(&mut self, target: RefTarget, taint: Taint) {
+        let taint = if target.whole {
+            taint
+        } else {
+            self.target_taint(target) | taint
+        };
+        let slot = self
+            .locals
+            .get(target.frame)
+            .and_then(|locals| locals.get(target.local))
+            .and_then(|(_, target)| *target);
+        self.set_local(target.frame, target.local, (taint, slot));
+    }
+}
+
+impl RefTarget {
+    fn part(self) -> Self {
+        Self { whole: false, ..self }
+    }
+}
+
+pub(crate) fn is_signer(ty: &Type) -> bool {
+    match ty {
+        Type::Signer => true,
+        Type::Reference(inner) | Type::MutableReference(inner) => is_signer(inner),
+        _ => false,
+    }
+}
+
+/// Values popped and pushed by instructions whose results derive from all
+/// of their operands.
+fn stack_effect(instruction: &Bytecode, extra: Option<MoveTracerExtraInfo>) -> Option<(usize, usize)> {
+    let effect = match instruction {
+        Bytecode::LdU8(_) |
+        Bytecode::LdU16(_) |
+        Bytecode::LdU32(_) |
+        Bytecode::LdU64(_) |
+        Bytecode::LdU128(_) |
+        Bytecode::LdU256(_) |
+        Bytecode::LdConst(_) |
+        Bytecode::LdTrue |
+        Bytecode::LdFalse => (0, 1),
+        Bytecode::Add |
+        Bytecode::Sub |
+        Bytecode::Mul |
+        Bytecode::Div |
+        Bytecode::Mod |
+        Bytecode::BitOr |
+        Bytecode::BitAnd |
+        Bytecode::Xor |
+        Bytecode::Shl |
+        Bytecode::Shr |
+        Bytecode::Or |
+        Bytecode::And |
+        Bytecode::Lt |
+        Bytecode::Gt |
+        Bytecode::Le |
+        Bytecode::Ge |
+        Bytecode::Eq |
+        Bytecode::Neq => (2, 1),
+        Bytecode::Not |
+        Bytecode::CastU8 |
+        Bytecode::CastU16 |
+        Bytecode::CastU32 |
+        Bytecode::CastU64 |
+        Bytecode::CastU128 |
+        Bytecode::CastU256 |
+        Bytecode::TestVariant(_) |
+        Bytecode::TestVariantGeneric(_) |
+        Bytecode::MutBorrowGlobal(_) |
+        Bytecode::ImmBorrowGlobal(_) |
+        Bytecode::MutBorrowGlobalGeneric(_) |
+        Bytecode::ImmBorrowGlobalGeneric(_) |
+        Bytecode::Exists(_) |
+        Bytecode::ExistsGeneric(_) |
+        Bytecode::MoveFrom(_) |
+        Bytecode::MoveFromGeneric(_) => (1, 1),
+        Bytecode::VecPack(_, len) => (*len as usize, 1),
+        Bytecode::VecUnpack(_, len) => (1, *len as usize),
+        Bytecode::Pack(_) | Bytecode::PackGeneric(_) | Bytecode::PackVariant(_) | Bytecode::PackVariantGeneric(_) => {
+            match extra? {
+                MoveTracerExtraInfo::Pack(count) |
+                MoveTracerExtraInfo::PackGeneric(count) |
+                MoveTracerExtraInfo::PackVariant(count) |
+                MoveTracerExtraInfo::PackVariantGeneric(count) => (count as usize, 1),
+                _ => return None,
+            }
+        }
+        Bytecode::Unpack(_) |
+        Bytecode::UnpackGeneric(_) |
+        Bytecode::UnpackVariant(_) |
+        Bytecode::UnpackVariantGeneric(_) => match extra? {
+            MoveTracerExtraInfo::Unpack(count) |
+            MoveTracerExtraInfo::UnpackGeneric(count) |
+            MoveTracerExtraInfo::UnpackVariant(count) |
+            MoveTracerExtraInfo::UnpackVariantGeneric(count) => (1, count as usize),
+            _ => return None,
+        },
+        _ => return None,
+    };
+    Some(effect)
+}
+
+#[cfg(test)]
+mod tests {
+    use aptos_move_binary_format::file_format::{Bytecode, FieldHandleIndex, SignatureIndex};
+    use aptos_move_vm_types::loaded_data::runtime_types::Type;
+
+    use super::{argument_taint, is_signer, tainting_arguments, Taint, TaintState};
+
+    /// A state inside the entry function, whose locals 0 and 1 are its
+    /// arguments, after running `code`.
+    fn run(code: &[Bytecode]) -> TaintState {
+        let mut state = TaintState::new();
+        state.frame_bases.push(0);
+        state
+            .locals
+            .push(vec![(argument_taint(0), None), (argument_taint(1), None)]);
+        for instruction in code {
+            let height = state.stack.len();
+            state.execute(instruction, height, None);
+        }
+        state
+    }
+
+    fn top(code: &[Bytecode]) -> Taint {
+        *run(code).stack().last().unwrap()
+    }
+
+    #[test]
+    fn arguments_past_the_63rd_share_a_bit() {
+        assert_eq!(argument_taint(70), argument_taint(63));
+        assert_eq!(tainting_arguments(argument_taint(0) | argument_taint(2)), vec![0, 2]);
+        assert!(tainting_arguments(0).is_empty());
+    }
+
+    #[test]
+    fn operations_combine_their_operands() {
+        assert_eq!(
+            top(&[Bytecode::CopyLoc(0), Bytecode::CopyLoc(1), Bytecode::Add]),
+            argument_taint(0) | argument_taint(1)
+        );
+        assert_eq!(
+            top(&[Bytecode::LdU64(1), Bytecode::CopyLoc(1), Bytecode::Lt]),
+            argument_taint(1)
+        );
+        assert_eq!(top(&[Bytecode::LdU64(1), Bytecode::CastU8]), 0);
+    }
+
+    #[test]
+    fn locals_carry_taint_until_moved_out() {
+        assert_eq!(
+            top(&[Bytecode::CopyLoc(1), Bytecode::StLoc(2), Bytecode::CopyLoc(2)]),
+            argument_taint(1)
+        );
+        assert_eq!(top(&[Bytecode::MoveLoc(0), Bytecode::Pop, Bytecode::CopyLoc(0)]), 0);
+    }
+
+    #[test]
+    fn writes_to_a_whole_local_replace_its_taint() {
+        let code = [
+            Bytecode::CopyLoc(0),
+            Bytecode::StLoc(2),
+            Bytecode::CopyLoc(1),
+            Bytecode::MutBorrowLoc(2),
+            Bytecode::WriteRef,
+            Bytecode::ImmBorrowLoc(2),
+            Bytecode::ReadRef,
+        ];
+        assert_eq!(top(&code), argument_taint(1));
+    }
+
+    #[test]
+    fn writes_to_part_of_a_local_add_to_its_taint() {
+        let code = [
+            Bytecode::CopyLoc(0),
+            Bytecode::StLoc(2),
+            Bytecode::CopyLoc(1),
+            Bytecode::MutBorrowLoc(2),
+            Bytecode::MutBorrowField(FieldHandleIndex::new(0)),
+            Bytecode::WriteRef,
+            Bytecode::CopyLoc(2),
+        ];
+        assert_eq!(top(&code), argument_taint(0) | argument_taint(1));
+    }
+
+    #[test]
+    fn vector_lengths_derive_from_pushed_elements() {
+        let code = [
+            Bytecode::VecPack(SignatureIndex::new(0), 0),
+            Bytecode::StLoc(2),
+            Bytecode::MutBorrowLoc(2),
+            Bytecode::CopyLoc(1),
+            Bytecode::VecPushBack(SignatureIndex::new(0)),
+            Bytecode::ImmBorrowLoc(2),
+            Bytecode::VecLen(SignatureIndex::new(0)),
+        ];
+        assert_eq!(top(&code), argument_taint(1));
+    }
+
+    #[test]
+    fn unmodeled_instructions_catch_up_with_the_stack_height() {
+        let mut state = run(&[Bytecode::CopyLoc(0)]);
+        state.execute(&Bytecode::CopyLoc(1), 3, None);
+        assert_eq!(state.stack(), &[argument_taint(0), 0, 0, argument_taint(1)]);
+        state.execute(&Bytecode::Pop, 1, None);
+        assert!(state.stack().is_empty());
+    }
+
+    #[test]
+    fn signers_and_references_to_them_are_signers() {
+        assert!(is_signer(&Type::Signer));
+        assert!(is_signer(&Type::Reference(Box::new(Type::Signer))));
+        assert!(!is_signer(&Type::U64));
+    }
+}
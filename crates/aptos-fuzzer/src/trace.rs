@@ -0,0 +1,81 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::conflicts::CallAccessSet;
+
+/// Values summarised from the top of the operand stack for each traced
+/// instruction
+pub const TRACE_OPERANDS: usize = 3;
+
+/// What a single run of an input did, frame by frame and instruction by
+/// instruction. Only recorded on request, e.g. for the solutions of a
+/// campaign.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ExecutionTrace {
+    /// VM status the run ended with
+    pub status: String,
+    pub events: Vec<TraceEvent>,
+    pub abort: Option<AbortSite>,
+    /// Global storage each call read and wrote
+    #[serde(default)]
+    pub accesses: Vec<CallAccessSet>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum TraceEvent {
+    OpenFrame {
+        module: String,
+        function: String,
+    },
+    CloseFrame {
+        module: String,
+        function: String,
+    },
+    Instruction {
+        pc: u16,
+        instruction: String,
+        /// Top of the operand stack before the instruction, innermost last;
+        /// `_` for values that aren't primitives or addresses
+        operands: Vec<String>,
+    },
+}
+
+/// The instruction a run aborted at, and the call stack leading to it.
+/// Stored as metadata of the testcases that abort.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct AbortSite {
+    pub module: String,
+    pub function: String,
+    pub pc: u16,
+    pub code: u64,
+    /// Frames that called `function`, outermost first, each at the pc of
+    /// its call
+    #[serde(default)]
+    pub callers: Vec<StackFrame>,
+}
+
+libafl_bolts::impl_serdeany!(AbortSite);
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct StackFrame {
+    pub module: String,
+    pub function: String,
+    pub pc: u16,
+}
+
+impl fmt::Display for AbortSite {
+    /// `code 3 at 0xcafe::pool::withdraw@12 <- 0xcafe::router::exit@4`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "code {} at {}::{}@{}",
+            self.code, self.module, self.function, self.pc
+        )?;
+        for caller in self.callers.iter().rev() {
+            write!(f, " <- {}::{}@{}", caller.module, caller.function, caller.pc)?;
+        }
+        Ok(())
+    }
+}
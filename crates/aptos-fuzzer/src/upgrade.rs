@@ -0,0 +1,45 @@
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// Seconds of fuzzing the first version of the targets gets by default
+pub const DEFAULT_UPGRADE_AFTER: u64 = 300;
+
+/// An upgrade of the target modules to the version in `modules_dir`, due
+/// once the first version has been fuzzed for a while. By then the corpus
+/// has built state worth carrying across the upgrade.
+pub struct PendingUpgrade {
+    modules_dir: PathBuf,
+    due: Instant,
+}
+
+impl PendingUpgrade {
+    pub fn new(modules_dir: PathBuf, after: Duration) -> Self {
+        Self {
+            modules_dir,
+            due: Instant::now() + after,
+        }
+    }
+
+    pub fn modules_dir(&self) -> &Path {
+        &self.modules_dir
+    }
+
+    pub fn is_due(&self) -> bool {
+        Instant::now() >= self.due
+    }
+}
+
+/// What publishing the new version of the targets over the built state
+/// changed and broke.
+#[derive(Clone, Debug, Default)]
+pub struct ModuleUpgrade {
+    /// Corpus entries dropped for calling removed or changed functions
+    pub dropped: usize,
+    /// Seeds added for new functions
+    pub added: usize,
+    /// Changes a compatible upgrade may not make, e.g. a struct's fields
+    /// changed or a public function removed
+    pub incompatibilities: Vec<String>,
+    /// Target resources in the state the new modules can't decode
+    pub unreadable: Vec<String>,
+}
@@ -0,0 +1,102 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+/// Time between two scans of the watched directory
+pub const WATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Modification time and length of each watched file
+type Snapshot = BTreeMap<PathBuf, (SystemTime, u64)>;
+
+/// Polls a module directory, or the sources of a Move package, for changes.
+/// A change is only reported once two scans in a row agree, so files still
+/// being written by the compiler aren't picked up half way.
+pub struct ModuleWatcher {
+    root: PathBuf,
+    extensions: &'static [&'static str],
+    /// Skip `build` directories, where a package's compiler output goes
+    skip_build: bool,
+    snapshot: Snapshot,
+    pending: Option<Snapshot>,
+    last_scan: Instant,
+}
+
+impl ModuleWatcher {
+    /// Watch the `.mv` files under `dir`.
+    pub fn modules(dir: PathBuf) -> Self {
+        Self::new(dir, &["mv"], false)
+    }
+
+    /// Watch the `.move` sources and `Move.toml` of the package at `dir`,
+    /// ignoring its build output.
+    pub fn package(dir: PathBuf) -> Self {
+        Self::new(dir, &["move", "toml"], true)
+    }
+
+    fn new(root: PathBuf, extensions: &'static [&'static str], skip_build: bool) -> Self {
+        let mut watcher = Self {
+            root,
+            extensions,
+            skip_build,
+            snapshot: Snapshot::new(),
+            pending: None,
+            last_scan: Instant::now(),
+        };
+        watcher.snapshot = watcher.scan();
+        watcher
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Whether the watched files changed since the last reported change.
+    /// Scans at most every `WATCH_INTERVAL`.
+    pub fn changed(&mut self) -> bool {
+        if self.last_scan.elapsed() < WATCH_INTERVAL {
+            return false;
+        }
+        self.last_scan = Instant::now();
+        let snapshot = self.scan();
+        if snapshot == self.snapshot {
+            self.pending = None;
+            return false;
+        }
+        if self.pending.as_ref() != Some(&snapshot) {
+            self.pending = Some(snapshot);
+            return false;
+        }
+        self.pending = None;
+        self.snapshot = snapshot;
+        true
+    }
+
+    fn scan(&self) -> Snapshot {
+        let mut snapshot = Snapshot::new();
+        let mut dirs = vec![self.root.clone()];
+        while let Some(dir) = dirs.pop() {
+            let Ok(entries) = fs::read_dir(&dir) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                let path = entry.path();
+                let Ok(metadata) = entry.metadata() else {
+                    continue;
+                };
+                if metadata.is_dir() {
+                    if !self.skip_build || path.file_name().is_none_or(|name| name != "build") {
+                        dirs.push(path);
+                    }
+                } else if path
+                    .extension()
+                    .is_some_and(|ext| self.extensions.iter().any(|watched| ext == *watched))
+                {
+                    let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+                    snapshot.insert(path, (modified, metadata.len()));
+                }
+            }
+        }
+        snapshot
+    }
+}